@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::fs;
+use std::process;
+
+mod stats;
+
+fn word_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: word-count <file>");
+        process::exit(2);
+    };
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("failed to read {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let counts = word_counts(&text);
+
+    let mut words: Vec<_> = counts.iter().collect();
+    words.sort_by(|a, b| b.1.cmp(a.1));
+
+    if words.is_empty() {
+        println!("no words found in {}", path);
+        return;
+    }
+
+    for (word, count) in words.iter().take(10) {
+        println!("{}: {}", word, count);
+    }
+
+    let lengths: Vec<f64> = counts.keys().map(|w| w.len() as f64).collect();
+    if let Some(average) = stats::average(&lengths) {
+        println!("average word length: {:.2}", average);
+    }
+}
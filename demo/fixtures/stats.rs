@@ -0,0 +1,7 @@
+/// Average of a list of values, or `None` if the list is empty
+pub fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
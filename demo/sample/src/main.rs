@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::fs;
+
+mod stats;
+
+fn word_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+    return counts;
+}
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    let counts = word_counts(&text);
+
+    let mut words: Vec<_> = counts.iter().collect();
+    words.sort_by(|a, b| b.1.cmp(a.1));
+
+    // TODO: print a friendlier message for empty input
+    if words.len() == 0 {
+        println!("no words found");
+    }
+
+    for (word, count) in words.iter().take(10) {
+        println!("{}: {}", word, count);
+    }
+
+    let lengths: Vec<f64> = counts.keys().map(|w| w.len() as f64).collect();
+    println!("average word length: {:.2}", stats::average(&lengths));
+}
@@ -0,0 +1,8 @@
+/// Average of a list of values
+pub fn average(values: &Vec<f64>) -> f64 {
+    let mut sum = 0.0;
+    for i in 0..values.len() {
+        sum += values[i];
+    }
+    sum / values.len() as f64
+}
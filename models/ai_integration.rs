@@ -5,14 +5,110 @@
 //! - Integration with OpenAI GPT models
 //! - Integration with Mistral AI
 //! - Integration with local models via Ollama
+//! - Retries with exponential backoff and a process-wide rate limiter
+//! - Reuse of cached completions for unchanged prompts
+//! - Context window budgets, refusing or chunking oversized prompts
+//! - Usage and cost accounting of every request
+//! - Embeddings, and retrieval of related project definitions from the code index
+//! - Tool calling, letting models read project context while generating code
 
-use crate::{AiModelConfig, AiModelType, Result, RustAiToolError};
+use crate::analysis::CodeIssue;
+use crate::chat::ChatTurn;
+use crate::chunking::split_at_items;
+use crate::feedback::{format_examples, relevant_examples, FeedbackEntry};
+use crate::index::CodeIndex;
+use crate::{AiModelConfig, AiModelType, ModelPricing, PromptOverflow, Result, RetryOptions, RustAiToolError};
+use super::cache::ResponseCache;
+use super::redaction::Redactor;
+use super::tools::{ConversationMessage, ProjectTools, ToolCall, ToolDefinition};
+use super::usage::{default_pricing, record_cache_hit, record_request};
+use super::tokenizer::{
+    default_context_window, fetch_ollama_context_window, tokenizer_for, DEFAULT_MAX_TOKENS,
+};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
 use log::{debug, info, warn, error};
 
+/// Default endpoint of a local Ollama server
+const OLLAMA_GENERATE_URL: &str = "http://localhost:11434/api/generate";
+
+/// Default endpoint of the OpenAI chat completions API
+const OPENAI_CHAT_COMPLETIONS_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+/// Default endpoint of the Claude messages API, which supports tool use
+const CLAUDE_MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+
+/// Default endpoint of the Mistral chat completions API
+const MISTRAL_CHAT_COMPLETIONS_URL: &str = "https://api.mistral.ai/v1/chat/completions";
+
+/// Number of texts sent in one embeddings request
+const EMBEDDING_BATCH_SIZE: usize = 32;
+
+/// Number of indexed definitions added to fix and generation prompts
+const RETRIEVED_DEFINITIONS: usize = 5;
+
+/// Accepted fixes added to fix prompts as examples
+const FEW_SHOT_EXAMPLES: usize = 3;
+
+/// Characters of a fix or generation prompt used to look up related definitions
+const MAX_QUERY_CHARS: usize = 8000;
+
+/// Limiters per provider, each configured by the first client that sends a request to it
+static RATE_LIMITERS: OnceLock<std::sync::Mutex<HashMap<String, &'static RateLimiter>>> = OnceLock::new();
+
+/// Limits the number of concurrent requests and the rate at which they start
+struct RateLimiter {
+    /// Permits for requests in flight
+    permits: Semaphore,
+    
+    /// Minimum time between the starts of two requests
+    min_interval: Duration,
+    
+    /// Earliest time the next request may start
+    next_start: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a limiter from retry options
+    fn new(options: &RetryOptions) -> Self {
+        let min_interval = options.requests_per_minute
+            .filter(|&rpm| rpm > 0)
+            .map(|rpm| Duration::from_secs(60) / rpm)
+            .unwrap_or_default();
+        
+        Self {
+            permits: Semaphore::new(options.max_concurrent_requests.max(1)),
+            min_interval,
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+    
+    /// Wait for a free slot and for the rate limit
+    async fn acquire(&self) -> Result<SemaphorePermit<'_>> {
+        let permit = self.permits.acquire().await
+            .map_err(|e| RustAiToolError::AiModel(format!("Rate limiter closed: {}", e)))?;
+        
+        let start = {
+            let mut next_start = self.next_start.lock().await;
+            let start = (*next_start).max(Instant::now());
+            *next_start = start + self.min_interval;
+            start
+        };
+        
+        tokio::time::sleep_until(start).await;
+        Ok(permit)
+    }
+}
+
 /// AI completion request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompletionRequest {
     /// The prompt for the AI model
     pub prompt: String,
@@ -65,6 +161,24 @@ pub struct AiModelClient {
     
     /// HTTP client for API requests
     client: reqwest::Client,
+    
+    /// Context window detected on first use
+    detected_context_window: OnceCell<usize>,
+    
+    /// Code index used to add related definitions to prompts
+    index: Option<CodeIndex>,
+    
+    /// Tools offered to models that support tool calling
+    tools: Option<ProjectTools>,
+    
+    /// Masks secrets in prompts and restores them in answers (if enabled)
+    redactor: Option<std::sync::Mutex<Redactor>>,
+    
+    /// Recorded fix outcomes, the kept ones serving as examples in fix prompts
+    feedback: Vec<FeedbackEntry>,
+    
+    /// Root of the project the requests are about, holding the response cache
+    project_root: Option<PathBuf>,
 }
 
 impl AiModelClient {
@@ -78,163 +192,1083 @@ impl AiModelClient {
     ///
     /// A new AI model client
     pub fn new(config: AiModelConfig) -> Result<Self> {
-        let client = reqwest::Client::builder()
+        let client = crate::network::client_builder()
             .timeout(std::time::Duration::from_secs(300))
             .build()
             .map_err(|e| RustAiToolError::AiModel(e.to_string()))?;
         
-        Ok(Self { config, client })
+        let redactor = if config.redaction.enabled {
+            Some(std::sync::Mutex::new(Redactor::new(&config.redaction.patterns)?))
+        } else {
+            None
+        };
+        
+        Ok(Self {
+            config,
+            client,
+            detected_context_window: OnceCell::new(),
+            index: None,
+            tools: None,
+            redactor,
+            feedback: Vec::new(),
+            project_root: None,
+        })
+    }
+    
+    /// Cache responses in the state directory of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - Root of the project the requests are about
+    ///
+    /// # Returns
+    ///
+    /// The client caching in the project
+    pub fn with_project_root(mut self, project_root: &Path) -> Self {
+        self.project_root = Some(project_root.to_path_buf());
+        self
+    }
+    
+    /// Add related definitions from a code index to fix and generation prompts
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Code index of the project
+    ///
+    /// # Returns
+    ///
+    /// The client using the index
+    pub fn with_index(mut self, index: CodeIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+    
+    /// Whether prompts include related definitions from a code index
+    pub fn has_index(&self) -> bool {
+        self.index.is_some()
+    }
+    
+    /// Add fixes the user accepted earlier as examples to fix prompts
+    ///
+    /// # Arguments
+    ///
+    /// * `feedback` - Recorded fix outcomes of the project
+    ///
+    /// # Returns
+    ///
+    /// The client using the outcomes
+    pub fn with_feedback(mut self, feedback: Vec<FeedbackEntry>) -> Self {
+        self.feedback = feedback;
+        self
+    }
+    
+    /// Let models that support tool calling read project context during generation
+    ///
+    /// Tools are only offered if enabled in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `tools` - Tools operating on the project
+    ///
+    /// # Returns
+    ///
+    /// The client offering the tools
+    pub fn with_tools(mut self, tools: ProjectTools) -> Self {
+        if self.config.tools.enabled {
+            self.tools = Some(tools);
+        }
+        self
+    }
+    
+    /// Whether the model type supports tool calling
+    pub fn supports_tools(&self) -> bool {
+        // Ollama's generate endpoint has no tools
+        !matches!(self.config.model_type, AiModelType::Local(_))
+    }
+    
+    /// Generate code using the AI model
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - Prompt for the AI model
+    /// * `max_tokens` - Maximum number of tokens to generate
+    /// * `temperature` - Temperature (randomness)
+    ///
+    /// # Returns
+    ///
+    /// The generated code
+    pub async fn generate_code(
+        &self,
+        prompt: &str,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that specializes in Rust code. \
+            Provide concise, idiomatic Rust code that follows best practices. \
+            Include helpful comments to explain your reasoning. \
+            When asked to generate or modify code, respond with only the requested code without explanations unless specifically asked."
+                .to_string(),
+        );
+        
+        let definitions = self.relevant_definitions(prompt).await;
+        
+        let request = CompletionRequest {
+            prompt: format!("{}{}", definitions, prompt),
+            max_tokens,
+            temperature,
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Analyze Rust code using the AI model
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Code to analyze
+    /// * `instructions` - Instructions for the analysis
+    ///
+    /// # Returns
+    ///
+    /// The analysis results
+    pub async fn analyze_code(&self, code: &str, instructions: &str) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that specializes in analyzing Rust code. \
+            Focus on identifying issues related to correctness, performance, security, and idiomatic Rust. \
+            Be thorough but concise in your analysis."
+                .to_string(),
+        );
+        
+        let make_request = |code: &str| CompletionRequest {
+            prompt: format!(
+                "Please analyze the following Rust code:\n\n```rust\n{}\n```\n\n{}",
+                code, instructions
+            ),
+            max_tokens: Some(4000),
+            temperature: Some(0.2),
+            system: system.clone(),
+        };
+        
+        let responses = self.send_chunked(code, make_request).await?;
+        
+        Ok(responses.into_iter().map(|r| r.content).collect::<Vec<_>>().join("\n\n"))
+    }
+    
+    /// Generate fixes for Rust code issues
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Code with issues
+    /// * `issues` - Description of the issues
+    ///
+    /// # Returns
+    ///
+    /// The fixed code
+    pub async fn generate_fixes(&self, code: &str, issues: &str) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that specializes in fixing Rust code issues. \
+            Provide only the fixed code without explanations unless specifically asked. \
+            Ensure your fixes are idiomatic and follow Rust best practices."
+                .to_string(),
+        );
+        
+        let query = format!("{}\n{}", issues, code);
+        let definitions = self.relevant_definitions(&query).await;
+        let examples = format_examples(&relevant_examples(&self.feedback, &query, FEW_SHOT_EXAMPLES));
+        
+        let make_request = |code: &str| CompletionRequest {
+            prompt: format!(
+                "{}{}Fix the following issues in this Rust code:\n\nIssues:\n{}\n\nCode:\n```rust\n{}\n```\n\nProvide the fixed code:",
+                definitions, examples, issues, code
+            ),
+            max_tokens: Some(4000),
+            temperature: Some(0.2),
+            system: system.clone(),
+        };
+        
+        let responses = self.send_chunked(code, make_request).await?;
+        
+        // Extract code blocks if present, otherwise use the raw response
+        let code_block_regex = regex::Regex::new(r"```(?:rust)?\s*\n([\s\S]+?)\n```").unwrap();
+        let fixed_chunks: Vec<String> = responses.into_iter()
+            .map(|response| {
+                code_block_regex.captures(&response.content)
+                    .and_then(|captures| captures.get(1))
+                    .map(|code_match| code_match.as_str().to_string())
+                    .unwrap_or(response.content)
+            })
+            .collect();
+        
+        Ok(fixed_chunks.join("\n"))
+    }
+    
+    /// Generate unit tests demonstrating a fix
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_code` - Code after the fix
+    /// * `issues` - Description of the fixed issues
+    /// * `module_name` - Name of the test module to generate
+    ///
+    /// # Returns
+    ///
+    /// A `#[cfg(test)]` module to append to the fixed code
+    pub async fn generate_tests(&self, fixed_code: &str, issues: &str, module_name: &str) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that specializes in testing Rust code. \
+            Write focused unit tests that only use items defined in or imported by the given code. \
+            Provide only the test module without explanations."
+                .to_string(),
+        );
+        
+        let prompt = format!(
+            "The following issues were fixed in this Rust code:\n\nIssues:\n{}\n\nFixed code:\n```rust\n{}\n```\n\n\
+            Write a `#[cfg(test)] mod {} {{ use super::*; ... }}` module with unit tests demonstrating the corrected behavior. \
+            The module is appended to the end of the file.",
+            issues, fixed_code, module_name
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(2000),
+            temperature: Some(0.2),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        let code_block_regex = regex::Regex::new(r"```(?:rust)?\s*\n([\s\S]+?)\n```").unwrap();
+        Ok(code_block_regex.captures(&response.content)
+            .and_then(|captures| captures.get(1))
+            .map(|code_match| code_match.as_str().to_string())
+            .unwrap_or(response.content))
+    }
+    
+    /// Rank analysis issues by estimated impact and fix risk
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_list` - Issues to rank, one per line with their ids
+    ///
+    /// # Returns
+    ///
+    /// The model's answer, containing a JSON array of `{"id", "priority"}` objects
+    pub async fn prioritize_issues(&self, issue_list: &str) -> Result<String> {
+        let system = Some(
+            "You are an experienced Rust reviewer triaging static analysis reports. \
+            Answer with JSON only."
+                .to_string(),
+        );
+        
+        let prompt = format!(
+            "Rank the following issues found in a Rust project:\n\n{}\n\n\
+            Give every issue a priority from 0 to 100. Rank issues with a high impact \
+            (bugs, security, crashes, performance in hot paths) above cosmetic ones, and issues \
+            that are safe to fix above those whose fix risks changing behavior.\n\
+            Answer with a JSON array of objects with the fields `id` and `priority`, one per issue.",
+            issue_list
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(4000),
+            temperature: Some(0.0),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Summarize the breaking changes of a dependency update from its changelog
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Crate name
+    /// * `from` - Version the project uses
+    /// * `to` - Version it is updated to
+    /// * `changelog` - Changelog entries of the versions in between
+    ///
+    /// # Returns
+    ///
+    /// A short list of the changes that can break the project's build or behavior
+    pub async fn summarize_breaking_changes(&self, name: &str, from: &str, to: &str, changelog: &str) -> Result<String> {
+        let system = Some(
+            "You are an experienced Rust developer helping a team upgrade their dependencies. \
+            Be brief and concrete."
+                .to_string(),
+        );
+        
+        let prompt = format!(
+            "The crate `{}` is being updated from {} to {}. Here are its changelog entries for the \
+            versions in between:\n\n{}\n\n\
+            List the breaking changes a project using the crate has to deal with (removed or renamed \
+            items, changed signatures, new minimum Rust version, changed behavior), one short bullet \
+            each, with how to migrate when the changelog says. Answer `No breaking changes listed.` \
+            if there are none.",
+            name, from, to, changelog
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(1000),
+            temperature: Some(0.0),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Explain an analysis issue in plain English
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - Issue to explain
+    /// * `excerpt` - Code around the issue, with line numbers
+    ///
+    /// # Returns
+    ///
+    /// The explanation, ending with references for further reading
+    pub async fn explain_issue(&self, issue: &CodeIssue, excerpt: &str) -> Result<String> {
+        let system = Some(
+            "You are a patient Rust mentor explaining compiler and Clippy diagnostics to developers who are new to Rust. \
+            Use plain English, define Rust terms when you first use them, and keep the explanation focused on the code shown."
+                .to_string(),
+        );
+        
+        let prompt = format!(
+            "Explain the following issue reported in `{}`:\n\n[{:?}/{:?}] line {}: {}\n\n\
+            Code around the issue (reported lines are marked with `>`):\n```rust\n{}```\n\n\
+            Explain what the issue means, why it matters and how to fix it. \
+            End with a short list of references (Clippy lint documentation, The Rust Book or the Rust Reference) for further reading.",
+            issue.file_path.display(),
+            issue.category,
+            issue.severity,
+            issue.line_start,
+            issue.message,
+            excerpt
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(2000),
+            temperature: Some(0.3),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Answer a question about the project in a conversation
+    ///
+    /// Related definitions from the code index are added to the question.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - Earlier questions and answers, oldest first
+    /// * `question` - Question of the user
+    ///
+    /// # Returns
+    ///
+    /// The answer, with the complete new content of any file it proposes to change
+    pub async fn chat(&self, history: &[ChatTurn], question: &str) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant answering questions about a Rust project. \
+            Base your answers on the project code you are given or can look up, and say so when you are unsure. \
+            To propose a change to a file, give its complete new content in a code block whose info string names the file \
+            relative to the project root, e.g. ```rust path=src/lib.rs"
+                .to_string(),
+        );
+        
+        let definitions = self.relevant_definitions(question).await;
+        
+        let conversation: String = history.iter()
+            .map(|turn| format!("User: {}\n\nAssistant: {}\n\n", turn.question, turn.answer))
+            .collect();
+        let conversation = if conversation.is_empty() {
+            conversation
+        } else {
+            format!("Conversation so far:\n\n{}", conversation)
+        };
+        
+        let request = CompletionRequest {
+            prompt: format!("{}{}User: {}", definitions, conversation, question),
+            max_tokens: Some(4000),
+            temperature: Some(0.3),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Generate a Rust project description based on requirements
+    ///
+    /// # Arguments
+    ///
+    /// * `requirements` - Project requirements
+    ///
+    /// # Returns
+    ///
+    /// The project description
+    pub async fn generate_project_description(&self, requirements: &str) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that specializes in designing Rust projects. \
+            Based on user requirements, create detailed project descriptions including structure, \
+            dependencies, and approaches to implementation."
+                .to_string(),
+        );
+        
+        let prompt = format!(
+            "Generate a detailed Rust project description based on these requirements:\n\n{}\n\n\
+            Include suggested crate dependencies, file structure, and implementation approach.",
+            requirements
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(2000),
+            temperature: Some(0.7),
+            system,
+        };
+        
+        let response = self.send_completion_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Extract the template, dependencies and crate type of a project from its description
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - Project description
+    /// * `templates` - Available templates, as name and what they generate
+    ///
+    /// # Returns
+    ///
+    /// The model's answer, containing a JSON object with the fields `template`,
+    /// `dependencies` and `crate_type`
+    pub async fn extract_project_requirements(&self, description: &str, templates: &[(&str, &str)]) -> Result<String> {
+        let system = Some(
+            "You are an experienced Rust developer setting up new projects. \
+            Answer with JSON only."
+                .to_string(),
+        );
+        
+        let template_list: String = templates.iter()
+            .map(|(name, summary)| format!("- {}: {}\n", name, summary))
+            .collect();
+        
+        let prompt = format!(
+            "A new Rust project is described as:\n\n{}\n\nAvailable templates:\n{}\n\
+            Choose the template that fits best, the crates.io crates the project needs and whether it is \
+            a binary (`bin`) or a library (`lib`). Only list crates the description calls for or that are \
+            essential to it, by their exact crates.io names.\n\
+            Answer with a JSON object with the fields `template`, `dependencies` (an array of crate names) and `crate_type`.",
+            description, template_list
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(500),
+            temperature: Some(0.0),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Write the README of a generated project
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Project name and description
+    /// * `sources` - Source files of the project, defining the API to document
+    /// * `badges` - Badge line the README starts with
+    /// * `doctests` - Whether the Rust code blocks are compiled as doc tests of the library
+    /// * `errors` - Doc test failures of the previous README, to fix in this one
+    ///
+    /// # Returns
+    ///
+    /// The README in Markdown
+    pub async fn generate_readme(
+        &self,
+        project: &str,
+        sources: &str,
+        badges: &str,
+        doctests: bool,
+        errors: Option<&str>,
+    ) -> Result<String> {
+        let system = Some(
+            "You are a helpful programming assistant that writes READMEs for Rust crates. \
+            Only document items that exist in the given code, and respond with the README in Markdown without explanations."
+                .to_string(),
+        );
+        
+        let examples = if doctests {
+            "Rust code blocks are compiled as doc tests of the library: import its items by crate name, \
+            write complete examples (hide setup lines with `# `) and do not start servers or read input."
+        } else {
+            "The project is a binary: show command-line usage in ```sh blocks and mark any Rust code blocks ```rust,ignore."
+        };
+        let errors = errors
+            .map(|errors| format!("\n\nThe examples of your previous README failed to compile or run:\n{}", errors))
+            .unwrap_or_default();
+        
+        let prompt = format!(
+            "Write the README of this Rust project:\n\n{}\n\nSource code:\n\n{}\n\n\
+            Start with the title and this badge line:\n{}\n\n\
+            Then give a short feature overview, installation instructions and usage examples using the actual API. {} \
+            Use ```toml blocks for Cargo.toml excerpts and ```sh blocks for shell commands.{}",
+            project, sources, badges, examples, errors
+        );
+        
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: Some(3000),
+            temperature: Some(0.3),
+            system,
+        };
+        
+        let response = self.send_request(request).await?;
+        
+        Ok(response.content)
+    }
+    
+    /// Send a request built around a piece of code, splitting the code if the prompt is too large
+    ///
+    /// With `PromptOverflow::Chunk`, code that does not fit the context window
+    /// next to the instructions and the completion budget is split into chunks
+    /// between items, each sent as its own request.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Code to include in the prompt
+    /// * `make_request` - Builds the request for a piece of code
+    ///
+    /// # Returns
+    ///
+    /// One response per chunk, in order
+    async fn send_chunked(
+        &self,
+        code: &str,
+        make_request: impl Fn(&str) -> CompletionRequest,
+    ) -> Result<Vec<CompletionResponse>> {
+        let window = self.context_window().await;
+        let tokenizer = tokenizer_for(&self.config.model_type);
+        let overhead = self.required_tokens(&make_request(""));
+        
+        if self.config.prompt_overflow == PromptOverflow::Refuse || overhead + tokenizer.count_tokens(code) <= window {
+            return Ok(vec![self.send_request(make_request(code)).await?]);
+        }
+        
+        let budget = window.saturating_sub(overhead);
+        if budget == 0 {
+            return Err(RustAiToolError::AiModel(format!(
+                "Instructions and completion budget alone need about {} tokens, more than the {}-token context window",
+                overhead, window
+            )));
+        }
+        
+        let chunks = split_at_items(code, budget, tokenizer.as_ref());
+        info!("Prompt exceeds the {}-token context window, sending the code in {} chunks", window, chunks.len());
+        
+        let mut responses = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            responses.push(self.send_request(make_request(chunk)).await?);
+        }
+        
+        Ok(responses)
+    }
+    
+    /// Context window of the model in tokens
+    ///
+    /// The configured value takes precedence; local models are looked up in
+    /// Ollama's model metadata once, other models use their known size.
+    async fn context_window(&self) -> usize {
+        if let Some(context_window) = self.config.context_window {
+            return context_window;
+        }
+        
+        *self.detected_context_window.get_or_init(|| async {
+            let default = default_context_window(&self.config.model_type);
+            
+            match &self.config.model_type {
+                AiModelType::Local(model_name) => {
+                    let api_base = self.config.api_base_url.as_deref().unwrap_or(OLLAMA_GENERATE_URL);
+                    fetch_ollama_context_window(&self.client, api_base, model_name).await.unwrap_or(default)
+                }
+                _ => default,
+            }
+        }).await
+    }
+    
+    /// Name of the model requests are sent to
+    pub fn model_name(&self) -> String {
+        match &self.config.model_type {
+            AiModelType::Claude => "claude-3-opus-20240229".to_string(),
+            AiModelType::Gpt => "gpt-4".to_string(),
+            AiModelType::Mistral => "mistral-large-latest".to_string(),
+            AiModelType::Local(model_name) => model_name.clone(),
+            AiModelType::OpenAiCompatible(model_name) => model_name.clone(),
+        }
+    }
+    
+    /// Name of the model used for embeddings
+    ///
+    /// # Returns
+    ///
+    /// The configured embedding model or the default of the model type
+    pub fn embedding_model(&self) -> Result<String> {
+        let default = match &self.config.model_type {
+            AiModelType::Claude => {
+                return Err(RustAiToolError::AiModel(
+                    "Claude has no embeddings API; use a Gpt, Mistral, Local or OpenAiCompatible model to index the project".to_string(),
+                ));
+            }
+            AiModelType::Gpt => "text-embedding-3-small".to_string(),
+            AiModelType::Mistral => "mistral-embed".to_string(),
+            AiModelType::Local(model_name) | AiModelType::OpenAiCompatible(model_name) => model_name.clone(),
+        };
+        
+        Ok(self.config.embedding_model.clone().unwrap_or(default))
+    }
+    
+    /// Compute embeddings of texts
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to embed
+    ///
+    /// # Returns
+    ///
+    /// One vector per text, in order
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self.embedding_model()?;
+        let tokenizer = tokenizer_for(&self.config.model_type);
+        let mut vectors = Vec::with_capacity(texts.len());
+        
+        for batch in texts.chunks(EMBEDDING_BATCH_SIZE) {
+            let batch: Vec<String> = batch.iter().map(|text| self.redact(text)).collect();
+            let (batch_vectors, prompt_tokens) = self.send_embeddings_request(&batch, &model).await?;
+            
+            if batch_vectors.len() != batch.len() {
+                return Err(RustAiToolError::AiModel(format!(
+                    "Embeddings API returned {} vectors for {} texts",
+                    batch_vectors.len(),
+                    batch.len()
+                )));
+            }
+            
+            let estimated = prompt_tokens.is_none();
+            let prompt_tokens = prompt_tokens
+                .unwrap_or_else(|| batch.iter().map(|text| tokenizer.count_tokens(text) as u64).sum());
+            record_request(&model, prompt_tokens, 0, estimated, self.config.pricing.get(&model).copied());
+            
+            vectors.extend(batch_vectors);
+        }
+        
+        Ok(vectors)
+    }
+    
+    /// Definitions from the code index related to a prompt, formatted as a prompt section
+    ///
+    /// Retrieval is best effort: without an index, or if the query cannot be
+    /// embedded, the prompt is sent without definitions.
+    async fn relevant_definitions(&self, query: &str) -> String {
+        let Some(index) = &self.index else {
+            return String::new();
+        };
+        
+        let query: String = query.chars().take(MAX_QUERY_CHARS).collect();
+        let query_vector = match self.embed(std::slice::from_ref(&query)).await {
+            Ok(mut vectors) => vectors.remove(0),
+            Err(e) => {
+                warn!("Failed to look up related definitions: {}", e);
+                return String::new();
+            }
+        };
+        
+        // Definitions already in the prompt add nothing
+        let definitions: Vec<String> = index.search(&query_vector, RETRIEVED_DEFINITIONS + 2)
+            .into_iter()
+            .filter(|(_, indexed)| !query.contains(indexed.chunk.content.trim()))
+            .take(RETRIEVED_DEFINITIONS)
+            .map(|(_, indexed)| format!(
+                "// {}:{}-{}\n```rust\n{}```",
+                indexed.chunk.file_path.display(),
+                indexed.chunk.start_line,
+                indexed.chunk.end_line,
+                indexed.chunk.content
+            ))
+            .collect();
+        
+        if definitions.is_empty() {
+            return String::new();
+        }
+        
+        debug!("Adding {} related definitions to the prompt", definitions.len());
+        format!("Relevant definitions from the project:\n\n{}\n\n", definitions.join("\n\n"))
+    }
+    
+    /// Send one embeddings request
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to embed
+    /// * `model` - Embedding model
+    ///
+    /// # Returns
+    ///
+    /// The vectors and the prompt tokens reported by the API
+    async fn send_embeddings_request(&self, texts: &[String], model: &str) -> Result<(Vec<Vec<f32>>, Option<u64>)> {
+        #[derive(Deserialize)]
+        struct OpenAiEmbedding {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+        
+        #[derive(Deserialize)]
+        struct OpenAiEmbeddingUsage {
+            prompt_tokens: u64,
+        }
+        
+        #[derive(Deserialize)]
+        struct OpenAiEmbeddingResponse {
+            data: Vec<OpenAiEmbedding>,
+            usage: Option<OpenAiEmbeddingUsage>,
+        }
+        
+        #[derive(Deserialize)]
+        struct OllamaEmbeddingResponse {
+            embeddings: Vec<Vec<f32>>,
+            prompt_eval_count: Option<u64>,
+        }
+        
+        let body = serde_json::json!({ "model": model, "input": texts });
+        
+        if let AiModelType::Local(_) = &self.config.model_type {
+            let api_base = self.config.api_base_url.as_deref().unwrap_or(OLLAMA_GENERATE_URL);
+            let url = format!("{}/api/embed", api_base.trim_end_matches("/api/generate"));
+            
+            let request = self.client.post(&url).json(&body);
+            let response = self.send_with_retry(request, "Ollama embeddings").await?;
+            
+            if !response.status().is_success() {
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(RustAiToolError::AiModel(format!("Ollama embeddings API returned error: {}", error_text)));
+            }
+            
+            let ollama_response = response
+                .json::<OllamaEmbeddingResponse>()
+                .await
+                .map_err(|e| RustAiToolError::AiModel(format!("Failed to parse Ollama embeddings response: {}", e)))?;
+            
+            return Ok((ollama_response.embeddings, ollama_response.prompt_eval_count));
+        }
+        
+        let mut request = self
+            .client
+            .post(embeddings_url(&self.chat_completions_endpoint()?))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        
+        if !self.config.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", &self.config.api_key));
+        }
+        
+        let response = self.send_with_retry(request, "embeddings").await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(RustAiToolError::AiModel(format!("Embeddings API returned error: {}", error_text)));
+        }
+        
+        let mut embedding_response = response
+            .json::<OpenAiEmbeddingResponse>()
+            .await
+            .map_err(|e| RustAiToolError::AiModel(format!("Failed to parse embeddings response: {}", e)))?;
+        
+        embedding_response.data.sort_by_key(|embedding| embedding.index);
+        
+        Ok((
+            embedding_response.data.into_iter().map(|embedding| embedding.embedding).collect(),
+            embedding_response.usage.map(|usage| usage.prompt_tokens),
+        ))
     }
     
-    /// Generate code using the AI model
+    /// Pricing of the model: configured, built-in, or free for local models
+    fn pricing(&self) -> Option<ModelPricing> {
+        let model_name = self.model_name();
+        
+        self.config.pricing.get(&model_name).copied().or_else(|| match self.config.model_type {
+            AiModelType::Local(_) => Some(ModelPricing {
+                input_per_million: 0.0,
+                output_per_million: 0.0,
+            }),
+            _ => default_pricing(&model_name),
+        })
+    }
+    
+    /// Tokens a request needs: system message, prompt and completion budget
+    fn required_tokens(&self, request: &CompletionRequest) -> usize {
+        let tokenizer = tokenizer_for(&self.config.model_type);
+        
+        tokenizer.count_tokens(&request.prompt)
+            + request.system.as_deref().map_or(0, |system| tokenizer.count_tokens(system))
+            + request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) as usize
+    }
+    
+    /// Chat completions endpoint of the models speaking the OpenAI API (GPT, Mistral, OpenAI-compatible)
+    fn chat_completions_endpoint(&self) -> Result<String> {
+        match &self.config.model_type {
+            AiModelType::OpenAiCompatible(_) => {
+                let base_url = self.config.api_base_url.as_deref().ok_or_else(|| {
+                    RustAiToolError::AiModel("OpenAiCompatible models require api_base_url".to_string())
+                })?;
+                Ok(chat_completions_url(base_url))
+            }
+            AiModelType::Mistral => Ok(self.config.api_base_url.clone().unwrap_or_else(|| MISTRAL_CHAT_COMPLETIONS_URL.to_string())),
+            _ => Ok(self.config.api_base_url.clone().unwrap_or_else(|| OPENAI_CHAT_COMPLETIONS_URL.to_string())),
+        }
+    }
+    
+    /// Send a request, letting the model call tools if they are available
     ///
     /// # Arguments
     ///
-    /// * `prompt` - Prompt for the AI model
-    /// * `max_tokens` - Maximum number of tokens to generate
-    /// * `temperature` - Temperature (randomness)
+    /// * `request` - Completion request
     ///
     /// # Returns
     ///
-    /// The generated code
-    pub async fn generate_code(
-        &self,
-        prompt: &str,
-        max_tokens: Option<u32>,
-        temperature: Option<f32>,
-    ) -> Result<String> {
-        let system = Some(
-            "You are a helpful programming assistant that specializes in Rust code. \
-            Provide concise, idiomatic Rust code that follows best practices. \
-            Include helpful comments to explain your reasoning. \
-            When asked to generate or modify code, respond with only the requested code without explanations unless specifically asked."
-                .to_string(),
-        );
-        
+    /// The final completion response
+    async fn send_request(&self, request: CompletionRequest) -> Result<CompletionResponse> {
         let request = CompletionRequest {
-            prompt: prompt.to_string(),
-            max_tokens,
-            temperature,
-            system,
+            prompt: self.redact(&request.prompt),
+            system: request.system.as_deref().map(|system| self.redact(system)),
+            ..request
         };
         
-        let response = self.send_completion_request(request).await?;
+        let mut response = match &self.tools {
+            Some(tools) if self.supports_tools() => self.send_with_tools(request, tools).await?,
+            _ => self.send_completion_request(request).await?,
+        };
         
-        Ok(response.content)
+        response.content = self.restore_secrets(&response.content);
+        Ok(response)
     }
     
-    /// Analyze Rust code using the AI model
-    ///
-    /// # Arguments
-    ///
-    /// * `code` - Code to analyze
-    /// * `instructions` - Instructions for the analysis
-    ///
-    /// # Returns
-    ///
-    /// The analysis results
-    pub async fn analyze_code(&self, code: &str, instructions: &str) -> Result<String> {
-        let system = Some(
-            "You are a helpful programming assistant that specializes in analyzing Rust code. \
-            Focus on identifying issues related to correctness, performance, security, and idiomatic Rust. \
-            Be thorough but concise in your analysis."
-                .to_string(),
-        );
-        
-        let prompt = format!(
-            "Please analyze the following Rust code:\n\n```rust\n{}\n```\n\n{}",
-            code, instructions
-        );
+    /// Mask the secrets in a text before it is sent to the provider
+    fn redact(&self, text: &str) -> String {
+        let Some(redactor) = &self.redactor else {
+            return text.to_string();
+        };
         
-        let request = CompletionRequest {
-            prompt,
-            max_tokens: Some(4000),
-            temperature: Some(0.2),
-            system,
+        let mut redactor = match redactor.lock() {
+            Ok(redactor) => redactor,
+            Err(poisoned) => poisoned.into_inner(),
         };
         
-        let response = self.send_completion_request(request).await?;
+        let known = redactor.redacted_count();
+        let redacted = redactor.redact(text);
+        if redactor.redacted_count() > known {
+            info!("Masked {} secrets before sending code to the AI model", redactor.redacted_count() - known);
+        }
         
-        Ok(response.content)
+        redacted
     }
     
-    /// Generate fixes for Rust code issues
+    /// Put masked secrets back into an answer of the provider
+    fn restore_secrets(&self, text: &str) -> String {
+        match &self.redactor {
+            Some(redactor) => match redactor.lock() {
+                Ok(redactor) => redactor.restore(text),
+                Err(poisoned) => poisoned.into_inner().restore(text),
+            },
+            None => text.to_string(),
+        }
+    }
+    
+    /// Run a tool-calling conversation until the model answers without calling tools
+    ///
+    /// The model may call tools for at most `max_rounds` rounds; the last
+    /// request is sent without tools so the model has to answer.
     ///
     /// # Arguments
     ///
-    /// * `code` - Code with issues
-    /// * `issues` - Description of the issues
+    /// * `request` - Completion request starting the conversation
+    /// * `tools` - Tools the model may call
     ///
     /// # Returns
     ///
-    /// The fixed code
-    pub async fn generate_fixes(&self, code: &str, issues: &str) -> Result<String> {
-        let system = Some(
-            "You are a helpful programming assistant that specializes in fixing Rust code issues. \
-            Provide only the fixed code without explanations unless specifically asked. \
-            Ensure your fixes are idiomatic and follow Rust best practices."
-                .to_string(),
-        );
-        
-        let prompt = format!(
-            "Fix the following issues in this Rust code:\n\nIssues:\n{}\n\nCode:\n```rust\n{}\n```\n\nProvide the fixed code:",
-            issues, code
-        );
-        
-        let request = CompletionRequest {
-            prompt,
-            max_tokens: Some(4000),
-            temperature: Some(0.2),
-            system,
-        };
-        
-        let response = self.send_completion_request(request).await?;
-        
-        // Extract code blocks if present
-        let code_block_regex = regex::Regex::new(r"```(?:rust)?\s*\n([\s\S]+?)\n```").unwrap();
-        if let Some(captures) = code_block_regex.captures(&response.content) {
-            if let Some(code_match) = captures.get(1) {
-                return Ok(code_match.as_str().to_string());
+    /// The final answer of the model
+    async fn send_with_tools(&self, request: CompletionRequest, tools: &ProjectTools) -> Result<CompletionResponse> {
+        let definitions = tools.definitions();
+        let max_rounds = self.config.tools.max_rounds;
+        let mut conversation = vec![ConversationMessage::User(request.prompt.clone())];
+        
+        for round in 0..=max_rounds {
+            let offered: &[ToolDefinition] = if round < max_rounds { &definitions } else { &[] };
+            let turn = self.send_tool_turn(&request, &conversation, offered).await?;
+            
+            if let Some(usage) = &turn.usage {
+                record_request(
+                    &self.model_name(),
+                    usage.prompt_tokens as u64,
+                    usage.completion_tokens as u64,
+                    false,
+                    self.pricing(),
+                );
             }
+            
+            if turn.tool_calls.is_empty() {
+                return Ok(CompletionResponse {
+                    content: turn.content,
+                    finish_reason: turn.finish_reason,
+                    usage: turn.usage,
+                });
+            }
+            
+            info!(
+                "Model requested {} tool calls: {}",
+                turn.tool_calls.len(),
+                turn.tool_calls.iter().map(|call| call.name.as_str()).collect::<Vec<_>>().join(", ")
+            );
+            
+            let results: Vec<ConversationMessage> = turn.tool_calls.iter()
+                .map(|call| ConversationMessage::ToolResult {
+                    call_id: call.id.clone(),
+                    content: self.redact(&tools.execute(call)),
+                })
+                .collect();
+            
+            conversation.push(ConversationMessage::Assistant {
+                content: turn.content,
+                tool_calls: turn.tool_calls,
+            });
+            conversation.extend(results);
         }
         
-        // Otherwise return the raw response
-        Ok(response.content)
+        Err(RustAiToolError::AiModel(format!(
+            "Model kept calling tools after {} rounds",
+            max_rounds
+        )))
     }
     
-    /// Generate a Rust project description based on requirements
+    /// Send one turn of a tool-calling conversation
     ///
     /// # Arguments
     ///
-    /// * `requirements` - Project requirements
+    /// * `request` - Completion request starting the conversation
+    /// * `conversation` - Messages so far
+    /// * `tools` - Tools offered in this turn
     ///
     /// # Returns
     ///
-    /// The project description
-    pub async fn generate_project_description(&self, requirements: &str) -> Result<String> {
-        let system = Some(
-            "You are a helpful programming assistant that specializes in designing Rust projects. \
-            Based on user requirements, create detailed project descriptions including structure, \
-            dependencies, and approaches to implementation."
-                .to_string(),
-        );
+    /// The answer of the model
+    async fn send_tool_turn(
+        &self,
+        request: &CompletionRequest,
+        conversation: &[ConversationMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<ToolTurn> {
+        let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let temperature = request.temperature.unwrap_or(0.5);
+        
+        let http_request = if let AiModelType::Claude = &self.config.model_type {
+            let mut body = serde_json::json!({
+                "model": self.model_name(),
+                "max_tokens": max_tokens,
+                "temperature": temperature,
+                "messages": claude_messages(conversation),
+            });
+            if let Some(system) = &request.system {
+                body["system"] = serde_json::json!(system);
+            }
+            if !tools.is_empty() {
+                body["tools"] = tools.iter()
+                    .map(|tool| serde_json::json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    }))
+                    .collect();
+            }
+            
+            let endpoint = match &self.config.api_base_url {
+                Some(url) => url.strip_suffix("/complete").map_or_else(|| url.clone(), |base| format!("{}/messages", base)),
+                None => CLAUDE_MESSAGES_URL.to_string(),
+            };
+            
+            self.client
+                .post(&endpoint)
+                .header("Content-Type", "application/json")
+                .header("x-api-key", &self.config.api_key)
+                .header("anthropic-version", "2023-06-01")
+                .json(&body)
+        } else {
+            let mut body = serde_json::json!({
+                "model": self.model_name(),
+                "max_tokens": max_tokens,
+                "temperature": temperature,
+                "messages": openai_messages(request.system.as_deref(), conversation),
+            });
+            if !tools.is_empty() {
+                body["tools"] = tools.iter()
+                    .map(|tool| serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                        },
+                    }))
+                    .collect();
+            }
+            
+            let mut http_request = self
+                .client
+                .post(self.chat_completions_endpoint()?)
+                .header("Content-Type", "application/json")
+                .json(&body);
+            if !self.config.api_key.is_empty() {
+                http_request = http_request.header("Authorization", format!("Bearer {}", &self.config.api_key));
+            }
+            http_request
+        };
         
-        let prompt = format!(
-            "Generate a detailed Rust project description based on these requirements:\n\n{}\n\n\
-            Include suggested crate dependencies, file structure, and implementation approach.",
-            requirements
-        );
+        let response = self.send_with_retry(http_request, "tool calling").await?;
         
-        let request = CompletionRequest {
-            prompt,
-            max_tokens: Some(2000),
-            temperature: Some(0.7),
-            system,
-        };
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(RustAiToolError::AiModel(format!(
+                "Tool-calling request returned error: {}",
+                error_text
+            )));
+        }
         
-        let response = self.send_completion_request(request).await?;
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| RustAiToolError::AiModel(format!("Failed to parse tool-calling response: {}", e)))?;
         
-        Ok(response.content)
+        if let AiModelType::Claude = &self.config.model_type {
+            parse_claude_turn(&body)
+        } else {
+            parse_openai_turn(&body)
+        }
     }
     
     /// Send a completion request to the AI model
@@ -246,15 +1280,172 @@ impl AiModelClient {
     /// # Returns
     ///
     /// The completion response
+    #[tracing::instrument(skip_all, fields(model = %self.model_name(), cached, prompt_tokens, completion_tokens), err)]
     async fn send_completion_request(
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionResponse> {
-        match &self.config.model_type {
+        let cache = ResponseCache::open(&self.config.cache, self.project_root.as_deref());
+        let key = ResponseCache::key(&self.config, &request);
+        
+        if let Some(cached) = cache.as_ref().and_then(|c| c.get(&key)) {
+            info!("Using cached AI response");
+            tracing::Span::current().record("cached", true);
+            record_cache_hit(&self.model_name());
+            return Ok(CompletionResponse {
+                content: cached.content,
+                finish_reason: cached.finish_reason,
+                usage: None,
+            });
+        }
+        
+        let tokenizer = tokenizer_for(&self.config.model_type);
+        let prompt_estimate = tokenizer.count_tokens(&request.prompt)
+            + request.system.as_deref().map_or(0, |system| tokenizer.count_tokens(system));
+        
+        let required = self.required_tokens(&request);
+        let window = self.context_window().await;
+        if required > window {
+            return Err(RustAiToolError::AiModel(format!(
+                "Prompt needs about {} tokens including the completion, more than the {}-token context window",
+                required, window
+            )));
+        }
+        
+        let response = match &self.config.model_type {
             AiModelType::Claude => self.send_claude_request(request).await,
-            AiModelType::Gpt => self.send_gpt_request(request).await,
+            AiModelType::Gpt => self.send_gpt_request(request, &self.chat_completions_endpoint()?, "GPT").await,
             AiModelType::Mistral => self.send_mistral_request(request).await,
             AiModelType::Local(model_name) => self.send_local_request(request, model_name).await,
+            AiModelType::OpenAiCompatible(_) => {
+                self.send_gpt_request(request, &self.chat_completions_endpoint()?, "OpenAI-compatible").await
+            }
+        }?;
+        
+        if let Some(usage) = &response.usage {
+            tracing::Span::current()
+                .record("prompt_tokens", usage.prompt_tokens)
+                .record("completion_tokens", usage.completion_tokens);
+        }
+        match &response.usage {
+            Some(usage) => record_request(
+                &self.model_name(),
+                usage.prompt_tokens as u64,
+                usage.completion_tokens as u64,
+                false,
+                self.pricing(),
+            ),
+            None => record_request(
+                &self.model_name(),
+                prompt_estimate as u64,
+                tokenizer.count_tokens(&response.content) as u64,
+                true,
+                self.pricing(),
+            ),
+        }
+        
+        if let Some(cache) = cache {
+            if let Err(e) = cache.put(&key, &response.content, response.finish_reason.as_deref()) {
+                warn!("Failed to cache AI response: {}", e);
+            }
+        }
+        
+        Ok(response)
+    }
+    
+    /// Rate limiter of the provider this client sends requests to
+    ///
+    /// Clients of the same model type and base URL share a limiter, so the
+    /// limits of one provider do not slow down requests to another.
+    fn rate_limiter(&self) -> &'static RateLimiter {
+        let provider = format!(
+            "{}|{}",
+            match &self.config.model_type {
+                AiModelType::Claude => "claude",
+                AiModelType::Gpt => "openai",
+                AiModelType::Mistral => "mistral",
+                AiModelType::Local(_) => "local",
+                AiModelType::OpenAiCompatible(_) => "openai-compatible",
+            },
+            self.config.api_base_url.as_deref().unwrap_or_default()
+        );
+        
+        let limiters = RATE_LIMITERS.get_or_init(Default::default);
+        let mut limiters = match limiters.lock() {
+            Ok(limiters) => limiters,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        
+        // Limiters live for the whole process; there is one per provider
+        limiters.entry(provider)
+            .or_insert_with(|| Box::leak(Box::new(RateLimiter::new(&self.config.retry))))
+    }
+    
+    /// Send an HTTP request, retrying rate-limited, failed and timed-out attempts
+    ///
+    /// Requests go through the rate limiter of the provider. A `Retry-After`
+    /// header takes precedence over the exponential backoff. Once the retries
+    /// are exhausted, the last response is returned for the caller to report.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Request to send
+    /// * `provider` - Provider name used in messages
+    ///
+    /// # Returns
+    ///
+    /// The HTTP response
+    #[tracing::instrument(skip_all, fields(provider, attempts), err)]
+    async fn send_with_retry(&self, request: reqwest::RequestBuilder, provider: &str) -> Result<reqwest::Response> {
+        let options = &self.config.retry;
+        let limiter = self.rate_limiter();
+        let mut attempt = 0;
+        
+        loop {
+            let attempt_request = request.try_clone().ok_or_else(|| {
+                RustAiToolError::AiModel(format!("{} API request cannot be retried", provider))
+            })?;
+            
+            let permit = limiter.acquire().await?;
+            tracing::Span::current().record("attempts", attempt + 1);
+            let result = attempt_request.send().await;
+            drop(permit);
+            
+            let delay = match result {
+                Ok(response) if !is_retryable_status(response.status()) || attempt >= options.max_retries => {
+                    return Ok(response);
+                }
+                Ok(response) => {
+                    let delay = retry_after(response.headers()).unwrap_or_else(|| backoff_delay(options, attempt));
+                    warn!(
+                        "{} API returned {}, retrying in {:?} ({}/{})",
+                        provider,
+                        response.status(),
+                        delay,
+                        attempt + 1,
+                        options.max_retries
+                    );
+                    delay
+                }
+                Err(e) if (e.is_timeout() || e.is_connect()) && attempt < options.max_retries => {
+                    let delay = backoff_delay(options, attempt);
+                    warn!(
+                        "{} API request failed ({}), retrying in {:?} ({}/{})",
+                        provider,
+                        e,
+                        delay,
+                        attempt + 1,
+                        options.max_retries
+                    );
+                    delay
+                }
+                Err(e) => {
+                    return Err(RustAiToolError::AiModel(format!("{} API request failed: {}", provider, e)));
+                }
+            };
+            
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
     }
     
@@ -285,9 +1476,9 @@ impl AiModelClient {
         debug!("Sending request to Claude AI");
         
         let claude_request = ClaudeRequest {
-            model: "claude-3-opus-20240229".to_string(), // Use appropriate model version
+            model: self.model_name(),
             prompt: request.prompt,
-            max_tokens_to_sample: request.max_tokens.unwrap_or(4000),
+            max_tokens_to_sample: request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
             temperature: request.temperature.unwrap_or(0.5),
             system: request.system,
         };
@@ -296,16 +1487,15 @@ impl AiModelClient {
             "https://api.anthropic.com/v1/complete".to_string()
         });
         
-        let response = self
+        let request = self
             .client
             .post(&api_base)
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .header("anthropic-version", "2023-06-01")
-            .json(&claude_request)
-            .send()
-            .await
-            .map_err(|e| RustAiToolError::AiModel(format!("Claude API request failed: {}", e)))?;
+            .json(&claude_request);
+        
+        let response = self.send_with_retry(request, "Claude").await?;
         
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
@@ -329,16 +1519,23 @@ impl AiModelClient {
         })
     }
     
-    /// Send a completion request to OpenAI GPT
+    /// Send a completion request to OpenAI GPT or an OpenAI-compatible server
     ///
     /// # Arguments
     ///
     /// * `request` - Completion request
+    /// * `endpoint` - URL of the chat completions endpoint
+    /// * `provider` - Provider name used in log and error messages
     ///
     /// # Returns
     ///
     /// The completion response
-    async fn send_gpt_request(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+    async fn send_gpt_request(
+        &self,
+        request: CompletionRequest,
+        endpoint: &str,
+        provider: &str,
+    ) -> Result<CompletionResponse> {
         #[derive(Serialize)]
         struct GptMessage {
             role: String,
@@ -372,7 +1569,7 @@ impl AiModelClient {
             usage: Option<GptResponseUsage>,
         }
         
-        debug!("Sending request to OpenAI GPT");
+        debug!("Sending request to {}", provider);
         
         let mut messages = Vec::new();
         
@@ -391,41 +1588,40 @@ impl AiModelClient {
         });
         
         let gpt_request = GptRequest {
-            model: "gpt-4".to_string(), // Use appropriate model version
+            model: self.model_name(),
             messages,
             max_tokens: request.max_tokens,
             temperature: request.temperature,
         };
         
-        let api_base = self.config.api_base_url.clone().unwrap_or_else(|| {
-            "https://api.openai.com/v1/chat/completions".to_string()
-        });
-        
-        let response = self
+        let mut request = self
             .client
-            .post(&api_base)
+            .post(endpoint)
             .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", &self.config.api_key))
-            .json(&gpt_request)
-            .send()
-            .await
-            .map_err(|e| RustAiToolError::AiModel(format!("GPT API request failed: {}", e)))?;
+            .json(&gpt_request);
+        
+        // Local OpenAI-compatible servers usually run without authentication
+        if !self.config.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", &self.config.api_key));
+        }
+        
+        let response = self.send_with_retry(request, provider).await?;
         
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             return Err(RustAiToolError::AiModel(format!(
-                "GPT API returned error: {}",
-                error_text
+                "{} API returned error: {}",
+                provider, error_text
             )));
         }
         
         let gpt_response = response
             .json::<GptResponse>()
             .await
-            .map_err(|e| RustAiToolError::AiModel(format!("Failed to parse GPT response: {}", e)))?;
+            .map_err(|e| RustAiToolError::AiModel(format!("Failed to parse {} response: {}", provider, e)))?;
         
         if gpt_response.choices.is_empty() {
-            return Err(RustAiToolError::AiModel("GPT API returned no choices".to_string()));
+            return Err(RustAiToolError::AiModel(format!("{} API returned no choices", provider)));
         }
         
         let content = gpt_response.choices[0].message.content.clone();
@@ -437,7 +1633,7 @@ impl AiModelClient {
             total_tokens: u.total_tokens,
         });
         
-        debug!("Received response from OpenAI GPT");
+        debug!("Received response from {}", provider);
         
         Ok(CompletionResponse {
             content,
@@ -508,25 +1704,24 @@ impl AiModelClient {
         });
         
         let mistral_request = MistralRequest {
-            model: "mistral-large-latest".to_string(), // Use appropriate model version
+            model: self.model_name(),
             messages,
             max_tokens: request.max_tokens,
             temperature: request.temperature,
         };
         
         let api_base = self.config.api_base_url.clone().unwrap_or_else(|| {
-            "https://api.mistral.ai/v1/chat/completions".to_string()
+            MISTRAL_CHAT_COMPLETIONS_URL.to_string()
         });
         
-        let response = self
+        let request = self
             .client
             .post(&api_base)
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", &self.config.api_key))
-            .json(&mistral_request)
-            .send()
-            .await
-            .map_err(|e| RustAiToolError::AiModel(format!("Mistral API request failed: {}", e)))?;
+            .json(&mistral_request);
+        
+        let response = self.send_with_retry(request, "Mistral").await?;
         
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
@@ -590,6 +1785,7 @@ impl AiModelClient {
         struct OllamaOptions {
             temperature: Option<f32>,
             num_predict: Option<u32>,
+            num_ctx: Option<u32>,
         }
         
         #[derive(Deserialize)]
@@ -600,6 +1796,10 @@ impl AiModelClient {
         
         debug!("Sending request to local Ollama model: {}", model_name);
         
+        // Ollama truncates prompts to its default context size unless told otherwise
+        let default_window = default_context_window(&self.config.model_type);
+        let num_ctx = self.required_tokens(&request).clamp(default_window, self.context_window().await.max(default_window));
+        
         let ollama_request = OllamaRequest {
             model: model_name.to_string(),
             prompt: request.prompt,
@@ -607,21 +1807,21 @@ impl AiModelClient {
             options: Some(OllamaOptions {
                 temperature: request.temperature,
                 num_predict: request.max_tokens,
+                num_ctx: Some(num_ctx as u32),
             }),
         };
         
         let api_base = self.config.api_base_url.clone().unwrap_or_else(|| {
-            "http://localhost:11434/api/generate".to_string()
+            OLLAMA_GENERATE_URL.to_string()
         });
         
-        let response = self
+        let request = self
             .client
             .post(&api_base)
             .header("Content-Type", "application/json")
-            .json(&ollama_request)
-            .send()
-            .await
-            .map_err(|e| RustAiToolError::AiModel(format!("Ollama API request failed: {}", e)))?;
+            .json(&ollama_request);
+        
+        let response = self.send_with_retry(request, "Ollama").await?;
         
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
@@ -644,4 +1844,269 @@ impl AiModelClient {
             usage: None,
         })
     }
-}
\ No newline at end of file
+}
+
+/// One answer of a model in a tool-calling conversation
+struct ToolTurn {
+    /// Text of the answer
+    content: String,
+    
+    /// Requested tool calls
+    tool_calls: Vec<ToolCall>,
+    
+    /// Finish reason
+    finish_reason: Option<String>,
+    
+    /// Token usage of the turn
+    usage: Option<UsageInfo>,
+}
+
+/// Conversation in the OpenAI chat format
+fn openai_messages(system: Option<&str>, conversation: &[ConversationMessage]) -> Vec<serde_json::Value> {
+    let mut messages: Vec<serde_json::Value> = system
+        .map(|system| serde_json::json!({ "role": "system", "content": system }))
+        .into_iter()
+        .collect();
+    
+    for message in conversation {
+        messages.push(match message {
+            ConversationMessage::User(content) => serde_json::json!({ "role": "user", "content": content }),
+            ConversationMessage::Assistant { content, tool_calls } => {
+                let calls: Vec<serde_json::Value> = tool_calls.iter()
+                    .map(|call| serde_json::json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                    }))
+                    .collect();
+                serde_json::json!({ "role": "assistant", "content": content, "tool_calls": calls })
+            }
+            ConversationMessage::ToolResult { call_id, content } => {
+                serde_json::json!({ "role": "tool", "tool_call_id": call_id, "content": content })
+            }
+        });
+    }
+    
+    messages
+}
+
+/// Conversation in the Claude messages format, with tool results grouped into user messages
+fn claude_messages(conversation: &[ConversationMessage]) -> Vec<serde_json::Value> {
+    let mut messages: Vec<serde_json::Value> = Vec::new();
+    
+    for message in conversation {
+        match message {
+            ConversationMessage::User(content) => {
+                messages.push(serde_json::json!({ "role": "user", "content": content }));
+            }
+            ConversationMessage::Assistant { content, tool_calls } => {
+                let mut blocks = Vec::new();
+                if !content.is_empty() {
+                    blocks.push(serde_json::json!({ "type": "text", "text": content }));
+                }
+                blocks.extend(tool_calls.iter().map(|call| serde_json::json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.name,
+                    "input": call.arguments,
+                })));
+                messages.push(serde_json::json!({ "role": "assistant", "content": blocks }));
+            }
+            ConversationMessage::ToolResult { call_id, content } => {
+                let block = serde_json::json!({ "type": "tool_result", "tool_use_id": call_id, "content": content });
+                
+                match messages.last_mut() {
+                    Some(last) if last["role"] == "user" && last["content"].is_array() => {
+                        last["content"].as_array_mut().unwrap().push(block);
+                    }
+                    _ => messages.push(serde_json::json!({ "role": "user", "content": [block] })),
+                }
+            }
+        }
+    }
+    
+    messages
+}
+
+/// Parse an OpenAI chat completions response of a tool-calling conversation
+fn parse_openai_turn(body: &serde_json::Value) -> Result<ToolTurn> {
+    let choice = body["choices"].get(0)
+        .ok_or_else(|| RustAiToolError::AiModel("Tool-calling response has no choices".to_string()))?;
+    
+    let tool_calls = choice["message"]["tool_calls"].as_array()
+        .map(|calls| calls.iter()
+            .map(|call| ToolCall {
+                id: call["id"].as_str().unwrap_or_default().to_string(),
+                name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                // Arguments arrive as a JSON-encoded string
+                arguments: call["function"]["arguments"].as_str()
+                    .and_then(|arguments| serde_json::from_str(arguments).ok())
+                    .unwrap_or_else(|| serde_json::json!({})),
+            })
+            .collect())
+        .unwrap_or_default();
+    
+    Ok(ToolTurn {
+        content: choice["message"]["content"].as_str().unwrap_or_default().to_string(),
+        tool_calls,
+        finish_reason: choice["finish_reason"].as_str().map(str::to_string),
+        usage: body["usage"]["prompt_tokens"].as_u64().map(|prompt_tokens| {
+            let completion_tokens = body["usage"]["completion_tokens"].as_u64().unwrap_or_default();
+            UsageInfo {
+                prompt_tokens: prompt_tokens as u32,
+                completion_tokens: completion_tokens as u32,
+                total_tokens: (prompt_tokens + completion_tokens) as u32,
+            }
+        }),
+    })
+}
+
+/// Parse a Claude messages response of a tool-calling conversation
+fn parse_claude_turn(body: &serde_json::Value) -> Result<ToolTurn> {
+    let blocks = body["content"].as_array()
+        .ok_or_else(|| RustAiToolError::AiModel("Tool-calling response has no content".to_string()))?;
+    
+    let content = blocks.iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    let tool_calls = blocks.iter()
+        .filter(|block| block["type"] == "tool_use")
+        .map(|block| ToolCall {
+            id: block["id"].as_str().unwrap_or_default().to_string(),
+            name: block["name"].as_str().unwrap_or_default().to_string(),
+            arguments: block["input"].clone(),
+        })
+        .collect();
+    
+    Ok(ToolTurn {
+        content,
+        tool_calls,
+        finish_reason: body["stop_reason"].as_str().map(str::to_string),
+        usage: body["usage"]["input_tokens"].as_u64().map(|input_tokens| {
+            let output_tokens = body["usage"]["output_tokens"].as_u64().unwrap_or_default();
+            UsageInfo {
+                prompt_tokens: input_tokens as u32,
+                completion_tokens: output_tokens as u32,
+                total_tokens: (input_tokens + output_tokens) as u32,
+            }
+        }),
+    })
+}
+
+/// Chat completions endpoint of an OpenAI-compatible server
+///
+/// Accepts either the API base (e.g. `http://localhost:8080/v1`) or the full endpoint URL.
+fn chat_completions_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    
+    if base_url.ends_with("/chat/completions") {
+        base_url.to_string()
+    } else {
+        format!("{}/chat/completions", base_url)
+    }
+}
+
+/// Embeddings endpoint next to a chat completions endpoint or API base
+fn embeddings_url(chat_url: &str) -> String {
+    let base_url = chat_url.trim_end_matches('/');
+    format!("{}/embeddings", base_url.strip_suffix("/chat/completions").unwrap_or(base_url))
+}
+
+/// Whether a response status is worth retrying
+fn is_retryable_status(status: StatusCode) -> bool {
+    // 529 is Anthropic's "overloaded"
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Delay requested by a `Retry-After` header given in seconds
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds: f64 = headers.get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Exponential backoff delay before a retry
+fn backoff_delay(options: &RetryOptions, attempt: u32) -> Duration {
+    let delay = options.initial_backoff_ms.saturating_mul(1u64 << attempt.min(20));
+    Duration::from_millis(delay.min(options.max_backoff_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_retry_delays() {
+        let options = RetryOptions {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3000,
+            ..Default::default()
+        };
+
+        assert_eq!(backoff_delay(&options, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&options, 2), Duration::from_millis(2000));
+        assert_eq!(backoff_delay(&options, 10), Duration::from_millis(3000));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_endpoint_urls() {
+        assert_eq!(chat_completions_url("http://localhost:8080/v1"), "http://localhost:8080/v1/chat/completions");
+        assert_eq!(chat_completions_url("https://openrouter.ai/api/v1/"), "https://openrouter.ai/api/v1/chat/completions");
+        assert_eq!(chat_completions_url("http://host/v1/chat/completions"), "http://host/v1/chat/completions");
+        assert_eq!(embeddings_url("https://api.mistral.ai/v1/chat/completions"), "https://api.mistral.ai/v1/embeddings");
+        assert_eq!(embeddings_url("http://localhost:8080/v1/"), "http://localhost:8080/v1/embeddings");
+    }
+
+    #[test]
+    fn test_tool_conversation_formats() {
+        let body = serde_json::json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "read_file", "arguments": "{\"path\":\"src/lib.rs\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5 }
+        });
+        let turn = parse_openai_turn(&body).unwrap();
+
+        assert_eq!(turn.content, "");
+        assert_eq!(turn.tool_calls[0].name, "read_file");
+        assert_eq!(turn.tool_calls[0].arguments["path"], "src/lib.rs");
+        assert_eq!(turn.usage.unwrap().total_tokens, 15);
+
+        let conversation = vec![
+            ConversationMessage::User("Fix it".to_string()),
+            ConversationMessage::Assistant { content: String::new(), tool_calls: turn.tool_calls.clone() },
+            ConversationMessage::ToolResult { call_id: "call_1".to_string(), content: "a".to_string() },
+            ConversationMessage::ToolResult { call_id: "call_2".to_string(), content: "b".to_string() },
+        ];
+
+        // Claude expects the results of one turn in a single user message
+        let messages = claude_messages(&conversation);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][0]["type"], "tool_use");
+        assert_eq!(messages[2]["content"].as_array().unwrap().len(), 2);
+
+        let messages = openai_messages(Some("system"), &conversation);
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[2]["tool_calls"][0]["function"]["arguments"], "{\"path\":\"src/lib.rs\"}");
+        assert_eq!(messages[4]["tool_call_id"], "call_2");
+    }
+}
@@ -0,0 +1,201 @@
+//! AI response cache module
+//!
+//! This module stores completions on disk so unchanged prompts are not sent twice:
+//! - Entries are addressed by a SHA-256 of the model, endpoint, prompt and parameters
+//! - Entries older than the configured TTL are ignored and removed
+//! - One JSON file per entry under `.rust-ai-tool/cache` of the project by default
+
+use crate::journal::STATE_DIR;
+use crate::{AiModelConfig, CacheOptions, Result, RustAiToolError};
+use super::ai_integration::CompletionRequest;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{debug, warn};
+
+/// Directory inside the state directory holding cached completions
+pub const CACHE_DIR: &str = "cache";
+
+/// A cached completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedCompletion {
+    /// Time the completion was received (seconds since the Unix epoch)
+    pub created_at: u64,
+
+    /// The generated text
+    pub content: String,
+
+    /// Finish reason
+    pub finish_reason: Option<String>,
+}
+
+/// On-disk cache of AI completions
+pub struct ResponseCache {
+    /// Directory holding the entries
+    dir: PathBuf,
+
+    /// Maximum age of a usable entry (seconds)
+    ttl_secs: u64,
+}
+
+impl ResponseCache {
+    /// Open the cache described by the options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Cache options
+    /// * `project_root` - Root of the project the completions are for, holding the default cache directory
+    ///
+    /// # Returns
+    ///
+    /// The cache, or `None` if caching is disabled, or if no directory is
+    /// configured and the completions are for no project
+    pub fn open(options: &CacheOptions, project_root: Option<&Path>) -> Option<Self> {
+        if !options.enabled {
+            return None;
+        }
+
+        let dir = match (&options.dir, project_root) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(project_root)) => project_root.join(STATE_DIR).join(CACHE_DIR),
+            (None, None) => {
+                debug!("Not caching completions outside of a project");
+                return None;
+            }
+        };
+
+        Some(Self {
+            dir,
+            ttl_secs: options.ttl_secs,
+        })
+    }
+
+    /// Compute the cache key of a request
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration of the model the request is sent to
+    /// * `request` - Completion request
+    ///
+    /// # Returns
+    ///
+    /// The hex-encoded SHA-256 key
+    pub fn key(config: &AiModelConfig, request: &CompletionRequest) -> String {
+        let material = serde_json::json!({
+            "model": format!("{:?}", config.model_type),
+            "endpoint": config.api_base_url,
+            "request": request,
+        });
+
+        let digest = ring::digest::digest(&ring::digest::SHA256, material.to_string().as_bytes());
+        data_encoding::HEXLOWER.encode(digest.as_ref())
+    }
+
+    /// Look up a completion
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Cache key
+    ///
+    /// # Returns
+    ///
+    /// The cached completion, if present and not expired
+    pub fn get(&self, key: &str) -> Option<CachedCompletion> {
+        let path = self.entry_path(key);
+        let content = fs::read_to_string(&path).ok()?;
+
+        let entry: CachedCompletion = match serde_json::from_str(&content) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+                return None;
+            }
+        };
+
+        if now().saturating_sub(entry.created_at) > self.ttl_secs {
+            debug!("Cache entry {} expired", key);
+            fs::remove_file(&path).ok();
+            return None;
+        }
+
+        debug!("Cache hit for {}", key);
+        Some(entry)
+    }
+
+    /// Store a completion
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Cache key
+    /// * `content` - The generated text
+    /// * `finish_reason` - Finish reason
+    pub fn put(&self, key: &str, content: &str, finish_reason: Option<&str>) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .map_err(RustAiToolError::Io)?;
+
+        let entry = CachedCompletion {
+            created_at: now(),
+            content: content.to_string(),
+            finish_reason: finish_reason.map(str::to_string),
+        };
+
+        let json = serde_json::to_string(&entry)
+            .map_err(RustAiToolError::Json)?;
+        fs::write(self.entry_path(key), json)
+            .map_err(RustAiToolError::Io)
+    }
+
+    /// Path of the file holding an entry
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// Current time in seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cache_roundtrip_and_ttl() {
+        let dir = tempdir().unwrap();
+        let options = CacheOptions {
+            enabled: true,
+            dir: Some(dir.path().to_path_buf()),
+            ttl_secs: 60,
+        };
+        let cache = ResponseCache::open(&options, None).unwrap();
+
+        let key = "abc";
+        assert!(cache.get(key).is_none());
+
+        cache.put(key, "fn main() {}", Some("stop")).unwrap();
+        assert_eq!(cache.get(key).unwrap().content, "fn main() {}");
+
+        // An entry older than the TTL is dropped
+        let stale = CachedCompletion {
+            created_at: now() - 120,
+            content: "old".to_string(),
+            finish_reason: None,
+        };
+        fs::write(cache.entry_path(key), serde_json::to_string(&stale).unwrap()).unwrap();
+        assert!(cache.get(key).is_none());
+        assert!(!cache.entry_path(key).exists());
+
+        let project = tempdir().unwrap();
+        let default = CacheOptions { dir: None, ..options.clone() };
+        assert_eq!(ResponseCache::open(&default, Some(project.path())).unwrap().dir, project.path().join(STATE_DIR).join(CACHE_DIR));
+        assert!(ResponseCache::open(&default, None).is_none());
+
+        assert!(ResponseCache::open(&CacheOptions { enabled: false, ..options }, None).is_none());
+    }
+}
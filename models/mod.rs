@@ -5,7 +5,17 @@
 //! - Integration with OpenAI GPT models
 //! - Integration with Mistral AI
 //! - Integration with local models via Ollama
+//! - On-disk caching of completions
+//! - Token counting and context window budgets
+//! - Usage and cost tracking
+//! - Read-only project tools for models that support tool calling
+//! - Masking of secrets in prompts
 
 pub mod ai_integration;
+pub mod cache;
+pub mod redaction;
+pub mod tokenizer;
+pub mod tools;
+pub mod usage;
 
 pub use ai_integration::*;
\ No newline at end of file
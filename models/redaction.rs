@@ -0,0 +1,151 @@
+//! Secret redaction module
+//!
+//! This module keeps credentials out of prompts sent to AI providers:
+//! - Detecting API keys, access tokens, private keys and `.env`-style values
+//! - Replacing every secret with a placeholder before the text leaves the machine
+//! - Restoring the placeholders in the model's answers from a mapping kept in memory
+
+use crate::journal::content_hash;
+use crate::{Result, RustAiToolError};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Built-in patterns; the `secret` group (or the whole match) is redacted
+const SECRET_PATTERNS: &[&str] = &[
+    // AWS access key ids
+    r"\b(?P<secret>(?:AKIA|ASIA)[0-9A-Z]{16})\b",
+    // OpenAI and Anthropic API keys
+    r"\b(?P<secret>sk-(?:ant-|proj-)?[A-Za-z0-9_\-]{20,})",
+    // GitHub tokens
+    r"\b(?P<secret>(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,}))",
+    // Slack tokens
+    r"\b(?P<secret>xox[abposr]-[A-Za-z0-9\-]{10,})",
+    // Google API keys
+    r"\b(?P<secret>AIza[0-9A-Za-z_\-]{35})",
+    // PEM private keys
+    r"(?s)(?P<secret>-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----)",
+    // `.env`-style assignments, e.g. `DATABASE_PASSWORD=hunter22`
+    r#"(?m)^\s*(?:export\s+)?[A-Z0-9_]*(?:KEY|SECRET|TOKEN|PASSWORD|PASSWD|CREDENTIALS?)[A-Z0-9_]*\s*=\s*["']?(?P<secret>[^\s"'#]{4,})"#,
+    // String literals assigned to secret-looking names, e.g. `let api_key = "..."`
+    r#"(?i)\b[a-z0-9_]*(?:api_?key|secret|token|password|passwd)[a-z0-9_]*"?\s*(?::\s*&?(?:'static\s+)?str\s*)?[=:]\s*"(?P<secret>[^"\s]{8,})""#,
+];
+
+/// Replaces secrets with placeholders and restores them
+///
+/// Only placeholders leave the machine; the mapping back to the secrets
+/// stays in the redactor.
+pub struct Redactor {
+    /// Compiled detection patterns
+    patterns: Vec<Regex>,
+
+    /// Secret of every placeholder handed out
+    secrets: HashMap<String, String>,
+
+    /// Placeholder of every secret seen, so a secret always gets the same one
+    placeholders: HashMap<String, String>,
+}
+
+impl Redactor {
+    /// Create a redactor with the built-in patterns and additional ones
+    ///
+    /// # Arguments
+    ///
+    /// * `extra_patterns` - Additional regular expressions; a `secret` group limits what is redacted
+    ///
+    /// # Returns
+    ///
+    /// The redactor, or an error if a pattern is invalid
+    pub fn new(extra_patterns: &[String]) -> Result<Self> {
+        let patterns = SECRET_PATTERNS.iter()
+            .map(|p| p.to_string())
+            .chain(extra_patterns.iter().cloned())
+            .map(|pattern| Regex::new(&pattern)
+                .map_err(|e| RustAiToolError::AiModel(format!("Invalid redaction pattern `{}`: {}", pattern, e))))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            patterns,
+            secrets: HashMap::new(),
+            placeholders: HashMap::new(),
+        })
+    }
+
+    /// Replace the secrets in a text with placeholders
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to send to a provider
+    ///
+    /// # Returns
+    ///
+    /// The text with every detected secret replaced
+    pub fn redact(&mut self, text: &str) -> String {
+        let mut redacted = text.to_string();
+
+        for pattern in &self.patterns {
+            let found: Vec<String> = pattern.captures_iter(&redacted)
+                .filter_map(|captures| captures.name("secret").or_else(|| captures.get(0)))
+                .map(|m| m.as_str().to_string())
+                .filter(|secret| !self.secrets.contains_key(secret))
+                .collect();
+
+            for secret in found {
+                // Derived from the secret, so cached answers from earlier runs restore correctly
+                let placeholder = self.placeholders.entry(secret.clone())
+                    .or_insert_with(|| format!("__REDACTED_SECRET_{}__", &content_hash(&secret)[..8]))
+                    .clone();
+                self.secrets.insert(placeholder.clone(), secret.clone());
+                redacted = redacted.replace(&secret, &placeholder);
+            }
+        }
+
+        redacted
+    }
+
+    /// Put the secrets back in place of their placeholders
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text received from a provider
+    ///
+    /// # Returns
+    ///
+    /// The text with every known placeholder restored
+    pub fn restore(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |restored, (placeholder, secret)| {
+            restored.replace(placeholder, secret)
+        })
+    }
+
+    /// Number of distinct secrets redacted so far
+    pub fn redacted_count(&self) -> usize {
+        self.secrets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_and_restore() {
+        let mut redactor = Redactor::new(&[]).unwrap();
+        let code = "const API_KEY: &str = \"sk-abcdefghijklmnopqrstuvwx\";\nlet password = \"correct-horse\";\nlet name = \"not a secret\";\n";
+
+        let redacted = redactor.redact(code);
+        assert!(!redacted.contains("sk-abcdefghijklmnopqrstuvwx"));
+        assert!(!redacted.contains("correct-horse"));
+        assert!(redacted.contains("let name = \"not a secret\";"));
+        assert_eq!(redactor.redacted_count(), 2);
+
+        // The same secret always maps to the same placeholder
+        assert_eq!(redactor.redact("KEY=sk-abcdefghijklmnopqrstuvwx"), redactor.redact("KEY=sk-abcdefghijklmnopqrstuvwx"));
+
+        let env = redactor.redact("DATABASE_PASSWORD=hunter22\nPORT=8080\n");
+        assert!(env.starts_with("DATABASE_PASSWORD=__REDACTED_SECRET_"));
+        assert!(env.ends_with("__\nPORT=8080\n"));
+
+        assert_eq!(redactor.restore(&redacted), code);
+        assert_eq!(redactor.restore(&env), "DATABASE_PASSWORD=hunter22\nPORT=8080\n");
+    }
+}
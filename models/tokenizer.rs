@@ -0,0 +1,243 @@
+//! Token counting module
+//!
+//! This module estimates prompt sizes before they are sent to a model:
+//! - A tiktoken-style estimator for GPT, Mistral and local models
+//! - An estimator for Claude's tokenizer
+//! - Context window sizes, read from Ollama model metadata for local models
+//! - Splitting of large inputs into chunks that fit a token budget
+
+use crate::AiModelType;
+use log::debug;
+
+/// Completion budget assumed when a request does not set `max_tokens`
+pub const DEFAULT_MAX_TOKENS: u32 = 4000;
+
+/// Counts the tokens a model would see for a text
+pub trait Tokenizer: Send + Sync {
+    /// Estimate the number of tokens in a text
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Estimator modelled on tiktoken's `cl100k_base` pre-tokenization
+///
+/// Text is split into letter, digit, punctuation and whitespace runs like
+/// tiktoken does before applying BPE; each run is then costed conservatively.
+pub struct BpeEstimator;
+
+/// Estimator for Claude models, whose tokenizer yields more tokens on code than `cl100k_base`
+pub struct ClaudeEstimator;
+
+/// Character class used for pre-tokenization
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Punct,
+}
+
+impl Tokenizer for BpeEstimator {
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut run_class = None;
+        let mut run_len = 0;
+
+        for c in text.chars() {
+            let class = if c.is_alphabetic() {
+                CharClass::Letter
+            } else if c.is_numeric() {
+                CharClass::Digit
+            } else if c.is_whitespace() {
+                CharClass::Space
+            } else {
+                CharClass::Punct
+            };
+
+            if run_class != Some(class) {
+                tokens += run_tokens(run_class, run_len);
+                run_class = Some(class);
+                run_len = 0;
+            }
+
+            run_len += 1;
+        }
+
+        tokens + run_tokens(run_class, run_len)
+    }
+}
+
+impl Tokenizer for ClaudeEstimator {
+    fn count_tokens(&self, text: &str) -> usize {
+        (BpeEstimator.count_tokens(text) as f64 * 1.2).ceil() as usize
+    }
+}
+
+/// Tokens for a run of characters of the same class
+fn run_tokens(class: Option<CharClass>, len: usize) -> usize {
+    match class {
+        None => 0,
+        // Common words are a single token, long identifiers split every few characters
+        Some(CharClass::Letter) => len.div_ceil(4),
+        // Numbers are split into groups of up to three digits
+        Some(CharClass::Digit) => len.div_ceil(3),
+        // Runs of spaces and newlines merge into one token
+        Some(CharClass::Space) => 1,
+        Some(CharClass::Punct) => len.div_ceil(2),
+    }
+}
+
+/// Select the tokenizer for a model type
+///
+/// # Arguments
+///
+/// * `model_type` - Type of the model
+///
+/// # Returns
+///
+/// The tokenizer to estimate prompt sizes with
+pub fn tokenizer_for(model_type: &AiModelType) -> Box<dyn Tokenizer> {
+    match model_type {
+        AiModelType::Claude => Box::new(ClaudeEstimator),
+        _ => Box::new(BpeEstimator),
+    }
+}
+
+/// Context window of the models used for each model type
+///
+/// # Arguments
+///
+/// * `model_type` - Type of the model
+///
+/// # Returns
+///
+/// The context window in tokens (Ollama's default `num_ctx` for local models)
+pub fn default_context_window(model_type: &AiModelType) -> usize {
+    match model_type {
+        AiModelType::Claude => 200_000,
+        AiModelType::Gpt => 8_192,
+        AiModelType::Mistral => 128_000,
+        AiModelType::Local(_) => 2_048,
+        // Unknown; servers usually run models with at least this much
+        AiModelType::OpenAiCompatible(_) => 8_192,
+    }
+}
+
+/// Read the context length of a local model from Ollama's model metadata
+///
+/// # Arguments
+///
+/// * `client` - HTTP client
+/// * `generate_url` - URL of Ollama's `/api/generate` endpoint
+/// * `model_name` - Local model name
+///
+/// # Returns
+///
+/// The context length, or `None` if the metadata is unavailable
+pub async fn fetch_ollama_context_window(
+    client: &reqwest::Client,
+    generate_url: &str,
+    model_name: &str,
+) -> Option<usize> {
+    let show_url = format!("{}/api/show", generate_url.strip_suffix("/api/generate")?);
+
+    let response = client
+        .post(&show_url)
+        .json(&serde_json::json!({ "name": model_name }))
+        .send()
+        .await
+        .ok()?;
+
+    let metadata: serde_json::Value = response.json().await.ok()?;
+
+    // e.g. "llama.context_length"
+    let context_length = metadata["model_info"]
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())?;
+
+    debug!("Ollama model {} has a context length of {}", model_name, context_length);
+    Some(context_length as usize)
+}
+
+/// Split a text into chunks of at most `budget` tokens, on line boundaries
+///
+/// Chunks end at blank lines where possible, so items are not cut in half;
+/// a paragraph larger than the budget is cut between lines. A single line
+/// larger than the budget becomes a chunk of its own.
+///
+/// # Arguments
+///
+/// * `text` - Text to split
+/// * `budget` - Maximum tokens per chunk
+/// * `tokenizer` - Tokenizer to count with
+///
+/// # Returns
+///
+/// The chunks, in order
+pub fn split_to_budget(text: &str, budget: usize, tokenizer: &dyn Tokenizer) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    let mut last_blank = None;
+
+    for line in text.split_inclusive('\n') {
+        let line_tokens = tokenizer.count_tokens(line);
+
+        if current_tokens + line_tokens > budget && !current.is_empty() {
+            // Cut at the last blank line if there is one, carrying the rest over
+            let cut = last_blank.unwrap_or(current.len());
+            let rest = current.split_off(cut);
+            chunks.push(std::mem::replace(&mut current, rest));
+            current_tokens = tokenizer.count_tokens(&current);
+            last_blank = None;
+
+            // The carried over lines and this one may still not fit together
+            if current_tokens + line_tokens > budget && !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+                current_tokens = 0;
+            }
+        }
+
+        current.push_str(line);
+        current_tokens += line_tokens;
+
+        if line.trim().is_empty() {
+            last_blank = Some(current.len());
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_estimates_and_chunking() {
+        assert_eq!(BpeEstimator.count_tokens(""), 0);
+        assert_eq!(BpeEstimator.count_tokens("fn main() {}"), 6);
+        assert!(ClaudeEstimator.count_tokens("fn main() {}") > 6);
+
+        let code = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n\nfn c() {\n    3\n}\n";
+        let chunks = split_to_budget(code, 25, &BpeEstimator);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), code);
+        assert!(chunks.iter().all(|c| c.starts_with("fn ")));
+
+        // A paragraph longer than the budget after a short one is cut between its lines
+        let long = format!("fn a() {{}}\n\n{}", "    let value = compute(1, 2, 3);\n".repeat(12));
+        for budget in 20..60 {
+            let chunks = split_to_budget(&long, budget, &BpeEstimator);
+
+            assert_eq!(chunks.concat(), long);
+            assert!(chunks.iter().all(|c| BpeEstimator.count_tokens(c) <= budget), "chunk over {} tokens", budget);
+        }
+    }
+}
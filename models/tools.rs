@@ -0,0 +1,329 @@
+//! Tool calling module
+//!
+//! This module lets models request project context while generating fixes:
+//! - `read_file` returns (part of) a file of the project
+//! - `grep` searches the project's Rust files with a regular expression
+//! - `run_clippy` runs Clippy on the project
+//! - `get_definition` returns the source of a named item
+//!
+//! Tools only read the project; paths outside the project root are refused.
+
+use crate::analysis::collect_rust_files;
+use crate::execution::CargoCommand;
+use crate::index::chunk_source;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::debug;
+
+/// Maximum characters of a tool result sent back to the model
+const MAX_TOOL_OUTPUT_CHARS: usize = 20_000;
+
+/// Maximum matches returned by `grep`
+const MAX_GREP_MATCHES: usize = 100;
+
+/// A tool offered to the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Tool name
+    pub name: String,
+
+    /// What the tool does, for the model
+    pub description: String,
+
+    /// JSON schema of the arguments
+    pub parameters: Value,
+}
+
+/// A tool call requested by the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Id of the call, echoed in the result
+    pub id: String,
+
+    /// Tool name
+    pub name: String,
+
+    /// Arguments as a JSON object
+    pub arguments: Value,
+}
+
+/// A message of a tool-calling conversation
+#[derive(Debug, Clone)]
+pub enum ConversationMessage {
+    /// Message from the user
+    User(String),
+
+    /// Answer of the model, possibly requesting tool calls
+    Assistant {
+        /// Text of the answer
+        content: String,
+
+        /// Requested tool calls
+        tool_calls: Vec<ToolCall>,
+    },
+
+    /// Result of a tool call
+    ToolResult {
+        /// Id of the call
+        call_id: String,
+
+        /// Output of the tool
+        content: String,
+    },
+}
+
+/// Read-only tools operating on a project
+pub struct ProjectTools {
+    /// Root of the project
+    root: PathBuf,
+}
+
+impl ProjectTools {
+    /// Create the tools for a project
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Root of the project
+    ///
+    /// # Returns
+    ///
+    /// The tools
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Definitions of the tools
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        vec![
+            ToolDefinition {
+                name: "read_file".to_string(),
+                description: "Read a file of the project, optionally only a range of lines".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path relative to the project root" },
+                        "start_line": { "type": "integer", "description": "First line to read (1-based)" },
+                        "end_line": { "type": "integer", "description": "Last line to read (inclusive)" }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            ToolDefinition {
+                name: "grep".to_string(),
+                description: "Search the project's Rust files for a regular expression".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": { "type": "string", "description": "Regular expression" },
+                        "path": { "type": "string", "description": "Directory or file to search, relative to the project root" }
+                    },
+                    "required": ["pattern"]
+                }),
+            },
+            ToolDefinition {
+                name: "run_clippy".to_string(),
+                description: "Run cargo clippy on the project and return its diagnostics".to_string(),
+                parameters: json!({ "type": "object", "properties": {} }),
+            },
+            ToolDefinition {
+                name: "get_definition".to_string(),
+                description: "Get the source of a function, type, trait or impl block by name".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "Item name, e.g. `parse_config` or `Config`" }
+                    },
+                    "required": ["name"]
+                }),
+            },
+        ]
+    }
+
+    /// Execute a tool call
+    ///
+    /// Failures are reported in the output, so the model can correct its call.
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - Tool call requested by the model
+    ///
+    /// # Returns
+    ///
+    /// The output of the tool
+    pub fn execute(&self, call: &ToolCall) -> String {
+        debug!("Executing tool {} with {}", call.name, call.arguments);
+
+        let argument = |name: &str| call.arguments.get(name).and_then(Value::as_str);
+        let line = |name: &str| call.arguments.get(name).and_then(Value::as_u64).map(|l| l as usize);
+
+        let output = match call.name.as_str() {
+            "read_file" => match argument("path") {
+                Some(path) => self.read_file(path, line("start_line"), line("end_line")),
+                None => Err("missing argument `path`".to_string()),
+            },
+            "grep" => match argument("pattern") {
+                Some(pattern) => self.grep(pattern, argument("path")),
+                None => Err("missing argument `pattern`".to_string()),
+            },
+            "run_clippy" => self.run_clippy(),
+            "get_definition" => match argument("name") {
+                Some(name) => self.get_definition(name),
+                None => Err("missing argument `name`".to_string()),
+            },
+            other => Err(format!("unknown tool `{}`", other)),
+        };
+
+        let output = output.unwrap_or_else(|e| format!("Error: {}", e));
+
+        match output.char_indices().nth(MAX_TOOL_OUTPUT_CHARS) {
+            Some((end, _)) => format!("{}\n... (output truncated)", &output[..end]),
+            None => output,
+        }
+    }
+
+    /// Resolve a path inside the project
+    fn resolve(&self, path: &str) -> std::result::Result<PathBuf, String> {
+        let root = self.root.canonicalize().map_err(|e| e.to_string())?;
+        let resolved = root.join(path).canonicalize()
+            .map_err(|e| format!("{}: {}", path, e))?;
+
+        if !resolved.starts_with(&root) {
+            return Err(format!("{} is outside the project", path));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Path relative to the project root, for display
+    fn display_path(&self, path: &Path) -> String {
+        let root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+        path.strip_prefix(&root).unwrap_or(path).display().to_string()
+    }
+
+    fn read_file(&self, path: &str, start_line: Option<usize>, end_line: Option<usize>) -> std::result::Result<String, String> {
+        let content = fs::read_to_string(self.resolve(path)?).map_err(|e| format!("{}: {}", path, e))?;
+
+        if start_line.is_none() && end_line.is_none() {
+            return Ok(content);
+        }
+
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let start = start_line.unwrap_or(1).max(1);
+        let end = end_line.unwrap_or(lines.len()).min(lines.len());
+
+        if start > end {
+            return Err(format!("{} has {} lines", path, lines.len()));
+        }
+
+        Ok(lines[start - 1..end].concat())
+    }
+
+    fn grep(&self, pattern: &str, path: Option<&str>) -> std::result::Result<String, String> {
+        let regex = regex::Regex::new(pattern).map_err(|e| e.to_string())?;
+        let search_root = self.resolve(path.unwrap_or("."))?;
+        let files = collect_rust_files(&search_root).map_err(|e| e.to_string())?;
+
+        let mut matches = Vec::new();
+        'files: for file in files {
+            let Ok(content) = fs::read_to_string(&file) else {
+                continue;
+            };
+
+            for (number, line) in content.lines().enumerate() {
+                if regex.is_match(line) {
+                    matches.push(format!("{}:{}: {}", self.display_path(&file), number + 1, line.trim()));
+
+                    if matches.len() == MAX_GREP_MATCHES {
+                        matches.push(format!("... (stopped after {} matches)", MAX_GREP_MATCHES));
+                        break 'files;
+                    }
+                }
+            }
+        }
+
+        if matches.is_empty() {
+            return Ok("No matches".to_string());
+        }
+
+        Ok(matches.join("\n"))
+    }
+
+    fn run_clippy(&self) -> std::result::Result<String, String> {
+        let output = CargoCommand::new(&self.root)
+            .args(["clippy", "--quiet", "--message-format=short"])
+            .output()
+            .map_err(|e| format!("failed to run cargo clippy: {}", e))?;
+
+        let diagnostics = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+        if diagnostics.is_empty() {
+            return Ok("No warnings".to_string());
+        }
+
+        Ok(diagnostics)
+    }
+
+    fn get_definition(&self, name: &str) -> std::result::Result<String, String> {
+        let files = collect_rust_files(&self.root).map_err(|e| e.to_string())?;
+        let mut definitions = Vec::new();
+
+        for file in files {
+            let Ok(source) = fs::read_to_string(&file) else {
+                continue;
+            };
+            let relative = PathBuf::from(self.display_path(&file));
+
+            for chunk in chunk_source(&source, &relative) {
+                // e.g. `fn Config::load`, `impl Display for Config`
+                let item_name = chunk.name.rsplit([' ', ':']).next().unwrap_or_default();
+
+                if item_name == name {
+                    definitions.push(format!(
+                        "// {}:{}-{}\n{}",
+                        chunk.file_path.display(),
+                        chunk.start_line,
+                        chunk.end_line,
+                        chunk.content
+                    ));
+                }
+            }
+        }
+
+        if definitions.is_empty() {
+            return Err(format!("no definition of `{}` found", name));
+        }
+
+        Ok(definitions.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_project_tools() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub struct Config {\n    pub name: String,\n}\n\npub fn load() -> Config {\n    todo!()\n}\n",
+        ).unwrap();
+
+        let tools = ProjectTools::new(dir.path().to_path_buf());
+        let call = |name: &str, arguments: Value| tools.execute(&ToolCall {
+            id: "1".to_string(),
+            name: name.to_string(),
+            arguments,
+        });
+
+        assert_eq!(call("read_file", json!({ "path": "src/lib.rs", "start_line": 5, "end_line": 5 })), "pub fn load() -> Config {\n");
+        assert_eq!(call("grep", json!({ "pattern": "fn load" })), "src/lib.rs:5: pub fn load() -> Config {");
+        assert!(call("get_definition", json!({ "name": "Config" })).contains("pub name: String"));
+        assert!(call("read_file", json!({ "path": "../outside.rs" })).starts_with("Error:"));
+        assert!(call("delete_file", json!({})).starts_with("Error: unknown tool"));
+    }
+}
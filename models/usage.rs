@@ -0,0 +1,199 @@
+//! Usage tracking module
+//!
+//! This module accounts for the AI calls made during a run:
+//! - Token usage per model, estimated when the API does not report it
+//! - Cost from configurable per-model pricing, with defaults for the built-in models
+//! - A summary for the console and a JSON report
+
+use crate::ModelPricing;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Usage of all clients in this process
+static USAGE: Mutex<Vec<ModelUsage>> = Mutex::new(Vec::new());
+
+/// Usage of a single model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Model name
+    pub model: String,
+
+    /// Requests sent to the API
+    pub requests: u32,
+
+    /// Requests answered from the response cache
+    pub cached_requests: u32,
+
+    /// Requests whose token counts were estimated locally
+    pub estimated_requests: u32,
+
+    /// Prompt tokens
+    pub prompt_tokens: u64,
+
+    /// Completion tokens
+    pub completion_tokens: u64,
+
+    /// Cost in US dollars (`None` if the model has no known pricing)
+    pub cost_usd: Option<f64>,
+}
+
+/// Usage of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Usage per model
+    pub models: Vec<ModelUsage>,
+
+    /// Total cost of the priced models in US dollars
+    pub total_cost_usd: f64,
+}
+
+/// Default pricing of the built-in models (US dollars per million tokens)
+///
+/// # Arguments
+///
+/// * `model` - Model name
+///
+/// # Returns
+///
+/// The pricing, or `None` for unknown models
+pub fn default_pricing(model: &str) -> Option<ModelPricing> {
+    let (input, output) = match model {
+        "claude-3-opus-20240229" => (15.0, 75.0),
+        "gpt-4" => (30.0, 60.0),
+        "mistral-large-latest" => (2.0, 6.0),
+        _ => return None,
+    };
+
+    Some(ModelPricing {
+        input_per_million: input,
+        output_per_million: output,
+    })
+}
+
+/// Record a request sent to a model
+///
+/// # Arguments
+///
+/// * `model` - Model name
+/// * `prompt_tokens` - Prompt tokens
+/// * `completion_tokens` - Completion tokens
+/// * `estimated` - Whether the token counts were estimated locally
+/// * `pricing` - Pricing of the model (if known)
+pub fn record_request(
+    model: &str,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    estimated: bool,
+    pricing: Option<ModelPricing>,
+) {
+    with_model_usage(model, |usage| {
+        usage.requests += 1;
+        usage.prompt_tokens += prompt_tokens;
+        usage.completion_tokens += completion_tokens;
+
+        if estimated {
+            usage.estimated_requests += 1;
+        }
+
+        if let Some(pricing) = pricing {
+            let cost = (prompt_tokens as f64 * pricing.input_per_million
+                + completion_tokens as f64 * pricing.output_per_million)
+                / 1_000_000.0;
+            usage.cost_usd = Some(usage.cost_usd.unwrap_or_default() + cost);
+        }
+    });
+}
+
+/// Record a request answered from the response cache
+pub fn record_cache_hit(model: &str) {
+    with_model_usage(model, |usage| usage.cached_requests += 1);
+}
+
+/// Usage recorded so far in this process
+pub fn usage_report() -> UsageReport {
+    let models = USAGE.lock().map(|usage| usage.clone()).unwrap_or_default();
+    let total_cost_usd = models.iter().filter_map(|m| m.cost_usd).fold(0.0, |total, cost| total + cost);
+
+    UsageReport {
+        models,
+        total_cost_usd,
+    }
+}
+
+/// Format a usage report for the console
+///
+/// # Arguments
+///
+/// * `report` - Usage report
+///
+/// # Returns
+///
+/// One line per model and a total
+pub fn format_usage_summary(report: &UsageReport) -> String {
+    let mut summary = String::from("AI usage:\n");
+
+    for usage in &report.models {
+        summary.push_str(&format!(
+            "  {}: {} requests ({} cached), {} prompt + {} completion tokens{}, {}\n",
+            usage.model,
+            usage.requests,
+            usage.cached_requests,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            if usage.estimated_requests > 0 { " (partly estimated)" } else { "" },
+            usage.cost_usd.map_or("cost unknown".to_string(), |cost| format!("${:.4}", cost))
+        ));
+    }
+
+    summary.push_str(&format!("  Total: ${:.4}", report.total_cost_usd));
+    summary
+}
+
+/// Update the usage entry of a model, creating it if needed
+fn with_model_usage(model: &str, update: impl FnOnce(&mut ModelUsage)) {
+    let mut usage = match USAGE.lock() {
+        Ok(usage) => usage,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    let index = match usage.iter().position(|u| u.model == model) {
+        Some(index) => index,
+        None => {
+            usage.push(ModelUsage {
+                model: model.to_string(),
+                ..Default::default()
+            });
+            usage.len() - 1
+        }
+    };
+
+    update(&mut usage[index]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_accounting() {
+        let model = "test-usage-model";
+        let pricing = ModelPricing {
+            input_per_million: 10.0,
+            output_per_million: 20.0,
+        };
+
+        record_request(model, 1000, 500, false, Some(pricing));
+        record_request(model, 2000, 0, true, Some(pricing));
+        record_cache_hit(model);
+
+        let report = usage_report();
+        let usage = report.models.iter().find(|m| m.model == model).unwrap();
+
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.cached_requests, 1);
+        assert_eq!(usage.estimated_requests, 1);
+        assert_eq!(usage.prompt_tokens, 3000);
+        assert!((usage.cost_usd.unwrap() - 0.04).abs() < 1e-9);
+        assert!(format_usage_summary(&report).contains("test-usage-model: 2 requests (1 cached)"));
+    }
+}
@@ -1,7 +1,12 @@
+use crate::analyzer_plugins::{Analyzer, PluginAnalyzer};
+use crate::events::{emit, Event};
+use crate::directory_config::DirectoryConfigs;
+use crate::execution::CargoCommand;
+use crate::journal::content_hash;
 use crate::{AnalysisOptions, Result, RustAiToolError, Severity, CustomRule};
 use ra_ap_syntax::{SourceFile, SyntaxNode, TextRange, Parse};
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use log::{debug, info, warn, error};
@@ -25,6 +30,22 @@ pub struct CodeIssue {
     pub severity: Severity,
     pub message: String,
     pub suggested_fix: Option<CodeFix>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// Compiler or Clippy lint reporting the issue, like `clippy::unwrap_used`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lint: Option<String>,
+    /// Code owners of the file of the issue, from the CODEOWNERS file of the repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+impl CodeIssue {
+    /// Short id of the issue, stable across runs while the issue stays at the same place
+    pub fn id(&self) -> String {
+        let key = format!("{}:{}:{}:{}", self.file_path.display(), self.line_start, self.column_start, self.message);
+        content_hash(&key)[..8].to_string()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -36,9 +57,20 @@ pub enum IssueCategory {
     Security,
     TauriCompatibility,
     CodeQuality,
+    Policy,
     CustomRule(String),
 }
 
+impl IssueCategory {
+    /// Name of the category, or of the custom rule, in reports
+    pub fn name(&self) -> String {
+        match self {
+            IssueCategory::CustomRule(name) => name.clone(),
+            category => format!("{:?}", category),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeFix {
     pub original_code: String,
@@ -82,58 +114,194 @@ struct ClippyText {
     text: String,
 }
 
+/// Sort issues by triage priority, most urgent first
+///
+/// Issues are sorted within each file and files by their most urgent issue;
+/// issues without a priority keep their order after the prioritized ones.
+pub fn sort_by_priority(results: &mut [AnalysisResult]) {
+    for result in results.iter_mut() {
+        result.issues.sort_by_key(|issue| std::cmp::Reverse(issue.priority));
+    }
+    
+    results.sort_by_key(|result| std::cmp::Reverse(result.issues.first().and_then(|issue| issue.priority)));
+}
+
 pub fn analyze_project(project_path: &Path, options: &AnalysisOptions) -> Result<Vec<AnalysisResult>> {
     info!("Analyzing Rust project at {}", project_path.display());
     
     let rust_files = collect_rust_files(project_path)?;
     debug!("Found {} Rust files to analyze", rust_files.len());
     
-    let mut results = Vec::new();
-    for file_path in rust_files {
-        match analyze_file(&file_path, options) {
-            Ok(result) => results.push(result),
-            Err(e) => {
-                error!("Failed to analyze file {}: {}", file_path.display(), e);
-                results.push(AnalysisResult {
-                    file_path,
-                    issues: Vec::new(),
-                    errors: vec![e.to_string()],
-                    success: false,
-                });
+    let mut results = analyze_files(project_path, &rust_files, options)?;
+    let project_results = results.len();
+    
+    if let Some(policy) = &options.policy {
+        match crate::supply_chain::check_policy(project_path, policy) {
+            Ok(mut result) => {
+                crate::lints::retain_issues(&options.lints, &options.filter, std::slice::from_mut(&mut result));
+                if !result.issues.is_empty() {
+                    results.push(result);
+                }
             }
+            Err(e) => warn!("Failed to check the dependency policy: {}", e),
         }
     }
     
-    if options.run_clippy {
-        match run_clippy_project(project_path) {
-            Ok(clippy_issues) => {
-                let issues_by_file = clippy_issues.iter()
-                    .fold(HashMap::new(), |mut map, issue| {
-                        map.entry(issue.file_path.clone())
-                            .or_insert_with(Vec::new)
-                            .push(issue.clone());
-                        map
-                    });
-                
-                for result in &mut results {
-                    if let Some(file_issues) = issues_by_file.get(&result.file_path) {
-                        result.issues.extend(file_issues.clone());
-                    }
-                }
-            },
-            Err(e) => {
-                warn!("Failed to run Clippy on project: {}", e);
-                for result in &mut results {
-                    result.errors.push(format!("Clippy analysis failed: {}", e));
+    match crate::tauri_config::check_config(project_path) {
+        Ok(mut config_results) => {
+            crate::lints::retain_issues(&options.lints, &options.filter, &mut config_results);
+            results.extend(config_results.into_iter().filter(|result| !result.issues.is_empty() || !result.errors.is_empty()));
+        }
+        Err(e) => warn!("Failed to check the Tauri configuration: {}", e),
+    }
+    
+    if let Err(e) = crate::codeowners::assign_owners(project_path, &mut results[project_results..]) {
+        warn!("Failed to read the code owners: {}", e);
+    }
+    
+    Ok(results)
+}
+
+/// Analyze some files of a project, such as the ones changed in git
+///
+/// Clippy still checks the whole project, but only issues in the given files
+/// are reported; nothing runs when there are no files. Files and issues left
+/// out by the filter of the options are dropped. Directory configurations of
+/// the project override the options for the files below them.
+#[tracing::instrument(skip_all, fields(project = %project_path.display(), files = files.len(), issues), err)]
+pub fn analyze_files(project_path: &Path, files: &[PathBuf], options: &AnalysisOptions) -> Result<Vec<AnalysisResult>> {
+    let files = options.filter.filter_files(project_path, files)?;
+    
+    let directories = DirectoryConfigs::new(project_path);
+    let mut file_options = Vec::with_capacity(files.len());
+    for file_path in files {
+        let options = directories.analysis_options(&file_path, options)?;
+        if matches!(options, Cow::Owned(_)) && options.filter.filter_files(project_path, std::slice::from_ref(&file_path))?.is_empty() {
+            continue;
+        }
+        file_options.push((file_path, options));
+    }
+    if file_options.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    emit(&Event::AnalysisStarted { project_path, files: file_options.len() });
+    
+    let mut results: Vec<AnalysisResult> = file_options.iter()
+        .map(|(file_path, options)| analyze_file_or_error(project_path, file_path, options))
+        .collect();
+    
+    // Clippy checks the whole project, so its issues are dropped for files whose directory turns it off
+    let clippy_files: Vec<&PathBuf> = file_options.iter()
+        .filter(|(_, options)| options.run_clippy)
+        .map(|(file_path, _)| file_path)
+        .collect();
+    if !clippy_files.is_empty() {
+        let clippy_issues = run_clippy_project(project_path)
+            .map(|issues| issues.into_iter().filter(|issue| clippy_files.contains(&&issue.file_path)).collect());
+        attach_clippy_issues(&mut results, &clippy_issues);
+    }
+    
+    // Tauri commands span the backend and the frontend, so they are checked once for the project
+    let mut frontend_results = match crate::tauri_commands::check_commands(project_path) {
+        Ok(tauri_issues) => attach_tauri_issues(&mut results, tauri_issues),
+        Err(e) => {
+            warn!("Failed to check Tauri commands: {}", e);
+            Vec::new()
+        }
+    };
+    
+    for (result, (_, options)) in results.iter_mut().zip(&file_options) {
+        crate::lints::retain_issues(&options.lints, &options.filter, std::slice::from_mut(result));
+    }
+    
+    crate::lints::retain_issues(&options.lints, &options.filter, &mut frontend_results);
+    results.extend(frontend_results.into_iter().filter(|result| !result.issues.is_empty()));
+    
+    if let Err(e) = crate::codeowners::assign_owners(project_path, &mut results) {
+        warn!("Failed to read the code owners: {}", e);
+    }
+    
+    let issues: Vec<&CodeIssue> = results.iter().flat_map(|result| &result.issues).collect();
+    for issue in &issues {
+        emit(&Event::IssueFound { issue });
+    }
+    emit(&Event::AnalysisFinished { files: results.len(), issues: issues.len() });
+    tracing::Span::current().record("issues", issues.len());
+    
+    Ok(results)
+}
+
+/// Analyze one file without Clippy, reporting a failure in the result
+pub(crate) fn analyze_file_or_error(project_path: &Path, file_path: &Path, options: &AnalysisOptions) -> AnalysisResult {
+    analyze_file(project_path, file_path, options).unwrap_or_else(|e| {
+        error!("Failed to analyze file {}: {}", file_path.display(), e);
+        AnalysisResult {
+            file_path: file_path.to_path_buf(),
+            issues: Vec::new(),
+            errors: vec![e.to_string()],
+            success: false,
+        }
+    })
+}
+
+/// Add the issues of a Clippy run to the results of the files they are in
+///
+/// A failed run is reported as an error of every result.
+pub(crate) fn attach_clippy_issues(results: &mut [AnalysisResult], clippy_issues: &Result<Vec<CodeIssue>>) {
+    match clippy_issues {
+        Ok(clippy_issues) => {
+            let issues_by_file = clippy_issues.iter()
+                .fold(HashMap::new(), |mut map, issue| {
+                    map.entry(issue.file_path.clone())
+                        .or_insert_with(Vec::new)
+                        .push(issue.clone());
+                    map
+                });
+            
+            for result in results.iter_mut() {
+                if let Some(file_issues) = issues_by_file.get(&result.file_path) {
+                    result.issues.extend(file_issues.clone());
                 }
             }
+        },
+        Err(e) => {
+            warn!("Failed to run Clippy on project: {}", e);
+            for result in results.iter_mut() {
+                result.errors.push(format!("Clippy analysis failed: {}", e));
+            }
+        }
+    }
+}
+
+/// Add the issues of the Tauri command check to the results of the Rust files they are in
+///
+/// Issues in Rust files that were not analyzed are dropped; issues in frontend
+/// files are returned as new results, one per file.
+fn attach_tauri_issues(results: &mut [AnalysisResult], tauri_issues: Vec<CodeIssue>) -> Vec<AnalysisResult> {
+    let mut frontend_results: Vec<AnalysisResult> = Vec::new();
+    
+    for issue in tauri_issues {
+        if let Some(result) = results.iter_mut().find(|result| result.file_path == issue.file_path) {
+            result.issues.push(issue);
+        } else if issue.file_path.extension().is_some_and(|extension| extension != "rs") {
+            match frontend_results.iter_mut().find(|result| result.file_path == issue.file_path) {
+                Some(result) => result.issues.push(issue),
+                None => frontend_results.push(AnalysisResult {
+                    file_path: issue.file_path.clone(),
+                    issues: vec![issue],
+                    errors: Vec::new(),
+                    success: true,
+                }),
+            }
         }
     }
     
-    Ok(results)
+    frontend_results
 }
 
-fn analyze_file(file_path: &Path, options: &AnalysisOptions) -> Result<AnalysisResult> {
+#[tracing::instrument(skip_all, fields(file = %file_path.display()), err)]
+fn analyze_file(project_path: &Path, file_path: &Path, options: &AnalysisOptions) -> Result<AnalysisResult> {
     debug!("Analyzing file: {}", file_path.display());
     
     let file_content = std::fs::read_to_string(file_path)
@@ -165,12 +333,12 @@ fn analyze_file(file_path: &Path, options: &AnalysisOptions) -> Result<AnalysisR
         }
     }
     
-    if is_tauri_file(file_path) {
-        match analyze_tauri_compatibility(&file_content, file_path) {
-            Ok(tauri_issues) => result.issues.extend(tauri_issues),
-            Err(e) => {
-                result.errors.push(format!("Tauri compatibility analysis failed: {}", e));
-            }
+    for plugin in &options.analyzers {
+        let issues = PluginAnalyzer::load(project_path, plugin)
+            .and_then(|analyzer| analyzer.analyze(file_path, &file_content));
+        match issues {
+            Ok(plugin_issues) => result.issues.extend(plugin_issues),
+            Err(e) => result.errors.push(e.to_string()),
         }
     }
     
@@ -196,6 +364,9 @@ fn analyze_syntax(content: &str, file_path: &Path) -> Result<Vec<CodeIssue>> {
             severity: Severity::Error,
             message: "Syntax error".to_string(),
             suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
         });
     }
     
@@ -214,7 +385,7 @@ fn find_syntax_errors(node: &SyntaxNode) -> Vec<TextRange> {
     errors
 }
 
-fn offset_to_line_column(text: &str, offset: usize) -> (usize, usize) {
+pub(crate) fn offset_to_line_column(text: &str, offset: usize) -> (usize, usize) {
     let mut line = 1;
     let mut col = 1;
     
@@ -234,12 +405,12 @@ fn offset_to_line_column(text: &str, offset: usize) -> (usize, usize) {
     (line, col)
 }
 
-fn run_clippy_project(project_path: &Path) -> Result<Vec<CodeIssue>> {
+#[tracing::instrument(skip_all, fields(project = %project_path.display()), err)]
+pub(crate) fn run_clippy_project(project_path: &Path) -> Result<Vec<CodeIssue>> {
     debug!("Running Clippy on project at {}", project_path.display());
     
-    let output = Command::new("cargo")
-        .args(&["clippy", "--message-format=json", "--", "-W", "clippy::all"])
-        .current_dir(project_path)
+    let output = CargoCommand::new(project_path)
+        .args(["clippy", "--message-format=json", "--", "-W", "clippy::all"])
         .output()
         .map_err(|e| RustAiToolError::Analysis(format!("Failed to execute Clippy: {}", e)))?;
     
@@ -250,13 +421,20 @@ fn run_clippy_project(project_path: &Path) -> Result<Vec<CodeIssue>> {
     let stdout = String::from_utf8_lossy(&output.stdout);
     let mut issues = Vec::new();
     
+    // Clippy reports paths relative to the workspace root, which is above
+    // the project when only a subdirectory of a repository is analyzed
+    let workspace_prefix = workspace_relative_path(project_path).unwrap_or_default();
+    
     for line in stdout.lines() {
         if let Ok(message) = serde_json::from_str::<ClippyMessage>(line) {
             if message.reason == "compiler-message" {
                 if let Some(diagnostic) = message.message {
                     if diagnostic.level == "warning" || diagnostic.level == "error" {
                         for span in diagnostic.spans.iter().filter(|s| s.is_primary) {
-                            let file_path = PathBuf::from(&span.file_name);
+                            let Ok(relative_path) = Path::new(&span.file_name).strip_prefix(&workspace_prefix) else {
+                                continue;
+                            };
+                            let file_path = project_path.join(relative_path);
                             
                             if !file_path.exists() {
                                 continue;
@@ -278,6 +456,9 @@ fn run_clippy_project(project_path: &Path) -> Result<Vec<CodeIssue>> {
                                 severity,
                                 message: diagnostic.message.clone(),
                                 suggested_fix: None,
+                                priority: None,
+                                lint: diagnostic.code.as_ref().map(|code| code.code.clone()),
+                                owners: Vec::new(),
                             });
                         }
                     }
@@ -289,7 +470,25 @@ fn run_clippy_project(project_path: &Path) -> Result<Vec<CodeIssue>> {
     Ok(issues)
 }
 
-fn collect_rust_files(project_path: &Path) -> Result<Vec<PathBuf>> {
+/// Path of a project relative to the root of its Cargo workspace (empty at the root)
+fn workspace_relative_path(project_path: &Path) -> Option<PathBuf> {
+    let output = CargoCommand::new(project_path)
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .output()
+        .ok()?;
+    
+    if !output.status.success() {
+        return None;
+    }
+    
+    let manifest_path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let workspace_root = manifest_path.parent()?.canonicalize().ok()?;
+    let project_path = project_path.canonicalize().ok()?;
+    
+    project_path.strip_prefix(workspace_root).ok().map(Path::to_path_buf)
+}
+
+pub(crate) fn collect_rust_files(project_path: &Path) -> Result<Vec<PathBuf>> {
     let mut rust_files = Vec::new();
     
     let walker = walkdir::WalkDir::new(project_path)
@@ -308,7 +507,8 @@ fn collect_rust_files(project_path: &Path) -> Result<Vec<PathBuf>> {
 }
 
 fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-    entry.file_name()
+    // The root itself may be `.`
+    entry.depth() > 0 && entry.file_name()
         .to_str()
         .map(|s| s.starts_with('.'))
         .unwrap_or(false)
@@ -321,71 +521,7 @@ fn is_target_dir(entry: &walkdir::DirEntry) -> bool {
         .unwrap_or(false)
 }
 
-fn is_tauri_file(file_path: &Path) -> bool {
-    let path_str = file_path.to_string_lossy();
-    path_str.contains("src-tauri") || path_str.contains("tauri.conf.json")
-}
-
-fn analyze_tauri_compatibility(content: &str, file_path: &Path) -> Result<Vec<CodeIssue>> {
-    let mut issues = Vec::new();
-    
-    let commands = extract_tauri_commands(content);
-    let handlers = extract_invoke_handlers(content);
-    
-    for cmd in &commands {
-        let is_registered = handlers.iter().any(|h| h.contains(cmd));
-        
-        if !is_registered {
-            issues.push(CodeIssue {
-                file_path: file_path.to_path_buf(),
-                line_start: 0,
-                column_start: 0,
-                line_end: 0,
-                column_end: 0,
-                category: IssueCategory::TauriCompatibility,
-                severity: Severity::Error,
-                message: format!("Tauri command '{}' is not registered in any invoke_handler", cmd),
-                suggested_fix: None,
-            });
-        }
-    }
-    
-    Ok(issues)
-}
-
-fn extract_tauri_commands(code: &str) -> Vec<String> {
-    let command_pattern = r"#\[tauri::command\]\s*(?:pub\s+)?fn\s+([a-zA-Z0-9_]+)";
-    
-    let mut commands = Vec::new();
-    
-    if let Ok(re) = ::regex::Regex::new(command_pattern) {
-        for cap in re.captures_iter(code) {
-            if let Some(m) = cap.get(1) {
-                commands.push(m.as_str().to_string());
-            }
-        }
-    }
-    
-    commands
-}
-
-fn extract_invoke_handlers(code: &str) -> Vec<String> {
-    let handler_pattern = r"\.invoke_handler\(([^)]+)\)";
-    
-    let mut handlers = Vec::new();
-    
-    if let Ok(re) = ::regex::Regex::new(handler_pattern) {
-        for cap in re.captures_iter(code) {
-            if let Some(m) = cap.get(1) {
-                handlers.push(m.as_str().to_string());
-            }
-        }
-    }
-    
-    handlers
-}
-
-fn apply_custom_rule(
+pub(crate) fn apply_custom_rule(
     rule: &CustomRule,
     content: &str,
     file_path: &Path,
@@ -408,6 +544,9 @@ fn apply_custom_rule(
                     severity: rule.severity.clone(),
                     message: rule.message.clone(),
                     suggested_fix: None,
+                    priority: None,
+                    lint: None,
+                    owners: Vec::new(),
                 });
             }
         }
@@ -0,0 +1,362 @@
+//! Analyzer plugins module
+//!
+//! This module runs third-party analyzers next to the built-in ones, so
+//! organizations can ship proprietary checks without forking the analysis:
+//! - `Analyzer` is the interface of an analyzer: it gets the content of a file
+//!   and returns the issues it finds
+//! - Analyzers declared in `[[analysis_options.analyzers]]` are dynamic
+//!   libraries (`.so`, `.dylib` or `.dll`) loaded on first use, with a path
+//!   relative to the project; only the configuration of the run declares them,
+//!   as directory configurations cannot
+//! - Libraries inside checkouts of remote repositories (webhook, pull request,
+//!   organization and batch analyses) are not loaded, as whoever pushed to the
+//!   repository wrote them
+//! - The libraries have a C interface exchanging JSON, so they can be built
+//!   with any Rust version (or another language); see `ANALYZER_ABI_VERSION`
+//! - Issues of a plugin are custom-rule issues whose lint is
+//!   `<analyzer>::<rule>`, so `[lints]` sets their level like Clippy lints
+//!
+//! A library exports three functions:
+//! - `rust_ai_tool_analyzer_abi_version() -> u32`, returning `ANALYZER_ABI_VERSION`
+//! - `rust_ai_tool_analyze(request: *const c_char) -> *mut c_char`, taking a
+//!   `PluginRequest` and returning a `PluginResponse`, as NUL-terminated JSON
+//! - `rust_ai_tool_free_string(response: *mut c_char)`, freeing the responses
+
+use crate::analysis::{CodeFix, CodeIssue, IssueCategory};
+use crate::{AnalysisOptions, AnalyzerPlugin, Result, RustAiToolError, Severity};
+use libloading::{Library, Symbol};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use log::{debug, warn};
+
+/// Version of the interface of analyzer libraries
+pub const ANALYZER_ABI_VERSION: u32 = 1;
+
+/// Libraries loaded by this process, by path; they are never unloaded
+static LIBRARIES: OnceLock<Mutex<HashMap<PathBuf, Arc<Library>>>> = OnceLock::new();
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type AnalyzeFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeStringFn = unsafe extern "C" fn(*mut c_char);
+
+/// Analyzer of Rust files
+pub trait Analyzer: Send + Sync {
+    /// Name of the analyzer, prefixing the lints of its issues
+    fn name(&self) -> &str;
+
+    /// Analyze a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Path to the file
+    /// * `content` - Content of the file
+    ///
+    /// # Returns
+    ///
+    /// The issues found in the file
+    fn analyze(&self, file_path: &Path, content: &str) -> Result<Vec<CodeIssue>>;
+}
+
+/// Request passed to `rust_ai_tool_analyze`
+#[derive(Debug, Serialize)]
+pub struct PluginRequest<'a> {
+    /// Version of the interface
+    pub abi_version: u32,
+
+    /// Name of the analyzer in the configuration
+    pub analyzer: &'a str,
+
+    /// Path to the file
+    pub file_path: &'a Path,
+
+    /// Content of the file
+    pub content: &'a str,
+
+    /// `options` of the analyzer in the configuration
+    pub options: &'a serde_json::Value,
+}
+
+/// Response returned by `rust_ai_tool_analyze`
+#[derive(Debug, Default, Deserialize)]
+pub struct PluginResponse {
+    /// Issues found in the file
+    #[serde(default)]
+    pub issues: Vec<PluginIssue>,
+
+    /// Why the file could not be analyzed, if it could not
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Issue found by an analyzer library
+#[derive(Debug, Deserialize)]
+pub struct PluginIssue {
+    /// Rule of the analyzer reporting the issue
+    pub rule: String,
+
+    /// Line of the start of the issue (1-based)
+    pub line_start: usize,
+
+    /// Column of the start of the issue (1-based)
+    #[serde(default = "default_position")]
+    pub column_start: usize,
+
+    /// Line of the end of the issue (the start line if not set)
+    #[serde(default)]
+    pub line_end: Option<usize>,
+
+    /// Column of the end of the issue (the start column if not set)
+    #[serde(default)]
+    pub column_end: Option<usize>,
+
+    /// `error`, `warning`, `info` or `style`
+    pub severity: String,
+
+    /// Description of the issue
+    pub message: String,
+
+    /// Fix of the issue, if the analyzer knows one
+    #[serde(default)]
+    pub suggested_fix: Option<CodeFix>,
+}
+
+fn default_position() -> usize {
+    1
+}
+
+/// Analyzer implemented by a dynamic library
+pub struct PluginAnalyzer {
+    /// Name of the analyzer
+    name: String,
+
+    /// Loaded library
+    library: Arc<Library>,
+
+    /// Options passed with each request
+    options: serde_json::Value,
+}
+
+impl PluginAnalyzer {
+    /// Load the analyzer declared in a configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Project directory, relative library paths start from
+    /// * `plugin` - Declaration of the analyzer
+    ///
+    /// # Returns
+    ///
+    /// The analyzer, or an error if the library cannot be loaded or has another
+    /// interface version
+    pub fn load(project_path: &Path, plugin: &AnalyzerPlugin) -> Result<Self> {
+        let path = project_path.join(&plugin.path);
+        let mut libraries = LIBRARIES.get_or_init(Default::default).lock().unwrap();
+
+        let library = match libraries.get(&path) {
+            Some(library) => library.clone(),
+            None => {
+                debug!("Loading analyzer {} from {}", plugin.name, path.display());
+                // Loading runs the initializers of the library, which the run's configuration declared
+                let library = unsafe { Library::new(&path) }
+                    .map_err(|e| plugin_error(&plugin.name, format!("failed to load {}: {}", path.display(), e)))?;
+
+                let abi_version = unsafe {
+                    let abi_version: Symbol<AbiVersionFn> = library.get(b"rust_ai_tool_analyzer_abi_version\0")
+                        .map_err(|e| plugin_error(&plugin.name, e))?;
+                    abi_version()
+                };
+                if abi_version != ANALYZER_ABI_VERSION {
+                    return Err(plugin_error(&plugin.name, format!(
+                        "{} implements interface version {}, but version {} is required",
+                        path.display(), abi_version, ANALYZER_ABI_VERSION
+                    )));
+                }
+
+                let library = Arc::new(library);
+                libraries.insert(path, library.clone());
+                library
+            }
+        };
+
+        Ok(PluginAnalyzer {
+            name: plugin.name.clone(),
+            library,
+            options: serde_json::Value::Object(plugin.options.clone()),
+        })
+    }
+
+    /// Send a request to the library
+    fn call(&self, request: &PluginRequest) -> Result<PluginResponse> {
+        let request = CString::new(serde_json::to_string(request)?)
+            .map_err(|e| plugin_error(&self.name, e))?;
+
+        let response = unsafe {
+            let analyze: Symbol<AnalyzeFn> = self.library.get(b"rust_ai_tool_analyze\0")
+                .map_err(|e| plugin_error(&self.name, e))?;
+            let free_string: Symbol<FreeStringFn> = self.library.get(b"rust_ai_tool_free_string\0")
+                .map_err(|e| plugin_error(&self.name, e))?;
+
+            let response = analyze(request.as_ptr());
+            if response.is_null() {
+                return Err(plugin_error(&self.name, "no response"));
+            }
+            let text = CStr::from_ptr(response).to_string_lossy().into_owned();
+            free_string(response);
+            text
+        };
+
+        serde_json::from_str(&response)
+            .map_err(|e| plugin_error(&self.name, format!("invalid response: {}", e)))
+    }
+}
+
+impl Analyzer for PluginAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn analyze(&self, file_path: &Path, content: &str) -> Result<Vec<CodeIssue>> {
+        let response = self.call(&PluginRequest {
+            abi_version: ANALYZER_ABI_VERSION,
+            analyzer: &self.name,
+            file_path,
+            content,
+            options: &self.options,
+        })?;
+
+        if let Some(error) = response.error {
+            return Err(plugin_error(&self.name, error));
+        }
+
+        response.issues.into_iter()
+            .map(|issue| plugin_issue(&self.name, file_path, issue))
+            .collect()
+    }
+}
+
+/// Fingerprint of the analyzer libraries of a configuration
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+/// * `plugins` - Declarations of the analyzers
+///
+/// # Returns
+///
+/// A string that changes when a library is rebuilt or the options of an analyzer change
+pub fn fingerprint(project_path: &Path, plugins: &[AnalyzerPlugin]) -> String {
+    plugins.iter()
+        .map(|plugin| {
+            let modified = project_path.join(&plugin.path).metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| format!("{:?}", modified))
+                .unwrap_or_default();
+            let options = serde_json::to_string(&plugin.options).unwrap_or_default();
+            format!("{}:{}:{}:{}", plugin.name, plugin.path.display(), modified, options)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Analysis options of a checkout of a remote repository, without the analyzers
+/// whose library is inside the checkout
+///
+/// # Arguments
+///
+/// * `options` - Analysis options of the run
+/// * `project_path` - Analyzed directory, relative library paths start from
+/// * `checkout` - Root of the checkout
+///
+/// # Returns
+///
+/// The options, with only the analyzers installed outside of the checkout
+pub fn checkout_options(options: &AnalysisOptions, project_path: &Path, checkout: &Path) -> AnalysisOptions {
+    let checkout_root = checkout.canonicalize().unwrap_or_else(|_| checkout.to_path_buf());
+    let mut options = options.clone();
+
+    options.analyzers.retain(|plugin| {
+        let path = project_path.join(&plugin.path);
+        let resolved = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let inside = path.starts_with(checkout) || resolved.starts_with(&checkout_root);
+        if inside {
+            warn!("Not loading analyzer {} from {}, which is part of the analyzed repository", plugin.name, path.display());
+        }
+        !inside
+    });
+
+    options
+}
+
+/// Issue of an analyzer library as an issue of the analysis
+fn plugin_issue(analyzer: &str, file_path: &Path, issue: PluginIssue) -> Result<CodeIssue> {
+    let severity = Severity::parse(&issue.severity)
+        .ok_or_else(|| plugin_error(analyzer, format!("unknown severity '{}' of rule {}", issue.severity, issue.rule)))?;
+
+    Ok(CodeIssue {
+        file_path: file_path.to_path_buf(),
+        line_start: issue.line_start,
+        column_start: issue.column_start,
+        line_end: issue.line_end.unwrap_or(issue.line_start),
+        column_end: issue.column_end.unwrap_or(issue.column_start),
+        lint: Some(format!("{}::{}", analyzer, issue.rule)),
+        owners: Vec::new(),
+        category: IssueCategory::CustomRule(issue.rule),
+        severity,
+        message: issue.message,
+        suggested_fix: issue.suggested_fix,
+        priority: None,
+    })
+}
+
+fn plugin_error(analyzer: &str, error: impl std::fmt::Display) -> RustAiToolError {
+    RustAiToolError::Analysis(format!("Analyzer plugin '{}': {}", analyzer, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_issues() {
+        let response: PluginResponse = serde_json::from_str(r#"{
+            "issues": [
+                { "rule": "no-println", "line_start": 3, "severity": "warning", "message": "Use the logger" },
+                { "rule": "no-println", "line_start": 4, "column_start": 5, "line_end": 4, "column_end": 14, "severity": "Fatal", "message": "x" }
+            ]
+        }"#).unwrap();
+        let mut issues = response.issues.into_iter();
+
+        let issue = plugin_issue("acme", Path::new("src/main.rs"), issues.next().unwrap()).unwrap();
+        assert_eq!((issue.line_start, issue.column_start, issue.line_end, issue.column_end), (3, 1, 3, 1));
+        assert_eq!(issue.severity, Severity::Warning);
+        assert_eq!(issue.category, IssueCategory::CustomRule("no-println".to_string()));
+        assert_eq!(issue.lint.as_deref(), Some("acme::no-println"));
+
+        let error = plugin_issue("acme", Path::new("src/main.rs"), issues.next().unwrap()).unwrap_err().to_string();
+        assert!(error.contains("unknown severity 'Fatal'"), "{}", error);
+
+        let plugin = AnalyzerPlugin { name: "acme".to_string(), path: PathBuf::from("missing/libacme.so"), options: Default::default() };
+        let error = PluginAnalyzer::load(Path::new("/nonexistent"), &plugin).err().unwrap().to_string();
+        assert!(error.contains("Analyzer plugin 'acme': failed to load"), "{}", error);
+
+        // Libraries of a remote checkout are dropped, installed ones kept
+        let checkout = tempfile::tempdir().unwrap();
+        let installed = AnalyzerPlugin { name: "installed".to_string(), path: PathBuf::from("/opt/analyzers/libacme.so"), options: Default::default() };
+        let smuggled = AnalyzerPlugin { name: "smuggled".to_string(), path: checkout.path().join("libevil.so"), options: Default::default() };
+        let options = AnalysisOptions {
+            run_clippy: false,
+            use_rust_analyzer: false,
+            custom_rules: Vec::new(),
+            analyzers: vec![plugin, installed, smuggled],
+            filter: Default::default(),
+            lints: Default::default(),
+            policy: None,
+        };
+        let options = checkout_options(&options, &checkout.path().join("crates"), checkout.path());
+        let names: Vec<&str> = options.analyzers.iter().map(|plugin| plugin.name.as_str()).collect();
+        assert_eq!(names, ["installed"]);
+    }
+}
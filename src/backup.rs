@@ -0,0 +1,422 @@
+//! Backup module
+//!
+//! This module snapshots files before fixes are written to them:
+//! - Inside a git repository, the current content is committed to a
+//!   `refs/rust-ai-tool/backup-<timestamp>` ref (`backup-<timestamp>-<n>` for later backups
+//!   within the same second), leaving the working tree and index untouched
+//! - Outside git, copies are written to `.rust-ai-tool/backups/<timestamp>/<relative path>`,
+//!   one directory per run so earlier generations are kept
+//! - Git backups can be rolled back with a single `git restore --source=<ref> -- <files>`,
+//!   which leaves the index alone
+
+use crate::journal::STATE_DIR;
+use crate::{Result, RustAiToolError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{debug, info, warn};
+
+/// Namespace of the refs holding git backups
+pub const BACKUP_REF_PREFIX: &str = "refs/rust-ai-tool/backup-";
+
+/// Directory inside the state directory holding file backups
+pub const BACKUP_DIR: &str = "backups";
+
+/// A snapshot of files committed to a backup ref
+#[derive(Debug, Clone)]
+pub struct GitBackup {
+    /// Root of the git repository
+    pub repo_root: PathBuf,
+
+    /// Full name of the backup ref
+    pub reference: String,
+
+    /// SHA of the backup commit
+    pub commit: String,
+}
+
+/// Find the root of the git repository containing a path
+///
+/// # Arguments
+///
+/// * `path` - File or directory inside the repository
+///
+/// # Returns
+///
+/// The repository root, or `None` if the path is not inside a git work tree
+pub fn find_git_root(path: &Path) -> Option<PathBuf> {
+    let dir = if path.is_dir() { path } else { path.parent()? };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if root.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(root))
+    }
+}
+
+/// Commit the current content of files to a new backup ref
+///
+/// The commit is built from a temporary index on top of `HEAD`, so neither
+/// the working tree nor the staging area are modified. An existing backup ref
+/// is never overwritten.
+///
+/// # Arguments
+///
+/// * `files` - Files about to be modified (all in the same repository)
+///
+/// # Returns
+///
+/// The created backup, or `None` if the files are not inside a git repository
+pub fn create_git_backup(files: &[PathBuf]) -> Result<Option<GitBackup>> {
+    let repo_root = match files.first().and_then(|f| find_git_root(f)) {
+        Some(root) => root,
+        None => return Ok(None),
+    };
+
+    let canonical_root = repo_root.canonicalize()
+        .map_err(RustAiToolError::Io)?;
+
+    let mut relative_paths = Vec::with_capacity(files.len());
+    for file in files {
+        let canonical = file.canonicalize()
+            .map_err(RustAiToolError::Io)?;
+        let relative = canonical.strip_prefix(&canonical_root)
+            .map_err(|_| RustAiToolError::Modification(format!(
+                "{} is outside the git repository at {}",
+                file.display(),
+                repo_root.display()
+            )))?;
+        relative_paths.push(relative.to_string_lossy().into_owned());
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    // A directory per call, as backups of one process may run concurrently
+    let index_dir = tempfile::Builder::new()
+        .prefix("rust-ai-tool-index-")
+        .tempdir()
+        .map_err(RustAiToolError::Io)?;
+    let index_file = index_dir.path().join("index");
+    let head = run_git(&repo_root, &["rev-parse", "--verify", "--quiet", "HEAD"], None).ok();
+
+    let commit = commit_snapshot(&repo_root, &relative_paths, head.as_deref(), &index_file)?;
+
+    let mut reference = format!("{}{}", BACKUP_REF_PREFIX, timestamp);
+    let mut generation = 1;
+    loop {
+        // The empty old value makes git refuse to overwrite an existing ref
+        match run_git(&repo_root, &["update-ref", reference.as_str(), commit.as_str(), ""], None) {
+            Ok(_) => break,
+            Err(_) if run_git(&repo_root, &["rev-parse", "--verify", "--quiet", reference.as_str()], None).is_ok() => {
+                generation += 1;
+                reference = format!("{}{}-{}", BACKUP_REF_PREFIX, timestamp, generation);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    info!("Backed up {} files to {} ({})", relative_paths.len(), reference, commit);
+
+    Ok(Some(GitBackup {
+        repo_root,
+        reference,
+        commit,
+    }))
+}
+
+/// Build a commit of `HEAD` plus the current content of the given files
+fn commit_snapshot(
+    repo_root: &Path,
+    relative_paths: &[String],
+    head: Option<&str>,
+    index_file: &Path,
+) -> Result<String> {
+    if head.is_some() {
+        run_git(repo_root, &["read-tree", "HEAD"], Some(index_file))?;
+    }
+
+    let mut add_args = vec!["update-index", "--add", "--"];
+    add_args.extend(relative_paths.iter().map(String::as_str));
+    run_git(repo_root, &add_args, Some(index_file))?;
+
+    let tree = run_git(repo_root, &["write-tree"], Some(index_file))?;
+
+    let message = format!("rust-ai-tool backup of {} files", relative_paths.len());
+    let mut commit_args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    if let Some(head) = head {
+        commit_args.extend(["-p", head]);
+    }
+
+    run_git(repo_root, &commit_args, None)
+}
+
+/// Restore files from a git backup ref
+///
+/// Only the working tree is restored; changes staged for the files are kept.
+///
+/// # Arguments
+///
+/// * `repo_root` - Root of the git repository
+/// * `reference` - Backup ref to restore from
+/// * `files` - Files to restore
+pub fn restore_git_backup(repo_root: &Path, reference: &str, files: &[PathBuf]) -> Result<()> {
+    let paths: Vec<String> = files.iter()
+        .map(|f| f.to_string_lossy().into_owned())
+        .collect();
+
+    let source = format!("--source={}", reference);
+    let mut args = vec!["restore", source.as_str(), "--worktree", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    run_git(repo_root, &args, None)?;
+
+    info!("Restored {} files from {}", files.len(), reference);
+    Ok(())
+}
+
+/// Restore a file from its recorded backup
+///
+/// A git backup ref takes precedence over a backup file. Backup files are kept
+/// after restoring, except legacy `.bak` files which are removed.
+///
+/// # Arguments
+///
+/// * `file_path` - File to restore
+/// * `backup_path` - Backup file holding the previous content (if any)
+/// * `backup_ref` - Git ref holding the previous content (if any)
+///
+/// # Returns
+///
+/// Whether the file was restored
+pub fn restore_file(file_path: &Path, backup_path: Option<&Path>, backup_ref: Option<&str>) -> Result<bool> {
+    if let Some(backup_ref) = backup_ref {
+        let repo_root = find_git_root(file_path).ok_or_else(|| {
+            RustAiToolError::Modification(format!(
+                "{} is no longer inside a git repository",
+                file_path.display()
+            ))
+        })?;
+
+        restore_git_backup(&repo_root, backup_ref, &[file_path.to_path_buf()])?;
+        return Ok(true);
+    }
+
+    match backup_path {
+        Some(backup_path) if backup_path.exists() => {
+            let backup_content = fs::read_to_string(backup_path)
+                .map_err(RustAiToolError::Io)?;
+
+            fs::write(file_path, backup_content)
+                .map_err(RustAiToolError::Io)?;
+
+            if backup_path.extension().is_some_and(|ext| ext == "bak") {
+                fs::remove_file(backup_path)
+                    .map_err(RustAiToolError::Io)?;
+            }
+
+            Ok(true)
+        }
+        Some(backup_path) => {
+            warn!("Backup file not found: {}", backup_path.display());
+            Ok(false)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Write a copy of a file's content to the backup directory of its project
+///
+/// Backups go to `.rust-ai-tool/backups/<timestamp>/<relative path>` under the
+/// project root, the nearest ancestor with a `.rust-ai-tool` directory or a
+/// `Cargo.toml`. All backups of a run share one timestamp; a file backed up
+/// twice in the same run gets a new `<timestamp>-<n>` generation.
+///
+/// # Arguments
+///
+/// * `file_path` - File being backed up
+/// * `content` - Content to back up
+///
+/// # Returns
+///
+/// Path to the backup file
+pub fn write_file_backup(file_path: &Path, content: &str) -> Result<PathBuf> {
+    let absolute = if file_path.is_absolute() {
+        file_path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(RustAiToolError::Io)?
+            .join(file_path)
+    };
+
+    let parent = absolute.parent().unwrap_or(Path::new("/"));
+    let project_root = parent.ancestors()
+        .find(|dir| dir.join(STATE_DIR).is_dir() || dir.join("Cargo.toml").is_file())
+        .unwrap_or(parent);
+
+    let relative = absolute.strip_prefix(project_root).unwrap_or(&absolute);
+    let backups_dir = project_root.join(STATE_DIR).join(BACKUP_DIR);
+
+    let mut backup_file = backups_dir.join(backup_generation()).join(relative);
+    let mut generation = 1;
+    while backup_file.exists() {
+        generation += 1;
+        backup_file = backups_dir
+            .join(format!("{}-{}", backup_generation(), generation))
+            .join(relative);
+    }
+
+    if let Some(dir) = backup_file.parent() {
+        fs::create_dir_all(dir)
+            .map_err(RustAiToolError::Io)?;
+    }
+
+    fs::write(&backup_file, content)
+        .map_err(RustAiToolError::Io)?;
+
+    debug!("Created backup at {}", backup_file.display());
+    Ok(backup_file)
+}
+
+/// Timestamp naming the backup generation of the current run
+fn backup_generation() -> &'static str {
+    static GENERATION: OnceLock<String> = OnceLock::new();
+
+    GENERATION.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+            .to_string()
+    })
+}
+
+/// Run a git command and return its trimmed stdout
+pub(crate) fn run_git(repo_root: &Path, args: &[&str], index_file: Option<&Path>) -> Result<String> {
+    debug!("Running git {:?} in {}", args, repo_root.display());
+
+    let mut cmd = Command::new("git");
+    cmd.args(args)
+        .current_dir(repo_root)
+        .env("GIT_AUTHOR_NAME", "rust-ai-tool")
+        .env("GIT_AUTHOR_EMAIL", "rust-ai-tool@localhost")
+        .env("GIT_COMMITTER_NAME", "rust-ai-tool")
+        .env("GIT_COMMITTER_EMAIL", "rust-ai-tool@localhost");
+
+    if let Some(index_file) = index_file {
+        cmd.env("GIT_INDEX_FILE", index_file);
+    }
+
+    let output = cmd.output().map_err(RustAiToolError::Io)?;
+
+    if !output.status.success() {
+        return Err(RustAiToolError::Modification(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_git_backup_roundtrip() {
+        let dir = tempdir().unwrap();
+        Command::new("git").arg("init").arg("-q").current_dir(dir.path()).status().unwrap();
+
+        let file_path = dir.path().join("main.rs");
+        fs::write(&file_path, "fn main() {}\n").unwrap();
+
+        let backup = create_git_backup(std::slice::from_ref(&file_path)).unwrap().unwrap();
+        assert!(backup.reference.starts_with(BACKUP_REF_PREFIX));
+
+        // Backups within the same second get their own refs
+        let second = create_git_backup(std::slice::from_ref(&file_path)).unwrap().unwrap();
+        let third = create_git_backup(std::slice::from_ref(&file_path)).unwrap().unwrap();
+        assert_ne!(second.reference, backup.reference);
+        assert_ne!(third.reference, second.reference);
+        assert!(run_git(&backup.repo_root, &["rev-parse", "--verify", "--quiet", backup.reference.as_str()], None).is_ok());
+
+        // The index is left alone
+        let staged = run_git(&backup.repo_root, &["diff", "--cached", "--name-only"], None).unwrap();
+        assert!(staged.is_empty());
+
+        // Restoring leaves what the user staged alone
+        fs::write(&file_path, "fn main() { staged(); }\n").unwrap();
+        run_git(&backup.repo_root, &["add", "main.rs"], None).unwrap();
+        fs::write(&file_path, "fn main() { println!(\"changed\"); }\n").unwrap();
+        restore_git_backup(&backup.repo_root, &backup.reference, std::slice::from_ref(&file_path)).unwrap();
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn main() {}\n");
+        let staged = run_git(&backup.repo_root, &["show", ":main.rs"], None).unwrap();
+        assert_eq!(staged, "fn main() { staged(); }");
+    }
+
+    #[test]
+    fn test_concurrent_git_backups() {
+        let dir = tempdir().unwrap();
+        Command::new("git").arg("init").arg("-q").current_dir(dir.path()).status().unwrap();
+
+        let backups: Vec<(PathBuf, GitBackup)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8).map(|i| {
+                let file_path = dir.path().join(format!("file{}.rs", i));
+                fs::write(&file_path, format!("const N: u32 = {};\n", i)).unwrap();
+                scope.spawn(move || {
+                    let backup = create_git_backup(std::slice::from_ref(&file_path)).unwrap().unwrap();
+                    (file_path, backup)
+                })
+            }).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        let mut references: Vec<&str> = backups.iter().map(|(_, backup)| backup.reference.as_str()).collect();
+        references.sort();
+        references.dedup();
+        assert_eq!(references.len(), backups.len());
+
+        // Each snapshot holds its own file only
+        for (file_path, backup) in &backups {
+            let files = run_git(&backup.repo_root, &["ls-tree", "--name-only", backup.commit.as_str()], None).unwrap();
+            assert_eq!(files, file_path.file_name().unwrap().to_string_lossy());
+        }
+    }
+
+    #[test]
+    fn test_file_backup_generations() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("src").join("main.rs");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+
+        let first = write_file_backup(&file_path, "first\n").unwrap();
+        let second = write_file_backup(&file_path, "second\n").unwrap();
+
+        let backups_dir = dir.path().join(STATE_DIR).join(BACKUP_DIR);
+        assert!(first.starts_with(&backups_dir));
+        assert!(first.ends_with(Path::new("src").join("main.rs")));
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first\n");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second\n");
+    }
+}
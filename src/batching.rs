@@ -0,0 +1,232 @@
+//! Batching module
+//!
+//! This module plans how many fixes are split into pull requests small enough to review:
+//! - Fixes are grouped per module (`src/analysis/rules.rs` is in `analysis`, and
+//!   `crates/app/src/db.rs` in `app::db`), per issue category or per file
+//! - All fixes of a file stay in the same batch, so batches never conflict
+//! - Groups with more fixes than the cap are split, each part its own batch
+//! - Every batch gets its own branch and title, derived from the ones given for
+//!   a single pull request
+
+use crate::modification::CodeModification;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// Group of fixes not resolving any analysis issue, when batching by category
+const UNCATEGORIZED: &str = "other";
+
+/// How fixes are grouped into batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchStrategy {
+    /// One batch per top-level module of a crate
+    Module,
+
+    /// One batch per category of the issues the fixes resolve
+    Category,
+
+    /// One batch per file
+    File,
+}
+
+/// Fixes opened as one pull request
+#[derive(Debug, Clone)]
+pub struct FixBatch {
+    /// Group of the batch (a module, a category or a file), with the part number if the
+    /// group was split
+    pub key: String,
+
+    /// Fixes of the batch
+    pub modifications: Vec<CodeModification>,
+}
+
+impl FixBatch {
+    /// Branch of the batch, like `fixes-analysis-2-3` for `analysis (2/3)`
+    pub fn branch(&self, base_branch: &str) -> String {
+        let mut slug = String::new();
+        for c in self.key.chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+
+        format!("{}-{}", base_branch, slug.trim_end_matches('-'))
+    }
+
+    /// Title of the pull request of the batch, like `Fix issues: analysis (2/3)`
+    pub fn title(&self, base_title: &str) -> String {
+        format!("{}: {}", base_title, self.key)
+    }
+}
+
+/// Plan the batches of a set of fixes
+///
+/// # Arguments
+///
+/// * `modifications` - Fixes to open pull requests for
+/// * `base_dir` - Project root, used to derive modules
+/// * `strategy` - How fixes are grouped
+/// * `max_size` - Most fixes in one batch (0 for no cap); a file with more fixes is still one batch
+///
+/// # Returns
+///
+/// The batches, ordered by group
+pub fn plan_batches(
+    modifications: &[CodeModification],
+    base_dir: &Path,
+    strategy: BatchStrategy,
+    max_size: usize,
+) -> Vec<FixBatch> {
+    let mut files: BTreeMap<&Path, Vec<&CodeModification>> = BTreeMap::new();
+    for modification in modifications {
+        files.entry(modification.file_path.as_path()).or_default().push(modification);
+    }
+
+    let mut groups: BTreeMap<String, Vec<Vec<&CodeModification>>> = BTreeMap::new();
+    for (file_path, file_modifications) in files {
+        let key = match strategy {
+            BatchStrategy::Module => module_of(file_path, base_dir),
+            BatchStrategy::Category => main_category(&file_modifications),
+            BatchStrategy::File => relative_path(file_path, base_dir),
+        };
+        groups.entry(key).or_default().push(file_modifications);
+    }
+
+    let mut batches = Vec::new();
+    for (key, files) in groups {
+        let mut parts: Vec<Vec<CodeModification>> = Vec::new();
+        for file_modifications in files {
+            let full = parts.last().is_none_or(|part| max_size > 0 && part.len() + file_modifications.len() > max_size);
+            if full {
+                parts.push(Vec::new());
+            }
+            if let Some(part) = parts.last_mut() {
+                part.extend(file_modifications.into_iter().cloned());
+            }
+        }
+
+        let part_count = parts.len();
+        for (index, modifications) in parts.into_iter().enumerate() {
+            let key = if part_count > 1 { format!("{} ({}/{})", key, index + 1, part_count) } else { key.clone() };
+            batches.push(FixBatch { key, modifications });
+        }
+    }
+
+    batches
+}
+
+/// Top-level module of a file, prefixed with its crate in a workspace
+fn module_of(file_path: &Path, base_dir: &Path) -> String {
+    let components: Vec<String> = file_path.strip_prefix(base_dir)
+        .unwrap_or(file_path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    let Some(src) = components.iter().rposition(|component| component == "src") else {
+        // Tests, benches, examples and the manifest
+        return match components.as_slice() {
+            [directory, _, ..] => directory.clone(),
+            _ => "crate".to_string(),
+        };
+    };
+
+    let module = components.get(src + 1)
+        .map(|part| part.strip_suffix(".rs").unwrap_or(part))
+        .filter(|part| !matches!(*part, "lib" | "main" | "mod"));
+    let crate_name = src.checked_sub(1).and_then(|index| components.get(index)).map(|name| name.replace('-', "_"));
+
+    match (crate_name, module) {
+        (Some(crate_name), Some(module)) => format!("{}::{}", crate_name, module),
+        (Some(crate_name), None) => crate_name,
+        (None, Some(module)) => module.to_string(),
+        (None, None) => "crate".to_string(),
+    }
+}
+
+/// Category resolved most often by the fixes of a file
+fn main_category(modifications: &[&CodeModification]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for issue in modifications.iter().flat_map(|modification| &modification.resolves) {
+        *counts.entry(issue.rule.as_str()).or_default() += 1;
+    }
+
+    // The first of the most frequent categories, in name order
+    counts.into_iter()
+        .fold(None, |best: Option<(&str, usize)>, (rule, count)| match best {
+            Some((_, best_count)) if best_count >= count => best,
+            _ => Some((rule, count)),
+        })
+        .map_or_else(|| UNCATEGORIZED.to_string(), |(rule, _)| rule.to_string())
+}
+
+fn relative_path(file_path: &Path, base_dir: &Path) -> String {
+    file_path.strip_prefix(base_dir).unwrap_or(file_path).to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::IssueReference;
+    use std::path::PathBuf;
+
+    fn fix(path: &str, rules: &[&str]) -> CodeModification {
+        CodeModification {
+            file_path: PathBuf::from("project").join(path),
+            original_content: String::new(),
+            modified_content: String::new(),
+            description: format!("Fix {}", path),
+            confidence: 90,
+            resolves: rules.iter()
+                .map(|rule| IssueReference {
+                    id: String::new(),
+                    line_start: 1,
+                    line_end: 1,
+                    rule: rule.to_string(),
+                    message: String::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_plan_batches() {
+        let base_dir = Path::new("project");
+        let fixes = vec![
+            fix("src/analysis.rs", &["Style"]),
+            fix("src/analysis/rules.rs", &["Performance", "Style", "Style"]),
+            fix("src/lib.rs", &["Security"]),
+            fix("src/analysis/rules.rs", &["Style"]),
+            fix("crates/my-app/src/db/mod.rs", &[]),
+            fix("tests/integration.rs", &["Style"]),
+        ];
+
+        let keys = |batches: &[FixBatch]| batches.iter()
+            .map(|batch| (batch.key.clone(), batch.modifications.len()))
+            .collect::<Vec<_>>();
+
+        let by_module = plan_batches(&fixes, base_dir, BatchStrategy::Module, 0);
+        assert_eq!(keys(&by_module), [
+            ("analysis".to_string(), 3),
+            ("crate".to_string(), 1),
+            ("my_app::db".to_string(), 1),
+            ("tests".to_string(), 1),
+        ]);
+
+        let capped = plan_batches(&fixes, base_dir, BatchStrategy::Module, 2);
+        assert_eq!(keys(&capped)[..2], [("analysis (1/2)".to_string(), 2), ("analysis (2/2)".to_string(), 1)]);
+        assert_eq!(capped[1].branch("fixes"), "fixes-analysis-2-2");
+        assert_eq!(capped[1].title("Fix issues"), "Fix issues: analysis (2/2)");
+
+        let by_category = plan_batches(&fixes, base_dir, BatchStrategy::Category, 0);
+        assert_eq!(keys(&by_category), [
+            ("Security".to_string(), 1),
+            ("Style".to_string(), 4),
+            ("other".to_string(), 1),
+        ]);
+    }
+}
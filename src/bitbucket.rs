@@ -0,0 +1,397 @@
+//! Bitbucket Cloud integration module
+//!
+//! This module talks to Bitbucket Cloud through its REST API (2.0):
+//! - Repository information, branches and file contents
+//! - Pull requests, pull request comments and inline comments
+//! - Status of the latest Bitbucket Pipelines run of a branch
+
+use crate::forge::ForgeProvider;
+use crate::github::{PullRequestInfo, PullRequestOptions, RepoInfo};
+use crate::{BitbucketRepo, Result, RustAiToolError, SshConfig};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use log::{debug, info, warn};
+
+/// Base URL of the Bitbucket Cloud API
+const BITBUCKET_API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// Client of a repository on Bitbucket Cloud
+pub struct BitbucketClient {
+    /// HTTP client for API requests
+    client: reqwest::Client,
+
+    /// Base URL of the API
+    api_url: String,
+
+    /// Workspace owning the repository
+    workspace: String,
+
+    /// Slug of the repository
+    repo_slug: String,
+
+    /// Username for app password authentication (`None` for access tokens)
+    username: Option<String>,
+
+    /// App password or access token
+    token: String,
+
+    /// SSH settings for cloning and pushing (HTTPS if `None`)
+    ssh: Option<SshConfig>,
+}
+
+/// Repository returned by the API
+#[derive(Debug, Deserialize)]
+struct BitbucketRepository {
+    mainbranch: Option<BitbucketBranchName>,
+    #[serde(default)]
+    description: String,
+    parent: Option<serde_json::Value>,
+}
+
+/// Branch name in API objects
+#[derive(Debug, Deserialize)]
+struct BitbucketBranchName {
+    name: String,
+}
+
+/// Branch returned by the refs API
+#[derive(Debug, Deserialize)]
+struct BitbucketBranch {
+    target: BitbucketCommit,
+}
+
+/// Commit in API objects
+#[derive(Debug, Deserialize)]
+struct BitbucketCommit {
+    hash: String,
+}
+
+/// Pull request returned by the API
+#[derive(Debug, Deserialize)]
+struct BitbucketPullRequest {
+    id: u64,
+    title: String,
+    state: String,
+    #[serde(default)]
+    description: String,
+    links: BitbucketLinks,
+}
+
+/// Links of an API object
+#[derive(Debug, Deserialize)]
+struct BitbucketLinks {
+    html: BitbucketLink,
+}
+
+/// Link of an API object
+#[derive(Debug, Deserialize)]
+struct BitbucketLink {
+    href: String,
+}
+
+/// Page of pull requests returned by the API
+#[derive(Debug, Deserialize)]
+struct BitbucketPullRequests {
+    values: Vec<BitbucketPullRequest>,
+}
+
+/// Page of pipelines returned by the API
+#[derive(Debug, Deserialize)]
+struct BitbucketPipelines {
+    values: Vec<BitbucketPipeline>,
+}
+
+/// Pipeline run returned by the API
+#[derive(Debug, Deserialize)]
+struct BitbucketPipeline {
+    build_number: u64,
+    state: BitbucketPipelineState,
+}
+
+/// State of a pipeline run (`PENDING`, `IN_PROGRESS` or `COMPLETED` with a result)
+#[derive(Debug, Deserialize)]
+struct BitbucketPipelineState {
+    name: String,
+    result: Option<BitbucketBranchName>,
+}
+
+impl From<BitbucketPullRequest> for PullRequestInfo {
+    fn from(pull_request: BitbucketPullRequest) -> Self {
+        Self {
+            number: pull_request.id,
+            title: pull_request.title,
+            url: pull_request.links.html.href,
+            is_merged: pull_request.state == "MERGED",
+            // OPEN, MERGED, DECLINED or SUPERSEDED
+            state: match pull_request.state.as_str() {
+                "OPEN" => "open".to_string(),
+                _ => "closed".to_string(),
+            },
+            body: pull_request.description,
+        }
+    }
+}
+
+impl BitbucketClient {
+    /// Create a client for a repository
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - Workspace owning the repository
+    /// * `repo_slug` - Slug of the repository
+    /// * `username` - Username for app password authentication (`None` for access tokens)
+    /// * `token` - App password or access token
+    ///
+    /// # Returns
+    ///
+    /// The client
+    pub fn new(workspace: &str, repo_slug: &str, username: Option<&str>, token: &str) -> Result<Self> {
+        let client = crate::network::client_builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| RustAiToolError::Forge(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            api_url: BITBUCKET_API_URL.to_string(),
+            workspace: workspace.to_string(),
+            repo_slug: repo_slug.to_string(),
+            username: username.map(String::from),
+            token: token.to_string(),
+            ssh: None,
+        })
+    }
+
+    /// Create a client for the repository of a configuration
+    pub fn from_repo(repo: &BitbucketRepo) -> Result<Self> {
+        Self::new(&repo.workspace, &repo.repo_slug, repo.username.as_deref(), &repo.access_token)
+    }
+
+    /// Clone and push over SSH with these settings (HTTPS if `None`)
+    pub fn with_ssh(mut self, ssh: Option<SshConfig>) -> Self {
+        self.ssh = ssh;
+        self
+    }
+
+    /// Use another API base URL (e.g. a proxy)
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Status of the latest pipeline run of a branch
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - Branch the pipeline ran on
+    ///
+    /// # Returns
+    ///
+    /// The status (e.g. `#12 COMPLETED (SUCCESSFUL)`), or `None` if no pipeline ran
+    pub async fn pipeline_status(&self, branch: &str) -> Result<Option<String>> {
+        info!("Getting the latest pipeline of branch {}", branch);
+
+        let request = self.client.get(self.endpoint("/pipelines/"))
+            .query(&[("target.branch", branch), ("sort", "-created_on"), ("pagelen", "1")]);
+        let pipelines: BitbucketPipelines = self.send_json(request).await?;
+
+        Ok(pipelines.values.into_iter().next().map(|pipeline| match pipeline.state.result {
+            Some(result) => format!("#{} {} ({})", pipeline.build_number, pipeline.state.name, result.name),
+            None => format!("#{} {}", pipeline.build_number, pipeline.state.name),
+        }))
+    }
+
+    /// URL of a repository API endpoint
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/repositories/{}/{}{}", self.api_url, self.workspace, self.repo_slug, path)
+    }
+
+    /// Send an authenticated request
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let request = match &self.username {
+            Some(username) => request.basic_auth(username, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RustAiToolError::Forge(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(RustAiToolError::Forge(format!("Bitbucket API returned {}: {}", status, body.trim())));
+        }
+
+        Ok(response)
+    }
+
+    /// Send an authenticated request and parse the JSON answer
+    async fn send_json<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        self.send(request).await?
+            .json()
+            .await
+            .map_err(|e| RustAiToolError::Forge(format!("Invalid Bitbucket API response: {}", e)))
+    }
+
+    /// Post a pull request comment
+    async fn post_comment(&self, pr_number: u64, comment: serde_json::Value) -> Result<()> {
+        let _: serde_json::Value = self.send_json(
+            self.client.post(self.endpoint(&format!("/pullrequests/{}/comments", pr_number))).json(&comment)
+        ).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ForgeProvider for BitbucketClient {
+    fn name(&self) -> &'static str {
+        "Bitbucket"
+    }
+
+    fn clone_url(&self) -> String {
+        match self.ssh {
+            Some(_) => format!("git@bitbucket.org:{}/{}.git", self.workspace, self.repo_slug),
+            None => format!("https://bitbucket.org/{}/{}.git", self.workspace, self.repo_slug),
+        }
+    }
+
+    fn ssh(&self) -> Option<&SshConfig> {
+        self.ssh.as_ref()
+    }
+
+    async fn get_repo_info(&self) -> Result<RepoInfo> {
+        info!("Getting information for repository {}/{}", self.workspace, self.repo_slug);
+
+        let repo: BitbucketRepository = self.send_json(self.client.get(self.endpoint(""))).await?;
+
+        Ok(RepoInfo {
+            owner: self.workspace.clone(),
+            repo: self.repo_slug.clone(),
+            default_branch: repo.mainbranch.map_or_else(|| "main".to_string(), |branch| branch.name),
+            is_fork: repo.parent.is_some(),
+            description: Some(repo.description).filter(|d| !d.is_empty()),
+        })
+    }
+
+    async fn create_branch(&self, base_branch: &str, new_branch: &str) -> Result<()> {
+        info!("Creating branch {} from {}", new_branch, base_branch);
+
+        let base: BitbucketBranch = self.send_json(
+            self.client.get(self.endpoint(&format!("/refs/branches/{}", base_branch)))
+        ).await?;
+        debug!("Base branch {} is at {}", base_branch, base.target.hash);
+
+        let _: serde_json::Value = self.send_json(self.client.post(self.endpoint("/refs/branches")).json(&serde_json::json!({
+            "name": new_branch,
+            "target": { "hash": base.target.hash },
+        }))).await?;
+
+        Ok(())
+    }
+
+    async fn create_pull_request(
+        &self,
+        title: &str,
+        body: &str,
+        head: &str,
+        base: &str,
+        options: &PullRequestOptions,
+    ) -> Result<PullRequestInfo> {
+        info!("Creating pull request: {} ({} -> {})", title, head, base);
+
+        if !options.labels.is_empty() || !options.assignees.is_empty() {
+            warn!("Bitbucket pull requests have no labels or assignees; skipping them");
+        }
+
+        // Users are identified by their UUID (`{...}`) or account ID
+        let reviewers: Vec<serde_json::Value> = options.reviewers.iter()
+            .map(|reviewer| if reviewer.starts_with('{') {
+                serde_json::json!({ "uuid": reviewer })
+            } else {
+                serde_json::json!({ "account_id": reviewer })
+            })
+            .collect();
+
+        let pull_request: BitbucketPullRequest = self.send_json(self.client.post(self.endpoint("/pullrequests")).json(&serde_json::json!({
+            "title": title,
+            "description": body,
+            "source": { "branch": { "name": head } },
+            "destination": { "branch": { "name": base } },
+            "draft": options.draft,
+            "reviewers": reviewers,
+        }))).await?;
+
+        info!("Successfully created pull request #{}: {}", pull_request.id, pull_request.links.html.href);
+        Ok(pull_request.into())
+    }
+
+    async fn get_pull_request(&self, pr_number: u64) -> Result<PullRequestInfo> {
+        let pull_request: BitbucketPullRequest = self.send_json(
+            self.client.get(self.endpoint(&format!("/pullrequests/{}", pr_number)))
+        ).await?;
+
+        debug!("PR info: {:?}", pull_request);
+        Ok(pull_request.into())
+    }
+
+    async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequestInfo>> {
+        let query = format!("source.branch.name = \"{}\" AND state = \"OPEN\"", head.replace('"', "\\\""));
+        let pull_requests: BitbucketPullRequests = self.send_json(
+            self.client.get(self.endpoint("/pullrequests"))
+                .query(&[("q", query.as_str()), ("pagelen", "1")])
+        ).await?;
+
+        Ok(pull_requests.values.into_iter().next().map(PullRequestInfo::from))
+    }
+
+    async fn update_pull_request(&self, pr_number: u64, title: &str, body: &str) -> Result<()> {
+        info!("Updating PR #{}", pr_number);
+
+        let _: serde_json::Value = self.send_json(
+            self.client.put(self.endpoint(&format!("/pullrequests/{}", pr_number)))
+                .json(&serde_json::json!({ "title": title, "description": body }))
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn add_pr_comment(&self, pr_number: u64, comment: &str) -> Result<()> {
+        info!("Adding comment to PR #{}", pr_number);
+
+        self.post_comment(pr_number, serde_json::json!({
+            "content": { "raw": comment },
+        })).await
+    }
+
+    async fn add_inline_comment(&self, pr_number: u64, path: &str, line: usize, comment: &str) -> Result<()> {
+        info!("Adding comment to {}:{} in PR #{}", path, line, pr_number);
+
+        self.post_comment(pr_number, serde_json::json!({
+            "content": { "raw": comment },
+            "inline": { "path": path, "to": line },
+        })).await
+    }
+
+    async fn get_file_content(&self, path: &str, branch: Option<&str>) -> Result<String> {
+        info!("Getting content of file: {}", path);
+
+        let branch = match branch {
+            Some(branch) => branch.to_string(),
+            None => self.get_repo_info().await?.default_branch,
+        };
+
+        self.send(self.client.get(self.endpoint(&format!("/src/{}/{}", branch, path))))
+            .await?
+            .text()
+            .await
+            .map_err(|e| RustAiToolError::Forge(format!("Failed to read {}: {}", path, e)))
+    }
+
+    async fn pipeline_status(&self, branch: &str) -> Result<Option<String>> {
+        BitbucketClient::pipeline_status(self, branch).await
+    }
+}
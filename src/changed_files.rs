@@ -0,0 +1,103 @@
+//! Changed files module
+//!
+//! This module finds the Rust files of a project that changed in git, so only
+//! they are analyzed:
+//! - Without a base, files modified in the working tree or the index since `HEAD`
+//! - With a base ref, files changed since the commit the current branch forked
+//!   from it, including uncommitted changes
+//! - Untracked files that are not ignored count as changed; deleted files are skipped
+
+use crate::backup::{find_git_root, run_git};
+use crate::{Result, RustAiToolError};
+use std::path::{Path, PathBuf};
+use log::debug;
+
+/// Rust files of a project changed in git
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory, inside a git repository
+/// * `base` - Ref to compare against, like `main` or `origin/main` (`HEAD` if not set)
+///
+/// # Returns
+///
+/// Paths of the changed files, under `project_path`
+pub fn changed_rust_files(project_path: &Path, base: Option<&str>) -> Result<Vec<PathBuf>> {
+    let repo_root = find_git_root(project_path)
+        .ok_or_else(|| RustAiToolError::Analysis(format!("{} is not inside a git repository", project_path.display())))?;
+
+    let changed = match base {
+        Some(base) => run_git(&repo_root, &["diff", "--name-only", "-z", "--merge-base", base], None)?,
+        None if run_git(&repo_root, &["rev-parse", "--verify", "--quiet", "HEAD"], None).is_ok() => {
+            run_git(&repo_root, &["diff", "--name-only", "-z", "HEAD"], None)?
+        }
+        // Before the first commit every tracked file is new
+        None => run_git(&repo_root, &["ls-files", "-z"], None)?,
+    };
+    let untracked = run_git(&repo_root, &["ls-files", "--others", "--exclude-standard", "-z"], None)?;
+
+    // Git reports paths relative to the resolved repository root
+    let repo_root = repo_root.canonicalize().map_err(RustAiToolError::Io)?;
+    let project_root = project_path.canonicalize().map_err(RustAiToolError::Io)?;
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for relative_path in changed.split('\0').chain(untracked.split('\0')) {
+        if !relative_path.ends_with(".rs") {
+            continue;
+        }
+
+        let Ok(project_relative) = repo_root.join(relative_path).strip_prefix(&project_root).map(Path::to_path_buf) else {
+            continue;
+        };
+        let file_path = project_path.join(project_relative);
+
+        if file_path.is_file() && !files.contains(&file_path) {
+            files.push(file_path);
+        }
+    }
+
+    debug!("{} Rust files changed in {}", files.len(), project_path.display());
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_changed_rust_files() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("crate");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(dir.path().join("build.rs"), "fn main() {}\n").unwrap();
+        fs::write(project.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(project.join("src/old.rs"), "pub fn b() {}\n").unwrap();
+        fs::write(project.join("src/gone.rs"), "pub fn c() {}\n").unwrap();
+
+        run_git(dir.path(), &["init", "-q", "-b", "main"], None).unwrap();
+        assert_eq!(changed_rust_files(&project, None).unwrap().len(), 3);
+
+        run_git(dir.path(), &["add", "."], None).unwrap();
+        assert_eq!(changed_rust_files(&project, None).unwrap().len(), 3);
+        run_git(dir.path(), &["commit", "-q", "-m", "Initial"], None).unwrap();
+        assert!(changed_rust_files(&project, None).unwrap().is_empty());
+
+        run_git(dir.path(), &["checkout", "-q", "-b", "feature"], None).unwrap();
+        fs::write(project.join("src/old.rs"), "pub fn b() { todo!() }\n").unwrap();
+        run_git(dir.path(), &["commit", "-q", "-am", "Change old.rs"], None).unwrap();
+        fs::write(project.join("src/lib.rs"), "pub fn a() { todo!() }\n").unwrap();
+        fs::write(project.join("src/new.rs"), "pub fn d() {}\n").unwrap();
+        fs::write(project.join("notes.md"), "notes\n").unwrap();
+        fs::write(dir.path().join("build.rs"), "fn main() { todo!() }\n").unwrap();
+        fs::remove_file(project.join("src/gone.rs")).unwrap();
+
+        assert_eq!(changed_rust_files(&project, None).unwrap(), [project.join("src/lib.rs"), project.join("src/new.rs")]);
+        assert_eq!(
+            changed_rust_files(&project, Some("main")).unwrap(),
+            [project.join("src/lib.rs"), project.join("src/old.rs"), project.join("src/new.rs")]
+        );
+        assert!(changed_rust_files(&project, Some("no-such-ref")).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Chat module
+//!
+//! This module answers questions about a project in an interactive session:
+//! - Conversation history kept across questions and sent with each one
+//! - Related definitions from the code index added to every question
+//! - Files the assistant proposes to change, turned into code modifications
+//!   that go through the usual validate and apply steps
+
+use crate::models::AiModelClient;
+use crate::modification::{create_modification, CodeModification};
+use crate::{Result, RustAiToolError};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use log::debug;
+
+/// Earlier turns sent with a question
+const MAX_HISTORY_TURNS: usize = 6;
+
+/// Confidence of proposed changes, which are not validated when proposed
+const PROPOSAL_CONFIDENCE: u8 = 60;
+
+/// One question and its answer
+#[derive(Debug, Clone)]
+pub struct ChatTurn {
+    /// Question of the user
+    pub question: String,
+
+    /// Answer of the assistant
+    pub answer: String,
+}
+
+/// Chat session about a project
+pub struct ChatSession<'a> {
+    /// Client answering the questions
+    client: &'a AiModelClient,
+
+    /// Root of the project
+    project_path: PathBuf,
+
+    /// Questions and answers so far, oldest first
+    history: Vec<ChatTurn>,
+
+    /// Latest proposed change of each file
+    proposals: Vec<CodeModification>,
+}
+
+impl<'a> ChatSession<'a> {
+    /// Start a chat session
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - AI model client (with the project's code index, if any)
+    /// * `project_path` - Root of the project
+    ///
+    /// # Returns
+    ///
+    /// The session
+    pub fn new(client: &'a AiModelClient, project_path: &Path) -> Self {
+        Self {
+            client,
+            project_path: project_path.to_path_buf(),
+            history: Vec::new(),
+            proposals: Vec::new(),
+        }
+    }
+
+    /// Ask a question
+    ///
+    /// Changes proposed in the answer replace earlier proposals for the same files.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - Question of the user
+    ///
+    /// # Returns
+    ///
+    /// The answer, and the number of changes it proposes
+    pub async fn ask(&mut self, question: &str) -> Result<(String, usize)> {
+        let history_start = self.history.len().saturating_sub(MAX_HISTORY_TURNS);
+        let answer = self.client.chat(&self.history[history_start..], question).await?;
+
+        let proposed = extract_proposals(&answer, &self.project_path)?;
+        let count = proposed.len();
+        debug!("Answer proposes {} changes", count);
+
+        for proposal in proposed {
+            self.proposals.retain(|p| p.file_path != proposal.file_path);
+            self.proposals.push(proposal);
+        }
+
+        self.history.push(ChatTurn {
+            question: question.to_string(),
+            answer: answer.clone(),
+        });
+
+        Ok((answer, count))
+    }
+
+    /// Changes proposed so far, one per file
+    pub fn proposals(&self) -> &[CodeModification] {
+        &self.proposals
+    }
+
+    /// Forget the conversation and the proposed changes
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.proposals.clear();
+    }
+}
+
+/// Changes proposed in an answer
+///
+/// A change is the complete new content of a file in a code block whose info
+/// string names the file, e.g. ```` ```rust path=src/lib.rs ````.
+///
+/// # Arguments
+///
+/// * `answer` - Answer of the assistant
+/// * `project_path` - Root of the project the paths are relative to
+///
+/// # Returns
+///
+/// One modification per proposed file, or an error if a path leaves the project
+pub fn extract_proposals(answer: &str, project_path: &Path) -> Result<Vec<CodeModification>> {
+    let block_regex = regex::Regex::new(r"(?s)```[A-Za-z]*[ \t]+path=(\S+)[^\n]*\n(.*?)```").unwrap();
+    let mut proposals = Vec::new();
+
+    for captures in block_regex.captures_iter(answer) {
+        let relative_path = Path::new(&captures[1]);
+        if !relative_path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(RustAiToolError::AiModel(format!(
+                "Proposed change to {} is outside the project",
+                relative_path.display()
+            )));
+        }
+
+        let file_path = project_path.join(relative_path);
+        let original = if file_path.exists() {
+            fs::read_to_string(&file_path).map_err(RustAiToolError::Io)?
+        } else {
+            String::new()
+        };
+
+        proposals.push(create_modification(
+            file_path,
+            original,
+            captures[2].to_string(),
+            format!("Change to {} proposed in chat", relative_path.display()),
+            PROPOSAL_CONFIDENCE,
+        ));
+    }
+
+    Ok(proposals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_extract_proposals() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn old() {}\n").unwrap();
+
+        let answer = "Rename it:\n\n```rust path=src/lib.rs\nfn new() {}\n```\n\nAn example:\n```rust\nnew();\n```\n";
+        let proposals = extract_proposals(answer, dir.path()).unwrap();
+
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].file_path, dir.path().join("src/lib.rs"));
+        assert_eq!(proposals[0].original_content, "fn old() {}\n");
+        assert_eq!(proposals[0].modified_content, "fn new() {}\n");
+
+        assert!(extract_proposals("```rust path=../outside.rs\nfn f() {}\n```", dir.path()).is_err());
+    }
+}
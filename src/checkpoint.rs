@@ -0,0 +1,268 @@
+//! Checkpoint module
+//!
+//! This module lets long runs continue where an interrupted run stopped:
+//! - Every unit of work a stage completes (the analysis of a project, the fix of a
+//!   file, the validation verdict of a fix, the analysis of a repository, the
+//!   pipeline of a repository of a batch) is appended to `.rust-ai-tool/checkpoints/<run>.jsonl` as soon as it is done
+//! - Units are keyed by a hash of their input, like a file's content and issues,
+//!   so work whose input changed since the interrupted run is done again
+//! - A run started with `--resume` reuses the units of the checkpoint; any other
+//!   run starts a new checkpoint
+//! - A run that completes removes its checkpoint
+//!
+//! A line cut short by the interruption is ignored, like a unit never recorded.
+
+use crate::analysis::collect_rust_files;
+use crate::journal::{content_hash, STATE_DIR};
+use crate::{Result, RustAiToolError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use log::{debug, info, warn};
+
+/// Directory of checkpoints inside the state directory
+pub const CHECKPOINT_DIR: &str = "checkpoints";
+
+/// Stage of the analysis of a project
+pub const ANALYSIS_STAGE: &str = "analysis";
+
+/// Stage of the fix of a file
+pub const FIX_STAGE: &str = "fix";
+
+/// Stage of the validation verdict of a fix
+pub const VALIDATION_STAGE: &str = "validation";
+
+/// Stage of the analysis of a repository of an organization
+pub const ORG_STAGE: &str = "org";
+
+/// Stage of the pipeline of a repository of a batch
+pub const BATCH_STAGE: &str = "batch";
+
+/// A completed unit of work, one line of a checkpoint file
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointEntry {
+    stage: String,
+    key: String,
+    value: Value,
+}
+
+/// Completed work of a run, persisted as it completes
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// Path to the checkpoint file
+    path: PathBuf,
+
+    /// Units completed by the interrupted run, by stage and key
+    completed: HashMap<(String, String), Value>,
+
+    /// Checkpoint file, opened for appending on the first recorded unit
+    file: Mutex<Option<File>>,
+}
+
+impl Checkpoint {
+    /// Open the checkpoint of a run
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dir` - Directory holding the state directory, usually the project
+    /// * `run` - Name of the run, like `fix` or `analyze-org-acme`
+    /// * `resume` - Whether to reuse the units of an interrupted run, instead of starting over
+    ///
+    /// # Returns
+    ///
+    /// The checkpoint, with the completed units when resuming
+    pub fn open(base_dir: &Path, run: &str, resume: bool) -> Result<Self> {
+        let file_name: String = run.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        let path = base_dir.join(STATE_DIR).join(CHECKPOINT_DIR).join(format!("{}.jsonl", file_name));
+
+        let mut completed = HashMap::new();
+        if resume {
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    for line in content.lines() {
+                        match serde_json::from_str::<CheckpointEntry>(line) {
+                            Ok(entry) => {
+                                completed.insert((entry.stage, entry.key), entry.value);
+                            }
+                            Err(e) => debug!("Skipping incomplete checkpoint line: {}", e),
+                        }
+                    }
+                    info!("Resuming from {} completed units in {}", completed.len(), path.display());
+
+                    // Drop a line cut short, so the next unit starts on a line of its own
+                    if !content.is_empty() && !content.ends_with('\n') {
+                        let complete = content.rfind('\n').map_or(0, |end| end + 1);
+                        OpenOptions::new().write(true).open(&path)
+                            .and_then(|file| file.set_len(complete as u64))
+                            .map_err(RustAiToolError::Io)?;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => warn!("No checkpoint to resume at {}; starting over", path.display()),
+                Err(e) => return Err(RustAiToolError::Io(e)),
+            }
+        } else if path.exists() {
+            fs::remove_file(&path).map_err(RustAiToolError::Io)?;
+        }
+
+        Ok(Self {
+            path,
+            completed,
+            file: Mutex::new(None),
+        })
+    }
+
+    /// Path to the checkpoint file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of units the interrupted run completed
+    pub fn resumed(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Output of a unit the interrupted run completed
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - Stage of the unit
+    /// * `key` - Key of the unit, from [`unit_key`]
+    ///
+    /// # Returns
+    ///
+    /// The output, or `None` if the unit has to be done
+    pub fn completed<T: DeserializeOwned>(&self, stage: &str, key: &str) -> Option<T> {
+        let value = self.completed.get(&(stage.to_string(), key.to_string()))?;
+
+        match serde_json::from_value(value.clone()) {
+            Ok(output) => Some(output),
+            Err(e) => {
+                warn!("Ignoring the checkpointed {} unit {}: {}", stage, key, e);
+                None
+            }
+        }
+    }
+
+    /// Record a completed unit, on disk before returning
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - Stage of the unit
+    /// * `key` - Key of the unit, from [`unit_key`]
+    /// * `output` - Output of the unit
+    pub fn record<T: Serialize>(&self, stage: &str, key: &str, output: &T) -> Result<()> {
+        let entry = CheckpointEntry {
+            stage: stage.to_string(),
+            key: key.to_string(),
+            value: serde_json::to_value(output)?,
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = self.file.lock().map_err(|_| RustAiToolError::Other("Checkpoint lock poisoned".to_string()))?;
+        if file.is_none() {
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent).map_err(RustAiToolError::Io)?;
+            }
+            *file = Some(OpenOptions::new().create(true).append(true).open(&self.path).map_err(RustAiToolError::Io)?);
+        }
+
+        if let Some(file) = file.as_mut() {
+            file.write_all(line.as_bytes()).map_err(RustAiToolError::Io)?;
+            file.sync_data().map_err(RustAiToolError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the checkpoint of a run that completed
+    pub fn finish(&self) -> Result<()> {
+        if let Ok(mut file) = self.file.lock() {
+            *file = None;
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(RustAiToolError::Io(e)),
+        }
+    }
+}
+
+/// Key of a unit of work, a hash of everything its output depends on
+pub fn unit_key(inputs: &[&str]) -> String {
+    content_hash(&inputs.join("\0"))
+}
+
+/// Key of the analysis of a project, a hash of its Rust files, its manifests and the analysis settings
+///
+/// # Arguments
+///
+/// * `project_path` - Project root
+/// * `settings` - Description of the settings the analysis depends on
+///
+/// # Returns
+///
+/// The key, which changes whenever a file or the settings change
+pub fn project_key(project_path: &Path, settings: &str) -> Result<String> {
+    let mut files = collect_rust_files(project_path)?;
+    files.extend(["Cargo.toml", "Cargo.lock"].iter().map(|name| project_path.join(name)).filter(|path| path.is_file()));
+    files.sort();
+
+    let mut inputs = vec![settings.to_string()];
+    for file in files {
+        let content = fs::read_to_string(&file).map_err(RustAiToolError::Io)?;
+        inputs.push(file.to_string_lossy().into_owned());
+        inputs.push(content);
+    }
+
+    let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+    Ok(unit_key(&inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resume_checkpoint() {
+        let dir = tempdir().unwrap();
+        let key = unit_key(&["src/lib.rs", "fn main() {}"]);
+
+        let checkpoint = Checkpoint::open(dir.path(), "fix", false).unwrap();
+        checkpoint.record(FIX_STAGE, &key, &vec!["fixed".to_string()]).unwrap();
+        assert!(checkpoint.completed::<Vec<String>>(FIX_STAGE, &key).is_none());
+
+        // The run is interrupted while writing its next unit
+        let mut file = OpenOptions::new().append(true).open(checkpoint.path()).unwrap();
+        file.write_all(b"{\"stage\":\"fix\",\"key\":\"cut").unwrap();
+        drop(checkpoint);
+
+        let resumed = Checkpoint::open(dir.path(), "fix", true).unwrap();
+        assert_eq!(resumed.resumed(), 1);
+
+        // A unit recorded after resuming is not lost with the cut line
+        let next_key = unit_key(&["src/main.rs", "fn main() {}"]);
+        resumed.record(FIX_STAGE, &next_key, &vec!["also fixed".to_string()]).unwrap();
+        drop(resumed);
+        let resumed = Checkpoint::open(dir.path(), "fix", true).unwrap();
+        assert_eq!(resumed.resumed(), 2);
+        assert_eq!(resumed.completed::<Vec<String>>(FIX_STAGE, &next_key).unwrap(), ["also fixed"]);
+        assert_eq!(resumed.completed::<Vec<String>>(FIX_STAGE, &key).unwrap(), ["fixed"]);
+        assert!(resumed.completed::<Vec<String>>(VALIDATION_STAGE, &key).is_none());
+        assert_ne!(key, unit_key(&["src/lib.rs", "fn main() { }"]));
+
+        let path = resumed.path().to_path_buf();
+        resumed.finish().unwrap();
+        assert!(!path.exists());
+
+        let restarted = Checkpoint::open(dir.path(), "fix", true).unwrap();
+        assert_eq!(restarted.resumed(), 0);
+    }
+}
@@ -0,0 +1,265 @@
+//! Item chunking module
+//!
+//! This module lets AI requests work on single items of large files:
+//! - Locating the items (functions, types, impl blocks or their methods) of a file
+//! - Building the minimal context of an item: imports and the type definitions it uses
+//! - Splicing a rewritten item back into its file
+//! - Splitting code into budget-sized chunks at item boundaries
+
+use crate::index::item_name;
+use crate::models::tokenizer::{split_to_budget, Tokenizer};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use std::collections::HashSet;
+
+/// Impl blocks longer than this are split into their methods
+const MAX_ITEM_LINES: usize = 150;
+
+/// An item of a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemSpan {
+    /// Name of the item (e.g. `fn parse`, `impl Config`)
+    pub name: String,
+
+    /// First line, including attributes and doc comments (1-based)
+    pub start_line: usize,
+
+    /// Last line (1-based, inclusive)
+    pub end_line: usize,
+
+    /// Header of the impl block containing the item, if it is a method
+    pub parent: Option<String>,
+}
+
+impl ItemSpan {
+    /// Whether the item covers the given lines
+    pub fn contains(&self, line_start: usize, line_end: usize) -> bool {
+        self.start_line <= line_start && line_end <= self.end_line
+    }
+}
+
+/// Find the items of a source file
+///
+/// Imports are not items; impl blocks longer than `MAX_ITEM_LINES` are
+/// replaced by their methods.
+///
+/// # Arguments
+///
+/// * `source` - Source of the file
+///
+/// # Returns
+///
+/// The items in source order, or `None` if the file does not parse
+pub fn item_spans(source: &str) -> Option<Vec<ItemSpan>> {
+    let file = syn::parse_file(source).ok()?;
+    let mut spans = Vec::new();
+
+    for item in &file.items {
+        let start_line = item.span().start().line;
+        let end_line = item.span().end().line;
+
+        match item {
+            syn::Item::Use(_) | syn::Item::ExternCrate(_) => {}
+            syn::Item::Impl(item_impl) if end_line - start_line + 1 > MAX_ITEM_LINES && !item_impl.items.is_empty() => {
+                let parent = item_name(item, "");
+
+                for impl_item in &item_impl.items {
+                    let name = match impl_item {
+                        syn::ImplItem::Fn(f) => format!("fn {}", f.sig.ident),
+                        syn::ImplItem::Const(c) => format!("const {}", c.ident),
+                        syn::ImplItem::Type(t) => format!("type {}", t.ident),
+                        other => other.to_token_stream().to_string(),
+                    };
+
+                    spans.push(ItemSpan {
+                        name,
+                        start_line: impl_item.span().start().line,
+                        end_line: impl_item.span().end().line,
+                        parent: Some(parent.clone()),
+                    });
+                }
+            }
+            _ => spans.push(ItemSpan {
+                name: item_name(item, ""),
+                start_line,
+                end_line,
+                parent: None,
+            }),
+        }
+    }
+
+    Some(spans)
+}
+
+/// Build the context an item needs to be understood on its own
+///
+/// The context holds the file's imports and the type, trait and constant
+/// definitions whose names appear in the item.
+///
+/// # Arguments
+///
+/// * `source` - Source of the file
+/// * `span` - The item
+///
+/// # Returns
+///
+/// The context, in source order
+pub fn item_context(source: &str, span: &ItemSpan) -> String {
+    let Ok(file) = syn::parse_file(source) else {
+        return String::new();
+    };
+
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let item_code = lines[span.start_line - 1..span.end_line.min(lines.len())].concat();
+
+    let identifier_regex = regex::Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").unwrap();
+    let identifiers: HashSet<&str> = identifier_regex.find_iter(&item_code).map(|m| m.as_str()).collect();
+
+    let mut context = String::new();
+
+    for item in &file.items {
+        let start_line = item.span().start().line;
+        let end_line = item.span().end().line;
+
+        // The item itself, or the impl block it belongs to
+        if start_line <= span.start_line && span.end_line <= end_line {
+            continue;
+        }
+
+        let ident = match item {
+            syn::Item::Use(_) | syn::Item::ExternCrate(_) => None,
+            syn::Item::Struct(i) => Some(&i.ident),
+            syn::Item::Enum(i) => Some(&i.ident),
+            syn::Item::Union(i) => Some(&i.ident),
+            syn::Item::Type(i) => Some(&i.ident),
+            syn::Item::Trait(i) => Some(&i.ident),
+            syn::Item::Const(i) => Some(&i.ident),
+            syn::Item::Static(i) => Some(&i.ident),
+            _ => continue,
+        };
+
+        if ident.is_none_or(|ident| identifiers.contains(ident.to_string().as_str())) {
+            context.push_str(&lines[start_line - 1..end_line.min(lines.len())].concat());
+        }
+    }
+
+    if let Some(parent) = &span.parent {
+        context.push_str(&format!("// The item is defined inside `{}`\n", parent));
+    }
+
+    context
+}
+
+/// Replace a range of lines
+///
+/// # Arguments
+///
+/// * `source` - Source of the file
+/// * `start_line` - First replaced line (1-based)
+/// * `end_line` - Last replaced line (1-based, inclusive)
+/// * `replacement` - New content of the lines
+///
+/// # Returns
+///
+/// The source with the lines replaced
+pub fn replace_lines(source: &str, start_line: usize, end_line: usize, replacement: &str) -> String {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let end_line = end_line.min(lines.len());
+
+    let mut result = lines[..start_line - 1].concat();
+    result.push_str(replacement);
+    if !replacement.ends_with('\n') && end_line < lines.len() {
+        result.push('\n');
+    }
+    result.push_str(&lines[end_line..].concat());
+
+    result
+}
+
+/// Split code into chunks of at most `budget` tokens, cutting between items
+///
+/// Items are kept whole where possible; an item larger than the budget, or
+/// code that does not parse, is split on line boundaries.
+///
+/// # Arguments
+///
+/// * `code` - Code to split
+/// * `budget` - Maximum tokens per chunk
+/// * `tokenizer` - Tokenizer to count with
+///
+/// # Returns
+///
+/// The chunks, in order
+pub fn split_at_items(code: &str, budget: usize, tokenizer: &dyn Tokenizer) -> Vec<String> {
+    let Some(spans) = item_spans(code) else {
+        return split_to_budget(code, budget, tokenizer);
+    };
+
+    let lines: Vec<&str> = code.split_inclusive('\n').collect();
+
+    // Segments run from the start of one item to the start of the next
+    let mut cuts: Vec<usize> = spans.iter().map(|span| span.start_line - 1).filter(|&cut| cut > 0).collect();
+    cuts.dedup();
+    cuts.push(lines.len());
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut segment_start = 0;
+
+    for cut in cuts {
+        if cut <= segment_start {
+            continue;
+        }
+
+        let segment = lines[segment_start..cut].concat();
+        segment_start = cut;
+
+        if tokenizer.count_tokens(&current) + tokenizer.count_tokens(&segment) <= budget {
+            current.push_str(&segment);
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        if tokenizer.count_tokens(&segment) <= budget {
+            current = segment;
+        } else {
+            chunks.extend(split_to_budget(&segment, budget, tokenizer));
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tokenizer::BpeEstimator;
+
+    #[test]
+    fn test_item_spans_and_context() {
+        let source = "use std::fmt;\n\nstruct Config {\n    name: String,\n}\n\nstruct Unused;\n\n/// Load\nfn load() -> Config {\n    todo!()\n}\n\nfn other() {}\n";
+
+        let spans = item_spans(source).unwrap();
+        let names: Vec<&str> = spans.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["struct Config", "struct Unused", "fn load", "fn other"]);
+        assert_eq!((spans[2].start_line, spans[2].end_line), (9, 12));
+
+        let context = item_context(source, &spans[2]);
+        assert_eq!(context, "use std::fmt;\nstruct Config {\n    name: String,\n}\n");
+
+        let replaced = replace_lines(source, 9, 12, "fn load() -> Config {\n    Config::default()\n}");
+        assert!(replaced.contains("fn load() -> Config {\n    Config::default()\n}\n\nfn other() {}\n"));
+
+        let chunks = split_at_items(source, 30, &BpeEstimator);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), source);
+        assert!(chunks.iter().skip(1).all(|c| c.starts_with("struct") || c.starts_with("///") || c.starts_with("fn")));
+    }
+}
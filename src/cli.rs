@@ -1,6 +1,11 @@
 use crate::{Result, RustAiToolError};
+use crate::modification::{
+    apply_hunk_decisions, create_modification, match_file_format, split_into_hunks, CodeModification, Hunk, HunkDecision,
+};
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
 use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
 use tokio::fs;
 
 pub async fn execute_command(command: &str, args: &[&str]) -> Result<String> {
@@ -191,7 +196,7 @@ async fn validate_fixes(project_path: &str, fixes_path: &str) -> Result<String>
     let fixes: Vec<crate::validation::FixToValidate> = serde_json::from_str(&fixes_content)
         .map_err(|e| RustAiToolError::Json(e))?;
     
-    let validation_results = crate::validation::validate_fixes(&fixes, &config.validation_options)?;
+    let validation_results = crate::validation::validate_project_fixes(Path::new(project_path), &fixes, &config.validation_options)?;
     
     let output = format_validation_results(&validation_results);
     
@@ -233,15 +238,12 @@ async fn apply_fixes(project_path: &str, fixes_path: &str, create_backup: bool)
     info!("Applying fixes to project at {} using {} (backup={})", 
           project_path, fixes_path, create_backup);
     
-    let fixes_content = fs::read_to_string(fixes_path)
-        .await
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    let modifications: Vec<crate::modification::CodeModification> = serde_json::from_str(&fixes_content)
-        .map_err(|e| RustAiToolError::Json(e))?;
+    let modifications = crate::modification::load_modifications(Path::new(fixes_path), Path::new(project_path))?;
     
     let changes = crate::modification::apply_modifications(&modifications, create_backup)?;
     
+    crate::journal::Journal::open(Path::new(project_path)).record(&changes)?;
+    
     let report = crate::modification::create_change_report(&changes);
     
     Ok(report)
@@ -251,12 +253,14 @@ async fn generate_project(description: &str, output_dir: &str, name: &str) -> Re
     info!("Generating project '{}' at {} from description", name, output_dir);
     
     let config = load_default_config().await?;
+    config.ensure_online("generate")?;
     
     let project_path = crate::project_generator::generate_project_from_description(
         description,
         Path::new(output_dir),
         name,
         &config.ai_model,
+        &crate::project_generator::TemplateOptions::default(),
     ).await?;
     
     Ok(format!("Project generated successfully at {}", project_path.display()))
@@ -266,6 +270,7 @@ async fn github_clone(owner: &str, repo: &str, target_dir: &str) -> Result<Strin
     info!("Cloning GitHub repository {}/{}", owner, repo);
     
     let config = load_default_config().await?;
+    config.ensure_online("github clone")?;
     
     let github_config = config.github_repo.ok_or_else(|| {
         RustAiToolError::GitHub("GitHub configuration not found in config file".to_string())
@@ -275,7 +280,8 @@ async fn github_clone(owner: &str, repo: &str, target_dir: &str) -> Result<Strin
         &github_config.access_token,
         owner,
         repo,
-    )?;
+    )?
+    .with_ssh(config.ssh.clone());
     
     let repo_path = client.clone_repo(None, Path::new(target_dir)).await?;
     
@@ -293,6 +299,7 @@ async fn github_create_pr(
           owner, repo, branch, title);
     
     let config = load_default_config().await?;
+    config.ensure_online("github create-pr")?;
     
     let github_config = config.github_repo.ok_or_else(|| {
         RustAiToolError::GitHub("GitHub configuration not found in config file".to_string())
@@ -302,7 +309,8 @@ async fn github_create_pr(
         &github_config.access_token,
         owner,
         repo,
-    )?;
+    )?
+    .with_ssh(config.ssh.clone());
     
     let repo_info = client.get_repo_info().await?;
     
@@ -344,7 +352,7 @@ async fn github_create_pr(
                 
                 let target = repo_path.join(file_name);
                 
-                fs::write(&target, &modification.modified_content)
+                fs::write(&target, match_file_format(&modification.original_content, &modification.modified_content))
                     .await
                     .map_err(|e| RustAiToolError::Io(e))?;
                 
@@ -358,7 +366,7 @@ async fn github_create_pr(
                         .map_err(|e| RustAiToolError::Io(e))?;
                 }
                 
-                fs::write(&target, &modification.modified_content)
+                fs::write(&target, match_file_format(&modification.original_content, &modification.modified_content))
                     .await
                     .map_err(|e| RustAiToolError::Io(e))?;
                 
@@ -368,12 +376,16 @@ async fn github_create_pr(
             files_to_commit.push(target_path);
         }
         
-        client.commit_changes(
+        let fallback_pr = client.commit_changes(
             &repo_path,
             &files_to_commit,
             &format!("Applied fixes: {}", title),
             branch,
         ).await?;
+        
+        if let Some(pr) = fallback_pr {
+            return Ok(format!("Branch {} is protected, pull request created: {}", branch, pr.url));
+        }
     }
     
     let pr = client.create_pull_request(
@@ -381,6 +393,7 @@ async fn github_create_pr(
         &format!("Automated fixes by Rust AI Tool\n\nApplied fixes"),
         branch,
         &repo_info.default_branch,
+        &crate::github::PullRequestOptions::default(),
     ).await?;
     
     Ok(format!("Pull request created: {}", pr.url))
@@ -440,23 +453,412 @@ async fn load_default_config() -> Result<crate::Config> {
 fn create_default_config() -> crate::Config {
     crate::Config {
         project_path: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        version: crate::config_migration::CONFIG_VERSION,
         github_repo: None,
+        gitea_repo: None,
+        bitbucket_repo: None,
+        jira: None,
+        linear: None,
+        ssh: None,
         ai_model: crate::AiModelConfig {
             model_type: crate::AiModelType::Claude,
             api_key: String::new(),
             api_base_url: None,
+            retry: Default::default(),
+            cache: Default::default(),
+            context_window: None,
+            prompt_overflow: Default::default(),
+            pricing: Default::default(),
+            embedding_model: None,
+            tools: Default::default(),
+            redaction: Default::default(),
         },
+        ai_models: Vec::new(),
+        model: None,
+        task_models: Default::default(),
+        lints: Default::default(),
+        policy: None,
         analysis_options: crate::AnalysisOptions {
             run_clippy: true,
             use_rust_analyzer: true,
             custom_rules: Vec::new(),
+            analyzers: Vec::new(),
+            filter: Default::default(),
+            lints: Default::default(),
+            policy: None,
         },
         validation_options: crate::ValidationOptions {
             syntax_only: false,
             tauri_compatibility: true,
             security_validation: true,
+            protected_paths: Vec::new(),
         },
+        offline: false,
+        network: Default::default(),
+        telemetry: Default::default(),
+        execution: Default::default(),
+        notifications: Vec::new(),
+        dependency_presets: Default::default(),
+    }
+}
+
+/// AI providers offered by the interactive init
+const AI_PROVIDERS: &[&str] = &["claude", "gpt", "mistral", "local", "openai-compatible"];
+
+/// Validation strictness levels offered by the interactive init
+const VALIDATION_LEVELS: &[&str] = &["syntax", "standard", "strict"];
+
+/// Ask for the settings of a project, starting from a configuration
+///
+/// Asks for the AI provider and model, the GitHub repository and how strictly
+/// fixes are validated. Empty answers keep the suggested value, and the end of
+/// the input keeps all remaining ones.
+///
+/// # Arguments
+///
+/// * `input` - Where the answers are read from
+/// * `output` - Where the questions are written to
+/// * `config` - Configuration providing everything that is not asked for
+///
+/// # Returns
+///
+/// The configuration with the answers applied
+pub fn interactive_config(input: &mut impl BufRead, output: &mut impl Write, mut config: crate::Config) -> Result<crate::Config> {
+    let provider = choose(input, output, "AI provider", AI_PROVIDERS, "claude")?;
+    
+    let ai_model = &mut config.ai_model;
+    ai_model.model_type = match provider.as_str() {
+        "gpt" => crate::AiModelType::Gpt,
+        "mistral" => crate::AiModelType::Mistral,
+        "local" => crate::AiModelType::Local(ask(input, output, "Model served by Ollama", "llama3")?),
+        "openai-compatible" => crate::AiModelType::OpenAiCompatible(ask(input, output, "Model name", "")?),
+        _ => crate::AiModelType::Claude,
+    };
+    
+    match ai_model.model_type {
+        crate::AiModelType::OpenAiCompatible(_) => {
+            let base_url = ask(input, output, "API base URL (e.g. http://localhost:8080/v1)", "")?;
+            ai_model.api_base_url = Some(base_url).filter(|url| !url.is_empty());
+            ai_model.api_key = ask(input, output, "API key (empty if the server needs none)", "")?;
+        }
+        crate::AiModelType::Local(_) => {}
+        _ => ai_model.api_key = ask(input, output, "API key (empty to add it later)", "")?,
+    }
+    
+    let repository = ask(input, output, "GitHub repository as owner/name (empty for none)", "")?;
+    config.github_repo = match repository.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() => Some(crate::GitHubRepo {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            access_token: ask(input, output, "GitHub access token (empty to add it later)", "")?,
+            webhook_secret: None,
+        }),
+        _ => {
+            if !repository.is_empty() {
+                writeln!(output, "Skipping GitHub: expected owner/name, got {}", repository).map_err(RustAiToolError::Io)?;
+            }
+            None
+        }
+    };
+    
+    writeln!(output, "Validation: syntax only checks that fixes parse, standard also checks semantics and structure, strict adds security and Tauri checks")
+        .map_err(RustAiToolError::Io)?;
+    let level = choose(input, output, "Validation strictness", VALIDATION_LEVELS, "strict")?;
+    config.validation_options = crate::ValidationOptions {
+        syntax_only: level == "syntax",
+        tauri_compatibility: level == "strict",
+        security_validation: level == "strict",
+        protected_paths: std::mem::take(&mut config.validation_options.protected_paths),
+    };
+    
+    Ok(config)
+}
+
+/// Ask a question, returning the suggested value for an empty answer or the end of the input
+fn ask(input: &mut impl BufRead, output: &mut impl Write, question: &str, suggested: &str) -> Result<String> {
+    if suggested.is_empty() {
+        write!(output, "{}: ", question)
+    } else {
+        write!(output, "{} [{}]: ", question, suggested)
+    }
+    .and_then(|_| output.flush())
+    .map_err(RustAiToolError::Io)?;
+    
+    let mut answer = String::new();
+    input.read_line(&mut answer).map_err(RustAiToolError::Io)?;
+    
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { suggested } else { answer }.to_string())
+}
+
+/// Ask until one of the choices is answered (case-insensitive)
+fn choose(input: &mut impl BufRead, output: &mut impl Write, question: &str, choices: &[&str], suggested: &str) -> Result<String> {
+    let question = format!("{} ({})", question, choices.join(", "));
+    
+    loop {
+        let answer = ask(input, output, &question, suggested)?.to_lowercase();
+        if choices.contains(&answer.as_str()) {
+            return Ok(answer);
+        }
+        
+        writeln!(output, "Choose one of: {}", choices.join(", ")).map_err(RustAiToolError::Io)?;
+    }
+}
+
+/// Decision recorded for a single hunk during an interactive review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkReview {
+    /// File the hunk belongs to
+    pub file_path: PathBuf,
+    
+    /// Unified diff header of the hunk
+    pub header: String,
+    
+    /// Decision taken by the user
+    pub decision: HunkDecision,
+}
+
+/// Outcome of an interactive review of modifications
+#[derive(Debug, Default)]
+pub struct ReviewOutcome {
+    /// Modifications containing the accepted and edited hunks
+    pub accepted: Vec<CodeModification>,
+    
+    /// Modifications containing the skipped hunks, based on the accepted content
+    pub skipped: Vec<CodeModification>,
+    
+    /// Decision recorded for every hunk
+    pub decisions: Vec<HunkReview>,
+}
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_CYAN: &str = "\x1b[36m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Review modifications hunk by hunk, asking the user what to apply
+///
+/// Works like `git add -p`: every hunk is shown as a colored diff and can be
+/// accepted, skipped or edited before applying.
+///
+/// # Arguments
+///
+/// * `modifications` - Modifications to review
+///
+/// # Returns
+///
+/// The accepted and skipped parts of the modifications, plus every decision taken
+pub fn review_modifications(modifications: &[CodeModification]) -> Result<ReviewOutcome> {
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut outcome = ReviewOutcome::default();
+    let mut quit = false;
+    
+    for modification in modifications {
+        let original = &modification.original_content;
+        let hunks = split_into_hunks(original, &modification.modified_content, 3);
+        if hunks.is_empty() {
+            continue;
+        }
+        
+        if !quit {
+            println!(
+                "\n{}{}{} - {} (confidence {}%)",
+                ANSI_BOLD,
+                modification.file_path.display(),
+                ANSI_RESET,
+                modification.description,
+                modification.confidence
+            );
+        }
+        
+        // Decision applied to all remaining hunks of the file (set by a/d/q)
+        let mut remaining = if quit { Some(HunkDecision::Skip) } else { None };
+        let mut decisions = Vec::with_capacity(hunks.len());
+        
+        for (i, hunk) in hunks.iter().enumerate() {
+            let decision = if let Some(decision) = remaining.clone() {
+                decision
+            } else {
+                loop {
+                    print_hunk(hunk);
+                    print!("({}/{}) Apply this hunk [y,n,e,a,d,q,?]? ", i + 1, hunks.len());
+                    std::io::stdout().flush().map_err(RustAiToolError::Io)?;
+                    
+                    let mut answer = String::new();
+                    if input.read_line(&mut answer).map_err(RustAiToolError::Io)? == 0 {
+                        // End of input: skip everything that is left
+                        quit = true;
+                        remaining = Some(HunkDecision::Skip);
+                        break HunkDecision::Skip;
+                    }
+                    
+                    match answer.trim() {
+                        "y" => break HunkDecision::Accept,
+                        "n" => break HunkDecision::Skip,
+                        "e" => {
+                            if let Some(text) = edit_hunk(hunk)? {
+                                break HunkDecision::Edit(text);
+                            }
+                        }
+                        "a" => {
+                            remaining = Some(HunkDecision::Accept);
+                            break HunkDecision::Accept;
+                        }
+                        "d" => {
+                            remaining = Some(HunkDecision::Skip);
+                            break HunkDecision::Skip;
+                        }
+                        "q" => {
+                            quit = true;
+                            remaining = Some(HunkDecision::Skip);
+                            break HunkDecision::Skip;
+                        }
+                        _ => print_review_help(),
+                    }
+                }
+            };
+            
+            outcome.decisions.push(HunkReview {
+                file_path: modification.file_path.clone(),
+                header: hunk.header.clone(),
+                decision: decision.clone(),
+            });
+            decisions.push(decision);
+        }
+        
+        let applied = apply_hunk_decisions(original, &hunks, &decisions);
+        
+        // The skipped hunks are exported relative to what actually gets applied
+        let with_skipped: Vec<HunkDecision> = decisions.iter()
+            .map(|d| match d {
+                HunkDecision::Skip => HunkDecision::Accept,
+                other => other.clone(),
+            })
+            .collect();
+        let skipped = apply_hunk_decisions(original, &hunks, &with_skipped);
+        
+        if applied != *original {
+            outcome.accepted.push(create_modification(
+                modification.file_path.clone(),
+                original.clone(),
+                applied.clone(),
+                modification.description.clone(),
+                modification.confidence,
+            ));
+        }
+        
+        if skipped != applied {
+            outcome.skipped.push(create_modification(
+                modification.file_path.clone(),
+                applied,
+                skipped,
+                modification.description.clone(),
+                modification.confidence,
+            ));
+        }
     }
+    
+    Ok(outcome)
+}
+
+fn print_hunk(hunk: &Hunk) {
+    println!("{}{}{}", ANSI_CYAN, hunk.header, ANSI_RESET);
+    
+    for line in &hunk.diff_lines {
+        let color = match line.chars().next() {
+            Some('-') => ANSI_RED,
+            Some('+') => ANSI_GREEN,
+            _ => "",
+        };
+        
+        if color.is_empty() {
+            println!("{}", line);
+        } else {
+            println!("{}{}{}", color, line, ANSI_RESET);
+        }
+    }
+}
+
+/// Color a unified diff for a terminal, like `git diff`
+///
+/// # Arguments
+///
+/// * `diff` - Unified diff
+///
+/// # Returns
+///
+/// The diff with file headers in bold, hunk headers in cyan, removed lines in
+/// red and added lines in green
+pub fn colorize_diff(diff: &str) -> String {
+    let mut colored = String::with_capacity(diff.len());
+    
+    for line in diff.split_inclusive('\n') {
+        let text = line.trim_end_matches('\n');
+        let color = if text.starts_with("diff ") || text.starts_with("--- ") || text.starts_with("+++ ") {
+            ANSI_BOLD
+        } else if text.starts_with("@@") {
+            ANSI_CYAN
+        } else if text.starts_with('-') {
+            ANSI_RED
+        } else if text.starts_with('+') {
+            ANSI_GREEN
+        } else {
+            ""
+        };
+        
+        if color.is_empty() {
+            colored.push_str(line);
+        } else {
+            colored.push_str(color);
+            colored.push_str(text);
+            colored.push_str(ANSI_RESET);
+            colored.push_str(&line[text.len()..]);
+        }
+    }
+    
+    colored
+}
+
+fn print_review_help() {
+    println!("y - apply this hunk");
+    println!("n - do not apply this hunk");
+    println!("e - edit the proposed text of this hunk before applying it");
+    println!("a - apply this hunk and all remaining hunks in the file");
+    println!("d - do not apply this hunk or any remaining hunks in the file");
+    println!("q - quit; do not apply this hunk or any remaining hunks");
+    println!("? - print help");
+}
+
+/// Open the proposed text of a hunk in the user's editor
+///
+/// Returns `None` if the editor exited with an error.
+fn edit_hunk(hunk: &Hunk) -> Result<Option<String>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    
+    let edit_path = std::env::temp_dir().join(format!("rust-ai-tool-hunk-{}.rs", std::process::id()));
+    std::fs::write(&edit_path, &hunk.new_text).map_err(RustAiToolError::Io)?;
+    
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&edit_path)
+        .status()
+        .map_err(RustAiToolError::Io)?;
+    
+    let edited = std::fs::read_to_string(&edit_path);
+    std::fs::remove_file(&edit_path).ok();
+    
+    if !status.success() {
+        warn!("Editor exited with {}; the hunk was left undecided", status);
+        return Ok(None);
+    }
+    
+    Ok(Some(edited.map_err(RustAiToolError::Io)?))
 }
 
 pub fn create_terminal_ui() -> Result<()> {
@@ -464,7 +866,7 @@ pub fn create_terminal_ui() -> Result<()> {
 }
 
 pub fn create_progress_display(operation: &str, total: u64) -> Result<ProgressHandler> {
-    println!("Starting {}...", operation);
+    info!("Starting {}...", operation);
     
     Ok(ProgressHandler {
         operation: operation.to_string(),
@@ -489,7 +891,7 @@ impl ProgressHandler {
             0
         };
         
-        println!("{}: {}% ({}/{})", self.operation, percentage, self.current, self.total);
+        info!("{}: {}% ({}/{})", self.operation, percentage, self.current, self.total);
     }
     
     pub fn increment(&mut self) {
@@ -498,7 +900,7 @@ impl ProgressHandler {
     
     pub fn complete(&mut self) {
         self.update(self.total);
-        println!("{} completed.", self.operation);
+        info!("{} completed.", self.operation);
     }
 }
 
@@ -520,4 +922,23 @@ mod tests {
         // Clean up
         fs::remove_file(config_path).await.ok();
     }
+    
+    #[test]
+    fn test_interactive_config() {
+        let answers = "gemini\nopenai-compatible\nqwen2.5-coder\nhttp://localhost:8080/v1\n\nacme/widgets\ntoken\nSyntax\n";
+        let mut output = Vec::new();
+        
+        let config = interactive_config(&mut answers.as_bytes(), &mut output, create_default_config()).unwrap();
+        assert_eq!(config.ai_model.model_type, crate::AiModelType::OpenAiCompatible("qwen2.5-coder".to_string()));
+        assert_eq!(config.ai_model.api_base_url.as_deref(), Some("http://localhost:8080/v1"));
+        assert_eq!(config.github_repo.as_ref().map(|repo| repo.name.as_str()), Some("widgets"));
+        assert!(config.validation_options.syntax_only && !config.validation_options.security_validation);
+        assert!(String::from_utf8(output).unwrap().contains("Choose one of: claude, gpt"));
+        
+        // Without answers, the suggested values are kept
+        let config = interactive_config(&mut "".as_bytes(), &mut Vec::new(), create_default_config()).unwrap();
+        assert_eq!(config.ai_model.model_type, crate::AiModelType::Claude);
+        assert!(config.github_repo.is_none());
+        assert!(config.validation_options.security_validation);
+    }
 }
\ No newline at end of file
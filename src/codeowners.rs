@@ -0,0 +1,286 @@
+//! Code owners module
+//!
+//! This module reads the CODEOWNERS file of a checkout:
+//! - Looked up in `.github/`, the repository root and `docs/`, like GitHub does
+//! - Patterns matched with gitignore semantics, the last matching rule winning
+//! - Owners of a set of changed paths, for requesting their review
+//! - Owners of analysis issues, found from the CODEOWNERS file of the repository
+//!   containing the project, so reports and GitHub issues can be routed to them
+
+use crate::analysis::AnalysisResult;
+use crate::{Result, RustAiToolError};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, warn};
+
+/// Locations of the CODEOWNERS file, in the order GitHub looks them up
+const CODEOWNERS_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Rules of a CODEOWNERS file
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    /// Path patterns and their owners, in file order
+    rules: Vec<(Regex, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Read the CODEOWNERS file of a checkout
+    ///
+    /// # Arguments
+    ///
+    /// * `repo_path` - Path to the checkout
+    ///
+    /// # Returns
+    ///
+    /// The rules, or `None` if the repository has no CODEOWNERS file
+    pub fn load(repo_path: &Path) -> Result<Option<Self>> {
+        for location in CODEOWNERS_LOCATIONS {
+            let path = repo_path.join(location);
+            if path.is_file() {
+                debug!("Reading code owners from {}", path.display());
+                let content = fs::read_to_string(&path).map_err(RustAiToolError::Io)?;
+                return Ok(Some(Self::parse(&content)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the CODEOWNERS file of the repository a project is in
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project, the repository root or a directory below it
+    ///
+    /// # Returns
+    ///
+    /// The repository root and its rules, or `None` if no CODEOWNERS file is found
+    /// up to the repository root
+    pub fn find(project_path: &Path) -> Result<Option<(PathBuf, Self)>> {
+        let project_path = fs::canonicalize(project_path).map_err(RustAiToolError::Io)?;
+
+        for dir in project_path.ancestors() {
+            if let Some(owners) = Self::load(dir)? {
+                return Ok(Some((dir.to_path_buf(), owners)));
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Parse the content of a CODEOWNERS file, skipping invalid patterns
+    pub fn parse(content: &str) -> Self {
+        let rules = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                let owners = fields.take_while(|field| !field.starts_with('#')).map(String::from).collect();
+
+                match pattern_regex(pattern) {
+                    Ok(regex) => Some((regex, owners)),
+                    Err(e) => {
+                        warn!("Skipping CODEOWNERS pattern {}: {}", pattern, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        CodeOwners { rules }
+    }
+
+    /// Owners of a repository-relative path (with forward slashes)
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        self.rules.iter()
+            .rev()
+            .find(|(regex, _)| regex.is_match(path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    /// Owners of any of the paths, without duplicates
+    pub fn owners_of_all<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut owners: Vec<String> = Vec::new();
+
+        for path in paths {
+            for owner in self.owners_of(path) {
+                if !owners.contains(owner) {
+                    owners.push(owner.clone());
+                }
+            }
+        }
+
+        owners
+    }
+}
+
+/// Set the owners of the issues of analysis results
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the analyzed project
+/// * `results` - Analysis results, with file paths under the project
+///
+/// # Returns
+///
+/// Whether a CODEOWNERS file was found; without one the results are left unchanged
+pub fn assign_owners(project_path: &Path, results: &mut [AnalysisResult]) -> Result<bool> {
+    let Some((repo_root, owners)) = CodeOwners::find(project_path)? else {
+        return Ok(false);
+    };
+    let project_dir = fs::canonicalize(project_path).map_err(RustAiToolError::Io)?;
+
+    for issue in results.iter_mut().flat_map(|result| &mut result.issues) {
+        let path = repo_relative_path(&issue.file_path, project_path, &project_dir, &repo_root);
+        issue.owners = owners.owners_of(&path).to_vec();
+    }
+
+    Ok(true)
+}
+
+/// Code owners of files of a project, without duplicates
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project
+/// * `files` - Files under the project
+///
+/// # Returns
+///
+/// The owners, empty if no CODEOWNERS file is found
+pub fn owners_of_files<'a>(project_path: &Path, files: impl IntoIterator<Item = &'a Path>) -> Result<Vec<String>> {
+    let Some((repo_root, owners)) = CodeOwners::find(project_path)? else {
+        return Ok(Vec::new());
+    };
+    let project_dir = fs::canonicalize(project_path).map_err(RustAiToolError::Io)?;
+
+    let paths: Vec<String> = files.into_iter()
+        .map(|file| repo_relative_path(file, project_path, &project_dir, &repo_root))
+        .collect();
+    Ok(owners.owners_of_all(paths.iter().map(String::as_str)))
+}
+
+/// Path of a file of a project relative to the repository root, with forward slashes
+///
+/// File paths are under the project path as given, like the paths of an analysis.
+fn repo_relative_path(file_path: &Path, project_path: &Path, project_dir: &Path, repo_root: &Path) -> String {
+    let file_path = project_dir.join(file_path.strip_prefix(project_path).unwrap_or(file_path));
+    let path = file_path.strip_prefix(repo_root).unwrap_or(&file_path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// GitHub logins among code owners, leaving out teams and e-mail addresses
+pub fn user_logins(owners: &[String]) -> Vec<String> {
+    owners.iter()
+        .filter_map(|owner| owner.strip_prefix('@'))
+        .filter(|login| !login.contains('/'))
+        .map(String::from)
+        .collect()
+}
+
+/// Regex matching the paths a CODEOWNERS pattern covers
+pub(crate) fn pattern_regex(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    let directory_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+
+    // A slash anywhere but at the end anchors the pattern at the repository root
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut rest = pattern;
+
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("**/") {
+            regex.push_str("(?:.*/)?");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('*') {
+            regex.push_str("[^/]*");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('?') {
+            regex.push_str("[^/]");
+            rest = tail;
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            regex.push_str(&regex::escape(&c.to_string()));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    // A pattern matching a directory covers everything inside it
+    regex.push_str(if directory_only { "/.*$" } else { "(?:/.*)?$" });
+    Regex::new(&regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owners_of() {
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             *       @org/core\n\
+             *.toml  @release-manager # manifests\n\
+             /src/github.rs @alice\n\
+             docs/   @org/docs\n\
+             **/tests/** @bob\n",
+        );
+
+        assert_eq!(owners.owners_of("src/lib.rs"), ["@org/core"]);
+        assert_eq!(owners.owners_of("crates/a/Cargo.toml"), ["@release-manager"]);
+        assert_eq!(owners.owners_of("src/github.rs"), ["@alice"]);
+        assert_eq!(owners.owners_of("vendor/src/github.rs"), ["@org/core"]);
+        assert_eq!(owners.owners_of("docs/guide/setup.md"), ["@org/docs"]);
+        assert_eq!(owners.owners_of("crates/a/tests/it.rs"), ["@bob"]);
+        assert_eq!(
+            owners.owners_of_all(["src/lib.rs", "src/github.rs", "src/main.rs"]),
+            ["@org/core", "@alice"]
+        );
+        assert!(CodeOwners::default().owners_of("src/lib.rs").is_empty());
+        assert_eq!(user_logins(&["@alice".to_string(), "@org/core".to_string(), "dev@example.com".to_string()]), ["alice"]);
+    }
+
+    #[test]
+    fn test_assign_owners() {
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join(".git")).unwrap();
+        fs::create_dir_all(repo.path().join(".github")).unwrap();
+        fs::create_dir_all(repo.path().join("crates/app/src")).unwrap();
+        fs::write(repo.path().join(".github/CODEOWNERS"), "* @org/core\n/crates/app/src/ @alice\n").unwrap();
+
+        let project = repo.path().join("crates/app");
+        let issue = |file_path: PathBuf| crate::analysis::CodeIssue {
+            file_path,
+            line_start: 1,
+            column_start: 1,
+            line_end: 1,
+            column_end: 1,
+            category: crate::analysis::IssueCategory::Style,
+            severity: crate::Severity::Info,
+            message: String::new(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        };
+        let mut results = vec![AnalysisResult {
+            file_path: project.join("src/lib.rs"),
+            issues: vec![issue(project.join("src/lib.rs")), issue(project.join("Cargo.toml"))],
+            errors: Vec::new(),
+            success: true,
+        }];
+
+        assert!(assign_owners(&project, &mut results).unwrap());
+        let owners: Vec<&[String]> = results[0].issues.iter().map(|issue| issue.owners.as_slice()).collect();
+        assert_eq!(owners, [&["@alice".to_string()][..], &["@org/core".to_string()][..]]);
+    }
+}
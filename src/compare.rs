@@ -0,0 +1,249 @@
+//! Compare module
+//!
+//! This module compares two analysis results, like before and after a
+//! refactoring or the heads of two branches:
+//! - Issues are matched by file, category and message; among issues with the
+//!   same key, the ones on the same line are paired first, the rest in order,
+//!   so issues moved by edits elsewhere in the file still persist
+//! - Unmatched issues of the first result are fixed, of the second one new
+//! - Counts per category, for reports and trend dashboards
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::{Result, RustAiToolError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Issues of two analysis results, by what happened to them
+#[derive(Debug, Default, Serialize)]
+pub struct IssueComparison {
+    /// Issues of the first result that are gone in the second one
+    pub fixed: Vec<CodeIssue>,
+
+    /// Issues of the second result that were not in the first one
+    pub new: Vec<CodeIssue>,
+
+    /// Issues found in both results, as found in the second one
+    pub persisting: Vec<CodeIssue>,
+
+    /// Number of issues of each category
+    pub categories: BTreeMap<String, CategoryCounts>,
+}
+
+/// Number of fixed, new and persisting issues of a category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CategoryCounts {
+    /// Issues fixed
+    pub fixed: usize,
+
+    /// Issues introduced
+    pub new: usize,
+
+    /// Issues in both results
+    pub persisting: usize,
+}
+
+/// Read the results of an analysis written with `--output json`
+pub fn load_results(path: &Path) -> Result<Vec<AnalysisResult>> {
+    let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| RustAiToolError::Analysis(format!("Invalid analysis results {}: {}", path.display(), e)))
+}
+
+/// Compare the issues of two analysis results
+///
+/// # Arguments
+///
+/// * `before` - Results of the first analysis
+/// * `after` - Results of the second analysis
+///
+/// # Returns
+///
+/// The fixed, new and persisting issues, with their counts per category
+pub fn compare_results(before: &[AnalysisResult], after: &[AnalysisResult]) -> IssueComparison {
+    let mut after_groups = group_by_key(after);
+    let mut comparison = IssueComparison::default();
+
+    for (key, mut old_issues) in group_by_key(before) {
+        let mut new_issues = after_groups.remove(&key).unwrap_or_default();
+
+        // Issues that stayed on their line first, then the rest in file order
+        old_issues.retain(|old| {
+            match new_issues.iter().position(|new| new.line_start == old.line_start) {
+                Some(index) => {
+                    comparison.persisting.push(new_issues.remove(index));
+                    false
+                }
+                None => true,
+            }
+        });
+
+        let paired = old_issues.len().min(new_issues.len());
+        comparison.persisting.extend(new_issues.drain(..paired));
+        comparison.fixed.extend(old_issues.into_iter().skip(paired));
+        comparison.new.extend(new_issues);
+    }
+
+    comparison.new.extend(after_groups.into_values().flatten());
+
+    for issue in &comparison.fixed {
+        comparison.categories.entry(issue.category.name()).or_default().fixed += 1;
+    }
+    for issue in &comparison.new {
+        comparison.categories.entry(issue.category.name()).or_default().new += 1;
+    }
+    for issue in &comparison.persisting {
+        comparison.categories.entry(issue.category.name()).or_default().persisting += 1;
+    }
+
+    for issues in [&mut comparison.fixed, &mut comparison.new, &mut comparison.persisting] {
+        issues.sort_by(|a, b| (&a.file_path, a.line_start).cmp(&(&b.file_path, b.line_start)));
+    }
+
+    comparison
+}
+
+/// Format a comparison for the console or as Markdown
+///
+/// # Arguments
+///
+/// * `comparison` - Compared issues
+/// * `markdown` - Whether to write a Markdown table instead of plain text
+///
+/// # Returns
+///
+/// Counts per category and a list of the fixed and new issues
+pub fn format_comparison(comparison: &IssueComparison, markdown: bool) -> String {
+    let total = comparison.categories.values().fold(CategoryCounts::default(), |total, counts| CategoryCounts {
+        fixed: total.fixed + counts.fixed,
+        new: total.new + counts.new,
+        persisting: total.persisting + counts.persisting,
+    });
+    let mut output = String::new();
+
+    if markdown {
+        output.push_str("# Analysis comparison\n\n");
+        output.push_str("| Category | Fixed | New | Persisting |\n");
+        output.push_str("|----------|-------|-----|------------|\n");
+        for (category, counts) in &comparison.categories {
+            output.push_str(&format!("| {} | {} | {} | {} |\n", category, counts.fixed, counts.new, counts.persisting));
+        }
+        output.push_str(&format!("| **Total** | **{}** | **{}** | **{}** |\n", total.fixed, total.new, total.persisting));
+    } else {
+        output.push_str(&format!(
+            "{} fixed, {} new, {} persisting issues\n\n",
+            total.fixed, total.new, total.persisting
+        ));
+        for (category, counts) in &comparison.categories {
+            output.push_str(&format!(
+                "  {:<20} {:>5} fixed {:>5} new {:>5} persisting\n",
+                category, counts.fixed, counts.new, counts.persisting
+            ));
+        }
+    }
+
+    for (title, issues) in [("Fixed issues", &comparison.fixed), ("New issues", &comparison.new)] {
+        if issues.is_empty() {
+            continue;
+        }
+
+        if markdown {
+            output.push_str(&format!("\n## {}\n\n", title));
+        } else {
+            output.push_str(&format!("\n{}:\n", title));
+        }
+        for issue in issues {
+            output.push_str(&format!(
+                "{} {}:{} ({}, {:?}): {}\n",
+                if markdown { "-" } else { " " },
+                issue.file_path.display(),
+                issue.line_start,
+                issue.category.name(),
+                issue.severity,
+                issue.message
+            ));
+        }
+    }
+
+    output
+}
+
+/// Issues of analysis results by file, category and message, in file order
+fn group_by_key(results: &[AnalysisResult]) -> BTreeMap<(PathBuf, String, String), Vec<CodeIssue>> {
+    let mut groups: BTreeMap<(PathBuf, String, String), Vec<CodeIssue>> = BTreeMap::new();
+
+    for issue in results.iter().flat_map(|result| &result.issues) {
+        groups.entry((issue.file_path.clone(), issue.category.name(), issue.message.clone()))
+            .or_default()
+            .push(issue.clone());
+    }
+
+    for issues in groups.values_mut() {
+        issues.sort_by_key(|issue| (issue.line_start, issue.column_start));
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+    use crate::Severity;
+
+    fn issue(line: usize, category: IssueCategory, message: &str) -> CodeIssue {
+        CodeIssue {
+            file_path: PathBuf::from("src/lib.rs"),
+            line_start: line,
+            column_start: 1,
+            line_end: line,
+            column_end: 10,
+            category,
+            severity: Severity::Warning,
+            message: message.to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        }
+    }
+
+    fn result(issues: Vec<CodeIssue>) -> Vec<AnalysisResult> {
+        vec![AnalysisResult {
+            file_path: PathBuf::from("src/lib.rs"),
+            issues,
+            errors: Vec::new(),
+            success: true,
+        }]
+    }
+
+    #[test]
+    fn test_compare_results() {
+        let before = result(vec![
+            issue(3, IssueCategory::Style, "Line too long"),
+            issue(8, IssueCategory::Style, "Line too long"),
+            issue(12, IssueCategory::Security, "Unsafe block"),
+        ]);
+        let after = result(vec![
+            issue(5, IssueCategory::Style, "Line too long"),
+            issue(8, IssueCategory::Style, "Line too long"),
+            issue(20, IssueCategory::Performance, "Needless clone"),
+        ]);
+
+        let comparison = compare_results(&before, &after);
+        let lines = |issues: &[CodeIssue]| issues.iter().map(|issue| issue.line_start).collect::<Vec<_>>();
+        assert_eq!(lines(&comparison.persisting), [5, 8]);
+        assert_eq!(lines(&comparison.fixed), [12]);
+        assert_eq!(lines(&comparison.new), [20]);
+
+        assert_eq!(comparison.categories["Style"], CategoryCounts { fixed: 0, new: 0, persisting: 2 });
+        assert_eq!(comparison.categories["Security"], CategoryCounts { fixed: 1, new: 0, persisting: 0 });
+        assert_eq!(comparison.categories["Performance"], CategoryCounts { fixed: 0, new: 1, persisting: 0 });
+
+        let report = format_comparison(&comparison, true);
+        assert!(report.contains("| **Total** | **1** | **1** | **2** |"));
+        assert!(report.contains("- src/lib.rs:20 (Performance, Warning): Needless clone"));
+    }
+}
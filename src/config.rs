@@ -0,0 +1,1019 @@
+//! Config module
+//!
+//! This module reads the `.rust-ai-tool.toml` configuration:
+//! - Named profiles in `[profile.<name>]` tables, like a cheap local profile
+//!   and a thorough CI profile, overriding the options of the file
+//! - Tables of a profile are merged key by key into the options they override;
+//!   other values, arrays included, replace the value of the file
+//! - `config check` compares the file with the schema of the configuration,
+//!   reporting unknown keys, values of the wrong type, missing keys and
+//!   deprecated keys with the line and column they are set at
+//! - `${NAME}` in string values is replaced with the environment variable
+//!   `NAME` (`${NAME:-default}` when it is unset, `$${` for a literal `${`),
+//!   so tokens and URLs stay out of the committed file
+//! - Configurations of older versions are migrated before they are read
+
+use crate::config_migration::migrate_config;
+use crate::{Config, Result, RustAiToolError, Severity};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fmt;
+use toml::{Table, Value};
+use log::debug;
+
+/// Key of the table holding the profiles
+const PROFILES_KEY: &str = "profile";
+
+/// Renamed keys, by dotted path of the old name, with their new name
+///
+/// The old names are still read as aliases of the new ones, and `config migrate`
+/// renames them.
+pub(crate) const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("ai", "ai_model"),
+    ("analysis", "analysis_options"),
+    ("validation", "validation_options"),
+    ("github", "github_repo"),
+    ("github_repo.repo", "name"),
+    ("github_repo.token", "access_token"),
+];
+
+/// Keys that may be left out when another key is set in the same table
+const ALTERNATIVE_KEYS: &[(&str, &str)] = &[
+    ("ai_model", "ai_models"),
+];
+
+/// Problem with a `${NAME}` reference: key path, message and severity
+type EnvProblem = (Vec<String>, String, Severity);
+
+/// Problem found by checking a configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigProblem {
+    /// `Error` if the configuration cannot be used, `Warning` otherwise
+    pub severity: Severity,
+
+    /// Line of the key (1-based)
+    pub line: usize,
+
+    /// Column of the key (1-based)
+    pub column: usize,
+
+    /// Dotted path of the key (empty for the whole file)
+    pub key: String,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.severity == Severity::Error { "error" } else { "warning" };
+
+        if self.key.is_empty() {
+            write!(f, "{}:{}: {}: {}", self.line, self.column, severity, self.message)
+        } else {
+            write!(f, "{}:{}: {}: `{}`: {}", self.line, self.column, severity, self.key, self.message)
+        }
+    }
+}
+
+/// Parse a configuration, applying a profile
+///
+/// # Arguments
+///
+/// * `content` - Content of the configuration file
+/// * `profile` - Name of the profile to apply (none if `None`)
+///
+/// # Returns
+///
+/// The configuration, or an error if it is invalid or has no such profile
+pub fn parse_config(content: &str, profile: Option<&str>) -> Result<Config> {
+    parse_config_with_env(content, profile, &|name| std::env::var(name).ok())
+}
+
+/// Parse a configuration, looking up `${NAME}` references with a function
+fn parse_config_with_env(content: &str, profile: Option<&str>, env: &dyn Fn(&str) -> Option<String>) -> Result<Config> {
+    let (content, _) = migrate_config(content)?;
+    let mut table: Table = toml::from_str(&content)
+        .map_err(|e| RustAiToolError::Other(format!("Failed to parse configuration: {}", e)))?;
+
+    let mut profiles = match table.remove(PROFILES_KEY) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => return Err(RustAiToolError::Other("`profile` must be a table of profiles".to_string())),
+        None => Table::new(),
+    };
+
+    if let Some(name) = profile {
+        match profiles.remove(name) {
+            Some(Value::Table(overrides)) => {
+                debug!("Applying configuration profile {}", name);
+                merge_tables(&mut table, overrides);
+            }
+            Some(_) => return Err(RustAiToolError::Other(format!("Profile `{}` must be a table", name))),
+            None => {
+                let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                return Err(RustAiToolError::Other(format!(
+                    "Unknown profile `{}` (available: {})",
+                    name,
+                    if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                )));
+            }
+        }
+    }
+
+    interpolate_table(&mut table, env)?;
+
+    // Without `ai_model`, the named entry of `ai_models` (or the first one) stands in for it
+    if !table.contains_key("ai_model") && !table.contains_key("ai") {
+        // An unknown name is reported once the configuration is read
+        let name = table.get("model").and_then(Value::as_str);
+        let models: Vec<&Table> = table.get("ai_models")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_table)
+            .collect();
+        let named_model = models.iter()
+            .find(|model| model.get("name").and_then(Value::as_str) == name)
+            .or(models.first())
+            .map(|model| (*model).clone());
+
+        if let Some(mut model) = named_model {
+            model.remove("name");
+            table.insert("ai_model".to_string(), Value::Table(model));
+        }
+    }
+
+    let mut config: Config = Value::Table(table).try_into()
+        .map_err(|e| RustAiToolError::Other(format!("Failed to parse configuration: {}", e)))?;
+
+    if let Some(name) = config.model.clone() {
+        config.select_model(&name)?;
+    }
+    config.analysis_options.lints = config.lints.clone();
+    config.analysis_options.policy = config.policy.clone();
+
+    Ok(config)
+}
+
+/// Parse a TOML table, replacing `${NAME}` references with environment variables
+pub(crate) fn parse_table(content: &str) -> Result<Table> {
+    let mut table: Table = toml::from_str(content)
+        .map_err(|e| RustAiToolError::Other(format!("Failed to parse configuration: {}", e)))?;
+
+    interpolate_table(&mut table, &|name| std::env::var(name).ok())?;
+    Ok(table)
+}
+
+/// Replace `${NAME}` references in the strings of a table, failing on any problem
+fn interpolate_table(table: &mut Table, env: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    let mut problems = Vec::new();
+    for (key, value) in table.iter_mut() {
+        interpolate_env(value, &mut vec![key.clone()], env, &mut problems);
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<String> = problems.iter()
+        .map(|(path, message, _)| format!("`{}`: {}", path.join("."), message))
+        .collect();
+    Err(RustAiToolError::Other(format!("Failed to parse configuration: {}", messages.join("; "))))
+}
+
+/// Replace `${NAME}` references in the strings of a value, recording the ones that fail
+fn interpolate_env(value: &mut Value, path: &mut Vec<String>, env: &dyn Fn(&str) -> Option<String>, problems: &mut Vec<EnvProblem>) {
+    match value {
+        Value::String(text) if text.contains('$') => match interpolate(text, env) {
+            Ok(interpolated) => *text = interpolated,
+            Err(problem) => problems.push((path.clone(), problem.0, problem.1)),
+        },
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                path.push(index.to_string());
+                interpolate_env(value, path, env, problems);
+                path.pop();
+            }
+        }
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                path.push(key.clone());
+                interpolate_env(value, path, env, problems);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace `${NAME}` and `${NAME:-default}` in a string; `$${` stands for `${`
+///
+/// # Returns
+///
+/// The string with the references replaced, or the problem and its severity:
+/// a warning for an unset variable, an error for an invalid reference
+fn interpolate(text: &str, env: &dyn Fn(&str) -> Option<String>) -> std::result::Result<String, (String, Severity)> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(tail) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("${") {
+            let end = tail.find('}')
+                .ok_or_else(|| ("unterminated `${` (write `$${` for a literal `${`)".to_string(), Severity::Error))?;
+            let (name, default) = match tail[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&tail[..end], None),
+            };
+
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err((format!("invalid environment variable name `{}`", name), Severity::Error));
+            }
+
+            match env(name).or_else(|| default.map(str::to_string)) {
+                Some(value) => result.push_str(&value),
+                None => return Err((format!("environment variable `{}` is not set", name), Severity::Warning)),
+            }
+            rest = &tail[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Merge the values of a profile or directory configuration into a table, recursing into tables present in both
+pub(crate) fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(override_table)) => merge_tables(base_table, override_table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// JSON schema of the configuration file
+pub fn config_schema() -> JsonValue {
+    serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default()
+}
+
+/// Check a configuration against its schema
+///
+/// Profiles are checked as partial configurations, then applied one by one to
+/// check the options they result in.
+///
+/// # Arguments
+///
+/// * `content` - Content of the configuration file
+///
+/// # Returns
+///
+/// The problems found, in the order of the file
+pub fn check_config(content: &str) -> Vec<ConfigProblem> {
+    let mut table: Table = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => {
+            let (line, column) = e.span().map_or((1, 1), |span| line_column(content, span.start));
+            return vec![ConfigProblem {
+                severity: Severity::Error,
+                line,
+                column,
+                key: String::new(),
+                message: e.message().to_string(),
+            }];
+        }
+    };
+
+    let schema = config_schema();
+    let mut checker = SchemaChecker {
+        definitions: schema.get("definitions").and_then(JsonValue::as_object).cloned().unwrap_or_default(),
+        problems: Vec::new(),
+        partial: false,
+        prefix_len: 0,
+    };
+
+    let mut all_values = Value::Table(table.clone());
+    let profiles = table.remove(PROFILES_KEY);
+    checker.check(&Value::Table(table), &schema, &mut Vec::new());
+
+    let mut profile_names = Vec::new();
+    match &profiles {
+        Some(Value::Table(profiles)) => {
+            // Profiles only set the options they override
+            checker.partial = true;
+            checker.prefix_len = 2;
+
+            for (name, profile) in profiles {
+                let mut path = vec![PROFILES_KEY.to_string(), name.clone()];
+                match profile {
+                    Value::Table(_) => {
+                        checker.check(profile, &schema, &mut path);
+                        profile_names.push(name.clone());
+                    }
+                    _ => checker.error(&path, format!("expected a table, found {}", value_kind(profile))),
+                }
+            }
+        }
+        Some(profiles) => checker.error(&[PROFILES_KEY.to_string()], format!("expected a table of profiles, found {}", value_kind(profiles))),
+        None => {}
+    }
+
+    // Unset variables are only warned about, since they may be set where the file is used
+    let mut env_problems = Vec::new();
+    interpolate_env(&mut all_values, &mut Vec::new(), &|name| std::env::var(name).ok(), &mut env_problems);
+    checker.problems.extend(env_problems.into_iter().map(|(path, message, severity)| (severity, path, message)));
+    let env = |name: &str| Some(std::env::var(name).unwrap_or_default());
+
+    // Anything the schema does not cover still fails to parse
+    if checker.problems.iter().all(|(severity, _, _)| *severity != Severity::Error) {
+        if let Err(e) = parse_config_with_env(content, None, &env) {
+            checker.error(&[], e.to_string());
+        }
+
+        for name in profile_names {
+            if let Err(e) = parse_config_with_env(content, Some(&name), &env) {
+                checker.error(&[PROFILES_KEY.to_string(), name], format!("with this profile: {}", e));
+            }
+        }
+    }
+
+    let locations = KeyLocations::scan(content);
+    let mut problems: Vec<ConfigProblem> = checker.problems.into_iter()
+        .map(|(severity, path, message)| {
+            let (line, column) = locations.locate(&path);
+            ConfigProblem { severity, line, column, key: path.join("."), message }
+        })
+        .collect();
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+
+    problems
+}
+
+/// Walks a configuration along its JSON schema, collecting problems
+struct SchemaChecker {
+    /// Schemas `$ref` points to, by name
+    definitions: serde_json::Map<String, JsonValue>,
+
+    /// Severity, key path and message of each problem
+    problems: Vec<(Severity, Vec<String>, String)>,
+
+    /// Whether required keys may be missing, as in profiles
+    partial: bool,
+
+    /// Number of leading keys that are not part of the configuration, like `profile.ci`
+    prefix_len: usize,
+}
+
+impl SchemaChecker {
+    fn error(&mut self, path: &[String], message: String) {
+        self.problems.push((Severity::Error, path.to_vec(), message));
+    }
+
+    fn warning(&mut self, path: &[String], message: String) {
+        self.problems.push((Severity::Warning, path.to_vec(), message));
+    }
+
+    /// Follow `$ref` and single-schema `allOf` to the schema they stand for
+    fn resolve<'a>(&'a self, schema: &'a JsonValue) -> &'a JsonValue {
+        if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+            let name = reference.trim_start_matches("#/definitions/");
+            if let Some(definition) = self.definitions.get(name) {
+                return self.resolve(definition);
+            }
+        }
+
+        match schema.get("allOf").and_then(JsonValue::as_array).map(Vec::as_slice) {
+            Some([inner]) => self.resolve(inner),
+            _ => schema,
+        }
+    }
+
+    fn check(&mut self, value: &Value, schema: &JsonValue, path: &mut Vec<String>) {
+        let schema = self.resolve(schema).clone();
+
+        if let Some(branches) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(JsonValue::as_array) {
+            return self.check_branches(value, branches, path);
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(JsonValue::as_array) {
+            if !serde_json::to_value(value).is_ok_and(|value| allowed.contains(&value)) {
+                self.error(path, format!("expected {}, found {}", describe_schema(&schema), value));
+            }
+            return;
+        }
+
+        if let Some(expected) = schema.get("type") {
+            if !type_matches(expected, value) {
+                self.error(path, format!("expected {}, found {}", describe_schema(&schema), value_kind(value)));
+                return;
+            }
+        }
+
+        match value {
+            Value::Table(table) => self.check_table(table, &schema, path),
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        path.push(index.to_string());
+                        self.check(item, item_schema, path);
+                        path.pop();
+                    }
+                }
+            }
+            Value::Integer(number) => {
+                if let Some(minimum) = schema.get("minimum").and_then(JsonValue::as_f64) {
+                    if (*number as f64) < minimum {
+                        self.error(path, format!("expected at least {}, found {}", minimum, number));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check a value against the alternatives of an `Option` or an enum with data
+    fn check_branches(&mut self, value: &Value, branches: &[JsonValue], path: &mut Vec<String>) {
+        let branches: Vec<JsonValue> = branches.iter()
+            .map(|branch| self.resolve(branch).clone())
+            .filter(|branch| branch.get("type").and_then(JsonValue::as_str) != Some("null"))
+            .collect();
+        let candidates: Vec<&JsonValue> = branches.iter()
+            .filter(|branch| branch.get("type").is_none_or(|expected| type_matches(expected, value)))
+            .collect();
+
+        match candidates.as_slice() {
+            [branch] => self.check(value, branch, path),
+            _ if candidates.iter().any(|branch| self.accepts(value, branch, path)) => {}
+            _ => {
+                let expected: Vec<String> = branches.iter().map(describe_schema).collect();
+                self.error(path, format!("expected {}, found {}", expected.join(" or "), value_kind(value)));
+            }
+        }
+    }
+
+    /// Whether a value matches a schema without errors
+    fn accepts(&self, value: &Value, schema: &JsonValue, path: &[String]) -> bool {
+        let mut probe = SchemaChecker {
+            definitions: self.definitions.clone(),
+            problems: Vec::new(),
+            partial: self.partial,
+            prefix_len: self.prefix_len,
+        };
+        probe.check(value, schema, &mut path.to_vec());
+
+        probe.problems.iter().all(|(severity, _, _)| *severity != Severity::Error)
+    }
+
+    fn check_table(&mut self, table: &Table, schema: &JsonValue, path: &mut Vec<String>) {
+        let properties = schema.get("properties").and_then(JsonValue::as_object);
+        let additional = schema.get("additionalProperties");
+
+        for (key, value) in table {
+            if let Some(property) = properties.and_then(|properties| properties.get(key)) {
+                path.push(key.clone());
+                self.check(value, property, path);
+                path.pop();
+                continue;
+            }
+
+            let renamed = self.renamed_key(path, key)
+                .and_then(|new_key| properties.and_then(|properties| properties.get(new_key)).map(|property| (new_key, property)));
+
+            path.push(key.clone());
+            match (renamed, additional) {
+                (Some((new_key, _)), _) if table.contains_key(new_key) => {
+                    self.error(path, format!("deprecated name of `{}`, which is set too", new_key));
+                }
+                (Some((new_key, property)), _) => {
+                    self.warning(path, format!("deprecated key, use `{}`", new_key));
+                    self.check(value, property, path);
+                }
+                (None, Some(additional)) if additional.is_object() => self.check(value, additional, path),
+                (None, _) if properties.is_some() || additional == Some(&JsonValue::Bool(false)) => {
+                    let suggestion = properties
+                        .and_then(|properties| closest_key(key, properties.keys()))
+                        .map(|closest| format!(" (did you mean `{}`?)", closest))
+                        .unwrap_or_default();
+                    self.error(path, format!("unknown key{}", suggestion));
+                }
+                _ => {}
+            }
+            path.pop();
+        }
+
+        if self.partial {
+            return;
+        }
+
+        for required in schema.get("required").and_then(JsonValue::as_array).into_iter().flatten().filter_map(JsonValue::as_str) {
+            let present = table.contains_key(required)
+                || table.keys().any(|key| self.renamed_key(path, key) == Some(required))
+                || ALTERNATIVE_KEYS.iter().any(|(key, alternative)| *key == required && table.contains_key(*alternative));
+            if !present {
+                self.error(path, format!("missing key `{}`", required));
+            }
+        }
+    }
+
+    /// New name of a deprecated key of a table
+    fn renamed_key(&self, table_path: &[String], key: &str) -> Option<&'static str> {
+        // Tables may be set under their deprecated name too, like `[github]`
+        let mut table = String::new();
+        for part in table_path.iter().skip(self.prefix_len) {
+            let key_path = dotted_key(&table, part);
+            table = match deprecated_key(&key_path) {
+                Some(new_key) => dotted_key(&table, new_key),
+                None => key_path,
+            };
+        }
+
+        deprecated_key(&dotted_key(&table, key))
+    }
+}
+
+/// New name of a deprecated key, by dotted path
+fn deprecated_key(dotted: &str) -> Option<&'static str> {
+    DEPRECATED_KEYS.iter()
+        .find(|(old_key, _)| *old_key == dotted)
+        .map(|(_, new_key)| *new_key)
+}
+
+fn dotted_key(table: &str, key: &str) -> String {
+    if table.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", table, key)
+    }
+}
+
+/// Whether a value has one of the JSON schema types
+fn type_matches(expected: &JsonValue, value: &Value) -> bool {
+    let types: Vec<&str> = match expected {
+        JsonValue::String(name) => vec![name.as_str()],
+        JsonValue::Array(names) => names.iter().filter_map(JsonValue::as_str).collect(),
+        _ => return true,
+    };
+
+    types.iter().any(|name| matches!(
+        (*name, value),
+        ("string", Value::String(_))
+            | ("integer", Value::Integer(_))
+            | ("number", Value::Integer(_) | Value::Float(_))
+            | ("boolean", Value::Boolean(_))
+            | ("array", Value::Array(_))
+            | ("object", Value::Table(_))
+    ))
+}
+
+/// What a schema accepts, as shown in problems
+fn describe_schema(schema: &JsonValue) -> String {
+    if let Some(allowed) = schema.get("enum").and_then(JsonValue::as_array) {
+        let allowed: Vec<String> = allowed.iter().map(JsonValue::to_string).collect();
+        return match allowed.as_slice() {
+            [single] => single.clone(),
+            _ => format!("one of {}", allowed.join(", ")),
+        };
+    }
+
+    // Enum variants with data are tables with a single key
+    if let Some([variant]) = schema.get("required").and_then(JsonValue::as_array).map(Vec::as_slice) {
+        if schema.get("additionalProperties") == Some(&JsonValue::Bool(false)) {
+            return format!("{{ {} = ... }}", variant.as_str().unwrap_or_default());
+        }
+    }
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(JsonValue::String(name)) => vec![name.as_str()],
+        Some(JsonValue::Array(names)) => names.iter().filter_map(JsonValue::as_str).filter(|name| *name != "null").collect(),
+        _ => Vec::new(),
+    };
+    if types.is_empty() {
+        return "a valid value".to_string();
+    }
+
+    types.iter()
+        .map(|name| match *name {
+            "string" => "a string",
+            "integer" => "an integer",
+            "number" => "a number",
+            "boolean" => "a boolean",
+            "array" => "an array",
+            "object" => "a table",
+            other => other,
+        })
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Kind of a TOML value, as shown in problems
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Datetime(_) => "a date-time",
+        Value::Array(_) => "an array",
+        Value::Table(_) => "a table",
+    }
+}
+
+/// Known key closest to a misspelled one, if any is close enough
+fn closest_key<'a>(key: &str, known_keys: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    known_keys
+        .map(|known_key| (edit_distance(key, known_key), known_key))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known_key)| known_key)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == *b_char { diagonal } else { 1 + diagonal.min(row[j]).min(above) };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Line and column (1-based) of a byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Where the keys of a configuration file are set
+///
+/// Tables of arrays get the index of the entry as a key, like `custom_rules.0`.
+struct KeyLocations {
+    keys: Vec<(Vec<String>, usize, usize)>,
+}
+
+impl KeyLocations {
+    fn scan(content: &str) -> Self {
+        let mut keys = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut array_lengths: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut multiline_string: Option<&str> = None;
+        let mut array_depth = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim_start();
+            let column = line.len() - trimmed.len() + 1;
+
+            // Lines of multi-line strings and arrays hold no keys
+            if let Some(delimiter) = multiline_string {
+                if line.matches(delimiter).count() % 2 == 1 {
+                    multiline_string = None;
+                }
+                continue;
+            }
+            if array_depth > 0 {
+                array_depth += bracket_depth(line);
+                continue;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = trimmed.strip_prefix("[[") {
+                let path = split_key(header.split("]]").next().unwrap_or_default());
+                let length = array_lengths.entry(path.clone()).or_insert(0);
+                table = path;
+                table.push(length.to_string());
+                *length += 1;
+                keys.push((table.clone(), index + 1, column));
+            } else if let Some(header) = trimmed.strip_prefix('[') {
+                table = split_key(header.split(']').next().unwrap_or_default());
+                keys.push((table.clone(), index + 1, column));
+            } else if let Some(equals) = find_unquoted(trimmed, '=') {
+                let mut path = table.clone();
+                path.extend(split_key(&trimmed[..equals]));
+                let value = &trimmed[equals + 1..];
+                keys.push((path.clone(), index + 1, column));
+
+                if value.trim_start().starts_with('{') {
+                    scan_inline_table(value, &path, index + 1, column + equals + 1, &mut keys);
+                }
+
+                multiline_string = ["\"\"\"", "'''"].into_iter().find(|delimiter| value.matches(delimiter).count() % 2 == 1);
+                if multiline_string.is_none() {
+                    array_depth = bracket_depth(value);
+                }
+            }
+        }
+
+        KeyLocations { keys }
+    }
+
+    /// Location of a key path
+    ///
+    /// Tables only set through their subtables, like `[ai_model.retry]`, are
+    /// located at the first of them, and keys that are not set at the
+    /// longest key path they start with (the start of the file if none).
+    fn locate(&self, path: &[String]) -> (usize, usize) {
+        if path.is_empty() {
+            return (1, 1);
+        }
+
+        self.keys.iter()
+            .find(|(key, _, _)| key.starts_with(path))
+            .or_else(|| {
+                self.keys.iter()
+                    .filter(|(key, _, _)| path.starts_with(key))
+                    .max_by_key(|(key, _, _)| key.len())
+            })
+            .map_or((1, 1), |(_, line, column)| (*line, *column))
+    }
+}
+
+/// Record the keys of an inline table, like `{ run_clippy = true, filter = { ... } }`
+///
+/// Keys of inline tables inside arrays are recorded without the index of the entry.
+fn scan_inline_table(value: &str, path: &[String], line: usize, column: usize, keys: &mut Vec<(Vec<String>, usize, usize)>) {
+    let mut tables: Vec<Vec<String>> = Vec::new();
+    let mut last_key = Some(path.to_vec());
+    let mut segment_start = 0;
+
+    for (index, c) in unquoted_chars(value) {
+        match c {
+            '{' => {
+                let table = last_key.take()
+                    .or_else(|| tables.last().cloned())
+                    .unwrap_or_else(|| path.to_vec());
+                tables.push(table);
+                segment_start = index + 1;
+            }
+            '}' => {
+                tables.pop();
+                last_key = None;
+            }
+            ',' => {
+                segment_start = index + 1;
+                last_key = None;
+            }
+            '=' => {
+                let Some(table) = tables.last() else { continue };
+                let segment = &value[segment_start..index];
+                let mut key_path = table.clone();
+                key_path.extend(split_key(segment));
+
+                let key_column = column + segment_start + (segment.len() - segment.trim_start().len());
+                keys.push((key_path.clone(), line, key_column));
+                last_key = Some(key_path);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Characters outside of strings and comments, with their byte index
+fn unquoted_chars(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut comment = false;
+
+    text.char_indices().filter(move |(_, c)| {
+        if comment {
+            return false;
+        }
+
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some('"') if *c == '\\' => escaped = true,
+            Some(delimiter) if *c == delimiter => quote = None,
+            Some(_) => {}
+            None if *c == '"' || *c == '\'' => quote = Some(*c),
+            None if *c == '#' => comment = true,
+            None => return true,
+        }
+
+        false
+    })
+}
+
+/// Byte index of the first occurrence of a character outside of strings and comments
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    unquoted_chars(text).find(|(_, c)| *c == target).map(|(index, _)| index)
+}
+
+/// Brackets opened minus brackets closed outside of strings and comments
+fn bracket_depth(text: &str) -> i32 {
+    unquoted_chars(text)
+        .map(|(_, c)| match c {
+            '[' => 1,
+            ']' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Parts of a dotted key, like `ai_model.retry` or `pricing."gpt-4"`
+fn split_key(key: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quote: Option<char> = None;
+
+    for c in key.chars() {
+        match quote {
+            Some(delimiter) if c == delimiter => quote = None,
+            Some(_) => parts.last_mut().into_iter().for_each(|part| part.push(c)),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '.' => parts.push(String::new()),
+            None if c.is_whitespace() => {}
+            None => parts.last_mut().into_iter().for_each(|part| part.push(c)),
+        }
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[ai_model]
+model_type = "Claude"
+api_key = "key"
+
+[analysis_options]
+run_clippy = false
+use_rust_analyzer = true
+
+[validation_options]
+syntax_only = true
+tauri_compatibility = false
+security_validation = false
+
+[profile.ci]
+offline = true
+analysis_options = { run_clippy = true, filter = { min_severity = "Warning" } }
+
+[profile.ci.validation_options]
+syntax_only = false
+"#;
+
+    #[test]
+    fn test_config_profiles() {
+        let config = parse_config(CONFIG, None).unwrap();
+        assert!(!config.offline);
+        assert!(!config.analysis_options.run_clippy);
+        assert!(config.validation_options.syntax_only);
+
+        let config = parse_config(CONFIG, Some("ci")).unwrap();
+        assert!(config.offline);
+        assert!(config.analysis_options.run_clippy);
+        assert!(config.analysis_options.use_rust_analyzer);
+        assert_eq!(config.analysis_options.filter.min_severity, Some(crate::Severity::Warning));
+        assert!(!config.validation_options.syntax_only);
+        assert_eq!(config.ai_model.api_key, "key");
+
+        let error = parse_config(CONFIG, Some("strict")).unwrap_err().to_string();
+        assert!(error.contains("Unknown profile `strict` (available: ci)"), "{}", error);
+    }
+
+    #[test]
+    fn test_check_config() {
+        assert!(check_config(CONFIG).is_empty(), "{:?}", check_config(CONFIG));
+
+        let content = r#"
+[ai]
+model_type = "Gemini"
+api_key = "key"
+
+[ai.retry]
+max_retries = -1
+
+[analysis_options]
+run_clipy = false
+use_rust_analyzer = "yes"
+
+[validation_options]
+syntax_only = true
+tauri_compatibility = false
+
+[profile.ci]
+analysis_options = { run_clippy = true, filter = { min_severity = 2 } }
+"#;
+        let problems: Vec<String> = check_config(content).iter().map(ToString::to_string).collect();
+        assert_eq!(problems, [
+            "2:1: warning: `ai`: deprecated key, use `ai_model`",
+            "3:1: error: `ai.model_type`: expected \"Claude\" or \"Gpt\" or \"Mistral\" or { Local = ... } or { OpenAiCompatible = ... }, found a string",
+            "7:1: error: `ai.retry.max_retries`: expected at least 0, found -1",
+            "9:1: error: `analysis_options`: missing key `run_clippy`",
+            "10:1: error: `analysis_options.run_clipy`: unknown key (did you mean `run_clippy`?)",
+            "11:1: error: `analysis_options.use_rust_analyzer`: expected a boolean, found a string",
+            "13:1: error: `validation_options`: missing key `security_validation`",
+            "18:52: error: `profile.ci.analysis_options.filter.min_severity`: expected \"Error\" or \"Warning\" or \"Info\" or \"Style\", found an integer",
+        ]);
+
+        let problems = check_config("offline = true\n[ai_model\n");
+        assert_eq!((problems[0].line, problems[0].column), (2, 10), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        let env = |name: &str| match name {
+            "TOKEN" => Some("secret".to_string()),
+            _ => None,
+        };
+        assert_eq!(interpolate("Bearer ${TOKEN}", &env).unwrap(), "Bearer secret");
+        assert_eq!(interpolate("${HOST:-localhost}:8080", &env).unwrap(), "localhost:8080");
+        assert_eq!(interpolate("$${TOKEN} costs $5", &env).unwrap(), "${TOKEN} costs $5");
+        assert_eq!(interpolate("${MISSING}", &env).unwrap_err().1, Severity::Warning);
+        assert_eq!(interpolate("${TOKEN", &env).unwrap_err().1, Severity::Error);
+        assert_eq!(interpolate("${BAD-NAME}", &env).unwrap_err().1, Severity::Error);
+
+        let content = r#"
+[ai_model]
+model_type = "Claude"
+api_key = "${TOKEN}"
+api_base_url = "${BASE_URL:-https://api.example.com}"
+
+[analysis_options]
+run_clippy = false
+use_rust_analyzer = true
+
+[validation_options]
+syntax_only = true
+tauri_compatibility = false
+security_validation = false
+
+[profile.ci.ai_model]
+api_key = "${CI_TOKEN}"
+"#;
+        let config = parse_config_with_env(content, None, &env).unwrap();
+        assert_eq!(config.ai_model.api_key, "secret");
+        assert_eq!(config.ai_model.api_base_url.as_deref(), Some("https://api.example.com"));
+
+        let error = parse_config_with_env(content, Some("ci"), &env).unwrap_err().to_string();
+        assert!(error.contains("`ai_model.api_key`") && error.contains("`CI_TOKEN` is not set"), "{}", error);
+    }
+
+    #[test]
+    fn test_named_models() {
+        let content = r#"
+model = "hosted"
+
+[[ai_models]]
+name = "local-ollama"
+model_type = { Local = "llama3" }
+api_key = ""
+
+[[ai_models]]
+name = "hosted"
+model_type = "Claude"
+api_key = "key"
+
+[analysis_options]
+run_clippy = false
+use_rust_analyzer = true
+
+[validation_options]
+syntax_only = true
+tauri_compatibility = false
+security_validation = false
+
+[profile.local]
+model = "local-ollama"
+"#;
+        assert!(check_config(content).is_empty(), "{:?}", check_config(content));
+
+        let mut config = parse_config(content, None).unwrap();
+        assert_eq!(config.ai_model.model_type, crate::AiModelType::Claude);
+        assert_eq!(config.ai_model.api_key, "key");
+
+        let config_local = parse_config(content, Some("local")).unwrap();
+        assert_eq!(config_local.ai_model.model_type, crate::AiModelType::Local("llama3".to_string()));
+
+        config.select_model("local-ollama").unwrap();
+        assert_eq!(config.ai_model.model_type, crate::AiModelType::Local("llama3".to_string()));
+        let error = config.select_model("gpt").unwrap_err().to_string();
+        assert!(error.contains("Unknown model `gpt` (available: local-ollama, hosted)"), "{}", error);
+
+        let error = parse_config(&content.replace("model = \"hosted\"", "model = \"none\""), None).unwrap_err().to_string();
+        assert!(error.contains("Unknown model `none`"), "{}", error);
+    }
+}
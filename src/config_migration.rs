@@ -0,0 +1,221 @@
+//! Config migration module
+//!
+//! This module upgrades `.rust-ai-tool.toml` files written for older versions of
+//! the configuration format:
+//! - `version` at the top of the file is the version of its format; files
+//!   without it are version 1, written before versions existed
+//! - Each migration step rewrites the keys changed by one version, in the file
+//!   and in its profiles, with `toml_edit` so comments and formatting survive
+//! - Keys that cannot be migrated, like a deprecated key set along with its new
+//!   name, are reported and left as they are
+//! - Configurations are migrated in memory when they are read, and a file newer
+//!   than this build is refused instead of being read with defaults
+
+use crate::config::DEPRECATED_KEYS;
+use crate::{Result, RustAiToolError};
+use toml_edit::{Document, Item, Key, TableLike, Value};
+
+/// Version of the configuration format written and read by this build
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Version of configurations without a `version` key
+pub const LEGACY_CONFIG_VERSION: u32 = 1;
+
+/// Key of the table holding the profiles
+const PROFILES_KEY: &str = "profile";
+
+/// Step upgrading the options of a configuration or profile by one version
+type Migration = fn(&mut dyn TableLike, &str, &mut MigrationReport);
+
+/// Migration steps; the first one upgrades version 1 to version 2
+const MIGRATIONS: &[Migration] = &[rename_deprecated_keys];
+
+/// What a migration changed in a configuration
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    /// Version of the configuration before the migration
+    pub from_version: u32,
+
+    /// Version of the configuration after the migration
+    pub to_version: u32,
+
+    /// Changes made, like renamed keys
+    pub changes: Vec<String>,
+
+    /// Deprecated options left in the configuration, with what to do about them
+    pub deprecated: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Whether the migration changed anything but the version
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Upgrade a configuration to the current version
+///
+/// # Arguments
+///
+/// * `content` - Content of the configuration file
+///
+/// # Returns
+///
+/// The upgraded content, with its formatting, and what was changed, or an error
+/// if the configuration cannot be parsed or is newer than this build
+pub fn migrate_config(content: &str) -> Result<(String, MigrationReport)> {
+    let mut document = content.parse::<Document>()
+        .map_err(|e| RustAiToolError::Other(format!("Failed to parse configuration: {}", e)))?;
+
+    let version = config_version(document.get("version"))?;
+    let mut report = MigrationReport {
+        from_version: version,
+        to_version: CONFIG_VERSION,
+        ..Default::default()
+    };
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(document.as_table_mut(), "", &mut report);
+
+        let profiles = document.get_mut(PROFILES_KEY).and_then(Item::as_table_like_mut);
+        for (name, profile) in profiles.into_iter().flat_map(|profiles| profiles.iter_mut()) {
+            if let Some(profile) = profile.as_table_like_mut() {
+                migration(profile, &format!("{}.{}.", PROFILES_KEY, name.get()), &mut report);
+            }
+        }
+    }
+
+    let root = document.as_table_mut();
+    if let Some(item) = root.get_mut("version") {
+        if version != CONFIG_VERSION {
+            *item = Item::Value(Value::from(i64::from(CONFIG_VERSION)));
+        }
+    } else {
+        let first_key = root.iter().find(|(_, item)| item.is_value()).map(|(key, _)| key.to_string());
+        root.insert("version", Item::Value(Value::from(i64::from(CONFIG_VERSION))));
+        // Stable, so only moves `version` first
+        root.sort_values_by(|key, _, other, _| (key.get() != "version").cmp(&(other.get() != "version")));
+
+        // Comments at the top of the file stay above `version`
+        let prefix = first_key
+            .and_then(|key| root.key_decor_mut(&key))
+            .and_then(|decor| {
+                let prefix = decor.prefix().cloned();
+                decor.set_prefix("");
+                prefix
+            });
+        if let (Some(prefix), Some(decor)) = (prefix, root.key_decor_mut("version")) {
+            decor.set_prefix(prefix);
+        }
+    }
+
+    Ok((document.to_string(), report))
+}
+
+/// Version of a configuration, checking that this build can read it
+fn config_version(item: Option<&Item>) -> Result<u32> {
+    let Some(item) = item else {
+        return Ok(LEGACY_CONFIG_VERSION);
+    };
+
+    match item.as_integer() {
+        Some(version) if version > i64::from(CONFIG_VERSION) => Err(RustAiToolError::Other(format!(
+            "Configuration version {} is newer than the versions this build reads (up to {}); upgrade rust-ai-tool",
+            version, CONFIG_VERSION
+        ))),
+        Some(version) if version >= i64::from(LEGACY_CONFIG_VERSION) => Ok(version as u32),
+        _ => Err(RustAiToolError::Other(format!(
+            "`version` must be an integer from {} to {}",
+            LEGACY_CONFIG_VERSION, CONFIG_VERSION
+        ))),
+    }
+}
+
+/// Version 1 to 2: rename the keys read through aliases to their new name
+fn rename_deprecated_keys(table: &mut dyn TableLike, prefix: &str, report: &mut MigrationReport) {
+    // Tables are renamed before their keys, like `github` before `github_repo.repo`
+    for (old_path, new_key) in DEPRECATED_KEYS {
+        let (table_path, old_key) = old_path.rsplit_once('.').unwrap_or(("", old_path));
+
+        let Some(parent) = table_at(&mut *table, table_path) else {
+            continue;
+        };
+        if !parent.contains_key(old_key) {
+            continue;
+        }
+
+        let old_name = format!("{}{}", prefix, old_path);
+        let new_name = format!("{}{}", prefix, if table_path.is_empty() { new_key.to_string() } else { format!("{}.{}", table_path, new_key) });
+
+        if parent.contains_key(new_key) {
+            report.deprecated.push(format!("`{}` is a deprecated name of `{}`, which is set too; remove one of them", old_name, new_name));
+            continue;
+        }
+
+        let decor = parent.key_decor(old_key).cloned();
+        if let Some(item) = parent.remove(old_key) {
+            let key = Key::new(*new_key).with_decor(decor.unwrap_or_default());
+            parent.entry_format(&key).or_insert(item);
+            report.changes.push(format!("renamed `{}` to `{}`", old_name, new_name));
+        }
+    }
+}
+
+/// Table at a dotted path of a table (the table itself for an empty path)
+fn table_at<'a>(table: &'a mut dyn TableLike, path: &str) -> Option<&'a mut dyn TableLike> {
+    match path.split_once('.') {
+        _ if path.is_empty() => Some(table),
+        Some((first, rest)) => table_at(table.get_mut(first)?.as_table_like_mut()?, rest),
+        None => table.get_mut(path)?.as_table_like_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_config() {
+        let content = r#"# Project configuration
+offline = false
+
+# The model
+[ai]
+model_type = "Claude"
+api_key = "key"
+
+[ai.retry]
+max_retries = 2
+
+[github]
+owner = "octo"
+repo = "tool" # the repository
+
+[profile.ci]
+analysis = { run_clippy = true }
+validation = { syntax_only = false }
+validation_options = { syntax_only = true }
+"#;
+        let (migrated, report) = migrate_config(content).unwrap();
+        assert_eq!((report.from_version, report.to_version), (1, CONFIG_VERSION));
+        assert_eq!(report.changes, [
+            "renamed `ai` to `ai_model`",
+            "renamed `github` to `github_repo`",
+            "renamed `github_repo.repo` to `github_repo.name`",
+            "renamed `profile.ci.analysis` to `profile.ci.analysis_options`",
+        ]);
+        assert_eq!(report.deprecated, ["`profile.ci.validation` is a deprecated name of `profile.ci.validation_options`, which is set too; remove one of them"]);
+
+        assert!(migrated.starts_with("# Project configuration\nversion = 2\noffline = false\n\n# The model\n[ai_model]\n"), "{}", migrated);
+        assert!(migrated.contains("[ai_model.retry]\nmax_retries = 2\n"), "{}", migrated);
+        assert!(migrated.contains("name = \"tool\" # the repository\n"), "{}", migrated);
+
+        let (again, report) = migrate_config(&migrated).unwrap();
+        assert_eq!(again, migrated);
+        assert!(!report.has_changes() && report.from_version == CONFIG_VERSION);
+
+        let error = migrate_config("version = 99\n").unwrap_err().to_string();
+        assert!(error.contains("newer than the versions this build reads"), "{}", error);
+        assert!(migrate_config("version = \"2\"\n").is_err());
+    }
+}
@@ -0,0 +1,531 @@
+//! Daemon module
+//!
+//! This module keeps the tool running in the background for IDE plugins and
+//! scripts:
+//! - JSON-RPC 2.0 over HTTP POST, on loopback addresses only
+//! - `analyze`, `validate`, `apply` and `fix` methods, answering with the same
+//!   JSON the CLI writes, and `shutdown` to stop the daemon
+//! - Analysis results of unchanged files and Clippy issues of unchanged
+//!   projects are kept in memory, as are the AI model clients of projects, so
+//!   repeated requests skip the slow parts
+
+use crate::analysis::{analyze_file_or_error, attach_clippy_issues, collect_rust_files, run_clippy_project, AnalysisResult, CodeIssue};
+use crate::analyzer_plugins;
+use crate::changed_files::changed_rust_files;
+use crate::directory_config::DirectoryConfigs;
+use crate::journal::{content_hash, Journal};
+use crate::models::AiModelClient;
+use crate::modification::{apply_modifications, coalesce_modifications, CodeModification, FileChange, MergeConflict};
+use crate::pipeline::{run_fix_pipeline, FixPipelineOptions, FixPipelineReport};
+use crate::validation::{validate_fixes, FixToValidate};
+use crate::{AnalysisOptions, Result, RustAiToolError, ValidationOptions};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use log::{debug, info};
+
+/// JSON-RPC error code of a request that is not valid JSON
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code of an unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code of parameters not matching the method
+const INVALID_PARAMS: i64 = -32602;
+
+/// JSON-RPC error code of a method that failed
+const SERVER_ERROR: i64 = -32000;
+
+/// Creates the AI model client used to fix the issues of a project
+pub type ClientFactory = Arc<dyn Fn(&Path) -> Result<AiModelClient> + Send + Sync>;
+
+/// Settings of the daemon
+#[derive(Clone)]
+pub struct DaemonOptions {
+    /// Options of `analyze` and of the analysis before `fix`
+    pub analysis_options: AnalysisOptions,
+
+    /// Options of `validate` and of the validation of generated fixes
+    pub validation_options: ValidationOptions,
+
+    /// Files fixed concurrently by `fix`
+    pub fix_concurrency: usize,
+
+    /// Creates AI model clients (`fix` is unavailable without one, as in offline mode)
+    pub ai_client: Option<ClientFactory>,
+}
+
+/// Results kept between requests
+#[derive(Default)]
+struct AnalysisCache {
+    /// Analysis of each file without Clippy, with the hash of the content analyzed
+    files: HashMap<PathBuf, (String, AnalysisResult)>,
+
+    /// Clippy issues of each project, with the fingerprint of the sources checked
+    clippy: HashMap<PathBuf, (String, Vec<CodeIssue>)>,
+}
+
+/// State shared by the requests of a daemon
+struct Daemon {
+    options: DaemonOptions,
+    cache: Mutex<AnalysisCache>,
+    clients: Mutex<HashMap<PathBuf, Arc<AiModelClient>>>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    method: String,
+
+    #[serde(default)]
+    params: Value,
+
+    /// Missing for notifications, which are answered all the same
+    #[serde(default)]
+    id: Value,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<RustAiToolError> for RpcError {
+    fn from(error: RustAiToolError) -> Self {
+        RpcError { code: SERVER_ERROR, message: error.to_string() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeParams {
+    /// Project directory
+    path: PathBuf,
+
+    /// Files to analyze, relative to the project (all files if not set)
+    #[serde(default)]
+    files: Option<Vec<PathBuf>>,
+
+    /// Only analyze the files changed in git
+    #[serde(default)]
+    changed: bool,
+
+    /// Ref the changed files are compared against
+    #[serde(default)]
+    base: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidateParams {
+    fixes: Vec<FixToValidate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyParams {
+    /// Project directory, which relative fix paths are resolved against
+    path: PathBuf,
+
+    fixes: Vec<CodeModification>,
+
+    #[serde(default = "default_backup")]
+    backup: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixParams {
+    /// Project directory
+    path: PathBuf,
+
+    #[serde(default = "default_max_refinements")]
+    max_refinements: u32,
+
+    #[serde(default)]
+    with_tests: bool,
+}
+
+/// Answer of `apply`
+#[derive(Debug, Serialize)]
+struct ApplyResponse {
+    /// Applied changes
+    changes: Vec<FileChange>,
+
+    /// Edits left out because they conflict with a higher-confidence fix
+    conflicts: Vec<MergeConflict>,
+
+    /// Journal entries of the changes, for `rust-ai-tool undo`
+    journal_ids: Vec<String>,
+}
+
+fn default_backup() -> bool {
+    true
+}
+
+fn default_max_refinements() -> u32 {
+    2
+}
+
+/// Run the daemon until a `shutdown` request
+///
+/// # Arguments
+///
+/// * `addr` - Loopback address to listen on
+/// * `options` - Settings of the daemon
+pub async fn serve_daemon(addr: SocketAddr, options: DaemonOptions) -> Result<()> {
+    // Requests read and change any file the daemon can access
+    if !addr.ip().is_loopback() {
+        return Err(RustAiToolError::Other(format!("The daemon only listens on loopback addresses, not {}", addr.ip())));
+    }
+
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let daemon = Arc::new(Daemon::new(options, shutdown_sender));
+    let make_service = make_service_fn(move |_connection| {
+        let daemon = daemon.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle_http(request, daemon.clone())))
+        }
+    });
+
+    let server = Server::try_bind(&addr)
+        .map_err(|e| RustAiToolError::Other(format!("Failed to listen on {}: {}", addr, e)))?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            shutdown_receiver.await.ok();
+        });
+
+    info!("Daemon listening for JSON-RPC requests on http://{}", addr);
+    server.await
+        .map_err(|e| RustAiToolError::Other(format!("Daemon failed: {}", e)))?;
+
+    info!("Daemon stopped");
+    Ok(())
+}
+
+/// Answer one HTTP request carrying a JSON-RPC request
+async fn handle_http(request: Request<Body>, daemon: Arc<Daemon>) -> std::result::Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .body(Body::from("Send JSON-RPC requests with POST"))
+            .unwrap_or_default());
+    }
+
+    let response = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => handle_rpc(daemon, &body).await,
+        Err(e) => rpc_response(Value::Null, Err(RpcError { code: PARSE_ERROR, message: format!("Failed to read body: {}", e) })),
+    };
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(response.to_string()))
+        .unwrap_or_default())
+}
+
+/// Answer one JSON-RPC request
+async fn handle_rpc(daemon: Arc<Daemon>, body: &[u8]) -> Value {
+    let request: RpcRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(e) => {
+            return rpc_response(Value::Null, Err(RpcError { code: PARSE_ERROR, message: format!("Invalid JSON-RPC request: {}", e) }));
+        }
+    };
+
+    debug!("Daemon request {} ({})", request.method, request.id);
+    let outcome = call(daemon, &request.method, request.params).await;
+    rpc_response(request.id, outcome)
+}
+
+/// Run a method of the daemon
+async fn call(daemon: Arc<Daemon>, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+    match method {
+        "analyze" => {
+            let params: AnalyzeParams = parse_params(params)?;
+            to_result(blocking(move || daemon.analyze(&params)).await?)
+        }
+        "validate" => {
+            let params: ValidateParams = parse_params(params)?;
+            to_result(blocking(move || validate_fixes(&params.fixes, &daemon.options.validation_options)).await?)
+        }
+        "apply" => {
+            let params: ApplyParams = parse_params(params)?;
+            to_result(blocking(move || Daemon::apply(params)).await?)
+        }
+        "fix" => {
+            let params: FixParams = parse_params(params)?;
+            to_result(daemon.fix(params).await?)
+        }
+        "shutdown" => {
+            if let Some(sender) = daemon.shutdown.lock().unwrap().take() {
+                sender.send(()).ok();
+            }
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("Unknown method: {}", method) }),
+    }
+}
+
+impl Daemon {
+    fn new(options: DaemonOptions, shutdown: oneshot::Sender<()>) -> Self {
+        Daemon {
+            options,
+            cache: Mutex::new(AnalysisCache::default()),
+            clients: Mutex::new(HashMap::new()),
+            shutdown: Mutex::new(Some(shutdown)),
+        }
+    }
+
+    /// Analyze a project, reusing the results of files and Clippy runs whose sources did not change
+    fn analyze(&self, params: &AnalyzeParams) -> Result<Vec<AnalysisResult>> {
+        let project_path = params.path.canonicalize().map_err(RustAiToolError::Io)?;
+        let all_files = collect_rust_files(&project_path)?;
+
+        let files = if params.changed {
+            changed_rust_files(&project_path, params.base.as_deref())?
+        } else if let Some(files) = &params.files {
+            files.iter()
+                .map(|file| project_path.join(file).canonicalize().map_err(RustAiToolError::Io))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            all_files.clone()
+        };
+
+        let options = &self.options.analysis_options;
+        let files = options.filter.filter_files(&project_path, &files)?;
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // The cache is only locked to look results up and store them, so requests
+        // for other projects are not held up by an analysis or a Clippy build
+        let directories = DirectoryConfigs::new(&project_path);
+        let mut results = Vec::with_capacity(files.len());
+        let mut file_options = Vec::with_capacity(files.len());
+
+        for file_path in &files {
+            let options = directories.analysis_options(file_path, options)?;
+            if matches!(options, Cow::Owned(_)) && options.filter.filter_files(&project_path, std::slice::from_ref(file_path))?.is_empty() {
+                continue;
+            }
+
+            // A changed directory configuration or analyzer library invalidates the results of its files
+            let hash = fs::read_to_string(file_path)
+                .map(|content| content_hash(&format!(
+                    "{}{}{}",
+                    directories.fingerprint(file_path).unwrap_or_default(),
+                    analyzer_plugins::fingerprint(&project_path, &options.analyzers),
+                    content
+                )))
+                .unwrap_or_default();
+
+            let cached = self.cache.lock().unwrap().files.get(file_path)
+                .filter(|(cached_hash, _)| *cached_hash == hash)
+                .map(|(_, result)| result.clone());
+            match cached {
+                Some(result) => results.push(result),
+                None => {
+                    let result = analyze_file_or_error(&project_path, file_path, &options);
+                    self.cache.lock().unwrap().files.insert(file_path.clone(), (hash, result.clone()));
+                    results.push(result);
+                }
+            }
+            file_options.push(options);
+        }
+
+        if file_options.iter().any(|options| options.run_clippy) {
+            let fingerprint = project_fingerprint(&project_path, &all_files);
+
+            let cached = self.cache.lock().unwrap().clippy.get(&project_path)
+                .filter(|(cached_fingerprint, _)| *cached_fingerprint == fingerprint)
+                .map(|(_, issues)| issues.clone());
+            let clippy_issues = match cached {
+                Some(issues) => Ok(issues),
+                None => run_clippy_project(&project_path),
+            };
+            if let Ok(issues) = &clippy_issues {
+                self.cache.lock().unwrap().clippy.insert(project_path.clone(), (fingerprint, issues.clone()));
+            }
+
+            // Clippy issues are dropped for files whose directory turns it off
+            let clippy_files: Vec<&PathBuf> = results.iter().zip(&file_options)
+                .filter(|(_, options)| options.run_clippy)
+                .map(|(result, _)| &result.file_path)
+                .collect();
+            let clippy_issues = clippy_issues
+                .map(|issues| issues.into_iter().filter(|issue| clippy_files.contains(&&issue.file_path)).collect());
+            attach_clippy_issues(&mut results, &clippy_issues);
+        }
+
+        for (result, options) in results.iter_mut().zip(&file_options) {
+            crate::lints::retain_issues(&options.lints, &options.filter, std::slice::from_mut(result));
+        }
+        Ok(results)
+    }
+
+    /// Apply fixes to a project and record them in its journal
+    fn apply(params: ApplyParams) -> Result<ApplyResponse> {
+        let fixes = params.fixes.into_iter()
+            .map(|mut fix| {
+                if fix.file_path.is_relative() {
+                    fix.file_path = params.path.join(&fix.file_path);
+                }
+                fix
+            })
+            .collect();
+
+        let coalesced = coalesce_modifications(fixes);
+        let changes = apply_modifications(&coalesced.modifications, params.backup)?;
+        let entries = Journal::open(&params.path).record(&changes)?;
+
+        Ok(ApplyResponse {
+            changes,
+            conflicts: coalesced.conflicts,
+            journal_ids: entries.into_iter().map(|entry| entry.id).collect(),
+        })
+    }
+
+    /// Generate and validate fixes for the issues of a project
+    async fn fix(self: Arc<Self>, params: FixParams) -> Result<FixPipelineReport> {
+        let factory = self.options.ai_client.clone()
+            .ok_or_else(|| RustAiToolError::Offline("fix".to_string()))?;
+
+        let analyze_params = AnalyzeParams { path: params.path.clone(), files: None, changed: false, base: None };
+        let daemon = self.clone();
+        let results = blocking(move || daemon.analyze(&analyze_params)).await?;
+
+        let project_path = params.path.canonicalize().map_err(RustAiToolError::Io)?;
+        let cached_client = self.clients.lock().unwrap().get(&project_path).cloned();
+        let client = match cached_client {
+            Some(client) => client,
+            None => {
+                let client = Arc::new(factory(&project_path)?);
+                self.clients.lock().unwrap().insert(project_path.clone(), client.clone());
+                client
+            }
+        };
+
+        let options = FixPipelineOptions {
+            max_refinements: params.max_refinements,
+            validation: self.options.validation_options.clone(),
+            project_path: project_path.clone(),
+            concurrency: self.options.fix_concurrency,
+            generate_tests: params.with_tests,
+            checkpoint: None,
+        };
+
+        // The pipeline's future is not `Send`, so it runs on a thread of its own
+        let runtime = tokio::runtime::Handle::current();
+        blocking(move || runtime.block_on(run_fix_pipeline(&results, &client, &options))).await
+    }
+}
+
+/// Fingerprint of the sources and manifests Clippy checks
+fn project_fingerprint(project_path: &Path, files: &[PathBuf]) -> String {
+    let manifests = [project_path.join("Cargo.toml"), project_path.join("Cargo.lock")];
+    let mut fingerprint = String::new();
+
+    for path in files.iter().chain(&manifests) {
+        // Cargo.lock is missing until the first build
+        if let Ok(content) = fs::read_to_string(path) {
+            fingerprint.push_str(&format!("{}:{}\n", path.display(), content_hash(&content)));
+        }
+    }
+
+    content_hash(&fingerprint)
+}
+
+/// Run blocking work, such as analysis or Cargo, off the server's threads
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| RustAiToolError::Other(format!("Request failed: {}", e)))?
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError { code: INVALID_PARAMS, message: format!("Invalid params: {}", e) })
+}
+
+fn to_result<T: Serialize>(value: T) -> std::result::Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::from(RustAiToolError::Json(e)))
+}
+
+fn rpc_response(id: Value, outcome: std::result::Result<Value, RpcError>) -> Value {
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CustomRule;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_daemon_requests() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub fn a() { todo!() }\n").unwrap();
+
+        let options = DaemonOptions {
+            analysis_options: AnalysisOptions {
+                run_clippy: false,
+                use_rust_analyzer: false,
+                custom_rules: vec![CustomRule {
+                    name: "no-todo".to_string(),
+                    pattern: "todo!".to_string(),
+                    message: "Unfinished code".to_string(),
+                    severity: crate::Severity::Warning,
+                }],
+                analyzers: Vec::new(),
+                filter: Default::default(),
+                lints: Default::default(),
+                policy: None,
+            },
+            validation_options: ValidationOptions {
+                syntax_only: true,
+                tauri_compatibility: false,
+                security_validation: false,
+                protected_paths: Vec::new(),
+            },
+            fix_concurrency: 1,
+            ai_client: None,
+        };
+        let (sender, _receiver) = oneshot::channel();
+        let daemon = Arc::new(Daemon::new(options, sender));
+
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze", "params": { "path": dir.path() } });
+        let response = handle_rpc(daemon.clone(), request.to_string().as_bytes()).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"][0]["issues"].as_array().unwrap().len(), 1);
+
+        // Changed files are analyzed again, unchanged ones come from the cache
+        let file_path = dir.path().join("src/lib.rs").canonicalize().unwrap();
+        daemon.cache.lock().unwrap().files.get_mut(&file_path).unwrap().1.issues.clear();
+        let response = handle_rpc(daemon.clone(), request.to_string().as_bytes()).await;
+        assert!(response["result"][0]["issues"].as_array().unwrap().is_empty());
+        fs::write(&file_path, "pub fn a() { todo!(); todo!() }\n").unwrap();
+        let response = handle_rpc(daemon.clone(), request.to_string().as_bytes()).await;
+        assert_eq!(response["result"][0]["issues"].as_array().unwrap().len(), 2);
+
+        let response = handle_rpc(daemon.clone(), br#"{"jsonrpc": "2.0", "id": 2, "method": "fix", "params": {"path": "."}}"#).await;
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        let response = handle_rpc(daemon.clone(), br#"{"jsonrpc": "2.0", "id": 3, "method": "analyze", "params": {}}"#).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = handle_rpc(daemon.clone(), br#"{"jsonrpc": "2.0", "id": 4, "method": "format"}"#).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        let response = handle_rpc(daemon, b"{").await;
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+    }
+}
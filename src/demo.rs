@@ -0,0 +1,177 @@
+//! Demo module
+//!
+//! This module bundles a small sample project with seeded issues and canned
+//! AI fixes, so the whole analyze → fix → apply pipeline can be shown without
+//! any network access or configuration.
+
+use crate::modification::{create_modification, CodeModification};
+use crate::{CustomRule, Result, RustAiToolError, Severity};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+
+/// A file of the bundled sample project
+struct DemoFile {
+    /// Path relative to the project root
+    path: &'static str,
+
+    /// File content
+    content: &'static str,
+}
+
+/// A canned AI response fixing one file of the sample project
+struct CannedFix {
+    /// Path relative to the project root
+    path: &'static str,
+
+    /// Description of the fix
+    description: &'static str,
+
+    /// Confidence level (0-100)
+    confidence: u8,
+
+    /// Content of the file after the fix
+    fixed_content: &'static str,
+}
+
+/// File marking a directory as an unpacked demo project, whose sample files `--force` overwrites
+const DEMO_MARKER: &str = ".rust-ai-tool-demo";
+
+/// Files of the sample project
+const SAMPLE_FILES: &[DemoFile] = &[
+    DemoFile { path: "Cargo.toml", content: include_str!("../demo/sample/Cargo.toml.in") },
+    DemoFile { path: "README.md", content: include_str!("../demo/sample/README.md") },
+    DemoFile { path: "src/main.rs", content: include_str!("../demo/sample/src/main.rs") },
+    DemoFile { path: "src/stats.rs", content: include_str!("../demo/sample/src/stats.rs") },
+];
+
+/// Canned fixes for the seeded issues
+const CANNED_FIXES: &[CannedFix] = &[
+    CannedFix {
+        path: "src/main.rs",
+        description: "Replace unwrap() with explicit error handling, drop the needless return and use is_empty()",
+        confidence: 92,
+        fixed_content: include_str!("../demo/fixtures/main.rs"),
+    },
+    CannedFix {
+        path: "src/stats.rs",
+        description: "Take a slice instead of &Vec, iterate instead of indexing and handle empty input",
+        confidence: 88,
+        fixed_content: include_str!("../demo/fixtures/stats.rs"),
+    },
+];
+
+/// Unpack the sample project into a directory
+///
+/// # Arguments
+///
+/// * `target_dir` - Directory to create the sample project in
+/// * `force` - Whether to overwrite the sample files of an earlier demo in the directory
+///
+/// # Returns
+///
+/// Path to the unpacked project
+pub fn unpack_demo_project(target_dir: &Path, force: bool) -> Result<PathBuf> {
+    let is_empty = match fs::read_dir(target_dir) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+        Err(e) => return Err(RustAiToolError::Io(e)),
+    };
+
+    if !is_empty {
+        if !target_dir.join(DEMO_MARKER).is_file() {
+            return Err(RustAiToolError::Other(format!(
+                "{} is not empty and does not hold a demo project; choose another directory",
+                target_dir.display()
+            )));
+        }
+        if !force {
+            return Err(RustAiToolError::Other(format!(
+                "Demo directory {} already exists (use --force to overwrite its sample files)",
+                target_dir.display()
+            )));
+        }
+    }
+
+    info!("Unpacking demo project to {}", target_dir.display());
+
+    for file in SAMPLE_FILES {
+        let path = target_dir.join(file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        debug!("Writing {}", path.display());
+        fs::write(&path, file.content)
+            .map_err(RustAiToolError::Io)?;
+    }
+
+    fs::write(target_dir.join(DEMO_MARKER), "Sample project unpacked by `rust-ai-tool demo`\n")
+        .map_err(RustAiToolError::Io)?;
+
+    Ok(target_dir.to_path_buf())
+}
+
+/// Custom rules used by the demo analysis
+pub fn demo_rules() -> Vec<CustomRule> {
+    vec![CustomRule {
+        name: "no-unwrap".to_string(),
+        pattern: r"\.unwrap\(\)".to_string(),
+        message: "Avoid unwrap() in application code; handle the error explicitly".to_string(),
+        severity: Severity::Warning,
+    }]
+}
+
+/// Build the modifications proposed by the canned AI fixtures
+///
+/// # Arguments
+///
+/// * `project_dir` - Path to the unpacked demo project
+///
+/// # Returns
+///
+/// Modifications against the current content of the demo project
+pub fn demo_modifications(project_dir: &Path) -> Result<Vec<CodeModification>> {
+    let mut modifications = Vec::new();
+
+    for fix in CANNED_FIXES {
+        let file_path = project_dir.join(fix.path);
+        let original_content = fs::read_to_string(&file_path)
+            .map_err(RustAiToolError::Io)?;
+
+        modifications.push(create_modification(
+            file_path,
+            original_content,
+            fix.fixed_content.to_string(),
+            fix.description.to_string(),
+            fix.confidence,
+        ));
+    }
+
+    Ok(modifications)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_unpack_only_over_demo_projects() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "keep me").unwrap();
+        assert!(unpack_demo_project(dir.path(), true).is_err());
+        assert!(dir.path().join("notes.txt").is_file());
+
+        let demo_dir = dir.path().join("demo");
+        unpack_demo_project(&demo_dir, false).unwrap();
+        fs::write(demo_dir.join("src/main.rs"), "// edited").unwrap();
+        fs::write(demo_dir.join("extra.rs"), "// kept").unwrap();
+        assert!(unpack_demo_project(&demo_dir, false).is_err());
+
+        unpack_demo_project(&demo_dir, true).unwrap();
+        assert_eq!(fs::read_to_string(demo_dir.join("src/main.rs")).unwrap(), SAMPLE_FILES[2].content);
+        assert!(demo_dir.join("extra.rs").is_file());
+    }
+}
@@ -0,0 +1,621 @@
+//! Dependency updates module
+//!
+//! This module advises on updating the crates.io dependencies of a project:
+//! - The versions of every crate come from the crates.io sparse index, and the
+//!   version the project uses from its `Cargo.lock` (or the lowest version its
+//!   requirement allows when there is no lock file)
+//! - Dependencies whose latest release is not allowed by their requirement
+//!   (a new major version, or minor version before 1.0) are reported as
+//!   outdated, and dependencies locked to a yanked version as security issues,
+//!   with the lints `dependencies::outdated` and `dependencies::yanked`
+//! - Updates of requirements are turned into `CodeModification`s of the
+//!   manifest, which keep its formatting and can be reviewed and applied like
+//!   any other fix
+//! - Optionally, the AI model summarizes the breaking changes listed in the
+//!   changelog of the crate's GitHub repository for the skipped versions
+//! - Compatible updates are left to `cargo update` and not reported
+
+use crate::analysis::{AnalysisResult, CodeFix, CodeIssue, IssueCategory};
+use crate::lockfile::Lockfile;
+use crate::manifest::Manifest;
+use crate::models::AiModelClient;
+use crate::modification::{CodeModification, IssueReference};
+use crate::supply_chain::manifest_entries;
+use crate::{Result, RustAiToolError, Severity};
+use regex::Regex;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use log::{debug, info, warn};
+
+/// URL of the crates.io sparse index
+const INDEX_URL: &str = "https://index.crates.io";
+
+/// URL of the crates.io API
+const API_URL: &str = "https://crates.io/api/v1";
+
+/// URL of raw files of GitHub repositories
+const RAW_GITHUB_URL: &str = "https://raw.githubusercontent.com";
+
+/// Changelog files looked up in repositories, in order
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "CHANGES.md", "RELEASES.md"];
+
+/// Longest changelog excerpt sent to the model
+const MAX_CHANGELOG_LENGTH: usize = 20_000;
+
+/// Client of the crates.io index and API
+pub struct CratesIoClient {
+    /// HTTP client for requests
+    client: reqwest::Client,
+
+    /// URL of the sparse index
+    index_url: String,
+
+    /// URL of the API
+    api_url: String,
+}
+
+/// Release of a crate in the index
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexVersion {
+    /// Version number
+    #[serde(rename = "vers")]
+    pub version: String,
+
+    /// Whether the release was yanked
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// Update advised for a dependency
+#[derive(Debug, Clone)]
+pub struct DependencyUpdate {
+    /// Path of the dependency table, like `["dev-dependencies"]`
+    pub table: Vec<String>,
+
+    /// Key of the dependency in the table
+    pub key: String,
+
+    /// Name of the crate
+    pub name: String,
+
+    /// Version requirement in the manifest
+    pub requirement: String,
+
+    /// Version the project uses
+    pub current: Version,
+
+    /// Latest release
+    pub latest: Version,
+
+    /// Whether the version the project uses was yanked
+    pub yanked: bool,
+
+    /// Breaking changes between the two versions, summarized by the AI model
+    pub breaking_changes: Option<String>,
+}
+
+impl DependencyUpdate {
+    /// Whether the requirement must change to use the latest release
+    pub fn is_breaking(&self) -> bool {
+        VersionReq::parse(&self.requirement).map_or(true, |requirement| !requirement.matches(&self.latest))
+    }
+
+    /// Requirement allowing the latest release, written with the precision of the current one
+    ///
+    /// `None` for requirements that are not a single version, like `>=1.2, <1.5`.
+    pub fn new_requirement(&self) -> Option<String> {
+        let requirement = self.requirement.trim();
+        let version = requirement.trim_start_matches(['^', '~', '=']).trim();
+        let operator = requirement[..requirement.len() - version.len()].trim();
+        if !["", "^", "~", "="].contains(&operator) || version.contains([',', '*', '<', '>']) {
+            return None;
+        }
+
+        // Build metadata is ignored by requirements
+        let latest = Version { build: semver::BuildMetadata::EMPTY, ..self.latest.clone() };
+        let updated = match version.split('.').count() {
+            1 => format!("{}{}", operator, latest.major),
+            2 => format!("{}{}.{}", operator, latest.major, latest.minor),
+            _ => format!("{}{}", operator, latest),
+        };
+
+        // Short requirements cannot name pre-releases
+        match VersionReq::parse(&updated) {
+            Ok(parsed) if parsed.matches(&latest) => Some(updated),
+            _ => Some(format!("{}{}", operator, latest)),
+        }
+    }
+}
+
+impl fmt::Display for DependencyUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} -> {}", self.name, self.current, self.latest)?;
+        if self.yanked {
+            write!(f, " (yanked)")?;
+        }
+        if self.is_breaking() {
+            write!(f, " (requirement {} to {})", self.requirement, self.new_requirement().unwrap_or_else(|| "update by hand".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl CratesIoClient {
+    /// Create a client of crates.io
+    ///
+    /// # Returns
+    ///
+    /// The client
+    pub fn new() -> Result<Self> {
+        // crates.io refuses requests without a user agent naming the tool
+        let client = crate::network::client_builder()
+            .user_agent(concat!("rust-ai-tool/", env!("CARGO_PKG_VERSION"), " (https://github.com/ibra2000sd/rust-ai-tool)"))
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| RustAiToolError::Registry(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            index_url: INDEX_URL.to_string(),
+            api_url: API_URL.to_string(),
+        })
+    }
+
+    /// Use another sparse index (e.g. a mirror of crates.io)
+    pub fn with_index_url(mut self, index_url: &str) -> Self {
+        self.index_url = index_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Releases of a crate, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Crate name
+    ///
+    /// # Returns
+    ///
+    /// Every release, yanked ones included
+    pub async fn versions(&self, name: &str) -> Result<Vec<IndexVersion>> {
+        let url = format!("{}/{}", self.index_url, index_path(name));
+        let response = self.client.get(&url)
+            .send()
+            .await
+            .map_err(|e| RustAiToolError::Registry(e.to_string()))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(RustAiToolError::Registry(format!("No crate named {} on crates.io", name)));
+        }
+        if !status.is_success() {
+            return Err(RustAiToolError::Registry(format!("crates.io index returned {} for {}", status, name)));
+        }
+
+        let body = response.text()
+            .await
+            .map_err(|e| RustAiToolError::Registry(e.to_string()))?;
+
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line)
+                .map_err(|e| RustAiToolError::Registry(format!("Invalid index entry of {}: {}", name, e))))
+            .collect()
+    }
+
+    /// Repository URL of a crate, if it declares one
+    pub async fn repository(&self, name: &str) -> Result<Option<String>> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(rename = "crate")]
+            krate: CrateInfo,
+        }
+        #[derive(Deserialize)]
+        struct CrateInfo {
+            repository: Option<String>,
+        }
+
+        let response: Response = self.client.get(format!("{}/crates/{}", self.api_url, name))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| RustAiToolError::Registry(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| RustAiToolError::Registry(format!("Invalid crates.io response for {}: {}", name, e)))?;
+
+        Ok(response.krate.repository)
+    }
+
+    /// Changelog of a GitHub repository, from its default branch
+    ///
+    /// # Arguments
+    ///
+    /// * `repository` - Repository URL, like `https://github.com/serde-rs/serde`
+    ///
+    /// # Returns
+    ///
+    /// The changelog, or `None` for repositories outside GitHub or without one
+    pub async fn changelog(&self, repository: &str) -> Result<Option<String>> {
+        let Some((owner, repo)) = github_repository(repository) else {
+            debug!("Not looking for a changelog outside GitHub: {}", repository);
+            return Ok(None);
+        };
+
+        for file in CHANGELOG_FILES {
+            let response = self.client.get(format!("{}/{}/{}/HEAD/{}", RAW_GITHUB_URL, owner, repo, file))
+                .send()
+                .await
+                .map_err(|e| RustAiToolError::Registry(e.to_string()))?;
+
+            if response.status().is_success() {
+                let changelog = response.text()
+                    .await
+                    .map_err(|e| RustAiToolError::Registry(e.to_string()))?;
+                return Ok(Some(changelog));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Find the dependencies of a project that are outdated or locked to a yanked version
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory, with a `Cargo.toml`
+/// * `registry` - crates.io client
+///
+/// # Returns
+///
+/// The advised updates, in the order of the manifest
+pub async fn find_updates(project_path: &Path, registry: &CratesIoClient) -> Result<Vec<DependencyUpdate>> {
+    let manifest = Manifest::load(&project_path.join("Cargo.toml"))?;
+    let locked = locked_versions(project_path)?;
+    let mut releases: HashMap<String, Vec<IndexVersion>> = HashMap::new();
+    let mut updates = Vec::new();
+
+    for dependency in manifest.dependencies() {
+        let Some(requirement) = dependency.requirement.filter(|_| dependency.crates_io) else {
+            continue;
+        };
+        let Ok(parsed) = VersionReq::parse(&requirement) else {
+            warn!("Skipping {}: invalid version requirement {}", dependency.key, requirement);
+            continue;
+        };
+
+        let current = locked.get(&dependency.package)
+            .and_then(|versions| versions.iter().filter(|version| parsed.matches(version)).max().cloned())
+            .or_else(|| lowest_version(&requirement));
+        let Some(current) = current else {
+            debug!("Skipping {}: no version of it is known", dependency.key);
+            continue;
+        };
+
+        if !releases.contains_key(&dependency.package) {
+            debug!("Fetching the releases of {}", dependency.package);
+            let versions = registry.versions(&dependency.package).await?;
+            releases.insert(dependency.package.clone(), versions);
+        }
+
+        if let Some(update) = advise(dependency.table, dependency.key, &dependency.package, requirement, current, &releases[&dependency.package]) {
+            updates.push(update);
+        }
+    }
+
+    info!("{} dependencies have updates to look at", updates.len());
+    Ok(updates)
+}
+
+/// Report advised updates as issues of the manifest of a project
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+/// * `updates` - Updates found by `find_updates`
+///
+/// # Returns
+///
+/// The result of the manifest, with one issue per update
+pub fn update_issues(project_path: &Path, updates: &[DependencyUpdate]) -> Result<AnalysisResult> {
+    let manifest_path = project_path.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).map_err(RustAiToolError::Io)?;
+
+    Ok(AnalysisResult {
+        issues: updates.iter().map(|update| issue_of(&manifest_path, &manifest, update)).collect(),
+        file_path: manifest_path,
+        errors: Vec::new(),
+        success: true,
+    })
+}
+
+/// Turn the requirement changes of advised updates into modifications of the manifest
+///
+/// Every update is one modification of the original manifest; they are merged when applied.
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+/// * `updates` - Updates found by `find_updates`
+///
+/// # Returns
+///
+/// One modification per update that needs a new requirement
+pub fn update_modifications(project_path: &Path, updates: &[DependencyUpdate]) -> Result<Vec<CodeModification>> {
+    let manifest_path = project_path.join("Cargo.toml");
+    let original = fs::read_to_string(&manifest_path).map_err(RustAiToolError::Io)?;
+    let mut modifications = Vec::new();
+
+    for update in updates.iter().filter(|update| update.is_breaking()) {
+        let Some(requirement) = update.new_requirement() else {
+            warn!("Not updating {}: its requirement {} must be changed by hand", update.key, update.requirement);
+            continue;
+        };
+
+        let mut manifest = Manifest::parse(&original)?;
+        let table: Vec<&str> = update.table.iter().map(String::as_str).collect();
+        manifest.set_dependency_requirement(&table, &update.key, &requirement)?;
+
+        let mut description = format!("Update {} from {} to {} (latest release: {})", update.name, update.requirement, requirement, update.latest);
+        if let Some(breaking_changes) = &update.breaking_changes {
+            description.push_str(&format!("\n\nBreaking changes:\n{}", breaking_changes.trim()));
+        }
+
+        modifications.push(CodeModification {
+            file_path: manifest_path.clone(),
+            original_content: original.clone(),
+            modified_content: manifest.to_string(),
+            description,
+            confidence: if update.latest.major == update.current.major { 70 } else { 60 },
+            resolves: vec![IssueReference::from(&issue_of(&manifest_path, &original, update))],
+        });
+    }
+
+    Ok(modifications)
+}
+
+/// Summarize the breaking changes of updates needing a new requirement
+///
+/// Updates whose crate has no changelog on GitHub are left without a summary;
+/// failures are warned about and skipped.
+///
+/// # Arguments
+///
+/// * `updates` - Advised updates, updated in place
+/// * `registry` - crates.io client, to find the changelogs
+/// * `client` - AI model client writing the summaries
+///
+/// # Returns
+///
+/// Number of updates that got a summary
+pub async fn summarize_breaking_changes(updates: &mut [DependencyUpdate], registry: &CratesIoClient, client: &AiModelClient) -> usize {
+    let mut summarized = 0;
+
+    for update in updates.iter_mut().filter(|update| update.is_breaking()) {
+        let changelog = match registry.repository(&update.name).await {
+            Ok(Some(repository)) => registry.changelog(&repository).await,
+            Ok(None) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let excerpt = match changelog {
+            Ok(changelog) => changelog.and_then(|changelog| changelog_excerpt(&changelog, &update.current, &update.latest)),
+            Err(e) => {
+                warn!("Failed to get the changelog of {}: {}", update.name, e);
+                continue;
+            }
+        };
+        let Some(excerpt) = excerpt else {
+            info!("No changelog entries found for {} {} to {}", update.name, update.current, update.latest);
+            continue;
+        };
+
+        match client.summarize_breaking_changes(&update.name, &update.current.to_string(), &update.latest.to_string(), &excerpt).await {
+            Ok(summary) => {
+                update.breaking_changes = Some(summary.trim().to_string());
+                summarized += 1;
+            }
+            Err(e) => warn!("Failed to summarize the changes of {}: {}", update.name, e),
+        }
+    }
+
+    summarized
+}
+
+/// Update of a dependency, if it is outdated or locked to a yanked version
+fn advise(table: Vec<String>, key: String, name: &str, requirement: String, current: Version, releases: &[IndexVersion]) -> Option<DependencyUpdate> {
+    let yanked = releases.iter().any(|release| release.yanked && Version::parse(&release.version).is_ok_and(|version| version == current));
+
+    // Pre-releases are only advised to projects already using one
+    let latest = releases.iter()
+        .filter(|release| !release.yanked)
+        .filter_map(|release| Version::parse(&release.version).ok())
+        .filter(|version| version.pre.is_empty() || !current.pre.is_empty())
+        .max()?;
+
+    let update = DependencyUpdate { table, key, name: name.to_string(), requirement, current, latest, yanked, breaking_changes: None };
+    (update.yanked || (update.latest > update.current && update.is_breaking())).then_some(update)
+}
+
+/// Issue of the manifest for an advised update
+fn issue_of(manifest_path: &Path, manifest: &str, update: &DependencyUpdate) -> CodeIssue {
+    let entry = manifest_entries(manifest).remove(&update.key);
+    let new_requirement = update.new_requirement().filter(|_| update.is_breaking());
+
+    let (category, severity, lint, message) = if update.yanked {
+        let advice = match &new_requirement {
+            Some(requirement) => format!("require {} instead", requirement),
+            None => format!("run `cargo update -p {}`", update.name),
+        };
+        (
+            IssueCategory::Security,
+            Severity::Error,
+            "dependencies::yanked",
+            format!("{} {} was yanked from crates.io; {} to use {}", update.name, update.current, advice, update.latest),
+        )
+    } else {
+        (
+            IssueCategory::CodeQuality,
+            Severity::Warning,
+            "dependencies::outdated",
+            format!("{} {} is outdated: {} does not allow the latest release, {}", update.name, update.current, update.requirement, update.latest),
+        )
+    };
+
+    let suggested_fix = entry.as_ref()
+        .and_then(|entry| entry.inline.as_ref())
+        .zip(new_requirement.as_ref())
+        .filter(|(line, _)| line.contains(&format!("\"{}\"", update.requirement)))
+        .map(|(line, requirement)| {
+            let mut description = format!("Require {} {}; code using it may need changes", update.name, requirement);
+            if let Some(breaking_changes) = &update.breaking_changes {
+                description.push_str(&format!("\n\nBreaking changes:\n{}", breaking_changes.trim()));
+            }
+
+            CodeFix {
+                original_code: line.clone(),
+                replacement_code: line.replacen(&format!("\"{}\"", update.requirement), &format!("\"{}\"", requirement), 1),
+                confidence: if update.latest.major == update.current.major { 70 } else { 60 },
+                description,
+            }
+        });
+
+    CodeIssue {
+        file_path: manifest_path.to_path_buf(),
+        line_start: entry.as_ref().map_or(1, |entry| entry.line),
+        column_start: 1,
+        line_end: entry.as_ref().map_or(1, |entry| entry.line),
+        column_end: entry.as_ref().and_then(|entry| entry.inline.as_ref()).map_or(1, |line| line.len() + 1),
+        category,
+        severity,
+        message,
+        suggested_fix,
+        priority: None,
+        lint: Some(lint.to_string()),
+        owners: Vec::new(),
+    }
+}
+
+/// Versions of every package in the lock file of a project or its workspace
+fn locked_versions(project_path: &Path) -> Result<HashMap<String, Vec<Version>>> {
+    let Some(lockfile) = Lockfile::find(project_path)? else {
+        debug!("No Cargo.lock found; using the lowest versions the requirements allow");
+        return Ok(HashMap::new());
+    };
+
+    let mut versions: HashMap<String, Vec<Version>> = HashMap::new();
+    for package in lockfile.packages {
+        if let Ok(version) = Version::parse(&package.version) {
+            versions.entry(package.name).or_default().push(version);
+        }
+    }
+    Ok(versions)
+}
+
+/// Lowest version a single-version requirement allows, like 1.2.0 for `^1.2`
+fn lowest_version(requirement: &str) -> Option<Version> {
+    let version = requirement.trim().trim_start_matches(['^', '~', '=']).trim();
+    let mut parts: Vec<&str> = version.split('.').collect();
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    Version::parse(&parts.join(".")).ok()
+}
+
+/// Path of a crate in the sparse index, like `se/rd/serde`
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Owner and name of a GitHub repository URL
+fn github_repository(url: &str) -> Option<(String, String)> {
+    let path = url.trim_end_matches('/').trim_end_matches(".git");
+    let path = path.strip_prefix("https://github.com/").or_else(|| path.strip_prefix("http://github.com/"))?;
+    let mut parts = path.split('/');
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+/// Entries of a changelog for the versions after `current` up to `latest`
+///
+/// Sections start at headings naming a version, like `## [2.0.0] - 2024-01-01`
+/// or `# v2.0.0`; the excerpt ends at the section of `current` or an older version.
+fn changelog_excerpt(changelog: &str, current: &Version, latest: &Version) -> Option<String> {
+    let heading_version = Regex::new(r"v?(\d+\.\d+\.\d+(?:-[0-9A-Za-z.]+)?)").ok()?;
+    let mut excerpt = String::new();
+    let mut collecting = false;
+
+    for line in changelog.lines() {
+        if line.starts_with('#') {
+            let version = heading_version.captures(line).and_then(|captures| Version::parse(&captures[1]).ok());
+            match version {
+                Some(version) if version <= *current && collecting => break,
+                Some(version) if version > *current => collecting = version <= *latest,
+                _ => {}
+            }
+        }
+
+        if collecting {
+            excerpt.push_str(line);
+            excerpt.push('\n');
+            if excerpt.len() > MAX_CHANGELOG_LENGTH {
+                break;
+            }
+        }
+    }
+
+    let excerpt: String = excerpt.chars().take(MAX_CHANGELOG_LENGTH).collect();
+    (!excerpt.trim().is_empty()).then_some(excerpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str, yanked: bool) -> IndexVersion {
+        IndexVersion { version: version.to_string(), yanked }
+    }
+
+    #[test]
+    fn test_advise_updates() {
+        let releases = [release("0.9.3", false), release("0.10.1", true), release("0.10.2", false), release("0.11.0", false), release("0.12.0-rc.1", false)];
+        let advise_for = |requirement: &str, current: &str| advise(
+            vec!["dependencies".to_string()], "foo".to_string(), "foo", requirement.to_string(), Version::parse(current).unwrap(), &releases,
+        );
+
+        let update = advise_for("0.9", "0.9.3").unwrap();
+        assert_eq!(update.latest, Version::parse("0.11.0").unwrap());
+        assert_eq!(update.new_requirement().as_deref(), Some("0.11"));
+        assert_eq!(update.to_string(), "foo 0.9.3 -> 0.11.0 (requirement 0.9 to 0.11)");
+        assert_eq!(advise_for("=0.9.3", "0.9.3").unwrap().new_requirement().as_deref(), Some("=0.11.0"));
+        assert_eq!(advise_for(">=0.9, <0.10", "0.9.3").unwrap().new_requirement(), None);
+        assert!(advise_for("0.11", "0.11.0").is_none());
+        let with_build = DependencyUpdate { latest: Version::parse("1.1.8+spec-1.1.0").unwrap(), ..advise_for("0.9.3", "0.9.3").unwrap() };
+        assert_eq!(with_build.new_requirement().as_deref(), Some("1.1.8"));
+
+        // A yanked version is an error, even without a newer compatible release
+        let yanked = advise_for("0.10", "0.10.1").unwrap();
+        assert!(yanked.yanked && yanked.is_breaking());
+        let manifest = "[package]\nname = \"app\"\n\n[dependencies]\nfoo = { version = \"0.10\", features = [\"std\"] }\n";
+        let issue = issue_of(Path::new("Cargo.toml"), manifest, &yanked);
+        assert_eq!((issue.line_start, issue.severity, issue.lint.as_deref()), (5, Severity::Error, Some("dependencies::yanked")));
+        assert_eq!(issue.message, "foo 0.10.1 was yanked from crates.io; require 0.11 instead to use 0.11.0");
+        assert_eq!(issue.suggested_fix.unwrap().replacement_code, "foo = { version = \"0.11\", features = [\"std\"] }");
+
+        assert_eq!(index_path("serde"), "se/rd/serde");
+        assert_eq!(index_path("syn"), "3/s/syn");
+        assert_eq!(github_repository("https://github.com/serde-rs/serde.git"), Some(("serde-rs".to_string(), "serde".to_string())));
+
+        let changelog = "# Changelog\n\n## [Unreleased]\n\n## [0.11.0] - 2024-03-01\n- Removed `Foo::bar`\n\n## 0.10.2\n- Fixed a panic\n\n## 0.9.3\n- Old\n";
+        let excerpt = changelog_excerpt(changelog, &Version::parse("0.9.3").unwrap(), &Version::parse("0.11.0").unwrap()).unwrap();
+        assert_eq!(excerpt, "## [0.11.0] - 2024-03-01\n- Removed `Foo::bar`\n\n## 0.10.2\n- Fixed a panic\n\n");
+    }
+}
@@ -0,0 +1,298 @@
+//! Directory config module
+//!
+//! This module reads `.rust-ai-tool.toml` files in subdirectories of a project,
+//! which override options for the files below them, like relaxed rules under
+//! `tests/` or stricter ones under `src-tauri/`:
+//! - A directory configuration may only set `analysis_options`,
+//!   `validation_options` and `lints`
+//! - Configurations are merged from the project down to the directory of a
+//!   file, deeper ones winning; tables are merged key by key and other values,
+//!   arrays included, replace the value of the parent, like profiles
+//! - Globs (`filter`, `protected_paths`) stay relative to the project
+//! - A directory configuration can turn Clippy off but not on, and cannot add
+//!   analyzer plugins, since both run code of the project; the run's settings
+//!   decide whether that is safe
+//! - The configuration of the project directory itself is not read here; it
+//!   is the configuration of the run
+
+use crate::config::{merge_tables, parse_table};
+use crate::journal::content_hash;
+use crate::lints::LintLevel;
+use crate::{AnalysisOptions, Result, RustAiToolError, ValidationOptions};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use toml::{Table, Value};
+
+/// Name of the configuration file of a directory
+pub const DIRECTORY_CONFIG_FILE: &str = ".rust-ai-tool.toml";
+
+/// Keys a directory configuration may set
+const DIRECTORY_KEYS: &[&str] = &["analysis_options", "validation_options", "lints"];
+
+/// Configuration of one directory
+#[derive(Debug, Clone)]
+struct DirectoryConfig {
+    /// Configuration file
+    path: PathBuf,
+
+    /// Content of the file, for fingerprints
+    content: String,
+
+    /// Parsed configuration
+    table: Table,
+}
+
+/// Directory configurations of a project, read on first use
+#[derive(Debug)]
+pub struct DirectoryConfigs {
+    /// Project directory
+    project_path: PathBuf,
+
+    /// Configuration of each directory looked at (`None` if it has none)
+    directories: Mutex<HashMap<PathBuf, Option<DirectoryConfig>>>,
+}
+
+impl DirectoryConfigs {
+    /// Directory configurations of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Project directory
+    ///
+    /// # Returns
+    ///
+    /// The configurations, read when options of a file are asked for
+    pub fn new(project_path: &Path) -> Self {
+        Self {
+            project_path: project_path.to_path_buf(),
+            directories: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Analysis options of a file, with the lints of the directory configurations
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - File of the project
+    /// * `base` - Analysis options of the run
+    ///
+    /// # Returns
+    ///
+    /// The options of the run if no directory configuration applies, or else
+    /// the options merged with them
+    pub fn analysis_options<'a>(&self, file_path: &Path, base: &'a AnalysisOptions) -> Result<Cow<'a, AnalysisOptions>> {
+        let configs = self.configs_for(file_path)?;
+        if configs.is_empty() {
+            return Ok(Cow::Borrowed(base));
+        }
+
+        let mut options: AnalysisOptions = merged_section(&configs, "analysis_options", base)?;
+        options.run_clippy &= base.run_clippy;
+        options.analyzers = base.analyzers.clone();
+        options.lints = base.lints.clone();
+        options.policy = base.policy.clone();
+        for config in &configs {
+            if let Some(lints) = config.table.get("lints") {
+                let lints: BTreeMap<String, LintLevel> = lints.clone().try_into()
+                    .map_err(|e| invalid_config(config, e))?;
+                options.lints.extend(lints);
+            }
+        }
+
+        Ok(Cow::Owned(options))
+    }
+
+    /// Validation options of a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - File of the project
+    /// * `base` - Validation options of the run
+    ///
+    /// # Returns
+    ///
+    /// The options of the run if no directory configuration applies, or else
+    /// the options merged with them
+    pub fn validation_options<'a>(&self, file_path: &Path, base: &'a ValidationOptions) -> Result<Cow<'a, ValidationOptions>> {
+        let configs = self.configs_for(file_path)?;
+        if configs.is_empty() {
+            return Ok(Cow::Borrowed(base));
+        }
+
+        merged_section(&configs, "validation_options", base).map(Cow::Owned)
+    }
+
+    /// Fingerprint of the directory configurations applying to a file
+    ///
+    /// # Returns
+    ///
+    /// A hash that changes when one of the configurations changes (empty if none applies)
+    pub fn fingerprint(&self, file_path: &Path) -> Result<String> {
+        let configs = self.configs_for(file_path)?;
+        if configs.is_empty() {
+            return Ok(String::new());
+        }
+
+        let contents: Vec<&str> = configs.iter().map(|config| config.content.as_str()).collect();
+        Ok(content_hash(&contents.join("\0")))
+    }
+
+    /// Configurations applying to a file, from the project down
+    fn configs_for(&self, file_path: &Path) -> Result<Vec<DirectoryConfig>> {
+        let Some(relative_dir) = self.relative_dir(file_path) else {
+            return Ok(Vec::new());
+        };
+
+        let mut directories = self.directories.lock().unwrap();
+        let mut configs = Vec::new();
+        let mut dir = self.project_path.clone();
+
+        for component in relative_dir.components() {
+            dir.push(component);
+
+            if !directories.contains_key(&dir) {
+                let config = read_config(&dir.join(DIRECTORY_CONFIG_FILE))?;
+                directories.insert(dir.clone(), config);
+            }
+            if let Some(Some(config)) = directories.get(&dir) {
+                configs.push(config.clone());
+            }
+        }
+
+        Ok(configs)
+    }
+
+    /// Directory of a file relative to the project, if it is in the project
+    fn relative_dir(&self, file_path: &Path) -> Option<PathBuf> {
+        let dir = file_path.parent()?;
+
+        if let Ok(relative) = dir.strip_prefix(&self.project_path) {
+            return Some(relative.to_path_buf());
+        }
+
+        // Absolute paths of a project given as a relative path, or through a symlink
+        let root = self.project_path.canonicalize().ok()?;
+        let dir = dir.canonicalize().ok()?;
+        dir.strip_prefix(root).ok().map(Path::to_path_buf)
+    }
+}
+
+/// Read the configuration of a directory, if it has one
+fn read_config(path: &Path) -> Result<Option<DirectoryConfig>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+    let table = parse_table(&content)
+        .map_err(|e| RustAiToolError::Other(format!("{}: {}", path.display(), e)))?;
+
+    if let Some(key) = table.keys().find(|key| !DIRECTORY_KEYS.contains(&key.as_str())) {
+        return Err(RustAiToolError::Other(format!(
+            "{}: `{}` cannot be set in a directory configuration (only {})",
+            path.display(),
+            key,
+            DIRECTORY_KEYS.join(", ")
+        )));
+    }
+
+    Ok(Some(DirectoryConfig { path: path.to_path_buf(), content, table }))
+}
+
+/// Options of a section with the values of the configurations merged into them
+fn merged_section<T: Serialize + DeserializeOwned>(configs: &[DirectoryConfig], section: &str, base: &T) -> Result<T> {
+    let mut table = match Value::try_from(base) {
+        Ok(Value::Table(table)) => table,
+        _ => return Err(RustAiToolError::Other(format!("Failed to merge {}", section))),
+    };
+
+    for config in configs {
+        match config.table.get(section) {
+            Some(Value::Table(overrides)) => merge_tables(&mut table, overrides.clone()),
+            Some(_) => return Err(invalid_config(config, format!("`{}` must be a table", section))),
+            None => {}
+        }
+    }
+
+    Value::Table(table).try_into().map_err(|e| {
+        let path = configs.iter().map(|config| config.path.display().to_string()).collect::<Vec<_>>();
+        RustAiToolError::Other(format!("Invalid {} in {}: {}", section, path.join(", "), e))
+    })
+}
+
+fn invalid_config(config: &DirectoryConfig, error: impl std::fmt::Display) -> RustAiToolError {
+    RustAiToolError::Other(format!("Invalid configuration {}: {}", config.path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_directory_configs() {
+        let dir = tempdir().unwrap();
+        let project = dir.path();
+        fs::create_dir_all(project.join("tests/ui")).unwrap();
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join(DIRECTORY_CONFIG_FILE), "[ai_model]\nmodel_type = \"Claude\"\n").unwrap();
+        fs::write(project.join("tests").join(DIRECTORY_CONFIG_FILE), concat!(
+            "[analysis_options]\nrun_clippy = false\n",
+            "[analysis_options.filter]\nmin_severity = \"Error\"\n",
+            "[lints]\n\"clippy::unwrap_used\" = \"allow\"\n",
+            "[validation_options]\nsecurity_validation = false\n",
+        )).unwrap();
+        fs::write(project.join("tests/ui").join(DIRECTORY_CONFIG_FILE), "[analysis_options.filter]\nrules = [\"no-todo\"]\n").unwrap();
+
+        let base = AnalysisOptions {
+            run_clippy: true,
+            use_rust_analyzer: true,
+            custom_rules: Vec::new(),
+            analyzers: Vec::new(),
+            filter: Default::default(),
+            lints: BTreeMap::from([("no-todo".to_string(), LintLevel::Deny)]),
+            policy: None,
+        };
+        let configs = DirectoryConfigs::new(project);
+
+        assert!(matches!(configs.analysis_options(&project.join("src/lib.rs"), &base).unwrap(), Cow::Borrowed(_)));
+        assert_eq!(configs.fingerprint(&project.join("src/lib.rs")).unwrap(), "");
+
+        let options = configs.analysis_options(&project.join("tests/ui/case.rs"), &base).unwrap();
+        assert!(!options.run_clippy && options.use_rust_analyzer);
+        assert_eq!(options.filter.min_severity, Some(Severity::Error));
+        assert_eq!(options.filter.rules, ["no-todo"]);
+        assert_eq!(options.lints.len(), 2);
+        assert_ne!(configs.fingerprint(&project.join("tests/ui/case.rs")).unwrap(), configs.fingerprint(&project.join("tests/it.rs")).unwrap());
+
+        let validation = ValidationOptions {
+            syntax_only: false,
+            tauri_compatibility: true,
+            security_validation: true,
+            protected_paths: Vec::new(),
+        };
+        let options = configs.validation_options(&project.join("tests/it.rs"), &validation).unwrap();
+        assert!(!options.security_validation && options.tauri_compatibility);
+
+        // Clippy and analyzer plugins run code of the project, so only the run enables them
+        fs::create_dir_all(project.join("examples")).unwrap();
+        fs::write(project.join("examples").join(DIRECTORY_CONFIG_FILE), concat!(
+            "[analysis_options]\nrun_clippy = true\n",
+            "[[analysis_options.analyzers]]\nname = \"evil\"\npath = \"examples/libevil.so\"\n",
+        )).unwrap();
+        let unsandboxed = AnalysisOptions { run_clippy: false, ..base.clone() };
+        let options = configs.analysis_options(&project.join("examples/demo.rs"), &unsandboxed).unwrap();
+        assert!(!options.run_clippy && options.analyzers.is_empty());
+
+        fs::create_dir_all(project.join("benches")).unwrap();
+        fs::write(project.join("benches").join(DIRECTORY_CONFIG_FILE), "offline = true\n").unwrap();
+        let error = configs.analysis_options(&project.join("benches/bench.rs"), &base).unwrap_err().to_string();
+        assert!(error.contains("`offline` cannot be set in a directory configuration"), "{}", error);
+    }
+}
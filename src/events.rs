@@ -0,0 +1,176 @@
+//! Events module
+//!
+//! This module reports the progress of a run as machine-readable events, for
+//! wrapper scripts and CI systems (`--log-format ndjson`):
+//! - One JSON object per line, naming the kind of event in `event`, like
+//!   `analysis-started`, `issue-found`, `fix-applied`, `error` or `summary`
+//! - Nothing is written until events are enabled; log records are then written
+//!   as `log` events too, so the stream holds nothing but events
+//! - Events go to stderr, next to the log, so stdout keeps the output of the
+//!   command, like a JSON report
+
+use crate::analysis::CodeIssue;
+use crate::summary::{RunStatus, RunSummary};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether events are written in this process
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Progress event of a run
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// Analysis of a project started
+    AnalysisStarted {
+        /// Project directory
+        project_path: &'a Path,
+
+        /// Number of files analyzed
+        files: usize,
+    },
+
+    /// Analysis found an issue
+    IssueFound {
+        /// The issue, with its fields next to `event`
+        #[serde(flatten)]
+        issue: &'a CodeIssue,
+    },
+
+    /// Analysis of a project finished
+    AnalysisFinished {
+        /// Number of files analyzed
+        files: usize,
+
+        /// Number of issues found
+        issues: usize,
+    },
+
+    /// A fix was written to a file
+    FixApplied {
+        /// Modified file
+        file_path: &'a Path,
+
+        /// Description of the fix
+        description: &'a str,
+
+        /// Backup of the previous content, as a file (if not backed up in git)
+        backup_path: Option<&'a Path>,
+
+        /// Git ref holding the previous content (if backed up in git)
+        backup_ref: Option<&'a str>,
+    },
+
+    /// Log record
+    Log {
+        /// Level, like `info` or `warn`
+        level: String,
+
+        /// Module that logged the record
+        target: &'a str,
+
+        /// Logged message
+        message: String,
+    },
+
+    /// The command failed
+    Error {
+        /// Error with its causes
+        message: String,
+    },
+
+    /// Last event of a run, with the outcome the exit code stands for
+    Summary {
+        /// Command that ran
+        command: &'a str,
+
+        /// Outcome of the run
+        status: RunStatus,
+
+        /// Exit code of the process
+        exit_code: i32,
+
+        /// Counts reported by the command
+        counts: BTreeMap<&'a str, usize>,
+    },
+}
+
+/// Start writing events in this process
+pub fn enable_events() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether events are written in this process
+pub fn events_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+impl<'a> From<&'a RunSummary> for Event<'a> {
+    fn from(summary: &'a RunSummary) -> Self {
+        Event::Summary {
+            command: &summary.command,
+            status: summary.status,
+            exit_code: summary.status.exit_code(),
+            counts: summary.counts.iter().map(|(name, value)| (name.as_str(), *value)).collect(),
+        }
+    }
+}
+
+/// Format an event as a line of NDJSON
+pub fn event_line(event: &Event) -> String {
+    serde_json::to_string(event).unwrap_or_default()
+}
+
+/// Write an event to stderr, if events are enabled
+pub fn emit(event: &Event) {
+    if !events_enabled() {
+        return;
+    }
+
+    // A closed stderr must not stop the run
+    writeln!(std::io::stderr().lock(), "{}", event_line(event)).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+    use crate::Severity;
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_event_lines() {
+        let issue = CodeIssue {
+            file_path: PathBuf::from("src/lib.rs"),
+            line_start: 3,
+            column_start: 5,
+            line_end: 3,
+            column_end: 9,
+            category: IssueCategory::Style,
+            severity: Severity::Warning,
+            message: "message".to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        };
+
+        let line = event_line(&Event::IssueFound { issue: &issue });
+        assert!(!line.contains('\n'));
+        let event: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["event"], "issue-found");
+        assert_eq!(event["file_path"], "src/lib.rs");
+        assert_eq!(event["line_start"], 3);
+
+        let event: Value = serde_json::from_str(&event_line(&Event::AnalysisStarted { project_path: Path::new("."), files: 2 })).unwrap();
+        assert_eq!(event["event"], "analysis-started");
+        assert_eq!(event["files"], 2);
+
+        let event: Value = serde_json::from_str(&event_line(&Event::Error { message: "failed".to_string() })).unwrap();
+        assert_eq!(event, serde_json::json!({ "event": "error", "message": "failed" }));
+    }
+}
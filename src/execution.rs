@@ -0,0 +1,260 @@
+//! Execution module
+//!
+//! This module runs the Cargo commands of Clippy, `cargo check`, `cargo metadata`,
+//! fix tests and project generation, and rustfmt, where the `[execution]` settings say:
+//! - On the host, with the toolchain of the project (the default)
+//! - In a Docker container of `docker_image`, pinning the toolchain and keeping
+//!   build scripts, proc macros and tests of untrusted code away from the host
+//!
+//! In the container, the project's workspace is mounted at the same path as on
+//! the host, so paths in Cargo's output stay valid, and commands run as the owner
+//! of the workspace, so build output stays owned by them. Dependencies are fetched
+//! into `target/rust-ai-tool/cargo-home` of the workspace with network access,
+//! which runs no build script; the command itself runs without network unless
+//! `docker_network` is set.
+
+use crate::ExecutionConfig;
+use log::{debug, warn};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+
+/// Settings applied to the Cargo commands of this process
+static SETTINGS: OnceLock<ExecutionConfig> = OnceLock::new();
+
+/// Environment variables passed from the host into the container
+const PASSED_ENV: &[&str] = &["CARGO_NET_OFFLINE", "http_proxy", "https_proxy", "HTTP_PROXY", "HTTPS_PROXY", "no_proxy", "NO_PROXY"];
+
+/// Apply execution settings to the Cargo commands of this process
+///
+/// Only the first call has an effect.
+///
+/// # Arguments
+///
+/// * `config` - Execution settings
+pub fn configure(config: &ExecutionConfig) {
+    if let Some(image) = &config.docker_image {
+        debug!("Running Cargo in Docker image {}", image);
+    }
+
+    let _ = SETTINGS.set(config.clone());
+}
+
+/// Whether Cargo commands run in a Docker container, keeping untrusted code off the host
+pub fn is_sandboxed() -> bool {
+    SETTINGS.get().is_some_and(|settings| settings.docker_image.is_some())
+}
+
+/// A Cargo command run in a project, on the host or in the configured container
+#[derive(Debug, Clone)]
+pub struct CargoCommand {
+    /// Directory the command runs in
+    project_dir: PathBuf,
+
+    /// Program of the toolchain to run, `cargo` unless set otherwise
+    program: String,
+
+    /// Arguments of the program
+    args: Vec<OsString>,
+
+    /// Target directory, instead of the one of the project
+    target_dir: Option<PathBuf>,
+}
+
+impl CargoCommand {
+    /// Create a Cargo command running in a directory
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            program: "cargo".to_string(),
+            args: Vec::new(),
+            target_dir: None,
+        }
+    }
+
+    /// Run another program of the toolchain, like `rustfmt`, instead of `cargo`
+    pub fn program(mut self, program: &str) -> Self {
+        self.program = program.to_string();
+        self
+    }
+
+    /// Add arguments of the program
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Build into another target directory, which is also mounted in the container
+    pub fn with_target_dir(mut self, target_dir: &Path) -> Self {
+        self.target_dir = Some(target_dir.to_path_buf());
+        self
+    }
+
+    /// Run the command and collect its output
+    ///
+    /// # Returns
+    ///
+    /// The output, or an error if Cargo (or Docker) could not be started
+    pub fn output(&self) -> io::Result<Output> {
+        match SETTINGS.get().filter(|settings| settings.docker_image.is_some()) {
+            Some(settings) => {
+                self.fetch_dependencies(settings);
+                self.docker_command(settings, &self.args, settings.docker_network)?.output()
+            }
+            None => self.host_command().output(),
+        }
+    }
+
+    /// Run the command without blocking the runtime and collect its output
+    ///
+    /// # Returns
+    ///
+    /// The output, or an error if Cargo (or Docker) could not be started
+    pub async fn output_async(&self) -> io::Result<Output> {
+        let command = self.clone();
+        tokio::task::spawn_blocking(move || command.output())
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    fn host_command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).current_dir(&self.project_dir);
+        if let Some(target_dir) = &self.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        command
+    }
+
+    /// `docker run` of Cargo with the workspace mounted at its host path
+    fn docker_command(&self, settings: &ExecutionConfig, args: &[OsString], network: bool) -> io::Result<Command> {
+        let project_dir = self.project_dir.canonicalize()?;
+        let workspace = workspace_root(&project_dir);
+        let cargo_home = workspace.join("target").join("rust-ai-tool").join("cargo-home");
+        fs::create_dir_all(&cargo_home)?;
+
+        let mut command = Command::new("docker");
+        command.args(["run", "--rm"])
+            .arg("--volume").arg(mount(&workspace))
+            .arg("--workdir").arg(&project_dir)
+            .arg("--env").arg(env_value("CARGO_HOME", &cargo_home));
+
+        if let Some(target_dir) = &self.target_dir {
+            fs::create_dir_all(target_dir)?;
+            let target_dir = target_dir.canonicalize()?;
+            if !target_dir.starts_with(&workspace) {
+                command.arg("--volume").arg(mount(&target_dir));
+            }
+            command.arg("--env").arg(env_value("CARGO_TARGET_DIR", &target_dir));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let metadata = fs::metadata(&workspace)?;
+            command.arg("--user").arg(format!("{}:{}", metadata.uid(), metadata.gid()));
+        }
+
+        if network {
+            for name in PASSED_ENV {
+                if std::env::var_os(name).is_some() {
+                    command.arg("--env").arg(name);
+                }
+            }
+        } else {
+            command.args(["--network", "none", "--env", "CARGO_NET_OFFLINE=true"]);
+        }
+
+        command.args(&settings.docker_args);
+        command.arg(settings.docker_image.as_deref().unwrap_or_default());
+        command.arg(&self.program).args(args);
+        Ok(command)
+    }
+
+    /// Fetch the dependencies of the project into the container's Cargo home
+    ///
+    /// Failures are only logged, as the command itself reports missing dependencies.
+    fn fetch_dependencies(&self, settings: &ExecutionConfig) {
+        if settings.docker_network || self.program != "cargo" || !self.project_dir.join("Cargo.toml").is_file() {
+            return;
+        }
+
+        let fetched = self.docker_command(settings, &[OsString::from("fetch")], true)
+            .and_then(|mut command| command.output());
+        match fetched {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => warn!("Failed to fetch dependencies in Docker: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Failed to run Docker to fetch dependencies: {}", e),
+        }
+    }
+}
+
+/// Outermost directory holding the project that is part of the same Cargo workspace,
+/// so path dependencies and the workspace manifest are visible in the container
+fn workspace_root(project_dir: &Path) -> PathBuf {
+    project_dir.ancestors()
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .last()
+        .unwrap_or(project_dir)
+        .to_path_buf()
+}
+
+/// `--volume` argument mounting a directory at the same path
+fn mount(dir: &Path) -> OsString {
+    let mut volume = dir.as_os_str().to_os_string();
+    volume.push(":");
+    volume.push(dir);
+    volume
+}
+
+/// `--env` argument setting a variable to a path
+fn env_value(name: &str, value: &Path) -> OsString {
+    let mut env = OsString::from(format!("{}=", name));
+    env.push(value);
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_docker_command() {
+        let dir = tempdir().unwrap();
+        let workspace = dir.path().canonicalize().unwrap();
+        let member = workspace.join("crates").join("app");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(workspace.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/app\"]\n").unwrap();
+        fs::write(member.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+
+        let settings = ExecutionConfig {
+            docker_image: Some("rust:1.79".to_string()),
+            docker_network: false,
+            docker_args: vec!["--memory".to_string(), "4g".to_string()],
+        };
+        let cargo = CargoCommand::new(&member).args(["clippy", "--message-format=json"]);
+        let command = cargo.docker_command(&settings, &cargo.args, false).unwrap();
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+
+        assert_eq!(command.get_program(), "docker");
+        let volume = format!("{}:{}", workspace.display(), workspace.display());
+        assert!(args.windows(2).any(|pair| pair == ["--volume", volume.as_str()]));
+        assert!(args.windows(2).any(|pair| pair[0] == "--workdir" && Path::new(&pair[1]) == member));
+        assert!(args.windows(2).any(|pair| pair == ["--network", "none"]));
+        assert!(args.ends_with(&["--memory", "4g", "rust:1.79", "cargo", "clippy", "--message-format=json"].map(String::from)));
+        assert!(workspace.join("target/rust-ai-tool/cargo-home").is_dir());
+
+        let rustfmt = CargoCommand::new(&member).program("rustfmt").args(["--edition", "2021"]);
+        let command = rustfmt.docker_command(&settings, &rustfmt.args, false).unwrap();
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert!(args.ends_with(&["rust:1.79", "rustfmt", "--edition", "2021"].map(String::from)));
+    }
+}
@@ -0,0 +1,332 @@
+//! Fix feedback module
+//!
+//! This module learns from how users treat proposed fixes:
+//! - One JSON line per reviewed hunk in `.rust-ai-tool/feedback.jsonl`
+//! - Outcomes from interactive apply (accepted, edited, skipped hunks) and
+//!   from pull request reviews (merged or closed pull requests)
+//! - Selection of accepted fixes similar to new issues, used as examples in fix prompts
+
+use crate::cli::HunkReview;
+use crate::journal::STATE_DIR;
+use crate::modification::{split_into_hunks, CodeModification, HunkDecision};
+use crate::{Result, RustAiToolError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::debug;
+
+/// File name of the feedback log inside the state directory
+pub const FEEDBACK_FILE: &str = "feedback.jsonl";
+
+/// Examples longer than this are not used in prompts
+const MAX_EXAMPLE_CHARS: usize = 2000;
+
+/// What the user did with a proposed change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FeedbackOutcome {
+    /// Applied as proposed
+    Accepted,
+
+    /// Applied after editing
+    Edited,
+
+    /// Not applied
+    Rejected,
+}
+
+/// Outcome of one proposed hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEntry {
+    /// Time the outcome was recorded (seconds since the Unix epoch)
+    pub timestamp: u64,
+
+    /// File the change was proposed for
+    pub file_path: PathBuf,
+
+    /// Description of the fix
+    pub description: String,
+
+    /// Where the outcome comes from (e.g. `interactive apply`, `pull request #12`)
+    pub source: String,
+
+    /// Original text of the hunk
+    pub original: String,
+
+    /// Proposed text of the hunk
+    pub proposed: String,
+
+    /// Text the user kept instead of the proposal, if they edited it
+    pub edited: Option<String>,
+
+    /// What the user did
+    pub outcome: FeedbackOutcome,
+}
+
+impl FeedbackEntry {
+    /// The text the user ended up with, if the change was kept
+    pub fn final_text(&self) -> Option<&str> {
+        match self.outcome {
+            FeedbackOutcome::Accepted => Some(&self.proposed),
+            FeedbackOutcome::Edited => self.edited.as_deref(),
+            FeedbackOutcome::Rejected => None,
+        }
+    }
+}
+
+/// Feedback log of a project
+pub struct FeedbackLog {
+    /// Path to the log file
+    path: PathBuf,
+}
+
+impl FeedbackLog {
+    /// Open the feedback log of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project
+    ///
+    /// # Returns
+    ///
+    /// The log (the file is created on the first recorded outcome)
+    pub fn open(project_path: &Path) -> Self {
+        Self {
+            path: project_path.join(STATE_DIR).join(FEEDBACK_FILE),
+        }
+    }
+
+    /// Append outcomes to the log
+    pub fn record(&self, entries: &[FeedbackEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(RustAiToolError::Io)?;
+
+        for entry in entries {
+            let line = serde_json::to_string(entry)
+                .map_err(RustAiToolError::Json)?;
+            writeln!(file, "{}", line)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        debug!("Recorded {} fix outcomes in {}", entries.len(), self.path.display());
+        Ok(())
+    }
+
+    /// Read all outcomes, oldest first
+    pub fn entries(&self) -> Result<Vec<FeedbackEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(RustAiToolError::Io)?;
+
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(RustAiToolError::Json))
+            .collect()
+    }
+}
+
+/// Outcomes of an interactive review
+///
+/// # Arguments
+///
+/// * `modifications` - Reviewed modifications
+/// * `decisions` - Decisions taken, in review order
+///
+/// # Returns
+///
+/// One outcome per reviewed hunk
+pub fn review_feedback(modifications: &[CodeModification], decisions: &[HunkReview]) -> Vec<FeedbackEntry> {
+    let mut decisions = decisions.iter();
+    let mut entries = Vec::new();
+
+    for modification in modifications {
+        for hunk in split_into_hunks(&modification.original_content, &modification.modified_content, 3) {
+            let Some(review) = decisions.next() else {
+                return entries;
+            };
+
+            let (outcome, edited) = match &review.decision {
+                HunkDecision::Accept => (FeedbackOutcome::Accepted, None),
+                HunkDecision::Edit(text) => (FeedbackOutcome::Edited, Some(text.clone())),
+                HunkDecision::Skip => (FeedbackOutcome::Rejected, None),
+            };
+
+            entries.push(feedback_entry(modification, "interactive apply", hunk.old_text, hunk.new_text, edited, outcome));
+        }
+    }
+
+    entries
+}
+
+/// Outcomes of a reviewed pull request
+///
+/// Hunks of a closed pull request were rejected. In a merged pull request,
+/// a hunk whose proposed text is still in the merged file was accepted; the
+/// others were edited during review.
+///
+/// # Arguments
+///
+/// * `modifications` - Modifications the pull request was created from
+/// * `merged_contents` - Content of each file after the merge (`None` if not merged)
+/// * `pr_number` - Number of the pull request
+///
+/// # Returns
+///
+/// One outcome per proposed hunk
+pub fn pull_request_feedback(
+    modifications: &[CodeModification],
+    merged_contents: &[Option<String>],
+    pr_number: u64,
+) -> Vec<FeedbackEntry> {
+    let source = format!("pull request #{}", pr_number);
+    let mut entries = Vec::new();
+
+    for (modification, merged) in modifications.iter().zip(merged_contents) {
+        for hunk in split_into_hunks(&modification.original_content, &modification.modified_content, 3) {
+            let outcome = match merged {
+                None => FeedbackOutcome::Rejected,
+                Some(content) if content.contains(&hunk.new_text) => FeedbackOutcome::Accepted,
+                Some(_) => FeedbackOutcome::Edited,
+            };
+
+            entries.push(feedback_entry(modification, &source, hunk.old_text, hunk.new_text, None, outcome));
+        }
+    }
+
+    entries
+}
+
+/// Kept fixes most similar to new code, best match first
+///
+/// Similarity is the number of identifiers the original text of a fix
+/// shares with the code.
+///
+/// # Arguments
+///
+/// * `entries` - Recorded outcomes
+/// * `code` - Code and issues a fix is requested for
+/// * `limit` - Maximum number of examples
+///
+/// # Returns
+///
+/// The selected outcomes
+pub fn relevant_examples<'a>(entries: &'a [FeedbackEntry], code: &str, limit: usize) -> Vec<&'a FeedbackEntry> {
+    let code_identifiers = identifiers(code);
+
+    let mut scored: Vec<(usize, &FeedbackEntry)> = entries.iter()
+        .filter(|entry| entry.final_text().is_some())
+        .filter(|entry| entry.original.len() + entry.final_text().unwrap_or_default().len() <= MAX_EXAMPLE_CHARS)
+        .map(|entry| (identifiers(&entry.original).intersection(&code_identifiers).count(), entry))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+
+    // Most recent first among equal scores
+    scored.reverse();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    scored.into_iter().take(limit).map(|(_, entry)| entry).collect()
+}
+
+/// Format kept fixes as examples for a fix prompt
+pub fn format_examples(examples: &[&FeedbackEntry]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from("Fixes accepted earlier in this project, as examples of the expected style:\n\n");
+    for example in examples {
+        section.push_str(&format!(
+            "Before:\n```rust\n{}```\nAfter:\n```rust\n{}```\n\n",
+            example.original,
+            example.final_text().unwrap_or_default()
+        ));
+    }
+
+    section
+}
+
+fn feedback_entry(
+    modification: &CodeModification,
+    source: &str,
+    original: String,
+    proposed: String,
+    edited: Option<String>,
+    outcome: FeedbackOutcome,
+) -> FeedbackEntry {
+    FeedbackEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        file_path: modification.file_path.clone(),
+        description: modification.description.clone(),
+        source: source.to_string(),
+        original,
+        proposed,
+        edited,
+        outcome,
+    }
+}
+
+fn identifiers(code: &str) -> HashSet<&str> {
+    let identifier_regex = regex::Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]{2,}\b").unwrap();
+    identifier_regex.find_iter(code).map(|m| m.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::create_modification;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_review_feedback_and_examples() {
+        let modification = create_modification(
+            PathBuf::from("src/lib.rs"),
+            "fn total(items: &Vec<u32>) -> u32 {\n    items.iter().sum()\n}\n".to_string(),
+            "fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n".to_string(),
+            "AI fix for 1 issues".to_string(),
+            80,
+        );
+        let decisions = vec![HunkReview {
+            file_path: modification.file_path.clone(),
+            header: String::new(),
+            decision: HunkDecision::Accept,
+        }];
+
+        let entries = review_feedback(std::slice::from_ref(&modification), &decisions);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].outcome, FeedbackOutcome::Accepted);
+
+        let dir = tempdir().unwrap();
+        let log = FeedbackLog::open(dir.path());
+        log.record(&entries).unwrap();
+        log.record(&pull_request_feedback(&[modification], &[None], 7)).unwrap();
+
+        let entries = log.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].outcome, FeedbackOutcome::Rejected);
+
+        let examples = relevant_examples(&entries, "fn count(items: &Vec<u32>) -> usize", 3);
+        assert_eq!(examples.len(), 1);
+        assert!(format_examples(&examples).contains("items: &[u32]"));
+        assert!(relevant_examples(&entries, "struct Unrelated;", 3).is_empty());
+    }
+}
@@ -0,0 +1,463 @@
+//! Forge provider module
+//!
+//! This module abstracts the code forges fixes are published to:
+//! - A `ForgeProvider` trait for repositories, branches, pull requests and comments
+//! - Implementations for GitHub (`github`), Gitea/Forgejo (`gitea`) and Bitbucket Cloud (`bitbucket`)
+//! - Publishing fixes as a pull request, with an inline comment per fix, on any provider
+
+use crate::github::{PullRequestInfo, PullRequestOptions, RepoInfo};
+use crate::modification::{split_into_hunks, CodeModification};
+use crate::{Result, RustAiToolError, SshConfig};
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use log::{debug, info, warn};
+
+/// Operations of a code forge hosting a repository
+#[async_trait]
+pub trait ForgeProvider: Send + Sync {
+    /// Name of the forge, for messages (e.g. `GitHub`)
+    fn name(&self) -> &'static str;
+
+    /// URL the repository is cloned from (an SSH URL when SSH is configured)
+    fn clone_url(&self) -> String;
+
+    /// SSH settings for cloning and pushing (`None` for HTTPS)
+    fn ssh(&self) -> Option<&SshConfig> {
+        None
+    }
+
+    /// Get information about the repository
+    async fn get_repo_info(&self) -> Result<RepoInfo>;
+
+    /// Create a branch from another one
+    async fn create_branch(&self, base_branch: &str, new_branch: &str) -> Result<()>;
+
+    /// Open a pull request merging `head` into `base`
+    ///
+    /// Options a forge does not support are skipped with a warning.
+    async fn create_pull_request(
+        &self,
+        title: &str,
+        body: &str,
+        head: &str,
+        base: &str,
+        options: &PullRequestOptions,
+    ) -> Result<PullRequestInfo>;
+
+    /// Get information about a pull request
+    async fn get_pull_request(&self, pr_number: u64) -> Result<PullRequestInfo>;
+
+    /// Find the open pull request from a branch, if any
+    async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequestInfo>>;
+
+    /// Replace the title and body of a pull request
+    async fn update_pull_request(&self, pr_number: u64, title: &str, body: &str) -> Result<()>;
+
+    /// Comment on a pull request
+    async fn add_pr_comment(&self, pr_number: u64, comment: &str) -> Result<()>;
+
+    /// Comment on a line of a file changed by a pull request
+    ///
+    /// # Arguments
+    ///
+    /// * `pr_number` - Number of the pull request
+    /// * `path` - Path of the file in the repository
+    /// * `line` - Line in the new version of the file (1-based)
+    /// * `comment` - Text of the comment
+    async fn add_inline_comment(&self, pr_number: u64, path: &str, line: usize, comment: &str) -> Result<()>;
+
+    /// Get the content of a file, from the default branch unless `branch` is given
+    async fn get_file_content(&self, path: &str, branch: Option<&str>) -> Result<String>;
+
+    /// Status of the latest CI pipeline run of a branch (`None` if none ran)
+    async fn pipeline_status(&self, _branch: &str) -> Result<Option<String>> {
+        Err(RustAiToolError::Forge(format!("{} does not report pipeline status", self.name())))
+    }
+}
+
+/// Clone one branch of a repository
+///
+/// # Arguments
+///
+/// * `provider` - Forge hosting the repository
+/// * `branch` - Branch to clone
+/// * `target_dir` - Directory the checkout is created in
+///
+/// # Returns
+///
+/// Path to the checkout
+pub async fn clone_branch(provider: &dyn ForgeProvider, branch: &str, target_dir: &Path) -> Result<PathBuf> {
+    let repo_url = provider.clone_url();
+    let output_dir = target_dir.join("repo");
+    info!("Cloning {} ({}) to {}", repo_url, branch, output_dir.display());
+
+    let output = git_clone_command(provider.ssh())
+        .args(["--single-branch", "--branch", branch])
+        .arg(&repo_url)
+        .arg(&output_dir)
+        .output()
+        .await
+        .map_err(RustAiToolError::Io)?;
+
+    if !output.status.success() {
+        return Err(RustAiToolError::Forge(format!(
+            "Failed to clone repository: {} (exit code: {:?}): {}",
+            repo_url,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output_dir)
+}
+
+/// `git clone` command connecting with the given SSH settings
+///
+/// The settings are stored in the clone, so later fetches and pushes use them too.
+pub(crate) fn git_clone_command(ssh: Option<&SshConfig>) -> Command {
+    let mut command = Command::new("git");
+    command.arg("clone");
+
+    if let Some(ssh) = ssh {
+        command.arg("--config").arg(format!("core.sshCommand={}", ssh.ssh_command()));
+    }
+
+    command
+}
+
+/// Commit files of a checkout to a branch and push it
+///
+/// # Arguments
+///
+/// * `repo_path` - Path to the checkout
+/// * `files` - Files to commit
+/// * `message` - Commit message
+/// * `branch` - Branch to commit to
+pub async fn commit_and_push(repo_path: &Path, files: &[PathBuf], message: &str, branch: &str) -> Result<()> {
+    info!("Committing {} files to branch {}", files.len(), branch);
+
+    // Every git command runs with `-C`, so the process working directory
+    // is never changed and concurrent operations are unaffected
+    run_git(repo_path, &["checkout", branch], &format!("switch to branch: {}", branch)).await?;
+
+    // Stage the files
+    for file in files {
+        debug!("Staging file: {}", file.display());
+        let file_arg = file.to_string_lossy();
+        run_git(repo_path, &["add", "--", &file_arg], &format!("stage file: {}", file.display())).await?;
+    }
+
+    // Commit and push the changes
+    run_git(repo_path, &["commit", "-m", message], "commit changes").await?;
+    run_git(repo_path, &["push", "origin", branch], "push changes").await?;
+
+    info!("Successfully committed and pushed changes");
+    Ok(())
+}
+
+/// Marker identifying pull requests of fixes in their body
+pub const FIXES_PR_MARKER: &str = "<!-- rust-ai-tool-fixes -->";
+
+/// Publish fixes as a pull request against the default branch
+///
+/// The branch is created if needed, the fixed files are committed to it,
+/// and with `inline_comments` each fix is described next to its first change.
+///
+/// # Arguments
+///
+/// * `provider` - Forge hosting the repository
+/// * `modifications` - Fixes to publish
+/// * `base_dir` - Directory the fix paths are relative to (the local checkout of the project)
+/// * `branch` - Branch the fixes are committed to
+/// * `title` - Title of the pull request
+/// * `options` - Draft status, labels, assignees and reviewers of the pull request
+/// * `inline_comments` - Whether to comment each fix inline
+///
+/// # Returns
+///
+/// The created pull request
+pub async fn publish_fixes(
+    provider: &dyn ForgeProvider,
+    modifications: &[CodeModification],
+    base_dir: &Path,
+    branch: &str,
+    title: &str,
+    options: &PullRequestOptions,
+    inline_comments: bool,
+) -> Result<PullRequestInfo> {
+    let repo_info = provider.get_repo_info().await?;
+
+    // The branch may exist from an earlier run
+    if let Err(e) = provider.create_branch(&repo_info.default_branch, branch).await {
+        debug!("Not creating branch {}: {}", branch, e);
+    }
+
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let repo_path = clone_branch(provider, branch, temp_dir.path()).await?;
+
+    let changed_files = write_modifications(&repo_path, modifications, base_dir)?;
+    commit_and_push(&repo_path, &changed_files, &format!("Applied fixes: {}", title), branch).await?;
+
+    let pr = provider.create_pull_request(
+        title,
+        &format_fixes_body(modifications, base_dir, &options.linked_issues),
+        branch,
+        &repo_info.default_branch,
+        options,
+    ).await?;
+
+    if inline_comments {
+        for modification in modifications {
+            let Some(line) = first_changed_line(modification) else {
+                continue;
+            };
+
+            let path = repo_relative_path(modification, base_dir).to_string_lossy().replace('\\', "/");
+            let comment = format!("{} (confidence {}%)", modification.description, modification.confidence);
+
+            if let Err(e) = provider.add_inline_comment(pr.number, &path, line, &comment).await {
+                warn!("Failed to comment the fix of {} on {}: {}", path, provider.name(), e);
+            }
+        }
+    }
+
+    Ok(pr)
+}
+
+/// Refresh the pull request of fixes opened from a branch, or open it
+///
+/// The branch is reset to the default branch with the fixes committed on top and
+/// force-pushed, and the pull request body is rewritten to list the new fixes, so
+/// regenerating fixes never opens a duplicate pull request.
+///
+/// # Arguments
+///
+/// * `provider` - Forge hosting the repository
+/// * `modifications` - Regenerated fixes
+/// * `base_dir` - Directory the fix paths are relative to (the local checkout of the project)
+/// * `branch` - Branch of the pull request
+/// * `title` - Title of the pull request
+/// * `options` - Settings of the pull request, if it has to be opened
+///
+/// # Returns
+///
+/// The updated or created pull request, or an error if the open pull request
+/// of the branch was not created by this tool
+pub async fn update_fixes(
+    provider: &dyn ForgeProvider,
+    modifications: &[CodeModification],
+    base_dir: &Path,
+    branch: &str,
+    title: &str,
+    options: &PullRequestOptions,
+) -> Result<PullRequestInfo> {
+    let Some(pr) = provider.find_open_pull_request(branch).await? else {
+        info!("No open pull request from {}, opening one", branch);
+        return publish_fixes(provider, modifications, base_dir, branch, title, options, false).await;
+    };
+
+    if !pr.body.contains(FIXES_PR_MARKER) {
+        return Err(RustAiToolError::Forge(format!(
+            "Pull request #{} from {} was not created by Rust AI Tool; not overwriting it",
+            pr.number,
+            branch
+        )));
+    }
+
+    let repo_info = provider.get_repo_info().await?;
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let repo_path = clone_branch(provider, &repo_info.default_branch, temp_dir.path()).await?;
+
+    run_git(&repo_path, &["checkout", "-B", branch], &format!("reset branch: {}", branch)).await?;
+    let changed_files = write_modifications(&repo_path, modifications, base_dir)?;
+    for file in &changed_files {
+        let file_arg = file.to_string_lossy();
+        run_git(&repo_path, &["add", "--", &file_arg], &format!("stage file: {}", file.display())).await?;
+    }
+    run_git(&repo_path, &["commit", "-m", &format!("Applied fixes: {}", title)], "commit changes").await?;
+    run_git(&repo_path, &["push", "--force", "origin", branch], "force-push changes").await?;
+
+    provider.update_pull_request(pr.number, title, &format_fixes_body(modifications, base_dir, &options.linked_issues)).await?;
+
+    info!("Updated pull request #{} with {} fixes", pr.number, modifications.len());
+    Ok(PullRequestInfo {
+        title: title.to_string(),
+        ..pr
+    })
+}
+
+/// Body of a pull request of fixes, ending with `FIXES_PR_MARKER`
+///
+/// Each fix is listed with the analysis issues it resolves, and every linked
+/// issue is referenced with `Fixes #N` so merging the pull request closes it.
+///
+/// # Arguments
+///
+/// * `modifications` - Fixes of the pull request
+/// * `base_dir` - Directory the fix paths are relative to
+/// * `linked_issues` - Issues the pull request resolves
+///
+/// # Returns
+///
+/// The body, in Markdown
+pub fn format_fixes_body(modifications: &[CodeModification], base_dir: &Path, linked_issues: &[u64]) -> String {
+    let mut body = "Automatically generated fixes by Rust AI Tool\n\n".to_string();
+
+    for modification in modifications {
+        let path = repo_relative_path(modification, base_dir).to_string_lossy().replace('\\', "/");
+        body.push_str(&format!(
+            "- `{}`: {} (confidence {}%)\n",
+            path,
+            modification.description.replace('\n', " "),
+            modification.confidence
+        ));
+
+        for issue in &modification.resolves {
+            let anchor = if issue.line_end > issue.line_start {
+                format!("{}#L{}-L{}", path, issue.line_start, issue.line_end)
+            } else {
+                format!("{}#L{}", path, issue.line_start)
+            };
+
+            body.push_str(&format!(
+                "  - `{}` `{}` {}: {}\n",
+                issue.id,
+                anchor,
+                issue.rule,
+                issue.message.replace('\n', " ")
+            ));
+        }
+    }
+
+    if !linked_issues.is_empty() {
+        body.push('\n');
+        for number in linked_issues {
+            body.push_str(&format!("Fixes #{}\n", number));
+        }
+    }
+
+    body.push_str(&format!("\n{}\n", FIXES_PR_MARKER));
+    body
+}
+
+/// Write the modified contents of fixes into a checkout
+///
+/// # Returns
+///
+/// The written files
+fn write_modifications(repo_path: &Path, modifications: &[CodeModification], base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut changed_files = Vec::with_capacity(modifications.len());
+
+    for modification in modifications {
+        let file_path = repo_path.join(repo_relative_path(modification, base_dir));
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).map_err(RustAiToolError::Io)?;
+        }
+        fs::write(&file_path, &modification.modified_content).map_err(RustAiToolError::Io)?;
+        changed_files.push(file_path);
+    }
+
+    Ok(changed_files)
+}
+
+/// Path of a modified file relative to the repository root
+fn repo_relative_path<'a>(modification: &'a CodeModification, base_dir: &Path) -> &'a Path {
+    let path = modification.file_path.strip_prefix(base_dir).unwrap_or(&modification.file_path);
+    path.strip_prefix(".").unwrap_or(path)
+}
+
+/// First line of the modified file a modification adds or changes (1-based)
+///
+/// For a pure deletion, the line before the deleted ones.
+fn first_changed_line(modification: &CodeModification) -> Option<usize> {
+    let hunk = split_into_hunks(&modification.original_content, &modification.modified_content, 0)
+        .into_iter()
+        .next()?;
+
+    // `@@ -a,b +c,d @@`; without context, `c` is the first new line, or the
+    // line after a deletion
+    let new_start: usize = hunk.header.split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))
+        .and_then(|range| range.split(',').next())
+        .and_then(|start| start.parse().ok())?;
+
+    if hunk.diff_lines.iter().any(|line| line.starts_with('+')) {
+        Some(new_start)
+    } else {
+        Some(new_start.saturating_sub(1).max(1))
+    }
+}
+
+/// Run a git command in a repository (`git -C`)
+///
+/// # Arguments
+///
+/// * `repo_path` - Path to the repository
+/// * `args` - Arguments of the git command
+/// * `action` - What the command does, for the error message
+pub(crate) async fn run_git(repo_path: &Path, args: &[&str], action: &str) -> Result<()> {
+    debug!("Running git {:?} in {}", args, repo_path.display());
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .output()
+        .await
+        .map_err(RustAiToolError::Io)?;
+
+    if !output.status.success() {
+        return Err(RustAiToolError::Forge(format!(
+            "Failed to {} (exit code: {:?}): {}",
+            action,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::{create_modification, IssueReference};
+
+    #[test]
+    fn test_first_changed_line() {
+        let original = "fn a() {}\nfn b() {}\nfn c() {}\n".to_string();
+        let changed = create_modification(
+            PathBuf::from("./src/lib.rs"),
+            original.clone(),
+            "fn a() {}\nfn b() -> u8 { 0 }\nfn c() {}\n".to_string(),
+            "Fix b".to_string(),
+            90,
+        );
+        let deleted = create_modification(
+            PathBuf::from("./src/lib.rs"),
+            original,
+            "fn a() {}\nfn b() {}\n".to_string(),
+            "Remove c".to_string(),
+            90,
+        );
+
+        assert_eq!(first_changed_line(&changed), Some(2));
+        assert_eq!(first_changed_line(&deleted), Some(2));
+        assert_eq!(repo_relative_path(&changed, Path::new("/elsewhere")), Path::new("src/lib.rs"));
+
+        let mut changed = changed;
+        changed.resolves.push(IssueReference {
+            id: "1a2b3c4d".to_string(),
+            line_start: 2,
+            line_end: 3,
+            rule: "CodeQuality".to_string(),
+            message: "b returns nothing".to_string(),
+        });
+        let body = format_fixes_body(&[changed], Path::new("."), &[7]);
+        assert!(body.contains("- `src/lib.rs`: Fix b (confidence 90%)\n  - `1a2b3c4d` `src/lib.rs#L2-L3` CodeQuality: b returns nothing\n"));
+        assert!(body.contains("\nFixes #7\n"));
+        assert!(body.ends_with(&format!("{}\n", FIXES_PR_MARKER)));
+    }
+}
@@ -0,0 +1,203 @@
+//! Git index module
+//!
+//! This module writes modifications to the git index instead of the working tree:
+//! - Fixes are checked against the staged content of each file
+//! - New content is written as blobs and staged with `git update-index`
+//! - Uncommitted edits in the working tree are left untouched
+
+use crate::backup::{find_git_root, run_git};
+use crate::modification::{match_file_format, CodeModification, FileChange};
+use crate::{Result, RustAiToolError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use log::{debug, info};
+
+/// A file as recorded in the git index
+struct StagedFile {
+    /// Root of the git repository
+    repo_root: PathBuf,
+
+    /// Path relative to the repository root, with `/` separators
+    relative_path: String,
+
+    /// File mode (e.g. `100644`)
+    mode: String,
+
+    /// Staged content
+    content: String,
+}
+
+/// Stage modifications in the git index without touching the working tree
+///
+/// Every modification is checked against the staged content of its file
+/// before anything is written, so either all modifications are staged or none.
+///
+/// # Arguments
+///
+/// * `modifications` - Modifications to stage
+///
+/// # Returns
+///
+/// The staged changes (no backups are created; `git restore --staged` reverts them)
+pub fn stage_modifications(modifications: &[CodeModification]) -> Result<Vec<FileChange>> {
+    info!("Staging {} modifications in the git index", modifications.len());
+
+    let mut staged_files = Vec::with_capacity(modifications.len());
+
+    for modification in modifications {
+        let staged = read_staged_file(&modification.file_path)?;
+
+        if staged.content != match_file_format(&staged.content, &modification.original_content) {
+            return Err(RustAiToolError::Modification(format!(
+                "Staged content of {} differs from the original content of the fix",
+                modification.file_path.display()
+            )));
+        }
+
+        staged_files.push(staged);
+    }
+
+    let mut changes = Vec::with_capacity(modifications.len());
+
+    for (modification, staged) in modifications.iter().zip(staged_files) {
+        let new_content = match_file_format(&staged.content, &modification.modified_content);
+        let blob = write_blob(&staged.repo_root, &new_content)?;
+
+        let cache_info = format!("{},{},{}", staged.mode, blob, staged.relative_path);
+        run_git(&staged.repo_root, &["update-index", "--cacheinfo", cache_info.as_str()], None)?;
+
+        debug!("Staged {} as {}", staged.relative_path, blob);
+
+        changes.push(FileChange {
+            file_path: modification.file_path.clone(),
+            original_content: Some(staged.content),
+            new_content,
+            description: modification.description.clone(),
+            backup_created: false,
+            backup_path: None,
+            backup_ref: None,
+        });
+    }
+
+    info!("Staged {} files", changes.len());
+    Ok(changes)
+}
+
+/// Read the index entry of a file
+fn read_staged_file(file_path: &Path) -> Result<StagedFile> {
+    let repo_root = find_git_root(file_path).ok_or_else(|| {
+        RustAiToolError::Modification(format!("{} is not inside a git repository", file_path.display()))
+    })?;
+
+    let canonical_root = repo_root.canonicalize()
+        .map_err(RustAiToolError::Io)?;
+    let canonical = file_path.canonicalize()
+        .map_err(RustAiToolError::Io)?;
+    let relative_path = canonical.strip_prefix(&canonical_root)
+        .map_err(|_| RustAiToolError::Modification(format!(
+            "{} is outside the git repository at {}",
+            file_path.display(),
+            repo_root.display()
+        )))?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    // "<mode> <sha> <stage>\t<path>"
+    let entry = run_git(&repo_root, &["ls-files", "--stage", "--", relative_path.as_str()], None)?;
+    let mode = entry.split_whitespace().next()
+        .ok_or_else(|| RustAiToolError::Modification(format!(
+            "{} is not tracked in the git index",
+            file_path.display()
+        )))?
+        .to_string();
+
+    let object = format!(":{}", relative_path);
+    let content = git_output(&repo_root, &["cat-file", "blob", object.as_str()], None)?;
+
+    Ok(StagedFile {
+        repo_root,
+        relative_path,
+        mode,
+        content,
+    })
+}
+
+/// Write content to the object database and return its blob id
+fn write_blob(repo_root: &Path, content: &str) -> Result<String> {
+    let blob = git_output(repo_root, &["hash-object", "-w", "--no-filters", "--stdin"], Some(content))?;
+    Ok(blob.trim().to_string())
+}
+
+/// Run a git command with optional stdin and return its untrimmed stdout
+fn git_output(repo_root: &Path, args: &[&str], input: Option<&str>) -> Result<String> {
+    debug!("Running git {:?} in {}", args, repo_root.display());
+
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(RustAiToolError::Io)?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())
+            .map_err(RustAiToolError::Io)?;
+    }
+
+    let output = child.wait_with_output()
+        .map_err(RustAiToolError::Io)?;
+
+    if !output.status.success() {
+        return Err(RustAiToolError::Modification(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|e| RustAiToolError::Modification(format!("git {} returned invalid UTF-8: {}", args.join(" "), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::create_modification;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stage_modifications() {
+        let dir = tempdir().unwrap();
+        Command::new("git").args(["init", "-q"]).current_dir(dir.path()).status().unwrap();
+
+        let file_path = dir.path().join("main.rs");
+        fs::write(&file_path, "fn main() {}\n").unwrap();
+        run_git(dir.path(), &["add", "main.rs"], None).unwrap();
+
+        // A local edit that must survive staging
+        fs::write(&file_path, "fn main() { local(); }\n").unwrap();
+
+        let modification = create_modification(
+            file_path.clone(),
+            "fn main() {}\n".to_string(),
+            "fn main() { fixed(); }\n".to_string(),
+            "Call fixed".to_string(),
+            90,
+        );
+
+        let changes = stage_modifications(&[modification]).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn main() { local(); }\n");
+        assert_eq!(
+            git_output(dir.path(), &["cat-file", "blob", ":main.rs"], None).unwrap(),
+            "fn main() { fixed(); }\n"
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Gitea and Forgejo integration module
+//!
+//! This module talks to self-hosted forges through the Gitea REST API (v1),
+//! which Forgejo and Codeberg share:
+//! - Repository information, branches and file contents
+//! - Pull requests, pull request comments and inline review comments
+
+use crate::forge::ForgeProvider;
+use crate::github::{PullRequestInfo, PullRequestOptions, RepoInfo};
+use crate::{GiteaRepo, Result, RustAiToolError, SshConfig};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use log::{debug, info, warn};
+
+/// Client of a repository on a Gitea or Forgejo instance
+pub struct GiteaClient {
+    /// HTTP client for API requests
+    client: reqwest::Client,
+
+    /// Base URL of the instance (e.g. `https://codeberg.org`)
+    base_url: String,
+
+    /// Access token
+    token: String,
+
+    /// Repository owner
+    owner: String,
+
+    /// Repository name
+    repo: String,
+
+    /// SSH settings for cloning and pushing (HTTPS if `None`)
+    ssh: Option<SshConfig>,
+}
+
+/// Repository returned by the API
+#[derive(Debug, Deserialize)]
+struct GiteaRepository {
+    default_branch: String,
+    #[serde(default)]
+    fork: bool,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Pull request returned by the API
+#[derive(Debug, Deserialize)]
+struct GiteaPullRequest {
+    number: u64,
+    title: String,
+    html_url: String,
+    #[serde(default)]
+    merged: bool,
+    state: String,
+    #[serde(default)]
+    body: Option<String>,
+    head: Option<GiteaBranchRef>,
+}
+
+/// Branch of a pull request
+#[derive(Debug, Deserialize)]
+struct GiteaBranchRef {
+    #[serde(rename = "ref")]
+    branch: String,
+}
+
+/// Label returned by the labels API
+#[derive(Debug, Deserialize)]
+struct GiteaLabel {
+    id: u64,
+    name: String,
+}
+
+/// File returned by the contents API
+#[derive(Debug, Deserialize)]
+struct GiteaContent {
+    #[serde(rename = "type")]
+    kind: String,
+    content: Option<String>,
+}
+
+/// Inline comment of a pull request review
+#[derive(Debug, Serialize)]
+struct GiteaReviewComment<'a> {
+    path: &'a str,
+    body: &'a str,
+    new_position: usize,
+}
+
+impl From<GiteaPullRequest> for PullRequestInfo {
+    fn from(pull_request: GiteaPullRequest) -> Self {
+        Self {
+            number: pull_request.number,
+            title: pull_request.title,
+            url: pull_request.html_url,
+            is_merged: pull_request.merged,
+            state: pull_request.state,
+            body: pull_request.body.unwrap_or_default(),
+        }
+    }
+}
+
+impl GiteaClient {
+    /// Create a client for a repository
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the instance (e.g. `https://codeberg.org`)
+    /// * `token` - Access token
+    /// * `owner` - Repository owner
+    /// * `repo` - Repository name
+    ///
+    /// # Returns
+    ///
+    /// The client
+    pub fn new(base_url: &str, token: &str, owner: &str, repo: &str) -> Result<Self> {
+        let client = crate::network::client_builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| RustAiToolError::Forge(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            ssh: None,
+        })
+    }
+
+    /// Create a client for the repository of a configuration
+    pub fn from_repo(repo: &GiteaRepo) -> Result<Self> {
+        Self::new(&repo.url, &repo.access_token, &repo.owner, &repo.name)
+    }
+
+    /// Clone and push over SSH with these settings (HTTPS if `None`)
+    pub fn with_ssh(mut self, ssh: Option<SshConfig>) -> Self {
+        self.ssh = ssh;
+        self
+    }
+
+    /// URL of a repository API endpoint
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/api/v1/repos/{}/{}{}", self.base_url, self.owner, self.repo, path)
+    }
+
+    /// IDs of the repository labels with the given names, skipping unknown ones
+    async fn label_ids(&self, names: &[String]) -> Result<Vec<u64>> {
+        let labels: Vec<GiteaLabel> = self.send(self.client.get(self.endpoint("/labels"))).await?;
+
+        Ok(names.iter()
+            .filter_map(|name| {
+                let id = labels.iter().find(|label| &label.name == name).map(|label| label.id);
+                if id.is_none() {
+                    warn!("Label {} does not exist in {}/{}", name, self.owner, self.repo);
+                }
+                id
+            })
+            .collect())
+    }
+
+    /// Send a request and parse the JSON answer
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let response = request
+            .header("Authorization", format!("token {}", self.token))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| RustAiToolError::Forge(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(RustAiToolError::Forge(format!("Gitea API returned {}: {}", status, body.trim())));
+        }
+
+        response.json().await
+            .map_err(|e| RustAiToolError::Forge(format!("Invalid Gitea API response: {}", e)))
+    }
+}
+
+#[async_trait]
+impl ForgeProvider for GiteaClient {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    fn clone_url(&self) -> String {
+        let Some(ssh) = &self.ssh else {
+            return format!("{}/{}/{}.git", self.base_url, self.owner, self.repo);
+        };
+
+        // The SSH server of the instance runs on the host of its web interface
+        let url = reqwest::Url::parse(&self.base_url).ok();
+        let host = url.as_ref().and_then(|url| url.host_str()).unwrap_or(&self.base_url);
+        match ssh.port.or_else(|| url.as_ref().and_then(|url| url.port())) {
+            Some(port) if port != 22 => format!("ssh://git@{}:{}/{}/{}.git", host, port, self.owner, self.repo),
+            _ => format!("ssh://git@{}/{}/{}.git", host, self.owner, self.repo),
+        }
+    }
+
+    fn ssh(&self) -> Option<&SshConfig> {
+        self.ssh.as_ref()
+    }
+
+    async fn get_repo_info(&self) -> Result<RepoInfo> {
+        info!("Getting information for repository {}/{}", self.owner, self.repo);
+
+        let repo: GiteaRepository = self.send(self.client.get(self.endpoint(""))).await?;
+
+        Ok(RepoInfo {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            default_branch: repo.default_branch,
+            is_fork: repo.fork,
+            description: repo.description.filter(|d| !d.is_empty()),
+        })
+    }
+
+    async fn create_branch(&self, base_branch: &str, new_branch: &str) -> Result<()> {
+        info!("Creating branch {} from {}", new_branch, base_branch);
+
+        let _: serde_json::Value = self.send(self.client.post(self.endpoint("/branches")).json(&serde_json::json!({
+            "new_branch_name": new_branch,
+            "old_branch_name": base_branch,
+        }))).await?;
+
+        Ok(())
+    }
+
+    async fn create_pull_request(
+        &self,
+        title: &str,
+        body: &str,
+        head: &str,
+        base: &str,
+        options: &PullRequestOptions,
+    ) -> Result<PullRequestInfo> {
+        info!("Creating pull request: {} ({} -> {})", title, head, base);
+
+        // Gitea marks pull requests as drafts by their title prefix
+        let title = if options.draft { format!("WIP: {}", title) } else { title.to_string() };
+        let labels = if options.labels.is_empty() { Vec::new() } else { self.label_ids(&options.labels).await? };
+
+        let pull_request: GiteaPullRequest = self.send(self.client.post(self.endpoint("/pulls")).json(&serde_json::json!({
+            "title": title,
+            "body": body,
+            "head": head,
+            "base": base,
+            "labels": labels,
+            "assignees": options.assignees,
+        }))).await?;
+
+        if !options.reviewers.is_empty() {
+            let _: serde_json::Value = self.send(
+                self.client.post(self.endpoint(&format!("/pulls/{}/requested_reviewers", pull_request.number)))
+                    .json(&serde_json::json!({ "reviewers": options.reviewers }))
+            ).await?;
+        }
+
+        info!("Successfully created pull request #{}: {}", pull_request.number, pull_request.html_url);
+        Ok(pull_request.into())
+    }
+
+    async fn get_pull_request(&self, pr_number: u64) -> Result<PullRequestInfo> {
+        let pull_request: GiteaPullRequest = self.send(
+            self.client.get(self.endpoint(&format!("/pulls/{}", pr_number)))
+        ).await?;
+
+        debug!("PR info: {:?}", pull_request);
+        Ok(pull_request.into())
+    }
+
+    async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequestInfo>> {
+        // The API cannot filter by head branch, so the open pull requests are searched
+        for page in 1u32.. {
+            let pull_requests: Vec<GiteaPullRequest> = self.send(
+                self.client.get(self.endpoint("/pulls"))
+                    .query(&[("state", "open"), ("limit", "50"), ("page", &page.to_string())])
+            ).await?;
+
+            let page_len = pull_requests.len();
+            let found = pull_requests.into_iter()
+                .find(|pull_request| pull_request.head.as_ref().is_some_and(|h| h.branch == head));
+
+            if found.is_some() || page_len < 50 {
+                return Ok(found.map(PullRequestInfo::from));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn update_pull_request(&self, pr_number: u64, title: &str, body: &str) -> Result<()> {
+        info!("Updating PR #{}", pr_number);
+
+        let _: serde_json::Value = self.send(
+            self.client.patch(self.endpoint(&format!("/pulls/{}", pr_number)))
+                .json(&serde_json::json!({ "title": title, "body": body }))
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn add_pr_comment(&self, pr_number: u64, comment: &str) -> Result<()> {
+        info!("Adding comment to PR #{}", pr_number);
+
+        // Pull requests share their number and comments with issues
+        let _: serde_json::Value = self.send(
+            self.client.post(self.endpoint(&format!("/issues/{}/comments", pr_number)))
+                .json(&serde_json::json!({ "body": comment }))
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn add_inline_comment(&self, pr_number: u64, path: &str, line: usize, comment: &str) -> Result<()> {
+        info!("Adding comment to {}:{} in PR #{}", path, line, pr_number);
+
+        let _: serde_json::Value = self.send(
+            self.client.post(self.endpoint(&format!("/pulls/{}/reviews", pr_number)))
+                .json(&serde_json::json!({
+                    "event": "COMMENT",
+                    "body": "",
+                    "comments": [GiteaReviewComment { path, body: comment, new_position: line }],
+                }))
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn get_file_content(&self, path: &str, branch: Option<&str>) -> Result<String> {
+        info!("Getting content of file: {}", path);
+
+        let mut request = self.client.get(self.endpoint(&format!("/contents/{}", path)));
+        if let Some(branch) = branch {
+            request = request.query(&[("ref", branch)]);
+        }
+
+        let content: GiteaContent = self.send(request).await?;
+        if content.kind != "file" {
+            return Err(RustAiToolError::Forge(format!("Path is not a file: {}", path)));
+        }
+
+        let encoded = content.content
+            .ok_or_else(|| RustAiToolError::Forge("File content is empty".to_string()))?
+            .replace('\n', "");
+        let decoded = BASE64.decode(encoded)
+            .map_err(|e| RustAiToolError::Forge(format!("Failed to decode base64: {}", e)))?;
+
+        String::from_utf8(decoded)
+            .map_err(|e| RustAiToolError::Forge(format!("Failed to decode UTF-8: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_clone_url() {
+        let client = |url: &str, port: Option<u16>| {
+            GiteaClient::new(url, "token", "acme", "app").unwrap()
+                .with_ssh(Some(SshConfig { port, ..Default::default() }))
+                .clone_url()
+        };
+
+        assert_eq!(client("https://codeberg.org", None), "ssh://git@codeberg.org/acme/app.git");
+        assert_eq!(client("http://git.local:3000/", None), "ssh://git@git.local:3000/acme/app.git");
+        assert_eq!(client("http://git.local:3000", Some(2222)), "ssh://git@git.local:2222/acme/app.git");
+        assert_eq!(client("https://git.local", Some(22)), "ssh://git@git.local/acme/app.git");
+    }
+}
@@ -1,15 +1,51 @@
-use crate::{GitHubRepo, Result, RustAiToolError};
+use crate::{GitHubRepo, Result, RustAiToolError, SshConfig};
+use crate::codeowners::CodeOwners;
+use crate::forge::{commit_and_push, git_clone_command, run_git, ForgeProvider};
+use crate::modification::{generate_unified_diff, CodeModification};
+use async_trait::async_trait;
 use octocrab::{models, Octocrab, params};
+use std::future::Future;
 use std::path::{Path, PathBuf};
-use tokio::process::Command;
-use log::{debug, info};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::{debug, info, warn};
 use serde::{Serialize, Deserialize};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
+/// Calls left below which requests wait for the quota to reset
+const RATE_LIMIT_RESERVE: u64 = 10;
+
+/// Retries of a call rejected by a rate limit
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Wait before retrying a call rejected by a secondary rate limit, in seconds
+const SECONDARY_RATE_LIMIT_WAIT: u64 = 60;
+
+/// Calls between two checks of the remaining quota
+const QUOTA_CHECK_INTERVAL: u64 = 50;
+
 pub struct GithubClient {
     client: Octocrab,
     owner: String,
     repo: String,
+    rate_limit: Mutex<RateLimitState>,
+    ssh: Option<SshConfig>,
+}
+
+/// GitHub API quota as last checked, counted down by each call
+#[derive(Debug, Default)]
+struct RateLimitState {
+    /// Calls left (`None` if unknown, e.g. on servers without rate limits)
+    remaining: Option<u64>,
+    
+    /// Calls allowed per window
+    limit: Option<u64>,
+    
+    /// When the quota resets, in seconds since the Unix epoch
+    reset: Option<u64>,
+    
+    /// Calls made since the last check
+    calls_since_check: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,10 +64,72 @@ pub struct PullRequestInfo {
     pub url: String,
     pub is_merged: bool,
     pub state: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// Repository of an organization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgRepository {
+    /// Repository name
+    pub name: String,
+    
+    /// Default branch
+    pub default_branch: String,
+    
+    /// Primary language, as detected by GitHub
+    #[serde(default)]
+    pub language: Option<String>,
+    
+    /// Topics of the repository
+    #[serde(default)]
+    pub topics: Vec<String>,
+    
+    /// Whether the repository is archived
+    #[serde(default)]
+    pub archived: bool,
+    
+    /// Whether the repository is a fork
+    #[serde(default)]
+    pub fork: bool,
+}
+
+/// Branches and commits a pull request compares
+#[derive(Debug, Clone)]
+pub struct PullRequestCommits {
+    /// Branch the pull request merges into
+    pub base_branch: String,
+    
+    /// Commit of the base branch the pull request is compared with
+    pub base_sha: String,
+    
+    /// Latest commit of the pull request
+    pub head_sha: String,
+}
+
+/// Settings of a pull request besides its title and body
+#[derive(Debug, Clone, Default)]
+pub struct PullRequestOptions {
+    /// Open the pull request as a draft
+    pub draft: bool,
+    
+    /// Labels added to the pull request
+    pub labels: Vec<String>,
+    
+    /// Users assigned to the pull request
+    pub assignees: Vec<String>,
+    
+    /// Users asked to review the pull request
+    pub reviewers: Vec<String>,
+    
+    /// Issues the pull request resolves, closed when it is merged
+    pub linked_issues: Vec<u64>,
 }
 
 impl GithubClient {
     pub fn new(token: &str, owner: &str, repo: &str) -> Result<Self> {
+        crate::network::warn_unsupported("GitHub");
+        
         let client = Octocrab::builder()
             .personal_token(token.to_string())
             .build()
@@ -41,22 +139,30 @@ impl GithubClient {
             client,
             owner: owner.to_string(),
             repo: repo.to_string(),
+            rate_limit: Mutex::new(RateLimitState::default()),
+            ssh: None,
         })
     }
     
+    /// Clone and push over SSH with these settings (HTTPS if `None`)
+    pub fn with_ssh(mut self, ssh: Option<SshConfig>) -> Self {
+        self.ssh = ssh;
+        self
+    }
+    
     pub fn from_repo(repo: &GitHubRepo) -> Result<Self> {
         Self::new(&repo.access_token, &repo.owner, &repo.name)
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), branch = ?branch), err)]
     pub async fn clone_repo(&self, branch: Option<&str>, target_dir: &Path) -> Result<PathBuf> {
         info!("Cloning repository {}/{} to {}", 
               self.owner, self.repo, target_dir.display());
               
-        let repo_url = format!("https://github.com/{}/{}.git", self.owner, self.repo);
+        let repo_url = ForgeProvider::clone_url(self);
         let output_dir = target_dir.join(&self.repo);
         
-        let mut cmd = Command::new("git");
-        cmd.arg("clone");
+        let mut cmd = git_clone_command(self.ssh.as_ref());
         
         if let Some(branch_name) = branch {
             debug!("Cloning branch: {}", branch_name);
@@ -86,10 +192,12 @@ impl GithubClient {
     pub async fn get_repo_info(&self) -> Result<RepoInfo> {
         info!("Getting information for repository {}/{}", self.owner, self.repo);
         
-        let repo = self.client
-            .repos(&self.owner, &self.repo)
-            .get()
-            .await
+        let repo = self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .get()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         let info = RepoInfo {
@@ -104,14 +212,17 @@ impl GithubClient {
         Ok(info)
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), branch = new_branch), err)]
     pub async fn create_branch(&self, base_branch: &str, new_branch: &str) -> Result<()> {
         info!("Creating branch {} from {}", new_branch, base_branch);
         
         // Get the SHA of the latest commit on the base branch
-        let reference = self.client
-            .repos(&self.owner, &self.repo)
-            .get_ref(&format!("heads/{}", base_branch))
-            .await
+        let reference = self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .get_ref(&format!("heads/{}", base_branch))
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         debug!("Base branch ref: {:?}", reference);
@@ -121,31 +232,38 @@ impl GithubClient {
         debug!("Base branch SHA: {}", sha);
         
         // Create a new reference (branch) using that SHA
-        self.client
-            .repos(&self.owner, &self.repo)
-            .create_ref(&format!("refs/heads/{}", new_branch), sha)
-            .await
+        self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .create_ref(&format!("refs/heads/{}", new_branch), sha.clone())
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         info!("Successfully created branch {}", new_branch);
         Ok(())
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), head = head, base = base), err)]
     pub async fn create_pull_request(
         &self,
         title: &str,
         body: &str,
         head: &str,
         base: &str,
+        options: &PullRequestOptions,
     ) -> Result<PullRequestInfo> {
         info!("Creating pull request: {} ({} -> {})", title, head, base);
         
-        let pull_request = self.client
-            .pulls(&self.owner, &self.repo)
-            .create(title, head, base)
-            .body(body)
-            .send()
-            .await
+        let pull_request = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .create(title, head, base)
+                .body(body)
+                .draft(options.draft)
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         let pr_info = PullRequestInfo {
@@ -160,124 +278,218 @@ impl GithubClient {
                 || "open".to_string(),
                 |s| format!("{:?}", s).to_lowercase() // Use debug formatting and convert to lowercase
             ),
+            body: pull_request.body.unwrap_or_default(),
         };
         
+        // Labels and assignees are set through the issue of the pull request
+        if !options.labels.is_empty() {
+            let endpoint = format!("repos/{}/{}/issues/{}/labels", self.owner, self.repo, pr_info.number);
+            let _: serde_json::Value = self.call(|| async {
+                self.client
+                    .post(&endpoint, Some(&serde_json::json!({ "labels": options.labels })))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(format!("Failed to add labels: {}", e)))?;
+        }
+        
+        if !options.assignees.is_empty() {
+            let endpoint = format!("repos/{}/{}/issues/{}/assignees", self.owner, self.repo, pr_info.number);
+            let _: serde_json::Value = self.call(|| async {
+                self.client
+                    .post(&endpoint, Some(&serde_json::json!({ "assignees": options.assignees })))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(format!("Failed to add assignees: {}", e)))?;
+        }
+        
+        if !options.reviewers.is_empty() {
+            // Teams are given as `org/team`, and requested by their slug
+            let (teams, users): (Vec<&String>, Vec<&String>) = options.reviewers.iter()
+                .partition(|reviewer| reviewer.contains('/'));
+            let team_slugs: Vec<&str> = teams.iter()
+                .filter_map(|team| team.rsplit('/').next())
+                .collect();
+            
+            let endpoint = format!("repos/{}/{}/pulls/{}/requested_reviewers", self.owner, self.repo, pr_info.number);
+            let _: serde_json::Value = self.call(|| async {
+                self.client
+                    .post(&endpoint, Some(&serde_json::json!({ "reviewers": users, "team_reviewers": team_slugs })))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(format!("Failed to request reviewers: {}", e)))?;
+        }
+        
         info!("Successfully created pull request #{}: {}", pr_info.number, pr_info.url);
         Ok(pr_info)
     }
     
+    /// Commit files of a checkout to a branch and push it
+    ///
+    /// If the branch only accepts changes through pull requests, the commit is
+    /// pushed to a new branch instead and a pull request into `branch` is opened,
+    /// asking the code owners of the changed files for review.
+    ///
+    /// # Arguments
+    ///
+    /// * `repo_path` - Path to the checkout
+    /// * `files` - Files to commit
+    /// * `message` - Commit message, also the title of a fallback pull request
+    /// * `branch` - Branch to commit to
+    ///
+    /// # Returns
+    ///
+    /// The fallback pull request, or `None` if the branch was pushed directly
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), files = files.len(), branch = branch), err)]
     pub async fn commit_changes(
         &self,
         repo_path: &Path,
         files: &[PathBuf],
         message: &str,
         branch: &str,
-    ) -> Result<()> {
-        info!("Committing {} files to branch {}", files.len(), branch);
+    ) -> Result<Option<PullRequestInfo>> {
+        if !self.branch_requires_pull_request(branch).await? {
+            commit_and_push(repo_path, files, message, branch).await?;
+            return Ok(None);
+        }
         
-        // Change to the repository directory
-        let current_dir = std::env::current_dir().map_err(|e| RustAiToolError::Io(e))?;
-        std::env::set_current_dir(repo_path).map_err(|e| RustAiToolError::Io(e))?;
+        let changed_paths: Vec<String> = files.iter()
+            .map(|file| file.strip_prefix(repo_path).unwrap_or(file).to_string_lossy().replace('\\', "/"))
+            .collect();
         
-        // Make sure we're on the right branch
-        let switch_result = Command::new("git")
-            .args(&["checkout", branch])
-            .status()
+        let author = self.call(|| async { self.client.current().user().await })
             .await
-            .map_err(|e| RustAiToolError::Io(e))?;
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?
+            .login;
         
-        if !switch_result.success() {
-            std::env::set_current_dir(current_dir).ok();
-            return Err(RustAiToolError::GitHub(format!(
-                "Failed to switch to branch: {} (exit code: {:?})",
-                branch,
-                switch_result.code()
-            )));
-        }
+        // Owners are `@user`, `@org/team` or e-mail addresses, which cannot be requested
+        let reviewers: Vec<String> = CodeOwners::load(repo_path)?
+            .map(|owners| owners.owners_of_all(changed_paths.iter().map(String::as_str)))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|owner| owner.strip_prefix('@').map(String::from))
+            .filter(|owner| !owner.eq_ignore_ascii_case(&author))
+            .collect();
         
-        // Stage the files
-        for file in files {
-            debug!("Staging file: {}", file.display());
-            let add_result = Command::new("git")
-                .args(&["add", &file.to_string_lossy()])
-                .status()
-                .await
-                .map_err(|e| RustAiToolError::Io(e))?;
-            
-            if !add_result.success() {
-                std::env::set_current_dir(current_dir).ok();
-                return Err(RustAiToolError::GitHub(format!(
-                    "Failed to stage file: {} (exit code: {:?})",
-                    file.display(),
-                    add_result.code()
-                )));
-            }
-        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let fix_branch = format!("rust-ai-tool/{}-{}", branch, now);
+        warn!("Branch {} does not accept direct pushes, opening a pull request from {}", branch, fix_branch);
         
-        // Commit the changes
-        let commit_result = Command::new("git")
-            .args(&["commit", "-m", message])
-            .status()
-            .await
-            .map_err(|e| RustAiToolError::Io(e))?;
+        run_git(repo_path, &["checkout", "-b", &fix_branch], &format!("create branch: {}", fix_branch)).await?;
+        commit_and_push(repo_path, files, message, &fix_branch).await?;
         
-        if !commit_result.success() {
-            std::env::set_current_dir(current_dir).ok();
-            return Err(RustAiToolError::GitHub(format!(
-                "Failed to commit changes (exit code: {:?})",
-                commit_result.code()
-            )));
-        }
+        let body = format!(
+            "Rust AI Tool could not push to `{}` directly because the branch is protected.\n\nChanged files:\n{}",
+            branch,
+            changed_paths.iter().map(|path| format!("- `{}`\n", path)).collect::<String>()
+        );
+        let options = PullRequestOptions {
+            reviewers,
+            ..PullRequestOptions::default()
+        };
         
-        // Push the changes
-        let push_result = Command::new("git")
-            .args(&["push", "origin", branch])
-            .status()
-            .await
-            .map_err(|e| RustAiToolError::Io(e))?;
+        self.create_pull_request(message, &body, &fix_branch, branch, &options).await.map(Some)
+    }
+    
+    /// Whether changes to a branch have to go through a pull request
+    ///
+    /// A branch that does not exist yet is created by the push, so it accepts it.
+    pub async fn branch_requires_pull_request(&self, branch: &str) -> Result<bool> {
+        let endpoint = format!("repos/{}/{}/branches/{}", self.owner, self.repo, branch);
+        let branch_info: serde_json::Value = match self.call(|| async {
+            self.client.get(&endpoint, None::<&()>).await
+        }).await {
+            Ok(branch_info) => branch_info,
+            Err(e) if is_not_found_error(&e) => return Ok(false),
+            Err(e) => return Err(RustAiToolError::GitHub(e.to_string())),
+        };
         
-        if !push_result.success() {
-            std::env::set_current_dir(current_dir).ok();
-            return Err(RustAiToolError::GitHub(format!(
-                "Failed to push changes (exit code: {:?})",
-                push_result.code()
-            )));
+        if !branch_info["protected"].as_bool().unwrap_or(false) {
+            return Ok(false);
         }
         
-        // Return to the original directory
-        std::env::set_current_dir(current_dir).map_err(|e| RustAiToolError::Io(e))?;
-        
-        info!("Successfully committed and pushed changes");
-        Ok(())
+        // Reading the protection rules needs admin access; without it, assume the worst
+        let endpoint = format!("repos/{}/{}/branches/{}/protection", self.owner, self.repo, branch);
+        match self.call(|| async { self.client.get::<serde_json::Value, _, _>(&endpoint, None::<&()>).await }).await {
+            Ok(protection) => {
+                let requires_reviews = !protection["required_pull_request_reviews"].is_null();
+                let restricts_pushes = !protection["restrictions"].is_null();
+                debug!("Protection of {}: reviews required {}, pushes restricted {}", branch, requires_reviews, restricts_pushes);
+                Ok(requires_reviews || restricts_pushes)
+            }
+            Err(e) => {
+                debug!("Cannot read the protection of {}, assuming pull requests are required: {}", branch, e);
+                Ok(true)
+            }
+        }
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), pr = pr_number), err)]
     pub async fn add_pr_comment(&self, pr_number: u64, comment: &str) -> Result<()> {
         info!("Adding comment to PR #{}", pr_number);
         
-        self.client
-            .issues(&self.owner, &self.repo)
-            .create_comment(pr_number, comment)
-            .await
+        self.call(|| async {
+            self.client
+                .issues(&self.owner, &self.repo)
+                .create_comment(pr_number, comment)
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         info!("Successfully added comment to PR #{}", pr_number);
         Ok(())
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), pr = pr_number, path = path), err)]
+    pub async fn add_inline_comment(&self, pr_number: u64, path: &str, line: usize, comment: &str) -> Result<()> {
+        info!("Adding comment to {}:{} in PR #{}", path, line, pr_number);
+        
+        // Review comments are attached to the head commit of the pull request
+        let pull_request = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .get(pr_number)
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        let endpoint = format!("repos/{}/{}/pulls/{}/comments", self.owner, self.repo, pr_number);
+        let body = serde_json::json!({
+            "body": comment,
+            "commit_id": pull_request.head.sha,
+            "path": path,
+            "line": line,
+            "side": "RIGHT",
+        });
+        
+        let _: serde_json::Value = self.call(|| async {
+            self.client
+                .post(&endpoint, Some(&body))
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), pr = pr_number), err)]
     pub async fn get_pull_request(&self, pr_number: u64) -> Result<PullRequestInfo> {
         info!("Getting information for PR #{}", pr_number);
         
-        let pull_request = self.client
-            .pulls(&self.owner, &self.repo)
-            .get(pr_number)
-            .await
+        let pull_request = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .get(pr_number)
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         // Check if the PR is merged with a separate API call
-        let is_merged = self.client
-            .pulls(&self.owner, &self.repo)
-            .is_merged(pr_number)
-            .await
+        let is_merged = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .is_merged(pr_number)
+                .await
+        }).await
             .unwrap_or(false);
         
         let pr_info = PullRequestInfo {
@@ -292,12 +504,144 @@ impl GithubClient {
                 || "unknown".to_string(),
                 |s| format!("{:?}", s).to_lowercase()
             ),
+            body: pull_request.body.unwrap_or_default(),
         };
         
         debug!("PR info: {:?}", pr_info);
         Ok(pr_info)
     }
     
+    /// List the repositories of an organization
+    pub async fn list_org_repositories(&self, org: &str) -> Result<Vec<OrgRepository>> {
+        info!("Listing repositories of {}", org);
+        
+        let endpoint = format!("orgs/{}/repos", org);
+        let mut repositories = Vec::new();
+        
+        for page in 1u32.. {
+            let page = page.to_string();
+            let page_repositories: Vec<OrgRepository> = self.call(|| async {
+                self.client
+                    .get(&endpoint, Some(&[("type", "all"), ("per_page", "100"), ("page", page.as_str())]))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+            
+            let page_len = page_repositories.len();
+            repositories.extend(page_repositories);
+            
+            if page_len < 100 {
+                break;
+            }
+        }
+        
+        Ok(repositories)
+    }
+    
+    /// Find the open pull request from a branch of this repository, if any
+    pub async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequestInfo>> {
+        let endpoint = format!("repos/{}/{}/pulls", self.owner, self.repo);
+        let head = format!("{}:{}", self.owner, head);
+        
+        let pull_requests: Vec<models::pulls::PullRequest> = self.call(|| async {
+            self.client
+                .get(&endpoint, Some(&[("state", "open"), ("head", head.as_str()), ("per_page", "1")]))
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        Ok(pull_requests.into_iter().next().map(|pull_request| PullRequestInfo {
+            number: pull_request.number,
+            title: pull_request.title.unwrap_or_else(|| "No title".to_string()),
+            url: pull_request.html_url.map_or_else(
+                || format!("https://github.com/{}/{}/pull/{}", self.owner, self.repo, pull_request.number),
+                |url| url.to_string(),
+            ),
+            is_merged: false,
+            state: "open".to_string(),
+            body: pull_request.body.unwrap_or_default(),
+        }))
+    }
+    
+    /// Replace the title and body of a pull request
+    pub async fn update_pull_request(&self, pr_number: u64, title: &str, body: &str) -> Result<()> {
+        info!("Updating PR #{}", pr_number);
+        
+        let endpoint = format!("repos/{}/{}/pulls/{}", self.owner, self.repo, pr_number);
+        let _: serde_json::Value = self.call(|| async {
+            self.client
+                .patch(&endpoint, Some(&serde_json::json!({ "title": title, "body": body })))
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    /// Get the base and head commits of a pull request
+    pub async fn get_pull_request_commits(&self, pr_number: u64) -> Result<PullRequestCommits> {
+        let pull_request = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .get(pr_number)
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        Ok(PullRequestCommits {
+            base_branch: pull_request.base.ref_field,
+            base_sha: pull_request.base.sha,
+            head_sha: pull_request.head.sha,
+        })
+    }
+    
+    /// Comment on a pull request, replacing the earlier comment with the same marker
+    ///
+    /// # Arguments
+    ///
+    /// * `pr_number` - Number of the pull request
+    /// * `marker` - Text identifying the comment (e.g. an HTML comment)
+    /// * `comment` - Text of the comment, containing `marker`
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), pr = pr_number), err)]
+    pub async fn upsert_pr_comment(&self, pr_number: u64, marker: &str, comment: &str) -> Result<()> {
+        let endpoint = format!("repos/{}/{}/issues/{}/comments", self.owner, self.repo, pr_number);
+        let mut existing = None;
+        
+        for page in 1u32.. {
+            let page = page.to_string();
+            let comments: Vec<serde_json::Value> = self.call(|| async {
+                self.client
+                    .get(&endpoint, Some(&[("per_page", "100"), ("page", page.as_str())]))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+            
+            let page_len = comments.len();
+            existing = comments.iter()
+                .find(|c| c["body"].as_str().is_some_and(|body| body.contains(marker)))
+                .and_then(|c| c["id"].as_u64());
+            
+            if existing.is_some() || page_len < 100 {
+                break;
+            }
+        }
+        
+        let Some(comment_id) = existing else {
+            return self.add_pr_comment(pr_number, comment).await;
+        };
+        
+        info!("Updating comment {} of PR #{}", comment_id, pr_number);
+        let endpoint = format!("repos/{}/{}/issues/comments/{}", self.owner, self.repo, comment_id);
+        let _: serde_json::Value = self.call(|| async {
+            self.client
+                .patch(&endpoint, Some(&serde_json::json!({ "body": comment })))
+                .await
+        }).await
+            .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+        
+        Ok(())
+    }
+    
     pub async fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequestInfo>> {
         info!("Listing {} pull requests", state);
         
@@ -309,21 +653,25 @@ impl GithubClient {
             _ => params::State::Open, // Default to open
         };
         
-        let pull_requests = self.client
-            .pulls(&self.owner, &self.repo)
-            .list()
-            .state(state_param)
-            .send()
-            .await
+        let pull_requests = self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .list()
+                .state(state_param)
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         let mut prs = Vec::new();
         for pr in pull_requests.items {
             // Check if the PR is merged with a separate API call
-            let is_merged = self.client
-                .pulls(&self.owner, &self.repo)
-                .is_merged(pr.number)
-                .await
+            let is_merged = self.call(|| async {
+                self.client
+                    .pulls(&self.owner, &self.repo)
+                    .is_merged(pr.number)
+                    .await
+            }).await
                 .unwrap_or(false);
             
             prs.push(PullRequestInfo {
@@ -338,6 +686,7 @@ impl GithubClient {
                     || "unknown".to_string(),
                     |s| format!("{:?}", s).to_lowercase()
                 ),
+                body: pr.body.unwrap_or_default(),
             });
         }
         
@@ -345,15 +694,18 @@ impl GithubClient {
         Ok(prs)
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), pr = pr_number), err)]
     pub async fn merge_pull_request(&self, pr_number: u64, commit_message: &str) -> Result<()> {
         info!("Merging PR #{}", pr_number);
         
-        self.client
-            .pulls(&self.owner, &self.repo)
-            .merge(pr_number)
-            .message(commit_message)
-            .send()
-            .await
+        self.call(|| async {
+            self.client
+                .pulls(&self.owner, &self.repo)
+                .merge(pr_number)
+                .message(commit_message)
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         info!("Successfully merged PR #{}", pr_number);
@@ -363,13 +715,15 @@ impl GithubClient {
     pub async fn get_file_content(&self, path: &str, branch: Option<&str>) -> Result<String> {
         info!("Getting content of file: {}", path);
         
-        let content = self.client
-            .repos(&self.owner, &self.repo)
-            .get_content()
-            .path(path)
-            .r#ref(branch.unwrap_or(""))
-            .send()
-            .await
+        let content = self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .get_content()
+                .path(path)
+                .r#ref(branch.unwrap_or(""))
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         match content {
@@ -397,17 +751,20 @@ impl GithubClient {
         }
     }
     
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo), path = path), err)]
     pub async fn create_or_update_file(&self, path: &str, content: &str, commit_message: &str, branch: Option<&str>) -> Result<()> {
         info!("Creating or updating file: {}", path);
         
         // Get the current file to get its SHA (if it exists)
-        let sha = match self.client
-            .repos(&self.owner, &self.repo)
-            .get_content()
-            .path(path)
-            .r#ref(branch.unwrap_or(""))
-            .send()
-            .await
+        let sha = match self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .get_content()
+                .path(path)
+                .r#ref(branch.unwrap_or(""))
+                .send()
+                .await
+        }).await
         {
             Ok(models::repos::ContentItems::File(file)) => file.sha,
             _ => None, // File doesn't exist yet
@@ -419,21 +776,25 @@ impl GithubClient {
         // Create or update the file
         if let Some(sha_str) = sha {
             // Update existing file
-            self.client
-                .repos(&self.owner, &self.repo)
-                .update_file(path, commit_message, &encoded, &sha_str)
-                .branch(branch.unwrap_or(""))
-                .send()
-                .await
+            self.call(|| async {
+                self.client
+                    .repos(&self.owner, &self.repo)
+                    .update_file(path, commit_message, &encoded, &sha_str)
+                    .branch(branch.unwrap_or(""))
+                    .send()
+                    .await
+            }).await
                 .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         } else {
             // Create new file
-            self.client
-                .repos(&self.owner, &self.repo)
-                .create_file(path, commit_message, &encoded)
-                .branch(branch.unwrap_or(""))
-                .send()
-                .await
+            self.call(|| async {
+                self.client
+                    .repos(&self.owner, &self.repo)
+                    .create_file(path, commit_message, &encoded)
+                    .branch(branch.unwrap_or(""))
+                    .send()
+                    .await
+            }).await
                 .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         }
         
@@ -441,30 +802,98 @@ impl GithubClient {
         Ok(())
     }
     
-    pub async fn create_issue(&self, title: &str, body: &str, labels: &[String]) -> Result<u64> {
+    #[tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", self.owner, self.repo)), err)]
+    pub async fn create_issue(&self, title: &str, body: &str, labels: &[String], assignees: &[String]) -> Result<u64> {
         info!("Creating issue: {}", title);
         
-        let issue = self.client
-            .issues(&self.owner, &self.repo)
-            .create(title)
-            .body(body)
-            .labels(labels.to_vec())
-            .send()
-            .await
+        let issue = self.call(|| async {
+            self.client
+                .issues(&self.owner, &self.repo)
+                .create(title)
+                .body(body)
+                .labels(labels.to_vec())
+                .assignees(assignees.to_vec())
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         info!("Successfully created issue #{}", issue.number);
         Ok(issue.number)
     }
     
+    /// Numbers and bodies of the open issues of the repository (pull requests excluded)
+    pub async fn list_open_issues(&self) -> Result<Vec<(u64, String)>> {
+        info!("Listing open issues of {}/{}", self.owner, self.repo);
+        
+        let endpoint = format!("repos/{}/{}/issues", self.owner, self.repo);
+        let mut open_issues = Vec::new();
+        
+        for page in 1u32.. {
+            let page = page.to_string();
+            let issues: Vec<serde_json::Value> = self.call(|| async {
+                self.client
+                    .get(&endpoint, Some(&[("state", "open"), ("per_page", "100"), ("page", page.as_str())]))
+                    .await
+            }).await
+                .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
+            
+            let page_len = issues.len();
+            open_issues.extend(issues.into_iter()
+                .filter(|issue| issue.get("pull_request").is_none())
+                .filter_map(|issue| Some((issue["number"].as_u64()?, issue["body"].as_str()?.to_string()))));
+            
+            if page_len < 100 {
+                break;
+            }
+        }
+        
+        Ok(open_issues)
+    }
+    
+    /// File a GitHub issue proposing a fix that needs human review
+    ///
+    /// # Arguments
+    ///
+    /// * `modification` - Modification to propose
+    /// * `base_dir` - Project root, used to make the file path repository-relative
+    ///
+    /// # Returns
+    ///
+    /// Number of the created issue
+    pub async fn create_pending_fix_issue(&self, modification: &CodeModification, base_dir: &Path) -> Result<u64> {
+        let relative_path = modification.file_path
+            .strip_prefix(base_dir)
+            .unwrap_or(&modification.file_path);
+        
+        let title = format!("Review suggested fix for {}", relative_path.display());
+        
+        let diff = generate_unified_diff(
+            relative_path,
+            &modification.original_content,
+            &modification.modified_content,
+        );
+        
+        let body = format!(
+            "{}\n\nConfidence: {}% (below the threshold for automatic application)\n\n```diff\n{}```\n",
+            modification.description,
+            modification.confidence,
+            diff
+        );
+        
+        self.create_issue(&title, &body, &["rust-ai-tool".to_string(), "needs-review".to_string()], &[]).await
+    }
+    
     pub async fn list_branches(&self) -> Result<Vec<String>> {
         info!("Listing branches for {}/{}", self.owner, self.repo);
         
-        let branches = self.client
-            .repos(&self.owner, &self.repo)
-            .list_branches()
-            .send()
-            .await
+        let branches = self.call(|| async {
+            self.client
+                .repos(&self.owner, &self.repo)
+                .list_branches()
+                .send()
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         let branch_names = branches.items
@@ -482,9 +911,11 @@ impl GithubClient {
         let endpoint = format!("repos/{}/{}/compare/{}...{}", 
             self.owner, self.repo, base, head);
         
-        let response: serde_json::Value = self.client
-            .get(&endpoint, None::<&()>)
-            .await
+        let response: serde_json::Value = self.call(|| async {
+            self.client
+                .get(&endpoint, None::<&()>)
+                .await
+        }).await
             .map_err(|e| RustAiToolError::GitHub(e.to_string()))?;
         
         // Extract filenames from the response
@@ -499,4 +930,184 @@ impl GithubClient {
         
         Ok(files)
     }
-}
\ No newline at end of file
+    
+    /// Make a GitHub API call within the rate limits
+    ///
+    /// Waits for the quota to reset when it is nearly used up, and retries
+    /// calls rejected by a primary or secondary rate limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Makes the call; run again for each retry
+    ///
+    /// # Returns
+    ///
+    /// The result of the last attempt
+    async fn call<T, F, Fut>(&self, request: F) -> std::result::Result<T, octocrab::Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = std::result::Result<T, octocrab::Error>>,
+    {
+        self.wait_for_quota().await;
+        
+        let mut retries = 0;
+        loop {
+            let result = request().await;
+            // Failed calls count too, so the quota is still checked every few calls
+            self.count_call();
+            
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) if retries < MAX_RATE_LIMIT_RETRIES && is_rate_limit_error(&e) => {
+                    retries += 1;
+                    
+                    // An exhausted quota waits for its reset; a secondary limit backs off
+                    let wait = match self.check_quota().await {
+                        Some(0) => self.seconds_until_reset(),
+                        _ => SECONDARY_RATE_LIMIT_WAIT * u64::from(retries),
+                    };
+                    
+                    warn!("GitHub API rate limit hit, retrying in {}s ({}/{}): {}", wait, retries, MAX_RATE_LIMIT_RETRIES, e);
+                    tokio::time::sleep(Duration::from_secs(wait)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    
+    /// Check the quota every few calls, and wait for its reset when it is nearly used up
+    async fn wait_for_quota(&self) {
+        let needs_check = self.rate_limit.lock().unwrap().calls_since_check == 0;
+        let remaining = if needs_check {
+            self.check_quota().await
+        } else {
+            self.rate_limit.lock().unwrap().remaining
+        };
+        
+        if remaining.is_some_and(|remaining| remaining <= RATE_LIMIT_RESERVE) {
+            let wait = self.seconds_until_reset();
+            warn!("GitHub API quota nearly used up ({:?} calls left), waiting {}s for it to reset", remaining, wait);
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+            self.check_quota().await;
+        }
+    }
+    
+    /// Get the remaining quota (this does not count against it)
+    async fn check_quota(&self) -> Option<u64> {
+        let rate_limit: serde_json::Value = match self.client.get("rate_limit", None::<&()>).await {
+            Ok(rate_limit) => rate_limit,
+            Err(e) => {
+                debug!("Could not get the GitHub API quota: {}", e);
+                return None;
+            }
+        };
+        
+        let rate = &rate_limit["rate"];
+        let mut state = self.rate_limit.lock().unwrap();
+        state.remaining = rate["remaining"].as_u64();
+        state.limit = rate["limit"].as_u64();
+        state.reset = rate["reset"].as_u64();
+        state.calls_since_check = 0;
+        
+        debug!(
+            "GitHub API quota: {} of {} calls left, resets in {}s",
+            state.remaining.map_or_else(|| "?".to_string(), |n| n.to_string()),
+            state.limit.map_or_else(|| "?".to_string(), |n| n.to_string()),
+            seconds_until(state.reset)
+        );
+        state.remaining
+    }
+    
+    /// Count a call against the known quota
+    fn count_call(&self) {
+        let mut state = self.rate_limit.lock().unwrap();
+        state.remaining = state.remaining.map(|remaining| remaining.saturating_sub(1));
+        state.calls_since_check = (state.calls_since_check + 1) % QUOTA_CHECK_INTERVAL;
+    }
+    
+    /// Seconds until the quota resets (at least one)
+    fn seconds_until_reset(&self) -> u64 {
+        seconds_until(self.rate_limit.lock().unwrap().reset)
+    }
+}
+
+/// Whether an API error comes from a primary or secondary rate limit
+fn is_rate_limit_error(error: &octocrab::Error) -> bool {
+    error.to_string().to_lowercase().contains("rate limit")
+}
+
+/// Whether an API error reports a missing resource
+fn is_not_found_error(error: &octocrab::Error) -> bool {
+    error.to_string().to_lowercase().contains("not found")
+}
+
+/// Seconds from now until a Unix timestamp (at least one, one minute if unknown)
+fn seconds_until(timestamp: Option<u64>) -> u64 {
+    let Some(timestamp) = timestamp else {
+        return SECONDARY_RATE_LIMIT_WAIT;
+    };
+    
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    timestamp.saturating_sub(now).max(1)
+}
+
+#[async_trait]
+impl ForgeProvider for GithubClient {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+    
+    fn clone_url(&self) -> String {
+        match self.ssh {
+            Some(_) => format!("git@github.com:{}/{}.git", self.owner, self.repo),
+            None => format!("https://github.com/{}/{}.git", self.owner, self.repo),
+        }
+    }
+    
+    fn ssh(&self) -> Option<&SshConfig> {
+        self.ssh.as_ref()
+    }
+    
+    async fn get_repo_info(&self) -> Result<RepoInfo> {
+        GithubClient::get_repo_info(self).await
+    }
+    
+    async fn create_branch(&self, base_branch: &str, new_branch: &str) -> Result<()> {
+        GithubClient::create_branch(self, base_branch, new_branch).await
+    }
+    
+    async fn create_pull_request(
+        &self,
+        title: &str,
+        body: &str,
+        head: &str,
+        base: &str,
+        options: &PullRequestOptions,
+    ) -> Result<PullRequestInfo> {
+        GithubClient::create_pull_request(self, title, body, head, base, options).await
+    }
+    
+    async fn get_pull_request(&self, pr_number: u64) -> Result<PullRequestInfo> {
+        GithubClient::get_pull_request(self, pr_number).await
+    }
+    
+    async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequestInfo>> {
+        GithubClient::find_open_pull_request(self, head).await
+    }
+    
+    async fn update_pull_request(&self, pr_number: u64, title: &str, body: &str) -> Result<()> {
+        GithubClient::update_pull_request(self, pr_number, title, body).await
+    }
+    
+    async fn add_pr_comment(&self, pr_number: u64, comment: &str) -> Result<()> {
+        GithubClient::add_pr_comment(self, pr_number, comment).await
+    }
+    
+    async fn add_inline_comment(&self, pr_number: u64, path: &str, line: usize, comment: &str) -> Result<()> {
+        GithubClient::add_inline_comment(self, pr_number, path, line, comment).await
+    }
+    
+    async fn get_file_content(&self, path: &str, branch: Option<&str>) -> Result<String> {
+        GithubClient::get_file_content(self, path, branch).await
+    }
+}
@@ -0,0 +1,179 @@
+//! GitHub Actions module
+//!
+//! This module reports analysis results from inside a GitHub Actions job:
+//! - One `::error`, `::warning` or `::notice` workflow command per issue, shown
+//!   as an annotation on the offending lines
+//! - A job summary with the issue counts by severity and the listed issues,
+//!   appended to the file named by `$GITHUB_STEP_SUMMARY`
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::{Result, RustAiToolError, Severity};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use log::{debug, info};
+
+/// Issues listed in the job summary; the rest are only counted
+const MAX_SUMMARY_ISSUES: usize = 100;
+
+/// Format analysis results as workflow commands
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+///
+/// # Returns
+///
+/// One workflow command per line, to be printed on standard output
+pub fn format_workflow_commands(results: &[AnalysisResult]) -> String {
+    results.iter()
+        .flat_map(|result| &result.issues)
+        .map(|issue| format!("{}\n", workflow_command(issue)))
+        .collect()
+}
+
+/// Append the job summary of analysis results to `$GITHUB_STEP_SUMMARY`
+///
+/// Does nothing outside of GitHub Actions, where the variable is not set.
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+///
+/// # Returns
+///
+/// Whether a summary was written
+pub fn write_step_summary(results: &[AnalysisResult]) -> Result<bool> {
+    let Some(summary_path) = env::var_os("GITHUB_STEP_SUMMARY") else {
+        debug!("GITHUB_STEP_SUMMARY is not set, not writing a job summary");
+        return Ok(false);
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&summary_path)
+        .map_err(RustAiToolError::Io)?;
+    file.write_all(format_step_summary(results).as_bytes()).map_err(RustAiToolError::Io)?;
+
+    info!("Job summary written to {}", Path::new(&summary_path).display());
+    Ok(true)
+}
+
+/// Format analysis results as a job summary, in GitHub-flavored Markdown
+pub fn format_step_summary(results: &[AnalysisResult]) -> String {
+    let issues: Vec<&CodeIssue> = results.iter().flat_map(|result| &result.issues).collect();
+
+    let mut summary = format!("## Rust AI Tool\n\n**Total Issues Found**: {}\n\n", issues.len());
+    if issues.is_empty() {
+        return summary;
+    }
+
+    summary.push_str("| Severity | Issues |\n|---|---|\n");
+    for severity in [Severity::Error, Severity::Warning, Severity::Info, Severity::Style] {
+        let count = issues.iter().filter(|issue| issue.severity == severity).count();
+        summary.push_str(&format!("| {:?} | {} |\n", severity, count));
+    }
+
+    summary.push_str("\n| File | Line | Severity | Category | Issue |\n|---|---|---|---|---|\n");
+    for issue in issues.iter().take(MAX_SUMMARY_ISSUES) {
+        summary.push_str(&format!(
+            "| `{}` | {} | {:?} | {:?} | {} |\n",
+            workspace_path(&issue.file_path),
+            issue.line_start,
+            issue.severity,
+            issue.category,
+            issue.message.replace('|', "\\|").replace('\n', " ")
+        ));
+    }
+
+    if issues.len() > MAX_SUMMARY_ISSUES {
+        summary.push_str(&format!("\n{} more issues not shown.\n", issues.len() - MAX_SUMMARY_ISSUES));
+    }
+
+    summary.push('\n');
+    summary
+}
+
+/// Workflow command annotating the lines of one issue
+fn workflow_command(issue: &CodeIssue) -> String {
+    let command = match issue.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Style => "notice",
+    };
+
+    format!(
+        "::{} file={},line={},endLine={},col={},title={}::{}",
+        command,
+        escape_property(&workspace_path(&issue.file_path)),
+        issue.line_start.max(1),
+        issue.line_end.max(issue.line_start).max(1),
+        issue.column_start.max(1),
+        escape_property(&format!("Rust AI Tool: {:?}", issue.category)),
+        escape_data(&issue.message)
+    )
+}
+
+/// Path of a file relative to the workspace the job checked out, with forward slashes
+fn workspace_path(file_path: &Path) -> String {
+    let workspace = env::var_os("GITHUB_WORKSPACE")
+        .map(Into::into)
+        .or_else(|| env::current_dir().ok());
+
+    let path = match &workspace {
+        Some(workspace) if file_path.is_absolute() => file_path.strip_prefix(workspace).unwrap_or(file_path),
+        _ => file_path,
+    };
+    let path = path.strip_prefix(".").unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Escape the message of a workflow command
+fn escape_data(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_workflow_command() {
+        let issue = CodeIssue {
+            file_path: PathBuf::from("./src/lib.rs"),
+            line_start: 3,
+            column_start: 5,
+            line_end: 4,
+            column_end: 1,
+            category: IssueCategory::Performance,
+            severity: Severity::Warning,
+            message: "100% slower,\nclone in a loop".to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        };
+
+        assert_eq!(
+            workflow_command(&issue),
+            "::warning file=src/lib.rs,line=3,endLine=4,col=5,title=Rust AI Tool%3A Performance::100%25 slower,%0Aclone in a loop"
+        );
+
+        let results = vec![AnalysisResult {
+            file_path: issue.file_path.clone(),
+            issues: vec![issue],
+            errors: Vec::new(),
+            success: true,
+        }];
+        assert!(format_step_summary(&results).contains("| Warning | 1 |"));
+    }
+}
@@ -0,0 +1,410 @@
+//! History module
+//!
+//! This module keeps the history of a project in a SQLite database,
+//! `.rust-ai-tool/history.db`:
+//! - Every analysis (`analyze`, `fix`) records a run with its issues by id, so
+//!   trends can be queried and issues reported before told apart from new ones
+//! - Fixes generated by `fix` are recorded with their outcome (validated or
+//!   failed), and the feedback on proposed fixes (accepted, edited, rejected)
+//!   along with the feedback log
+//! - `runs` shows the trend of the recorded runs; `analyze --new` only reports
+//!   issues no earlier run reported, and `analyze --baseline <run>` the issues
+//!   that run did not report
+//! - The schema version is kept in `PRAGMA user_version`, so later versions can
+//!   migrate the database
+
+use crate::analysis::AnalysisResult;
+use crate::feedback::FeedbackEntry;
+use crate::journal::STATE_DIR;
+use crate::pipeline::FixPipelineReport;
+use crate::{Result, RustAiToolError, Severity};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::debug;
+
+/// File name of the history database inside the state directory
+pub const HISTORY_FILE: &str = "history.db";
+
+/// Version of the schema created by `SCHEMA`
+const SCHEMA_VERSION: i64 = 1;
+
+/// Tables of the database
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    files INTEGER NOT NULL,
+    issues INTEGER NOT NULL,
+    errors INTEGER NOT NULL,
+    warnings INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS issues (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    issue_id TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    line_start INTEGER NOT NULL,
+    category TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    lint TEXT,
+    message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS issues_by_id ON issues (issue_id, run_id);
+CREATE INDEX IF NOT EXISTS issues_by_run ON issues (run_id);
+CREATE TABLE IF NOT EXISTS fixes (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    file_path TEXT NOT NULL,
+    description TEXT NOT NULL,
+    confidence INTEGER,
+    issue_ids TEXT NOT NULL,
+    outcome TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS feedback (
+    timestamp INTEGER NOT NULL,
+    file_path TEXT NOT NULL,
+    description TEXT NOT NULL,
+    source TEXT NOT NULL,
+    outcome TEXT NOT NULL
+);
+";
+
+/// A recorded run, with its issue counts
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    /// Id of the run, for `analyze --baseline`
+    pub id: i64,
+
+    /// Time of the run (seconds since the Unix epoch)
+    pub timestamp: u64,
+
+    /// Command of the run, like `analyze`
+    pub command: String,
+
+    /// Files analyzed
+    pub files: usize,
+
+    /// Issues reported
+    pub issues: usize,
+
+    /// Issues with `Error` severity
+    pub errors: usize,
+
+    /// Issues with `Warning` severity
+    pub warnings: usize,
+
+    /// Issues no earlier run reported
+    pub new_issues: usize,
+}
+
+/// Outcomes of the feedback on proposed fixes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedbackTotals {
+    /// Changes applied as proposed
+    pub accepted: usize,
+
+    /// Changes applied after editing
+    pub edited: usize,
+
+    /// Changes not applied
+    pub rejected: usize,
+}
+
+/// History database of a project
+#[derive(Debug, Clone)]
+pub struct HistoryDb {
+    pool: SqlitePool,
+}
+
+impl HistoryDb {
+    /// Open the history database of a project, creating it if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project
+    ///
+    /// # Returns
+    ///
+    /// The database, with its schema created
+    pub async fn open(project_path: &Path) -> Result<Self> {
+        let state_dir = project_path.join(STATE_DIR);
+        fs::create_dir_all(&state_dir).map_err(RustAiToolError::Io)?;
+
+        let options = SqliteConnectOptions::new()
+            .filename(state_dir.join(HISTORY_FILE))
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(db_error)?;
+
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(&pool).await.map_err(db_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(RustAiToolError::Other(format!(
+                "History database {} has schema version {}, newer than this build (version {})",
+                state_dir.join(HISTORY_FILE).display(), version, SCHEMA_VERSION
+            )));
+        }
+
+        sqlx::raw_sql(SCHEMA).execute(&pool).await.map_err(db_error)?;
+        sqlx::raw_sql(&format!("PRAGMA user_version = {}", SCHEMA_VERSION)).execute(&pool).await.map_err(db_error)?;
+
+        Ok(HistoryDb { pool })
+    }
+
+    /// Record an analysis run and its issues
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - Command of the run, like `analyze`
+    /// * `results` - Analysis results
+    ///
+    /// # Returns
+    ///
+    /// Id of the recorded run
+    pub async fn record_analysis(&self, command: &str, results: &[AnalysisResult]) -> Result<i64> {
+        let issues: Vec<_> = results.iter().flat_map(|result| &result.issues).collect();
+        let count = |severity: Severity| issues.iter().filter(|issue| issue.severity == severity).count() as i64;
+
+        let mut transaction = self.pool.begin().await.map_err(db_error)?;
+        let run_id = sqlx::query("INSERT INTO runs (timestamp, command, files, issues, errors, warnings) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(now() as i64)
+            .bind(command)
+            .bind(results.len() as i64)
+            .bind(issues.len() as i64)
+            .bind(count(Severity::Error))
+            .bind(count(Severity::Warning))
+            .execute(&mut *transaction)
+            .await
+            .map_err(db_error)?
+            .last_insert_rowid();
+
+        for issue in &issues {
+            sqlx::query("INSERT INTO issues (run_id, issue_id, file_path, line_start, category, severity, lint, message) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+                .bind(run_id)
+                .bind(issue.id())
+                .bind(issue.file_path.display().to_string())
+                .bind(issue.line_start as i64)
+                .bind(issue.category.name())
+                .bind(format!("{:?}", issue.severity))
+                .bind(issue.lint.as_deref())
+                .bind(&issue.message)
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+        }
+        transaction.commit().await.map_err(db_error)?;
+
+        debug!("Recorded run {} with {} issues in the history", run_id, issues.len());
+        Ok(run_id)
+    }
+
+    /// Record the fixes generated for a run
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Run whose issues were fixed
+    /// * `report` - Validated fixes and files that could not be fixed
+    pub async fn record_fixes(&self, run_id: i64, report: &FixPipelineReport) -> Result<()> {
+        let mut transaction = self.pool.begin().await.map_err(db_error)?;
+
+        for fix in &report.fixes {
+            let issue_ids: Vec<&str> = fix.resolves.iter().map(|issue| issue.id.as_str()).collect();
+            sqlx::query("INSERT INTO fixes (run_id, file_path, description, confidence, issue_ids, outcome) VALUES (?, ?, ?, ?, ?, 'validated')")
+                .bind(run_id)
+                .bind(fix.file_path.display().to_string())
+                .bind(&fix.description)
+                .bind(i64::from(fix.confidence))
+                .bind(issue_ids.join(","))
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+        }
+        for failed in &report.failed {
+            sqlx::query("INSERT INTO fixes (run_id, file_path, description, confidence, issue_ids, outcome) VALUES (?, ?, ?, NULL, '', 'failed')")
+                .bind(run_id)
+                .bind(failed.file_path.display().to_string())
+                .bind(failed.errors.join("; "))
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+        }
+
+        transaction.commit().await.map_err(db_error)
+    }
+
+    /// Record feedback on proposed fixes
+    pub async fn record_feedback(&self, entries: &[FeedbackEntry]) -> Result<()> {
+        let mut transaction = self.pool.begin().await.map_err(db_error)?;
+
+        for entry in entries {
+            sqlx::query("INSERT INTO feedback (timestamp, file_path, description, source, outcome) VALUES (?, ?, ?, ?, ?)")
+                .bind(entry.timestamp as i64)
+                .bind(entry.file_path.display().to_string())
+                .bind(&entry.description)
+                .bind(&entry.source)
+                .bind(format!("{:?}", entry.outcome))
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+        }
+
+        transaction.commit().await.map_err(db_error)
+    }
+
+    /// Most recent runs, most recent first
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Number of runs
+    ///
+    /// # Returns
+    ///
+    /// The runs with their issue counts
+    pub async fn runs(&self, limit: usize) -> Result<Vec<RunRecord>> {
+        let rows = sqlx::query(
+            "SELECT r.id, r.timestamp, r.command, r.files, r.issues, r.errors, r.warnings,
+                (SELECT COUNT(DISTINCT i.issue_id) FROM issues i WHERE i.run_id = r.id
+                    AND NOT EXISTS (SELECT 1 FROM issues p WHERE p.issue_id = i.issue_id AND p.run_id < r.id)) AS new_issues
+             FROM runs r ORDER BY r.id DESC LIMIT ?"
+        )
+            .bind(limit.min(i64::MAX as usize) as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(rows.iter()
+            .map(|row| RunRecord {
+                id: row.get("id"),
+                timestamp: row.get::<i64, _>("timestamp") as u64,
+                command: row.get("command"),
+                files: row.get::<i64, _>("files") as usize,
+                issues: row.get::<i64, _>("issues") as usize,
+                errors: row.get::<i64, _>("errors") as usize,
+                warnings: row.get::<i64, _>("warnings") as usize,
+                new_issues: row.get::<i64, _>("new_issues") as usize,
+            })
+            .collect())
+    }
+
+    /// Ids of the issues reported by a run, or by any run
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Run, or `None` for all recorded runs
+    ///
+    /// # Returns
+    ///
+    /// The issue ids, or an error if there is no such run
+    pub async fn reported_issue_ids(&self, run_id: Option<i64>) -> Result<HashSet<String>> {
+        let ids: Vec<String> = match run_id {
+            Some(run_id) => {
+                let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM runs WHERE id = ?")
+                    .bind(run_id)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(db_error)?;
+                if exists.is_none() {
+                    return Err(RustAiToolError::Other(format!("No run {} in the history (see `rust-ai-tool runs`)", run_id)));
+                }
+
+                sqlx::query_scalar("SELECT DISTINCT issue_id FROM issues WHERE run_id = ?")
+                    .bind(run_id)
+                    .fetch_all(&self.pool)
+                    .await
+            }
+            None => sqlx::query_scalar("SELECT DISTINCT issue_id FROM issues").fetch_all(&self.pool).await,
+        }
+        .map_err(db_error)?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Outcomes of all recorded feedback
+    pub async fn feedback_totals(&self) -> Result<FeedbackTotals> {
+        let rows: Vec<(String, i64)> = sqlx::query_as("SELECT outcome, COUNT(*) FROM feedback GROUP BY outcome")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        let mut totals = FeedbackTotals::default();
+        for (outcome, count) in rows {
+            let count = count as usize;
+            match outcome.as_str() {
+                "Accepted" => totals.accepted += count,
+                "Edited" => totals.edited += count,
+                _ => totals.rejected += count,
+            }
+        }
+
+        Ok(totals)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn db_error(error: sqlx::Error) -> RustAiToolError {
+    RustAiToolError::Other(format!("History database error: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{CodeIssue, IssueCategory};
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn results(lines: &[usize]) -> Vec<AnalysisResult> {
+        vec![AnalysisResult {
+            file_path: PathBuf::from("src/lib.rs"),
+            issues: lines.iter()
+                .map(|line| CodeIssue {
+                    file_path: PathBuf::from("src/lib.rs"),
+                    line_start: *line,
+                    column_start: 1,
+                    line_end: *line,
+                    column_end: 1,
+                    category: IssueCategory::CodeQuality,
+                    severity: Severity::Warning,
+                    message: "message".to_string(),
+                    suggested_fix: None,
+                    priority: None,
+                    lint: None,
+                    owners: Vec::new(),
+                })
+                .collect(),
+            errors: Vec::new(),
+            success: true,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let dir = tempdir().unwrap();
+        let history = HistoryDb::open(dir.path()).await.unwrap();
+
+        let first = history.record_analysis("analyze", &results(&[1, 2])).await.unwrap();
+        let second = history.record_analysis("analyze", &results(&[2, 3, 4])).await.unwrap();
+
+        let runs = history.runs(10).await.unwrap();
+        assert_eq!(runs.iter().map(|run| (run.id, run.issues, run.new_issues)).collect::<Vec<_>>(), [(second, 3, 2), (first, 2, 2)]);
+        assert_eq!(runs[0].warnings, 3);
+
+        assert_eq!(history.reported_issue_ids(Some(first)).await.unwrap().len(), 2);
+        assert_eq!(history.reported_issue_ids(None).await.unwrap().len(), 4);
+        assert!(history.reported_issue_ids(Some(99)).await.is_err());
+
+        // Reopening keeps the runs
+        drop(history);
+        let history = HistoryDb::open(dir.path()).await.unwrap();
+        assert_eq!(history.runs(1).await.unwrap()[0].id, second);
+        assert_eq!(history.feedback_totals().await.unwrap().accepted, 0);
+    }
+}
@@ -0,0 +1,289 @@
+//! HTML report module
+//!
+//! This module renders analysis results as a static, browsable site:
+//! - One page per module, mirroring the crate's module tree
+//! - A navigation sidebar with per-module issue counts
+//! - An index page summarizing issues by severity
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::{Result, RustAiToolError, Severity};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use log::{debug, info};
+
+/// Stylesheet shared by all pages of the report
+const STYLESHEET: &str = r#"body { margin: 0; font-family: sans-serif; display: flex; }
+nav { width: 280px; min-height: 100vh; background: #f5f5f5; padding: 1em; box-sizing: border-box; }
+nav a { display: block; color: #1a4d8f; text-decoration: none; padding: 2px 0; font-family: monospace; }
+nav a.current { font-weight: bold; }
+nav .count { color: #888; }
+main { flex: 1; padding: 1em 2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { text-align: left; padding: 4px 8px; border-bottom: 1px solid #ddd; vertical-align: top; }
+pre { background: #f5f5f5; padding: 0.5em; overflow-x: auto; }
+.Error { color: #b00020; }
+.Warning { color: #b36b00; }
+.Info { color: #1a4d8f; }
+.Style { color: #555; }
+"#;
+
+/// A module of the analyzed project and the files that belong to it
+struct ModulePage<'a> {
+    /// Analysis results of the files in this module
+    files: Vec<&'a AnalysisResult>,
+}
+
+impl ModulePage<'_> {
+    fn issues(&self) -> impl Iterator<Item = &CodeIssue> {
+        self.files.iter().flat_map(|f| f.issues.iter())
+    }
+
+    fn issue_count(&self) -> usize {
+        self.files.iter().map(|f| f.issues.len()).sum()
+    }
+}
+
+/// Write the analysis results as a static HTML site
+///
+/// # Arguments
+///
+/// * `results` - Analysis results to render
+/// * `project_path` - Root of the analyzed project, used to derive module paths
+/// * `output_dir` - Directory the site is written to
+///
+/// # Returns
+///
+/// Path to the index page of the site
+pub fn write_html_report(
+    results: &[AnalysisResult],
+    project_path: &Path,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    info!("Writing HTML report to {}", output_dir.display());
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| RustAiToolError::Io(e))?;
+
+    let crate_name = crate_name(project_path);
+    let mut modules: BTreeMap<Vec<String>, ModulePage> = BTreeMap::new();
+
+    for result in results {
+        let module = module_path(&result.file_path, project_path, &crate_name);
+        modules.entry(module)
+            .or_insert_with(|| ModulePage { files: Vec::new() })
+            .files
+            .push(result);
+    }
+
+    fs::write(output_dir.join("style.css"), STYLESHEET)
+        .map_err(|e| RustAiToolError::Io(e))?;
+
+    let index_path = output_dir.join("index.html");
+    fs::write(&index_path, render_index(&modules))
+        .map_err(|e| RustAiToolError::Io(e))?;
+
+    for (module, page) in &modules {
+        let page_path = output_dir.join(page_file_name(module));
+        debug!("Writing module page {}", page_path.display());
+
+        fs::write(&page_path, render_module_page(module, page, &modules))
+            .map_err(|e| RustAiToolError::Io(e))?;
+    }
+
+    info!("HTML report written with {} module pages", modules.len());
+    Ok(index_path)
+}
+
+/// Derive the crate name from the project directory
+fn crate_name(project_path: &Path) -> String {
+    project_path
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "crate".to_string())
+        .replace('-', "_")
+}
+
+/// Map a source file to the module path it defines
+///
+/// `src/lib.rs` and `src/main.rs` map to the crate root, `src/foo/mod.rs` and
+/// `src/foo.rs` map to `crate::foo`. Files of workspace members are attributed
+/// to the directory containing their `src` folder.
+fn module_path(file_path: &Path, project_path: &Path, crate_name: &str) -> Vec<String> {
+    let relative = file_path.strip_prefix(project_path).unwrap_or(file_path);
+    let components: Vec<String> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    let (root, rest) = match components.iter().rposition(|c| c == "src") {
+        Some(0) | None => (crate_name.to_string(), components.as_slice()),
+        Some(i) => (components[i - 1].replace('-', "_"), &components[i + 1..]),
+    };
+    let rest = if rest.first().map_or(false, |c| c == "src") { &rest[1..] } else { rest };
+
+    let mut module = vec![root];
+    for (i, segment) in rest.iter().enumerate() {
+        if i + 1 < rest.len() {
+            module.push(segment.clone());
+        } else {
+            let stem = segment.strip_suffix(".rs").unwrap_or(segment);
+            if !matches!(stem, "lib" | "main" | "mod") {
+                module.push(stem.to_string());
+            }
+        }
+    }
+
+    module
+}
+
+/// File name of the page for a module
+fn page_file_name(module: &[String]) -> String {
+    format!("{}.html", module.join("."))
+}
+
+/// Render the navigation sidebar
+fn render_nav(modules: &BTreeMap<Vec<String>, ModulePage>, current: Option<&[String]>) -> String {
+    let mut nav = String::new();
+    nav.push_str("<nav>\n<a href=\"index.html\"><strong>Summary</strong></a>\n<hr>\n");
+
+    for (module, page) in modules {
+        let class = if current == Some(module.as_slice()) { " class=\"current\"" } else { "" };
+        nav.push_str(&format!(
+            "<a href=\"{}\"{} style=\"padding-left: {}em\">{} <span class=\"count\">({})</span></a>\n",
+            page_file_name(module),
+            class,
+            (module.len() - 1),
+            escape_html(module.last().map(String::as_str).unwrap_or_default()),
+            page.issue_count()
+        ));
+    }
+
+    nav.push_str("</nav>\n");
+    nav
+}
+
+/// Wrap page content with the shared layout
+fn render_layout(title: &str, nav: &str, content: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+        <link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n{}<main>\n{}</main>\n</body>\n</html>\n",
+        escape_html(title),
+        nav,
+        content
+    )
+}
+
+/// Render the index page with a per-severity and per-module summary
+fn render_index(modules: &BTreeMap<Vec<String>, ModulePage>) -> String {
+    let mut content = String::new();
+    content.push_str("<h1>Rust AI Tool Analysis Results</h1>\n");
+
+    let issues: Vec<&CodeIssue> = modules.values().flat_map(|p| p.issues()).collect();
+    content.push_str(&format!("<p><strong>Total Issues Found</strong>: {}</p>\n", issues.len()));
+
+    content.push_str("<table>\n<tr><th>Severity</th><th>Issues</th></tr>\n");
+    for severity in [Severity::Error, Severity::Warning, Severity::Info, Severity::Style] {
+        let count = issues.iter().filter(|i| i.severity == severity).count();
+        content.push_str(&format!(
+            "<tr><td class=\"{0:?}\">{0:?}</td><td>{1}</td></tr>\n",
+            severity, count
+        ));
+    }
+    content.push_str("</table>\n");
+
+    content.push_str("<h2>Modules</h2>\n<table>\n<tr><th>Module</th><th>Files</th><th>Issues</th></tr>\n");
+    for (module, page) in modules {
+        content.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            page_file_name(module),
+            escape_html(&module.join("::")),
+            page.files.len(),
+            page.issue_count()
+        ));
+    }
+    content.push_str("</table>\n");
+
+    render_layout("Analysis Results", &render_nav(modules, None), &content)
+}
+
+/// Render the page of a single module
+fn render_module_page(
+    module: &[String],
+    page: &ModulePage,
+    modules: &BTreeMap<Vec<String>, ModulePage>,
+) -> String {
+    let title = module.join("::");
+    let mut content = String::new();
+    content.push_str(&format!("<h1>Module <code>{}</code></h1>\n", escape_html(&title)));
+
+    for file in &page.files {
+        content.push_str(&format!("<h2>{}</h2>\n", escape_html(&file.file_path.display().to_string())));
+
+        for error in &file.errors {
+            content.push_str(&format!("<p class=\"Error\">Analysis error: {}</p>\n", escape_html(error)));
+        }
+
+        if file.issues.is_empty() {
+            content.push_str("<p>No issues found.</p>\n");
+            continue;
+        }
+
+        content.push_str("<table>\n<tr><th>Lines</th><th>Priority</th><th>Severity</th><th>Category</th><th>Message</th></tr>\n");
+        for issue in &file.issues {
+            content.push_str(&format!(
+                "<tr><td>{}-{}</td><td>{}</td><td class=\"{:?}\">{:?}</td><td>{}</td><td>{}",
+                issue.line_start,
+                issue.line_end,
+                issue.priority.map_or("-".to_string(), |priority| priority.to_string()),
+                issue.severity,
+                issue.severity,
+                escape_html(&format!("{:?}", issue.category)),
+                escape_html(&issue.message)
+            ));
+
+            if let Some(fix) = &issue.suggested_fix {
+                content.push_str(&format!(
+                    "<details><summary>Suggested fix (confidence {}%)</summary><pre>{}</pre></details>",
+                    fix.confidence,
+                    escape_html(&fix.replacement_code)
+                ));
+            }
+
+            content.push_str("</td></tr>\n");
+        }
+        content.push_str("</table>\n");
+    }
+
+    render_layout(&title, &render_nav(modules, Some(module)), &content)
+}
+
+/// Escape text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_path() {
+        let root = Path::new("/work/app");
+        let module = |p: &str| module_path(&root.join(p), root, "app");
+
+        assert_eq!(module("src/lib.rs"), vec!["app"]);
+        assert_eq!(module("src/analysis.rs"), vec!["app", "analysis"]);
+        assert_eq!(module("src/models/mod.rs"), vec!["app", "models"]);
+        assert_eq!(module("src/models/ai.rs"), vec!["app", "models", "ai"]);
+        assert_eq!(module("crates/core-lib/src/lib.rs"), vec!["core_lib"]);
+        assert_eq!(module("tests/integration.rs"), vec!["app", "tests", "integration"]);
+    }
+}
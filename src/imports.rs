@@ -0,0 +1,364 @@
+//! Import organization module
+//!
+//! This module tidies the `use` statements of a project:
+//! - Remove imports reported as unused by the compiler
+//! - Merge imports of the same module into a single statement
+//! - Sort imports into std, external and local groups, like rustfmt's `StdExternalCrate`
+
+use crate::analysis::collect_rust_files;
+use crate::execution::CargoCommand;
+use crate::modification::{create_modification, CodeModification};
+use crate::{Result, RustAiToolError};
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info, warn};
+
+/// Location of an unused import reported by the compiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnusedImport {
+    /// First line of the span (1-based)
+    pub line_start: usize,
+
+    /// First column of the span (1-based)
+    pub column_start: usize,
+
+    /// Last line of the span (1-based)
+    pub line_end: usize,
+
+    /// Column after the end of the span (1-based)
+    pub column_end: usize,
+}
+
+/// A single imported name
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Leaf {
+    /// `path::self`
+    SelfModule,
+
+    /// `path::Name`
+    Name(String),
+
+    /// `path::Name as Alias`
+    Rename(String, String),
+
+    /// `path::*`
+    Glob,
+}
+
+/// Organize the imports of every Rust file in a project
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project
+///
+/// # Returns
+///
+/// Modifications for the files whose imports changed
+pub fn organize_imports(project_path: &Path) -> Result<Vec<CodeModification>> {
+    info!("Organizing imports in {}", project_path.display());
+
+    let unused = find_unused_imports(project_path).unwrap_or_else(|e| {
+        warn!("Could not detect unused imports, only merging and sorting: {}", e);
+        HashMap::new()
+    });
+
+    let mut modifications = Vec::new();
+
+    for file_path in collect_rust_files(project_path)? {
+        let source = fs::read_to_string(&file_path)
+            .map_err(RustAiToolError::Io)?;
+
+        let file_unused = unused.get(&file_path).map(Vec::as_slice).unwrap_or_default();
+
+        let organized = match organize_file_imports(&source, file_unused) {
+            Ok(organized) => organized,
+            Err(e) => {
+                warn!("Skipping {}: {}", file_path.display(), e);
+                continue;
+            }
+        };
+
+        if organized != source {
+            debug!("Imports of {} changed", file_path.display());
+            modifications.push(create_modification(
+                file_path,
+                source,
+                organized,
+                "Organize imports".to_string(),
+                95,
+            ));
+        }
+    }
+
+    info!("Imports changed in {} files", modifications.len());
+    Ok(modifications)
+}
+
+/// Organize the top-level imports of a single file
+///
+/// Only private, attribute-free `use` items that occupy whole lines are
+/// rewritten; `pub use` re-exports and `#[cfg]`-gated imports are left alone.
+///
+/// # Arguments
+///
+/// * `source` - File content
+/// * `unused` - Unused imports reported for the file
+///
+/// # Returns
+///
+/// The file content with organized imports
+pub fn organize_file_imports(source: &str, unused: &[UnusedImport]) -> Result<String> {
+    let file = syn::parse_file(source)
+        .map_err(|e| RustAiToolError::Modification(format!("Failed to parse source: {}", e)))?;
+    let source_lines: Vec<&str> = source.split_inclusive('\n').collect();
+
+    let mut imports: BTreeMap<Vec<String>, Vec<Leaf>> = BTreeMap::new();
+    let mut removed_lines = Vec::new();
+
+    for item in &file.items {
+        let item_use = match item {
+            syn::Item::Use(item_use) => item_use,
+            _ => continue,
+        };
+
+        if !matches!(item_use.vis, syn::Visibility::Inherited)
+            || !item_use.attrs.is_empty()
+            || item_use.leading_colon.is_some()
+        {
+            continue;
+        }
+
+        let (start, end) = (item.span().start().line, item.span().end().line);
+        let item_text: String = source_lines[start - 1..end].concat();
+        if !item_text.trim_start().starts_with("use ") || !item_text.trim_end().ends_with(';') {
+            // Shares its lines with other code
+            continue;
+        }
+
+        let mut leaves = Vec::new();
+        flatten_use_tree(&item_use.tree, Vec::new(), &mut leaves);
+
+        for (path, leaf, span) in leaves {
+            if leaf != Leaf::Glob && unused.iter().any(|u| span_within(span, u)) {
+                debug!("Removing unused import {}", path.join("::"));
+                continue;
+            }
+
+            imports.entry(path).or_default().push(leaf);
+        }
+
+        removed_lines.push(start..=end);
+    }
+
+    let first_line = match removed_lines.first() {
+        Some(range) => *range.start(),
+        None => return Ok(source.to_string()),
+    };
+
+    let mut result = String::with_capacity(source.len());
+    for (i, line) in source_lines.iter().enumerate() {
+        let line_number = i + 1;
+
+        if line_number == first_line {
+            result.push_str(&render_imports(&imports));
+        }
+
+        if !removed_lines.iter().any(|range| range.contains(&line_number)) {
+            result.push_str(line);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Run `cargo check` and collect the unused imports it reports, by file
+fn find_unused_imports(project_path: &Path) -> Result<HashMap<PathBuf, Vec<UnusedImport>>> {
+    let output = CargoCommand::new(project_path)
+        .args(["check", "--all-targets", "--message-format=json"])
+        .output()
+        .map_err(|e| RustAiToolError::Analysis(format!("Failed to execute cargo check: {}", e)))?;
+
+    let mut unused: HashMap<PathBuf, Vec<UnusedImport>> = HashMap::new();
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let message: serde_json::Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+
+        if message["reason"] != "compiler-message"
+            || message["message"]["code"]["code"] != "unused_imports"
+        {
+            continue;
+        }
+
+        for span in message["message"]["spans"].as_array().into_iter().flatten() {
+            let file_name = match span["file_name"].as_str() {
+                Some(file_name) => file_name,
+                None => continue,
+            };
+            let field = |name: &str| span[name].as_u64().unwrap_or_default() as usize;
+
+            unused.entry(project_path.join(file_name))
+                .or_default()
+                .push(UnusedImport {
+                    line_start: field("line_start"),
+                    column_start: field("column_start"),
+                    line_end: field("line_end"),
+                    column_end: field("column_end"),
+                });
+        }
+    }
+
+    Ok(unused)
+}
+
+/// Flatten a use tree into (module path, leaf, leaf span) triples
+fn flatten_use_tree(tree: &syn::UseTree, prefix: Vec<String>, out: &mut Vec<(Vec<String>, Leaf, Span)>) {
+    match tree {
+        syn::UseTree::Path(use_path) => {
+            let mut path = prefix;
+            path.push(use_path.ident.to_string());
+            flatten_use_tree(&use_path.tree, path, out);
+        }
+        syn::UseTree::Name(use_name) if use_name.ident == "self" => {
+            out.push((prefix, Leaf::SelfModule, use_name.span()));
+        }
+        syn::UseTree::Name(use_name) => {
+            out.push((prefix, Leaf::Name(use_name.ident.to_string()), use_name.span()));
+        }
+        syn::UseTree::Rename(use_rename) => {
+            out.push((
+                prefix,
+                Leaf::Rename(use_rename.ident.to_string(), use_rename.rename.to_string()),
+                use_rename.span(),
+            ));
+        }
+        syn::UseTree::Glob(use_glob) => out.push((prefix, Leaf::Glob, use_glob.span())),
+        syn::UseTree::Group(use_group) => {
+            for item in &use_group.items {
+                flatten_use_tree(item, prefix.clone(), out);
+            }
+        }
+    }
+}
+
+/// Whether a span lies within a compiler-reported span
+fn span_within(span: Span, unused: &UnusedImport) -> bool {
+    // proc-macro2 columns are 0-based, rustc columns are 1-based
+    let start = (span.start().line, span.start().column + 1);
+    let end = (span.end().line, span.end().column + 1);
+
+    (unused.line_start, unused.column_start) <= start && end <= (unused.line_end, unused.column_end)
+}
+
+/// Render merged imports, grouped and sorted like rustfmt
+fn render_imports(imports: &BTreeMap<Vec<String>, Vec<Leaf>>) -> String {
+    let mut groups: [Vec<String>; 3] = Default::default();
+
+    for (path, leaves) in imports {
+        let mut leaves = leaves.clone();
+        leaves.sort_by_key(leaf_sort_key);
+        leaves.dedup();
+
+        let rendered: Vec<String> = leaves.iter().map(render_leaf).collect();
+        let statement = match (path.is_empty(), rendered.as_slice()) {
+            (_, []) => continue,
+            (false, [single]) if single == "self" => format!("use {};", path.join("::")),
+            (true, [single]) => format!("use {};", single),
+            (false, [single]) => format!("use {}::{};", path.join("::"), single),
+            (true, _) => format!("use {{{}}};", rendered.join(", ")),
+            (false, _) => format!("use {}::{{{}}};", path.join("::"), rendered.join(", ")),
+        };
+
+        let group = match path.first().map(String::as_str) {
+            Some("std" | "core" | "alloc") => 0,
+            Some("crate" | "self" | "super") => 2,
+            _ => 1,
+        };
+        groups[group].push(statement);
+    }
+
+    let mut rendered = String::new();
+    for group in groups.iter_mut().filter(|g| !g.is_empty()) {
+        if !rendered.is_empty() {
+            rendered.push('\n');
+        }
+
+        group.sort_by_key(|statement| statement.to_lowercase());
+        for statement in group.iter() {
+            rendered.push_str(statement);
+            rendered.push('\n');
+        }
+    }
+
+    rendered
+}
+
+/// Sort key placing `self` first, then modules, types and constants, then globs
+fn leaf_sort_key(leaf: &Leaf) -> (u8, u8, String) {
+    let name = match leaf {
+        Leaf::SelfModule => return (0, 0, String::new()),
+        Leaf::Glob => return (2, 0, String::new()),
+        Leaf::Name(name) | Leaf::Rename(name, _) => name,
+    };
+
+    let case = if name.chars().all(|c| !c.is_lowercase()) && name.chars().any(char::is_uppercase) {
+        2
+    } else if name.starts_with(char::is_uppercase) {
+        1
+    } else {
+        0
+    };
+
+    (1, case, name.clone())
+}
+
+/// Render a leaf as it appears inside braces
+fn render_leaf(leaf: &Leaf) -> String {
+    match leaf {
+        Leaf::SelfModule => "self".to_string(),
+        Leaf::Name(name) => name.clone(),
+        Leaf::Rename(name, alias) => format!("{} as {}", name, alias),
+        Leaf::Glob => "*".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_organize_file_imports() {
+        let source = [
+            "use crate::config::Config;",
+            "use std::path::PathBuf;",
+            "use serde::Serialize;",
+            "use std::path::Path;",
+            "use std::collections::HashMap;",
+            "use std::path::Path;",
+            "",
+            "fn main() {}",
+            "",
+        ].join("\n");
+
+        // `HashMap` on line 5 is unused
+        let unused = vec![UnusedImport { line_start: 5, column_start: 5, line_end: 5, column_end: 30 }];
+
+        let expected = [
+            "use std::path::{Path, PathBuf};",
+            "",
+            "use serde::Serialize;",
+            "",
+            "use crate::config::Config;",
+            "",
+            "fn main() {}",
+            "",
+        ].join("\n");
+
+        assert_eq!(organize_file_imports(&source, &unused).unwrap(), expected);
+    }
+}
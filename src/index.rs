@@ -0,0 +1,422 @@
+//! Code index module
+//!
+//! This module builds a local vector index of a project for retrieval-augmented prompts:
+//! - Source files are split into chunks along item boundaries (functions, types, impls)
+//! - Each chunk is embedded with the configured model and stored under `.rust-ai-tool`
+//! - Unchanged chunks keep their vectors when the index is rebuilt
+//! - Chunks are retrieved by cosine similarity to a query
+
+use crate::analysis::collect_rust_files;
+use crate::journal::{content_hash, STATE_DIR};
+use crate::models::AiModelClient;
+use crate::{Result, RustAiToolError};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info, warn};
+
+/// File inside the state directory holding the index
+pub const INDEX_FILE: &str = "index.json";
+
+/// Maximum lines of a chunk; longer items are split
+const MAX_CHUNK_LINES: usize = 80;
+
+/// Maximum characters of a chunk sent to the embeddings API
+const MAX_EMBEDDED_CHARS: usize = 8000;
+
+/// A piece of source code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeChunk {
+    /// File path relative to the project root
+    pub file_path: PathBuf,
+
+    /// Name of the item (e.g. `fn parse`, `impl Config`)
+    pub name: String,
+
+    /// First line (1-based)
+    pub start_line: usize,
+
+    /// Last line (1-based, inclusive)
+    pub end_line: usize,
+
+    /// Source of the chunk
+    pub content: String,
+}
+
+/// A chunk with its embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedChunk {
+    /// The chunk
+    #[serde(flatten)]
+    pub chunk: CodeChunk,
+
+    /// Hash of the embedded text
+    pub content_hash: String,
+
+    /// Embedding vector
+    pub vector: Vec<f32>,
+}
+
+/// Vector index of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeIndex {
+    /// Embedding model the vectors were computed with
+    pub model: String,
+
+    /// Indexed chunks
+    pub chunks: Vec<IndexedChunk>,
+}
+
+impl CodeIndex {
+    /// Path of the index of a project
+    pub fn path(project_path: &Path) -> PathBuf {
+        project_path.join(STATE_DIR).join(INDEX_FILE)
+    }
+
+    /// Load the index of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project
+    ///
+    /// # Returns
+    ///
+    /// The index, or `None` if the project has not been indexed
+    pub fn load(project_path: &Path) -> Result<Option<Self>> {
+        let path = Self::path(project_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(RustAiToolError::Io)?;
+        let index = serde_json::from_str(&content)
+            .map_err(RustAiToolError::Json)?;
+
+        Ok(Some(index))
+    }
+
+    /// Load the index of the project containing a path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File or directory inside the project
+    ///
+    /// # Returns
+    ///
+    /// The index of the nearest indexed ancestor, if any
+    pub fn find(path: &Path) -> Result<Option<Self>> {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map_err(RustAiToolError::Io)?
+                .join(path)
+        };
+
+        match absolute.ancestors().find(|dir| Self::path(dir).is_file()) {
+            Some(project_path) => Self::load(project_path),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the index of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project
+    pub fn save(&self, project_path: &Path) -> Result<()> {
+        let path = Self::path(project_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        let json = serde_json::to_string(self)
+            .map_err(RustAiToolError::Json)?;
+        fs::write(&path, json)
+            .map_err(RustAiToolError::Io)
+    }
+
+    /// Find the chunks most similar to a query vector
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Embedding of the query
+    /// * `limit` - Maximum number of chunks
+    ///
+    /// # Returns
+    ///
+    /// The chunks with their cosine similarity, most similar first
+    pub fn search(&self, query: &[f32], limit: usize) -> Vec<(f32, &IndexedChunk)> {
+        let mut scored: Vec<(f32, &IndexedChunk)> = self.chunks.iter()
+            .map(|chunk| (cosine_similarity(query, &chunk.vector), chunk))
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(limit);
+        scored
+    }
+}
+
+/// Build the index of a project
+///
+/// Chunks whose text is unchanged since the previous index, built with the
+/// same embedding model, keep their vectors; only new or edited chunks are embedded.
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project
+/// * `client` - AI model client used for embeddings
+/// * `previous` - Previous index of the project
+///
+/// # Returns
+///
+/// The new index
+pub async fn build_index(
+    project_path: &Path,
+    client: &AiModelClient,
+    previous: Option<&CodeIndex>,
+) -> Result<CodeIndex> {
+    let model = client.embedding_model()?;
+
+    let known: HashMap<&str, &Vec<f32>> = previous
+        .filter(|index| index.model == model)
+        .map(|index| index.chunks.iter().map(|c| (c.content_hash.as_str(), &c.vector)).collect())
+        .unwrap_or_default();
+
+    let mut chunks = Vec::new();
+    for file_path in collect_rust_files(project_path)? {
+        let source = fs::read_to_string(&file_path)
+            .map_err(RustAiToolError::Io)?;
+        let relative = file_path.strip_prefix(project_path).unwrap_or(&file_path);
+
+        chunks.extend(chunk_source(&source, relative));
+    }
+
+    let texts: Vec<String> = chunks.iter().map(embedding_text).collect();
+    let hashes: Vec<String> = texts.iter().map(|text| content_hash(text)).collect();
+
+    let missing: Vec<usize> = (0..chunks.len())
+        .filter(|&i| !known.contains_key(hashes[i].as_str()))
+        .collect();
+
+    info!(
+        "Indexing {} chunks ({} unchanged, {} to embed with {})",
+        chunks.len(),
+        chunks.len() - missing.len(),
+        missing.len(),
+        model
+    );
+
+    let missing_texts: Vec<String> = missing.iter().map(|&i| texts[i].clone()).collect();
+    let mut new_vectors: HashMap<usize, Vec<f32>> = missing.into_iter()
+        .zip(client.embed(&missing_texts).await?)
+        .collect();
+
+    let chunks = chunks.into_iter()
+        .zip(hashes)
+        .enumerate()
+        .map(|(i, (chunk, hash))| {
+            let vector = new_vectors.remove(&i)
+                .or_else(|| known.get(hash.as_str()).map(|v| (*v).clone()))
+                .unwrap_or_default();
+
+            IndexedChunk {
+                chunk,
+                content_hash: hash,
+                vector,
+            }
+        })
+        .collect();
+
+    Ok(CodeIndex { model, chunks })
+}
+
+/// Split a Rust source file into chunks along item boundaries
+///
+/// Top-level items become one chunk each; large impl blocks are split into
+/// their functions and inline modules into their items. Files that do not
+/// parse are split into fixed-size line windows.
+///
+/// # Arguments
+///
+/// * `source` - Source of the file
+/// * `file_path` - Path recorded in the chunks
+///
+/// # Returns
+///
+/// The chunks, in source order
+pub fn chunk_source(source: &str, file_path: &Path) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut chunks = Vec::new();
+
+    match syn::parse_file(source) {
+        Ok(file) => collect_item_chunks(&file.items, "", &lines, file_path, &mut chunks),
+        Err(e) => {
+            warn!("Failed to parse {}, indexing it by lines: {}", file_path.display(), e);
+            push_chunks(&file_path.display().to_string(), 1, lines.len(), &lines, file_path, &mut chunks);
+        }
+    }
+
+    chunks
+}
+
+/// Add the chunks of a list of items
+fn collect_item_chunks(
+    items: &[syn::Item],
+    prefix: &str,
+    lines: &[&str],
+    file_path: &Path,
+    chunks: &mut Vec<CodeChunk>,
+) {
+    for item in items {
+        let start = item.span().start().line;
+        let end = item.span().end().line;
+
+        match item {
+            syn::Item::Use(_) | syn::Item::ExternCrate(_) => {}
+            syn::Item::Mod(item_mod) if item_mod.content.is_some() => {
+                let prefix = format!("{}{}::", prefix, item_mod.ident);
+                let (_, mod_items) = item_mod.content.as_ref().unwrap();
+                collect_item_chunks(mod_items, &prefix, lines, file_path, chunks);
+            }
+            syn::Item::Impl(item_impl) if end - start + 1 > MAX_CHUNK_LINES => {
+                let self_ty = item_impl.self_ty.to_token_stream().to_string();
+
+                for impl_item in &item_impl.items {
+                    let name = match impl_item {
+                        syn::ImplItem::Fn(f) => format!("fn {}{}::{}", prefix, self_ty, f.sig.ident),
+                        syn::ImplItem::Const(c) => format!("const {}{}::{}", prefix, self_ty, c.ident),
+                        syn::ImplItem::Type(t) => format!("type {}{}::{}", prefix, self_ty, t.ident),
+                        _ => continue,
+                    };
+
+                    let span = impl_item.span();
+                    push_chunks(&name, span.start().line, span.end().line, lines, file_path, chunks);
+                }
+            }
+            _ => {
+                let name = item_name(item, prefix);
+                push_chunks(&name, start, end, lines, file_path, chunks);
+            }
+        }
+    }
+}
+
+/// Add the chunks covering a line range, split into windows if it is too long
+fn push_chunks(
+    name: &str,
+    start: usize,
+    end: usize,
+    lines: &[&str],
+    file_path: &Path,
+    chunks: &mut Vec<CodeChunk>,
+) {
+    let end = end.min(lines.len());
+    let mut window_start = start.max(1);
+
+    while window_start <= end {
+        let window_end = (window_start + MAX_CHUNK_LINES - 1).min(end);
+
+        chunks.push(CodeChunk {
+            file_path: file_path.to_path_buf(),
+            name: name.to_string(),
+            start_line: window_start,
+            end_line: window_end,
+            content: lines[window_start - 1..window_end].concat(),
+        });
+
+        window_start = window_end + 1;
+    }
+}
+
+/// Descriptive name of an item
+pub(crate) fn item_name(item: &syn::Item, prefix: &str) -> String {
+    let (kind, ident) = match item {
+        syn::Item::Fn(i) => ("fn", i.sig.ident.to_string()),
+        syn::Item::Struct(i) => ("struct", i.ident.to_string()),
+        syn::Item::Enum(i) => ("enum", i.ident.to_string()),
+        syn::Item::Union(i) => ("union", i.ident.to_string()),
+        syn::Item::Trait(i) => ("trait", i.ident.to_string()),
+        syn::Item::Type(i) => ("type", i.ident.to_string()),
+        syn::Item::Const(i) => ("const", i.ident.to_string()),
+        syn::Item::Static(i) => ("static", i.ident.to_string()),
+        syn::Item::Mod(i) => ("mod", i.ident.to_string()),
+        syn::Item::Macro(i) => ("macro", i.ident.as_ref().map_or_else(|| "invocation".to_string(), |i| i.to_string())),
+        syn::Item::Impl(i) => {
+            let self_ty = i.self_ty.to_token_stream().to_string();
+            let name = match &i.trait_ {
+                Some((_, path, _)) => format!("{} for {}", path.to_token_stream(), self_ty),
+                None => self_ty,
+            };
+            ("impl", name)
+        }
+        _ => ("item", String::new()),
+    };
+
+    format!("{} {}{}", kind, prefix, ident)
+}
+
+/// Text sent to the embeddings API for a chunk
+fn embedding_text(chunk: &CodeChunk) -> String {
+    let text = format!("// {} ({})\n{}", chunk.file_path.display(), chunk.name, chunk.content);
+
+    match text.char_indices().nth(MAX_EMBEDDED_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+/// Cosine similarity of two vectors (0 for empty or mismatched vectors)
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        debug!("Ignoring zero vector in similarity");
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_and_search() {
+        let source = "use std::fmt;\n\n/// Parse input\nfn parse() {}\n\nstruct Config {\n    name: String,\n}\n\nmod inner {\n    fn helper() {}\n}\n";
+        let chunks = chunk_source(source, Path::new("src/lib.rs"));
+
+        let names: Vec<&str> = chunks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["fn parse", "struct Config", "fn inner::helper"]);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (3, 4));
+        assert_eq!(chunks[1].content, "struct Config {\n    name: String,\n}\n");
+
+        let index = CodeIndex {
+            model: "test".to_string(),
+            chunks: chunks.into_iter()
+                .zip([vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7]])
+                .map(|(chunk, vector)| IndexedChunk {
+                    content_hash: content_hash(&chunk.content),
+                    chunk,
+                    vector,
+                })
+                .collect(),
+        };
+
+        let results = index.search(&[0.0, 2.0], 2);
+        assert_eq!(results[0].1.chunk.name, "struct Config");
+        assert_eq!(results[1].1.chunk.name, "fn inner::helper");
+    }
+}
@@ -0,0 +1,168 @@
+//! Issue filter module
+//!
+//! This module narrows down the issues an analysis reports:
+//! - Files included or excluded by gitignore-style globs, relative to the project
+//! - Issue categories, like `style` or `performance`, or custom rules by name
+//! - A minimum severity
+//!
+//! The filter is part of the analysis options, so every output format, the
+//! fix pipeline and the daemon see the same issues.
+
+use crate::analysis::{AnalysisResult, CodeIssue, IssueCategory};
+use crate::codeowners::pattern_regex;
+use crate::{Result, RustAiToolError, Severity};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Files and issues kept in analysis results
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct IssueFilter {
+    /// Only analyze files matching one of these globs (all files if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Skip files matching one of these globs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Only keep issues of these categories (all categories if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+
+    /// Only keep issues of at least this severity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<Severity>,
+
+    /// Only keep issues of these custom rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+}
+
+impl IssueFilter {
+    /// Whether the filter keeps every file and issue
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.categories.is_empty()
+            && self.min_severity.is_none()
+            && self.rules.is_empty()
+    }
+
+    /// Files of a project the filter includes
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Project directory the globs are relative to
+    /// * `files` - Files of the project
+    ///
+    /// # Returns
+    ///
+    /// The included files, in the same order
+    pub fn filter_files(&self, project_path: &Path, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let include = compile_globs(&self.include)?;
+        let exclude = compile_globs(&self.exclude)?;
+
+        Ok(files.iter()
+            .filter(|file_path| {
+                let relative_path = file_path.strip_prefix(project_path).unwrap_or(file_path);
+                let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+
+                (include.is_empty() || include.iter().any(|glob| glob.is_match(&relative_path)))
+                    && !exclude.iter().any(|glob| glob.is_match(&relative_path))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Whether the filter keeps an issue
+    pub fn keeps(&self, issue: &CodeIssue) -> bool {
+        let category = normalize(&issue.category.name());
+
+        let category_kept = self.categories.is_empty()
+            || self.categories.iter().any(|name| {
+                let name = normalize(name);
+                name == category || (name == "customrule" && matches!(issue.category, IssueCategory::CustomRule(_)))
+            });
+        let rule_kept = self.rules.is_empty()
+            || matches!(&issue.category, IssueCategory::CustomRule(rule) if self.rules.contains(rule));
+        let severity_kept = self.min_severity.as_ref()
+            .is_none_or(|threshold| issue.severity.is_at_least(threshold));
+
+        category_kept && rule_kept && severity_kept
+    }
+
+    /// Drop the issues the filter does not keep from analysis results
+    pub fn retain_issues(&self, results: &mut [AnalysisResult]) {
+        for result in results.iter_mut() {
+            result.issues.retain(|issue| self.keeps(issue));
+        }
+    }
+}
+
+fn compile_globs(globs: &[String]) -> Result<Vec<Regex>> {
+    globs.iter()
+        .map(|glob| pattern_regex(glob).map_err(|e| RustAiToolError::Analysis(format!("Invalid glob '{}': {}", glob, e))))
+        .collect()
+}
+
+/// Category name compared case-insensitively, ignoring `-` and `_` (`code-quality` is `CodeQuality`)
+pub(crate) fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(category: IssueCategory, severity: Severity) -> CodeIssue {
+        CodeIssue {
+            file_path: PathBuf::from("src/lib.rs"),
+            line_start: 1,
+            column_start: 1,
+            line_end: 1,
+            column_end: 1,
+            category,
+            severity,
+            message: "message".to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_issue_filter() {
+        let project = Path::new("/project");
+        let files = ["src/lib.rs", "src/generated/api.rs", "tests/it.rs", "build.rs"].map(|file| project.join(file));
+
+        let filter = IssueFilter {
+            include: vec!["src/".to_string(), "*.rs".to_string()],
+            exclude: vec!["generated/".to_string(), "/build.rs".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(filter.filter_files(project, &files).unwrap(), [project.join("src/lib.rs"), project.join("tests/it.rs")]);
+        assert!(IssueFilter::default().is_empty());
+        assert_eq!(IssueFilter::default().filter_files(project, &files).unwrap(), files);
+
+        let filter = IssueFilter {
+            categories: vec!["code-quality".to_string(), "custom_rule".to_string()],
+            min_severity: Some(Severity::Warning),
+            ..Default::default()
+        };
+        assert!(filter.keeps(&issue(IssueCategory::CodeQuality, Severity::Error)));
+        assert!(filter.keeps(&issue(IssueCategory::CustomRule("no-unwrap".to_string()), Severity::Warning)));
+        assert!(!filter.keeps(&issue(IssueCategory::CodeQuality, Severity::Info)));
+        assert!(!filter.keeps(&issue(IssueCategory::Style, Severity::Error)));
+
+        let filter = IssueFilter { rules: vec!["no-unwrap".to_string()], ..Default::default() };
+        assert!(filter.keeps(&issue(IssueCategory::CustomRule("no-unwrap".to_string()), Severity::Style)));
+        assert!(!filter.keeps(&issue(IssueCategory::CustomRule("no-todo".to_string()), Severity::Error)));
+        assert!(!filter.keeps(&issue(IssueCategory::Security, Severity::Error)));
+    }
+}
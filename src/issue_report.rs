@@ -0,0 +1,355 @@
+//! Issue report module
+//!
+//! This module files analysis results as GitHub issues:
+//! - Code issues grouped into one GitHub issue per file, per category or per code owner
+//! - Code owners of the issues mentioned in the body and assigned when they are users
+//! - Issue bodies with the offending code and the suggested fixes
+//! - A hidden marker in every body, so a group with an open issue is not filed again
+//!   and pull requests fixing a file close the issue of that file
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::codeowners::user_logins;
+use crate::github::GithubClient;
+use crate::journal::content_hash;
+use crate::modification::CodeModification;
+use crate::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use log::info;
+
+/// Code issues listed in one GitHub issue; the rest are only counted
+const MAX_ISSUES_PER_GROUP: usize = 50;
+
+/// Source lines shown for one code issue
+const MAX_SNIPPET_LINES: usize = 15;
+
+/// Group of the issues of files without code owners, when grouping by owner
+const UNOWNED: &str = "unowned";
+
+/// How code issues are grouped into GitHub issues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueGrouping {
+    /// One GitHub issue per file
+    File,
+
+    /// One GitHub issue per issue category
+    Category,
+
+    /// One GitHub issue per code owner, listing the issues in the files they own
+    Owner,
+}
+
+/// GitHub issue to be filed for a group of code issues
+#[derive(Debug, Clone)]
+pub struct IssueDraft {
+    /// Key of the group (a file path, a category or a code owner)
+    pub key: String,
+
+    /// Title of the issue
+    pub title: String,
+
+    /// Body of the issue, ending with the marker of the group
+    pub body: String,
+
+    /// Number of code issues in the group
+    pub issue_count: usize,
+
+    /// GitHub users to assign, the code owners of the group
+    pub assignees: Vec<String>,
+}
+
+/// Outcome of filing issue drafts
+#[derive(Debug, Default)]
+pub struct FiledIssues {
+    /// Groups filed, with the number of the created issue
+    pub created: Vec<(String, u64)>,
+
+    /// Groups that already have an open issue
+    pub skipped: Vec<String>,
+}
+
+/// Group the code issues of analysis results into issue drafts
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+/// * `base_dir` - Project root, used to make file paths repository-relative
+/// * `grouping` - How code issues are grouped
+///
+/// # Returns
+///
+/// One draft per group with at least one code issue, ordered by key
+pub fn group_issues(results: &[AnalysisResult], base_dir: &Path, grouping: IssueGrouping) -> Vec<IssueDraft> {
+    let mut groups: BTreeMap<String, Vec<&CodeIssue>> = BTreeMap::new();
+
+    for issue in results.iter().flat_map(|r| &r.issues) {
+        let keys = match grouping {
+            IssueGrouping::File => vec![relative_path(&issue.file_path, base_dir)],
+            IssueGrouping::Category => vec![issue.category.name()],
+            IssueGrouping::Owner if issue.owners.is_empty() => vec![UNOWNED.to_string()],
+            IssueGrouping::Owner => issue.owners.clone(),
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(issue);
+        }
+    }
+
+    groups.into_iter()
+        .map(|(key, issues)| {
+            let title = match grouping {
+                IssueGrouping::File => format!("Rust AI Tool: {} issues in {}", issues.len(), key),
+                IssueGrouping::Category => format!("Rust AI Tool: {} {} issues", issues.len(), key),
+                IssueGrouping::Owner if key == UNOWNED => format!("Rust AI Tool: {} issues without code owners", issues.len()),
+                IssueGrouping::Owner => format!("Rust AI Tool: {} issues owned by {}", issues.len(), key),
+            };
+
+            let mut body = format!("Rust AI Tool found {} issues", issues.len());
+            match grouping {
+                IssueGrouping::File => body.push_str(&format!(" in `{}`.\n\n", key)),
+                IssueGrouping::Category => body.push_str(&format!(" of category {}.\n\n", key)),
+                IssueGrouping::Owner if key == UNOWNED => body.push_str(" in files without code owners.\n\n"),
+                IssueGrouping::Owner => body.push_str(&format!(" in files owned by {}.\n\n", key)),
+            }
+
+            for issue in issues.iter().take(MAX_ISSUES_PER_GROUP) {
+                body.push_str(&format_code_issue(issue, base_dir));
+            }
+
+            if issues.len() > MAX_ISSUES_PER_GROUP {
+                body.push_str(&format!("{} more issues not shown.\n\n", issues.len() - MAX_ISSUES_PER_GROUP));
+            }
+
+            // Grouped by owner, the owner is mentioned above
+            let owners = match grouping {
+                IssueGrouping::Owner if key == UNOWNED => Vec::new(),
+                IssueGrouping::Owner => vec![key.clone()],
+                IssueGrouping::File | IssueGrouping::Category => group_owners(&issues),
+            };
+            if grouping != IssueGrouping::Owner && !owners.is_empty() {
+                body.push_str(&format!("cc {}\n\n", owners.join(" ")));
+            }
+
+            body.push_str(&group_marker(&key));
+
+            // Assigning every owner of a category would assign most of the repository
+            let assignees = match grouping {
+                IssueGrouping::Category => Vec::new(),
+                IssueGrouping::File | IssueGrouping::Owner => user_logins(&owners),
+            };
+
+            IssueDraft {
+                key,
+                title,
+                body,
+                issue_count: issues.len(),
+                assignees,
+            }
+        })
+        .collect()
+}
+
+/// Hidden marker identifying the group of an issue in its body
+pub fn group_marker(key: &str) -> String {
+    format!("<!-- rust-ai-tool-group: {} -->", &content_hash(key)[..16])
+}
+
+/// File issue drafts, skipping groups that already have an open issue
+///
+/// # Arguments
+///
+/// * `client` - GitHub client of the repository
+/// * `drafts` - Issue drafts to file
+/// * `labels` - Labels of the created issues
+///
+/// # Returns
+///
+/// The created and the skipped groups
+pub async fn file_issues(client: &GithubClient, drafts: &[IssueDraft], labels: &[String]) -> Result<FiledIssues> {
+    let open_issues = client.list_open_issues().await?;
+    let mut filed = FiledIssues::default();
+
+    for draft in drafts {
+        let marker = group_marker(&draft.key);
+        if open_issues.iter().any(|(_, body)| body.contains(&marker)) {
+            info!("Skipping {}: an open issue already covers it", draft.key);
+            filed.skipped.push(draft.key.clone());
+            continue;
+        }
+
+        let number = client.create_issue(&draft.title, &draft.body, labels, &draft.assignees).await?;
+        filed.created.push((draft.key.clone(), number));
+    }
+
+    Ok(filed)
+}
+
+/// Open issues filed for the files that fixes change
+///
+/// # Arguments
+///
+/// * `client` - GitHub client of the repository
+/// * `modifications` - Fixes of a pull request
+/// * `base_dir` - Project root, used to make file paths repository-relative
+///
+/// # Returns
+///
+/// Numbers of the open issues grouping the issues of a fixed file, ordered
+pub async fn issues_resolved_by(
+    client: &GithubClient,
+    modifications: &[CodeModification],
+    base_dir: &Path,
+) -> Result<Vec<u64>> {
+    let markers: Vec<String> = modifications.iter()
+        .map(|modification| group_marker(&relative_path(&modification.file_path, base_dir)))
+        .collect();
+
+    let mut numbers: Vec<u64> = client.list_open_issues()
+        .await?
+        .into_iter()
+        .filter(|(_, body)| markers.iter().any(|marker| body.contains(marker)))
+        .map(|(number, _)| number)
+        .collect();
+
+    numbers.sort_unstable();
+    Ok(numbers)
+}
+
+/// Code owners of a group of issues, without duplicates
+fn group_owners(issues: &[&CodeIssue]) -> Vec<String> {
+    let mut owners: Vec<String> = Vec::new();
+
+    for owner in issues.iter().flat_map(|issue| &issue.owners) {
+        if !owners.contains(owner) {
+            owners.push(owner.clone());
+        }
+    }
+
+    owners
+}
+
+/// Format one code issue as a section of an issue body
+fn format_code_issue(issue: &CodeIssue, base_dir: &Path) -> String {
+    let mut section = format!(
+        "### `{}:{}` {:?} ({})\n\n{}\n\n",
+        relative_path(&issue.file_path, base_dir),
+        issue.line_start,
+        issue.severity,
+        issue.category.name(),
+        issue.message
+    );
+
+    if let Some(snippet) = read_snippet(issue) {
+        section.push_str(&format!("```rust\n{}\n```\n\n", snippet));
+    }
+
+    if let Some(fix) = &issue.suggested_fix {
+        section.push_str(&format!(
+            "**Suggested fix** (confidence {}%): {}\n\n```rust\n{}\n```\n\n",
+            fix.confidence,
+            fix.description,
+            fix.replacement_code.trim_end()
+        ));
+    }
+
+    section
+}
+
+/// Lines of the file an issue covers, if the file can still be read
+pub(crate) fn read_snippet(issue: &CodeIssue) -> Option<String> {
+    let content = fs::read_to_string(&issue.file_path).ok()?;
+    let start = issue.line_start.max(1) - 1;
+    let line_count = (issue.line_end.max(issue.line_start) - issue.line_start + 1).min(MAX_SNIPPET_LINES);
+
+    let snippet: Vec<&str> = content.lines().skip(start).take(line_count).collect();
+    if snippet.is_empty() {
+        None
+    } else {
+        Some(snippet.join("\n"))
+    }
+}
+
+/// Repository-relative path of a file, with forward slashes
+pub(crate) fn relative_path(file_path: &Path, base_dir: &Path) -> String {
+    let path = file_path.strip_prefix(base_dir).unwrap_or(file_path);
+    let path = path.strip_prefix(".").unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{CodeFix, IssueCategory};
+    use crate::Severity;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn code_issue(file_path: PathBuf, line: usize, category: IssueCategory) -> CodeIssue {
+        CodeIssue {
+            file_path,
+            line_start: line,
+            column_start: 1,
+            line_end: line,
+            column_end: 10,
+            category,
+            severity: Severity::Warning,
+            message: "Something is off".to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_group_issues() {
+        let dir = tempdir().unwrap();
+        let lib = dir.path().join("src/lib.rs");
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(&lib, "fn a() {}\nfn b() { unwrap() }\n").unwrap();
+
+        let mut fixed = code_issue(lib.clone(), 2, IssueCategory::CodeQuality);
+        fixed.suggested_fix = Some(CodeFix {
+            original_code: "unwrap()".to_string(),
+            replacement_code: "expect(\"b\")".to_string(),
+            confidence: 80,
+            description: "Explain the panic".to_string(),
+        });
+
+        let results = vec![AnalysisResult {
+            file_path: lib.clone(),
+            issues: vec![
+                fixed,
+                code_issue(lib.clone(), 1, IssueCategory::Style),
+                code_issue(dir.path().join("src/main.rs"), 3, IssueCategory::Style),
+            ],
+            errors: Vec::new(),
+            success: true,
+        }];
+
+        let by_file = group_issues(&results, dir.path(), IssueGrouping::File);
+        assert_eq!(by_file.len(), 2);
+        assert_eq!(by_file[0].key, "src/lib.rs");
+        assert_eq!(by_file[0].issue_count, 2);
+        assert!(by_file[0].body.contains("fn b() { unwrap() }"));
+        assert!(by_file[0].body.contains("expect(\"b\")"));
+        assert!(by_file[0].body.ends_with(&group_marker("src/lib.rs")));
+
+        let by_category = group_issues(&results, dir.path(), IssueGrouping::Category);
+        let keys: Vec<_> = by_category.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(keys, ["CodeQuality", "Style"]);
+        assert_ne!(group_marker("Style"), group_marker("src/lib.rs"));
+
+        let mut results = results;
+        results[0].issues[0].owners = vec!["@alice".to_string(), "@org/core".to_string()];
+        results[0].issues[1].owners = vec!["@alice".to_string(), "@org/core".to_string()];
+        let by_owner = group_issues(&results, dir.path(), IssueGrouping::Owner);
+        let groups: Vec<_> = by_owner.iter().map(|d| (d.key.as_str(), d.issue_count, d.assignees.clone())).collect();
+        assert_eq!(groups, [("@alice", 2, vec!["alice".to_string()]), ("@org/core", 2, Vec::new()), ("unowned", 1, Vec::new())]);
+        assert_eq!(by_owner[0].title, "Rust AI Tool: 2 issues owned by @alice");
+
+        let by_file = group_issues(&results, dir.path(), IssueGrouping::File);
+        assert!(by_file[0].body.contains("cc @alice @org/core\n"));
+        assert_eq!(by_file[0].assignees, ["alice"]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Issue tracker module
+//!
+//! This module files analysis issues into the trackers teams triage work in:
+//! - An `IssueTracker` trait for listing the issues already filed and filing new ones
+//! - Implementations for Jira (`jira`) and Linear (`linear`)
+//! - One tracker issue per code issue, labelled `rust-ai-tool` and with its
+//!   severity and category (like `severity-warning` and `category-code-quality`)
+//! - A fingerprint of every code issue, stored in the tracker with the issue, so
+//!   a code issue with an open tracker issue is not filed again; it depends on
+//!   the file, category and message, so it survives code moving around
+
+use crate::analysis::{AnalysisResult, CodeFix, CodeIssue};
+use crate::issue_report::{read_snippet, relative_path};
+use crate::journal::content_hash;
+use crate::Result;
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use log::info;
+
+/// Label of every issue filed by rust-ai-tool
+pub const TRACKER_LABEL: &str = "rust-ai-tool";
+
+/// Longest title trackers accept (Jira's summary limit)
+const MAX_TITLE_LENGTH: usize = 255;
+
+/// Operations of an issue tracker
+#[async_trait]
+pub trait IssueTracker: Send + Sync {
+    /// Name of the tracker, for messages (e.g. `Jira`)
+    fn name(&self) -> &'static str;
+
+    /// Fingerprints of the open issues filed by rust-ai-tool
+    async fn open_fingerprints(&self) -> Result<HashSet<String>>;
+
+    /// File an issue
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - Issue to file
+    ///
+    /// # Returns
+    ///
+    /// The key of the created issue (like `ENG-42`)
+    async fn create_issue(&self, issue: &TrackerIssue) -> Result<String>;
+}
+
+/// Tracker issue to be filed for a code issue
+#[derive(Debug, Clone)]
+pub struct TrackerIssue {
+    /// Fingerprint of the code issue
+    pub fingerprint: String,
+
+    /// Title of the issue
+    pub title: String,
+
+    /// Repository-relative path of the file
+    pub file: String,
+
+    /// Line of the start of the code issue
+    pub line: usize,
+
+    /// Severity, like `Warning`
+    pub severity: String,
+
+    /// Category or custom rule, like `CodeQuality`
+    pub category: String,
+
+    /// Description of the code issue
+    pub message: String,
+
+    /// Offending lines, if the file could be read
+    pub snippet: Option<String>,
+
+    /// Suggested fix, if any
+    pub suggested_fix: Option<CodeFix>,
+
+    /// Labels of the issue
+    pub labels: Vec<String>,
+}
+
+/// Outcome of an export
+#[derive(Debug, Default)]
+pub struct ExportedIssues {
+    /// Code issues filed, with the key of the created tracker issue
+    pub created: Vec<(String, String)>,
+
+    /// Code issues that already have an open tracker issue
+    pub skipped: Vec<String>,
+}
+
+/// Turn the code issues of analysis results into tracker issues
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+/// * `base_dir` - Project root, used to make file paths repository-relative
+/// * `labels` - Labels added to every issue besides the generated ones
+///
+/// # Returns
+///
+/// One tracker issue per code issue, in the order of the results
+pub fn tracker_issues(results: &[AnalysisResult], base_dir: &Path, labels: &[String]) -> Vec<TrackerIssue> {
+    // Identical issues of a file are told apart by their order
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    results.iter()
+        .flat_map(|result| &result.issues)
+        .map(|issue| {
+            let file = relative_path(&issue.file_path, base_dir);
+            let key = format!("{}:{}:{}", file, issue.category.name(), issue.message);
+            let occurrence = occurrences.entry(key.clone()).or_default();
+            *occurrence += 1;
+
+            tracker_issue(issue, file, &format!("{}:{}", key, occurrence), labels)
+        })
+        .collect()
+}
+
+/// File tracker issues, skipping the ones with an open issue
+///
+/// # Arguments
+///
+/// * `tracker` - Tracker to file into
+/// * `issues` - Issues to file
+///
+/// # Returns
+///
+/// The created and the skipped issues, by title
+pub async fn export_issues(tracker: &dyn IssueTracker, issues: &[TrackerIssue]) -> Result<ExportedIssues> {
+    let open = tracker.open_fingerprints().await?;
+    let mut exported = ExportedIssues::default();
+
+    for issue in issues {
+        if open.contains(&issue.fingerprint) {
+            info!("Skipping {}: an open {} issue already covers it", issue.title, tracker.name());
+            exported.skipped.push(issue.title.clone());
+            continue;
+        }
+
+        let key = tracker.create_issue(issue).await?;
+        exported.created.push((issue.title.clone(), key));
+    }
+
+    Ok(exported)
+}
+
+/// Label of the fingerprint of a code issue, as stored in Jira
+pub fn fingerprint_label(fingerprint: &str) -> String {
+    format!("{}-{}", TRACKER_LABEL, fingerprint)
+}
+
+fn tracker_issue(issue: &CodeIssue, file: String, key: &str, labels: &[String]) -> TrackerIssue {
+    let severity = format!("{:?}", issue.severity);
+    let category = issue.category.name();
+
+    let mut all_labels = vec![
+        TRACKER_LABEL.to_string(),
+        format!("severity-{}", label_name(&severity)),
+        format!("category-{}", label_name(&category)),
+    ];
+    all_labels.extend(labels.iter().cloned());
+
+    let title = format!("[{}] {}:{}: {}", severity, file, issue.line_start, issue.message.lines().next().unwrap_or_default());
+
+    TrackerIssue {
+        fingerprint: content_hash(key)[..16].to_string(),
+        title: title.chars().take(MAX_TITLE_LENGTH).collect(),
+        file,
+        line: issue.line_start,
+        severity,
+        category,
+        message: issue.message.clone(),
+        snippet: read_snippet(issue),
+        suggested_fix: issue.suggested_fix.clone(),
+        labels: all_labels,
+    }
+}
+
+/// Name usable as a label: kebab-case, since Jira labels cannot contain spaces
+fn label_name(name: &str) -> String {
+    let mut label = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 && !label.ends_with('-') {
+            label.push('-');
+        }
+        if c.is_alphanumeric() {
+            label.extend(c.to_lowercase());
+        } else if !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    label.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+    use crate::Severity;
+    use std::path::PathBuf;
+
+    fn code_issue(line: usize, category: IssueCategory, message: &str) -> CodeIssue {
+        CodeIssue {
+            file_path: PathBuf::from("/work/src/lib.rs"),
+            line_start: line,
+            column_start: 1,
+            line_end: line,
+            column_end: 5,
+            category,
+            severity: Severity::Warning,
+            message: message.to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_tracker_issues() {
+        let results = vec![AnalysisResult {
+            file_path: PathBuf::from("/work/src/lib.rs"),
+            issues: vec![
+                code_issue(3, IssueCategory::CodeQuality, "Avoid unwrap"),
+                code_issue(9, IssueCategory::CodeQuality, "Avoid unwrap"),
+                code_issue(12, IssueCategory::CustomRule("no println".to_string()), "Use the logger"),
+            ],
+            errors: Vec::new(),
+            success: true,
+        }];
+
+        let issues = tracker_issues(&results, Path::new("/work"), &["backend".to_string()]);
+        assert_eq!(issues[0].title, "[Warning] src/lib.rs:3: Avoid unwrap");
+        assert_eq!(issues[0].labels, ["rust-ai-tool", "severity-warning", "category-code-quality", "backend"]);
+        assert_eq!(issues[2].labels[2], "category-no-println");
+        assert_ne!(issues[0].fingerprint, issues[1].fingerprint);
+
+        // Moving the code keeps the fingerprints
+        let mut moved = results.clone();
+        moved[0].issues.iter_mut().for_each(|issue| issue.line_start += 10);
+        let moved = tracker_issues(&moved, Path::new("/work"), &[]);
+        let fingerprints = |issues: &[TrackerIssue]| issues.iter().map(|issue| issue.fingerprint.clone()).collect::<Vec<_>>();
+        assert_eq!(fingerprints(&moved), fingerprints(&issues));
+        assert_eq!(fingerprint_label(&issues[0].fingerprint).len(), "rust-ai-tool-".len() + 16);
+    }
+}
@@ -0,0 +1,213 @@
+//! Jira integration module
+//!
+//! This module files analysis issues in Jira through its REST API (version 2,
+//! available on Jira Cloud and Data Center):
+//! - Authentication with an email and API token (Cloud) or a personal access
+//!   token (Data Center)
+//! - Issues of the configured type, with labels and a description in Jira's
+//!   wiki markup
+//! - Fingerprints stored as `rust-ai-tool-<fingerprint>` labels and found again
+//!   with a JQL search of the open issues of the project
+
+use crate::issue_tracker::{fingerprint_label, IssueTracker, TrackerIssue, TRACKER_LABEL};
+use crate::{JiraConfig, Result, RustAiToolError};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use log::{debug, info};
+
+/// Issues returned per page of a search
+const PAGE_SIZE: usize = 100;
+
+/// Client of a Jira project
+pub struct JiraClient {
+    /// HTTP client for API requests
+    client: reqwest::Client,
+
+    /// Base URL of the site
+    url: String,
+
+    /// Account email for API token authentication (`None` for personal access tokens)
+    email: Option<String>,
+
+    /// API token or personal access token
+    token: String,
+
+    /// Key of the project
+    project: String,
+
+    /// Type of the filed issues
+    issue_type: String,
+}
+
+/// Page of a search
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JiraSearchResults {
+    issues: Vec<JiraIssue>,
+    total: usize,
+}
+
+/// Issue in search results
+#[derive(Debug, Deserialize)]
+struct JiraIssue {
+    fields: JiraIssueFields,
+}
+
+/// Fields of an issue requested in a search
+#[derive(Debug, Deserialize)]
+struct JiraIssueFields {
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+/// Issue created by the API
+#[derive(Debug, Deserialize)]
+struct JiraCreatedIssue {
+    key: String,
+}
+
+impl JiraClient {
+    /// Create a client for the project of a configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Jira settings
+    ///
+    /// # Returns
+    ///
+    /// The client
+    pub fn new(config: &JiraConfig) -> Result<Self> {
+        let client = crate::network::client_builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| RustAiToolError::IssueTracker(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            url: config.url.trim_end_matches('/').to_string(),
+            email: config.email.clone(),
+            token: config.api_token.clone(),
+            project: config.project.clone(),
+            issue_type: config.issue_type.clone(),
+        })
+    }
+
+    /// Use another project than the configured one
+    pub fn with_project(mut self, project: &str) -> Self {
+        self.project = project.to_string();
+        self
+    }
+
+    /// URL of the web page of an issue
+    pub fn browse_url(&self, key: &str) -> String {
+        format!("{}/browse/{}", self.url, key)
+    }
+
+    /// Send an authenticated request and parse the JSON answer
+    async fn send_json<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RustAiToolError::IssueTracker(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(RustAiToolError::IssueTracker(format!("Jira API returned {}: {}", status, body.trim())));
+        }
+
+        response.json()
+            .await
+            .map_err(|e| RustAiToolError::IssueTracker(format!("Invalid Jira API response: {}", e)))
+    }
+}
+
+#[async_trait]
+impl IssueTracker for JiraClient {
+    fn name(&self) -> &'static str {
+        "Jira"
+    }
+
+    async fn open_fingerprints(&self) -> Result<HashSet<String>> {
+        let jql = format!(
+            "project = \"{}\" AND labels = \"{}\" AND statusCategory != Done",
+            self.project, TRACKER_LABEL
+        );
+        let prefix = format!("{}-", TRACKER_LABEL);
+        let mut fingerprints = HashSet::new();
+        let mut start_at = 0;
+
+        loop {
+            let request = self.client.get(format!("{}/rest/api/2/search", self.url))
+                .query(&[("jql", jql.as_str()), ("fields", "labels")])
+                .query(&[("startAt", start_at), ("maxResults", PAGE_SIZE)]);
+            let page: JiraSearchResults = self.send_json(request).await?;
+
+            let count = page.issues.len();
+            fingerprints.extend(page.issues.into_iter()
+                .flat_map(|issue| issue.fields.labels)
+                .filter_map(|label| label.strip_prefix(&prefix).map(String::from)));
+
+            start_at += count;
+            if count == 0 || start_at >= page.total {
+                break;
+            }
+        }
+
+        debug!("Found {} open Jira issues filed by rust-ai-tool in {}", fingerprints.len(), self.project);
+        Ok(fingerprints)
+    }
+
+    async fn create_issue(&self, issue: &TrackerIssue) -> Result<String> {
+        let mut labels = issue.labels.clone();
+        labels.push(fingerprint_label(&issue.fingerprint));
+
+        let body = json!({
+            "fields": {
+                "project": { "key": self.project },
+                "issuetype": { "name": self.issue_type },
+                "summary": issue.title,
+                "description": description(issue),
+                "labels": labels,
+            }
+        });
+
+        let created: JiraCreatedIssue = self.send_json(
+            self.client.post(format!("{}/rest/api/2/issue", self.url)).json(&body)
+        ).await?;
+
+        info!("Created Jira issue {}: {}", created.key, self.browse_url(&created.key));
+        Ok(created.key)
+    }
+}
+
+/// Description of an issue in Jira's wiki markup
+fn description(issue: &TrackerIssue) -> String {
+    let mut description = format!(
+        "rust-ai-tool found a *{}* issue ({}) in {{{{{}:{}}}}}:\n\n{}\n\n",
+        issue.severity, issue.category, issue.file, issue.line, issue.message
+    );
+
+    if let Some(snippet) = &issue.snippet {
+        description.push_str(&format!("{{code:rust}}\n{}\n{{code}}\n\n", snippet));
+    }
+
+    if let Some(fix) = &issue.suggested_fix {
+        description.push_str(&format!(
+            "*Suggested fix* (confidence {}%): {}\n{{code:rust}}\n{}\n{{code}}\n",
+            fix.confidence,
+            fix.description,
+            fix.replacement_code.trim_end()
+        ));
+    }
+
+    description
+}
@@ -0,0 +1,682 @@
+//! Journal module
+//!
+//! This module keeps a record of every change applied to a project:
+//! - One JSON line per changed file in `.rust-ai-tool/journal.jsonl`
+//! - Content hashes before and after the change, to detect later edits
+//! - The backup location used to revert the change
+//! - Undo of the last N changes or of a specific entry
+//! - Change sets: the files changed by one run, listed and rolled back together
+
+use crate::backup::restore_file;
+use crate::modification::FileChange;
+use crate::{Result, RustAiToolError};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
+
+/// Directory holding the tool's per-project state
+pub const STATE_DIR: &str = ".rust-ai-tool";
+
+/// File name of the journal inside the state directory
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// A change recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Identifier of the entry (`<timestamp>-<n>`)
+    pub id: String,
+
+    /// Time the change was applied (seconds since the Unix epoch)
+    pub timestamp: u64,
+
+    /// Path to the changed file
+    pub file_path: PathBuf,
+
+    /// SHA-256 of the content before the change
+    pub original_hash: String,
+
+    /// SHA-256 of the content after the change
+    pub new_hash: String,
+
+    /// Description of the change
+    pub description: String,
+
+    /// Path to the backup file (if created)
+    pub backup_path: Option<PathBuf>,
+
+    /// Git ref holding the backup (if created)
+    pub backup_ref: Option<String>,
+
+    /// Whether the change has been undone
+    #[serde(default)]
+    pub undone: bool,
+
+    /// Id of the change set the entry belongs to, which is the id of its first
+    /// entry (empty in journals written before change sets were recorded)
+    #[serde(default)]
+    pub change_set: String,
+}
+
+impl JournalEntry {
+    /// Id of the change set of the entry (its own id if none was recorded)
+    pub fn change_set_id(&self) -> &str {
+        if self.change_set.is_empty() {
+            &self.id
+        } else {
+            &self.change_set
+        }
+    }
+}
+
+/// Changes recorded together, like the fixes applied by one run
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeSet {
+    /// Identifier of the change set (the id of its first entry)
+    pub id: String,
+
+    /// Time the changes were applied (seconds since the Unix epoch)
+    pub timestamp: u64,
+
+    /// Changes of the set, in the order they were applied
+    pub entries: Vec<JournalEntry>,
+}
+
+impl ChangeSet {
+    /// Whether every change of the set has been undone
+    pub fn undone(&self) -> bool {
+        self.entries.iter().all(|e| e.undone)
+    }
+
+    /// One-line summary of the changes
+    pub fn summary(&self) -> String {
+        let files = if self.entries.len() == 1 { "file" } else { "files" };
+
+        match self.entries.first() {
+            Some(first) if self.entries.len() > 1 => {
+                format!("{} {}: {} (and {} more)", self.entries.len(), files, first.description, self.entries.len() - 1)
+            }
+            Some(first) => format!("1 {}: {}", files, first.description),
+            None => "no changes".to_string(),
+        }
+    }
+}
+
+/// Journal of the changes applied to a project
+pub struct Journal {
+    /// Path to the journal file
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Open the journal of a project
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Path to the project
+    ///
+    /// # Returns
+    ///
+    /// The journal (the file is created on the first recorded change)
+    pub fn open(project_path: &Path) -> Self {
+        Self {
+            path: project_path.join(STATE_DIR).join(JOURNAL_FILE),
+        }
+    }
+
+    /// Path to the journal file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record applied changes
+    ///
+    /// # Arguments
+    ///
+    /// * `changes` - Changes to record
+    ///
+    /// # Returns
+    ///
+    /// The recorded entries
+    pub fn record(&self, changes: &[FileChange]) -> Result<Vec<JournalEntry>> {
+        if changes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        // Keep ids unique when several runs happen within the same second
+        let sequence_start = self.entries()?
+            .iter()
+            .filter(|e| e.timestamp == timestamp)
+            .count();
+        let change_set = format!("{}-{}", timestamp, sequence_start + 1);
+
+        let entries: Vec<JournalEntry> = changes.iter()
+            .enumerate()
+            .map(|(i, change)| JournalEntry {
+                id: format!("{}-{}", timestamp, sequence_start + i + 1),
+                timestamp,
+                file_path: change.file_path.clone(),
+                original_hash: content_hash(change.original_content.as_deref().unwrap_or_default()),
+                new_hash: content_hash(&change.new_content),
+                description: change.description.clone(),
+                backup_path: change.backup_path.clone(),
+                backup_ref: change.backup_ref.clone(),
+                undone: false,
+                change_set: change_set.clone(),
+            })
+            .collect();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(RustAiToolError::Io)?;
+
+        for entry in &entries {
+            let line = serde_json::to_string(entry)
+                .map_err(RustAiToolError::Json)?;
+            writeln!(file, "{}", line)
+                .map_err(RustAiToolError::Io)?;
+        }
+
+        debug!("Recorded {} changes in {}", entries.len(), self.path.display());
+        Ok(entries)
+    }
+
+    /// Read all entries of the journal, oldest first
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(RustAiToolError::Io)?;
+
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(RustAiToolError::Json))
+            .collect()
+    }
+
+    /// Group the entries of the journal into change sets, oldest first
+    pub fn change_sets(&self) -> Result<Vec<ChangeSet>> {
+        let mut change_sets: Vec<ChangeSet> = Vec::new();
+
+        for entry in self.entries()? {
+            match change_sets.iter_mut().find(|set| set.id == entry.change_set_id()) {
+                Some(set) => set.entries.push(entry),
+                None => change_sets.push(ChangeSet {
+                    id: entry.change_set_id().to_string(),
+                    timestamp: entry.timestamp,
+                    entries: vec![entry],
+                }),
+            }
+        }
+
+        Ok(change_sets)
+    }
+
+    /// Revert a change set, or a single entry
+    ///
+    /// The rollback is all or nothing: every file is checked before any is
+    /// reverted, and files reverted before a failure are put back as they were.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of a change set, or of an entry
+    /// * `force` - Whether to revert files that changed since
+    ///
+    /// # Returns
+    ///
+    /// The reverted entries
+    pub fn rollback(&self, id: &str, force: bool) -> Result<Vec<JournalEntry>> {
+        let ids: Vec<String> = match self.change_sets()?.into_iter().find(|set| set.id == id) {
+            Some(set) => set.entries.into_iter()
+                .filter(|e| !e.undone)
+                .map(|e| e.id)
+                .collect(),
+            None => vec![id.to_string()],
+        };
+
+        if ids.is_empty() {
+            return Err(RustAiToolError::Modification(format!("Change set {} was already rolled back", id)));
+        }
+
+        self.revert(&ids, force, true)
+    }
+
+    /// Select the most recent entries that have not been undone
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of entries to select
+    ///
+    /// # Returns
+    ///
+    /// Ids of the selected entries, most recent first
+    pub fn last_ids(&self, count: usize) -> Result<Vec<String>> {
+        Ok(self.entries()?
+            .into_iter()
+            .rev()
+            .filter(|e| !e.undone)
+            .take(count)
+            .map(|e| e.id)
+            .collect())
+    }
+
+    /// Revert recorded changes
+    ///
+    /// Entries are reverted most recent first. A file that was edited after
+    /// the change was applied is only reverted when `force` is set. Every entry
+    /// is checked before any file is reverted, and the entries reverted before a
+    /// failure are recorded as undone.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - Ids of the entries to revert
+    /// * `force` - Whether to revert files that changed since
+    ///
+    /// # Returns
+    ///
+    /// The reverted entries
+    pub fn undo(&self, ids: &[String], force: bool) -> Result<Vec<JournalEntry>> {
+        self.revert(ids, force, false)
+    }
+
+    /// Revert entries, keeping the ones reverted before a failure unless `all_or_nothing` is set
+    fn revert(&self, ids: &[String], force: bool, all_or_nothing: bool) -> Result<Vec<JournalEntry>> {
+        let mut entries = self.entries()?;
+
+        for id in ids {
+            if !entries.iter().any(|e| &e.id == id) {
+                return Err(RustAiToolError::Other(format!("No journal entry with id {}", id)));
+            }
+        }
+
+        // Content hash of each file once the newer selected changes to it are reverted
+        let mut expected_hashes: HashMap<&Path, String> = HashMap::new();
+        for entry in entries.iter().rev().filter(|e| ids.contains(&e.id) && !e.undone) {
+            let current_hash = match expected_hashes.get(entry.file_path.as_path()) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let current = fs::read_to_string(&entry.file_path)
+                        .map_err(RustAiToolError::Io)?;
+                    content_hash(&current)
+                }
+            };
+
+            check_revertible(entry, &current_hash, force)?;
+            expected_hashes.insert(&entry.file_path, entry.original_hash.clone());
+        }
+
+        let mut reverted = Vec::new();
+        let mut previous_contents = Vec::new();
+        let mut failure = None;
+
+        for entry in entries.iter_mut().rev().filter(|e| ids.contains(&e.id)) {
+            if entry.undone {
+                warn!("Change {} to {} was already undone", entry.id, entry.file_path.display());
+                continue;
+            }
+
+            let previous = match fs::read_to_string(&entry.file_path) {
+                Ok(previous) => previous,
+                Err(e) => {
+                    failure = Some(RustAiToolError::Io(e));
+                    break;
+                }
+            };
+
+            match restore_file(&entry.file_path, entry.backup_path.as_deref(), entry.backup_ref.as_deref()) {
+                Ok(true) => {
+                    previous_contents.push(previous);
+                    info!("Reverted change {} to {}", entry.id, entry.file_path.display());
+                    entry.undone = true;
+                    reverted.push(entry.clone());
+                }
+                Ok(false) => {
+                    failure = Some(RustAiToolError::Modification(format!(
+                        "No backup found for change {} to {}",
+                        entry.id,
+                        entry.file_path.display()
+                    )));
+                    break;
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if failure.is_some() && all_or_nothing {
+            // Oldest first, so a file changed twice ends up with the content of its newest change
+            for (entry, previous) in reverted.drain(..).zip(previous_contents.drain(..)).rev() {
+                // A file that cannot be put back stays recorded as reverted
+                if let Err(e) = reapply(&entry, &previous) {
+                    warn!("Failed to put back change {} to {}: {}", entry.id, entry.file_path.display(), e);
+                    continue;
+                }
+                if let Some(recorded) = entries.iter_mut().find(|e| e.id == entry.id) {
+                    recorded.undone = false;
+                }
+            }
+        }
+
+        self.write_entries(&entries)?;
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(reverted),
+        }
+    }
+
+    /// Rewrite the journal with the given entries
+    fn write_entries(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut content = String::new();
+
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry).map_err(RustAiToolError::Json)?);
+            content.push('\n');
+        }
+
+        fs::write(&self.path, content)
+            .map_err(RustAiToolError::Io)
+    }
+}
+
+/// Check that a change can be reverted: the file content it would be reverted
+/// from is the one it wrote (unless `force` is set) and a backup of it was recorded
+fn check_revertible(entry: &JournalEntry, current_hash: &str, force: bool) -> Result<()> {
+    if current_hash != entry.new_hash && !force {
+        return Err(RustAiToolError::Modification(format!(
+            "{} changed after change {} was applied (use --force to revert anyway)",
+            entry.file_path.display(),
+            entry.id
+        )));
+    }
+
+    let backup_found = entry.backup_ref.is_some()
+        || entry.backup_path.as_deref().is_some_and(Path::exists);
+
+    if !backup_found {
+        return Err(RustAiToolError::Modification(format!(
+            "No backup recorded for change {} to {} (apply with --backup to make changes undoable)",
+            entry.id,
+            entry.file_path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Put back the content a file had before its change was reverted, and the
+/// backup file the revert consumed, so the change can be reverted later
+fn reapply(entry: &JournalEntry, previous: &str) -> Result<()> {
+    if let Some(backup_path) = entry.backup_path.as_deref().filter(|path| !path.exists()) {
+        let backup_content = fs::read_to_string(&entry.file_path)
+            .map_err(RustAiToolError::Io)?;
+        fs::write(backup_path, backup_content)
+            .map_err(RustAiToolError::Io)?;
+    }
+
+    fs::write(&entry.file_path, previous)
+        .map_err(RustAiToolError::Io)?;
+
+    warn!("Put back change {} to {}", entry.id, entry.file_path.display());
+    Ok(())
+}
+
+/// Time of a journal entry as UTC date and time (`YYYY-MM-DD HH:MM:SS`)
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Civil date of a day counted from 1970-01-01, in 400-year eras
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// SHA-256 of a file content, hex encoded
+pub fn content_hash(content: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, content.as_bytes());
+    data_encoding::HEXLOWER.encode(digest.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_and_undo() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("main.rs");
+        let backup_path = dir.path().join("main.bak");
+
+        fs::write(&file_path, "fn main() { new(); }\n").unwrap();
+        fs::write(&backup_path, "fn main() { old(); }\n").unwrap();
+
+        let journal = Journal::open(dir.path());
+        let entries = journal.record(&[FileChange {
+            file_path: file_path.clone(),
+            original_content: Some("fn main() { old(); }\n".to_string()),
+            new_content: "fn main() { new(); }\n".to_string(),
+            description: "Call new()".to_string(),
+            backup_created: true,
+            backup_path: Some(backup_path.clone()),
+            backup_ref: None,
+        }]).unwrap();
+
+        assert_eq!(journal.last_ids(5).unwrap(), vec![entries[0].id.clone()]);
+
+        let reverted = journal.undo(&journal.last_ids(1).unwrap(), false).unwrap();
+        assert_eq!(reverted.len(), 1);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn main() { old(); }\n");
+        assert!(journal.entries().unwrap()[0].undone);
+        assert!(journal.last_ids(1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_when_second_entry_fails() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::open(dir.path());
+
+        let change = |name: &str| {
+            let file_path = dir.path().join(name);
+            let backup_path = dir.path().join(format!("{}.bak", name));
+            fs::write(&file_path, "new\n").unwrap();
+            fs::write(&backup_path, "old\n").unwrap();
+
+            FileChange {
+                file_path,
+                original_content: Some("old\n".to_string()),
+                new_content: "new\n".to_string(),
+                description: format!("Fix {}", name),
+                backup_created: true,
+                backup_path: Some(backup_path),
+                backup_ref: None,
+            }
+        };
+
+        journal.record(&[change("a.rs")]).unwrap();
+        journal.record(&[change("b.rs")]).unwrap();
+        let ids = journal.last_ids(2).unwrap();
+
+        // a.rs, reverted second, was edited since: nothing is reverted
+        fs::write(dir.path().join("a.rs"), "edited\n").unwrap();
+        assert!(journal.undo(&ids, false).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("b.rs")).unwrap(), "new\n");
+        assert!(journal.entries().unwrap().iter().all(|e| !e.undone));
+
+        // The backup of a.rs cannot be read: b.rs stays reverted and is recorded so
+        fs::remove_file(dir.path().join("a.rs.bak")).unwrap();
+        fs::create_dir(dir.path().join("a.rs.bak")).unwrap();
+        assert!(journal.undo(&ids, true).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("b.rs")).unwrap(), "old\n");
+
+        let entries = journal.entries().unwrap();
+        assert!(!entries[0].undone && entries[1].undone);
+        assert_eq!(journal.last_ids(2).unwrap(), vec![ids[1].clone()]);
+    }
+
+    #[test]
+    fn test_undo_stacked_changes() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::open(dir.path());
+        let file_path = dir.path().join("lib.rs");
+
+        let change = |generation: usize| {
+            let backup_path = dir.path().join(format!("lib.rs.{}.bak", generation));
+            fs::write(&backup_path, format!("v{}\n", generation)).unwrap();
+            fs::write(&file_path, format!("v{}\n", generation + 1)).unwrap();
+
+            FileChange {
+                file_path: file_path.clone(),
+                original_content: Some(format!("v{}\n", generation)),
+                new_content: format!("v{}\n", generation + 1),
+                description: format!("Change {}", generation + 1),
+                backup_created: true,
+                backup_path: Some(backup_path),
+                backup_ref: None,
+            }
+        };
+
+        journal.record(&[change(0)]).unwrap();
+        journal.record(&[change(1)]).unwrap();
+
+        // Each older change is checked against the content the newer one started from
+        let reverted = journal.undo(&journal.last_ids(2).unwrap(), false).unwrap();
+        assert_eq!(reverted.len(), 2);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "v0\n");
+        assert!(journal.entries().unwrap().iter().all(|e| e.undone));
+    }
+
+    #[test]
+    fn test_rollback_stacked_changes() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::open(dir.path());
+
+        let change = |name: &str, generation: usize| {
+            let file_path = dir.path().join(name);
+            let backup_path = dir.path().join(format!("{}.{}.bak", name, generation));
+            fs::write(&backup_path, format!("v{}\n", generation)).unwrap();
+            fs::write(&file_path, format!("v{}\n", generation + 1)).unwrap();
+
+            FileChange {
+                file_path,
+                original_content: Some(format!("v{}\n", generation)),
+                new_content: format!("v{}\n", generation + 1),
+                description: format!("Change {} of {}", generation + 1, name),
+                backup_created: true,
+                backup_path: Some(backup_path),
+                backup_ref: None,
+            }
+        };
+
+        // One run changed lib.rs twice; the backup of main.rs, reverted last, cannot be read
+        let set = journal.record(&[change("main.rs", 0), change("lib.rs", 0), change("lib.rs", 1)]).unwrap();
+        fs::remove_file(dir.path().join("main.rs.0.bak")).unwrap();
+        fs::create_dir(dir.path().join("main.rs.0.bak")).unwrap();
+
+        assert!(journal.rollback(&set[0].id, false).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("lib.rs")).unwrap(), "v2\n");
+        assert!(journal.entries().unwrap().iter().all(|e| !e.undone));
+
+        fs::remove_dir(dir.path().join("main.rs.0.bak")).unwrap();
+        fs::write(dir.path().join("main.rs.0.bak"), "v0\n").unwrap();
+        assert_eq!(journal.rollback(&set[0].id, false).unwrap().len(), 3);
+        assert_eq!(fs::read_to_string(dir.path().join("lib.rs")).unwrap(), "v0\n");
+        assert_eq!(fs::read_to_string(dir.path().join("main.rs")).unwrap(), "v0\n");
+    }
+
+    #[test]
+    fn test_change_sets_and_rollback() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::open(dir.path());
+
+        let change = |name: &str| {
+            let file_path = dir.path().join(name);
+            let backup_path = dir.path().join(format!("{}.bak", name));
+            fs::write(&file_path, "new\n").unwrap();
+            fs::write(&backup_path, "old\n").unwrap();
+
+            FileChange {
+                file_path,
+                original_content: Some("old\n".to_string()),
+                new_content: "new\n".to_string(),
+                description: format!("Fix {}", name),
+                backup_created: true,
+                backup_path: Some(backup_path),
+                backup_ref: None,
+            }
+        };
+
+        let first = journal.record(&[change("a.rs"), change("b.rs")]).unwrap();
+        let second = journal.record(&[change("c.rs")]).unwrap();
+
+        let sets = journal.change_sets().unwrap();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].id, first[0].id);
+        assert_eq!(sets[0].summary(), "2 files: Fix a.rs (and 1 more)");
+        assert_eq!(sets[1].summary(), "1 file: Fix c.rs");
+
+        let reverted = journal.rollback(&sets[0].id, false).unwrap();
+        assert_eq!(reverted.len(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "old\n");
+        assert_eq!(fs::read_to_string(dir.path().join("b.rs")).unwrap(), "old\n");
+        assert_eq!(fs::read_to_string(dir.path().join("c.rs")).unwrap(), "new\n");
+
+        let sets = journal.change_sets().unwrap();
+        assert!(sets[0].undone() && !sets[1].undone());
+        assert!(journal.rollback(&sets[0].id, false).is_err());
+        assert_eq!(journal.rollback(&second[0].id, false).unwrap().len(), 1);
+
+        // The rollback of a set fails on its second file: the first is put back
+        let third = journal.record(&[change("d.rs"), change("e.rs")]).unwrap();
+        fs::remove_file(dir.path().join("d.rs.bak")).unwrap();
+        fs::create_dir(dir.path().join("d.rs.bak")).unwrap();
+        assert!(journal.rollback(&third[0].id, false).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("e.rs")).unwrap(), "new\n");
+        assert_eq!(fs::read_to_string(dir.path().join("e.rs.bak")).unwrap(), "old\n");
+        assert!(!journal.change_sets().unwrap()[2].entries.iter().any(|e| e.undone));
+
+        fs::remove_dir(dir.path().join("d.rs.bak")).unwrap();
+        fs::write(dir.path().join("d.rs.bak"), "old\n").unwrap();
+        assert_eq!(journal.rollback(&third[0].id, false).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("e.rs")).unwrap(), "old\n");
+
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34:56");
+    }
+}
@@ -4,14 +4,72 @@
 //! with AI assistance. It integrates with Rust Analyzer, Clippy, and AI models to
 //! provide intelligent code suggestions and automated fixes.
 
+pub mod config;
+pub mod config_migration;
+pub mod directory_config;
 pub mod analysis;
+pub mod issue_filter;
+pub mod lints;
+pub mod supply_chain;
+pub mod dependency_updates;
+pub mod lockfile;
+pub mod tauri_commands;
+pub mod tauri_config;
+pub mod analyzer_plugins;
+pub mod rule_test;
+pub mod events;
+pub mod summary;
+pub mod plugins;
+pub mod network;
+pub mod execution;
+pub mod telemetry;
+pub mod notifications;
 pub mod validation;
 pub mod project_generator;
+pub mod templates;
+pub mod manifest;
+pub mod license;
 pub mod modification;
+pub mod protected_paths;
+pub mod backup;
+pub mod patch;
+pub mod journal;
+pub mod checkpoint;
+pub mod git_index;
+pub mod changed_files;
+pub mod refactoring;
+pub mod imports;
+pub mod index;
+pub mod chunking;
+pub mod pipeline;
+pub mod batching;
+pub mod triage;
+pub mod issue_report;
+pub mod issue_tracker;
+pub mod jira;
+pub mod linear;
+pub mod compare;
+pub mod feedback;
+pub mod history;
+pub mod chat;
 pub mod cli;
+pub mod codeowners;
+pub mod forge;
+pub mod bitbucket;
 pub mod github;
+pub mod gitea;
+pub mod webhook;
+pub mod daemon;
+pub mod pr_delta;
+pub mod org_analysis;
+pub mod repo_batch;
+pub mod github_actions;
 pub mod models;
+pub mod demo;
+#[cfg(feature = "html-report")]
+pub mod html_report;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -38,6 +96,18 @@ pub enum RustAiToolError {
     #[error("GitHub API error: {0}")]
     GitHub(String),
 
+    /// Errors related to other code forges (Gitea, Forgejo, Bitbucket)
+    #[error("Forge API error: {0}")]
+    Forge(String),
+
+    /// Errors related to issue trackers (Jira, Linear)
+    #[error("Issue tracker error: {0}")]
+    IssueTracker(String),
+
+    /// Errors related to the crates.io registry
+    #[error("Registry error: {0}")]
+    Registry(String),
+
     /// Errors related to AI model integration
     #[error("AI model error: {0}")]
     AiModel(String),
@@ -50,6 +120,10 @@ pub enum RustAiToolError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// A feature needing network access was used in offline mode
+    #[error("{0} needs network access, which is disabled in offline mode")]
+    Offline(String),
+
     /// Other errors
     #[error("Other error: {0}")]
     Other(String),
@@ -59,40 +133,284 @@ pub enum RustAiToolError {
 pub type Result<T> = std::result::Result<T, RustAiToolError>;
 
 /// Core configuration for the Rust AI Tool
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     /// Path to the Rust project to analyze
     #[serde(skip)]
     pub project_path: std::path::PathBuf,
     
+    /// Version of the configuration format (1 for files without `version`)
+    #[serde(default = "default_config_version")]
+    pub version: u32,
+    
     /// GitHub repository information (if enabled)
+    #[serde(alias = "github")]
     pub github_repo: Option<GitHubRepo>,
     
-    /// AI model configuration
+    /// Gitea or Forgejo repository information (if enabled)
+    #[serde(default)]
+    pub gitea_repo: Option<GiteaRepo>,
+    
+    /// Bitbucket Cloud repository information (if enabled)
+    #[serde(default)]
+    pub bitbucket_repo: Option<BitbucketRepo>,
+    
+    /// Jira project analysis issues are exported to (if enabled)
+    #[serde(default)]
+    pub jira: Option<JiraConfig>,
+    
+    /// Linear team analysis issues are exported to (if enabled)
+    #[serde(default)]
+    pub linear: Option<LinearConfig>,
+    
+    /// Clone and push repositories over SSH instead of HTTPS (if set)
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+    
+    /// AI model configuration (the entry of `ai_models` named by `model` if not set)
+    #[serde(alias = "ai")]
     pub ai_model: AiModelConfig,
     
+    /// Named AI model configurations, selected by `model`, `task_models` or `--model`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ai_models: Vec<NamedAiModel>,
+    
+    /// Name of the entry of `ai_models` used instead of `ai_model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    
+    /// Name of the entry of `ai_models` used by a command, by command name
+    /// (subcommands joined by `-`, like `github-review`)
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub task_models: std::collections::BTreeMap<String, String>,
+    
+    /// Levels of compiler and Clippy lints, custom rules and analyzers, by name
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub lints: std::collections::BTreeMap<String, lints::LintLevel>,
+    
+    /// Licenses, banned crates and sources allowed in the dependency tree,
+    /// checked when a whole project is analyzed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<SupplyChainPolicy>,
+    
     /// Analysis options
+    #[serde(alias = "analysis")]
     pub analysis_options: AnalysisOptions,
     
     /// Validation options
+    #[serde(alias = "validation")]
     pub validation_options: ValidationOptions,
+    
+    /// Never access the network: AI and GitHub features are disabled and
+    /// Cargo runs offline, so only local analyzers are available
+    #[serde(default)]
+    pub offline: bool,
+    
+    /// Proxy and TLS settings of the HTTP clients
+    #[serde(default)]
+    pub network: NetworkConfig,
+    
+    /// Export of tracing spans to an OpenTelemetry collector
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    
+    /// Where Cargo runs for Clippy, validation and project generation
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    
+    /// Chat webhooks the summaries of runs are posted to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<NotificationTarget>,
+    
+    /// Versions and features of the dependencies of generated projects,
+    /// extending or overriding the built-in presets
+    #[serde(default)]
+    pub dependency_presets: std::collections::BTreeMap<String, project_generator::DependencyPreset>,
+}
+
+fn default_config_version() -> u32 {
+    config_migration::LEGACY_CONFIG_VERSION
+}
+
+impl Config {
+    /// Fail if a feature needing network access is used in offline mode
+    ///
+    /// # Arguments
+    ///
+    /// * `feature` - Name of the feature, as shown to the user (e.g. `fix`)
+    ///
+    /// # Returns
+    ///
+    /// An `Offline` error if offline mode is enabled
+    pub fn ensure_online(&self, feature: &str) -> Result<()> {
+        if self.offline {
+            return Err(RustAiToolError::Offline(feature.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Use an entry of `ai_models` as the AI model
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the entry
+    ///
+    /// # Returns
+    ///
+    /// An error naming the available models if there is no such entry
+    pub fn select_model(&mut self, name: &str) -> Result<()> {
+        match self.ai_models.iter().find(|model| model.name == name) {
+            Some(model) => {
+                self.ai_model = model.config.clone();
+                Ok(())
+            }
+            None => {
+                let available: Vec<&str> = self.ai_models.iter().map(|model| model.name.as_str()).collect();
+                Err(RustAiToolError::Other(format!(
+                    "Unknown model `{}` (available: {})",
+                    name,
+                    if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                )))
+            }
+        }
+    }
+}
+
+/// SSH settings for cloning and pushing repositories
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SshConfig {
+    /// Private key file (keys of the running ssh-agent are used when unset)
+    #[serde(default)]
+    pub key_path: Option<std::path::PathBuf>,
+    
+    /// Trust the host key of a server on first connection (e.g. in clean CI containers)
+    #[serde(default)]
+    pub accept_new_host_keys: bool,
+    
+    /// SSH port of a Gitea or Forgejo instance (defaults to the port of its URL, or 22)
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+impl SshConfig {
+    /// Command git runs to connect (`core.sshCommand`)
+    ///
+    /// Never prompts, so a missing key fails instead of hanging.
+    pub fn ssh_command(&self) -> String {
+        let mut command = "ssh -o BatchMode=yes".to_string();
+        
+        if let Some(key_path) = &self.key_path {
+            let key_path = key_path.to_string_lossy().replace('\'', "'\\''");
+            command.push_str(&format!(" -i '{}' -o IdentitiesOnly=yes", key_path));
+        }
+        
+        if self.accept_new_host_keys {
+            command.push_str(" -o StrictHostKeyChecking=accept-new");
+        }
+        
+        command
+    }
 }
 
 /// GitHub repository information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GitHubRepo {
     /// GitHub repository owner
     pub owner: String,
     
     /// GitHub repository name
+    #[serde(alias = "repo")]
     pub name: String,
     
     /// GitHub access token
+    #[serde(alias = "token")]
+    pub access_token: String,
+    
+    /// Secret of the webhook `serve --github-webhook` receives deliveries from
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+}
+
+/// Gitea or Forgejo repository information
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GiteaRepo {
+    /// Base URL of the instance (e.g. `https://codeberg.org`)
+    pub url: String,
+    
+    /// Repository owner
+    pub owner: String,
+    
+    /// Repository name
+    pub name: String,
+    
+    /// Access token
     pub access_token: String,
 }
 
+/// Bitbucket Cloud repository information
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BitbucketRepo {
+    /// Workspace owning the repository
+    pub workspace: String,
+    
+    /// Slug of the repository
+    pub repo_slug: String,
+    
+    /// Username, when `access_token` is an app password
+    #[serde(default)]
+    pub username: Option<String>,
+    
+    /// App password or repository/workspace access token
+    pub access_token: String,
+}
+
+/// Jira project issues are exported to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JiraConfig {
+    /// Base URL of the site (e.g. `https://acme.atlassian.net`)
+    pub url: String,
+    
+    /// Account email, when `api_token` is a Jira Cloud API token
+    /// (the token is sent as a bearer token, like a Data Center personal access token, without it)
+    #[serde(default)]
+    pub email: Option<String>,
+    
+    /// API token or personal access token
+    pub api_token: String,
+    
+    /// Key of the project issues are filed in (e.g. `ENG`)
+    pub project: String,
+    
+    /// Type of the filed issues
+    #[serde(default = "default_jira_issue_type")]
+    pub issue_type: String,
+    
+    /// Labels of the filed issues, besides the generated ones
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+fn default_jira_issue_type() -> String {
+    "Bug".to_string()
+}
+
+/// Linear team issues are exported to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LinearConfig {
+    /// Personal API key
+    pub api_key: String,
+    
+    /// Key of the team issues are filed in (e.g. `ENG`)
+    pub team: String,
+    
+    /// Labels of the filed issues, besides the generated ones (created when missing)
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
 /// AI model configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AiModelConfig {
     /// Type of AI model to use
     pub model_type: AiModelType,
@@ -102,10 +420,283 @@ pub struct AiModelConfig {
     
     /// Base URL for the AI model API
     pub api_base_url: Option<String>,
+    
+    /// Retry and rate-limit settings for API requests
+    #[serde(default)]
+    pub retry: RetryOptions,
+    
+    /// On-disk cache of completions
+    #[serde(default)]
+    pub cache: CacheOptions,
+    
+    /// Context window of the model in tokens (detected from the model type if not set)
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    
+    /// What to do with prompts that do not fit the context window
+    #[serde(default)]
+    pub prompt_overflow: PromptOverflow,
+    
+    /// Pricing per model name, overriding the built-in prices
+    #[serde(default)]
+    pub pricing: std::collections::HashMap<String, ModelPricing>,
+    
+    /// Model used for embeddings (a default of the model type if not set)
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    
+    /// Tools the model may call to read project context
+    #[serde(default)]
+    pub tools: ToolOptions,
+    
+    /// Masking of secrets in prompts
+    #[serde(default)]
+    pub redaction: RedactionOptions,
+}
+
+/// AI model configuration selected by name
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NamedAiModel {
+    /// Name of the model, like `local-ollama`
+    pub name: String,
+    
+    /// Model configuration, with the same keys as `ai_model`
+    #[serde(flatten)]
+    pub config: AiModelConfig,
+}
+
+/// Price of a model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModelPricing {
+    /// Price of a million prompt tokens
+    pub input_per_million: f64,
+    
+    /// Price of a million completion tokens
+    pub output_per_million: f64,
+}
+
+/// Handling of prompts larger than the model's context window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum PromptOverflow {
+    /// Split the code in the prompt into chunks sent as separate requests
+    #[default]
+    Chunk,
+    
+    /// Fail before sending the request
+    Refuse,
+}
+
+/// Proxy and TLS settings for AI providers, forges and the tools run by rust-ai-tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for HTTP and HTTPS requests, like `http://proxy.corp:3128`
+    /// (the `HTTPS_PROXY` and `HTTP_PROXY` environment variables are used when unset)
+    pub proxy: Option<String>,
+    
+    /// Hosts reached without the proxy, like `localhost` or `.corp.example.com`
+    pub no_proxy: Vec<String>,
+    
+    /// PEM file with additional certificate authorities to trust, like a corporate root
+    pub ca_bundle: Option<std::path::PathBuf>,
+    
+    /// Whether TLS certificates are verified (disabling it is only meant for debugging)
+    pub verify_tls: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_bundle: None,
+            verify_tls: true,
+        }
+    }
+}
+
+/// Where the Cargo commands of analysis, validation and project generation run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ExecutionConfig {
+    /// Docker image Cargo runs in, like `rust:1.79` (Cargo runs on the host when unset);
+    /// the image must include Clippy
+    pub docker_image: Option<String>,
+    
+    /// Whether build scripts and tests can access the network in the container
+    /// (dependencies are always fetched beforehand, without running any build script)
+    pub docker_network: bool,
+    
+    /// Additional arguments of `docker run`, like `["--memory", "4g"]`
+    pub docker_args: Vec<String>,
+}
+
+/// Settings for exporting the spans of a run with OTLP
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP endpoint of the collector, like `http://localhost:4318`
+    /// (`OTEL_EXPORTER_OTLP_ENDPOINT` is used when unset; nothing is exported without either)
+    pub otlp_endpoint: Option<String>,
+    
+    /// `service.name` of the exported spans
+    /// (`OTEL_SERVICE_NAME`, then `rust-ai-tool`, when unset)
+    pub service_name: Option<String>,
+    
+    /// Headers sent to the collector, like an API key
+    pub headers: std::collections::BTreeMap<String, String>,
+}
+
+/// Chat webhook receiving the summaries of runs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationTarget {
+    /// Chat service the webhook belongs to
+    pub kind: NotificationKind,
+    
+    /// Incoming webhook URL
+    pub url: String,
+    
+    /// Commands whose runs are posted (`webhook` for reviews of the webhook server)
+    #[serde(default = "default_notified_commands")]
+    pub commands: Vec<String>,
+    
+    /// Which runs are posted
+    #[serde(default)]
+    pub on: NotifyOn,
+}
+
+fn default_notified_commands() -> Vec<String> {
+    ["analyze", "fix", "webhook"].iter().map(|command| command.to_string()).collect()
+}
+
+/// Chat service of a notification webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    /// Slack incoming webhook
+    Slack,
+    
+    /// Discord channel webhook
+    Discord,
+    
+    /// Microsoft Teams incoming webhook
+    Teams,
+}
+
+/// Runs a notification webhook is told about
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+    /// Every run
+    #[default]
+    Always,
+    
+    /// Runs that found issues or failed
+    Issues,
+    
+    /// Runs that failed or produced invalid fixes
+    Failure,
+}
+
+/// Settings for the on-disk cache of AI completions
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CacheOptions {
+    /// Whether completions are cached
+    pub enabled: bool,
+    
+    /// Directory holding the cache (defaults to `.rust-ai-tool/cache` of the project;
+    /// completions outside of a project are not cached without it)
+    pub dir: Option<std::path::PathBuf>,
+    
+    /// Maximum age of a cached completion (seconds)
+    pub ttl_secs: u64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+            ttl_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Settings for tool calling during fix and code generation
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ToolOptions {
+    /// Whether models that support tools may call them
+    pub enabled: bool,
+    
+    /// Maximum number of tool-calling rounds before the model must answer
+    pub max_rounds: u32,
+}
+
+impl Default for ToolOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_rounds: 8,
+        }
+    }
+}
+
+/// Settings for masking secrets before code is sent to a provider
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RedactionOptions {
+    /// Whether detected secrets are replaced with placeholders
+    pub enabled: bool,
+    
+    /// Additional regular expressions matching secrets (a `secret` group limits what is masked)
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Retry and rate-limit settings for AI model requests
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RetryOptions {
+    /// Maximum number of retries after a rate-limited, failed or timed-out request
+    pub max_retries: u32,
+    
+    /// Delay before the first retry, doubled on every further retry (milliseconds)
+    pub initial_backoff_ms: u64,
+    
+    /// Upper bound for the delay between retries (milliseconds)
+    pub max_backoff_ms: u64,
+    
+    /// Maximum number of requests in flight at once, per provider
+    pub max_concurrent_requests: usize,
+    
+    /// Maximum number of requests started per minute, per provider (unlimited if not set)
+    pub requests_per_minute: Option<u32>,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+            max_concurrent_requests: 4,
+            requests_per_minute: None,
+        }
+    }
 }
 
 /// Supported AI model types
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum AiModelType {
     /// Claude AI model
     Claude,
@@ -118,10 +709,15 @@ pub enum AiModelType {
     
     /// Local model (e.g., using Ollama)
     Local(String),
+    
+    /// Any server implementing the OpenAI chat completions API (e.g., llama.cpp
+    /// server, vLLM, LM Studio, OpenRouter, Together), with the model name
+    /// and the base URL in `api_base_url`
+    OpenAiCompatible(String),
 }
 
 /// Options for code analysis
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnalysisOptions {
     /// Whether to run Clippy
     pub run_clippy: bool,
@@ -132,10 +728,93 @@ pub struct AnalysisOptions {
     /// Custom rules to apply during analysis
     #[serde(default)]
     pub custom_rules: Vec<CustomRule>,
+    
+    /// Analyzers loaded from dynamic libraries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub analyzers: Vec<AnalyzerPlugin>,
+    
+    /// Files and issues kept in the results
+    #[serde(default, skip_serializing_if = "issue_filter::IssueFilter::is_empty")]
+    pub filter: issue_filter::IssueFilter,
+    
+    /// Lint levels applied to the collected issues (the `lints` of the configuration)
+    #[serde(skip)]
+    pub lints: std::collections::BTreeMap<String, lints::LintLevel>,
+    
+    /// Dependency policy checked by project analyses (the `policy` of the configuration)
+    #[serde(skip)]
+    pub policy: Option<SupplyChainPolicy>,
+}
+
+/// Rules for the dependency tree of a project, in the spirit of cargo-deny
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SupplyChainPolicy {
+    /// Licenses dependencies may have (unchecked if unset)
+    pub licenses: Option<LicensePolicy>,
+    
+    /// Crates that must not be in the dependency tree
+    pub bans: Vec<BannedCrate>,
+    
+    /// Where dependencies may come from (unchecked if unset)
+    pub sources: Option<SourcePolicy>,
+}
+
+/// Licenses dependencies may have, as SPDX identifiers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LicensePolicy {
+    /// Licenses allowed (any license that is not denied if empty)
+    pub allow: Vec<String>,
+    
+    /// Licenses denied, even if allowed
+    pub deny: Vec<String>,
+    
+    /// Crates whose license is not checked
+    pub exceptions: Vec<String>,
+}
+
+/// Crate that must not be in the dependency tree
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BannedCrate {
+    /// Name of the crate
+    pub name: String,
+    
+    /// Banned versions, as a semver requirement like `<0.10.55` (all versions if unset)
+    #[serde(default)]
+    pub version: Option<String>,
+    
+    /// Why the crate is banned
+    #[serde(default)]
+    pub reason: Option<String>,
+    
+    /// Crate to use instead
+    #[serde(default)]
+    pub use_instead: Option<String>,
+}
+
+/// Registries and git repositories dependencies may come from
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SourcePolicy {
+    /// Index URLs of the allowed registries (`crates.io` for crates.io)
+    pub allow_registries: Vec<String>,
+    
+    /// Allowed git repositories, as URL prefixes like `https://github.com/acme/`
+    pub allow_git: Vec<String>,
+}
+
+impl Default for SourcePolicy {
+    fn default() -> Self {
+        Self {
+            allow_registries: vec!["crates.io".to_string()],
+            allow_git: Vec::new(),
+        }
+    }
 }
 
 /// Options for validation of suggested fixes
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ValidationOptions {
     /// Whether to validate syntax only
     pub syntax_only: bool,
@@ -145,10 +824,15 @@ pub struct ValidationOptions {
     
     /// Whether to validate security implications
     pub security_validation: bool,
+    
+    /// Files fixes must never modify, as gitignore-style globs relative to the
+    /// project (like `src/crypto/**` or `migrations/`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_paths: Vec<String>,
 }
 
 /// Custom analysis rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CustomRule {
     /// Name of the rule
     pub name: String,
@@ -163,8 +847,22 @@ pub struct CustomRule {
     pub severity: Severity,
 }
 
+/// Analyzer loaded from a dynamic library
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AnalyzerPlugin {
+    /// Name of the analyzer, prefixing the lints of its issues
+    pub name: String,
+    
+    /// Path to the library, relative to the project
+    pub path: std::path::PathBuf,
+    
+    /// Options passed to the analyzer with each file
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
 /// Severity of an issue or rule
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Severity {
     /// Error - must be fixed
     Error,
@@ -177,4 +875,32 @@ pub enum Severity {
     
     /// Style - code style issue
     Style,
+}
+
+impl Severity {
+    /// Severity by name (case-insensitive), like `error` or `warning`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "info" => Some(Severity::Info),
+            "style" => Some(Severity::Style),
+            _ => None,
+        }
+    }
+    
+    /// Whether this severity is at least as severe as another
+    pub fn is_at_least(&self, threshold: &Severity) -> bool {
+        self.rank() >= threshold.rank()
+    }
+    
+    /// Rank of the severity, higher is more severe
+    fn rank(&self) -> u8 {
+        match self {
+            Severity::Error => 3,
+            Severity::Warning => 2,
+            Severity::Info => 1,
+            Severity::Style => 0,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+//! License module
+//!
+//! This module licenses generated projects:
+//! - SPDX identifiers for MIT, Apache-2.0, their dual license and the GPL
+//!   2.0/3.0 variants, with common spellings like `dual` or `GPL-3.0` accepted
+//! - The license file, or `LICENSE-MIT` and `LICENSE-APACHE` for the dual
+//!   license, with the year and copyright holder filled in
+//! - A copyright header for source files, for the licenses asking for a notice
+//!   in every file; templates place it with `{{license_header}}`
+
+use crate::{Result, RustAiToolError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MIT_TEXT: &str = include_str!("../templates/licenses/MIT.txt");
+const APACHE_2_TEXT: &str = include_str!("../templates/licenses/Apache-2.0.txt");
+const GPL_2_TEXT: &str = include_str!("../templates/licenses/GPL-2.0.txt");
+const GPL_3_TEXT: &str = include_str!("../templates/licenses/GPL-3.0.txt");
+
+/// A license the generator writes the text of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum License {
+    /// MIT
+    Mit,
+
+    /// Apache License 2.0
+    Apache2,
+
+    /// MIT or Apache 2.0 at the user's option, as most Rust crates are
+    MitOrApache2,
+
+    /// GNU GPL version 2 only
+    Gpl2Only,
+
+    /// GNU GPL version 2 or any later version
+    Gpl2OrLater,
+
+    /// GNU GPL version 3 only
+    Gpl3Only,
+
+    /// GNU GPL version 3 or any later version
+    Gpl3OrLater,
+}
+
+impl License {
+    /// License of an SPDX expression or alias (case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The license, or `None` if its text is not shipped with the tool
+    pub fn parse(value: &str) -> Option<Self> {
+        let normalized = value.split_whitespace().collect::<Vec<_>>().join(" ").to_ascii_uppercase();
+
+        match normalized.as_str() {
+            "MIT" => Some(License::Mit),
+            "APACHE-2.0" | "APACHE2" | "APACHE" => Some(License::Apache2),
+            "MIT OR APACHE-2.0" | "APACHE-2.0 OR MIT" | "MIT/APACHE-2.0" | "DUAL" => Some(License::MitOrApache2),
+            // The deprecated SPDX identifiers without a suffix mean "only"
+            "GPL-2.0-ONLY" | "GPL-2.0" => Some(License::Gpl2Only),
+            "GPL-2.0-OR-LATER" | "GPL-2.0+" => Some(License::Gpl2OrLater),
+            "GPL-3.0-ONLY" | "GPL-3.0" => Some(License::Gpl3Only),
+            "GPL-3.0-OR-LATER" | "GPL-3.0+" | "GPL" => Some(License::Gpl3OrLater),
+            _ => None,
+        }
+    }
+
+    /// SPDX expression, as written to the `license` field of Cargo.toml
+    pub fn spdx(&self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::MitOrApache2 => "MIT OR Apache-2.0",
+            License::Gpl2Only => "GPL-2.0-only",
+            License::Gpl2OrLater => "GPL-2.0-or-later",
+            License::Gpl3Only => "GPL-3.0-only",
+            License::Gpl3OrLater => "GPL-3.0-or-later",
+        }
+    }
+
+    /// License files of a project, as file name and content
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - Copyright year
+    /// * `holder` - Copyright holder
+    ///
+    /// # Returns
+    ///
+    /// One file, or one per license for the dual license
+    pub fn files(&self, year: u32, holder: &str) -> Vec<(&'static str, String)> {
+        let mit = || MIT_TEXT.replace("[year]", &year.to_string()).replace("[fullname]", holder);
+
+        match self {
+            License::Mit => vec![("LICENSE", mit())],
+            License::Apache2 => vec![("LICENSE", APACHE_2_TEXT.to_string())],
+            License::MitOrApache2 => vec![
+                ("LICENSE-MIT", mit()),
+                ("LICENSE-APACHE", APACHE_2_TEXT.to_string()),
+            ],
+            License::Gpl2Only | License::Gpl2OrLater => vec![("COPYING", GPL_2_TEXT.to_string())],
+            License::Gpl3Only | License::Gpl3OrLater => vec![("COPYING", GPL_3_TEXT.to_string())],
+        }
+    }
+
+    /// Copyright header for Rust source files, followed by a blank line
+    ///
+    /// Empty for MIT and the dual license, which need no notice in every file.
+    pub fn header(&self, year: u32, holder: &str) -> String {
+        match self {
+            License::Mit | License::MitOrApache2 => String::new(),
+            _ => format!("// Copyright {} {}\n// SPDX-License-Identifier: {}\n\n", year, holder, self.spdx()),
+        }
+    }
+}
+
+/// Write the license files of a project
+///
+/// # Arguments
+///
+/// * `project_dir` - Project directory
+/// * `license` - License of the project
+/// * `holder` - Copyright holder
+///
+/// # Returns
+///
+/// The written files
+pub fn write_license_files(project_dir: &Path, license: License, holder: &str) -> Result<Vec<PathBuf>> {
+    license.files(current_year(), holder)
+        .into_iter()
+        .map(|(name, content)| {
+            let path = project_dir.join(name);
+            fs::write(&path, content).map_err(RustAiToolError::Io)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Current year in UTC
+pub fn current_year() -> u32 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or(0);
+
+    year_of_day(days)
+}
+
+/// Year of a day counted from 1970-01-01
+fn year_of_day(days: u64) -> u32 {
+    let mut year = 1970;
+    let mut remaining = days;
+
+    loop {
+        let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let length = if leap { 366 } else { 365 };
+        if remaining < length {
+            return year;
+        }
+        remaining -= length;
+        year += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_files() {
+        assert_eq!(License::parse("dual"), Some(License::MitOrApache2));
+        assert_eq!(License::parse("apache-2.0 OR  MIT"), Some(License::MitOrApache2));
+        assert_eq!(License::parse("GPL-3.0"), Some(License::Gpl3Only));
+        assert_eq!(License::parse("BSD-3-Clause"), None);
+
+        let files = License::MitOrApache2.files(2026, "Ada Lovelace");
+        assert_eq!(files.iter().map(|(name, _)| *name).collect::<Vec<_>>(), ["LICENSE-MIT", "LICENSE-APACHE"]);
+        assert!(files[0].1.contains("Copyright (c) 2026 Ada Lovelace"));
+        assert!(files[1].1.contains("Version 2.0, January 2004"));
+
+        assert_eq!(License::Mit.header(2026, "Ada"), "");
+        assert_eq!(
+            License::Gpl2OrLater.header(2026, "Ada"),
+            "// Copyright 2026 Ada\n// SPDX-License-Identifier: GPL-2.0-or-later\n\n"
+        );
+        assert_eq!(year_of_day(0), 1970);
+        assert_eq!(year_of_day(20_453), 2025);
+        assert_eq!(year_of_day(20_454), 2026);
+    }
+}
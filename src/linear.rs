@@ -0,0 +1,346 @@
+//! Linear integration module
+//!
+//! This module files analysis issues in Linear through its GraphQL API:
+//! - Authentication with a personal API key
+//! - Issues in the configured team, with labels that are created in the team
+//!   when they do not exist yet, and a Markdown description
+//! - Fingerprints stored on the last line of the description and found again
+//!   by listing the open issues of the team that have one
+
+use crate::issue_tracker::{IssueTracker, TrackerIssue};
+use crate::{LinearConfig, Result, RustAiToolError};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{Mutex, OnceCell};
+use log::{debug, info};
+
+/// URL of the Linear API
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// Start of the description line holding the fingerprint
+const FINGERPRINT_PREFIX: &str = "rust-ai-tool fingerprint: ";
+
+/// Client of a Linear team
+pub struct LinearClient {
+    /// HTTP client for API requests
+    client: reqwest::Client,
+
+    /// URL of the API
+    api_url: String,
+
+    /// Personal API key
+    api_key: String,
+
+    /// Key of the team
+    team: String,
+
+    /// Id of the team, looked up on first use
+    team_id: OnceCell<String>,
+
+    /// Ids of the labels of the team, by name
+    label_ids: Mutex<HashMap<String, String>>,
+}
+
+/// Answer of a GraphQL request
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+/// Error of a GraphQL request
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Page of a connection
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    nodes: Vec<T>,
+    #[serde(default)]
+    page_info: Option<PageInfo>,
+}
+
+/// Position of a page
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+/// Object with an id
+#[derive(Debug, Deserialize)]
+struct Node {
+    id: String,
+}
+
+/// Label of a team
+#[derive(Debug, Deserialize)]
+struct Label {
+    id: String,
+    name: String,
+}
+
+/// Issue in a list
+#[derive(Debug, Deserialize)]
+struct ListedIssue {
+    #[serde(default)]
+    description: Option<String>,
+}
+
+impl LinearClient {
+    /// Create a client for the team of a configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Linear settings
+    ///
+    /// # Returns
+    ///
+    /// The client
+    pub fn new(config: &LinearConfig) -> Result<Self> {
+        let client = crate::network::client_builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| RustAiToolError::IssueTracker(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            api_url: LINEAR_API_URL.to_string(),
+            api_key: config.api_key.clone(),
+            team: config.team.clone(),
+            team_id: OnceCell::new(),
+            label_ids: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Use another team than the configured one
+    pub fn with_team(mut self, team: &str) -> Self {
+        self.team = team.to_string();
+        self
+    }
+
+    /// Use another API URL (e.g. a proxy)
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Send a GraphQL request
+    async fn query<T: DeserializeOwned>(&self, query: &str, variables: JsonValue) -> Result<T> {
+        let response = self.client.post(&self.api_url)
+            .header("Authorization", &self.api_key)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| RustAiToolError::IssueTracker(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(RustAiToolError::IssueTracker(format!("Linear API returned {}: {}", status, body.trim())));
+        }
+
+        let response: GraphQlResponse<T> = response.json()
+            .await
+            .map_err(|e| RustAiToolError::IssueTracker(format!("Invalid Linear API response: {}", e)))?;
+
+        match response.data {
+            Some(data) if response.errors.is_empty() => Ok(data),
+            _ => {
+                let errors: Vec<String> = response.errors.into_iter().map(|error| error.message).collect();
+                Err(RustAiToolError::IssueTracker(format!("Linear API error: {}", errors.join("; "))))
+            }
+        }
+    }
+
+    /// Id of the team
+    async fn team_id(&self) -> Result<&String> {
+        self.team_id.get_or_try_init(|| async {
+            #[derive(Deserialize)]
+            struct Data {
+                teams: Connection<Node>,
+            }
+
+            let data: Data = self.query(
+                "query($key: String!) { teams(filter: { key: { eq: $key } }) { nodes { id } } }",
+                json!({ "key": self.team }),
+            ).await?;
+
+            data.teams.nodes.into_iter()
+                .next()
+                .map(|team| team.id)
+                .ok_or_else(|| RustAiToolError::IssueTracker(format!("No Linear team with key {}", self.team)))
+        }).await
+    }
+
+    /// Ids of labels of the team, creating the missing ones
+    async fn label_ids(&self, names: &[String]) -> Result<Vec<String>> {
+        let team_id = self.team_id().await?.clone();
+        let mut label_ids = self.label_ids.lock().await;
+
+        if label_ids.is_empty() {
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Data {
+                issue_labels: Connection<Label>,
+            }
+
+            // Workspace labels apply to every team, so they are looked up too
+            let data: Data = self.query(
+                "query($teamId: ID!) { issueLabels(first: 250, filter: { or: [{ team: { id: { eq: $teamId } } }, { team: { null: true } }] }) { nodes { id name } } }",
+                json!({ "teamId": team_id }),
+            ).await?;
+            label_ids.extend(data.issue_labels.nodes.into_iter().map(|label| (label.name, label.id)));
+        }
+
+        let mut ids = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some(id) = label_ids.get(name) {
+                ids.push(id.clone());
+                continue;
+            }
+
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Data {
+                issue_label_create: CreatedLabel,
+            }
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct CreatedLabel {
+                issue_label: Node,
+            }
+
+            debug!("Creating Linear label {} in team {}", name, self.team);
+            let data: Data = self.query(
+                "mutation($name: String!, $teamId: String!) { issueLabelCreate(input: { name: $name, teamId: $teamId }) { issueLabel { id } } }",
+                json!({ "name": name, "teamId": team_id }),
+            ).await?;
+
+            let id = data.issue_label_create.issue_label.id;
+            label_ids.insert(name.clone(), id.clone());
+            ids.push(id);
+        }
+
+        Ok(ids)
+    }
+}
+
+#[async_trait]
+impl IssueTracker for LinearClient {
+    fn name(&self) -> &'static str {
+        "Linear"
+    }
+
+    async fn open_fingerprints(&self) -> Result<HashSet<String>> {
+        #[derive(Deserialize)]
+        struct Data {
+            issues: Connection<ListedIssue>,
+        }
+
+        let mut fingerprints = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let data: Data = self.query(
+                "query($team: String!, $marker: String!, $after: String) { \
+                    issues(first: 100, after: $after, filter: { \
+                        team: { key: { eq: $team } }, \
+                        description: { contains: $marker }, \
+                        state: { type: { nin: [\"completed\", \"canceled\"] } } \
+                    }) { nodes { description } pageInfo { hasNextPage endCursor } } }",
+                json!({ "team": self.team, "marker": FINGERPRINT_PREFIX, "after": cursor }),
+            ).await?;
+
+            fingerprints.extend(data.issues.nodes.iter()
+                .filter_map(|issue| issue.description.as_deref())
+                .filter_map(fingerprint_of));
+
+            match data.issues.page_info {
+                Some(PageInfo { has_next_page: true, end_cursor: Some(end_cursor) }) => cursor = Some(end_cursor),
+                _ => break,
+            }
+        }
+
+        debug!("Found {} open Linear issues filed by rust-ai-tool in {}", fingerprints.len(), self.team);
+        Ok(fingerprints)
+    }
+
+    async fn create_issue(&self, issue: &TrackerIssue) -> Result<String> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Data {
+            issue_create: CreatedIssue,
+        }
+        #[derive(Deserialize)]
+        struct CreatedIssue {
+            issue: Option<Created>,
+        }
+        #[derive(Deserialize)]
+        struct Created {
+            identifier: String,
+            url: String,
+        }
+
+        let team_id = self.team_id().await?.clone();
+        let label_ids = self.label_ids(&issue.labels).await?;
+
+        let data: Data = self.query(
+            "mutation($input: IssueCreateInput!) { issueCreate(input: $input) { issue { identifier url } } }",
+            json!({
+                "input": {
+                    "teamId": team_id,
+                    "title": issue.title,
+                    "description": description(issue),
+                    "labelIds": label_ids,
+                }
+            }),
+        ).await?;
+
+        let created = data.issue_create.issue
+            .ok_or_else(|| RustAiToolError::IssueTracker("Linear did not create the issue".to_string()))?;
+
+        info!("Created Linear issue {}: {}", created.identifier, created.url);
+        Ok(created.identifier)
+    }
+}
+
+/// Description of an issue in Markdown, ending with its fingerprint
+fn description(issue: &TrackerIssue) -> String {
+    let mut description = format!(
+        "rust-ai-tool found a **{}** issue ({}) in `{}:{}`:\n\n{}\n\n",
+        issue.severity, issue.category, issue.file, issue.line, issue.message
+    );
+
+    if let Some(snippet) = &issue.snippet {
+        description.push_str(&format!("```rust\n{}\n```\n\n", snippet));
+    }
+
+    if let Some(fix) = &issue.suggested_fix {
+        description.push_str(&format!(
+            "**Suggested fix** (confidence {}%): {}\n\n```rust\n{}\n```\n\n",
+            fix.confidence,
+            fix.description,
+            fix.replacement_code.trim_end()
+        ));
+    }
+
+    description.push_str(&format!("{}{}", FINGERPRINT_PREFIX, issue.fingerprint));
+    description
+}
+
+/// Fingerprint stored in a description
+fn fingerprint_of(description: &str) -> Option<String> {
+    description.lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix(FINGERPRINT_PREFIX))
+        .map(|fingerprint| fingerprint.trim().to_string())
+}
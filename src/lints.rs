@@ -0,0 +1,144 @@
+//! Lints module
+//!
+//! This module tunes the issues an analysis reports with the `[lints]` table of
+//! the configuration, like `[lints]` in Cargo manifests, without attributes in
+//! every crate:
+//! - Keys name a compiler or Clippy lint (`"clippy::unwrap_used"`,
+//!   `unused_variables`), a custom rule (`no-unwrap`) or a built-in analyzer by
+//!   its category (`syntax`, `tauri-compatibility`); a lint wins over its category
+//! - `allow` drops the issues, `warn` reports them as warnings and `deny` as errors
+//! - `forbid` reports them as errors that the issue filter never drops, so
+//!   `--min-severity` or `--category` cannot hide them
+//!
+//! Levels apply to the collected issues, after Clippy and before the issue filter.
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::issue_filter::{normalize, IssueFilter};
+use crate::Severity;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Level of a lint, rule or analyzer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Drop the issues
+    Allow,
+
+    /// Report the issues as warnings
+    Warn,
+
+    /// Report the issues as errors
+    Deny,
+
+    /// Report the issues as errors, whatever the issue filter keeps
+    Forbid,
+}
+
+/// Level configured for an issue
+///
+/// # Arguments
+///
+/// * `lints` - Levels by lint, rule or category name
+/// * `issue` - Issue to look up
+///
+/// # Returns
+///
+/// The level of the lint of the issue, or else of its category or custom rule
+pub fn lint_level(lints: &BTreeMap<String, LintLevel>, issue: &CodeIssue) -> Option<LintLevel> {
+    let level_of = |name: &str| {
+        let name = normalize(name);
+        lints.iter()
+            .find(|(key, _)| normalize(key) == name)
+            .map(|(_, level)| *level)
+    };
+
+    issue.lint.as_deref()
+        .and_then(level_of)
+        .or_else(|| level_of(&issue.category.name()))
+}
+
+/// Apply lint levels and the issue filter to analysis results
+///
+/// # Arguments
+///
+/// * `lints` - Levels by lint, rule or category name
+/// * `filter` - Issues kept in the results
+/// * `results` - Analysis results to update
+pub fn retain_issues(lints: &BTreeMap<String, LintLevel>, filter: &IssueFilter, results: &mut [AnalysisResult]) {
+    for result in results.iter_mut() {
+        result.issues.retain_mut(|issue| {
+            let level = lint_level(lints, issue);
+            match level {
+                Some(LintLevel::Allow) => return false,
+                Some(LintLevel::Warn) => issue.severity = Severity::Warning,
+                Some(LintLevel::Deny | LintLevel::Forbid) => issue.severity = Severity::Error,
+                None => {}
+            }
+
+            level == Some(LintLevel::Forbid) || filter.keeps(issue)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+    use std::path::PathBuf;
+
+    fn issue(category: IssueCategory, lint: Option<&str>) -> CodeIssue {
+        CodeIssue {
+            file_path: PathBuf::from("src/lib.rs"),
+            line_start: 1,
+            column_start: 1,
+            line_end: 1,
+            column_end: 1,
+            category,
+            severity: Severity::Warning,
+            message: "message".to_string(),
+            suggested_fix: None,
+            priority: None,
+            lint: lint.map(str::to_string),
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let lints = BTreeMap::from([
+            ("clippy::unwrap_used".to_string(), LintLevel::Forbid),
+            ("code-quality".to_string(), LintLevel::Allow),
+            ("no-todo".to_string(), LintLevel::Warn),
+            ("syntax".to_string(), LintLevel::Deny),
+        ]);
+        let mut results = vec![AnalysisResult {
+            file_path: PathBuf::from("src/lib.rs"),
+            issues: vec![
+                issue(IssueCategory::CodeQuality, Some("clippy::unwrap_used")),
+                issue(IssueCategory::CodeQuality, Some("clippy::needless_return")),
+                issue(IssueCategory::CustomRule("no-todo".to_string()), None),
+                issue(IssueCategory::Syntax, None),
+                issue(IssueCategory::Style, None),
+            ],
+            errors: Vec::new(),
+            success: true,
+        }];
+
+        assert_eq!(lint_level(&lints, &results[0].issues[0]), Some(LintLevel::Forbid));
+        assert_eq!(lint_level(&lints, &results[0].issues[1]), Some(LintLevel::Allow));
+        assert_eq!(lint_level(&lints, &results[0].issues[4]), None);
+
+        let filter = IssueFilter { min_severity: Some(Severity::Warning), categories: vec!["syntax".to_string(), "no-todo".to_string()], ..Default::default() };
+        retain_issues(&lints, &filter, &mut results);
+        let kept: Vec<(String, Severity)> = results[0].issues.iter()
+            .map(|issue| (issue.lint.clone().unwrap_or_else(|| issue.category.name()), issue.severity.clone()))
+            .collect();
+        assert_eq!(kept, [
+            ("clippy::unwrap_used".to_string(), Severity::Error),
+            ("no-todo".to_string(), Severity::Warning),
+            ("Syntax".to_string(), Severity::Error),
+        ]);
+    }
+}
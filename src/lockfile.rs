@@ -0,0 +1,371 @@
+//! Lock file module
+//!
+//! This module analyzes the resolved dependency graph recorded in `Cargo.lock`,
+//! which the manifest alone does not show:
+//! - Crates locked in several versions, which slow down builds and grow
+//!   binaries, reported with the packages requiring each version and how to
+//!   unify them (bumping the requirement of the workspace, updating the
+//!   dependents, or using a single source for crates from several sources)
+//! - Packages locked to a yanked crates.io release, looked up in the sparse
+//!   index when network access is allowed
+//! - Git dependencies, which get no yanked releases or advisories and pin
+//!   whatever commit was current when they were locked
+//! - Issues are reported on `Cargo.lock` of the project or its workspace, with
+//!   the lints `lockfile::duplicate`, `lockfile::yanked` and `lockfile::git`
+
+use crate::analysis::{AnalysisResult, CodeIssue, IssueCategory};
+use crate::dependency_updates::CratesIoClient;
+use crate::supply_chain::is_crates_io;
+use crate::{Result, RustAiToolError, Severity};
+use futures::stream::{self, StreamExt};
+use semver::Version;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, warn};
+
+/// Crates looked up in the index at the same time
+const INDEX_CONCURRENCY: usize = 8;
+
+/// Parsed `Cargo.lock`
+#[derive(Debug, Clone)]
+pub struct Lockfile {
+    /// Path of the file
+    pub path: PathBuf,
+
+    /// Locked packages, in the order of the file
+    pub packages: Vec<LockedPackage>,
+}
+
+/// Package of a lock file
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockedPackage {
+    /// Crate name
+    pub name: String,
+
+    /// Locked version
+    pub version: String,
+
+    /// Source, like `registry+https://...` (none for workspace and path packages)
+    #[serde(default)]
+    pub source: Option<String>,
+
+    /// Dependencies, as `name`, `name version` or `name version (source)`
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+
+    /// Line of the `[[package]]` header of the entry
+    #[serde(skip)]
+    pub line: usize,
+}
+
+/// Content of a lock file (only the fields used)
+#[derive(Debug, Deserialize)]
+struct LockfileContent {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// Parse the content of a lock file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the file, for issues and errors
+    /// * `content` - Content of the file
+    ///
+    /// # Returns
+    ///
+    /// The parsed lock file
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let parsed: LockfileContent = toml::from_str(content)
+            .map_err(|e| RustAiToolError::Analysis(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+        // Entries are `[[package]]` tables, so their headers come in the order of the array
+        let headers = content.lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim() == "[[package]]")
+            .map(|(index, _)| index + 1);
+        let mut packages = parsed.package;
+        for (package, line) in packages.iter_mut().zip(headers) {
+            package.line = line;
+        }
+
+        Ok(Lockfile { path: path.to_path_buf(), packages })
+    }
+
+    /// Read the lock file of a project, or of the workspace it belongs to
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Project directory
+    ///
+    /// # Returns
+    ///
+    /// The lock file, or `None` if the project has none
+    pub fn find(project_path: &Path) -> Result<Option<Self>> {
+        let project_path = project_path.canonicalize().unwrap_or_else(|_| project_path.to_path_buf());
+        let Some(path) = project_path.ancestors().map(|dir| dir.join("Cargo.lock")).find(|path| path.is_file()) else {
+            return Ok(None);
+        };
+
+        let content = fs::read_to_string(&path).map_err(RustAiToolError::Io)?;
+        Self::parse(&path, &content).map(Some)
+    }
+
+    /// Packages depending on a locked package, by name
+    fn dependents(&self, package: &LockedPackage) -> Vec<&LockedPackage> {
+        let unique_name = self.packages.iter().filter(|other| other.name == package.name).count() == 1;
+
+        self.packages.iter()
+            .filter(|dependent| dependent.dependencies.iter().any(|dependency| {
+                let mut parts = dependency.split_whitespace();
+                parts.next() == Some(package.name.as_str())
+                    && parts.next().map_or(unique_name, |version| version == package.version)
+            }))
+            .collect()
+    }
+}
+
+/// Analyze the lock file of a project
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+/// * `registry` - crates.io client to look up yanked releases, or `None` to skip them (offline)
+///
+/// # Returns
+///
+/// The result of the lock file, or `None` if the project has none
+pub async fn analyze_lockfile(project_path: &Path, registry: Option<&CratesIoClient>) -> Result<Option<AnalysisResult>> {
+    let Some(lockfile) = Lockfile::find(project_path)? else {
+        debug!("No Cargo.lock found for {}", project_path.display());
+        return Ok(None);
+    };
+
+    let mut issues = duplicate_issues(&lockfile);
+    issues.extend(git_issues(&lockfile));
+    match registry {
+        Some(registry) => issues.extend(yanked_issues(&lockfile, registry).await?),
+        None => warn!("Not looking up yanked releases of {} in offline mode", lockfile.path.display()),
+    }
+    issues.sort_by_key(|issue| issue.line_start);
+
+    Ok(Some(AnalysisResult {
+        file_path: lockfile.path,
+        issues,
+        errors: Vec::new(),
+        success: true,
+    }))
+}
+
+/// Issues of crates locked in several versions, one per crate
+fn duplicate_issues(lockfile: &Lockfile) -> Vec<CodeIssue> {
+    let mut by_name: BTreeMap<&str, Vec<&LockedPackage>> = BTreeMap::new();
+    for package in &lockfile.packages {
+        by_name.entry(package.name.as_str()).or_default().push(package);
+    }
+
+    by_name.into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .map(|(name, mut packages)| {
+            packages.sort_by_key(|package| Version::parse(&package.version).ok());
+            let newest = packages[packages.len() - 1];
+
+            let versions: Vec<String> = packages.iter()
+                .map(|package| {
+                    let mut dependents: Vec<&str> = lockfile.dependents(package).iter().map(|dependent| dependent.name.as_str()).collect();
+                    dependents.dedup();
+                    if dependents.is_empty() {
+                        package.version.clone()
+                    } else {
+                        format!("{} (required by {})", package.version, dependents.join(", "))
+                    }
+                })
+                .collect();
+            let mut message = format!("{} versions of {} are locked: {}", packages.len(), name, versions.join(", "));
+
+            let sources: HashSet<Option<&str>> = packages.iter()
+                .map(|package| package.source.as_deref().map(|source| source.split('#').next().unwrap_or(source)))
+                .collect();
+            if sources.len() > 1 {
+                message.push_str(". They come from different sources; depend on one of them everywhere, or redirect the others with a [patch] section");
+            } else {
+                let mut seen = HashSet::new();
+                let outdated_dependents: Vec<&LockedPackage> = packages[..packages.len() - 1].iter()
+                    .flat_map(|package| lockfile.dependents(package))
+                    .filter(|dependent| seen.insert((&dependent.name, &dependent.version)))
+                    .collect();
+                let (workspace, external): (Vec<&LockedPackage>, Vec<&LockedPackage>) = outdated_dependents.into_iter()
+                    .partition(|dependent| dependent.source.is_none());
+
+                if !workspace.is_empty() {
+                    let members: Vec<&str> = workspace.iter().map(|member| member.name.as_str()).collect();
+                    message.push_str(&format!(". Bump the requirement of {} in {} to {}", name, members.join(", "), newest.version));
+                }
+                if !external.is_empty() {
+                    let crates: Vec<String> = external.iter().map(|dependent| format!("{}@{}", dependent.name, dependent.version)).collect();
+                    message.push_str(&format!(
+                        ". Update {} to releases depending on {} {} (`cargo update -p <crate>`), or wait for them to upgrade",
+                        crates.join(", "), name, newest.version
+                    ));
+                }
+            }
+
+            lock_issue(lockfile, packages[0], IssueCategory::Performance, Severity::Warning, "lockfile::duplicate", message)
+        })
+        .collect()
+}
+
+/// Issues of packages locked to a git commit
+fn git_issues(lockfile: &Lockfile) -> Vec<CodeIssue> {
+    lockfile.packages.iter()
+        .filter_map(|package| {
+            let source = package.source.as_deref()?.strip_prefix("git+")?;
+            let (url, commit) = source.split_once('#').unwrap_or((source, "unknown"));
+            let url = url.split('?').next().unwrap_or(url);
+
+            let message = format!(
+                "{} {} is locked to commit {} of {}; git dependencies get no yanked releases or advisories and \
+                keep that commit until `cargo update -p {}`. Prefer a crates.io release",
+                package.name, package.version, &commit[..commit.len().min(12)], url, package.name
+            );
+            Some(lock_issue(lockfile, package, IssueCategory::Security, Severity::Warning, "lockfile::git", message))
+        })
+        .collect()
+}
+
+/// Issues of packages locked to a yanked crates.io release
+async fn yanked_issues(lockfile: &Lockfile, registry: &CratesIoClient) -> Result<Vec<CodeIssue>> {
+    let names: Vec<&str> = lockfile.packages.iter()
+        .filter(|package| package.source.as_deref().is_some_and(is_crates_io))
+        .map(|package| package.name.as_str())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    debug!("Looking up {} locked crates in the crates.io index", names.len());
+
+    let releases: Vec<(&str, Vec<crate::dependency_updates::IndexVersion>)> = stream::iter(names)
+        .map(|name| async move { registry.versions(name).await.map(|versions| (name, versions)) })
+        .buffer_unordered(INDEX_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_>>()?;
+
+    let mut issues = Vec::new();
+    for (name, versions) in releases {
+        for package in lockfile.packages.iter().filter(|package| package.name == name && package.source.as_deref().is_some_and(is_crates_io)) {
+            if !versions.iter().any(|release| release.yanked && release.version == package.version) {
+                continue;
+            }
+
+            let mut message = format!("{} {} was yanked from crates.io", package.name, package.version);
+            let locked = Version::parse(&package.version).ok();
+            let replacement = versions.iter()
+                .filter(|release| !release.yanked)
+                .filter_map(|release| Version::parse(&release.version).ok())
+                .filter(|version| locked.as_ref().is_some_and(|locked| version > locked && compatible(locked, version)))
+                .max();
+            match replacement {
+                Some(version) => message.push_str(&format!("; `cargo update -p {}@{}` moves to {}", package.name, package.version, version)),
+                None => message.push_str("; no newer compatible release exists, so the requirement pulling it in must be bumped"),
+            }
+
+            issues.push(lock_issue(lockfile, package, IssueCategory::Security, Severity::Error, "lockfile::yanked", message));
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Whether Cargo may replace one version by the other (same major, or minor before 1.0)
+fn compatible(version: &Version, other: &Version) -> bool {
+    match (version.major, version.minor) {
+        (0, 0) => other.major == 0 && other.minor == 0 && other.patch == version.patch,
+        (0, minor) => other.major == 0 && other.minor == minor,
+        (major, _) => other.major == major,
+    }
+}
+
+/// Issue on the entry of a package
+fn lock_issue(lockfile: &Lockfile, package: &LockedPackage, category: IssueCategory, severity: Severity, lint: &str, message: String) -> CodeIssue {
+    CodeIssue {
+        file_path: lockfile.path.clone(),
+        line_start: package.line.max(1),
+        column_start: 1,
+        // The header, name and version lines
+        line_end: package.line.max(1) + 2,
+        column_end: 1,
+        category,
+        severity,
+        message,
+        suggested_fix: None,
+        priority: None,
+        lint: Some(lint.to_string()),
+        owners: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockfile_issues() {
+        let content = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "forked",
+ "syn 1.0.109",
+ "syn 2.0.48",
+ "thiserror",
+]
+
+[[package]]
+name = "forked"
+version = "0.3.0"
+source = "git+https://github.com/someone/forked?branch=main#0123456789abcdef0123"
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "2.0.48"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "thiserror"
+version = "1.0.50"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "syn 2.0.48",
+]
+"#;
+        let lockfile = Lockfile::parse(Path::new("Cargo.lock"), content).unwrap();
+        assert_eq!(lockfile.packages.iter().map(|package| package.line).collect::<Vec<_>>(), [3, 13, 18, 23, 28]);
+
+        let duplicates = duplicate_issues(&lockfile);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].line_start, 18);
+        assert_eq!(
+            duplicates[0].message,
+            "2 versions of syn are locked: 1.0.109 (required by app), 2.0.48 (required by app, thiserror). Bump the requirement of syn in app to 2.0.48"
+        );
+
+        let git = git_issues(&lockfile);
+        assert_eq!(git[0].lint.as_deref(), Some("lockfile::git"));
+        assert!(git[0].message.starts_with("forked 0.3.0 is locked to commit 0123456789ab of https://github.com/someone/forked;"), "{}", git[0].message);
+
+        let version = |version: &str| Version::parse(version).unwrap();
+        assert!(compatible(&version("0.3.1"), &version("0.3.9")) && !compatible(&version("0.3.1"), &version("0.4.0")));
+        assert!(compatible(&version("1.2.0"), &version("1.9.0")) && !compatible(&version("0.0.1"), &version("0.0.2")));
+    }
+}
@@ -1,18 +1,71 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
 use log::{debug, error, info, warn};
 use rust_ai_tool::{
-    analysis::{self, analyze_project, AnalysisResult},
+    analysis::{self, analyze_files, analyze_project, AnalysisResult, CodeIssue},
+    analyzer_plugins::checkout_options,
+    changed_files::changed_rust_files,
+    codeowners::{assign_owners, owners_of_files, user_logins},
+    compare::{compare_results, format_comparison, load_results},
+    config::{check_config, config_schema, parse_config},
+    config_migration::{migrate_config, CONFIG_VERSION},
+    events::{emit, enable_events, event_line, events_enabled, Event},
+    summary::{RunStatus, RunSummary},
+    chat::ChatSession,
     cli,
-    github::GithubClient,
-    modification::{apply_modifications, CodeModification, create_change_report},
-    project_generator::{generate_project_from_description, ProjectConfig, ProjectTemplate},
-    validation::{self, validate_fixes, FixToValidate, ValidationResult},
-    AiModelConfig, AiModelType, AnalysisOptions, Config, GitHubRepo, ValidationOptions,
+    demo,
+    network,
+    telemetry,
+    execution,
+    notifications,
+    bitbucket::BitbucketClient,
+    forge::{publish_fixes, update_fixes, ForgeProvider},
+    github::{GithubClient, PullRequestOptions},
+    gitea::GiteaClient,
+    webhook::{serve, WebhookOptions},
+    daemon::{serve_daemon, ClientFactory, DaemonOptions},
+    pr_delta::review_pull_request_delta,
+    org_analysis::{analyze_org, format_org_report, RepoFilter},
+    repo_batch::{format_batch_report, load_manifest, run_batch, BatchOptions},
+    github_actions::{format_workflow_commands, write_step_summary},
+    git_index::stage_modifications,
+    journal::{format_timestamp, Journal},
+    models::{tools::ProjectTools, usage::{format_usage_summary, usage_report}, AiModelClient},
+    patch::is_patch_file,
+    modification::{apply_modifications, apply_modifications_keep_going, coalesce_modifications, ApplySummary, EditSession, apply_validated_fixes, create_patch, CodeModification, create_change_report, load_modifications, partition_by_confidence, preview_modifications, rebase_modification, verify_changes, HunkDecision},
+    refactoring::extract_function,
+    rule_test::{load_rule_pack, test_rules, RuleTestOutcome},
+    imports::organize_imports,
+    dependency_updates::{find_updates, summarize_breaking_changes, update_issues, update_modifications, CratesIoClient},
+    lockfile::analyze_lockfile,
+    pipeline::{run_fix_pipeline, FixPipelineOptions},
+    checkpoint::{project_key, unit_key, Checkpoint, ANALYSIS_STAGE, VALIDATION_STAGE},
+    batching::{plan_batches, BatchStrategy},
+    protected_paths::protected_modifications,
+    plugins::{find_plugin, find_project_root, run_plugin, PluginContext, PLUGIN_PREFIX},
+    triage::triage_issues,
+    issue_filter::IssueFilter,
+    lints::retain_issues,
+    issue_report::{file_issues, group_issues, issues_resolved_by, IssueGrouping},
+    feedback::{pull_request_feedback, review_feedback, FeedbackEntry, FeedbackLog},
+    history::HistoryDb,
+    issue_tracker::{export_issues, tracker_issues, IssueTracker},
+    jira::JiraClient,
+    linear::LinearClient,
+    index::{build_index, CodeIndex},
+    project_generator::{generate_project_from_description, ProjectConfig, ProjectTemplate, TemplateOptions},
+    validation::{self, validate_fixes, validate_project_fixes, FixToValidate, ValidationResult},
+    AiModelConfig, AiModelType, AnalysisOptions, Config, GitHubRepo, RustAiToolError, Severity, ValidationOptions,
 };
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::runtime::Runtime;
+use tracing::Instrument;
 
 /// Rust AI-Powered Project Analyzer & Code Refactoring Tool
 #[derive(Parser, Debug)]
@@ -26,9 +79,50 @@ struct Cli {
     #[clap(short, long)]
     verbose: bool,
 
+    /// Only log errors; stdout carries nothing but the command's output
+    #[clap(short, long, global = true)]
+    quiet: bool,
+
     /// Configuration file path
     #[clap(short, long, default_value = ".rust-ai-tool.toml")]
     config: PathBuf,
+
+    /// Always query the AI model instead of reusing cached responses
+    #[clap(long, global = true)]
+    no_cache: bool,
+
+    /// Maximum age of a reusable cached AI response, in seconds
+    #[clap(long, global = true)]
+    cache_ttl: Option<u64>,
+
+    /// Write a JSON report of AI token usage and cost to this file
+    #[clap(long, global = true)]
+    usage_report: Option<PathBuf>,
+
+    /// Do not let the AI model call tools to read project context
+    #[clap(long, global = true)]
+    no_tools: bool,
+
+    /// Use this entry of `ai_models` in the configuration as the AI model
+    #[clap(long, global = true)]
+    model: Option<String>,
+
+    /// Let fixes modify the files listed in `protected_paths` for this run
+    #[clap(long, global = true)]
+    allow_protected: bool,
+
+    /// Never access the network: only local analyzers run, AI and GitHub features are disabled
+    #[clap(long, global = true)]
+    offline: bool,
+
+    /// Apply the `[profile.<name>]` overrides of the configuration file
+    #[clap(long, global = true)]
+    profile: Option<String>,
+
+    /// Format of the log on stderr: text, or ndjson for one JSON event per line
+    /// (analysis-started, issue-found, analysis-finished, fix-applied, log, error)
+    #[clap(long, global = true, default_value = "text", value_parser = ["text", "ndjson"])]
+    log_format: String,
 }
 
 /// Supported commands
@@ -40,13 +134,63 @@ enum Commands {
         #[clap(default_value = ".")]
         project_path: PathBuf,
 
-        /// Output format (json, markdown, console)
+        /// Output format (json, markdown, console, html, github-actions)
         #[clap(short, long, default_value = "console")]
         output: String,
 
-        /// Output file path (if not specified, output to stdout)
+        /// Output file path (if not specified, output to stdout; directory for html)
         #[clap(short, long)]
         file: Option<PathBuf>,
+
+        /// Let the AI model rank the issues by impact and fix risk, most urgent first
+        #[clap(long)]
+        triage: bool,
+
+        /// Also report dependencies that are outdated or locked to a yanked version (queries crates.io)
+        #[clap(long)]
+        outdated: bool,
+
+        /// Also analyze Cargo.lock: crates locked in several versions, git dependencies, and yanked
+        /// releases (looked up on crates.io unless offline)
+        #[clap(long)]
+        lockfile: bool,
+
+        /// Only issues of at least this severity (error, warning, info, style) make the run exit
+        /// with 1 (issues found); without it any issue does
+        #[clap(long, value_parser = parse_severity)]
+        fail_on: Option<Severity>,
+
+        /// Only analyze Rust files changed in git: uncommitted changes, or changes since --base
+        #[clap(long)]
+        changed: bool,
+
+        /// Ref the changed files are compared against, from where the current branch forked off it
+        #[clap(long, requires = "changed")]
+        base: Option<String>,
+
+        /// Only report issues that no earlier run recorded in the history reported
+        #[clap(long, conflicts_with = "baseline")]
+        new: bool,
+
+        /// Only report issues that this run of the history (listed by `runs`) did not report
+        #[clap(long, value_name = "RUN")]
+        baseline: Option<i64>,
+
+        #[clap(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Compare two analysis results written with `--output json` (fixed, new and persisting issues)
+    Compare {
+        /// Results of the first analysis, like before a refactoring
+        before: PathBuf,
+
+        /// Results of the second analysis
+        after: PathBuf,
+
+        /// Output format (console, markdown, json)
+        #[clap(short, long, default_value = "console")]
+        output: String,
     },
 
     /// Validate suggested fixes for a Rust project
@@ -55,9 +199,28 @@ enum Commands {
         #[clap(default_value = ".")]
         project_path: PathBuf,
 
-        /// Path to JSON file containing suggested fixes
+        /// Path to JSON file containing suggested fixes, or a unified diff (.patch/.diff)
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Reuse the verdicts of an interrupted run on the same fixes
+        #[clap(long)]
+        resume: bool,
+    },
+
+    /// Show suggested fixes as diffs against the current files, without applying them
+    Diff {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Path to JSON file containing suggested fixes, or a unified diff (.patch/.diff)
         #[clap(short, long)]
         fixes: PathBuf,
+
+        /// When to color the diffs: auto (on a terminal), always or never
+        #[clap(long, default_value = "auto", value_parser = ["auto", "always", "never"])]
+        color: String,
     },
 
     /// Apply suggested fixes to a Rust project
@@ -66,13 +229,61 @@ enum Commands {
         #[clap(default_value = ".")]
         project_path: PathBuf,
 
-        /// Path to JSON file containing suggested fixes
+        /// Path to JSON file containing suggested fixes, or a unified diff (.patch/.diff)
         #[clap(short, long)]
         fixes: PathBuf,
 
-        /// Create a backup before applying fixes
+        /// Back up files before applying fixes (to a git ref inside a repository, .rust-ai-tool/backups otherwise)
         #[clap(short, long)]
         backup: bool,
+
+        /// Check the fixes and write them to a patch file instead of modifying the project
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Patch file written in dry-run mode (`-` for stdout)
+        #[clap(long, default_value = "rust-ai-tool.patch")]
+        patch_file: PathBuf,
+
+        /// Review every hunk and choose whether to apply it
+        #[clap(short, long)]
+        interactive: bool,
+
+        /// File the skipped hunks are exported to in interactive mode
+        #[clap(long, default_value = "skipped-fixes.json")]
+        skipped_file: PathBuf,
+
+        /// Only apply fixes with at least this confidence (0-100); the rest are left for review
+        #[clap(long)]
+        min_confidence: Option<u8>,
+
+        /// File the low-confidence fixes are written to
+        #[clap(long, default_value = "pending-fixes.json")]
+        pending_file: PathBuf,
+
+        /// File a GitHub issue for each low-confidence fix instead of writing the pending file
+        #[clap(long)]
+        file_issues: bool,
+
+        /// Three-way merge fixes into files that changed since they were generated
+        #[clap(short, long)]
+        merge: bool,
+
+        /// Run rustfmt and cargo check after applying, rolling back if the build breaks
+        #[clap(long)]
+        verify: bool,
+
+        /// Apply what can be applied and report the files that failed instead of stopping
+        #[clap(short, long)]
+        keep_going: bool,
+
+        /// File the edits that conflict with other fixes for the same lines are written to
+        #[clap(long, default_value = "conflicting-fixes.json")]
+        conflicts_file: PathBuf,
+
+        /// Stage the changes in the git index instead of writing the working tree
+        #[clap(long, conflicts_with_all = &["dry_run", "verify", "keep_going"])]
+        index: bool,
     },
 
     /// Generate a new Rust project from description
@@ -88,6 +299,39 @@ enum Commands {
         /// Project name
         #[clap(short, long)]
         name: String,
+
+        /// Template to use instead of the one matching the description (built-in, from --template-dir, or a git URL)
+        #[clap(long)]
+        template: Option<String>,
+
+        /// Directory of user templates (`<template>/<path>.hbs`), overriding the built-in ones
+        #[clap(long = "template-dir")]
+        template_dirs: Vec<PathBuf>,
+
+        /// License of the project: an SPDX expression (MIT, Apache-2.0, "MIT OR Apache-2.0",
+        /// GPL-2.0-only, GPL-2.0-or-later, GPL-3.0-only, GPL-3.0-or-later) or `dual`
+        #[clap(long)]
+        license: Option<String>,
+
+        /// Run the post-generation hooks of the template (they can run any command)
+        #[clap(long)]
+        allow_hooks: bool,
+
+        /// Add a .pre-commit-config.yaml and a git hook running fmt, clippy and `analyze --changed --fail-on error`
+        #[clap(long)]
+        pre_commit: bool,
+
+        /// Add a .devcontainer definition with the Rust toolchain
+        #[clap(long)]
+        devcontainer: bool,
+
+        /// Times the AI model may repair the project when it fails to build or test
+        #[clap(long, default_value = "3")]
+        repair_iterations: u32,
+
+        /// Do not build and test the generated project
+        #[clap(long)]
+        no_verify: bool,
     },
 
     /// GitHub integration commands
@@ -97,304 +341,2540 @@ enum Commands {
         command: GitHubCommands,
     },
 
-    /// Initialize a new Rust AI Tool configuration
-    Init {
+    /// Gitea and Forgejo integration commands (the instance is set by `gitea_repo` in the configuration)
+    Gitea {
+        /// Repository owner (defaults to the configured one)
+        #[clap(short, long, global = true)]
+        owner: Option<String>,
+
+        /// Repository name (defaults to the configured one)
+        #[clap(short, long, global = true)]
+        repo: Option<String>,
+
+        /// Gitea subcommand
+        #[clap(subcommand)]
+        command: ForgeCommands,
+    },
+
+    /// Bitbucket Cloud integration commands (the repository is set by `bitbucket_repo` in the configuration)
+    Bitbucket {
+        /// Workspace owning the repository (defaults to the configured one)
+        #[clap(short, long, global = true)]
+        workspace: Option<String>,
+
+        /// Repository slug (defaults to the configured one)
+        #[clap(short, long, global = true)]
+        repo: Option<String>,
+
+        /// Bitbucket subcommand
+        #[clap(subcommand)]
+        command: ForgeCommands,
+    },
+
+    /// Run as a server, e.g. a review bot analyzing GitHub pull requests
+    Serve {
+        /// Receive GitHub `pull_request` webhooks and comment the analysis of each pull request
+        #[clap(long)]
+        github_webhook: bool,
+
+        /// Answer JSON-RPC requests to analyze, validate, apply and fix, keeping results warm between requests
+        #[clap(long, conflicts_with = "github_webhook")]
+        daemon: bool,
+
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        listen: std::net::SocketAddr,
+
+        /// Secret of the webhook (overrides `webhook_secret` of the GitHub configuration)
+        #[clap(long)]
+        webhook_secret: Option<String>,
+    },
+
+    /// Run the full analyze, fix and apply pipeline on a bundled sample project (offline)
+    Demo {
+        /// Directory to unpack the sample project into
+        #[clap(default_value = "rust-ai-tool-demo")]
+        output: PathBuf,
+
+        /// Overwrite the sample files of an earlier demo in the directory
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Extract a range of lines into a new function and write it as a fix
+    ExtractFunction {
+        /// File containing the code to extract
+        file: PathBuf,
+
+        /// Lines to extract (e.g. 10-20)
+        #[clap(short, long, value_parser = parse_line_range)]
+        lines: RangeInclusive<usize>,
+
+        /// Name of the new function
+        #[clap(short, long)]
+        name: String,
+
+        /// JSON file the fix is written to
+        #[clap(short, long, default_value = "fixes.json")]
+        output: PathBuf,
+    },
+
+    /// Remove unused imports, merge and sort `use` statements
+    OrganizeImports {
         /// Path to Rust project
         #[clap(default_value = ".")]
         project_path: PathBuf,
-    },
-}
 
-/// GitHub-specific commands
-#[derive(Subcommand, Debug)]
-enum GitHubCommands {
-    /// Create a pull request with suggested fixes
-    CreatePr {
-        /// Repository owner
+        /// Write the changes as fixes to this JSON file instead of applying them
         #[clap(short, long)]
-        owner: String,
+        output: Option<PathBuf>,
+    },
 
-        /// Repository name
-        #[clap(short, long)]
-        repo: String,
+    /// List dependencies that are outdated or locked to a yanked version, and write the
+    /// manifest updates as fixes
+    UpdateDeps {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
 
-        /// Branch name
-        #[clap(short, long)]
-        branch: String,
+        /// JSON file the manifest updates are written to
+        #[clap(short, long, default_value = "dependency-updates.json")]
+        output: PathBuf,
 
-        /// Pull request title
-        #[clap(short, long)]
-        title: String,
+        /// Let the AI model summarize the breaking changes of each update from the crate's changelog
+        #[clap(long)]
+        summarize: bool,
+    },
 
-        /// Path to fixes JSON file
+    /// Fix analysis issues with the AI model, refining fixes until they pass validation
+    Fix {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// JSON file the validated fixes are written to
+        #[clap(short, long, default_value = "fixes.json")]
+        output: PathBuf,
+
+        /// Refinement rounds after a fix fails validation
+        #[clap(long, default_value = "2")]
+        max_refinements: u32,
+
+        /// Files fixed concurrently (defaults to `max_concurrent_requests` of the AI retry options)
         #[clap(short, long)]
-        fixes: PathBuf,
+        jobs: Option<usize>,
+
+        /// Also generate unit tests for each fix, run them and emit the passing ones as extra fixes
+        #[clap(long)]
+        with_tests: bool,
+
+        /// Continue an interrupted run, reusing its analysis and the fixes it completed
+        #[clap(long)]
+        resume: bool,
+
+        #[clap(flatten)]
+        filter: FilterArgs,
     },
 
-    /// Clone and analyze a GitHub repository
-    Analyze {
-        /// Repository owner
+    /// Run the analyze or fix pipeline over the repositories of a manifest, with one report for all
+    Batch {
+        /// Manifest listing the repositories (TOML with `[[repos]]` entries)
+        #[clap(long)]
+        repos: PathBuf,
+
+        /// Repositories run at the same time (defaults to `concurrency` of the manifest)
         #[clap(short, long)]
-        owner: String,
+        jobs: Option<usize>,
 
-        /// Repository name
+        /// Continue an interrupted batch, skipping the repositories it completed
+        #[clap(long)]
+        resume: bool,
+
+        /// Output format (markdown, json)
+        #[clap(short, long, default_value = "markdown")]
+        output: String,
+
+        /// Output file path (if not specified, output to stdout)
         #[clap(short, long)]
-        repo: String,
+        file: Option<PathBuf>,
+    },
 
-        /// Branch name
-        #[clap(short, long, default_value = "main")]
-        branch: String,
+    /// Explain an analysis issue in plain English
+    Explain {
+        /// Location of the issue, relative to the project (e.g. src/main.rs:42)
+        #[clap(value_parser = parse_issue_location, required_unless_present = "issue_id")]
+        location: Option<(PathBuf, usize)>,
+
+        /// Id of the issue, as printed by `analyze`
+        #[clap(long, conflicts_with = "location")]
+        issue_id: Option<String>,
+
+        /// Path to Rust project
+        #[clap(short, long, default_value = ".")]
+        project_path: PathBuf,
+
+        /// Lines of code sent around the issue
+        #[clap(long, default_value = "10")]
+        context_lines: usize,
+    },
+
+    /// Chat about the project; proposed changes can be validated, saved as fixes or applied
+    Chat {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// JSON file `/save` writes the validated changes to
+        #[clap(short, long, default_value = "fixes.json")]
+        output: PathBuf,
+    },
+
+    /// Develop and test custom rules
+    Rule {
+        /// Rule subcommand
+        #[clap(subcommand)]
+        command: RuleCommands,
+    },
+
+    /// Check the configuration file
+    Config {
+        /// Config subcommand
+        #[clap(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Upgrade the configuration file to the current version (same as `config migrate`)
+    MigrateConfig {
+        /// Configuration file (defaults to the one given with --config)
+        file: Option<PathBuf>,
+
+        /// Print the upgraded configuration instead of writing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Index the project for related-definition lookup in AI prompts
+    Index {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Search the existing index instead of rebuilding it
+        #[clap(long)]
+        query: Option<String>,
+
+        /// Number of results shown for a query
+        #[clap(long, default_value = "5")]
+        limit: usize,
+    },
+
+    /// Revert changes recorded in the journal
+    Undo {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Revert the last N changes
+        #[clap(long, conflicts_with = "id")]
+        last: Option<usize>,
+
+        /// Revert the change with this journal id
+        #[clap(long)]
+        id: Option<String>,
+
+        /// Revert files even if they changed after the fix was applied
+        #[clap(long)]
+        force: bool,
+
+        /// List the recorded changes instead of reverting
+        #[clap(long)]
+        list: bool,
+    },
+
+    /// List the change sets applied to the project, most recent first
+    History {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Only list the N most recent change sets
+        #[clap(long)]
+        limit: Option<usize>,
+
+        /// List the changed files of each change set
+        #[clap(long)]
+        files: bool,
+    },
+
+    /// List the analysis runs recorded in the history database, most recent first, with their
+    /// issue counts and the outcomes of proposed fixes
+    Runs {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Only list the N most recent runs
+        #[clap(long, default_value = "20")]
+        limit: usize,
+
+        /// Output format (console, json)
+        #[clap(short, long, default_value = "console")]
+        output: String,
+    },
+
+    /// File the issues of an analysis report in Jira or Linear, one tracker issue per code
+    /// issue; issues that already have an open tracker issue are skipped
+    ExportIssues {
+        /// Tracker to file into (jira, linear)
+        #[clap(long)]
+        tracker: String,
+
+        /// Analysis results JSON file (from `analyze --output json`)
+        #[clap(long = "from")]
+        from: PathBuf,
+
+        /// Path to the analyzed project, to make file paths repository-relative
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        /// Jira project or Linear team key, instead of the configured one
+        #[clap(long)]
+        project: Option<String>,
+
+        /// Label of the filed issues, besides the configured and generated ones (repeatable)
+        #[clap(long = "label")]
+        labels: Vec<String>,
+
+        /// Print the issues that would be filed without filing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Restore the files of a change set (or of a single change) listed by `history`
+    Rollback {
+        /// Id of the change set or change
+        id: String,
+
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Restore files even if they changed after the change set was applied
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Initialize a new Rust AI Tool configuration
+    Init {
+        /// Path to Rust project
+        #[clap(default_value = ".")]
+        project_path: PathBuf,
+
+        /// Overwrite an existing configuration file
+        #[clap(long)]
+        force: bool,
+
+        /// Ask for the AI provider and model, GitHub repository and validation strictness
+        #[clap(short, long)]
+        interactive: bool,
     },
+
+    /// Run a plugin: `rust-ai-tool foo` runs `rust-ai-tool-foo` from PATH
+    #[clap(external_subcommand)]
+    External(Vec<OsString>),
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Draft status, labels, assignees and reviewers of a created pull request
+#[derive(Args, Debug)]
+struct PullRequestArgs {
+    /// Open the pull request as a draft
+    #[clap(long)]
+    draft: bool,
 
-    // Initialize logger
-    let log_level = if cli.verbose {
-        log::LevelFilter::Debug
-    } else {
-        log::LevelFilter::Info
-    };
-    
-    env_logger::Builder::new()
-        .filter_level(log_level)
-        .format_timestamp(None)
-        .init();
+    /// Label to add (repeatable)
+    #[clap(long = "label")]
+    labels: Vec<String>,
 
-    debug!("Parsed CLI arguments: {:#?}", cli);
+    /// User to assign (repeatable)
+    #[clap(long = "assignee")]
+    assignees: Vec<String>,
 
-    // Load configuration or create default
-    let config = match load_config(&cli.config) {
-        Ok(config) => {
-            debug!("Loaded configuration from {}", cli.config.display());
-            config
+    /// User to request a review from (repeatable)
+    #[clap(long = "reviewer")]
+    reviewers: Vec<String>,
+
+    /// Also request reviews from the code owners (from CODEOWNERS) of the fixed files
+    /// that are users
+    #[clap(long)]
+    owner_reviewers: bool,
+}
+
+impl PullRequestArgs {
+    fn to_options(&self, project_path: &Path, modifications: &[CodeModification]) -> Result<PullRequestOptions> {
+        let mut reviewers = self.reviewers.clone();
+        if self.owner_reviewers {
+            let owners = owners_of_files(project_path, modifications.iter().map(|m| m.file_path.as_path()))
+                .context("Failed to read the code owners")?;
+            for login in user_logins(&owners) {
+                if !reviewers.contains(&login) {
+                    reviewers.push(login);
+                }
+            }
         }
-        Err(e) => {
-            warn!("Failed to load configuration: {}", e);
-            warn!("Using default configuration");
-            create_default_config()
+        
+        Ok(PullRequestOptions {
+            draft: self.draft,
+            labels: self.labels.clone(),
+            assignees: self.assignees.clone(),
+            reviewers,
+            linked_issues: Vec::new(),
+        })
+    }
+}
+
+/// Splitting of many fixes into several pull requests
+#[derive(Args, Debug)]
+struct BatchArgs {
+    /// Open one pull request per batch of fixes instead of a single one, grouping them by
+    /// module, category or file; the branch and title of each get the batch appended
+    #[clap(long)]
+    batch_by: Option<String>,
+
+    /// Most fixes in one batch, larger groups are split (0 for no cap)
+    #[clap(long, default_value = "20")]
+    max_batch_size: usize,
+
+    /// Print the planned pull requests without opening them
+    #[clap(long)]
+    plan_only: bool,
+}
+
+impl BatchArgs {
+    /// Branch, title and fixes of each pull request to open
+    fn plan(&self, modifications: Vec<CodeModification>, project_path: &Path, branch: &str, title: &str) -> Result<Vec<(String, String, Vec<CodeModification>)>> {
+        let strategy = match self.batch_by.as_deref().map(str::to_lowercase).as_deref() {
+            None => return Ok(vec![(branch.to_string(), title.to_string(), modifications)]),
+            Some("module") => BatchStrategy::Module,
+            Some("category") => BatchStrategy::Category,
+            Some("file") => BatchStrategy::File,
+            Some(other) => anyhow::bail!("Unknown batching: {} (expected module, category or file)", other),
+        };
+        
+        Ok(plan_batches(&modifications, project_path, strategy, self.max_batch_size)
+            .into_iter()
+            .map(|batch| (batch.branch(branch), batch.title(title), batch.modifications))
+            .collect())
+    }
+}
+
+/// Print planned pull requests
+fn print_batches(batches: &[(String, String, Vec<CodeModification>)]) {
+    for (branch, title, modifications) in batches {
+        println!("{} ({} fixes) from {}", title, modifications.len(), branch);
+        for modification in modifications {
+            println!("  {}: {}", modification.file_path.display(), modification.description);
         }
-    };
+    }
+    println!("{} pull requests planned", batches.len());
+}
 
-    debug!("Using configuration: {:#?}", config);
+/// Files and issues kept in the analysis, overriding `[analysis_options.filter]` of the configuration
+#[derive(Args, Debug)]
+struct FilterArgs {
+    /// Only analyze files matching this glob, relative to the project (repeatable)
+    #[clap(long)]
+    include: Vec<String>,
 
-    // Execute command
-    match &cli.command {
-        Commands::Analyze {
-            project_path,
+    /// Skip files matching this glob, relative to the project (repeatable)
+    #[clap(long)]
+    exclude: Vec<String>,
+
+    /// Only report issues of these categories (e.g. style,performance or a custom rule name)
+    #[clap(long = "category", value_delimiter = ',')]
+    categories: Vec<String>,
+
+    /// Only report issues of at least this severity (error, warning, info, style)
+    #[clap(long, value_parser = parse_severity)]
+    min_severity: Option<Severity>,
+
+    /// Only report issues of this custom rule (repeatable)
+    #[clap(long = "rule")]
+    rules: Vec<String>,
+}
+
+impl FilterArgs {
+    /// Override the filter of the configuration with the options given
+    fn apply_to(&self, filter: &mut IssueFilter) {
+        if !self.include.is_empty() {
+            filter.include = self.include.clone();
+        }
+        if !self.exclude.is_empty() {
+            filter.exclude = self.exclude.clone();
+        }
+        if !self.categories.is_empty() {
+            filter.categories = self.categories.clone();
+        }
+        if self.min_severity.is_some() {
+            filter.min_severity = self.min_severity.clone();
+        }
+        if !self.rules.is_empty() {
+            filter.rules = self.rules.clone();
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum RuleCommands {
+    /// Run custom rules on fixture files annotated with `// expect: <rule>` on the lines they should match
+    Test {
+        /// Fixture files, or directories searched for Rust files
+        #[clap(required = true)]
+        fixtures: Vec<PathBuf>,
+
+        /// Rule pack file with `[[custom_rules]]` entries (defaults to the custom rules of the configuration)
+        #[clap(long)]
+        rules: Option<PathBuf>,
+
+        /// Only test this rule (repeatable)
+        #[clap(long = "rule")]
+        only: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Report unknown keys, values of the wrong type, missing keys and deprecated keys, with their location
+    Check {
+        /// Configuration file (defaults to the one given with --config)
+        file: Option<PathBuf>,
+    },
+
+    /// Print the JSON schema of the configuration file
+    Schema,
+
+    /// Upgrade the configuration file to the current version, renaming old keys
+    Migrate {
+        /// Configuration file (defaults to the one given with --config)
+        file: Option<PathBuf>,
+
+        /// Print the upgraded configuration instead of writing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+/// Commands of forges other than GitHub
+#[derive(Subcommand, Debug)]
+enum ForgeCommands {
+    /// Create a pull request with suggested fixes
+    CreatePr {
+        /// Branch name
+        #[clap(short, long)]
+        branch: String,
+
+        /// Pull request title
+        #[clap(short, long)]
+        title: String,
+
+        /// Path to fixes JSON file
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Path to the local checkout of the project the fixes were made in
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        /// Comment each fix next to its first change in the pull request
+        #[clap(long)]
+        inline_comments: bool,
+
+        #[clap(flatten)]
+        pr_options: PullRequestArgs,
+
+        #[clap(flatten)]
+        batches: BatchArgs,
+    },
+
+    /// Refresh the pull request of fixes from a branch instead of opening a duplicate
+    UpdatePr {
+        /// Branch name
+        #[clap(short, long)]
+        branch: String,
+
+        /// Pull request title
+        #[clap(short, long)]
+        title: String,
+
+        /// Path to fixes JSON file
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Path to the local checkout of the project the fixes were made in
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        #[clap(flatten)]
+        pr_options: PullRequestArgs,
+    },
+
+    /// Show the status of the latest CI pipeline run of a branch
+    PipelineStatus {
+        /// Branch name
+        #[clap(short, long)]
+        branch: String,
+    },
+}
+
+/// GitHub-specific commands
+#[derive(Subcommand, Debug)]
+enum GitHubCommands {
+    /// Create a pull request with suggested fixes
+    CreatePr {
+        /// Repository owner
+        #[clap(short, long)]
+        owner: String,
+
+        /// Repository name
+        #[clap(short, long)]
+        repo: String,
+
+        /// Branch name
+        #[clap(short, long)]
+        branch: String,
+
+        /// Pull request title
+        #[clap(short, long)]
+        title: String,
+
+        /// Path to fixes JSON file
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Path to the local checkout of the project the fixes were made in
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        /// Comment each fix next to its first change in the pull request
+        #[clap(long)]
+        inline_comments: bool,
+
+        #[clap(flatten)]
+        pr_options: PullRequestArgs,
+
+        #[clap(flatten)]
+        batches: BatchArgs,
+    },
+
+    /// Refresh the pull request of fixes from a branch instead of opening a duplicate
+    UpdatePr {
+        /// Repository owner
+        #[clap(short, long)]
+        owner: String,
+
+        /// Repository name
+        #[clap(short, long)]
+        repo: String,
+
+        /// Branch name
+        #[clap(short, long)]
+        branch: String,
+
+        /// Pull request title
+        #[clap(short, long)]
+        title: String,
+
+        /// Path to fixes JSON file
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Path to the local checkout of the project the fixes were made in
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        #[clap(flatten)]
+        pr_options: PullRequestArgs,
+    },
+
+    /// Record how a pull request created from fixes was reviewed, to improve future fixes
+    Feedback {
+        /// Repository owner
+        #[clap(short, long)]
+        owner: String,
+
+        /// Repository name
+        #[clap(short, long)]
+        repo: String,
+
+        /// Number of the pull request
+        #[clap(long)]
+        pr: u64,
+
+        /// Path to the fixes JSON file the pull request was created from
+        #[clap(short, long)]
+        fixes: PathBuf,
+
+        /// Path to the local checkout of the project
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+    },
+
+    /// File GitHub issues for the issues of an analysis report
+    CreateIssues {
+        /// Repository owner
+        #[clap(short, long)]
+        owner: String,
+
+        /// Repository name
+        #[clap(short, long)]
+        repo: String,
+
+        /// Analysis results JSON file (from `analyze --output json`)
+        #[clap(long = "from")]
+        from: PathBuf,
+
+        /// Group issues by file, by category or by code owner (from CODEOWNERS); code owners
+        /// are mentioned in the issues and assigned when they are users
+        #[clap(long, default_value = "file")]
+        group_by: String,
+
+        /// Path to the analyzed project, to make file paths repository-relative
+        #[clap(long, default_value = ".")]
+        project_path: PathBuf,
+
+        /// Label of the created issues (repeatable)
+        #[clap(long = "label", default_value = "rust-ai-tool")]
+        labels: Vec<String>,
+
+        /// Print the issues that would be filed without filing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Clone and analyze the repositories of a GitHub organization
+    AnalyzeOrg {
+        /// Organization name
+        #[clap(long)]
+        org: String,
+
+        /// Only repositories with this primary language (e.g. Rust)
+        #[clap(long)]
+        language: Option<String>,
+
+        /// Only repositories with this topic
+        #[clap(long)]
+        topic: Option<String>,
+
+        /// Also analyze archived repositories
+        #[clap(long)]
+        include_archived: bool,
+
+        /// Also analyze forks
+        #[clap(long)]
+        include_forks: bool,
+
+        /// Repositories cloned and analyzed at the same time
+        #[clap(long, default_value = "4")]
+        concurrency: usize,
+
+        /// Continue an interrupted run, only analyzing the repositories it did not complete
+        #[clap(long)]
+        resume: bool,
+
+        /// Output format (markdown, json)
+        #[clap(short, long, default_value = "markdown")]
+        output: String,
+
+        /// Output file path (if not specified, output to stdout)
+        #[clap(short, long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Clone and analyze a GitHub repository
+    Analyze {
+        /// Repository owner
+        #[clap(short, long)]
+        owner: String,
+
+        /// Repository name
+        #[clap(short, long)]
+        repo: String,
+
+        /// Branch name
+        #[clap(short, long, default_value = "main")]
+        branch: String,
+
+        /// Only analyze this subdirectory of the repository (e.g. a crate of a monorepo)
+        #[clap(long)]
+        path: Option<PathBuf>,
+
+        /// Compare the base and head commits of this pull request and comment the delta on it
+        #[clap(long)]
+        pr: Option<u64>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = Cli::command().get_matches();
+    let mut summary = RunSummary::new(command_name(&matches));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let usage_report_path = cli.usage_report.clone();
+    let quiet = cli.quiet;
+    
+    // The run is the root span of everything it traces
+    telemetry::init();
+    let span = tracing::info_span!("run", command = %summary.command);
+    let mut result = run(cli, &mut summary).instrument(span.clone()).await;
+    
+    // Report usage even when the command failed, since the tokens were spent anyway.
+    // It goes to stderr so stdout stays parseable
+    let report = usage_report();
+    if !report.models.is_empty() && !quiet {
+        eprintln!("\n{}", format_usage_summary(&report));
+    }
+    
+    if let Some(path) = usage_report_path {
+        let written = serde_json::to_string_pretty(&report)
+            .context("Failed to serialize usage report")
+            .and_then(|report_json| {
+                fs::write(&path, report_json)
+                    .context(format!("Failed to write usage report to {}", path.display()))
+            });
+        result = result.and(written);
+    }
+    
+    // Failures are events too, so the stream stays parseable
+    if let Err(e) = &result {
+        summary.fail(RunStatus::Error);
+        tracing::error!(parent: &span, "{:#}", e);
+        notifications::record_failure(format!("{:#}", e));
+        
+        if events_enabled() {
+            emit(&Event::Error { message: format!("{:#}", e) });
+        } else {
+            eprintln!("Error: {:?}", e);
+        }
+    }
+    
+    // The summary is always the last line, for scripts to parse
+    if events_enabled() {
+        emit(&Event::from(&summary));
+    } else if !quiet {
+        eprintln!("{}", summary);
+    }
+    
+    std::io::stdout().flush().ok();
+    
+    notifications::notify_run(&summary).await;
+    drop(span);
+    telemetry::shutdown().await;
+    std::process::exit(summary.status.exit_code());
+}
+
+/// Name of the command in the run summary, with its subcommands (like `rule-test`)
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    
+    while let Some((name, subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    
+    names.join("-")
+}
+
+/// Run the command given on the command line, recording its outcome in the summary
+async fn run(cli: Cli, summary: &mut RunSummary) -> Result<()> {
+    // Initialize logger
+    let log_level = if cli.verbose {
+        log::LevelFilter::Debug
+    } else if cli.quiet {
+        log::LevelFilter::Error
+    } else {
+        log::LevelFilter::Info
+    };
+    
+    let mut logger = env_logger::Builder::new();
+    logger.filter_level(log_level).format_timestamp(None);
+    
+    if cli.log_format == "ndjson" {
+        enable_events();
+        logger.format(|buf, record| {
+            let event = Event::Log {
+                level: record.level().as_str().to_lowercase(),
+                target: record.target(),
+                message: record.args().to_string(),
+            };
+            writeln!(buf, "{}", event_line(&event))
+        });
+    }
+    
+    logger.init();
+
+    debug!("Parsed CLI arguments: {:#?}", cli);
+
+    // Checking the configuration must not depend on loading it
+    if let Commands::Config { command } = &cli.command {
+        return run_config_command(command, &cli.config, summary);
+    }
+    if let Commands::MigrateConfig { file, dry_run } = &cli.command {
+        let command = ConfigCommands::Migrate { file: file.clone(), dry_run: *dry_run };
+        return run_config_command(&command, &cli.config, summary);
+    }
+
+    // Load configuration, or create default if there is no configuration file.
+    // A profile only exists in the configuration file, so defaults would not honour it
+    let mut config = if matches!(cli.command, Commands::Init { .. }) {
+        // `init` writes a new configuration, so an invalid one must not stop it
+        create_default_config()
+    } else if cli.config.exists() || cli.profile.is_some() {
+        let config = load_config(&cli.config, cli.profile.as_deref())?;
+        debug!("Loaded configuration from {}", cli.config.display());
+        config
+    } else {
+        warn!("Configuration file {} not found", cli.config.display());
+        warn!("Using default configuration");
+        create_default_config()
+    };
+
+    if cli.no_cache {
+        config.ai_model.cache.enabled = false;
+    }
+    if let Some(cache_ttl) = cli.cache_ttl {
+        config.ai_model.cache.ttl_secs = cache_ttl;
+    }
+    if cli.no_tools {
+        config.ai_model.tools.enabled = false;
+    }
+    if cli.allow_protected {
+        config.validation_options.protected_paths.clear();
+    }
+    
+    // A model chosen on the command line wins over the model of the command
+    let model = cli.model.clone().or_else(|| config.task_models.get(&summary.command).cloned());
+    if let Some(name) = model {
+        config.select_model(&name)?;
+        debug!("Using AI model {}", name);
+    }
+    if let Commands::Analyze { filter, .. } | Commands::Fix { filter, .. } = &cli.command {
+        filter.apply_to(&mut config.analysis_options.filter);
+    }
+    if cli.offline {
+        config.offline = true;
+    }
+    if config.offline {
+        // Cargo and Clippy inherit this and never fetch from the registry
+        std::env::set_var("CARGO_NET_OFFLINE", "true");
+        
+        if let Some(feature) = network_feature(&cli.command) {
+            config.ensure_online(feature)?;
+        }
+    }
+    network::configure(&config.network)?;
+    telemetry::configure(&config.telemetry, config.offline)?;
+    execution::configure(&config.execution);
+    notifications::configure(&config.notifications, config.offline);
+
+    debug!("Using configuration: {:#?}", config);
+
+    // Execute command
+    match &cli.command {
+        Commands::Analyze {
+            project_path,
+            output,
+            file,
+            triage,
+            outdated,
+            lockfile,
+            fail_on,
+            changed,
+            base,
+            new,
+            baseline,
+            ..
+        } => {
+            info!("Analyzing project at {}", project_path.display());
+            
+            let mut results = if *changed {
+                let files = changed_rust_files(project_path, base.as_deref())
+                    .context("Failed to list changed files")?;
+                info!("Analyzing {} changed files", files.len());
+                
+                analyze_files(project_path, &files, &config.analysis_options)
+            } else {
+                analyze_project(project_path, &config.analysis_options)
+            }
+            .context("Failed to analyze project")?;
+            
+            if *outdated {
+                let registry = CratesIoClient::new()?;
+                let updates = find_updates(project_path, &registry)
+                    .await
+                    .context("Failed to check for dependency updates")?;
+                let mut result = update_issues(project_path, &updates)?;
+                
+                retain_issues(&config.analysis_options.lints, &config.analysis_options.filter, std::slice::from_mut(&mut result));
+                if !result.issues.is_empty() {
+                    results.push(result);
+                }
+            }
+            
+            if *lockfile {
+                let registry = if config.offline { None } else { Some(CratesIoClient::new()?) };
+                let result = analyze_lockfile(project_path, registry.as_ref())
+                    .await
+                    .context("Failed to analyze Cargo.lock")?;
+                
+                match result {
+                    Some(mut result) => {
+                        retain_issues(&config.analysis_options.lints, &config.analysis_options.filter, std::slice::from_mut(&mut result));
+                        if !result.issues.is_empty() {
+                            results.push(result);
+                        }
+                    }
+                    None => warn!("No Cargo.lock found; run `cargo generate-lockfile` to analyze the resolved dependencies"),
+                }
+            }
+            
+            if *triage {
+                let client = create_project_ai_client(&config.ai_model, project_path)?;
+                let ranked = triage_issues(&mut results, &client)
+                    .await
+                    .context("Failed to triage issues")?;
+                
+                info!("Ranked {} issues by priority", ranked);
+            }
+            
+            // Recording the run is best effort, unless the history decides which issues are reported
+            let history = HistoryDb::open(project_path).await;
+            let reported = match &history {
+                Ok(history) if *new || baseline.is_some() => Some(history.reported_issue_ids(*baseline).await?),
+                Err(e) if *new || baseline.is_some() => anyhow::bail!("Failed to open the history database: {}", e),
+                _ => None,
+            };
+            match &history {
+                Ok(history) => match history.record_analysis("analyze", &results).await {
+                    Ok(run_id) => info!("Recorded run {} in the history", run_id),
+                    Err(e) => warn!("Failed to record the run in the history: {}", e),
+                },
+                Err(e) => warn!("Failed to open the history database: {}", e),
+            }
+            if let Some(reported) = reported {
+                for result in &mut results {
+                    result.issues.retain(|issue| !reported.contains(&issue.id()));
+                }
+            }
+            notifications::record_results(project_path, &results);
+            
+            // Without a threshold every issue counts
+            let failing = results.iter()
+                .flat_map(|result| &result.issues)
+                .filter(|issue| fail_on.as_ref().is_none_or(|threshold| issue.severity.is_at_least(threshold)))
+                .count();
+            summary.count("files", results.len())
+                .count("issues", results.iter().map(|result| result.issues.len()).sum());
+            if failing > 0 {
+                summary.fail(RunStatus::IssuesFound);
+            }
+            
+            if output.eq_ignore_ascii_case("html") {
+                let site_dir = file.clone().unwrap_or_else(|| PathBuf::from("rust-ai-tool-report"));
+                let index_path = write_html_report(&results, project_path, &site_dir)?;
+                
+                info!("HTML report written to {}", index_path.display());
+                return Ok(());
+            }
+            
+            let output_content = format_analysis_results(&results, output)?;
+            
+            if let Some(output_file) = file {
+                fs::write(output_file, &output_content)
+                    .context(format!("Failed to write output to {}", output_file.display()))?;
+                
+                info!("Analysis results written to {}", output_file.display());
+            } else {
+                println!("{}", output_content);
+            }
+            
+            if output.eq_ignore_ascii_case("github-actions") {
+                write_step_summary(&results).context("Failed to write the job summary")?;
+            }
+            
+            if let Some(threshold) = fail_on {
+                if failing > 0 {
+                    error!("{} issues with severity {:?} or higher found", failing, threshold);
+                }
+            }
+            
+            info!("Analysis complete");
+        }
+        Commands::Compare { before, after, output } => {
+            let before_results = load_results(before)
+                .context(format!("Failed to read analysis results: {}", before.display()))?;
+            let after_results = load_results(after)
+                .context(format!("Failed to read analysis results: {}", after.display()))?;
+            
+            let comparison = compare_results(&before_results, &after_results);
+            summary.count("fixed", comparison.fixed.len())
+                .count("new", comparison.new.len())
+                .count("persisting", comparison.persisting.len());
+            if !comparison.new.is_empty() {
+                summary.fail(RunStatus::IssuesFound);
+            }
+            
+            let report = match output.as_str() {
+                "json" => serde_json::to_string_pretty(&comparison)
+                    .context("Failed to serialize comparison to JSON")?,
+                "markdown" => format_comparison(&comparison, true),
+                "console" => format_comparison(&comparison, false),
+                _ => anyhow::bail!("Unsupported output format: {} (expected console, markdown or json)", output),
+            };
+            
+            println!("{}", report);
+        }
+        Commands::Validate { project_path, fixes, resume } => {
+            info!(
+                "Validating fixes for project at {} using {}",
+                project_path.display(),
+                fixes.display()
+            );
+            
+            let fixes_to_validate: Vec<FixToValidate> = if is_patch_file(fixes) {
+                load_modifications(fixes, project_path)
+                    .context(format!("Failed to read patch: {}", fixes.display()))?
+                    .into_iter()
+                    .map(|m| FixToValidate {
+                        file_path: m.file_path,
+                        original_code: m.original_content,
+                        modified_code: m.modified_content,
+                        description: m.description,
+                    })
+                    .collect()
+            } else {
+                let fixes_content = fs::read_to_string(fixes)
+                    .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+                
+                serde_json::from_str(&fixes_content)
+                    .context("Failed to parse fixes JSON")?
+            };
+            
+            let checkpoint = Checkpoint::open(project_path, "validate", *resume)
+                .context("Failed to open the checkpoint")?;
+            let mut validation_results = Vec::new();
+            for fix in &fixes_to_validate {
+                let path = fix.file_path.to_string_lossy();
+                let key = unit_key(&[&path, &fix.original_code, &fix.modified_code]);
+                if let Some(result) = checkpoint.completed::<ValidationResult>(VALIDATION_STAGE, &key) {
+                    validation_results.push(result);
+                    continue;
+                }
+                
+                for result in validate_project_fixes(project_path, std::slice::from_ref(fix), &config.validation_options)
+                    .context("Failed to validate fixes")? {
+                    checkpoint.record(VALIDATION_STAGE, &key, &result)
+                        .context("Failed to record the verdict in the checkpoint")?;
+                    validation_results.push(result);
+                }
+            }
+            checkpoint.finish().context("Failed to remove the checkpoint")?;
+            
+            let valid_count = validation_results.iter().filter(|r| r.is_valid).count();
+            let total_count = validation_results.len();
+            
+            println!("Validation complete: {}/{} fixes are valid", valid_count, total_count);
+            summary.count("fixes", total_count).count("invalid", total_count - valid_count);
+            if valid_count < total_count {
+                summary.fail(RunStatus::InvalidFixes);
+            }
+            
+            for (i, result) in validation_results.iter().enumerate() {
+                if !result.is_valid {
+                    println!("Fix #{} for {} is invalid:", i + 1, result.file_path.display());
+                    for msg in &result.messages {
+                        println!("  - {}: {}", msg.message_type, msg.text);
+                    }
+                }
+            }
+        }
+        Commands::Diff { project_path, fixes, color } => {
+            let modifications = load_modifications(fixes, project_path)
+                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            let previews = preview_modifications(&modifications, project_path)
+                .context("Failed to compare the fixes with the project")?;
+            
+            let colored = match color.as_str() {
+                "always" => true,
+                "never" => false,
+                _ => std::io::stdout().is_terminal(),
+            };
+            
+            for (i, preview) in previews.iter().enumerate() {
+                println!(
+                    "# Fix #{}: {} (confidence {}%)",
+                    i + 1,
+                    preview.description,
+                    preview.confidence
+                );
+                
+                if preview.drifted {
+                    println!(
+                        "# {} changed since the fix was generated; shown merged into the current file ({} conflicts)",
+                        preview.file_path.display(),
+                        preview.conflicts
+                    );
+                }
+                
+                if preview.diff.is_empty() {
+                    println!("# No changes to {}", preview.file_path.display());
+                } else if colored {
+                    print!("{}", cli::colorize_diff(&preview.diff));
+                } else {
+                    print!("{}", preview.diff);
+                }
+            }
+            
+            let drifted = previews.iter().filter(|preview| preview.drifted).count();
+            summary.count("fixes", previews.len()).count("drifted", drifted);
+            info!("{} fixes, {} for files that changed since they were generated", previews.len(), drifted);
+        }
+        Commands::Apply {
+            project_path,
+            fixes,
+            backup,
+            dry_run,
+            patch_file,
+            interactive,
+            skipped_file,
+            min_confidence,
+            pending_file,
+            file_issues,
+            merge,
+            verify,
+            keep_going,
+            conflicts_file,
+            index,
+        } => {
+            info!(
+                "Applying fixes to project at {} using {}",
+                project_path.display(),
+                fixes.display()
+            );
+            
+            if *backup {
+                info!("Creating backup before applying fixes");
+            }
+            
+            let mut modifications = load_modifications(fixes, project_path)
+                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            
+            let protected = protected_modifications(&modifications, &config.validation_options.protected_paths, project_path)
+                .context("Failed to check the protected paths")?;
+            if !protected.is_empty() {
+                for (file_path, glob) in &protected {
+                    error!("{} is protected by '{}' in protected_paths", file_path.display(), glob);
+                }
+                error!("Refusing to apply fixes to protected files (use --allow-protected to override)");
+                summary.count("fixes", modifications.len()).count("protected", protected.len()).fail(RunStatus::InvalidFixes);
+                return Ok(());
+            }
+            
+            // Fixes for the same lines (e.g. Clippy and AI) would otherwise edit twice
+            let fix_count = modifications.len();
+            summary.count("fixes", fix_count);
+            let coalesced = coalesce_modifications(modifications);
+            modifications = coalesced.modifications;
+            
+            if modifications.len() < fix_count {
+                info!("Coalesced {} fixes into {} modifications", fix_count, modifications.len());
+            }
+            
+            if !coalesced.conflicts.is_empty() {
+                for conflict in &coalesced.conflicts {
+                    eprintln!(
+                        "{}:{}-{}\n<<<<<<< applied fix\n{}=======\n{}>>>>>>> conflicting fix",
+                        conflict.file_path.display(),
+                        conflict.line_start,
+                        conflict.line_end,
+                        conflict.current_text,
+                        conflict.modified_text
+                    );
+                }
+                
+                let conflicts_json = serde_json::to_string_pretty(&coalesced.conflicts)
+                    .context("Failed to serialize conflicting fixes")?;
+                fs::write(conflicts_file, conflicts_json)
+                    .context(format!("Failed to write conflicting fixes to {}", conflicts_file.display()))?;
+                
+                warn!(
+                    "{} conflicting edits were left out; resolve them manually from {}",
+                    coalesced.conflicts.len(),
+                    conflicts_file.display()
+                );
+            }
+            
+            if *merge {
+                let mut rebased = Vec::with_capacity(modifications.len());
+                let mut conflict_count = 0;
+                
+                for modification in &modifications {
+                    let outcome = rebase_modification(modification)
+                        .context(format!("Failed to merge fix for {}", modification.file_path.display()))?;
+                    
+                    for conflict in &outcome.conflicts {
+                        warn!(
+                            "Conflict in {} at lines {}-{}, keeping the current content",
+                            conflict.file_path.display(),
+                            conflict.line_start,
+                            conflict.line_end
+                        );
+                        eprintln!("<<<<<<< current\n{}=======\n{}>>>>>>> fix", conflict.current_text, conflict.modified_text);
+                    }
+                    
+                    conflict_count += outcome.conflicts.len();
+                    rebased.push(outcome.modification);
+                }
+                
+                if conflict_count > 0 {
+                    warn!("{} conflicting changes were not applied", conflict_count);
+                }
+                
+                modifications = rebased;
+            }
+            
+            if let Some(min_confidence) = min_confidence {
+                let (confident, pending) = partition_by_confidence(modifications, *min_confidence);
+                modifications = confident;
+                
+                let mut unfiled = Vec::new();
+                
+                if *file_issues && !pending.is_empty() {
+                    let github_config = config.github_repo.as_ref()
+                        .context("GitHub configuration not found in config file")?;
+                    
+                    let github = GithubClient::from_repo(github_config)
+                        .context("Failed to create GitHub client")?;
+                    
+                    for modification in pending {
+                        match github.create_pending_fix_issue(&modification, project_path).await {
+                            Ok(number) => info!(
+                                "Filed issue #{} for {}",
+                                number,
+                                modification.file_path.display()
+                            ),
+                            Err(e) => {
+                                warn!(
+                                    "Failed to file issue for {}: {}",
+                                    modification.file_path.display(),
+                                    e
+                                );
+                                unfiled.push(modification);
+                            }
+                        }
+                    }
+                } else {
+                    unfiled = pending;
+                }
+                
+                if !unfiled.is_empty() {
+                    let pending_json = serde_json::to_string_pretty(&unfiled)
+                        .context("Failed to serialize pending fixes")?;
+                    fs::write(pending_file, pending_json)
+                        .context(format!("Failed to write pending fixes to {}", pending_file.display()))?;
+                    
+                    info!("{} low-confidence fixes written to {}", unfiled.len(), pending_file.display());
+                }
+            }
+            
+            if *interactive {
+                let outcome = cli::review_modifications(&modifications)
+                    .context("Interactive review failed")?;
+                
+                let accepted_hunks = outcome.decisions.iter()
+                    .filter(|d| d.decision != HunkDecision::Skip)
+                    .count();
+                info!("Accepted {}/{} hunks", accepted_hunks, outcome.decisions.len());
+                
+                let feedback = review_feedback(&modifications, &outcome.decisions);
+                if let Err(e) = FeedbackLog::open(project_path).record(&feedback) {
+                    warn!("Failed to record fix feedback: {}", e);
+                }
+                record_feedback_history(project_path, &feedback).await;
+                
+                if !outcome.skipped.is_empty() {
+                    let skipped_json = serde_json::to_string_pretty(&outcome.skipped)
+                        .context("Failed to serialize skipped fixes")?;
+                    fs::write(skipped_file, skipped_json)
+                        .context(format!("Failed to write skipped fixes to {}", skipped_file.display()))?;
+                    
+                    info!("{} skipped fixes exported to {}", outcome.skipped.len(), skipped_file.display());
+                }
+                
+                modifications = outcome.accepted;
+            }
+            
+            if *dry_run {
+                let patch = create_patch(&modifications, project_path)
+                    .context("Failed to create patch")?;
+                
+                if patch_file.as_os_str() == "-" {
+                    print!("{}", patch);
+                    info!("Dry run: {} modifications written to stdout", modifications.len());
+                } else {
+                    fs::write(patch_file, &patch)
+                        .context(format!("Failed to write patch to {}", patch_file.display()))?;
+                    
+                    info!(
+                        "Dry run: {} modifications written to {} (apply with `git apply {}`)",
+                        modifications.len(),
+                        patch_file.display(),
+                        patch_file.display()
+                    );
+                }
+            } else if *index {
+                let changes = stage_modifications(&modifications)
+                    .context("Failed to stage modifications")?;
+                
+                println!("{}", create_change_report(&changes));
+                
+                info!("Staged {} changes; the working tree was left untouched", changes.len());
+            } else {
+                // Verification rolls back through the backups, so always create them
+                let apply_summary = if *keep_going {
+                    apply_modifications_keep_going(&modifications, *backup || *verify)
+                } else {
+                    match apply_modifications(&modifications, *backup || *verify) {
+                        Ok(applied) => ApplySummary { applied, ..Default::default() },
+                        // A fix that no longer matches its file is an invalid fix, not a tool error
+                        Err(e @ RustAiToolError::Modification(_)) => {
+                            error!("Failed to apply modifications: {}", e);
+                            summary.count("applied", 0).fail(RunStatus::InvalidFixes);
+                            return Ok(());
+                        }
+                        Err(e) => return Err(e).context("Failed to apply modifications"),
+                    }
+                };
+                let mut changes = apply_summary.applied;
+                
+                let verification = if *verify {
+                    let verification = verify_changes(&mut changes, project_path)
+                        .context("Failed to verify applied changes")?;
+                    
+                    if !verification.formatted.is_empty() {
+                        info!("Reformatted {} files with rustfmt", verification.formatted.len());
+                    }
+                    
+                    Some(verification)
+                } else {
+                    None
+                };
+                
+                let journal = Journal::open(project_path);
+                let entries = journal.record(&changes)
+                    .context("Failed to record changes in the journal")?;
+                
+                if let Some(verification) = verification {
+                    if !verification.compiles {
+                        for error in &verification.errors {
+                            eprintln!("{}", error);
+                        }
+                        
+                        let ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+                        let reverted = journal.undo(&ids, true)
+                            .context("Failed to roll back changes")?;
+                        
+                        error!(
+                            "Project no longer compiles after applying fixes; rolled back {} files",
+                            reverted.len()
+                        );
+                        summary.count("applied", 0).count("rolled_back", reverted.len()).fail(RunStatus::InvalidFixes);
+                        return Ok(());
+                    }
+                    
+                    info!("Project compiles after applying fixes");
+                }
+                
+                let report = create_change_report(&changes);
+                println!("{}", report);
+                
+                for skipped in &apply_summary.skipped {
+                    info!("Skipped {}: {}", skipped.file_path.display(), skipped.reason);
+                }
+                for failed in &apply_summary.failed {
+                    error!("Failed {}: {}", failed.file_path.display(), failed.reason);
+                }
+                
+                summary.count("applied", changes.len())
+                    .count("skipped", apply_summary.skipped.len())
+                    .count("failed", apply_summary.failed.len());
+                
+                if !apply_summary.failed.is_empty() {
+                    error!(
+                        "{} of {} modifications failed to apply",
+                        apply_summary.failed.len(),
+                        modifications.len()
+                    );
+                    summary.fail(RunStatus::InvalidFixes);
+                    return Ok(());
+                }
+                
+                info!("Successfully applied {} changes", changes.len());
+            }
+        }
+        Commands::Generate {
+            description,
             output,
+            name,
+            template,
+            template_dirs,
+            license,
+            allow_hooks,
+            pre_commit,
+            devcontainer,
+            repair_iterations,
+            no_verify,
+        } => {
+            info!(
+                "Generating project '{}' at {} from description",
+                name,
+                output.display()
+            );
+            
+            if !output.exists() {
+                fs::create_dir_all(output)
+                    .context(format!("Failed to create output directory: {}", output.display()))?;
+            }
+            
+            let project_path = generate_project_from_description(
+                description,
+                output,
+                name,
+                &config.ai_model,
+                &TemplateOptions {
+                    template: template.clone(),
+                    template_dirs: template_dirs.clone(),
+                    license: license.clone(),
+                    dependency_presets: config.dependency_presets.clone(),
+                    allow_hooks: *allow_hooks,
+                    pre_commit: *pre_commit,
+                    devcontainer: *devcontainer,
+                    repair_iterations: (!*no_verify).then_some(*repair_iterations),
+                }
+            ).await.context("Failed to generate project")?;
+            
+            info!("Project generated at {}", project_path.display());
+        }
+        Commands::GitHub { command } => match command {
+            GitHubCommands::CreatePr {
+                owner,
+                repo,
+                branch,
+                title,
+                fixes,
+                project_path,
+                inline_comments,
+                pr_options,
+                batches,
+            } => {
+                info!(
+                    "Creating PR for {}/{} on branch {} with title: {}",
+                    owner, repo, branch, title
+                );
+                
+                let modifications = load_modifications(fixes, project_path)
+                    .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+                let planned = batches.plan(modifications, project_path, branch, title)?;
+                if batches.plan_only {
+                    print_batches(&planned);
+                    return Ok(());
+                }
+                
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let github = GithubClient::new(&github_config.access_token, owner, repo)
+                    .context("Failed to create GitHub client")?
+                    .with_ssh(config.ssh.clone());
+                
+                for (branch, title, modifications) in &planned {
+                    let mut options = pr_options.to_options(project_path, modifications)?;
+                    options.linked_issues = issues_resolved_by(&github, modifications, project_path)
+                        .await
+                        .context("Failed to look up the issues the fixes resolve")?;
+                    
+                    let pr = publish_fixes(&github, modifications, project_path, branch, title, &options, *inline_comments)
+                        .await
+                        .context(format!("Failed to create pull request from {}", branch))?;
+                    
+                    println!("Pull request created successfully: {}", pr.url);
+                    notifications::record_pull_request(&pr.url);
+                }
+            }
+            GitHubCommands::UpdatePr {
+                owner,
+                repo,
+                branch,
+                title,
+                fixes,
+                project_path,
+                pr_options,
+            } => {
+                info!("Updating the PR of {}/{} from branch {}", owner, repo, branch);
+                
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let github = GithubClient::new(&github_config.access_token, owner, repo)
+                    .context("Failed to create GitHub client")?
+                    .with_ssh(config.ssh.clone());
+                
+                let modifications = load_modifications(fixes, project_path)
+                    .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+                
+                let mut options = pr_options.to_options(project_path, &modifications)?;
+                options.linked_issues = issues_resolved_by(&github, &modifications, project_path)
+                    .await
+                    .context("Failed to look up the issues the fixes resolve")?;
+                
+                let pr = update_fixes(&github, &modifications, project_path, branch, title, &options)
+                    .await
+                    .context("Failed to update pull request")?;
+                
+                println!("Pull request #{} is up to date: {}", pr.number, pr.url);
+                notifications::record_pull_request(&pr.url);
+            }
+            GitHubCommands::CreateIssues { owner, repo, from, group_by, project_path, labels, dry_run } => {
+                let grouping = match group_by.to_lowercase().as_str() {
+                    "file" => IssueGrouping::File,
+                    "category" => IssueGrouping::Category,
+                    "owner" => IssueGrouping::Owner,
+                    _ => anyhow::bail!("Unknown grouping: {} (expected file, category or owner)", group_by),
+                };
+                
+                let content = fs::read_to_string(from)
+                    .context(format!("Failed to read analysis results: {}", from.display()))?;
+                let mut results: Vec<AnalysisResult> = serde_json::from_str(&content)
+                    .context("Failed to parse analysis results")?;
+                
+                // Owners follow the current CODEOWNERS, which may have changed since the analysis
+                assign_owners(project_path, &mut results)
+                    .context("Failed to read the code owners")?;
+                
+                let drafts = group_issues(&results, project_path, grouping);
+                if drafts.is_empty() {
+                    println!("No issues to file");
+                    return Ok(());
+                }
+                
+                if *dry_run {
+                    for draft in &drafts {
+                        println!("{}", draft.title);
+                    }
+                    println!("{} issues would be filed (dry run)", drafts.len());
+                    return Ok(());
+                }
+                
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let github = GithubClient::new(&github_config.access_token, owner, repo)
+                    .context("Failed to create GitHub client")?
+                    .with_ssh(config.ssh.clone());
+                
+                let filed = file_issues(&github, &drafts, labels)
+                    .await
+                    .context("Failed to file issues")?;
+                
+                for (key, number) in &filed.created {
+                    println!("Filed issue #{} for {}", number, key);
+                }
+                if !filed.skipped.is_empty() {
+                    println!("Skipped {} groups that already have an open issue", filed.skipped.len());
+                }
+            }
+            GitHubCommands::Feedback { owner, repo, pr, fixes, project_path } => {
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let github = GithubClient::new(&github_config.access_token, owner, repo)
+                    .context("Failed to create GitHub client")?
+                    .with_ssh(config.ssh.clone());
+                
+                let pr_info = github.get_pull_request(*pr).await
+                    .context("Failed to get pull request")?;
+                
+                if pr_info.state == "open" {
+                    anyhow::bail!("Pull request #{} is still open; record its feedback once it is merged or closed", pr);
+                }
+                
+                let modifications = load_modifications(fixes, project_path)
+                    .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+                
+                let mut merged_contents = Vec::with_capacity(modifications.len());
+                if pr_info.is_merged {
+                    let repo_info = github.get_repo_info().await
+                        .context("Failed to get repository information")?;
+                    
+                    for modification in &modifications {
+                        let rel_path: PathBuf = modification.file_path.strip_prefix(project_path)
+                            .unwrap_or(&modification.file_path)
+                            .components()
+                            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+                            .collect();
+                        let content = github.get_file_content(&rel_path.to_string_lossy(), Some(&repo_info.default_branch)).await
+                            .context(format!("Failed to read {} after the merge", rel_path.display()))?;
+                        merged_contents.push(Some(content));
+                    }
+                } else {
+                    merged_contents.resize(modifications.len(), None);
+                }
+                
+                let entries = pull_request_feedback(&modifications, &merged_contents, *pr);
+                FeedbackLog::open(project_path).record(&entries)
+                    .context("Failed to record fix feedback")?;
+                record_feedback_history(project_path, &entries).await;
+                
+                info!(
+                    "Recorded {} hunk outcomes from pull request #{} ({})",
+                    entries.len(),
+                    pr,
+                    if pr_info.is_merged { "merged" } else { "closed" }
+                );
+            }
+            GitHubCommands::AnalyzeOrg {
+                org,
+                language,
+                topic,
+                include_archived,
+                include_forks,
+                concurrency,
+                resume,
+                output,
+                file,
+            } => {
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                let checkpoint = Checkpoint::open(Path::new("."), &format!("analyze-org-{}", org), *resume)
+                    .context("Failed to open the checkpoint")?;
+                
+                let filter = RepoFilter {
+                    language: language.clone(),
+                    topic: topic.clone(),
+                    include_archived: *include_archived,
+                    include_forks: *include_forks,
+                };
+                
+                let reports = analyze_org(
+                    &github_config.access_token,
+                    org,
+                    &filter,
+                    &config.analysis_options,
+                    *concurrency,
+                    config.ssh.as_ref(),
+                    Some(&checkpoint),
+                ).await.context(format!("Failed to analyze organization {}; run again with --resume to skip the analyzed repositories", org))?;
+                checkpoint.finish().context("Failed to remove the checkpoint")?;
+                
+                let output_content = match output.to_lowercase().as_str() {
+                    "json" => serde_json::to_string_pretty(&reports)
+                        .context("Failed to serialize organization report to JSON")?,
+                    "markdown" => format_org_report(org, &reports),
+                    _ => anyhow::bail!("Unsupported output format: {} (expected markdown or json)", output),
+                };
+                
+                match file {
+                    Some(path) => {
+                        fs::write(path, output_content)
+                            .context(format!("Failed to write report to {}", path.display()))?;
+                        info!("Report of {} repositories written to {}", reports.len(), path.display());
+                    }
+                    None => println!("{}", output_content),
+                }
+            }
+            GitHubCommands::Analyze {
+                owner,
+                repo,
+                branch,
+                path,
+                pr,
+            } => {
+                info!("Analyzing GitHub repository {}/{} on branch {}", owner, repo, branch);
+                
+                if let Some(path) = path {
+                    if !path.components().all(|c| matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir)) {
+                        anyhow::bail!("--path must be a relative path inside the repository: {}", path.display());
+                    }
+                }
+                
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let github = GithubClient::new(&github_config.access_token, owner, repo)
+                    .context("Failed to create GitHub client")?
+                    .with_ssh(config.ssh.clone());
+                
+                if let Some(pr) = pr {
+                    let (_, comment) = review_pull_request_delta(&github, *pr, path.as_deref(), &config.analysis_options)
+                        .await
+                        .context(format!("Failed to analyze pull request #{}", pr))?;
+                    
+                    println!("{}", comment);
+                    return Ok(());
+                }
+                
+                // Clone the repository to a temporary directory
+                let temp_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+                let repo_path = github.clone_repo(Some(branch), temp_dir.path()).await
+                    .context("Failed to clone repository")?;
+                
+                // Run analysis, on the subdirectory only if one is given
+                let analyzed_path = match path {
+                    Some(path) => repo_path.join(path),
+                    None => repo_path.clone(),
+                };
+                if !analyzed_path.is_dir() {
+                    anyhow::bail!("Directory not found in {}/{}: {}", owner, repo, analyzed_path.strip_prefix(&repo_path).unwrap_or(&analyzed_path).display());
+                }
+                
+                let analysis_options = checkout_options(&config.analysis_options, &analyzed_path, &repo_path);
+                let results = analyze_project(&analyzed_path, &analysis_options)
+                    .context("Failed to analyze project")?;
+                
+                // Output results
+                let output_content = format_analysis_results(&results, "markdown")?;
+                println!("{}", output_content);
+                
+                info!("GitHub repository analysis complete");
+            }
+        },
+        Commands::Gitea { owner, repo, command } => {
+            let gitea_config = config.gitea_repo.as_ref()
+                .context("Gitea configuration not found in config file")?;
+            
+            let gitea = GiteaClient::new(
+                &gitea_config.url,
+                &gitea_config.access_token,
+                owner.as_deref().unwrap_or(&gitea_config.owner),
+                repo.as_deref().unwrap_or(&gitea_config.name),
+            ).context("Failed to create Gitea client")?
+                .with_ssh(config.ssh.clone());
+            
+            run_forge_command(&gitea, command).await?;
+        }
+        Commands::Bitbucket { workspace, repo, command } => {
+            let bitbucket_config = config.bitbucket_repo.as_ref()
+                .context("Bitbucket configuration not found in config file")?;
+            
+            let bitbucket = BitbucketClient::new(
+                workspace.as_deref().unwrap_or(&bitbucket_config.workspace),
+                repo.as_deref().unwrap_or(&bitbucket_config.repo_slug),
+                bitbucket_config.username.as_deref(),
+                &bitbucket_config.access_token,
+            ).context("Failed to create Bitbucket client")?
+                .with_ssh(config.ssh.clone());
+            
+            run_forge_command(&bitbucket, command).await?;
+        }
+        Commands::Serve { github_webhook, daemon, listen, webhook_secret } => {
+            if *daemon {
+                let ai_model = config.ai_model.clone();
+                let ai_client: Option<ClientFactory> = if config.offline {
+                    None
+                } else {
+                    Some(Arc::new(move |path: &Path| {
+                        create_project_ai_client(&ai_model, path)
+                            .map_err(|e| RustAiToolError::AiModel(format!("{:#}", e)))
+                    }))
+                };
+                
+                let options = DaemonOptions {
+                    analysis_options: config.analysis_options.clone(),
+                    validation_options: config.validation_options.clone(),
+                    fix_concurrency: config.ai_model.retry.max_concurrent_requests,
+                    ai_client,
+                };
+                
+                serve_daemon(*listen, options).await.context("Daemon failed")?;
+            } else if *github_webhook {
+                let github_config = config.github_repo.as_ref()
+                    .context("GitHub configuration not found in config file")?;
+                
+                let options = WebhookOptions {
+                    secret: webhook_secret.clone().or_else(|| github_config.webhook_secret.clone()),
+                    access_token: github_config.access_token.clone(),
+                    analysis_options: config.analysis_options.clone(),
+                    ssh: config.ssh.clone(),
+                    notifications: config.notifications.clone(),
+                };
+                
+                serve(*listen, options).await.context("Webhook server failed")?;
+            } else {
+                anyhow::bail!("Nothing to serve; use --daemon for the JSON-RPC API or --github-webhook to run the pull request review bot");
+            }
+        }
+        Commands::Demo { output, force } => {
+            info!("Running demo in {}", output.display());
+            
+            let project_dir = demo::unpack_demo_project(output, *force)
+                .context("Failed to unpack demo project")?;
+            
+            let mut demo_config = create_default_config();
+            demo_config.project_path = project_dir.clone();
+            demo_config.analysis_options.custom_rules = demo::demo_rules();
+            
+            let config_content = toml::to_string_pretty(&demo_config)
+                .context("Failed to serialize configuration")?;
+            fs::write(project_dir.join(".rust-ai-tool.toml"), config_content)
+                .context("Failed to write demo configuration")?;
+            
+            println!("== Step 1/4: Analyze {} ==\n", project_dir.display());
+            
+            let results = analyze_project(&project_dir, &demo_config.analysis_options)
+                .context("Failed to analyze demo project")?;
+            let issues_before: usize = results.iter().map(|r| r.issues.len()).sum();
+            
+            println!("{}", format_analysis_results(&results, "console")?);
+            
+            println!("== Step 2/4: Propose fixes (canned AI responses) ==\n");
+            
+            let modifications = demo::demo_modifications(&project_dir)
+                .context("Failed to load demo fixes")?;
+            
+            let fixes_path = project_dir.join("fixes.json");
+            fs::write(&fixes_path, serde_json::to_string_pretty(&modifications)?)
+                .context(format!("Failed to write fixes to {}", fixes_path.display()))?;
+            
+            for modification in &modifications {
+                println!(
+                    "- {}: {} (confidence {}%)",
+                    modification.file_path.strip_prefix(&project_dir).unwrap_or(&modification.file_path).display(),
+                    modification.description,
+                    modification.confidence
+                );
+            }
+            println!("\nFixes written to {}\n", fixes_path.display());
+            
+            println!("== Step 3/4: Validate ==\n");
+            
+            let fixes_to_validate: Vec<FixToValidate> = modifications.iter()
+                .map(|m| FixToValidate {
+                    file_path: m.file_path.clone(),
+                    original_code: m.original_content.clone(),
+                    modified_code: m.modified_content.clone(),
+                    description: m.description.clone(),
+                })
+                .collect();
+            
+            let validation_results = validate_fixes(&fixes_to_validate, &demo_config.validation_options)
+                .context("Failed to validate fixes")?;
+            
+            for result in &validation_results {
+                println!(
+                    "- {}: {}",
+                    result.file_path.strip_prefix(&project_dir).unwrap_or(&result.file_path).display(),
+                    if result.is_valid { "VALID" } else { "INVALID" }
+                );
+                for msg in result.messages.iter().filter(|m| m.message_type != validation::ValidationMessageType::Info) {
+                    println!("    {}: {}", msg.message_type, msg.text);
+                }
+            }
+            println!();
+            
+            println!("== Step 4/4: Apply ==\n");
+            
+            let changes = apply_validated_fixes(&modifications, &validation_results, true)
+                .context("Failed to apply fixes")?;
+            println!("{}", create_change_report(&changes));
+            
+            let results_after = analyze_project(&project_dir, &demo_config.analysis_options)
+                .context("Failed to analyze demo project")?;
+            let issues_after: usize = results_after.iter().map(|r| r.issues.len()).sum();
+            
+            println!("Issues before: {}, after: {}", issues_before, issues_after);
+            println!(
+                "The demo project is in {}; try `rust-ai-tool analyze {}` next.",
+                project_dir.display(),
+                project_dir.display()
+            );
+        }
+        Commands::ExtractFunction {
             file,
+            lines,
+            name,
+            output,
         } => {
-            info!("Analyzing project at {}", project_path.display());
+            let has_ai_model = match config.ai_model.model_type {
+                AiModelType::Local(_) | AiModelType::OpenAiCompatible(_) => true,
+                _ => !config.ai_model.api_key.is_empty(),
+            };
             
-            let results = analyze_project(project_path, &config.analysis_options)
-                .context("Failed to analyze project")?;
+            let ai_client = if has_ai_model {
+                Some(create_project_ai_client(&config.ai_model, file)?)
+            } else {
+                None
+            };
             
-            let output_content = format_analysis_results(&results, output)?;
+            let modification = extract_function(file, lines.clone(), name, ai_client.as_ref())
+                .await
+                .context("Failed to extract function")?;
             
-            if let Some(output_file) = file {
-                fs::write(output_file, &output_content)
-                    .context(format!("Failed to write output to {}", output_file.display()))?;
+            let fixes_json = serde_json::to_string_pretty(&vec![modification])
+                .context("Failed to serialize fix")?;
+            fs::write(output, fixes_json)
+                .context(format!("Failed to write fix to {}", output.display()))?;
+            
+            info!("Extraction written to {} (apply with `rust-ai-tool apply --fixes {}`)", output.display(), output.display());
+        }
+        Commands::OrganizeImports { project_path, output } => {
+            let modifications = organize_imports(project_path)
+                .context("Failed to organize imports")?;
+            
+            if modifications.is_empty() {
+                info!("Imports are already organized");
+                return Ok(());
+            }
+            
+            if let Some(output) = output {
+                let fixes_json = serde_json::to_string_pretty(&modifications)
+                    .context("Failed to serialize fixes")?;
+                fs::write(output, fixes_json)
+                    .context(format!("Failed to write fixes to {}", output.display()))?;
                 
-                info!("Analysis results written to {}", output_file.display());
-            } else {
-                println!("{}", output_content);
+                info!("Import changes for {} files written to {}", modifications.len(), output.display());
+                return Ok(());
             }
             
-            info!("Analysis complete");
+            let mut session = EditSession::new(project_path.clone()).with_backup(true);
+            for modification in modifications {
+                session.add_modification(modification);
+            }
+            
+            let changes = session.commit()
+                .context("Failed to apply modifications")?;
+            Journal::open(project_path).record(&changes)
+                .context("Failed to record changes in the journal")?;
+            
+            println!("{}", create_change_report(&changes));
+            
+            info!("Organized imports in {} files", changes.len());
+        }
+        Commands::UpdateDeps { project_path, output, summarize } => {
+            let registry = CratesIoClient::new()?;
+            let mut updates = find_updates(project_path, &registry)
+                .await
+                .context("Failed to check for dependency updates")?;
+            
+            summary.count("updates", updates.len());
+            if updates.is_empty() {
+                info!("Dependencies are up to date");
+                return Ok(());
+            }
+            summary.fail(RunStatus::IssuesFound);
+            
+            if *summarize {
+                let client = create_project_ai_client(&config.ai_model, project_path)?;
+                let summarized = summarize_breaking_changes(&mut updates, &registry, &client).await;
+                
+                info!("Summarized the breaking changes of {} updates", summarized);
+            }
+            
+            for update in &updates {
+                println!("{}", update);
+                if let Some(breaking_changes) = &update.breaking_changes {
+                    println!("{}\n", breaking_changes);
+                }
+            }
+            
+            let modifications = update_modifications(project_path, &updates)?;
+            if modifications.is_empty() {
+                info!("No requirement needs to change; run `cargo update` to use the latest compatible versions");
+                return Ok(());
+            }
+            
+            let fixes_json = serde_json::to_string_pretty(&modifications)
+                .context("Failed to serialize fixes")?;
+            fs::write(output, fixes_json)
+                .context(format!("Failed to write fixes to {}", output.display()))?;
+            
+            info!("{} manifest updates written to {}", modifications.len(), output.display());
         }
-        Commands::Validate { project_path, fixes } => {
+        Commands::Fix { project_path, output, max_refinements, jobs, with_tests, resume, .. } => {
+            info!("Fixing issues in project at {}", project_path.display());
+            
+            let checkpoint = Arc::new(Checkpoint::open(project_path, "fix", *resume)
+                .context("Failed to open the checkpoint")?);
+            let analysis_key = project_key(project_path, &format!("{:?}", config.analysis_options))
+                .context("Failed to hash the project")?;
+            let results = match checkpoint.completed::<Vec<AnalysisResult>>(ANALYSIS_STAGE, &analysis_key) {
+                Some(results) => {
+                    info!("Reusing the checkpointed analysis");
+                    results
+                }
+                None => {
+                    let results = analyze_project(project_path, &config.analysis_options)
+                        .context("Failed to analyze project")?;
+                    checkpoint.record(ANALYSIS_STAGE, &analysis_key, &results)
+                        .context("Failed to record the analysis in the checkpoint")?;
+                    results
+                }
+            };
+            notifications::record_results(project_path, &results);
+            
+            let client = create_project_ai_client(&config.ai_model, project_path)?;
+            let options = FixPipelineOptions {
+                max_refinements: *max_refinements,
+                validation: config.validation_options.clone(),
+                project_path: project_path.clone(),
+                concurrency: jobs.unwrap_or(config.ai_model.retry.max_concurrent_requests),
+                generate_tests: *with_tests,
+                checkpoint: Some(checkpoint.clone()),
+            };
+            
+            let report = run_fix_pipeline(&results, &client, &options)
+                .await
+                .context("Failed to generate fixes; run again with --resume to keep the completed ones")?;
+            
+            let recorded = match HistoryDb::open(project_path).await {
+                Ok(history) => match history.record_analysis("fix", &results).await {
+                    Ok(run_id) => history.record_fixes(run_id, &report).await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = recorded {
+                warn!("Failed to record the fixes in the history: {}", e);
+            }
+            
+            summary.count("fixes", report.fixes.len()).count("failed", report.failed.len());
+            if !report.failed.is_empty() {
+                summary.fail(RunStatus::InvalidFixes);
+            }
+            
+            for failed in &report.failed {
+                warn!(
+                    "No valid fix for {} after {} attempts: {}",
+                    failed.file_path.display(),
+                    failed.attempts,
+                    failed.errors.join("; ")
+                );
+                notifications::record_failure(format!("{}: no valid fix after {} attempts", failed.file_path.display(), failed.attempts));
+            }
+            
+            let fixes_json = serde_json::to_string_pretty(&report.fixes)
+                .context("Failed to serialize fixes")?;
+            fs::write(output, fixes_json)
+                .context(format!("Failed to write fixes to {}", output.display()))?;
+            
+            checkpoint.finish().context("Failed to remove the checkpoint")?;
+            
             info!(
-                "Validating fixes for project at {} using {}",
-                project_path.display(),
-                fixes.display()
+                "{} validated fixes written to {} (apply with `rust-ai-tool apply --fixes {}`)",
+                report.fixes.len(),
+                output.display(),
+                output.display()
             );
+        }
+        Commands::Batch { repos, jobs, resume, output, file } => {
+            let mut manifest = load_manifest(repos)
+                .context(format!("Failed to read repository manifest {}", repos.display()))?;
+            if let Some(jobs) = jobs {
+                manifest.concurrency = *jobs;
+            }
+            if manifest.needs_network() {
+                config.ensure_online("batch")?;
+            }
             
-            let fixes_content = fs::read_to_string(fixes)
-                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            let ai_model = config.ai_model.clone();
+            let ai_client: Option<ClientFactory> = if config.offline {
+                None
+            } else {
+                Some(Arc::new(move |path: &Path| {
+                    create_project_ai_client(&ai_model, path)
+                        .map_err(|e| RustAiToolError::AiModel(format!("{:#}", e)))
+                }))
+            };
+            let options = BatchOptions {
+                analysis_options: config.analysis_options.clone(),
+                validation_options: config.validation_options.clone(),
+                fix_concurrency: config.ai_model.retry.max_concurrent_requests,
+                access_token: config.github_repo.as_ref().map(|github| github.access_token.clone()),
+                ssh: config.ssh.clone(),
+                ai_client,
+            };
             
-            let fixes_to_validate: Vec<FixToValidate> = serde_json::from_str(&fixes_content)
-                .context("Failed to parse fixes JSON")?;
+            let run = format!("batch-{}", repos.file_stem().unwrap_or_default().to_string_lossy());
+            let checkpoint = Checkpoint::open(Path::new("."), &run, *resume)
+                .context("Failed to open the checkpoint")?;
+            let reports = run_batch(&manifest, &options, Some(&checkpoint))
+                .await
+                .context("Failed to run the batch; run again with --resume to skip the completed repositories")?;
+            checkpoint.finish().context("Failed to remove the checkpoint")?;
             
-            let validation_results = validate_fixes(&fixes_to_validate, &config.validation_options)
-                .context("Failed to validate fixes")?;
+            let failed = reports.iter().filter(|report| report.error.is_some()).count();
+            summary.count("repositories", reports.len())
+                .count("issues", reports.iter().map(|report| report.issues).sum())
+                .count("fixes", reports.iter().map(|report| report.fixes).sum())
+                .count("failed", failed);
+            if failed > 0 {
+                summary.fail(RunStatus::Error);
+            } else if reports.iter().any(|report| report.issues > 0) {
+                summary.fail(RunStatus::IssuesFound);
+            }
+            for url in reports.iter().filter_map(|report| report.pull_request.as_ref()) {
+                notifications::record_pull_request(url);
+            }
             
-            let valid_count = validation_results.iter().filter(|r| r.is_valid).count();
-            let total_count = validation_results.len();
+            let output_content = match output.to_lowercase().as_str() {
+                "json" => serde_json::to_string_pretty(&reports)
+                    .context("Failed to serialize batch report to JSON")?,
+                "markdown" => format_batch_report(&reports),
+                _ => anyhow::bail!("Unsupported output format: {} (expected markdown or json)", output),
+            };
             
-            println!("Validation complete: {}/{} fixes are valid", valid_count, total_count);
+            match file {
+                Some(path) => {
+                    fs::write(path, output_content)
+                        .context(format!("Failed to write report to {}", path.display()))?;
+                    info!("Report of {} repositories written to {}", reports.len(), path.display());
+                }
+                None => println!("{}", output_content),
+            }
+        }
+        Commands::Explain { location, issue_id, project_path, context_lines } => {
+            let results = analyze_project(project_path, &config.analysis_options)
+                .context("Failed to analyze project")?;
+            let issues = results.iter().flat_map(|r| &r.issues);
             
-            for (i, result) in validation_results.iter().enumerate() {
-                if !result.is_valid {
-                    println!("Fix #{} for {} is invalid:", i + 1, result.file_path.display());
-                    for msg in &result.messages {
-                        println!("  - {}: {}", msg.message_type, msg.text);
-                    }
+            let matching: Vec<&CodeIssue> = match (location, issue_id) {
+                (Some((file, line)), _) => {
+                    let file = project_path.join(file).canonicalize()
+                        .context(format!("Failed to find {} in {}", file.display(), project_path.display()))?;
+                    issues
+                        .filter(|issue| issue.file_path.canonicalize().is_ok_and(|path| path == file))
+                        .filter(|issue| issue.line_start <= *line && *line <= issue.line_end)
+                        .collect()
                 }
+                (None, Some(id)) => issues.filter(|issue| issue.id() == *id).collect(),
+                (None, None) => Vec::new(),
+            };
+            
+            if matching.is_empty() {
+                anyhow::bail!("No issue found there; run `rust-ai-tool analyze` to list the issues and their ids");
+            }
+            
+            let client = create_project_ai_client(&config.ai_model, project_path)?;
+            
+            for issue in matching {
+                let excerpt = issue_excerpt(issue, *context_lines)?;
+                let explanation = client.explain_issue(issue, &excerpt)
+                    .await
+                    .context("Failed to explain issue")?;
+                
+                println!("{}:{} [{}] {}\n", issue.file_path.display(), issue.line_start, issue.id(), issue.message);
+                println!("{}\n", explanation.trim());
             }
         }
-        Commands::Apply {
+        Commands::Chat { project_path, output } => {
+            let client = create_project_ai_client(&config.ai_model, project_path)?;
+            
+            if !client.has_index() {
+                warn!("Project is not indexed; run `rust-ai-tool index` so answers include related definitions");
+            }
+            
+            run_chat(&client, project_path, output, &config.validation_options).await?;
+        }
+        Commands::Index { project_path, query, limit } => {
+            let client = AiModelClient::new(config.ai_model.clone())
+                .context("Failed to create AI model client")?
+                .with_project_root(project_path);
+            let previous = CodeIndex::load(project_path)
+                .context("Failed to load code index")?;
+            
+            if let Some(query) = query {
+                let index = previous.context("Project is not indexed; run `rust-ai-tool index` first")?;
+                let query_vector = client.embed(std::slice::from_ref(query))
+                    .await
+                    .context("Failed to embed query")?
+                    .remove(0);
+                
+                for (score, indexed) in index.search(&query_vector, *limit) {
+                    println!(
+                        "{:.3}  {}:{}-{}  {}",
+                        score,
+                        indexed.chunk.file_path.display(),
+                        indexed.chunk.start_line,
+                        indexed.chunk.end_line,
+                        indexed.chunk.name
+                    );
+                }
+                
+                return Ok(());
+            }
+            
+            let index = build_index(project_path, &client, previous.as_ref())
+                .await
+                .context("Failed to index project")?;
+            index.save(project_path)
+                .context("Failed to save code index")?;
+            
+            info!("Indexed {} chunks to {}", index.chunks.len(), CodeIndex::path(project_path).display());
+        }
+        Commands::Undo {
             project_path,
-            fixes,
-            backup,
+            last,
+            id,
+            force,
+            list,
         } => {
-            info!(
-                "Applying fixes to project at {} using {}",
-                project_path.display(),
-                fixes.display()
-            );
+            let journal = Journal::open(project_path);
+            
+            if *list {
+                for entry in journal.entries().context("Failed to read the journal")? {
+                    println!(
+                        "{}{}  {}  {}",
+                        entry.id,
+                        if entry.undone { " (undone)" } else { "" },
+                        entry.file_path.display(),
+                        entry.description
+                    );
+                }
+                return Ok(());
+            }
+            
+            let ids = match id {
+                Some(id) => vec![id.clone()],
+                None => journal.last_ids(last.unwrap_or(1))
+                    .context("Failed to read the journal")?,
+            };
+            
+            if ids.is_empty() {
+                info!("Nothing to undo in {}", journal.path().display());
+                return Ok(());
+            }
+            
+            let reverted = journal.undo(&ids, *force)
+                .context("Failed to undo changes")?;
+            
+            for entry in &reverted {
+                println!("Reverted {} ({}): {}", entry.file_path.display(), entry.id, entry.description);
+            }
+            
+            info!("Reverted {} changes", reverted.len());
+        }
+        Commands::History { project_path, limit, files } => {
+            let journal = Journal::open(project_path);
+            let change_sets = journal.change_sets().context("Failed to read the journal")?;
+            
+            if change_sets.is_empty() {
+                info!("No changes recorded in {}", journal.path().display());
+                return Ok(());
+            }
+            
+            for change_set in change_sets.iter().rev().take(limit.unwrap_or(usize::MAX)) {
+                println!(
+                    "{}  {} UTC  {}{}",
+                    change_set.id,
+                    format_timestamp(change_set.timestamp),
+                    change_set.summary(),
+                    if change_set.undone() { " (rolled back)" } else { "" }
+                );
+                
+                if *files {
+                    for entry in &change_set.entries {
+                        println!(
+                            "    {}{}  {}  {}",
+                            entry.id,
+                            if entry.undone { " (undone)" } else { "" },
+                            entry.file_path.display(),
+                            entry.description
+                        );
+                    }
+                }
+            }
+        }
+        Commands::Runs { project_path, limit, output } => {
+            let history = HistoryDb::open(project_path).await
+                .context("Failed to open the history database")?;
+            let runs = history.runs(*limit).await.context("Failed to read the history")?;
+            let feedback = history.feedback_totals().await.context("Failed to read the history")?;
+            
+            if output.eq_ignore_ascii_case("json") {
+                let json = serde_json::json!({ "runs": runs, "feedback": feedback });
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
             
-            if *backup {
-                info!("Creating backup before applying fixes");
+            if runs.is_empty() {
+                info!("No runs recorded yet; `analyze` and `fix` record theirs");
+            }
+            for run in &runs {
+                println!(
+                    "{:>4}  {} UTC  {:<8} {} files, {} issues ({} new), {} errors, {} warnings",
+                    run.id,
+                    format_timestamp(run.timestamp),
+                    run.command,
+                    run.files,
+                    run.issues,
+                    run.new_issues,
+                    run.errors,
+                    run.warnings
+                );
             }
             
-            let fixes_content = fs::read_to_string(fixes)
-                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            let reviewed = feedback.accepted + feedback.edited + feedback.rejected;
+            if let Some(kept) = ((feedback.accepted + feedback.edited) * 100).checked_div(reviewed) {
+                println!(
+                    "\nFix feedback: {} accepted, {} edited, {} rejected ({}% kept)",
+                    feedback.accepted,
+                    feedback.edited,
+                    feedback.rejected,
+                    kept
+                );
+            }
+        }
+        Commands::ExportIssues { tracker, from, project_path, project, labels, dry_run } => {
+            let content = fs::read_to_string(from)
+                .context(format!("Failed to read analysis results: {}", from.display()))?;
+            let results: Vec<AnalysisResult> = serde_json::from_str(&content)
+                .context("Failed to parse analysis results")?;
             
-            let modifications: Vec<CodeModification> = serde_json::from_str(&fixes_content)
-                .context("Failed to parse fixes JSON")?;
+            let client: Box<dyn IssueTracker> = match tracker.to_lowercase().as_str() {
+                "jira" => {
+                    let jira_config = config.jira.as_ref()
+                        .context("Jira configuration not found in config file")?;
+                    let client = JiraClient::new(jira_config).context("Failed to create Jira client")?;
+                    Box::new(match project {
+                        Some(project) => client.with_project(project),
+                        None => client,
+                    })
+                }
+                "linear" => {
+                    let linear_config = config.linear.as_ref()
+                        .context("Linear configuration not found in config file")?;
+                    let client = LinearClient::new(linear_config).context("Failed to create Linear client")?;
+                    Box::new(match project {
+                        Some(team) => client.with_team(team),
+                        None => client,
+                    })
+                }
+                _ => anyhow::bail!("Unknown tracker: {} (expected jira or linear)", tracker),
+            };
             
-            let changes = apply_modifications(&modifications, *backup)
-                .context("Failed to apply modifications")?;
+            let configured_labels = match tracker.to_lowercase().as_str() {
+                "jira" => config.jira.as_ref().map(|jira| jira.labels.clone()),
+                _ => config.linear.as_ref().map(|linear| linear.labels.clone()),
+            };
+            let all_labels: Vec<String> = configured_labels.unwrap_or_default().into_iter().chain(labels.iter().cloned()).collect();
             
-            let report = create_change_report(&changes);
-            println!("{}", report);
+            let issues = tracker_issues(&results, project_path, &all_labels);
+            if issues.is_empty() {
+                println!("No issues to file");
+                return Ok(());
+            }
             
-            info!("Successfully applied {} changes", changes.len());
-        }
-        Commands::Generate {
-            description,
-            output,
-            name,
-        } => {
-            info!(
-                "Generating project '{}' at {} from description",
-                name,
-                output.display()
-            );
+            if *dry_run {
+                for issue in &issues {
+                    println!("{} [{}]", issue.title, issue.labels.join(", "));
+                }
+                println!("{} issues would be filed in {} unless already open (dry run)", issues.len(), client.name());
+                return Ok(());
+            }
             
-            if !output.exists() {
-                fs::create_dir_all(output)
-                    .context(format!("Failed to create output directory: {}", output.display()))?;
+            let exported = export_issues(client.as_ref(), &issues)
+                .await
+                .context(format!("Failed to file issues in {}", client.name()))?;
+            
+            for (title, key) in &exported.created {
+                println!("Filed {} for {}", key, title);
             }
+            if !exported.skipped.is_empty() {
+                println!("Skipped {} issues that already have an open {} issue", exported.skipped.len(), client.name());
+            }
+            summary.count("filed", exported.created.len()).count("skipped", exported.skipped.len());
+        }
+        Commands::Rollback { id, project_path, force } => {
+            let reverted = Journal::open(project_path).rollback(id, *force)
+                .context(format!("Failed to roll back {}", id))?;
             
-            let project_path = generate_project_from_description(
-                description,
-                output,
-                name,
-                &config.ai_model
-            ).await.context("Failed to generate project")?;
+            for entry in &reverted {
+                println!("Restored {} ({}): {}", entry.file_path.display(), entry.id, entry.description);
+            }
             
-            info!("Project generated at {}", project_path.display());
+            info!("Rolled back {} changes", reverted.len());
         }
-        Commands::GitHub { command } => match command {
-            GitHubCommands::CreatePr {
-                owner,
-                repo,
-                branch,
-                title,
-                fixes,
-            } => {
-                info!(
-                    "Creating PR for {}/{} on branch {} with title: {}",
-                    owner, repo, branch, title
-                );
-                
-                let github_config = config.github_repo.as_ref()
-                    .context("GitHub configuration not found in config file")?;
-                
-                let github = GithubClient::new(&github_config.access_token, owner, repo)
-                    .context("Failed to create GitHub client")?;
-                
-                // Get repo info
-                let repo_info = github.get_repo_info().await
-                    .context("Failed to get repository information")?;
-                
-                // Create a new branch if it doesn't exist
-                info!("Creating branch {} from {}", branch, repo_info.default_branch);
-                let _ = github.create_branch(&repo_info.default_branch, branch).await;
+        Commands::Rule { command } => match command {
+            RuleCommands::Test { fixtures, rules, only } => {
+                let mut rules = match rules {
+                    Some(path) => load_rule_pack(path)
+                        .context(format!("Failed to read rule pack {}", path.display()))?,
+                    None => config.analysis_options.custom_rules.clone(),
+                };
                 
-                // Clone the repository to a temporary directory
-                let temp_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
-                let repo_path = github.clone_repo(Some(branch), temp_dir.path()).await
-                    .context("Failed to clone repository")?;
-                
-                // Read fixes
-                let fixes_content = fs::read_to_string(fixes)
-                    .context(format!("Failed to read fixes file: {}", fixes.display()))?;
-                
-                let modifications: Vec<CodeModification> = serde_json::from_str(&fixes_content)
-                    .context("Failed to parse fixes JSON")?;
-                
-                // Apply modifications
-                let changed_files: Vec<PathBuf> = modifications.iter()
-                    .map(|m| {
-                        let rel_path = m.file_path.strip_prefix(project_path).unwrap_or(&m.file_path);
-                        repo_path.join(rel_path)
-                    })
-                    .collect();
-                
-                // Commit and push changes
-                github.commit_changes(
-                    &repo_path,
-                    &changed_files,
-                    &format!("Applied fixes: {}", title),
-                    branch,
-                ).await.context("Failed to commit changes")?;
-                
-                // Create pull request
-                let pr = github.create_pull_request(
-                    title,
-                    &format!("Automatically generated fixes by Rust AI Tool"),
-                    branch,
-                    &repo_info.default_branch,
-                ).await.context("Failed to create pull request")?;
-                
-                println!("Pull request created successfully: {}", pr.url);
-            }
-            GitHubCommands::Analyze {
-                owner,
-                repo,
-                branch,
-            } => {
-                info!("Analyzing GitHub repository {}/{} on branch {}", owner, repo, branch);
+                if !only.is_empty() {
+                    if let Some(unknown) = only.iter().find(|name| !rules.iter().any(|rule| rule.name == **name)) {
+                        anyhow::bail!("Unknown rule: {}", unknown);
+                    }
+                    rules.retain(|rule| only.contains(&rule.name));
+                }
                 
-                let github_config = config.github_repo.as_ref()
-                    .context("GitHub configuration not found in config file")?;
+                if rules.is_empty() {
+                    anyhow::bail!("No custom rules to test; add them to the configuration or pass --rules");
+                }
                 
-                let github = GithubClient::new(&github_config.access_token, owner, repo)
-                    .context("Failed to create GitHub client")?;
+                let report = test_rules(&rules, fixtures).context("Failed to test rules")?;
                 
-                // Clone the repository to a temporary directory
-                let temp_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
-                let repo_path = github.clone_repo(Some(branch), temp_dir.path()).await
-                    .context("Failed to clone repository")?;
+                for line in report.lines.iter().filter(|line| line.outcome != RuleTestOutcome::Matched) {
+                    let problem = match line.outcome {
+                        RuleTestOutcome::Missed => "expected match missing",
+                        _ => "unexpected match",
+                    };
+                    println!("{}:{}: {}: {}", line.file_path.display(), line.line, line.rule, problem);
+                }
                 
-                // Run analysis
-                let results = analyze_project(&repo_path, &config.analysis_options)
-                    .context("Failed to analyze project")?;
+                for rule in &rules {
+                    println!(
+                        "{}: {} matched, {} missed, {} unexpected",
+                        rule.name,
+                        report.count(Some(&rule.name), RuleTestOutcome::Matched),
+                        report.count(Some(&rule.name), RuleTestOutcome::Missed),
+                        report.count(Some(&rule.name), RuleTestOutcome::Unexpected)
+                    );
+                }
                 
-                // Output results
-                let output_content = format_analysis_results(&results, "markdown")?;
-                println!("{}", output_content);
+                summary.count("matched", report.count(None, RuleTestOutcome::Matched))
+                    .count("missed", report.count(None, RuleTestOutcome::Missed))
+                    .count("unexpected", report.count(None, RuleTestOutcome::Unexpected));
                 
-                info!("GitHub repository analysis complete");
+                if !report.passed() {
+                    error!(
+                        "Rule test failed: {} missed and {} unexpected matches",
+                        report.count(None, RuleTestOutcome::Missed),
+                        report.count(None, RuleTestOutcome::Unexpected)
+                    );
+                    summary.fail(RunStatus::IssuesFound);
+                }
             }
         },
-        Commands::Init { project_path } => {
+        // Handled before the configuration is loaded
+        Commands::Config { .. } | Commands::MigrateConfig { .. } => {}
+        Commands::Init { project_path, force, interactive } => {
             info!("Initializing configuration for project at {}", project_path.display());
             
             let config_path = project_path.join(".rust-ai-tool.toml");
             
-            if config_path.exists() {
+            if config_path.exists() && !*force {
                 warn!("Configuration file already exists at {}", config_path.display());
                 warn!("Use --force to overwrite existing configuration");
                 return Ok(());
             }
             
-            let config = create_default_config();
+            let config = if *interactive {
+                let stdin = std::io::stdin();
+                cli::interactive_config(&mut stdin.lock(), &mut std::io::stdout(), create_default_config())
+                    .context("Failed to read the settings")?
+            } else {
+                create_default_config()
+            };
             let config_content = toml::to_string_pretty(&config)
                 .context("Failed to serialize configuration")?;
             
@@ -403,13 +2883,35 @@ async fn main() -> Result<()> {
             
             info!("Configuration initialized at {}", config_path.display());
         }
+        Commands::External(args) => {
+            let (name, plugin_args) = args.split_first().context("Missing plugin name")?;
+            let name = name.to_string_lossy();
+            let plugin = find_plugin(&name).with_context(|| {
+                format!("No such command: `{}` (and no {}{} on PATH)", name, PLUGIN_PREFIX, name)
+            })?;
+            
+            let context = PluginContext {
+                version: env!("CARGO_PKG_VERSION"),
+                config_path: &cli.config,
+                profile: cli.profile.as_deref(),
+                project_root: find_project_root(Path::new(".")),
+                config: &config,
+            };
+            
+            let code = run_plugin(&plugin, plugin_args, &context)
+                .context(format!("Failed to run plugin {}", plugin.display()))?;
+            if code != 0 {
+                warn!("{} exited with code {}", plugin.display(), code);
+            }
+            summary.fail(RunStatus::from_exit_code(code));
+        }
     }
 
     Ok(())
 }
 
-/// Load the configuration from a file
-fn load_config(config_path: &PathBuf) -> Result<Config> {
+/// Load the configuration from a file, applying a profile if one is given
+fn load_config(config_path: &PathBuf, profile: Option<&str>) -> Result<Config> {
     // Check if the file exists
     if !config_path.exists() {
         return Err(anyhow::anyhow!("Configuration file not found: {}", config_path.display()));
@@ -419,8 +2921,34 @@ fn load_config(config_path: &PathBuf) -> Result<Config> {
     let config_content = fs::read_to_string(config_path)
         .context(format!("Failed to read configuration file: {}", config_path.display()))?;
     
-    let mut config: Config = toml::from_str(&config_content)
-        .context("Failed to parse configuration file")?;
+    // Problems that do not keep the file from loading, like unknown keys, are only reported
+    let problems = check_config(&config_content);
+    let mut config = match parse_config(&config_content, profile) {
+        Ok(config) => {
+            for problem in &problems {
+                warn!("{}:{}", config_path.display(), problem);
+            }
+            config
+        }
+        Err(e) => {
+            let errors: Vec<String> = problems.iter()
+                .filter(|problem| problem.severity == Severity::Error)
+                .map(|problem| format!("{}:{}", config_path.display(), problem))
+                .collect();
+            if errors.is_empty() {
+                return Err(e).context("Failed to parse configuration file");
+            }
+            anyhow::bail!("Invalid configuration file (see `rust-ai-tool config check`):\n{}", errors.join("\n"));
+        }
+    };
+    
+    // Old files keep working, but the next breaking change should find them migrated
+    if let Ok((_, report)) = migrate_config(&config_content) {
+        if report.has_changes() {
+            warn!("{} uses configuration version {}; run `rust-ai-tool migrate-config` to upgrade it to version {}",
+                  config_path.display(), report.from_version, report.to_version);
+        }
+    }
     
     // Set project path to the parent directory of the config file
     if let Some(parent) = config_path.parent() {
@@ -433,29 +2961,460 @@ fn load_config(config_path: &PathBuf) -> Result<Config> {
     Ok(config)
 }
 
+/// Check, describe or migrate the configuration file
+fn run_config_command(command: &ConfigCommands, config_path: &Path, summary: &mut RunSummary) -> Result<()> {
+    match command {
+        ConfigCommands::Check { file } => {
+            let path = file.as_deref().unwrap_or(config_path);
+            let content = fs::read_to_string(path)
+                .context(format!("Failed to read configuration file: {}", path.display()))?;
+            
+            let problems = check_config(&content);
+            for problem in &problems {
+                println!("{}:{}", path.display(), problem);
+            }
+            
+            let errors = problems.iter().filter(|problem| problem.severity == Severity::Error).count();
+            let warnings = problems.len() - errors;
+            summary.count("errors", errors).count("warnings", warnings);
+            
+            if errors > 0 {
+                error!("{} has {} errors and {} warnings", path.display(), errors, warnings);
+                summary.fail(RunStatus::IssuesFound);
+            } else {
+                info!("{} is valid ({} warnings)", path.display(), warnings);
+            }
+        }
+        ConfigCommands::Schema => {
+            println!("{}", serde_json::to_string_pretty(&config_schema())?);
+        }
+        ConfigCommands::Migrate { file, dry_run } => {
+            let path = file.as_deref().unwrap_or(config_path);
+            let content = fs::read_to_string(path)
+                .context(format!("Failed to read configuration file: {}", path.display()))?;
+            
+            let (migrated, report) = migrate_config(&content)?;
+            for change in &report.changes {
+                info!("{}: {}", path.display(), change);
+            }
+            for deprecated in &report.deprecated {
+                warn!("{}: {}", path.display(), deprecated);
+            }
+            summary.count("changes", report.changes.len()).count("deprecated", report.deprecated.len());
+            
+            if *dry_run {
+                print!("{}", migrated);
+            } else if migrated != content {
+                fs::write(path, &migrated)
+                    .context(format!("Failed to write configuration file: {}", path.display()))?;
+                info!("Migrated {} from version {} to version {}", path.display(), report.from_version, report.to_version);
+            } else {
+                info!("{} is already at version {}", path.display(), CONFIG_VERSION);
+            }
+            
+            if !report.deprecated.is_empty() {
+                summary.fail(RunStatus::IssuesFound);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Run a command against a forge other than GitHub
+async fn run_forge_command(provider: &dyn ForgeProvider, command: &ForgeCommands) -> Result<()> {
+    match command {
+        ForgeCommands::CreatePr {
+            branch,
+            title,
+            fixes,
+            project_path,
+            inline_comments,
+            pr_options,
+            batches,
+        } => {
+            info!("Creating {} PR on branch {} with title: {}", provider.name(), branch, title);
+            
+            let modifications = load_modifications(fixes, project_path)
+                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            let planned = batches.plan(modifications, project_path, branch, title)?;
+            if batches.plan_only {
+                print_batches(&planned);
+                return Ok(());
+            }
+            
+            for (branch, title, modifications) in &planned {
+                let pr = publish_fixes(provider, modifications, project_path, branch, title, &pr_options.to_options(project_path, modifications)?, *inline_comments)
+                    .await
+                    .context(format!("Failed to create pull request from {}", branch))?;
+                
+                println!("Pull request created successfully: {}", pr.url);
+                notifications::record_pull_request(&pr.url);
+            }
+        }
+        ForgeCommands::UpdatePr {
+            branch,
+            title,
+            fixes,
+            project_path,
+            pr_options,
+        } => {
+            info!("Updating the {} PR from branch {}", provider.name(), branch);
+            
+            let modifications = load_modifications(fixes, project_path)
+                .context(format!("Failed to read fixes file: {}", fixes.display()))?;
+            
+            let pr = update_fixes(provider, &modifications, project_path, branch, title, &pr_options.to_options(project_path, &modifications)?)
+                .await
+                .context("Failed to update pull request")?;
+            
+            println!("Pull request #{} is up to date: {}", pr.number, pr.url);
+            notifications::record_pull_request(&pr.url);
+        }
+        ForgeCommands::PipelineStatus { branch } => {
+            let status = provider.pipeline_status(branch)
+                .await
+                .context("Failed to get pipeline status")?;
+            
+            match status {
+                Some(status) => println!("Latest pipeline of {}: {}", branch, status),
+                None => println!("No pipeline ran on {}", branch),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Record feedback on proposed fixes in the history database, warning on failure
+async fn record_feedback_history(project_path: &Path, entries: &[FeedbackEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    
+    let recorded = match HistoryDb::open(project_path).await {
+        Ok(history) => history.record_feedback(entries).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = recorded {
+        warn!("Failed to record fix feedback in the history: {}", e);
+    }
+}
+
+/// Name of the network feature a command uses, if any
+fn network_feature(command: &Commands) -> Option<&'static str> {
+    match command {
+        Commands::Analyze { triage: true, .. } => Some("analyze --triage"),
+        Commands::Analyze { outdated: true, .. } => Some("analyze --outdated"),
+        Commands::Apply { file_issues: true, .. } => Some("apply --file-issues"),
+        Commands::Generate { .. } => Some("generate"),
+        Commands::GitHub { .. } => Some("github"),
+        Commands::Gitea { .. } => Some("gitea"),
+        Commands::Bitbucket { .. } => Some("bitbucket"),
+        Commands::Fix { .. } => Some("fix"),
+        Commands::Explain { .. } => Some("explain"),
+        Commands::Chat { .. } => Some("chat"),
+        Commands::Serve { github_webhook: true, .. } => Some("serve --github-webhook"),
+        Commands::Index { .. } => Some("index"),
+        Commands::UpdateDeps { .. } => Some("update-deps"),
+        Commands::ExportIssues { dry_run: false, .. } => Some("export-issues"),
+        Commands::Analyze { .. }
+        | Commands::Compare { .. }
+        | Commands::Validate { .. }
+        | Commands::Batch { .. }
+        | Commands::Diff { .. }
+        | Commands::Apply { .. }
+        | Commands::Serve { .. }
+        | Commands::Demo { .. }
+        | Commands::ExtractFunction { .. }
+        | Commands::OrganizeImports { .. }
+        | Commands::Undo { .. }
+        | Commands::History { .. }
+        | Commands::Runs { .. }
+        | Commands::ExportIssues { .. }
+        | Commands::Rollback { .. }
+        | Commands::Rule { .. }
+        | Commands::Config { .. }
+        | Commands::MigrateConfig { .. }
+        | Commands::Init { .. }
+        | Commands::External(..) => None,
+    }
+}
+
+/// Create an AI model client with the project's tools, code index and fix feedback
+fn create_project_ai_client(config: &AiModelConfig, path: &Path) -> Result<AiModelClient> {
+    let mut client = AiModelClient::new(config.clone()).context("Failed to create AI model client")?;
+    
+    let project_root = path.canonicalize().ok().and_then(|path| {
+        path.ancestors().find(|dir| dir.join("Cargo.toml").is_file()).map(Path::to_path_buf)
+    });
+    if let Some(project_root) = project_root {
+        match FeedbackLog::open(&project_root).entries() {
+            Ok(feedback) => client = client.with_feedback(feedback),
+            Err(e) => warn!("Ignoring unreadable fix feedback: {}", e),
+        }
+        
+        client = client.with_project_root(&project_root)
+            .with_tools(ProjectTools::new(project_root));
+    }
+    
+    match CodeIndex::find(path) {
+        Ok(Some(index)) => Ok(client.with_index(index)),
+        Ok(None) => Ok(client),
+        Err(e) => {
+            warn!("Ignoring unreadable code index: {}", e);
+            Ok(client)
+        }
+    }
+}
+
+/// Parse an issue location such as `src/main.rs:42`
+fn parse_issue_location(value: &str) -> std::result::Result<(PathBuf, usize), String> {
+    let (file, line) = value.rsplit_once(':')
+        .ok_or_else(|| format!("expected <file>:<line>, got '{}'", value))?;
+    
+    let line: usize = line.trim().parse().map_err(|_| format!("invalid line in '{}'", value))?;
+    if line == 0 {
+        return Err(format!("invalid line in '{}'", value));
+    }
+    
+    Ok((PathBuf::from(file), line))
+}
+
+/// Answer questions about a project until the user quits
+///
+/// Lines starting with `/` are commands acting on the changes proposed so far.
+async fn run_chat(
+    client: &AiModelClient,
+    project_path: &Path,
+    fixes_file: &Path,
+    options: &ValidationOptions,
+) -> Result<()> {
+    let mut session = ChatSession::new(client, project_path);
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    
+    println!("Ask questions about {} (/help lists the commands, /quit exits)", project_path.display());
+    
+    loop {
+        print!("\n> ");
+        std::io::stdout().flush().context("Failed to write prompt")?;
+        
+        let mut line = String::new();
+        if input.read_line(&mut line).context("Failed to read question")? == 0 {
+            break;
+        }
+        
+        match line.trim() {
+            "" => {}
+            "/quit" | "/exit" => break,
+            "/help" => {
+                println!("/fixes     List the proposed changes");
+                println!("/validate  Validate the proposed changes");
+                println!("/save      Write the valid changes to {}", fixes_file.display());
+                println!("/apply     Apply the valid changes (with backups; revert with `undo`)");
+                println!("/clear     Forget the conversation and the proposed changes");
+                println!("/quit      Leave the chat");
+            }
+            "/clear" => {
+                session.clear();
+                println!("Conversation cleared");
+            }
+            "/fixes" => {
+                if session.proposals().is_empty() {
+                    println!("No changes proposed yet");
+                }
+                for proposal in session.proposals() {
+                    println!("{}", proposal.file_path.display());
+                }
+            }
+            "/validate" => {
+                let valid = validate_proposals(session.proposals(), options)?;
+                println!("{}/{} proposed changes are valid", valid.len(), session.proposals().len());
+            }
+            "/save" => {
+                let valid = validate_proposals(session.proposals(), options)?;
+                let fixes_json = serde_json::to_string_pretty(&valid)
+                    .context("Failed to serialize fixes")?;
+                fs::write(fixes_file, fixes_json)
+                    .context(format!("Failed to write fixes to {}", fixes_file.display()))?;
+                
+                println!(
+                    "{} valid changes written to {} (apply with `rust-ai-tool apply {} --fixes {}`)",
+                    valid.len(),
+                    fixes_file.display(),
+                    project_path.display(),
+                    fixes_file.display()
+                );
+            }
+            "/apply" => {
+                let valid = validate_proposals(session.proposals(), options)?;
+                let changes = apply_modifications(&valid, true)
+                    .context("Failed to apply modifications")?;
+                Journal::open(project_path).record(&changes)
+                    .context("Failed to record changes in the journal")?;
+                
+                println!("{}", create_change_report(&changes));
+                session.clear();
+            }
+            command if command.starts_with('/') => {
+                println!("Unknown command {}; /help lists the commands", command);
+            }
+            question => match session.ask(question).await {
+                Ok((answer, proposed)) => {
+                    println!("\n{}", answer.trim());
+                    
+                    if proposed > 0 {
+                        println!("\n{} changes proposed; /validate, /save or /apply them", proposed);
+                    }
+                }
+                Err(e) => error!("Failed to answer: {}", e),
+            },
+        }
+    }
+    
+    Ok(())
+}
+
+/// Validate proposed changes, printing why invalid ones were rejected
+///
+/// # Returns
+///
+/// The valid changes
+fn validate_proposals(proposals: &[CodeModification], options: &ValidationOptions) -> Result<Vec<CodeModification>> {
+    let fixes: Vec<FixToValidate> = proposals.iter()
+        .map(|m| FixToValidate {
+            file_path: m.file_path.clone(),
+            original_code: m.original_content.clone(),
+            modified_code: m.modified_content.clone(),
+            description: m.description.clone(),
+        })
+        .collect();
+    
+    let results = validate_fixes(&fixes, options)
+        .context("Failed to validate proposed changes")?;
+    
+    let mut valid = Vec::new();
+    for (proposal, result) in proposals.iter().zip(&results) {
+        if result.is_valid {
+            valid.push(proposal.clone());
+        } else {
+            println!("Change to {} is invalid:", proposal.file_path.display());
+            for msg in &result.messages {
+                println!("  - {}: {}", msg.message_type, msg.text);
+            }
+        }
+    }
+    
+    Ok(valid)
+}
+
+/// Code around an issue with line numbers, marking the reported lines with `>`
+fn issue_excerpt(issue: &CodeIssue, context_lines: usize) -> Result<String> {
+    let source = fs::read_to_string(&issue.file_path)
+        .context(format!("Failed to read {}", issue.file_path.display()))?;
+    
+    let first = issue.line_start.saturating_sub(context_lines).max(1);
+    let last = issue.line_end + context_lines;
+    
+    Ok(source.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(number, _)| (first..=last).contains(number))
+        .map(|(number, line)| {
+            let marker = if (issue.line_start..=issue.line_end).contains(&number) { ">" } else { " " };
+            format!("{}{:>5} | {}\n", marker, number, line)
+        })
+        .collect())
+}
+
+/// Parse a severity such as `error` or `warning`
+fn parse_severity(value: &str) -> std::result::Result<Severity, String> {
+    Severity::parse(value).ok_or_else(|| format!("invalid severity '{}', expected error, warning, info or style", value))
+}
+
+/// Parse a line range such as `10-20` or `15`
+fn parse_line_range(value: &str) -> std::result::Result<RangeInclusive<usize>, String> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    
+    let start: usize = start.trim().parse().map_err(|_| format!("invalid start line in '{}'", value))?;
+    let end: usize = end.trim().parse().map_err(|_| format!("invalid end line in '{}'", value))?;
+    
+    if start == 0 || end < start {
+        return Err(format!("invalid line range '{}'", value));
+    }
+    
+    Ok(start..=end)
+}
+
 /// Create a default configuration
 fn create_default_config() -> Config {
     Config {
         project_path: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        version: CONFIG_VERSION,
         github_repo: None,
+        gitea_repo: None,
+        bitbucket_repo: None,
+        jira: None,
+        linear: None,
+        ssh: None,
         ai_model: AiModelConfig {
             model_type: AiModelType::Claude,
             api_key: String::new(),
             api_base_url: None,
+            retry: Default::default(),
+            cache: Default::default(),
+            context_window: None,
+            prompt_overflow: Default::default(),
+            pricing: Default::default(),
+            embedding_model: None,
+            tools: Default::default(),
+            redaction: Default::default(),
         },
+        ai_models: Vec::new(),
+        model: None,
+        task_models: Default::default(),
+        lints: Default::default(),
+        policy: None,
         analysis_options: AnalysisOptions {
             run_clippy: true,
             use_rust_analyzer: true,
             custom_rules: Vec::new(),
+            analyzers: Vec::new(),
+            filter: Default::default(),
+            lints: Default::default(),
+            policy: None,
         },
         validation_options: ValidationOptions {
             syntax_only: false,
             tauri_compatibility: true,
             security_validation: true,
+            protected_paths: Vec::new(),
         },
+        offline: false,
+        network: Default::default(),
+        telemetry: Default::default(),
+        execution: Default::default(),
+        notifications: Vec::new(),
+        dependency_presets: Default::default(),
     }
 }
 
+/// Write analysis results as a browsable HTML site
+#[cfg(feature = "html-report")]
+fn write_html_report(results: &[AnalysisResult], project_path: &Path, site_dir: &Path) -> Result<PathBuf> {
+    rust_ai_tool::html_report::write_html_report(results, project_path, site_dir)
+        .context(format!("Failed to write HTML report to {}", site_dir.display()))
+}
+
+/// Write analysis results as a browsable HTML site
+#[cfg(not(feature = "html-report"))]
+fn write_html_report(_results: &[AnalysisResult], _project_path: &Path, _site_dir: &Path) -> Result<PathBuf> {
+    Err(anyhow::anyhow!(
+        "HTML output is not available: rebuild rust-ai-tool with `--features html-report`"
+    ))
+}
+
 /// Format analysis results as the specified output format
 fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<String> {
     match format.to_lowercase().as_str() {
@@ -474,6 +3433,20 @@ fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<S
             
             markdown.push_str(&format!("**Total Issues Found**: {}\n\n", issue_count));
             
+            let mut by_owner: BTreeMap<&str, usize> = BTreeMap::new();
+            for issue in results.iter().flat_map(|r| &r.issues) {
+                for owner in &issue.owners {
+                    *by_owner.entry(owner.as_str()).or_default() += 1;
+                }
+            }
+            if !by_owner.is_empty() {
+                markdown.push_str("| Owner | Issues |\n|---|---|\n");
+                for (owner, count) in &by_owner {
+                    markdown.push_str(&format!("| {} | {} |\n", owner, count));
+                }
+                markdown.push('\n');
+            }
+            
             for result in results {
                 if result.issues.is_empty() {
                     continue;
@@ -488,9 +3461,16 @@ fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<S
                         issue.line_end
                     ));
                     
+                    markdown.push_str(&format!("**Id**: {}\n\n", issue.id()));
+                    if let Some(priority) = issue.priority {
+                        markdown.push_str(&format!("**Priority**: {}\n\n", priority));
+                    }
                     markdown.push_str(&format!("**Category**: {}\n\n", format!("{:?}", issue.category)));
                     markdown.push_str(&format!("**Severity**: {}\n\n", format!("{:?}", issue.severity)));
                     markdown.push_str(&format!("**Message**: {}\n\n", issue.message));
+                    if !issue.owners.is_empty() {
+                        markdown.push_str(&format!("**Owners**: {}\n\n", issue.owners.join(" ")));
+                    }
                     
                     if let Some(fix) = &issue.suggested_fix {
                         markdown.push_str("**Suggested Fix**:\n\n");
@@ -522,8 +3502,9 @@ fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<S
                 output.push_str(&format!("File: {}\n", result.file_path.display()));
                 
                 for (i, issue) in result.issues.iter().enumerate() {
-                    output.push_str(&format!("Issue #{}: {}:{}-{} ({:?}, {:?})\n", 
+                    output.push_str(&format!("Issue #{} [{}]: {}:{}-{} ({:?}, {:?})\n", 
                         i + 1,
+                        issue.id(),
                         issue.file_path.display(),
                         issue.line_start,
                         issue.line_end,
@@ -533,6 +3514,14 @@ fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<S
                     
                     output.push_str(&format!("  {}\n", issue.message));
                     
+                    if let Some(priority) = issue.priority {
+                        output.push_str(&format!("  Priority: {}\n", priority));
+                    }
+                    
+                    if !issue.owners.is_empty() {
+                        output.push_str(&format!("  Owners: {}\n", issue.owners.join(" ")));
+                    }
+                    
                     if let Some(fix) = &issue.suggested_fix {
                         output.push_str("  Suggested Fix:\n");
                         for line in fix.replacement_code.lines() {
@@ -549,6 +3538,26 @@ fn format_analysis_results(results: &[AnalysisResult], format: &str) -> Result<S
             
             Ok(output)
         }
+        "github-actions" => Ok(format_workflow_commands(results)),
         _ => Err(anyhow::anyhow!("Unsupported output format: {}", format))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_migrate_config_command() {
+        let cli = Cli::try_parse_from(["rust-ai-tool", "migrate-config", "--dry-run", "old.toml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::MigrateConfig { file: Some(ref file), dry_run: true } if file == Path::new("old.toml")
+        ));
+    }
+}
@@ -0,0 +1,359 @@
+//! Manifest module
+//!
+//! This module edits Cargo.toml files without losing their formatting:
+//! - Parsed with `toml_edit`, so comments, key order and whitespace of the
+//!   untouched parts survive the edit
+//! - Package metadata and other fields, like profile settings, set or replaced,
+//!   creating their tables if needed
+//! - Dependencies added as a plain version requirement, or as an inline table
+//!   when features are enabled or default features disabled, to `[dependencies]`
+//!   or another dependency table
+//! - Dependencies of every table (target-specific and workspace ones included)
+//!   listed, and their version requirement replaced in place
+
+use crate::{Result, RustAiToolError};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use toml_edit::{Array, Document, InlineTable, Item, Table, TableLike, Value};
+
+/// Tables declaring dependencies, besides the target-specific ones
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// A Cargo.toml document being edited
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    /// The parsed document, keeping its formatting
+    document: Document,
+}
+
+/// Dependency declared in a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestDependency {
+    /// Path of the table, like `["target", "cfg(unix)", "dependencies"]`
+    pub table: Vec<String>,
+
+    /// Key of the dependency in the table
+    pub key: String,
+
+    /// Name of the crate, which differs from the key for renamed dependencies
+    pub package: String,
+
+    /// Version requirement, if any (none for path dependencies or ones inherited from the workspace)
+    pub requirement: Option<String>,
+
+    /// Whether the crate comes from crates.io, rather than git, a path or another registry
+    pub crates_io: bool,
+}
+
+impl Manifest {
+    /// Parse the content of a manifest
+    pub fn parse(content: &str) -> Result<Self> {
+        let document = content.parse::<Document>()
+            .map_err(|e| RustAiToolError::Other(format!("Failed to parse Cargo.toml: {}", e)))?;
+
+        Ok(Manifest { document })
+    }
+
+    /// Read a manifest from disk
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the Cargo.toml file
+    ///
+    /// # Returns
+    ///
+    /// The parsed manifest
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+        Self::parse(&content)
+    }
+
+    /// Write the manifest to disk
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the Cargo.toml file
+    ///
+    /// # Returns
+    ///
+    /// Success status
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.document.to_string()).map_err(RustAiToolError::Io)
+    }
+
+    /// Set a `[package]` field, replacing its current value
+    pub fn set_package_field(&mut self, key: &str, value: impl Into<Value>) -> Result<()> {
+        self.set_field(&["package"], key, value)
+    }
+
+    /// Set a field of a table, replacing its current value
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Path of the table, like `["profile", "release"]`
+    /// * `key` - Field name
+    /// * `value` - Field value
+    ///
+    /// # Returns
+    ///
+    /// Success status
+    pub fn set_field(&mut self, table: &[&str], key: &str, value: impl Into<Value>) -> Result<()> {
+        let table = table_mut(&mut self.document, table)?;
+        set_value(table, key, value.into());
+        Ok(())
+    }
+
+    /// Set a `[package]` field holding a list of strings, like `authors` or `keywords`
+    pub fn set_package_list<S: AsRef<str>>(&mut self, key: &str, values: &[S]) -> Result<()> {
+        let list: Array = values.iter().map(|value| value.as_ref()).collect();
+        self.set_package_field(key, list)
+    }
+
+    /// Whether a dependency table declares a crate
+    pub fn has_dependency(&self, table: &str, name: &str) -> bool {
+        self.document.get(table)
+            .and_then(Item::as_table_like)
+            .is_some_and(|dependencies| dependencies.contains_key(name))
+    }
+
+    /// Add a dependency, replacing its current requirement
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Dependency table, like `dependencies` or `dev-dependencies`
+    /// * `name` - Crate name
+    /// * `version` - Version requirement
+    /// * `features` - Features to enable; the dependency is written as an inline
+    ///   table when there are any
+    ///
+    /// # Returns
+    ///
+    /// Success status
+    pub fn add_dependency(&mut self, table: &str, name: &str, version: &str, features: &[&str]) -> Result<()> {
+        let dependencies = table_mut(&mut self.document, &[table])?;
+
+        let requirement = if features.is_empty() {
+            Value::from(version)
+        } else {
+            let mut spec = InlineTable::new();
+            spec.insert("version", version.into());
+            spec.insert("features", Value::Array(features.iter().copied().collect()));
+            Value::InlineTable(spec)
+        };
+
+        set_value(dependencies, name, requirement);
+        Ok(())
+    }
+
+    /// Enable or disable the default features of a dependency
+    ///
+    /// A dependency declared as a plain version requirement is turned into an inline table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Dependency table, like `dependencies` or `dev-dependencies`
+    /// * `name` - Crate name
+    /// * `enabled` - Whether the default features are enabled
+    ///
+    /// # Returns
+    ///
+    /// Success status; an error if the table does not declare the dependency
+    pub fn set_default_features(&mut self, table: &str, name: &str, enabled: bool) -> Result<()> {
+        let dependencies = table_mut(&mut self.document, &[table])?;
+        let dependency = dependencies.get_mut(name)
+            .ok_or_else(|| RustAiToolError::Other(format!("`{}` is not declared in [{}]", name, table)))?;
+
+        if let Some(version) = dependency.as_str().map(str::to_string) {
+            let mut spec = InlineTable::new();
+            spec.insert("version", version.into());
+            *dependency = Item::Value(Value::InlineTable(spec));
+        }
+
+        let spec = dependency.as_table_like_mut()
+            .ok_or_else(|| RustAiToolError::Other(format!("`{}.{}` in Cargo.toml is not a table", table, name)))?;
+        spec.insert("default-features", Item::Value(enabled.into()));
+        Ok(())
+    }
+
+    /// Dependencies of every dependency table, in the order of the document
+    pub fn dependencies(&self) -> Vec<ManifestDependency> {
+        let mut tables: Vec<Vec<String>> = Vec::new();
+        tables.extend(DEPENDENCY_TABLES.iter().map(|table| vec![table.to_string()]));
+        if let Some(targets) = self.document.get("target").and_then(Item::as_table_like) {
+            for (target, _) in targets.iter() {
+                tables.extend(DEPENDENCY_TABLES.iter().map(|table| vec!["target".to_string(), target.to_string(), table.to_string()]));
+            }
+        }
+        tables.push(vec!["workspace".to_string(), "dependencies".to_string()]);
+
+        let mut dependencies = Vec::new();
+        for table in tables {
+            let mut item = Some(self.document.as_item());
+            for name in &table {
+                item = item.and_then(|item| item.get(name.as_str()));
+            }
+            let Some(entries) = item.and_then(Item::as_table_like) else {
+                continue;
+            };
+
+            for (key, spec) in entries.iter() {
+                let field = |name: &str| spec.as_table_like().and_then(|spec| spec.get(name));
+                let requirement = spec.as_str()
+                    .or_else(|| field("version").and_then(Item::as_str))
+                    .map(str::to_string);
+
+                dependencies.push(ManifestDependency {
+                    table: table.clone(),
+                    key: key.to_string(),
+                    package: field("package").and_then(Item::as_str).unwrap_or(key).to_string(),
+                    crates_io: requirement.is_some() && ["git", "path", "registry", "workspace"].iter().all(|name| field(name).is_none()),
+                    requirement,
+                });
+            }
+        }
+
+        dependencies
+    }
+
+    /// Replace the version requirement of a dependency, keeping the rest of its declaration
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Path of the dependency table, like `["dependencies"]`
+    /// * `key` - Key of the dependency in the table
+    /// * `requirement` - New version requirement
+    ///
+    /// # Returns
+    ///
+    /// Success status; an error if the table does not declare the dependency
+    pub fn set_dependency_requirement(&mut self, table: &[&str], key: &str, requirement: &str) -> Result<()> {
+        let dependencies = table_mut(&mut self.document, table)?;
+        let declared_as_string = dependencies.get(key)
+            .ok_or_else(|| RustAiToolError::Other(format!("`{}` is not declared in [{}]", key, table.join("."))))?
+            .is_str();
+
+        if declared_as_string {
+            set_value(dependencies, key, requirement.into());
+            return Ok(());
+        }
+
+        let spec = dependencies.get_mut(key)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| RustAiToolError::Other(format!("`{}.{}` in Cargo.toml is not a table", table.join("."), key)))?;
+        set_value(spec, "version", requirement.into());
+        Ok(())
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+/// A table of the document, created with its parents if missing
+fn table_mut<'a>(document: &'a mut Document, path: &[&str]) -> Result<&'a mut dyn TableLike> {
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+
+    for (depth, name) in path.iter().enumerate() {
+        // Parents only holding the table, like `profile` of `[profile.release]`, get no header
+        let mut created = Table::new();
+        created.set_implicit(depth + 1 < path.len());
+
+        table = table.entry(name)
+            .or_insert(Item::Table(created))
+            .as_table_like_mut()
+            .ok_or_else(|| RustAiToolError::Other(format!("`{}` in Cargo.toml is not a table", path[..=depth].join("."))))?;
+    }
+
+    Ok(table)
+}
+
+/// Set a key of a table, keeping the decoration of the value it replaces
+fn set_value(table: &mut dyn TableLike, key: &str, mut value: Value) {
+    if let Some(current) = table.get(key).and_then(Item::as_value) {
+        let decor = current.decor();
+        let (prefix, suffix) = (decor.prefix().cloned(), decor.suffix().cloned());
+        if let Some(prefix) = prefix {
+            value.decor_mut().set_prefix(prefix);
+        }
+        if let Some(suffix) = suffix {
+            value.decor_mut().set_suffix(suffix);
+        }
+    }
+
+    table.insert(key, Item::Value(value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_preserves_formatting() {
+        let mut manifest = Manifest::parse(
+            "# Generated by cargo new\n\
+             [package]\n\
+             name = \"demo\"\n\
+             version = \"0.1.0\"\n\
+             description = \"old\" # keep me\n\
+             \n\
+             [dependencies]\n\
+             # Logging\n\
+             log = \"0.4\"\n",
+        )
+        .unwrap();
+
+        manifest.set_package_field("description", "A demo").unwrap();
+        manifest.set_package_list("authors", &["Ada <ada@example.com>"]).unwrap();
+        manifest.add_dependency("dependencies", "tokio", "1.28", &["full"]).unwrap();
+        manifest.add_dependency("dev-dependencies", "tempfile", "3.6", &[]).unwrap();
+        manifest.set_field(&["profile", "release"], "lto", true).unwrap();
+        manifest.set_default_features("dev-dependencies", "tempfile", false).unwrap();
+
+        assert_eq!(
+            manifest.to_string(),
+            "# Generated by cargo new\n\
+             [package]\n\
+             name = \"demo\"\n\
+             version = \"0.1.0\"\n\
+             description = \"A demo\" # keep me\n\
+             authors = [\"Ada <ada@example.com>\"]\n\
+             \n\
+             [dependencies]\n\
+             # Logging\n\
+             log = \"0.4\"\n\
+             tokio = { version = \"1.28\", features = [\"full\"] }\n\
+             \n\
+             [dev-dependencies]\n\
+             tempfile = { version = \"3.6\", default-features = false }\n\
+             \n\
+             [profile.release]\n\
+             lto = true\n"
+        );
+        assert!(manifest.has_dependency("dependencies", "log"));
+        assert!(!manifest.has_dependency("build-dependencies", "log"));
+        assert!(manifest.set_default_features("dependencies", "rand", false).is_err());
+        assert!(Manifest::parse("[package\n").is_err());
+
+        manifest.set_dependency_requirement(&["dependencies"], "log", "1.0").unwrap();
+        manifest.set_dependency_requirement(&["dependencies"], "tokio", "2").unwrap();
+        let dependencies = manifest.dependencies();
+        assert_eq!(dependencies[0].requirement.as_deref(), Some("1.0"));
+        assert_eq!(dependencies[1].requirement.as_deref(), Some("2"));
+        assert_eq!(dependencies[2].table, ["dev-dependencies"]);
+        assert!(manifest.to_string().contains("tokio = { version = \"2\", features = [\"full\"] }\n"));
+
+        let renamed = Manifest::parse(
+            "[target.'cfg(unix)'.dependencies]\n\
+             nix2 = { package = \"nix\", version = \"0.26\" }\n\
+             local = { path = \"../local\" }\n",
+        )
+        .unwrap();
+        let dependencies = renamed.dependencies();
+        assert_eq!(dependencies[0].table, ["target", "cfg(unix)", "dependencies"]);
+        assert_eq!((dependencies[0].package.as_str(), dependencies[0].crates_io), ("nix", true));
+        assert!(!dependencies[1].crates_io && dependencies[1].requirement.is_none());
+    }
+}
@@ -5,13 +5,35 @@
 //! - Apply refactorings
 //! - Handle batch modifications
 //! - Track changes
+//! - Queue edits across files in an `EditSession` and commit them atomically
 
-use crate::{Result, RustAiToolError};
+use crate::analysis::CodeIssue;
+use crate::events::{emit, Event};
+use crate::execution::CargoCommand;
+use crate::backup::{create_git_backup, restore_file, write_file_backup, GitBackup};
+use crate::patch::{is_patch_file, parse_unified_diff};
+use crate::validation::{validate_fixes, FixToValidate, ValidationResult};
+use crate::{Result, RustAiToolError, ValidationOptions};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use log::{debug, info, warn, error};
 use serde::{Serialize, Deserialize};
+use similar::{ChangeTag, DiffTag, TextDiff};
+use std::ops::Range;
+
+/// Byte order mark some editors write at the start of UTF-8 files
+const UTF8_BOM: char = '\u{feff}';
+
+/// Line ending style of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix line endings (`\n`)
+    Lf,
+    
+    /// Windows line endings (`\r\n`)
+    CrLf,
+}
 
 /// Represents a code modification
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +52,112 @@ pub struct CodeModification {
     
     /// Confidence level (0-100)
     pub confidence: u8,
+    
+    /// Analysis issues the modification resolves, if it was generated from an analysis
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolves: Vec<IssueReference>,
+}
+
+/// Analysis issue resolved by a modification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueReference {
+    /// Id of the issue, as shown in analysis reports
+    pub id: String,
+    
+    /// First line of the issue
+    pub line_start: usize,
+    
+    /// Last line of the issue
+    pub line_end: usize,
+    
+    /// Category or custom rule that reported the issue
+    pub rule: String,
+    
+    /// Issue message
+    pub message: String,
+}
+
+impl From<&CodeIssue> for IssueReference {
+    fn from(issue: &CodeIssue) -> Self {
+        IssueReference {
+            id: issue.id(),
+            line_start: issue.line_start,
+            line_end: issue.line_end,
+            rule: issue.category.name(),
+            message: issue.message.clone(),
+        }
+    }
+}
+
+/// A contiguous group of changed lines within a modification
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// Unified diff header (`@@ -a,b +c,d @@`)
+    pub header: String,
+    
+    /// Range of original lines covered by the hunk (0-based, including context)
+    pub old_range: Range<usize>,
+    
+    /// Original text of the covered lines
+    pub old_text: String,
+    
+    /// Proposed text replacing the covered lines
+    pub new_text: String,
+    
+    /// Diff lines of the hunk, each prefixed with ' ', '-' or '+'
+    pub diff_lines: Vec<String>,
+}
+
+/// Decision taken for a single hunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HunkDecision {
+    /// Apply the proposed text
+    Accept,
+    
+    /// Keep the original text
+    Skip,
+    
+    /// Apply a user-edited replacement text
+    Edit(String),
+}
+
+/// A region where a file and a modification changed the same lines differently
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    /// Path to the file
+    pub file_path: PathBuf,
+    
+    /// First line of the original content involved in the conflict (1-based)
+    pub line_start: usize,
+    
+    /// Last line of the original content involved in the conflict (1-based, inclusive)
+    pub line_end: usize,
+    
+    /// Current text of the file in that region
+    pub current_text: String,
+    
+    /// Text the modification wanted to write in that region
+    pub modified_text: String,
+}
+
+/// Result of rebasing a modification onto the current file content
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// Modification from the current content to the merged content
+    pub modification: CodeModification,
+    
+    /// Regions that could not be merged and were left as they are in the file
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Result of coalescing modifications that target the same files
+#[derive(Debug, Clone)]
+pub struct CoalesceOutcome {
+    /// At most one modification per file and original content
+    pub modifications: Vec<CodeModification>,
+    
+    /// Edits that conflict with a higher-confidence fix and were left out
+    pub conflicts: Vec<MergeConflict>,
 }
 
 /// Represents a change in a file
@@ -50,108 +178,858 @@ pub struct FileChange {
     /// Whether a backup was created
     pub backup_created: bool,
     
-    /// Path to the backup file (if created)
-    pub backup_path: Option<PathBuf>,
+    /// Path to the backup file (if created)
+    pub backup_path: Option<PathBuf>,
+    
+    /// Git ref holding the backup (if the file is in a git repository)
+    #[serde(default)]
+    pub backup_ref: Option<String>,
+}
+
+/// A modification that was not applied in a keep-going batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnappliedModification {
+    /// Path to the file
+    pub file_path: PathBuf,
+    
+    /// Description of the modification
+    pub description: String,
+    
+    /// Why the modification was not applied
+    pub reason: String,
+}
+
+/// Outcome of applying a batch of modifications with per-file error tolerance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplySummary {
+    /// Changes that were applied
+    pub applied: Vec<FileChange>,
+    
+    /// Modifications that had nothing left to do
+    pub skipped: Vec<UnappliedModification>,
+    
+    /// Modifications that could not be applied
+    pub failed: Vec<UnappliedModification>,
+}
+
+/// Load modifications from a fixes file
+///
+/// JSON files contain serialized `CodeModification`s; `.patch` and `.diff`
+/// files are read as unified diffs against the current project files.
+///
+/// # Arguments
+///
+/// * `fixes_path` - Path to the fixes file
+/// * `base_dir` - Directory that patch paths are relative to (usually the project root)
+///
+/// # Returns
+///
+/// List of modifications
+pub fn load_modifications(fixes_path: &Path, base_dir: &Path) -> Result<Vec<CodeModification>> {
+    let fixes_content = fs::read_to_string(fixes_path)
+        .map_err(RustAiToolError::Io)?;
+    
+    if is_patch_file(fixes_path) {
+        debug!("Reading {} as a unified diff", fixes_path.display());
+        parse_unified_diff(&fixes_content, base_dir)
+    } else {
+        serde_json::from_str(&fixes_content)
+            .map_err(RustAiToolError::Json)
+    }
+}
+
+/// Apply a list of code modifications
+///
+/// # Arguments
+///
+/// * `modifications` - List of modifications to apply
+/// * `create_backup` - Whether to create backups of modified files
+///
+/// # Returns
+///
+/// List of applied changes
+#[tracing::instrument(skip_all, fields(modifications = modifications.len()), err)]
+pub fn apply_modifications(
+    modifications: &[CodeModification],
+    create_backup: bool,
+) -> Result<Vec<FileChange>> {
+    info!("Applying {} modifications with backup={}", modifications.len(), create_backup);
+    let mut changes = Vec::new();
+    
+    let git_backup = if create_backup {
+        backup_to_git(modifications.iter())
+    } else {
+        None
+    };
+    
+    for (i, modification) in modifications.iter().enumerate() {
+        debug!("Applying modification #{} to {}", i + 1, modification.file_path.display());
+        match apply_with_backup(modification, create_backup, git_backup.as_ref()) {
+            Ok(change) => {
+                info!("Successfully applied modification to {}", modification.file_path.display());
+                changes.push(change);
+            },
+            Err(e) => {
+                error!(
+                    "Failed to apply modification to {}: {}",
+                    modification.file_path.display(),
+                    e
+                );
+                return Err(e);
+            }
+        }
+    }
+    
+    info!("Successfully applied {} modifications", changes.len());
+    Ok(changes)
+}
+
+/// Apply a list of code modifications, continuing past files that fail
+///
+/// Modifications whose file already has the modified content are skipped;
+/// any other error is recorded and the remaining modifications are still applied.
+///
+/// # Arguments
+///
+/// * `modifications` - List of modifications to apply
+/// * `create_backup` - Whether to create backups of modified files
+///
+/// # Returns
+///
+/// Summary of the applied, skipped and failed modifications
+#[tracing::instrument(skip_all, fields(modifications = modifications.len()))]
+pub fn apply_modifications_keep_going(
+    modifications: &[CodeModification],
+    create_backup: bool,
+) -> ApplySummary {
+    info!("Applying {} modifications with backup={}, keeping going on errors", modifications.len(), create_backup);
+    let mut summary = ApplySummary::default();
+    
+    let git_backup = if create_backup {
+        backup_to_git(modifications.iter())
+    } else {
+        None
+    };
+    
+    for (i, modification) in modifications.iter().enumerate() {
+        debug!("Applying modification #{} to {}", i + 1, modification.file_path.display());
+        
+        let unapplied = |reason: String| UnappliedModification {
+            file_path: modification.file_path.clone(),
+            description: modification.description.clone(),
+            reason,
+        };
+        
+        let already_applied = fs::read_to_string(&modification.file_path)
+            .is_ok_and(|current| current == match_file_format(&current, &modification.modified_content));
+        
+        if already_applied {
+            info!("Skipping {}: modification already applied", modification.file_path.display());
+            summary.skipped.push(unapplied("already applied".to_string()));
+            continue;
+        }
+        
+        match apply_with_backup(modification, create_backup, git_backup.as_ref()) {
+            Ok(change) => {
+                info!("Successfully applied modification to {}", modification.file_path.display());
+                summary.applied.push(change);
+            },
+            Err(e) => {
+                error!(
+                    "Failed to apply modification to {}: {}",
+                    modification.file_path.display(),
+                    e
+                );
+                summary.failed.push(unapplied(e.to_string()));
+            }
+        }
+    }
+    
+    info!(
+        "Applied {} modifications, skipped {}, failed {}",
+        summary.applied.len(),
+        summary.skipped.len(),
+        summary.failed.len()
+    );
+    summary
+}
+
+/// Apply a single code modification
+///
+/// # Arguments
+///
+/// * `modification` - Modification to apply
+/// * `create_backup` - Whether to create a backup of the modified file
+///
+/// # Returns
+///
+/// The file change
+fn apply_modification(
+    modification: &CodeModification,
+    create_backup: bool,
+) -> Result<FileChange> {
+    let file_path = &modification.file_path;
+    
+    let current_content = check_modification(modification)?;
+    
+    // Create a backup if requested
+    let backup_path = if create_backup {
+        Some(write_file_backup(file_path, &current_content)?)
+    } else {
+        None
+    };
+    
+    // Write the modified content in the file's line ending style
+    let new_content = match_file_format(&current_content, &modification.modified_content);
+    fs::write(file_path, &new_content)
+        .map_err(|e| RustAiToolError::Io(e))?;
+    
+    Ok(FileChange {
+        file_path: file_path.to_path_buf(),
+        original_content: Some(current_content),
+        new_content,
+        description: modification.description.clone(),
+        backup_created: backup_path.is_some(),
+        backup_path,
+        backup_ref: None,
+    })
+}
+
+/// Snapshot the files of a batch of modifications to a git backup ref
+///
+/// Returns `None` when the files are not in a git repository or the snapshot
+/// fails, in which case backup files are written instead.
+fn backup_to_git<'a>(modifications: impl Iterator<Item = &'a CodeModification>) -> Option<GitBackup> {
+    let files: Vec<PathBuf> = modifications.map(|m| m.file_path.clone()).collect();
+    
+    match create_git_backup(&files) {
+        Ok(backup) => backup,
+        Err(e) => {
+            warn!("Failed to create git backup, falling back to backup files: {}", e);
+            None
+        }
+    }
+}
+
+/// Apply a modification, recording the git backup or writing a backup file
+fn apply_with_backup(
+    modification: &CodeModification,
+    create_backup: bool,
+    git_backup: Option<&GitBackup>,
+) -> Result<FileChange> {
+    let mut change = apply_modification(modification, create_backup && git_backup.is_none())?;
+    
+    if let Some(backup) = git_backup {
+        change.backup_created = true;
+        change.backup_ref = Some(backup.reference.clone());
+    }
+    
+    emit(&Event::FixApplied {
+        file_path: &change.file_path,
+        description: &change.description,
+        backup_path: change.backup_path.as_deref(),
+        backup_ref: change.backup_ref.as_deref(),
+    });
+    
+    Ok(change)
+}
+
+/// Detect the line ending style of a file
+///
+/// # Arguments
+///
+/// * `content` - File content
+///
+/// # Returns
+///
+/// The predominant line ending, or `None` if the content has no line breaks
+pub fn detect_line_ending(content: &str) -> Option<LineEnding> {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    
+    match (crlf, lf) {
+        (0, 0) => None,
+        (crlf, lf) if crlf > lf => Some(LineEnding::CrLf),
+        _ => Some(LineEnding::Lf),
+    }
+}
+
+/// Normalize modified content to the line endings and BOM of the original file
+///
+/// # Arguments
+///
+/// * `original` - Current content of the file
+/// * `modified` - Content about to be written
+///
+/// # Returns
+///
+/// The modified content with the original file's line endings and BOM
+pub fn match_file_format(original: &str, modified: &str) -> String {
+    let body = modified.strip_prefix(UTF8_BOM).unwrap_or(modified);
+    
+    let normalized = match detect_line_ending(original) {
+        Some(LineEnding::CrLf) => body.replace("\r\n", "\n").replace('\n', "\r\n"),
+        Some(LineEnding::Lf) => body.replace("\r\n", "\n"),
+        None => body.to_string(),
+    };
+    
+    if original.starts_with(UTF8_BOM) {
+        format!("{}{}", UTF8_BOM, normalized)
+    } else {
+        normalized
+    }
+}
+
+/// Check that a modification can be applied to the current tree
+///
+/// # Arguments
+///
+/// * `modification` - Modification to check
+///
+/// # Returns
+///
+/// The current content of the target file
+fn check_modification(modification: &CodeModification) -> Result<String> {
+    let file_path = &modification.file_path;
+    
+    // Check if the file exists
+    if !file_path.exists() {
+        return Err(RustAiToolError::Modification(format!(
+            "File not found: {}",
+            file_path.display()
+        )));
+    }
+    
+    // Read the current content
+    let current_content = fs::read_to_string(file_path)
+        .map_err(RustAiToolError::Io)?;
+    
+    // Compare with the original content to make sure it hasn't changed,
+    // ignoring line endings and BOM that may have been lost on the way
+    if current_content != match_file_format(&current_content, &modification.original_content) {
+        return Err(RustAiToolError::Modification(format!(
+            "File {} has been modified since the original content was read",
+            file_path.display()
+        )));
+    }
+    
+    Ok(current_content)
+}
+
+/// Create a unified patch for a list of modifications without touching the tree
+///
+/// Every modification goes through the same checks as `apply_modifications`,
+/// so a patch is only produced if the whole batch would apply cleanly.
+///
+/// # Arguments
+///
+/// * `modifications` - List of modifications to include in the patch
+/// * `base_dir` - Directory that patch paths are made relative to (usually the project root)
+///
+/// # Returns
+///
+/// A patch in unified diff format that can be applied with `git apply`
+pub fn create_patch(modifications: &[CodeModification], base_dir: &Path) -> Result<String> {
+    info!("Creating patch for {} modifications", modifications.len());
+    let mut patch = String::new();
+    
+    for modification in modifications {
+        let current_content = check_modification(modification)?;
+        
+        let relative_path = modification.file_path
+            .strip_prefix(base_dir)
+            .unwrap_or(&modification.file_path);
+        
+        patch.push_str(&generate_unified_diff(
+            relative_path,
+            &current_content,
+            &match_file_format(&current_content, &modification.modified_content),
+        ));
+    }
+    
+    Ok(patch)
+}
+
+/// Preview of a modification against the current file
+#[derive(Debug, Clone, Serialize)]
+pub struct ModificationPreview {
+    /// Path to the file
+    pub file_path: PathBuf,
+    
+    /// Description of the modification
+    pub description: String,
+    
+    /// Confidence level (0-100)
+    pub confidence: u8,
+    
+    /// Whether the file changed since the modification was generated
+    pub drifted: bool,
+    
+    /// Regions of a drifted file the modification could not be merged into
+    pub conflicts: usize,
+    
+    /// Unified diff from the current content to the content after applying
+    pub diff: String,
+}
+
+/// Preview modifications as unified diffs against the current tree
+///
+/// Nothing is checked or written: files that changed since a modification was
+/// generated are flagged as drifted, and their diff shows the modification
+/// merged into the current content, as `apply --merge` would write it.
+///
+/// # Arguments
+///
+/// * `modifications` - Modifications to preview
+/// * `base_dir` - Directory that diff paths are made relative to (usually the project root)
+///
+/// # Returns
+///
+/// A preview of every modification, in order
+pub fn preview_modifications(modifications: &[CodeModification], base_dir: &Path) -> Result<Vec<ModificationPreview>> {
+    let mut previews = Vec::with_capacity(modifications.len());
+    
+    for modification in modifications {
+        let file_path = &modification.file_path;
+        let current_content = if file_path.exists() {
+            fs::read_to_string(file_path).map_err(RustAiToolError::Io)?
+        } else {
+            String::new()
+        };
+        
+        let original_content = match_file_format(&current_content, &modification.original_content);
+        let modified_content = match_file_format(&current_content, &modification.modified_content);
+        let drifted = current_content != original_content;
+        
+        let (new_content, conflicts) = if drifted {
+            debug!("{} changed since the fix was generated", file_path.display());
+            merge_three_way(&original_content, &current_content, &modified_content)
+        } else {
+            (modified_content, Vec::new())
+        };
+        
+        let relative_path = file_path.strip_prefix(base_dir).unwrap_or(file_path);
+        
+        previews.push(ModificationPreview {
+            file_path: file_path.clone(),
+            description: modification.description.clone(),
+            confidence: modification.confidence,
+            drifted,
+            conflicts: conflicts.len(),
+            diff: generate_unified_diff(relative_path, &current_content, &new_content),
+        });
+    }
+    
+    Ok(previews)
+}
+
+/// Generate a git-style unified diff for a single file
+///
+/// # Arguments
+///
+/// * `file_path` - Path of the file as it should appear in the diff headers
+/// * `original` - Original text
+/// * `modified` - Modified text
+///
+/// # Returns
+///
+/// Unified diff with `a/` and `b/` prefixed headers, or an empty string if nothing changed
+pub fn generate_unified_diff(file_path: &Path, original: &str, modified: &str) -> String {
+    if original == modified {
+        return String::new();
+    }
+    
+    let path = file_path.to_string_lossy().replace('\\', "/");
+    let old_header = format!("a/{}", path);
+    let new_header = format!("b/{}", path);
+    
+    let diff = TextDiff::from_lines(original, modified);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(3).header(&old_header, &new_header);
+    
+    format!("diff --git {} {}\n{}", old_header, new_header, unified)
+}
+
+/// Split a modification into hunks
+///
+/// # Arguments
+///
+/// * `original` - Original text
+/// * `modified` - Modified text
+/// * `context` - Number of unchanged context lines around each change
+///
+/// # Returns
+///
+/// Hunks in file order
+pub fn split_into_hunks(original: &str, modified: &str, context: usize) -> Vec<Hunk> {
+    let diff = TextDiff::from_lines(original, modified);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    
+    diff.grouped_ops(context)
+        .iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            
+            let diff_lines = group.iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| {
+                    let sign = match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    };
+                    format!("{}{}", sign, change.value().trim_end_matches(['\r', '\n']))
+                })
+                .collect();
+            
+            Some(Hunk {
+                header: format!(
+                    "@@ -{},{} +{},{} @@",
+                    old_range.start + 1,
+                    old_range.len(),
+                    new_range.start + 1,
+                    new_range.len()
+                ),
+                old_text: old_lines[old_range.clone()].concat(),
+                new_text: new_lines[new_range].concat(),
+                old_range,
+                diff_lines,
+            })
+        })
+        .collect()
+}
+
+/// Rebuild file content from the original text and per-hunk decisions
+///
+/// # Arguments
+///
+/// * `original` - Original text the hunks were computed from
+/// * `hunks` - Hunks in file order
+/// * `decisions` - One decision per hunk
+///
+/// # Returns
+///
+/// The resulting file content
+pub fn apply_hunk_decisions(original: &str, hunks: &[Hunk], decisions: &[HunkDecision]) -> String {
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let mut result = String::with_capacity(original.len());
+    let mut position = 0;
+    
+    for (hunk, decision) in hunks.iter().zip(decisions) {
+        result.extend(lines[position..hunk.old_range.start].iter().copied());
+        
+        match decision {
+            HunkDecision::Accept => result.push_str(&hunk.new_text),
+            HunkDecision::Skip => result.push_str(&hunk.old_text),
+            HunkDecision::Edit(text) => result.push_str(text),
+        }
+        
+        position = hunk.old_range.end;
+    }
+    
+    result.extend(lines[position..].iter().copied());
+    result
+}
+
+/// Rebase a modification onto the current content of its file
+///
+/// Performs a three-way merge between the original content the fix was
+/// generated from, the current content of the file and the modified content.
+/// Changes that do not touch lines edited since are carried over; overlapping
+/// changes are reported as conflicts and the current text is kept.
+///
+/// # Arguments
+///
+/// * `modification` - Modification to rebase
+///
+/// # Returns
+///
+/// The rebased modification and the conflicts found
+pub fn rebase_modification(modification: &CodeModification) -> Result<MergeOutcome> {
+    let file_path = &modification.file_path;
+    
+    if !file_path.exists() {
+        return Err(RustAiToolError::Modification(format!(
+            "File not found: {}",
+            file_path.display()
+        )));
+    }
+    
+    let current_content = fs::read_to_string(file_path)
+        .map_err(RustAiToolError::Io)?;
+    
+    if current_content == modification.original_content {
+        return Ok(MergeOutcome {
+            modification: modification.clone(),
+            conflicts: Vec::new(),
+        });
+    }
+    
+    debug!("{} changed since the fix was generated, merging", file_path.display());
+    
+    let (merged, conflicts) = merge_three_way(
+        &modification.original_content,
+        &current_content,
+        &modification.modified_content,
+    );
+    
+    let conflicts = conflicts.into_iter()
+        .map(|mut conflict| {
+            conflict.file_path = file_path.clone();
+            conflict
+        })
+        .collect();
+    
+    Ok(MergeOutcome {
+        modification: create_modification(
+            file_path.clone(),
+            current_content,
+            merged,
+            modification.description.clone(),
+            modification.confidence,
+        ),
+        conflicts,
+    })
+}
+
+/// Merge the changes from `original` to `modified` into `current`
+///
+/// # Returns
+///
+/// The merged text and the conflicting regions (with an empty `file_path`)
+pub fn merge_three_way(original: &str, current: &str, modified: &str) -> (String, Vec<MergeConflict>) {
+    let current_diff = TextDiff::from_lines(original, current);
+    let modified_diff = TextDiff::from_lines(original, modified);
+    let original_lines = current_diff.old_slices();
+    let current_lines = current_diff.new_slices();
+    let modified_lines = modified_diff.new_slices();
+    
+    let current_changes: Vec<_> = current_diff.ops().iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .collect();
+    
+    // Map a line of the original to the same line in the current content
+    let to_current = |line: usize| -> usize {
+        for op in current_diff.ops() {
+            let old = op.old_range();
+            if old.start <= line && line < old.end {
+                return match op.tag() {
+                    DiffTag::Equal => op.new_range().start + (line - old.start),
+                    _ => op.new_range().start,
+                };
+            }
+        }
+        current_lines.len()
+    };
+    
+    let mut replacements = Vec::new();
+    let mut conflicts = Vec::new();
+    
+    for op in modified_diff.ops().iter().filter(|op| op.tag() != DiffTag::Equal) {
+        let old = op.old_range();
+        let new_text = modified_lines[op.new_range()].concat();
+        
+        let overlapping: Vec<_> = current_changes.iter()
+            .filter(|c| ranges_touch(&c.old_range(), &old))
+            .collect();
+        
+        if overlapping.is_empty() {
+            let start = to_current(old.start);
+            replacements.push((start..start + old.len(), new_text));
+            continue;
+        }
+        
+        // Both sides made the same change
+        if let [change] = overlapping.as_slice() {
+            if change.old_range() == old && current_lines[change.new_range()].concat() == new_text {
+                continue;
+            }
+        }
+        
+        let region_start = overlapping.iter().map(|c| c.old_range().start).chain([old.start]).min().unwrap_or(old.start);
+        let region_end = overlapping.iter().map(|c| c.old_range().end).chain([old.end]).max().unwrap_or(old.end);
+        let current_start = overlapping.iter().map(|c| c.new_range().start).chain([to_current(region_start)]).min().unwrap_or(0);
+        let current_end = overlapping.iter().map(|c| c.new_range().end).chain([to_current(region_end)]).max().unwrap_or(0);
+        
+        let modified_region = [
+            &original_lines[region_start..old.start],
+            &modified_lines[op.new_range()],
+            &original_lines[old.end..region_end],
+        ].concat();
+        let current_region = &current_lines[current_start..current_end];
+        
+        // The current side already contains this change
+        if contains_change(current_region, &original_lines[old.clone()], &modified_lines[op.new_range()]) {
+            continue;
+        }
+        
+        // This change contains every overlapping change of the current side
+        let nests_current = replacements.last().is_none_or(|(range, _): &(Range<usize>, String)| range.end <= current_start)
+            && overlapping.iter().all(|c| {
+                contains_change(&modified_region, &original_lines[c.old_range()], &current_lines[c.new_range()])
+            });
+        if nests_current {
+            replacements.push((current_start..current_end, modified_region.concat()));
+            continue;
+        }
+        
+        conflicts.push(MergeConflict {
+            file_path: PathBuf::new(),
+            line_start: region_start + 1,
+            line_end: region_end.max(region_start + 1),
+            current_text: current_lines[current_start..current_end].concat(),
+            modified_text: new_text,
+        });
+    }
+    
+    debug!(
+        "Three-way merge over {} original lines: {} changes carried over, {} conflicts",
+        original_lines.len(),
+        replacements.len(),
+        conflicts.len()
+    );
+    
+    let mut merged = String::with_capacity(current.len());
+    let mut position = 0;
+    
+    for (range, text) in replacements {
+        merged.extend(current_lines[position..range.start].iter().copied());
+        merged.push_str(&text);
+        position = range.end;
+    }
+    
+    merged.extend(current_lines[position..].iter().copied());
+    (merged, conflicts)
 }
 
-/// Apply a list of code modifications
+/// Whether a region contains a change: all of its added lines and none of the lines it removed
+fn contains_change(region: &[&str], removed: &[&str], added: &[&str]) -> bool {
+    added.iter().all(|line| region.contains(line))
+        && removed.iter()
+            .filter(|line| !added.contains(line))
+            .all(|line| !region.contains(line))
+}
+
+/// Whether two line ranges overlap, treating insertions as touching their neighbours
+fn ranges_touch(a: &Range<usize>, b: &Range<usize>) -> bool {
+    let start = a.start.max(b.start);
+    let end = a.end.min(b.end);
+    
+    if a.is_empty() || b.is_empty() {
+        start <= end
+    } else {
+        start < end
+    }
+}
+
+/// Coalesce modifications that edit the same file from the same original content
+///
+/// Fixes are merged in order of decreasing confidence. Identical and nested
+/// edits are combined; an edit that genuinely conflicts with a higher-confidence
+/// fix is left out of the merged result and reported for manual resolution.
 ///
 /// # Arguments
 ///
-/// * `modifications` - List of modifications to apply
-/// * `create_backup` - Whether to create backups of modified files
+/// * `modifications` - Modifications to coalesce
 ///
 /// # Returns
 ///
-/// List of applied changes
-pub fn apply_modifications(
-    modifications: &[CodeModification],
-    create_backup: bool,
-) -> Result<Vec<FileChange>> {
-    info!("Applying {} modifications with backup={}", modifications.len(), create_backup);
-    let mut changes = Vec::new();
+/// The coalesced modifications, in order of first appearance, and the conflicts
+pub fn coalesce_modifications(modifications: Vec<CodeModification>) -> CoalesceOutcome {
+    let mut groups: Vec<Vec<CodeModification>> = Vec::new();
     
-    for (i, modification) in modifications.iter().enumerate() {
-        debug!("Applying modification #{} to {}", i + 1, modification.file_path.display());
-        match apply_modification(modification, create_backup) {
-            Ok(change) => {
-                info!("Successfully applied modification to {}", modification.file_path.display());
-                changes.push(change);
-            },
-            Err(e) => {
-                error!(
-                    "Failed to apply modification to {}: {}",
+    for modification in modifications {
+        let group = groups.iter_mut().find(|g| {
+            g[0].file_path == modification.file_path && g[0].original_content == modification.original_content
+        });
+        
+        match group {
+            Some(group) => group.push(modification),
+            None => groups.push(vec![modification]),
+        }
+    }
+    
+    let mut outcome = CoalesceOutcome {
+        modifications: Vec::with_capacity(groups.len()),
+        conflicts: Vec::new(),
+    };
+    
+    for mut group in groups {
+        if group.len() == 1 {
+            outcome.modifications.extend(group);
+            continue;
+        }
+        
+        debug!("Coalescing {} fixes for {}", group.len(), group[0].file_path.display());
+        group.sort_by_key(|modification| std::cmp::Reverse(modification.confidence));
+        
+        let mut merged = group[0].clone();
+        let mut descriptions = vec![merged.description.clone()];
+        
+        for modification in &group[1..] {
+            let (content, conflicts) = merge_three_way(
+                &merged.original_content,
+                &merged.modified_content,
+                &modification.modified_content,
+            );
+            
+            for mut conflict in conflicts {
+                warn!(
+                    "Fix '{}' conflicts with '{}' in {} at lines {}-{}",
+                    modification.description,
+                    descriptions[0],
                     modification.file_path.display(),
-                    e
+                    conflict.line_start,
+                    conflict.line_end
                 );
-                return Err(e);
+                conflict.file_path = modification.file_path.clone();
+                outcome.conflicts.push(conflict);
             }
+            
+            if content != merged.modified_content && !descriptions.contains(&modification.description) {
+                descriptions.push(modification.description.clone());
+            }
+            
+            merged.modified_content = content;
+            merged.confidence = merged.confidence.min(modification.confidence);
         }
+        
+        merged.description = descriptions.join("; ");
+        outcome.modifications.push(merged);
     }
     
-    info!("Successfully applied {} modifications", changes.len());
-    Ok(changes)
+    outcome
 }
 
-/// Apply a single code modification
+/// Split modifications by confidence level
 ///
 /// # Arguments
 ///
-/// * `modification` - Modification to apply
-/// * `create_backup` - Whether to create a backup of the modified file
+/// * `modifications` - Modifications to split
+/// * `min_confidence` - Minimum confidence (0-100) for a modification to be applied automatically
 ///
 /// # Returns
 ///
-/// The file change
-fn apply_modification(
-    modification: &CodeModification,
-    create_backup: bool,
-) -> Result<FileChange> {
-    let file_path = &modification.file_path;
-    
-    // Check if the file exists
-    if !file_path.exists() {
-        return Err(RustAiToolError::Modification(format!(
-            "File not found: {}",
-            file_path.display()
-        )));
-    }
-    
-    // Read the current content
-    let current_content = fs::read_to_string(file_path)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Compare with the original content to make sure it hasn't changed
-    if current_content != modification.original_content {
-        return Err(RustAiToolError::Modification(format!(
-            "File {} has been modified since the original content was read",
-            file_path.display()
-        )));
-    }
-    
-    // Create a backup if requested
-    let backup_path = if create_backup {
-        let backup_file = file_path.with_extension("bak");
-        fs::write(&backup_file, &current_content)
-            .map_err(|e| RustAiToolError::Io(e))?;
-        debug!("Created backup at {}", backup_file.display());
-        Some(backup_file)
-    } else {
-        None
-    };
+/// The modifications to apply directly and the ones left for human review
+pub fn partition_by_confidence(
+    modifications: Vec<CodeModification>,
+    min_confidence: u8,
+) -> (Vec<CodeModification>, Vec<CodeModification>) {
+    let (confident, pending): (Vec<_>, Vec<_>) = modifications.into_iter()
+        .partition(|m| m.confidence >= min_confidence);
     
-    // Write the modified content
-    fs::write(file_path, &modification.modified_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    info!(
+        "{} modifications at or above {}% confidence, {} left for review",
+        confident.len(),
+        min_confidence,
+        pending.len()
+    );
     
-    Ok(FileChange {
-        file_path: file_path.to_path_buf(),
-        original_content: Some(current_content),
-        new_content: modification.modified_content.clone(),
-        description: modification.description.clone(),
-        backup_created: backup_path.is_some(),
-        backup_path,
-    })
+    (confident, pending)
 }
 
 /// Apply validated fixes
@@ -187,10 +1065,16 @@ pub fn apply_validated_fixes(
         warn!("Skipping {} invalid modifications", invalid_count);
     }
     
+    let git_backup = if create_backup {
+        backup_to_git(valid_modifications.iter().copied())
+    } else {
+        None
+    };
+    
     // Apply only the valid modifications
     let mut changes = Vec::new();
     for modification in valid_modifications {
-        match apply_modification(modification, create_backup) {
+        match apply_with_backup(modification, create_backup, git_backup.as_ref()) {
             Ok(change) => {
                 changes.push(change);
             },
@@ -238,10 +1122,17 @@ pub fn create_change_report(changes: &[FileChange]) -> String {
             report.push_str("```\n\n");
         }
         
-        if change.backup_created {
+        if let Some(backup_ref) = &change.backup_ref {
+            report.push_str(&format!(
+                "Backup created: {} (restore with `git checkout {} -- {}`)\n\n",
+                backup_ref,
+                backup_ref,
+                change.file_path.display()
+            ));
+        } else if let Some(backup_path) = &change.backup_path {
             report.push_str(&format!(
                 "Backup created: {}\n\n",
-                change.backup_path.as_ref().unwrap().display()
+                backup_path.display()
             ));
         }
         
@@ -288,6 +1179,84 @@ fn generate_diff(original: &str, modified: &str) -> String {
     diff
 }
 
+/// Outcome of verifying applied changes
+#[derive(Debug, Clone, Default)]
+pub struct VerificationResult {
+    /// Whether the project still compiles
+    pub compiles: bool,
+    
+    /// Files reformatted by rustfmt
+    pub formatted: Vec<PathBuf>,
+    
+    /// Errors reported by `cargo check`
+    pub errors: Vec<String>,
+}
+
+/// Format changed files with rustfmt and check that the project still compiles
+///
+/// The `new_content` of reformatted changes is updated to match the files.
+///
+/// # Arguments
+///
+/// * `changes` - Applied changes
+/// * `project_path` - Path to the project to check
+///
+/// # Returns
+///
+/// Verification result
+pub fn verify_changes(changes: &mut [FileChange], project_path: &Path) -> Result<VerificationResult> {
+    let mut result = VerificationResult::default();
+    
+    // Absolute paths, as rustfmt runs in the project (or in a container)
+    let rust_files: Vec<PathBuf> = changes.iter()
+        .map(|c| &c.file_path)
+        .filter(|p| p.extension().is_some_and(|ext| ext == "rs"))
+        .filter_map(|p| p.canonicalize().ok())
+        .collect();
+    
+    if !rust_files.is_empty() {
+        debug!("Running rustfmt on {} files", rust_files.len());
+        
+        let formatted = CargoCommand::new(project_path)
+            .program("rustfmt")
+            .args(["--edition", "2021"])
+            .args(&rust_files)
+            .output();
+        
+        match formatted {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => warn!("rustfmt failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Could not run rustfmt, skipping formatting: {}", e),
+        }
+    }
+    
+    for change in changes.iter_mut() {
+        let content = fs::read_to_string(&change.file_path)
+            .map_err(RustAiToolError::Io)?;
+        
+        if content != change.new_content {
+            result.formatted.push(change.file_path.clone());
+            change.new_content = content;
+        }
+    }
+    
+    info!("Running cargo check on {}", project_path.display());
+    
+    let output = CargoCommand::new(project_path)
+        .args(["check", "--all-targets", "--message-format=short"])
+        .output()
+        .map_err(|e| RustAiToolError::Validation(format!("Failed to execute cargo check: {}", e)))?;
+    
+    result.compiles = output.status.success();
+    result.errors = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| line.contains("error"))
+        .map(String::from)
+        .collect();
+    
+    Ok(result)
+}
+
 /// Restore files from backups
 ///
 /// # Arguments
@@ -301,24 +1270,9 @@ pub fn restore_backups(changes: &[FileChange]) -> Result<usize> {
     let mut restored = 0;
     
     for change in changes {
-        if let Some(backup_path) = &change.backup_path {
-            if backup_path.exists() {
-                // Read the backup content
-                let backup_content = fs::read_to_string(backup_path)
-                    .map_err(|e| RustAiToolError::Io(e))?;
-                
-                // Write it back to the original file
-                fs::write(&change.file_path, backup_content)
-                    .map_err(|e| RustAiToolError::Io(e))?;
-                
-                // Remove the backup file
-                fs::remove_file(backup_path).map_err(|e| RustAiToolError::Io(e))?;
-                
-                restored += 1;
-                info!("Restored {} from backup", change.file_path.display());
-            } else {
-                warn!("Backup file not found: {}", backup_path.display());
-            }
+        if restore_file(&change.file_path, change.backup_path.as_deref(), change.backup_ref.as_deref())? {
+            restored += 1;
+            info!("Restored {} from backup", change.file_path.display());
         }
     }
     
@@ -354,6 +1308,8 @@ pub fn apply_file_changes(
         let current_content = fs::read_to_string(file_path)
             .map_err(|e| RustAiToolError::Io(e))?;
         
+        let new_content = &match_file_format(&current_content, new_content);
+        
         // Skip if the content is already the same
         if current_content == *new_content {
             info!("Skipping {} - content unchanged", file_path.display());
@@ -362,11 +1318,7 @@ pub fn apply_file_changes(
         
         // Create a backup if requested
         let backup_path = if create_backup {
-            let backup_file = file_path.with_extension("bak");
-            fs::write(&backup_file, &current_content)
-                .map_err(|e| RustAiToolError::Io(e))?;
-            info!("Created backup at {}", backup_file.display());
-            Some(backup_file)
+            Some(write_file_backup(file_path, &current_content)?)
         } else {
             None
         };
@@ -384,6 +1336,7 @@ pub fn apply_file_changes(
             description: "Modified file content".to_string(),
             backup_created: backup_path.is_some(),
             backup_path,
+            backup_ref: None,
         });
     }
     
@@ -420,16 +1373,12 @@ pub fn update_code_section(
         )));
     }
     
-    // Replace the text
-    let new_content = current_content.replace(search_text, replacement);
+    // Replace the text, keeping the file's line ending style
+    let new_content = match_file_format(&current_content, &current_content.replace(search_text, replacement));
     
     // Create a backup if requested
     let backup_path = if create_backup {
-        let backup_file = file_path.with_extension("bak");
-        fs::write(&backup_file, &current_content)
-            .map_err(|e| RustAiToolError::Io(e))?;
-        debug!("Created backup at {}", backup_file.display());
-        Some(backup_file)
+        Some(write_file_backup(file_path, &current_content)?)
     } else {
         None
     };
@@ -447,6 +1396,7 @@ pub fn update_code_section(
         description: format!("Updated code section in {}", file_path.display()),
         backup_created: backup_path.is_some(),
         backup_path,
+        backup_ref: None,
     })
 }
 
@@ -476,6 +1426,230 @@ pub fn create_modification(
         modified_content,
         description,
         confidence,
+        resolves: Vec::new(),
+    }
+}
+
+/// A set of edits across files that is previewed, validated and committed as a whole
+///
+/// Edits to the same file are stacked on top of each other, so the session
+/// holds at most one modification per file.
+pub struct EditSession {
+    /// Directory that preview paths are made relative to
+    base_dir: PathBuf,
+    
+    /// Whether to back up files when committing
+    create_backup: bool,
+    
+    /// Queued modifications, one per file
+    modifications: Vec<CodeModification>,
+}
+
+impl EditSession {
+    /// Create an empty session
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dir` - Directory that preview paths are made relative to (usually the project root)
+    ///
+    /// # Returns
+    ///
+    /// The session, with backups disabled
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            create_backup: false,
+            modifications: Vec::new(),
+        }
+    }
+    
+    /// Back up files before they are written on commit
+    pub fn with_backup(mut self, create_backup: bool) -> Self {
+        self.create_backup = create_backup;
+        self
+    }
+    
+    /// Queue a new content for a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - File to edit
+    /// * `new_content` - Content after the edit
+    /// * `description` - Description of the edit
+    ///
+    /// # Returns
+    ///
+    /// The session, for chaining
+    pub fn edit(&mut self, file_path: impl AsRef<Path>, new_content: impl Into<String>, description: &str) -> Result<&mut Self> {
+        let file_path = file_path.as_ref();
+        let new_content = new_content.into();
+        
+        match self.modifications.iter_mut().find(|m| m.file_path == file_path) {
+            Some(modification) => {
+                modification.modified_content = new_content;
+                modification.description = format!("{}; {}", modification.description, description);
+            }
+            None => {
+                let original_content = fs::read_to_string(file_path)
+                    .map_err(RustAiToolError::Io)?;
+                
+                self.modifications.push(create_modification(
+                    file_path.to_path_buf(),
+                    original_content,
+                    new_content,
+                    description.to_string(),
+                    100,
+                ));
+            }
+        }
+        
+        Ok(self)
+    }
+    
+    /// Queue a text replacement in a file, on top of the edits already queued for it
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - File to edit
+    /// * `search_text` - Text to search for
+    /// * `replacement` - Replacement text
+    /// * `description` - Description of the edit
+    ///
+    /// # Returns
+    ///
+    /// The session, for chaining
+    pub fn replace(&mut self, file_path: impl AsRef<Path>, search_text: &str, replacement: &str, description: &str) -> Result<&mut Self> {
+        let file_path = file_path.as_ref();
+        let current = self.content(file_path)?;
+        
+        if !current.contains(search_text) {
+            return Err(RustAiToolError::Modification(format!(
+                "Search text not found in {}",
+                file_path.display()
+            )));
+        }
+        
+        let new_content = current.replace(search_text, replacement);
+        self.edit(file_path, new_content, description)
+    }
+    
+    /// Queue an existing modification
+    ///
+    /// Modifications to a file that already has queued edits are merged into them;
+    /// conflicting regions are returned and left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `modification` - Modification to queue
+    ///
+    /// # Returns
+    ///
+    /// The conflicts with previously queued edits
+    pub fn add_modification(&mut self, modification: CodeModification) -> Vec<MergeConflict> {
+        let queued = self.modifications.iter()
+            .position(|m| m.file_path == modification.file_path && m.original_content == modification.original_content);
+        
+        let index = match queued {
+            Some(index) => index,
+            None => {
+                self.modifications.push(modification);
+                return Vec::new();
+            }
+        };
+        
+        let queued = self.modifications.remove(index);
+        let outcome = coalesce_modifications(vec![queued, modification]);
+        self.modifications.splice(index..index, outcome.modifications);
+        outcome.conflicts
+    }
+    
+    /// Content a file will have once the session is committed
+    pub fn content(&self, file_path: &Path) -> Result<String> {
+        match self.modifications.iter().find(|m| m.file_path == file_path) {
+            Some(modification) => Ok(modification.modified_content.clone()),
+            None => fs::read_to_string(file_path).map_err(RustAiToolError::Io),
+        }
+    }
+    
+    /// Queued modifications, one per file
+    pub fn modifications(&self) -> &[CodeModification] {
+        &self.modifications
+    }
+    
+    /// Combined unified diff of all queued edits
+    pub fn preview(&self) -> Result<String> {
+        create_patch(&self.modifications, &self.base_dir)
+    }
+    
+    /// Validate the queued edits
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Validation options
+    ///
+    /// # Returns
+    ///
+    /// One validation result per edited file
+    pub fn validate(&self, options: &ValidationOptions) -> Result<Vec<ValidationResult>> {
+        let fixes: Vec<FixToValidate> = self.modifications.iter()
+            .map(|m| FixToValidate {
+                file_path: m.file_path.clone(),
+                original_code: m.original_content.clone(),
+                modified_code: m.modified_content.clone(),
+                description: m.description.clone(),
+            })
+            .collect();
+        
+        validate_fixes(&fixes, options)
+    }
+    
+    /// Write all queued edits
+    ///
+    /// Every file is checked before anything is written. If a write fails,
+    /// the files already written are put back, so either all edits land or none.
+    ///
+    /// # Returns
+    ///
+    /// The applied changes
+    pub fn commit(self) -> Result<Vec<FileChange>> {
+        info!("Committing edit session with {} files", self.modifications.len());
+        
+        for modification in &self.modifications {
+            check_modification(modification)?;
+        }
+        
+        let git_backup = if self.create_backup {
+            backup_to_git(self.modifications.iter())
+        } else {
+            None
+        };
+        
+        let mut changes: Vec<FileChange> = Vec::with_capacity(self.modifications.len());
+        
+        for modification in &self.modifications {
+            match apply_with_backup(modification, self.create_backup, git_backup.as_ref()) {
+                Ok(change) => changes.push(change),
+                Err(e) => {
+                    error!(
+                        "Failed to write {}, rolling back {} files",
+                        modification.file_path.display(),
+                        changes.len()
+                    );
+                    
+                    for change in &changes {
+                        if let Some(original) = &change.original_content {
+                            if let Err(restore_error) = fs::write(&change.file_path, original) {
+                                error!("Failed to roll back {}: {}", change.file_path.display(), restore_error);
+                            }
+                        }
+                    }
+                    
+                    return Err(e);
+                }
+            }
+        }
+        
+        Ok(changes)
     }
 }
 
@@ -488,6 +1662,7 @@ mod tests {
     fn test_apply_modification() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test.rs");
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
         
         let original_content = "fn main() {\n    println!(\"Hello\");\n}";
         fs::write(&file_path, original_content).unwrap();
@@ -500,6 +1675,7 @@ mod tests {
             modified_content: modified_content.to_string(),
             description: "Update greeting".to_string(),
             confidence: 90,
+            resolves: Vec::new(),
         };
         
         let change = apply_modification(&modification, true).unwrap();
@@ -515,8 +1691,237 @@ mod tests {
         assert_eq!(updated_content, modified_content);
         
         // Check that the backup was created
-        let backup_path = file_path.with_extension("bak");
+        let backup_path = change.backup_path.unwrap();
+        assert!(backup_path.starts_with(dir.path().join(".rust-ai-tool").join("backups")));
         let backup_content = fs::read_to_string(&backup_path).unwrap();
         assert_eq!(backup_content, original_content);
     }
+    
+    #[test]
+    fn test_apply_modifications_keep_going() {
+        let dir = tempdir().unwrap();
+        let modification = |name: &str, original: &str, modified: &str| CodeModification {
+            file_path: dir.path().join(name),
+            original_content: original.to_string(),
+            modified_content: modified.to_string(),
+            description: format!("Update {}", name),
+            confidence: 90,
+            resolves: Vec::new(),
+        };
+        
+        fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        fs::write(dir.path().join("b.rs"), "fn b() {}\n").unwrap();
+        fs::write(dir.path().join("c.rs"), "fn c2() {}\n").unwrap();
+        
+        let modifications = vec![
+            modification("a.rs", "fn a() {}\n", "fn a2() {}\n"),
+            modification("b.rs", "fn stale() {}\n", "fn b2() {}\n"),
+            modification("c.rs", "fn c() {}\n", "fn c2() {}\n"),
+            modification("missing.rs", "", "fn d() {}\n"),
+        ];
+        
+        let summary = apply_modifications_keep_going(&modifications, false);
+        
+        assert_eq!(summary.applied.len(), 1);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.failed.len(), 2);
+        assert_eq!(summary.skipped[0].file_path, dir.path().join("c.rs"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "fn a2() {}\n");
+        assert_eq!(fs::read_to_string(dir.path().join("b.rs")).unwrap(), "fn b() {}\n");
+    }
+    
+    #[test]
+    fn test_match_file_format() {
+        let original = "\u{feff}fn main() {\r\n    run();\r\n}\r\n";
+        let modified = "fn main() {\n    run();\n    done();\n}\n";
+        
+        assert_eq!(detect_line_ending(original), Some(LineEnding::CrLf));
+        assert_eq!(
+            match_file_format(original, modified),
+            "\u{feff}fn main() {\r\n    run();\r\n    done();\r\n}\r\n"
+        );
+        assert_eq!(match_file_format("a\nb\n", "a\r\nc\r\n"), "a\nc\n");
+    }
+    
+    #[test]
+    fn test_coalesce_modifications() {
+        let original = "fn main() {\n    let v = vec![1];\n    if v.len() == 0 {\n        return;\n    }\n}\n";
+        let fix = |modified: &str, description: &str, confidence: u8| CodeModification {
+            file_path: PathBuf::from("src/main.rs"),
+            original_content: original.to_string(),
+            modified_content: modified.to_string(),
+            description: description.to_string(),
+            confidence,
+            resolves: Vec::new(),
+        };
+        
+        let clippy = original.replace("v.len() == 0", "v.is_empty()");
+        let nested = clippy.replace("        return;\n", "        println!(\"empty\");\n        return;\n");
+        let conflicting = original.replace("v.len() == 0", "v.len() < 1");
+        
+        let outcome = coalesce_modifications(vec![
+            fix(&clippy, "Use is_empty", 95),
+            fix(&clippy, "Use is_empty", 90),
+            fix(&nested, "Log empty input", 80),
+            fix(&conflicting, "Compare with 1", 70),
+        ]);
+        
+        assert_eq!(outcome.modifications.len(), 1);
+        assert_eq!(outcome.modifications[0].modified_content, nested);
+        assert_eq!(outcome.modifications[0].description, "Use is_empty; Log empty input");
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].line_start, 3);
+    }
+    
+    #[test]
+    fn test_edit_session() {
+        let dir = tempdir().unwrap();
+        let main_path = dir.path().join("main.rs");
+        let lib_path = dir.path().join("lib.rs");
+        fs::write(&main_path, "fn main() {\n    old();\n}\n").unwrap();
+        fs::write(&lib_path, "pub fn old() {}\n").unwrap();
+        
+        let mut session = EditSession::new(dir.path());
+        session.replace(&main_path, "old()", "new()", "Call new").unwrap()
+            .replace(&lib_path, "fn old", "fn new", "Rename old").unwrap()
+            .replace(&main_path, "fn main", "pub fn main", "Make main public").unwrap();
+        
+        assert_eq!(session.modifications().len(), 2);
+        
+        let preview = session.preview().unwrap();
+        assert!(preview.contains("+pub fn main() {"));
+        assert!(preview.contains("+pub fn new() {}"));
+        assert_eq!(fs::read_to_string(&lib_path).unwrap(), "pub fn old() {}\n");
+        
+        // A file changed behind the session's back aborts the whole commit
+        fs::write(&lib_path, "pub fn other() {}\n").unwrap();
+        let mut stale = EditSession::new(dir.path());
+        stale.add_modification(session.modifications()[1].clone());
+        stale.add_modification(session.modifications()[0].clone());
+        assert!(stale.commit().is_err());
+        assert_eq!(fs::read_to_string(&main_path).unwrap(), "fn main() {\n    old();\n}\n");
+        
+        fs::write(&lib_path, "pub fn old() {}\n").unwrap();
+        let changes = session.commit().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(fs::read_to_string(&main_path).unwrap(), "pub fn main() {\n    new();\n}\n");
+    }
+    
+    #[test]
+    fn test_create_patch_leaves_tree_untouched() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("src").join("main.rs");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        
+        let original_content = "fn main() {\n    println!(\"Hello\");\n}\n";
+        fs::write(&file_path, original_content).unwrap();
+        
+        let modification = create_modification(
+            file_path.clone(),
+            original_content.to_string(),
+            "fn main() {\n    println!(\"Hello, world!\");\n}\n".to_string(),
+            "Update greeting".to_string(),
+            90,
+        );
+        
+        let patch = create_patch(&[modification], dir.path()).unwrap();
+        
+        assert!(patch.starts_with("diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n"));
+        assert!(patch.contains("-    println!(\"Hello\");\n"));
+        assert!(patch.contains("+    println!(\"Hello, world!\");\n"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), original_content);
+    }
+    
+    #[test]
+    fn test_preview_modifications_flags_drift() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("lib.rs");
+        
+        let original_content = "fn a() {}\n\nfn b() {}\n";
+        let modification = create_modification(
+            file_path.clone(),
+            original_content.to_string(),
+            "fn a() {}\n\nfn b() -> u8 { 0 }\n".to_string(),
+            "Return a value".to_string(),
+            80,
+        );
+        
+        fs::write(&file_path, original_content).unwrap();
+        let previews = preview_modifications(std::slice::from_ref(&modification), dir.path()).unwrap();
+        assert!(!previews[0].drifted);
+        assert!(previews[0].diff.starts_with("diff --git a/lib.rs b/lib.rs\n"));
+        assert!(previews[0].diff.contains("+fn b() -> u8 { 0 }\n"));
+        
+        fs::write(&file_path, "fn a() -> bool { true }\n\nfn b() {}\n").unwrap();
+        let previews = preview_modifications(&[modification], dir.path()).unwrap();
+        assert!(previews[0].drifted);
+        assert_eq!(previews[0].conflicts, 0);
+        assert!(!previews[0].diff.contains("-fn a() -> bool { true }"));
+        assert!(previews[0].diff.contains("+fn b() -> u8 { 0 }\n"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn a() -> bool { true }\n\nfn b() {}\n");
+    }
+    
+    #[test]
+    fn test_partition_by_confidence() {
+        let modification = |confidence| create_modification(
+            PathBuf::from("src/lib.rs"),
+            String::new(),
+            String::new(),
+            format!("Fix with confidence {}", confidence),
+            confidence,
+        );
+        
+        let (confident, pending) = partition_by_confidence(
+            vec![modification(95), modification(40), modification(80)],
+            80,
+        );
+        
+        assert_eq!(confident.iter().map(|m| m.confidence).collect::<Vec<_>>(), vec![95, 80]);
+        assert_eq!(pending.iter().map(|m| m.confidence).collect::<Vec<_>>(), vec![40]);
+    }
+    
+    #[test]
+    fn test_merge_three_way() {
+        let original = (1..=10).map(|i| format!("line {}\n", i)).collect::<String>();
+        let current = original.replace("line 2\n", "line 2 (edited)\n");
+        
+        // Non-overlapping changes are carried over onto the current content
+        let modified = original.replace("line 8\n", "line eight\n");
+        let (merged, conflicts) = merge_three_way(&original, &current, &modified);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged, current.replace("line 8\n", "line eight\n"));
+        
+        // Overlapping changes are reported and the current text is kept
+        let modified = original.replace("line 2\n", "line two\n");
+        let (merged, conflicts) = merge_three_way(&original, &current, &modified);
+        assert_eq!(merged, current);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!((conflicts[0].line_start, conflicts[0].line_end), (2, 2));
+        assert_eq!(conflicts[0].current_text, "line 2 (edited)\n");
+        assert_eq!(conflicts[0].modified_text, "line two\n");
+        
+        // Identical changes on both sides are not conflicts
+        let (merged, conflicts) = merge_three_way(&original, &current, &current);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged, current);
+    }
+    
+    #[test]
+    fn test_apply_hunk_decisions() {
+        let original = (1..=20).map(|i| format!("line {}\n", i)).collect::<String>();
+        let modified = original.replace("line 2\n", "line two\n").replace("line 18\n", "line eighteen\n");
+        
+        let hunks = split_into_hunks(&original, &modified, 3);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header, "@@ -1,5 +1,5 @@");
+        
+        let all = apply_hunk_decisions(&original, &hunks, &[HunkDecision::Accept, HunkDecision::Accept]);
+        assert_eq!(all, modified);
+        
+        let none = apply_hunk_decisions(&original, &hunks, &[HunkDecision::Skip, HunkDecision::Skip]);
+        assert_eq!(none, original);
+        
+        let partial = apply_hunk_decisions(&original, &hunks, &[HunkDecision::Skip, HunkDecision::Accept]);
+        assert!(partial.contains("line 2\n") && partial.contains("line eighteen\n"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+//! Network module
+//!
+//! This module applies the `[network]` settings to everything that goes online,
+//! so rust-ai-tool works behind corporate proxies and TLS inspection:
+//! - HTTP clients of AI providers, Gitea and Bitbucket use the proxy (except for
+//!   the `no_proxy` hosts), trust the certificate authorities of `ca_bundle` in
+//!   addition to the system ones, and skip verification if `verify_tls` is off
+//! - git and Cargo, run for cloning, pushing and analysis, get the settings
+//!   through their environment variables (`https_proxy`, `no_proxy`,
+//!   `GIT_SSL_CAINFO`, `CARGO_HTTP_CAINFO`, `GIT_SSL_NO_VERIFY`), as do plugins
+//! - The GitHub client trusts `ca_bundle` through `SSL_CERT_FILE`, but connects
+//!   directly and always verifies certificates; a warning says so when a proxy
+//!   is configured or verification is off
+//! - For git, Cargo and the GitHub client the bundle replaces the system
+//!   certificate authorities, so it must hold every root they need
+
+use crate::{NetworkConfig, Result, RustAiToolError};
+use log::{debug, warn};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use std::fs;
+use std::sync::{Once, OnceLock};
+
+/// Settings applied to the HTTP clients of this process
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Parsed network settings
+#[derive(Debug, Clone)]
+struct Settings {
+    /// Proxy for all requests (the environment proxies if `None`)
+    proxy: Option<Proxy>,
+
+    /// Additional trusted certificate authorities
+    certificates: Vec<Certificate>,
+
+    /// Whether certificates are verified
+    verify_tls: bool,
+}
+
+/// Apply network settings to the HTTP clients and child processes of this process
+///
+/// Only the first call changes the HTTP clients; the settings are checked on every call.
+///
+/// # Arguments
+///
+/// * `config` - Network settings
+///
+/// # Returns
+///
+/// An error if the proxy URL is invalid or the CA bundle cannot be read
+pub fn configure(config: &NetworkConfig) -> Result<()> {
+    let settings = parse_settings(config)?;
+
+    if let Some(proxy) = &config.proxy {
+        debug!("Using proxy {}", proxy);
+        for name in ["http_proxy", "https_proxy", "HTTP_PROXY", "HTTPS_PROXY"] {
+            std::env::set_var(name, proxy);
+        }
+    }
+    if !config.no_proxy.is_empty() {
+        let no_proxy = config.no_proxy.join(",");
+        std::env::set_var("no_proxy", &no_proxy);
+        std::env::set_var("NO_PROXY", &no_proxy);
+    }
+    if let Some(ca_bundle) = &config.ca_bundle {
+        for name in ["SSL_CERT_FILE", "GIT_SSL_CAINFO", "CARGO_HTTP_CAINFO"] {
+            std::env::set_var(name, ca_bundle);
+        }
+    }
+    if !config.verify_tls {
+        warn!("TLS certificate verification is disabled");
+        std::env::set_var("GIT_SSL_NO_VERIFY", "true");
+    }
+
+    let _ = SETTINGS.set(settings);
+    Ok(())
+}
+
+/// Builder of an HTTP client with the network settings applied
+///
+/// # Returns
+///
+/// A client builder using the configured proxy, certificate authorities and verification
+pub fn client_builder() -> ClientBuilder {
+    match SETTINGS.get() {
+        Some(settings) => apply_settings(reqwest::Client::builder(), settings),
+        None => reqwest::Client::builder(),
+    }
+}
+
+/// Warn once that a client connects directly and always verifies certificates
+///
+/// # Arguments
+///
+/// * `client` - Name of the client, like `GitHub`
+pub fn warn_unsupported(client: &str) {
+    static WARNED: Once = Once::new();
+
+    let Some(settings) = SETTINGS.get() else {
+        return;
+    };
+    if settings.proxy.is_some() || !settings.verify_tls {
+        WARNED.call_once(|| {
+            warn!("The {} client ignores the `proxy` and `verify_tls` network settings", client);
+        });
+    }
+}
+
+/// Check and parse network settings
+fn parse_settings(config: &NetworkConfig) -> Result<Settings> {
+    let proxy = match &config.proxy {
+        Some(url) => {
+            let proxy = Proxy::all(url.as_str())
+                .map_err(|e| RustAiToolError::Other(format!("Invalid proxy '{}': {}", url, e)))?;
+            Some(proxy.no_proxy(NoProxy::from_string(&config.no_proxy.join(","))))
+        }
+        None => None,
+    };
+
+    let certificates = match &config.ca_bundle {
+        Some(path) => {
+            let pem = fs::read(path)
+                .map_err(|e| RustAiToolError::Other(format!("Failed to read CA bundle {}: {}", path.display(), e)))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .map_err(|e| RustAiToolError::Other(format!("Invalid CA bundle {}: {}", path.display(), e)))?;
+            if certificates.is_empty() {
+                return Err(RustAiToolError::Other(format!("CA bundle {} holds no certificates", path.display())));
+            }
+            certificates
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Settings {
+        proxy,
+        certificates,
+        verify_tls: config.verify_tls,
+    })
+}
+
+/// Apply parsed network settings to a client builder
+fn apply_settings(mut builder: ClientBuilder, settings: &Settings) -> ClientBuilder {
+    if let Some(proxy) = &settings.proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    for certificate in &settings.certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    builder.danger_accept_invalid_certs(!settings.verify_tls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_settings() {
+        let config = NetworkConfig {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: vec!["localhost".to_string(), ".corp.example.com".to_string()],
+            ..Default::default()
+        };
+        let settings = parse_settings(&config).unwrap();
+        assert!(settings.proxy.is_some() && settings.verify_tls);
+        assert!(apply_settings(reqwest::Client::builder(), &settings).build().is_ok());
+
+        let config = NetworkConfig { proxy: Some("not a proxy".to_string()), ..Default::default() };
+        assert!(parse_settings(&config).unwrap_err().to_string().contains("Invalid proxy"));
+
+        let dir = tempdir().unwrap();
+        let ca_bundle = dir.path().join("ca.pem");
+        let config = NetworkConfig { ca_bundle: Some(ca_bundle.clone()), ..Default::default() };
+        assert!(parse_settings(&config).unwrap_err().to_string().contains("Failed to read CA bundle"));
+        fs::write(&ca_bundle, "no certificates here\n").unwrap();
+        assert!(parse_settings(&config).unwrap_err().to_string().contains("holds no certificates"));
+    }
+}
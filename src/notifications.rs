@@ -0,0 +1,347 @@
+//! Notifications module
+//!
+//! This module posts the outcome of runs to team chats, so results are seen
+//! without reading CI logs:
+//! - `[[notifications]]` entries name a Slack, Discord or Teams incoming
+//!   webhook, the commands whose runs are posted, and whether every run, runs
+//!   with issues or only failures are
+//! - A message gives the status of the run, its issues by severity, links to
+//!   the pull requests involved and what failed
+//! - Commands record their results while they run and the message is posted
+//!   when the run ends; reviews of the webhook server are posted one by one
+//! - Posting is best effort: a failing webhook is warned about but never fails
+//!   the run, and nothing is posted in offline mode
+
+use crate::analysis::AnalysisResult;
+use crate::summary::{RunStatus, RunSummary};
+use crate::{NotificationKind, NotificationTarget, NotifyOn, Severity};
+use log::{debug, warn};
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Failures listed in a message; the rest are only counted
+const MAX_FAILURES: usize = 10;
+
+/// Longest message Discord accepts
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+/// Webhooks the current run is posted to
+static TARGETS: OnceLock<Vec<NotificationTarget>> = OnceLock::new();
+
+/// What the current run recorded for its notification
+static RECORDED: Mutex<Recorded> = Mutex::new(Recorded { project: None, issues: None, pull_requests: Vec::new(), failures: Vec::new() });
+
+/// Results recorded by the command of the current run
+#[derive(Debug)]
+struct Recorded {
+    project: Option<String>,
+    issues: Option<IssueCounts>,
+    pull_requests: Vec<String>,
+    failures: Vec<String>,
+}
+
+/// Number of issues of each severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IssueCounts {
+    /// Issues that must be fixed
+    pub errors: usize,
+
+    /// Issues that should be fixed
+    pub warnings: usize,
+
+    /// Optional fixes
+    pub info: usize,
+
+    /// Code style issues
+    pub style: usize,
+}
+
+impl IssueCounts {
+    /// Count the issues of analysis results
+    pub fn of(results: &[AnalysisResult]) -> Self {
+        let mut counts = IssueCounts::default();
+        for issue in results.iter().flat_map(|result| &result.issues) {
+            match issue.severity {
+                Severity::Error => counts.errors += 1,
+                Severity::Warning => counts.warnings += 1,
+                Severity::Info => counts.info += 1,
+                Severity::Style => counts.style += 1,
+            }
+        }
+        counts
+    }
+
+    /// Number of issues of any severity
+    pub fn total(&self) -> usize {
+        self.errors + self.warnings + self.info + self.style
+    }
+}
+
+/// Outcome of a run, as posted to the webhooks
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    /// Command that ran, like `analyze`, or `webhook` for a review of the webhook server
+    pub command: String,
+
+    /// Project or repository the run was about
+    pub project: Option<String>,
+
+    /// Outcome of the run
+    pub status: RunStatus,
+
+    /// Issues found, if the run analyzed anything
+    pub issues: Option<IssueCounts>,
+
+    /// Pull requests created, updated or reviewed
+    pub pull_requests: Vec<String>,
+
+    /// What failed, like the error of the run or files without a valid fix
+    pub failures: Vec<String>,
+}
+
+/// Post the summary of this run to the configured webhooks when it ends
+///
+/// # Arguments
+///
+/// * `targets` - Webhooks of the configuration
+/// * `offline` - Whether network access is disabled
+pub fn configure(targets: &[NotificationTarget], offline: bool) {
+    if targets.is_empty() {
+        return;
+    }
+    if offline {
+        warn!("Not posting notifications to {} webhooks in offline mode", targets.len());
+        return;
+    }
+
+    let _ = TARGETS.set(targets.to_vec());
+}
+
+/// Record the analysis results of the run
+///
+/// # Arguments
+///
+/// * `project_path` - Analyzed project
+/// * `results` - Reported results
+pub fn record_results(project_path: &Path, results: &[AnalysisResult]) {
+    let mut recorded = RECORDED.lock().unwrap();
+    recorded.project = Some(project_name(project_path));
+    recorded.issues = Some(IssueCounts::of(results));
+}
+
+/// Record a pull request created or updated by the run
+pub fn record_pull_request(url: &str) {
+    RECORDED.lock().unwrap().pull_requests.push(url.to_string());
+}
+
+/// Record something that failed in the run, like a file without a valid fix
+pub fn record_failure(message: impl Into<String>) {
+    RECORDED.lock().unwrap().failures.push(message.into());
+}
+
+/// Post the summary of the run to the webhooks of its command
+///
+/// # Arguments
+///
+/// * `summary` - Summary of the finished run
+pub async fn notify_run(summary: &RunSummary) {
+    let Some(targets) = TARGETS.get() else {
+        return;
+    };
+
+    let report = {
+        let mut recorded = RECORDED.lock().unwrap();
+        RunReport {
+            command: summary.command.clone(),
+            project: recorded.project.take(),
+            status: summary.status,
+            issues: recorded.issues.take(),
+            pull_requests: std::mem::take(&mut recorded.pull_requests),
+            failures: std::mem::take(&mut recorded.failures),
+        }
+    };
+
+    send(targets, &report).await;
+}
+
+/// Post a run report to the webhooks that want it
+///
+/// # Arguments
+///
+/// * `targets` - Webhooks to consider
+/// * `report` - Outcome of the run
+pub async fn send(targets: &[NotificationTarget], report: &RunReport) {
+    let targets: Vec<&NotificationTarget> = targets.iter().filter(|target| wants(target, report)).collect();
+    if targets.is_empty() {
+        return;
+    }
+
+    let client = match crate::network::client_builder().build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to create the notification client: {}", e);
+            return;
+        }
+    };
+
+    for target in targets {
+        let result = client.post(&target.url)
+            .json(&payload(target.kind, report))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match result {
+            Ok(_) => debug!("Posted the {} run to {:?}", report.command, target.kind),
+            Err(e) => warn!("Failed to post the {} run to {:?}: {}", report.command, target.kind, e),
+        }
+    }
+}
+
+/// Whether a webhook is told about a run
+fn wants(target: &NotificationTarget, report: &RunReport) -> bool {
+    let status = match target.on {
+        NotifyOn::Always => true,
+        NotifyOn::Issues => report.status != RunStatus::Clean,
+        NotifyOn::Failure => matches!(report.status, RunStatus::Error | RunStatus::InvalidFixes),
+    };
+
+    status && target.commands.contains(&report.command)
+}
+
+/// Body posted to a webhook of a chat service
+fn payload(kind: NotificationKind, report: &RunReport) -> JsonValue {
+    let title = title(report);
+    let lines = details(report);
+
+    match kind {
+        NotificationKind::Slack => json!({
+            "text": std::iter::once(format!("*{}*", title)).chain(lines).collect::<Vec<_>>().join("\n"),
+        }),
+        NotificationKind::Discord => {
+            let content = std::iter::once(format!("**{}**", title)).chain(lines).collect::<Vec<_>>().join("\n");
+            json!({ "content": truncate(&content, DISCORD_MAX_LENGTH) })
+        }
+        NotificationKind::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": title,
+            "title": title,
+            "themeColor": match report.status {
+                RunStatus::Clean => "2EB67D",
+                RunStatus::IssuesFound => "ECB22E",
+                RunStatus::Error | RunStatus::InvalidFixes => "E01E5A",
+            },
+            // Teams only breaks lines on blank lines
+            "text": lines.join("\n\n"),
+        }),
+    }
+}
+
+/// First line of a message, like `rust-ai-tool analyze of my-crate: issues found`
+fn title(report: &RunReport) -> String {
+    let status = match report.status {
+        RunStatus::Clean => "clean",
+        RunStatus::IssuesFound => "issues found",
+        RunStatus::Error => "failed",
+        RunStatus::InvalidFixes => "invalid fixes",
+    };
+
+    match &report.project {
+        Some(project) => format!("rust-ai-tool {} of {}: {}", report.command, project, status),
+        None => format!("rust-ai-tool {}: {}", report.command, status),
+    }
+}
+
+/// Lines of a message after its title
+fn details(report: &RunReport) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(issues) = &report.issues {
+        let by_severity: Vec<String> = [
+            (issues.errors, "error", "errors"),
+            (issues.warnings, "warning", "warnings"),
+            (issues.info, "info", "info"),
+            (issues.style, "style", "style"),
+        ]
+        .iter()
+        .filter(|(count, _, _)| *count > 0)
+        .map(|(count, one, many)| format!("{} {}", count, if *count == 1 { one } else { many }))
+        .collect();
+
+        if by_severity.is_empty() {
+            lines.push("No issues".to_string());
+        } else {
+            lines.push(format!("Issues: {} ({})", issues.total(), by_severity.join(", ")));
+        }
+    }
+
+    for url in &report.pull_requests {
+        lines.push(format!("Pull request: {}", url));
+    }
+
+    for failure in report.failures.iter().take(MAX_FAILURES) {
+        lines.push(format!("- {}", failure));
+    }
+    if report.failures.len() > MAX_FAILURES {
+        lines.push(format!("- and {} more failures", report.failures.len() - MAX_FAILURES));
+    }
+
+    lines
+}
+
+/// Name of a project in messages: the name of its directory
+fn project_name(project_path: &Path) -> String {
+    let path = project_path.canonicalize().unwrap_or_else(|_| project_path.to_path_buf());
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| project_path.display().to_string())
+}
+
+/// Cut a message to a number of characters, marking the cut
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_payloads() {
+        let report = RunReport {
+            command: "fix".to_string(),
+            project: Some("my-crate".to_string()),
+            status: RunStatus::InvalidFixes,
+            issues: Some(IssueCounts { errors: 1, warnings: 2, info: 0, style: 0 }),
+            pull_requests: vec!["https://github.com/octo/tool/pull/7".to_string()],
+            failures: (1..=12).map(|i| format!("src/file{}.rs: no valid fix", i)).collect(),
+        };
+
+        let slack = payload(NotificationKind::Slack, &report);
+        let text = slack["text"].as_str().unwrap();
+        assert!(text.starts_with("*rust-ai-tool fix of my-crate: invalid fixes*\nIssues: 3 (1 error, 2 warnings)\nPull request: https://github.com/octo/tool/pull/7\n- src/file1.rs"), "{}", text);
+        assert!(text.ends_with("- src/file10.rs: no valid fix\n- and 2 more failures"), "{}", text);
+
+        let teams = payload(NotificationKind::Teams, &report);
+        assert_eq!(teams["title"], "rust-ai-tool fix of my-crate: invalid fixes");
+        assert_eq!(teams["themeColor"], "E01E5A");
+
+        let long = RunReport { failures: vec!["x".repeat(3000)], ..report.clone() };
+        let discord = payload(NotificationKind::Discord, &long);
+        assert_eq!(discord["content"].as_str().unwrap().chars().count(), DISCORD_MAX_LENGTH);
+
+        let target = |on| NotificationTarget { kind: NotificationKind::Slack, url: String::new(), commands: vec!["fix".to_string()], on };
+        assert!(wants(&target(NotifyOn::Failure), &report));
+        let clean = RunReport { status: RunStatus::Clean, ..report.clone() };
+        assert!(wants(&target(NotifyOn::Always), &clean) && !wants(&target(NotifyOn::Issues), &clean));
+        assert!(!wants(&target(NotifyOn::Always), &RunReport { command: "analyze".to_string(), ..report }));
+    }
+}
@@ -0,0 +1,262 @@
+//! Organization analysis module
+//!
+//! This module analyzes every repository of a GitHub organization in one run:
+//! - Repositories listed through the API and filtered by language, topic,
+//!   archived and fork status
+//! - Each repository cloned and analyzed, a bounded number at a time
+//! - A consolidated report with the issue counts of every repository
+//!
+//! A repository that fails to clone or analyze is reported with its error
+//! instead of stopping the run. With a checkpoint, every analyzed repository is
+//! recorded, and a resumed run only analyzes the repositories left.
+
+use crate::analysis::{analyze_project, AnalysisResult};
+use crate::analyzer_plugins::checkout_options;
+use crate::checkpoint::{unit_key, Checkpoint, ORG_STAGE};
+use crate::github::{GithubClient, OrgRepository};
+use crate::{AnalysisOptions, Result, RustAiToolError, Severity, SshConfig};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use log::{info, warn};
+
+/// Which repositories of an organization are analyzed
+#[derive(Debug, Clone, Default)]
+pub struct RepoFilter {
+    /// Only repositories with this primary language (case-insensitive)
+    pub language: Option<String>,
+
+    /// Only repositories with this topic
+    pub topic: Option<String>,
+
+    /// Also analyze archived repositories
+    pub include_archived: bool,
+
+    /// Also analyze forks
+    pub include_forks: bool,
+}
+
+impl RepoFilter {
+    /// Whether a repository passes the filter
+    pub fn matches(&self, repository: &OrgRepository) -> bool {
+        (self.include_archived || !repository.archived)
+            && (self.include_forks || !repository.fork)
+            && self.language.as_ref().is_none_or(|language| {
+                repository.language.as_ref().is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+            && self.topic.as_ref().is_none_or(|topic| repository.topics.contains(topic))
+    }
+}
+
+/// Analysis of one repository of an organization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoReport {
+    /// Repository name
+    pub name: String,
+
+    /// Analyzed branch
+    pub branch: String,
+
+    /// Analysis results, with paths relative to the repository root
+    pub results: Vec<AnalysisResult>,
+
+    /// Why the repository could not be analyzed
+    pub error: Option<String>,
+}
+
+impl RepoReport {
+    /// Number of issues found
+    pub fn issue_count(&self) -> usize {
+        self.results.iter().map(|r| r.issues.len()).sum()
+    }
+
+    /// Number of issues found with a severity
+    pub fn count_severity(&self, severity: &Severity) -> usize {
+        self.results.iter().flat_map(|r| &r.issues).filter(|issue| &issue.severity == severity).count()
+    }
+}
+
+/// Analyze the repositories of an organization
+///
+/// # Arguments
+///
+/// * `access_token` - GitHub access token
+/// * `org` - Organization name
+/// * `filter` - Which repositories are analyzed
+/// * `options` - Analysis options
+/// * `concurrency` - Repositories cloned and analyzed at the same time
+/// * `ssh` - SSH settings for cloning (HTTPS if `None`)
+/// * `checkpoint` - Checkpoint recording every analyzed repository, to resume an interrupted run
+///
+/// # Returns
+///
+/// One report per analyzed repository, ordered by name
+pub async fn analyze_org(
+    access_token: &str,
+    org: &str,
+    filter: &RepoFilter,
+    options: &AnalysisOptions,
+    concurrency: usize,
+    ssh: Option<&SshConfig>,
+    checkpoint: Option<&Checkpoint>,
+) -> Result<Vec<RepoReport>> {
+    let lister = GithubClient::new(access_token, org, "")?;
+    let repositories: Vec<OrgRepository> = lister.list_org_repositories(org)
+        .await?
+        .into_iter()
+        .filter(|repository| filter.matches(repository))
+        .collect();
+
+    info!("Analyzing {} repositories of {}", repositories.len(), org);
+
+    let mut reports: Vec<RepoReport> = stream::iter(repositories)
+        .map(|repository| async move {
+            let key = unit_key(&[org, &repository.name, &repository.default_branch]);
+            if let Some(report) = checkpoint.and_then(|checkpoint| checkpoint.completed::<RepoReport>(ORG_STAGE, &key)) {
+                info!("Reusing the checkpointed analysis of {}/{}", org, repository.name);
+                return report;
+            }
+
+            let result = analyze_repository(access_token, org, &repository, options, ssh).await;
+            let (results, error) = match result {
+                Ok(results) => (results, None),
+                Err(e) => {
+                    warn!("Failed to analyze {}/{}: {}", org, repository.name, e);
+                    (Vec::new(), Some(e.to_string()))
+                }
+            };
+
+            let report = RepoReport {
+                name: repository.name,
+                branch: repository.default_branch,
+                results,
+                error,
+            };
+
+            // Failed repositories are analyzed again when resuming
+            if let (Some(checkpoint), None) = (checkpoint, &report.error) {
+                if let Err(e) = checkpoint.record(ORG_STAGE, &key, &report) {
+                    warn!("Failed to checkpoint the analysis of {}/{}: {}", org, report.name, e);
+                }
+            }
+
+            report
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    reports.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(reports)
+}
+
+/// Clone and analyze the default branch of one repository
+async fn analyze_repository(
+    access_token: &str,
+    org: &str,
+    repository: &OrgRepository,
+    options: &AnalysisOptions,
+    ssh: Option<&SshConfig>,
+) -> Result<Vec<AnalysisResult>> {
+    let client = GithubClient::new(access_token, org, &repository.name)?.with_ssh(ssh.cloned());
+
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let repo_path = client.clone_repo(Some(&repository.default_branch), temp_dir.path()).await?;
+
+    let analysis_options = checkout_options(options, &repo_path, &repo_path);
+    let analyzed_path = repo_path.clone();
+    let mut results = tokio::task::spawn_blocking(move || analyze_project(&analyzed_path, &analysis_options))
+        .await
+        .map_err(|e| RustAiToolError::Analysis(format!("Analysis task failed: {}", e)))??;
+
+    // The checkout is removed, so only repository-relative paths stay meaningful
+    for result in &mut results {
+        result.file_path = result.file_path.strip_prefix(&repo_path).unwrap_or(&result.file_path).to_path_buf();
+        for issue in &mut result.issues {
+            issue.file_path = issue.file_path.strip_prefix(&repo_path).unwrap_or(&issue.file_path).to_path_buf();
+        }
+    }
+
+    Ok(results)
+}
+
+/// Format the reports of an organization as one Markdown report
+///
+/// # Arguments
+///
+/// * `org` - Organization name
+/// * `reports` - Reports of the analyzed repositories
+///
+/// # Returns
+///
+/// A summary table of all repositories, followed by the issues of each one
+pub fn format_org_report(org: &str, reports: &[RepoReport]) -> String {
+    let total: usize = reports.iter().map(RepoReport::issue_count).sum();
+    let failed = reports.iter().filter(|report| report.error.is_some()).count();
+
+    let mut markdown = format!(
+        "# Rust AI Tool Analysis of {}\n\n**Repositories**: {} ({} failed)\n**Total Issues Found**: {}\n\n",
+        org,
+        reports.len(),
+        failed,
+        total
+    );
+
+    markdown.push_str("| Repository | Issues | Errors | Warnings | Info | Style | Status |\n|---|---|---|---|---|---|---|\n");
+    for report in reports {
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            report.name,
+            report.issue_count(),
+            report.count_severity(&Severity::Error),
+            report.count_severity(&Severity::Warning),
+            report.count_severity(&Severity::Info),
+            report.count_severity(&Severity::Style),
+            report.error.as_ref().map_or_else(|| "analyzed".to_string(), |e| format!("failed: {}", e.replace('|', "\\|").replace('\n', " "))),
+        ));
+    }
+
+    for report in reports.iter().filter(|report| report.issue_count() > 0) {
+        markdown.push_str(&format!("\n## {} ({})\n\n", report.name, report.branch));
+
+        for issue in report.results.iter().flat_map(|r| &r.issues) {
+            markdown.push_str(&format!(
+                "- `{}:{}` {:?}: {}\n",
+                issue.file_path.display(),
+                issue.line_start,
+                issue.severity,
+                issue.message.replace('\n', " ")
+            ));
+        }
+    }
+
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_filter() {
+        let repository = |name: &str, language: Option<&str>, archived: bool| OrgRepository {
+            name: name.to_string(),
+            default_branch: "main".to_string(),
+            language: language.map(String::from),
+            topics: vec!["backend".to_string()],
+            archived,
+            fork: false,
+        };
+
+        let filter = RepoFilter {
+            language: Some("rust".to_string()),
+            topic: Some("backend".to_string()),
+            ..RepoFilter::default()
+        };
+
+        assert!(filter.matches(&repository("api", Some("Rust"), false)));
+        assert!(!filter.matches(&repository("web", Some("TypeScript"), false)));
+        assert!(!filter.matches(&repository("docs", None, false)));
+        assert!(!filter.matches(&repository("old-api", Some("Rust"), true)));
+        assert!(RepoFilter { include_archived: true, ..filter }.matches(&repository("old-api", Some("Rust"), true)));
+    }
+}
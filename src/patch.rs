@@ -0,0 +1,351 @@
+//! Patch module
+//!
+//! This module reads fixes expressed as unified diffs:
+//! - Parse `git diff` and `diff -u` output
+//! - Locate hunks in the current files, tolerating shifted line numbers and
+//!   CRLF line endings
+//! - Reject paths outside of the base directory
+//! - Convert each patched file into a `CodeModification`
+
+use crate::modification::{create_modification, CodeModification};
+use crate::{Result, RustAiToolError};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use log::{debug, info};
+
+/// Confidence assigned to modifications read from a patch, which carries no score of its own
+pub const PATCH_CONFIDENCE: u8 = 100;
+
+/// A file section of a unified diff
+#[derive(Debug, Default)]
+struct FilePatch {
+    /// Path from the `---` line
+    old_path: String,
+
+    /// Path from the `+++` line
+    new_path: String,
+
+    /// Hunks in file order
+    hunks: Vec<PatchHunk>,
+}
+
+/// A hunk of a unified diff
+#[derive(Debug, Default)]
+struct PatchHunk {
+    /// First line of the hunk in the old file (1-based)
+    old_start: usize,
+
+    /// Lines expected in the old file (context and removals)
+    old_lines: Vec<String>,
+
+    /// Lines written to the new file (context and additions)
+    new_lines: Vec<String>,
+
+    /// Whether the last new line has no trailing newline
+    new_missing_newline: bool,
+}
+
+/// Check whether a fixes file is a patch rather than JSON
+///
+/// # Arguments
+///
+/// * `path` - Path to the fixes file
+///
+/// # Returns
+///
+/// `true` for `.patch` and `.diff` files
+pub fn is_patch_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("patch") || ext.eq_ignore_ascii_case("diff"))
+}
+
+/// Convert a unified diff into code modifications
+///
+/// # Arguments
+///
+/// * `patch` - Unified diff text
+/// * `base_dir` - Directory the paths in the patch are relative to
+///
+/// # Returns
+///
+/// One modification per patched file, against the file's current content
+pub fn parse_unified_diff(patch: &str, base_dir: &Path) -> Result<Vec<CodeModification>> {
+    let file_patches = parse_file_patches(patch)?;
+    info!("Parsed patch with {} files", file_patches.len());
+
+    let mut modifications = Vec::new();
+
+    for file_patch in file_patches {
+        if file_patch.old_path == "/dev/null" || file_patch.new_path == "/dev/null" {
+            return Err(RustAiToolError::Modification(format!(
+                "Creating or deleting files is not supported: {}",
+                if file_patch.new_path == "/dev/null" { &file_patch.old_path } else { &file_patch.new_path }
+            )));
+        }
+
+        let file_path = resolve_patch_path(&file_patch.new_path, base_dir)?;
+        let original_content = fs::read_to_string(&file_path)
+            .map_err(RustAiToolError::Io)?;
+
+        let modified_content = apply_file_patch(&original_content, &file_patch)
+            .map_err(|e| RustAiToolError::Modification(format!("{}: {}", file_path.display(), e)))?;
+
+        debug!("Patch changes {} in {} hunks", file_path.display(), file_patch.hunks.len());
+
+        modifications.push(create_modification(
+            file_path,
+            original_content,
+            modified_content,
+            format!("Apply {} patch hunks", file_patch.hunks.len()),
+            PATCH_CONFIDENCE,
+        ));
+    }
+
+    Ok(modifications)
+}
+
+/// Split a unified diff into per-file sections
+fn parse_file_patches(patch: &str) -> Result<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = patch.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(old_path) = line.strip_prefix("--- ") {
+            let new_path = lines.next()
+                .and_then(|l| l.strip_prefix("+++ "))
+                .ok_or_else(|| RustAiToolError::Modification(format!(
+                    "Malformed patch: expected '+++' after '{}'",
+                    line
+                )))?;
+
+            files.push(FilePatch {
+                old_path: clean_patch_path(old_path),
+                new_path: clean_patch_path(new_path),
+                hunks: Vec::new(),
+            });
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            let file = files.last_mut().ok_or_else(|| {
+                RustAiToolError::Modification("Malformed patch: hunk before file header".to_string())
+            })?;
+
+            let (old_start, old_count, new_count) = parse_hunk_header(header)?;
+            let mut hunk = PatchHunk { old_start, ..Default::default() };
+            let (mut old_seen, mut new_seen) = (0, 0);
+            let mut last_tag = "";
+
+            while old_seen < old_count || new_seen < new_count || lines.peek().is_some_and(|l| l.starts_with('\\')) {
+                let body = match lines.next() {
+                    Some(body) => body,
+                    None => break,
+                };
+                let (tag, text) = body.split_at(body.len().min(1));
+
+                match tag {
+                    " " | "" => {
+                        hunk.old_lines.push(text.to_string());
+                        hunk.new_lines.push(text.to_string());
+                        old_seen += 1;
+                        new_seen += 1;
+                    }
+                    "-" => {
+                        hunk.old_lines.push(text.to_string());
+                        old_seen += 1;
+                    }
+                    "+" => {
+                        hunk.new_lines.push(text.to_string());
+                        new_seen += 1;
+                    }
+                    // "\ No newline at end of file" applies to the line before it
+                    "\\" => {
+                        hunk.new_missing_newline = last_tag != "-" && new_seen == new_count;
+                        continue;
+                    }
+                    _ => {
+                        return Err(RustAiToolError::Modification(format!(
+                            "Malformed patch: unexpected line in hunk: {}",
+                            body
+                        )))
+                    }
+                }
+
+                last_tag = tag;
+            }
+
+            file.hunks.push(hunk);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Parse the `-a,b +c,d @@` part of a hunk header
+fn parse_hunk_header(header: &str) -> Result<(usize, usize, usize)> {
+    let malformed = || RustAiToolError::Modification(format!("Malformed hunk header: @@ {}", header));
+
+    let mut ranges = header.split_whitespace();
+    let old = ranges.next().and_then(|r| r.strip_prefix('-')).ok_or_else(malformed)?;
+    let new = ranges.next().and_then(|r| r.strip_prefix('+')).ok_or_else(malformed)?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+
+    let (old_start, old_count) = parse_range(old).ok_or_else(malformed)?;
+    let (_, new_count) = parse_range(new).ok_or_else(malformed)?;
+
+    Ok((old_start, old_count, new_count))
+}
+
+/// Strip timestamps from `diff -u` file headers
+fn clean_patch_path(path: &str) -> String {
+    path.split('\t').next().unwrap_or(path).trim().to_string()
+}
+
+/// Resolve a patch path against the base directory, handling git's `a/` and `b/` prefixes
+///
+/// Paths with `..` components, and absolute paths or symbolic links leading
+/// outside of the base directory, are rejected.
+fn resolve_patch_path(path: &str, base_dir: &Path) -> Result<PathBuf> {
+    let outside = || RustAiToolError::Modification(format!(
+        "Patch path {} is outside of {}",
+        path,
+        base_dir.display()
+    ));
+
+    let patch_path = Path::new(path);
+    if patch_path.components().any(|c| c == Component::ParentDir) {
+        return Err(outside());
+    }
+
+    let resolved = if patch_path.is_absolute() {
+        if !patch_path.starts_with(base_dir) {
+            return Err(outside());
+        }
+        patch_path.to_path_buf()
+    } else {
+        let as_is = base_dir.join(path);
+
+        match path.split_once('/') {
+            Some(("a" | "b", rest)) if !as_is.exists() => base_dir.join(rest),
+            _ => as_is,
+        }
+    };
+
+    if let (Ok(file), Ok(base)) = (resolved.canonicalize(), base_dir.canonicalize()) {
+        if !file.starts_with(base) {
+            return Err(outside());
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Apply the hunks of a file patch to its current content
+fn apply_file_patch(original: &str, file_patch: &FilePatch) -> std::result::Result<String, String> {
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let stripped: Vec<&str> = lines.iter()
+        .map(|l| l.strip_suffix('\n').unwrap_or(l))
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .collect();
+    // Lines written by the patch keep the line endings of the file
+    let line_ending = if original.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut result = String::with_capacity(original.len());
+    let mut position = 0;
+
+    for (i, hunk) in file_patch.hunks.iter().enumerate() {
+        let expected = hunk.old_start.saturating_sub(1).max(position);
+        let start = find_hunk(&stripped, &hunk.old_lines, expected, position)
+            .ok_or_else(|| format!("hunk #{} does not apply (expected near line {})", i + 1, hunk.old_start))?;
+
+        result.extend(lines[position..start].iter().copied());
+
+        for (j, line) in hunk.new_lines.iter().enumerate() {
+            result.push_str(line.strip_suffix('\r').unwrap_or(line));
+            let is_last = j + 1 == hunk.new_lines.len();
+            if !(is_last && hunk.new_missing_newline) {
+                result.push_str(line_ending);
+            }
+        }
+
+        position = start + hunk.old_lines.len();
+    }
+
+    result.extend(lines[position..].iter().copied());
+    Ok(result)
+}
+
+/// Find where a hunk's old lines occur, preferring the position closest to the expected line
+fn find_hunk(lines: &[&str], old_lines: &[String], expected: usize, min_start: usize) -> Option<usize> {
+    let max_start = lines.len().checked_sub(old_lines.len())?;
+
+    let mut candidates: Vec<usize> = (min_start..=max_start).collect();
+    candidates.sort_by_key(|&start| start.abs_diff(expected));
+
+    candidates.into_iter().find(|&start| {
+        lines[start..start + old_lines.len()].iter().zip(old_lines).all(|(a, b)| *a == b.strip_suffix('\r').unwrap_or(b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("src").join("main.rs");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+
+        // An extra line at the top shifts the hunk by one line
+        let original = "// header\nfn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+        fs::write(&file_path, original).unwrap();
+
+        let patch = [
+            "diff --git a/src/main.rs b/src/main.rs",
+            "--- a/src/main.rs",
+            "+++ b/src/main.rs",
+            "@@ -1,4 +1,4 @@",
+            " fn main() {",
+            "-    let x = 1;",
+            "+    let x = 2;",
+            "     println!(\"{}\", x);",
+            " }",
+        ].join("\n") + "\n";
+
+        let modifications = parse_unified_diff(&patch, dir.path()).unwrap();
+
+        assert_eq!(modifications.len(), 1);
+        assert_eq!(modifications[0].file_path, file_path);
+        assert_eq!(modifications[0].original_content, original);
+        assert_eq!(modifications[0].modified_content, original.replace("x = 1", "x = 2"));
+    }
+    #[test]
+    fn test_crlf_files_and_paths_outside_base_dir() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("lib.rs");
+        fs::write(&file_path, "fn a() {}\r\nfn b() {}\r\nfn c() {}\r\n").unwrap();
+
+        let patch = |path: &str| format!(
+            "--- a/{0}\n+++ b/{0}\n@@ -1,3 +1,3 @@\n fn a() {{}}\n-fn b() {{}}\n+fn b() {{ todo!() }}\n fn c() {{}}\n",
+            path
+        );
+
+        let modifications = parse_unified_diff(&patch("lib.rs"), dir.path()).unwrap();
+        assert_eq!(modifications[0].modified_content, "fn a() {}\r\nfn b() { todo!() }\r\nfn c() {}\r\n");
+
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        assert!(parse_unified_diff(&patch("../lib.rs"), &nested).is_err());
+        assert!(parse_unified_diff(&patch("src/../../lib.rs"), &nested).is_err());
+
+        let absolute = format!("--- {0}\n+++ {0}\n@@ -1 +1 @@\n-fn a() {{}}\n+fn z() {{}}\n", file_path.display());
+        assert!(parse_unified_diff(&absolute, &nested).is_err());
+        assert_eq!(parse_unified_diff(&absolute, dir.path()).unwrap().len(), 1);
+    }
+}
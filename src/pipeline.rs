@@ -0,0 +1,427 @@
+//! Fix pipeline module
+//!
+//! This module runs the AI fix loop end to end:
+//! - Issues found by analysis are sent to the AI model, one request per file
+//!   (one per affected item for large files), with a bounded number of files in flight
+//! - Every proposed fix is validated as soon as it arrives
+//! - Validation errors are fed back to the model for a bounded number of refinement rounds
+//! - Fixes that pass validation are returned as modifications ready to apply,
+//!   optionally followed by generated unit tests that passed against the fix
+//! - With a checkpoint, every file's outcome is recorded as it completes, and
+//!   files whose content and issues did not change reuse the recorded outcome
+
+use crate::analysis::{AnalysisResult, CodeIssue};
+use crate::checkpoint::{unit_key, Checkpoint, FIX_STAGE};
+use crate::chunking::{item_context, item_spans, replace_lines, ItemSpan};
+use crate::directory_config::DirectoryConfigs;
+use crate::modification::{create_modification, CodeModification, IssueReference};
+use crate::models::AiModelClient;
+use crate::validation::{run_fix_tests, validate_fix, FixToValidate, ValidationMessageType};
+use crate::{Result, RustAiToolError, ValidationOptions};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::{debug, info, warn};
+
+/// Confidence of a fix accepted on the first attempt
+const FIRST_ATTEMPT_CONFIDENCE: u8 = 80;
+
+/// Confidence lost for every refinement round a fix needed
+const REFINEMENT_CONFIDENCE_PENALTY: u8 = 10;
+
+/// Files longer than this are fixed item by item
+const ITEM_MODE_MIN_LINES: usize = 300;
+
+/// Name of the test module generated for a fix
+const FIX_TEST_MODULE: &str = "ai_fix_tests";
+
+/// Options of the fix pipeline
+#[derive(Debug, Clone)]
+pub struct FixPipelineOptions {
+    /// Refinement rounds after a fix fails validation
+    pub max_refinements: u32,
+
+    /// Options used to validate proposed fixes
+    pub validation: ValidationOptions,
+
+    /// Project of the fixed files, whose directory configurations override the validation options
+    pub project_path: PathBuf,
+
+    /// Files fixed concurrently
+    pub concurrency: usize,
+
+    /// Whether to generate unit tests for validated fixes and run them
+    pub generate_tests: bool,
+
+    /// Checkpoint recording every file's outcome, to resume an interrupted run
+    pub checkpoint: Option<Arc<Checkpoint>>,
+}
+
+/// A file the pipeline could not fix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedFix {
+    /// File with the issues
+    pub file_path: PathBuf,
+
+    /// Attempts made, including refinements
+    pub attempts: u32,
+
+    /// Why the last attempt was rejected
+    pub errors: Vec<String>,
+}
+
+/// Outcome of fixing one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FixOutcome {
+    /// A fix passed validation
+    Fixed {
+        /// The fix
+        fix: CodeModification,
+
+        /// The fix followed by tests demonstrating it, if tests were generated and passed
+        test: Option<CodeModification>,
+    },
+
+    /// No fix passed validation
+    Failed(FailedFix),
+}
+
+/// Outcome of a pipeline run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixPipelineReport {
+    /// Validated fixes
+    pub fixes: Vec<CodeModification>,
+
+    /// Files whose fixes never passed validation
+    pub failed: Vec<FailedFix>,
+}
+
+/// Generate and validate fixes for the issues found by analysis
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+/// * `client` - AI model client generating the fixes
+/// * `options` - Pipeline options
+///
+/// # Returns
+///
+/// The validated fixes and the files that could not be fixed
+#[tracing::instrument(skip_all, fields(files = results.len()), err)]
+pub async fn run_fix_pipeline(
+    results: &[AnalysisResult],
+    client: &AiModelClient,
+    options: &FixPipelineOptions,
+) -> Result<FixPipelineReport> {
+    // Dependency policy issues are on Cargo.toml and come with their own manifest fixes
+    let files: Vec<&AnalysisResult> = results.iter()
+        .filter(|r| !r.issues.is_empty() && r.file_path.extension().is_some_and(|extension| extension == "rs"))
+        .collect();
+    let total = files.len();
+    info!("Generating fixes for {} files with issues, {} at a time", total, options.concurrency.max(1));
+
+    // Files complete in any order; each fix is validated by its own task as
+    // soon as the model answers, while the other requests are still in flight
+    let mut outcomes = stream::iter(files)
+        .map(|result| checkpointed_fix_file(result, client, options))
+        .buffer_unordered(options.concurrency.max(1));
+
+    let mut report = FixPipelineReport::default();
+
+    while let Some(outcome) = outcomes.next().await {
+        match outcome? {
+            FixOutcome::Fixed { fix, test } => report.fixes.extend(std::iter::once(fix).chain(test)),
+            FixOutcome::Failed(failed) => report.failed.push(failed),
+        }
+
+        info!("{} of {} files done", report.fixes.len() + report.failed.len(), total);
+    }
+
+    report.fixes.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    report.failed.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    info!("{} fixes passed validation, {} files could not be fixed", report.fixes.len(), report.failed.len());
+    Ok(report)
+}
+
+/// Fix one file, or reuse its outcome recorded in the checkpoint
+async fn checkpointed_fix_file(
+    result: &AnalysisResult,
+    client: &AiModelClient,
+    options: &FixPipelineOptions,
+) -> Result<FixOutcome> {
+    let Some(checkpoint) = &options.checkpoint else {
+        return fix_file(&result.file_path, &result.issues, client, options).await;
+    };
+
+    let content = fs::read_to_string(&result.file_path)
+        .map_err(RustAiToolError::Io)?;
+    let issues: Vec<String> = result.issues.iter()
+        .map(|issue| format!("{}:{}:{}", issue.id(), issue.line_end, issue.column_end))
+        .collect();
+    let path = result.file_path.to_string_lossy();
+    let key = unit_key(&[&path, &content, &issues.join(","), &options.generate_tests.to_string()]);
+
+    if let Some(outcome) = checkpoint.completed::<FixOutcome>(FIX_STAGE, &key) {
+        debug!("Reusing the checkpointed fix outcome of {}", result.file_path.display());
+        return Ok(outcome);
+    }
+
+    let outcome = fix_file(&result.file_path, &result.issues, client, options).await?;
+    checkpoint.record(FIX_STAGE, &key, &outcome)?;
+    Ok(outcome)
+}
+
+/// Generate a fix for the issues of one file, refining it until it passes validation
+///
+/// # Arguments
+///
+/// * `file_path` - File with the issues
+/// * `issues` - Issues to fix
+/// * `client` - AI model client generating the fix
+/// * `options` - Pipeline options
+///
+/// # Returns
+///
+/// The validated fix, or why no fix passed after all refinement rounds
+#[tracing::instrument(skip_all, fields(file = %file_path.display(), issues = issues.len()), err)]
+pub async fn fix_file(
+    file_path: &Path,
+    issues: &[CodeIssue],
+    client: &AiModelClient,
+    options: &FixPipelineOptions,
+) -> Result<FixOutcome> {
+    let original = fs::read_to_string(file_path)
+        .map_err(RustAiToolError::Io)?;
+    let validation_options = DirectoryConfigs::new(&options.project_path)
+        .validation_options(file_path, &options.validation)?;
+
+    // Large files are fixed one item at a time, if every issue lies inside an item
+    let item_groups = if original.lines().count() > ITEM_MODE_MIN_LINES {
+        group_issues_by_item(&original, issues)
+    } else {
+        None
+    };
+
+    let mut errors = Vec::new();
+    let mut rejected: Option<String> = None;
+    let attempts = options.max_refinements + 1;
+
+    for attempt in 1..=attempts {
+        debug!("Fix attempt {} of {} for {}", attempt, attempts, file_path.display());
+
+        let fixed = match &item_groups {
+            Some(groups) => {
+                let feedback = rejection_feedback(&errors, None);
+                fix_items(&original, groups, &feedback, client).await?
+            }
+            None => {
+                let feedback = rejection_feedback(&errors, rejected.as_deref());
+                let issue_list = format_issues(issues, 1);
+                let fixed = client.generate_fixes(&original, &format!("{}{}", issue_list, feedback)).await?;
+                if fixed.ends_with('\n') || !original.ends_with('\n') { fixed } else { fixed + "\n" }
+            }
+        };
+
+        errors = if fixed == original {
+            vec!["The fix does not change the code".to_string()]
+        } else {
+            let fix = FixToValidate {
+                file_path: file_path.to_path_buf(),
+                original_code: original.clone(),
+                modified_code: fixed.clone(),
+                description: String::new(),
+            };
+            let validation = validate_fix(&fix, &validation_options)?;
+
+            if validation.is_valid {
+                let refinements = (attempt - 1).min(u8::MAX as u32) as u8;
+                let confidence = FIRST_ATTEMPT_CONFIDENCE
+                    .saturating_sub(REFINEMENT_CONFIDENCE_PENALTY.saturating_mul(refinements));
+
+                info!("Fix for {} passed validation after {} attempts", file_path.display(), attempt);
+
+                let test = if options.generate_tests {
+                    generate_fix_tests(file_path, &fixed, issues, client).await
+                } else {
+                    None
+                };
+
+                let mut fix = create_modification(
+                    file_path.to_path_buf(),
+                    original.clone(),
+                    fixed,
+                    format!("AI fix for {} issues", issues.len()),
+                    confidence,
+                );
+                fix.resolves = issues.iter().map(IssueReference::from).collect();
+
+                return Ok(FixOutcome::Fixed {
+                    fix,
+                    test: test.map(|tested| create_modification(
+                        file_path.to_path_buf(),
+                        original,
+                        tested,
+                        format!("Tests for the AI fix of {}", file_path.display()),
+                        confidence,
+                    )),
+                });
+            }
+
+            validation.messages.iter()
+                .filter(|m| m.message_type == ValidationMessageType::Error)
+                .map(|m| match &m.location {
+                    Some(location) => format!("line {}: {}", location.line, m.text),
+                    None => m.text.clone(),
+                })
+                .collect()
+        };
+
+        warn!("Fix attempt {} for {} was rejected: {}", attempt, file_path.display(), errors.join("; "));
+        rejected = Some(fixed);
+    }
+
+    Ok(FixOutcome::Failed(FailedFix {
+        file_path: file_path.to_path_buf(),
+        attempts,
+        errors,
+    }))
+}
+
+/// Generate unit tests for a validated fix and run them against it
+///
+/// Tests are best effort: if they cannot be generated, do not compile or
+/// fail, the fix is kept without them.
+///
+/// # Returns
+///
+/// The fixed code followed by the passing test module
+async fn generate_fix_tests(
+    file_path: &Path,
+    fixed: &str,
+    issues: &[CodeIssue],
+    client: &AiModelClient,
+) -> Option<String> {
+    if fixed.contains(&format!("mod {}", FIX_TEST_MODULE)) {
+        debug!("{} already has a {} module, not generating tests", file_path.display(), FIX_TEST_MODULE);
+        return None;
+    }
+
+    let Some(project_root) = file_path.canonicalize().ok().and_then(|path| {
+        path.ancestors().find(|dir| dir.join("Cargo.toml").is_file()).map(Path::to_path_buf)
+    }) else {
+        warn!("{} is not inside a Cargo project, not generating tests", file_path.display());
+        return None;
+    };
+
+    let tests = match client.generate_tests(fixed, &format_issues(issues, 1), FIX_TEST_MODULE).await {
+        Ok(tests) => tests,
+        Err(e) => {
+            warn!("Failed to generate tests for the fix of {}: {}", file_path.display(), e);
+            return None;
+        }
+    };
+
+    let tested = format!("{}\n\n{}\n", fixed.trim_end(), tests.trim_end());
+
+    match run_fix_tests(&project_root, file_path, &tested, FIX_TEST_MODULE) {
+        Ok(result) if result.is_valid => {
+            info!("Generated tests for the fix of {} passed", file_path.display());
+            Some(tested)
+        }
+        Ok(result) => {
+            let errors: Vec<&str> = result.messages.iter().map(|m| m.text.as_str()).collect();
+            warn!("Dropping generated tests for {}: {}", file_path.display(), errors.join("; "));
+            None
+        }
+        Err(e) => {
+            warn!("Failed to run generated tests for {}: {}", file_path.display(), e);
+            None
+        }
+    }
+}
+
+/// Group issues by the item containing them
+///
+/// Returns `None` if the file does not parse or an issue lies outside every
+/// item (e.g. in the imports), in which case the whole file has to be sent.
+fn group_issues_by_item<'a>(source: &str, issues: &'a [CodeIssue]) -> Option<Vec<(ItemSpan, Vec<&'a CodeIssue>)>> {
+    let spans = item_spans(source)?;
+    let mut groups: Vec<(ItemSpan, Vec<&CodeIssue>)> = Vec::new();
+
+    for issue in issues {
+        let span = spans.iter().find(|span| span.contains(issue.line_start, issue.line_end))?;
+
+        match groups.iter_mut().find(|(s, _)| s == span) {
+            Some((_, group)) => group.push(issue),
+            None => groups.push((span.clone(), vec![issue])),
+        }
+    }
+
+    groups.sort_by_key(|(span, _)| span.start_line);
+    Some(groups)
+}
+
+/// Fix the issues of a large file item by item, splicing the fixed items into the file
+async fn fix_items(
+    original: &str,
+    groups: &[(ItemSpan, Vec<&CodeIssue>)],
+    feedback: &str,
+    client: &AiModelClient,
+) -> Result<String> {
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let mut fixed = original.to_string();
+
+    // Bottom-up, so the line numbers of earlier items stay valid
+    for (span, issues) in groups.iter().rev() {
+        debug!("Fixing {} issues in {} (lines {}-{})", issues.len(), span.name, span.start_line, span.end_line);
+
+        let item_code = lines[span.start_line - 1..span.end_line.min(lines.len())].concat();
+        let issue_list = format!(
+            "{}{}\n\nContext from the same file, for reference only (do not repeat it):\n```rust\n{}```",
+            format_issues(issues.iter().copied(), span.start_line),
+            feedback,
+            item_context(original, span)
+        );
+
+        let fixed_item = client.generate_fixes(&item_code, &issue_list).await?;
+        fixed = replace_lines(&fixed, span.start_line, span.end_line, &fixed_item);
+    }
+
+    Ok(fixed)
+}
+
+/// Feedback about a rejected fix, appended to the issue list
+fn rejection_feedback(errors: &[String], rejected: Option<&str>) -> String {
+    if errors.is_empty() {
+        return String::new();
+    }
+
+    let mut feedback = format!(
+        "\n\nA previous fix was rejected by validation:\n{}",
+        errors.iter().map(|e| format!("- {}", e)).collect::<Vec<_>>().join("\n")
+    );
+
+    if let Some(rejected) = rejected {
+        feedback.push_str(&format!("\n\nRejected fix:\n```rust\n{}\n```", rejected));
+    }
+
+    feedback.push_str("\nFix the issues again, avoiding these problems.");
+    feedback
+}
+
+/// List issues for a prompt, one per line, with line numbers relative to `first_line`
+fn format_issues<'a>(issues: impl IntoIterator<Item = &'a CodeIssue>, first_line: usize) -> String {
+    issues.into_iter()
+        .map(|issue| format!(
+            "- line {}: [{:?}/{:?}] {}",
+            issue.line_start + 1 - first_line.min(issue.line_start),
+            issue.category,
+            issue.severity,
+            issue.message
+        ))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
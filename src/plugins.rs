@@ -0,0 +1,204 @@
+//! Plugins module
+//!
+//! This module runs commands provided by other programs, like cargo does:
+//! - `rust-ai-tool foo` runs the first `rust-ai-tool-foo` executable on `PATH`
+//!   with the remaining arguments
+//! - The plugin gets the context of the run in environment variables:
+//!   `RUST_AI_TOOL` (path to this executable, to call back), `RUST_AI_TOOL_CONFIG`
+//!   (configuration file), `RUST_AI_TOOL_PROJECT` (root of the enclosing Cargo
+//!   project, if any) and `RUST_AI_TOOL_CONTEXT` (all of it as JSON, including
+//!   the loaded configuration with the profile applied)
+//! - Secrets of the configuration (API keys, access tokens, webhook secrets and
+//!   URLs) are cleared from the context, so a plugin only gets those it reads itself
+//! - The exit code of the plugin is the exit code of the run
+
+use crate::{Config, Result, RustAiToolError};
+use serde::{Serialize, Serializer};
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use log::debug;
+
+/// Prefix of the executables providing a command
+pub const PLUGIN_PREFIX: &str = "rust-ai-tool-";
+
+/// Context of a run, passed to a plugin as JSON
+#[derive(Debug, Serialize)]
+pub struct PluginContext<'a> {
+    /// Version of rust-ai-tool running the plugin
+    pub version: &'a str,
+
+    /// Configuration file of the run (it may not exist)
+    pub config_path: &'a Path,
+
+    /// Configuration profile of the run
+    pub profile: Option<&'a str>,
+
+    /// Root of the Cargo project enclosing the working directory
+    pub project_root: Option<PathBuf>,
+
+    /// Configuration, with the profile and command line options applied
+    /// (serialized without its secrets)
+    #[serde(serialize_with = "serialize_without_secrets")]
+    pub config: &'a Config,
+}
+
+/// Serialize a configuration with its secrets cleared
+fn serialize_without_secrets<S: Serializer>(config: &&Config, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    without_secrets(config).serialize(serializer)
+}
+
+/// Copy of a configuration with its API keys, access tokens, webhook secrets and
+/// notification webhook URLs cleared
+fn without_secrets(config: &Config) -> Config {
+    let mut config = config.clone();
+
+    config.ai_model.api_key.clear();
+    for model in &mut config.ai_models {
+        model.config.api_key.clear();
+    }
+    if let Some(repo) = &mut config.github_repo {
+        repo.access_token.clear();
+        repo.webhook_secret = None;
+    }
+    if let Some(repo) = &mut config.gitea_repo {
+        repo.access_token.clear();
+    }
+    if let Some(repo) = &mut config.bitbucket_repo {
+        repo.access_token.clear();
+    }
+    if let Some(jira) = &mut config.jira {
+        jira.api_token.clear();
+    }
+    if let Some(linear) = &mut config.linear {
+        linear.api_key.clear();
+    }
+    for target in &mut config.notifications {
+        target.url.clear();
+    }
+
+    config
+}
+
+/// Find the executable providing a command on `PATH`
+///
+/// # Arguments
+///
+/// * `name` - Name of the command, like `foo` for `rust-ai-tool-foo`
+///
+/// # Returns
+///
+/// Path to the first matching executable, or `None` if there is none
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}{}{}", PLUGIN_PREFIX, name, env::consts::EXE_SUFFIX);
+    let path = env::var_os("PATH")?;
+
+    env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Root of the Cargo project enclosing a directory
+pub fn find_project_root(dir: &Path) -> Option<PathBuf> {
+    let dir = dir.canonicalize().ok()?;
+
+    dir.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Run a plugin with the context of the run
+///
+/// # Arguments
+///
+/// * `plugin` - Path to the plugin executable
+/// * `args` - Arguments after the command name
+/// * `context` - Context of the run
+///
+/// # Returns
+///
+/// Exit code of the plugin (2 if it was killed by a signal)
+pub fn run_plugin(plugin: &Path, args: &[OsString], context: &PluginContext) -> Result<i32> {
+    let context_json = serde_json::to_string(context).map_err(RustAiToolError::Json)?;
+    debug!("Running plugin {} with {:?}", plugin.display(), args);
+
+    let mut command = Command::new(plugin);
+    command.args(args)
+        .env("RUST_AI_TOOL_CONFIG", context.config_path)
+        .env("RUST_AI_TOOL_CONTEXT", context_json);
+    if let Ok(executable) = env::current_exe() {
+        command.env("RUST_AI_TOOL", executable);
+    }
+    if let Some(project_root) = &context.project_root {
+        command.env("RUST_AI_TOOL_PROJECT", project_root);
+    }
+
+    let status = command.status()
+        .map_err(|e| RustAiToolError::Other(format!("Failed to run plugin {}: {}", plugin.display(), e)))?;
+
+    Ok(status.code().unwrap_or(2))
+}
+
+/// Whether a path is a file that can be executed
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_run_plugin() {
+        let dir = tempdir().unwrap();
+        let plugin = dir.path().join("rust-ai-tool-hello");
+        fs::write(&plugin, "#!/bin/sh\nprintf '%s' \"$RUST_AI_TOOL_CONTEXT\" > \"$1\"\nexit 3\n").unwrap();
+        fs::set_permissions(&plugin, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.path().join("rust-ai-tool-plain"), "not executable").unwrap();
+
+        let output = dir.path().join("context.json");
+        let config = crate::config::parse_config(concat!(
+            "[ai_model]\nmodel_type = \"Claude\"\napi_key = \"sk-secret-key\"\n",
+            "[github_repo]\nowner = \"acme\"\nname = \"app\"\naccess_token = \"ghp_secret_token\"\nwebhook_secret = \"hook-secret\"\n",
+            "[analysis_options]\nrun_clippy = false\nuse_rust_analyzer = false\n",
+            "[validation_options]\nsyntax_only = true\ntauri_compatibility = false\nsecurity_validation = false\n",
+        ), None).unwrap();
+        let context = PluginContext {
+            version: "1.0.0",
+            config_path: Path::new(".rust-ai-tool.toml"),
+            profile: Some("ci"),
+            project_root: None,
+            config: &config,
+        };
+
+        assert_eq!(run_plugin(&plugin, &[output.clone().into()], &context).unwrap(), 3);
+        let passed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(passed["profile"], "ci");
+        assert_eq!(passed["config_path"], ".rust-ai-tool.toml");
+        assert!(passed["config"]["analysis_options"].is_object());
+        assert_eq!(passed["config"]["github_repo"]["owner"], "acme");
+
+        let passed = fs::read_to_string(&output).unwrap();
+        for secret in ["sk-secret-key", "ghp_secret_token", "hook-secret"] {
+            assert!(!passed.contains(secret), "{} passed to the plugin", secret);
+        }
+        assert_eq!(config.ai_model.api_key, "sk-secret-key");
+
+        assert!(is_executable(&plugin));
+        assert!(!is_executable(&dir.path().join("rust-ai-tool-plain")));
+        assert!(!is_executable(dir.path()));
+    }
+}
@@ -0,0 +1,291 @@
+//! Pull request delta module
+//!
+//! This module reports what a pull request changes in the analysis results:
+//! - Analysis of the base and head commits of the pull request in one checkout
+//! - Issues matched across commits by file, message and offending code, so
+//!   unrelated line shifts do not count as changes
+//! - One summary comment with severity tables, updated in place on later pushes
+
+use crate::analysis::{analyze_project, AnalysisResult, CodeIssue};
+use crate::analyzer_plugins::checkout_options;
+use crate::forge::run_git;
+use crate::github::GithubClient;
+use crate::{AnalysisOptions, Result, RustAiToolError, Severity};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::info;
+
+/// Marker identifying the summary comment of a pull request
+pub const DELTA_COMMENT_MARKER: &str = "<!-- rust-ai-tool-delta -->";
+
+/// Issues listed per section of the summary comment; the rest are only counted
+const MAX_LISTED_ISSUES: usize = 50;
+
+/// Severities in the order of the tables
+const SEVERITIES: [Severity; 4] = [Severity::Error, Severity::Warning, Severity::Info, Severity::Style];
+
+/// Issue with the key matching it across commits
+#[derive(Debug, Clone)]
+pub struct KeyedIssue {
+    /// File, category, message and trimmed offending line of the issue
+    pub key: String,
+
+    /// The issue, with a path relative to the checkout
+    pub issue: CodeIssue,
+}
+
+/// Issues a pull request introduces and resolves
+#[derive(Debug, Default)]
+pub struct IssueDelta {
+    /// Issues of the head commit missing from the base commit
+    pub introduced: Vec<CodeIssue>,
+
+    /// Issues of the base commit missing from the head commit
+    pub resolved: Vec<CodeIssue>,
+
+    /// Issues found in both commits
+    pub unchanged: usize,
+}
+
+/// Analyze the base and head commits of a pull request and comment the delta
+///
+/// The comment replaces the earlier summary comment of the pull request, if any.
+///
+/// # Arguments
+///
+/// * `client` - GitHub client of the repository the pull request targets
+/// * `pr_number` - Number of the pull request
+/// * `subdirectory` - Only analyze this subdirectory of the repository
+/// * `options` - Analysis options
+///
+/// # Returns
+///
+/// The delta and the posted comment
+pub async fn review_pull_request_delta(
+    client: &GithubClient,
+    pr_number: u64,
+    subdirectory: Option<&Path>,
+    options: &AnalysisOptions,
+) -> Result<(IssueDelta, String)> {
+    let commits = client.get_pull_request_commits(pr_number).await?;
+    info!("Comparing pull request #{} ({} -> {})", pr_number, commits.head_sha, commits.base_branch);
+
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let repo_path = client.clone_repo(Some(&commits.base_branch), temp_dir.path()).await?;
+    let analyzed_path = match subdirectory {
+        Some(subdirectory) => repo_path.join(subdirectory),
+        None => repo_path.clone(),
+    };
+    let options = &checkout_options(options, &analyzed_path, &repo_path);
+
+    // The head commit is fetched through the pull request ref, which also covers forks
+    run_git(&repo_path, &["checkout", "--detach", &commits.base_sha], "check out the base commit").await?;
+    let base = analyze_commit(&analyzed_path, options).await?;
+
+    let pull_ref = format!("pull/{}/head", pr_number);
+    run_git(&repo_path, &["fetch", "origin", &pull_ref], "fetch the pull request").await?;
+    run_git(&repo_path, &["checkout", "--detach", "FETCH_HEAD"], "check out the head commit").await?;
+    let head = analyze_commit(&analyzed_path, options).await?;
+
+    let delta = compute_delta(base, head);
+    let comment = format_delta_comment(&delta, &commits.base_sha, &commits.head_sha);
+    client.upsert_pr_comment(pr_number, DELTA_COMMENT_MARKER, &comment).await?;
+
+    info!(
+        "Pull request #{} introduces {} issues and resolves {}",
+        pr_number,
+        delta.introduced.len(),
+        delta.resolved.len()
+    );
+    Ok((delta, comment))
+}
+
+/// Analyze the checked out commit and key its issues before the next checkout
+async fn analyze_commit(analyzed_path: &Path, options: &AnalysisOptions) -> Result<Vec<KeyedIssue>> {
+    let path = analyzed_path.to_path_buf();
+    let options = options.clone();
+    let results = tokio::task::spawn_blocking(move || analyze_project(&path, &options))
+        .await
+        .map_err(|e| RustAiToolError::Analysis(format!("Analysis task failed: {}", e)))??;
+
+    Ok(key_issues(&results, analyzed_path))
+}
+
+/// Key the issues of analysis results for matching across commits
+///
+/// Reads the offending lines, so it must run while the analyzed commit is checked out.
+///
+/// # Arguments
+///
+/// * `results` - Analysis results
+/// * `root` - Root of the analyzed checkout, stripped from file paths
+///
+/// # Returns
+///
+/// The issues with their keys
+pub fn key_issues(results: &[AnalysisResult], root: &Path) -> Vec<KeyedIssue> {
+    let mut file_lines: HashMap<PathBuf, Vec<String>> = HashMap::new();
+
+    results.iter()
+        .flat_map(|result| &result.issues)
+        .map(|issue| {
+            let lines = file_lines.entry(issue.file_path.clone()).or_insert_with(|| {
+                fs::read_to_string(&issue.file_path)
+                    .map(|content| content.lines().map(|line| line.trim().to_string()).collect())
+                    .unwrap_or_default()
+            });
+            let line = issue.line_start.checked_sub(1).and_then(|index| lines.get(index)).map_or("", String::as_str);
+
+            let mut issue = issue.clone();
+            issue.file_path = issue.file_path.strip_prefix(root).unwrap_or(&issue.file_path).to_path_buf();
+
+            KeyedIssue {
+                key: format!("{}\n{:?}\n{}\n{}", issue.file_path.display(), issue.category, issue.message, line),
+                issue,
+            }
+        })
+        .collect()
+}
+
+/// Match the issues of two commits
+///
+/// # Arguments
+///
+/// * `base` - Keyed issues of the base commit
+/// * `head` - Keyed issues of the head commit
+///
+/// # Returns
+///
+/// The issues introduced and resolved by the head commit
+pub fn compute_delta(base: Vec<KeyedIssue>, head: Vec<KeyedIssue>) -> IssueDelta {
+    // Counts, since the same issue may occur several times on identical lines
+    let mut base_counts: HashMap<String, usize> = HashMap::new();
+    for keyed in &base {
+        *base_counts.entry(keyed.key.clone()).or_default() += 1;
+    }
+
+    let mut delta = IssueDelta::default();
+    let mut matched: HashMap<String, usize> = HashMap::new();
+
+    for keyed in head {
+        match base_counts.get_mut(&keyed.key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                *matched.entry(keyed.key).or_default() += 1;
+                delta.unchanged += 1;
+            }
+            _ => delta.introduced.push(keyed.issue),
+        }
+    }
+
+    for keyed in base {
+        match matched.get_mut(&keyed.key) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => delta.resolved.push(keyed.issue),
+        }
+    }
+
+    delta
+}
+
+/// Format the delta as the summary comment of a pull request
+///
+/// # Arguments
+///
+/// * `delta` - Issues introduced and resolved
+/// * `base_sha` - Base commit
+/// * `head_sha` - Head commit
+///
+/// # Returns
+///
+/// The comment, in GitHub-flavored Markdown, starting with `DELTA_COMMENT_MARKER`
+pub fn format_delta_comment(delta: &IssueDelta, base_sha: &str, head_sha: &str) -> String {
+    let short = |sha: &str| sha.chars().take(7).collect::<String>();
+
+    let mut comment = format!(
+        "{}\n### Rust AI Tool analysis delta\n\nComparing {} (head) with {} (base): {} introduced, {} resolved, {} unchanged.\n\n",
+        DELTA_COMMENT_MARKER,
+        short(head_sha),
+        short(base_sha),
+        delta.introduced.len(),
+        delta.resolved.len(),
+        delta.unchanged
+    );
+
+    comment.push_str("| Severity | Introduced | Resolved |\n|---|---|---|\n");
+    for severity in &SEVERITIES {
+        let count = |issues: &[CodeIssue]| issues.iter().filter(|issue| &issue.severity == severity).count();
+        comment.push_str(&format!(
+            "| {:?} | {} | {} |\n",
+            severity,
+            count(&delta.introduced),
+            count(&delta.resolved)
+        ));
+    }
+
+    for (title, issues) in [("Introduced issues", &delta.introduced), ("Resolved issues", &delta.resolved)] {
+        if issues.is_empty() {
+            continue;
+        }
+
+        comment.push_str(&format!("\n#### {}\n\n| File | Line | Severity | Issue |\n|---|---|---|---|\n", title));
+        for issue in issues.iter().take(MAX_LISTED_ISSUES) {
+            comment.push_str(&format!(
+                "| `{}` | {} | {:?} | {} |\n",
+                issue.file_path.display(),
+                issue.line_start,
+                issue.severity,
+                issue.message.replace('|', "\\|").replace('\n', " ")
+            ));
+        }
+
+        if issues.len() > MAX_LISTED_ISSUES {
+            comment.push_str(&format!("\n{} more not shown.\n", issues.len() - MAX_LISTED_ISSUES));
+        }
+    }
+
+    comment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::IssueCategory;
+
+    fn keyed(key: &str, line: usize) -> KeyedIssue {
+        KeyedIssue {
+            key: key.to_string(),
+            issue: CodeIssue {
+                file_path: PathBuf::from("src/lib.rs"),
+                line_start: line,
+                column_start: 1,
+                line_end: line,
+                column_end: 1,
+                category: IssueCategory::CodeQuality,
+                severity: Severity::Warning,
+                message: key.to_string(),
+                suggested_fix: None,
+                priority: None,
+                lint: None,
+                owners: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_compute_delta() {
+        // `kept` moved from line 3 to line 5 and occurs once more in the head
+        let base = vec![keyed("kept", 3), keyed("fixed", 4)];
+        let head = vec![keyed("kept", 5), keyed("kept", 6), keyed("new", 1)];
+
+        let delta = compute_delta(base, head);
+        assert_eq!(delta.unchanged, 1);
+        assert_eq!(delta.introduced.iter().map(|i| i.message.as_str()).collect::<Vec<_>>(), ["kept", "new"]);
+        assert_eq!(delta.resolved.iter().map(|i| i.message.as_str()).collect::<Vec<_>>(), ["fixed"]);
+
+        let comment = format_delta_comment(&delta, "aaaaaaaaaa", "bbbbbbbbbb");
+        assert!(comment.starts_with(DELTA_COMMENT_MARKER));
+        assert!(comment.contains("| Warning | 2 | 1 |"));
+    }
+}
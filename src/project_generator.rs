@@ -4,12 +4,22 @@
 //! - Create new Rust projects from templates
 //! - Generate code based on AI descriptions
 //! - Create project scaffolding with best practices
+//! - Render project files from embedded or user-provided Handlebars templates
 
 use crate::{Result, RustAiToolError, AiModelConfig};
+use crate::execution::CargoCommand;
+use crate::license::{current_year, write_license_files, License};
+use crate::manifest::Manifest;
+use crate::models::AiModelClient;
+use crate::templates::{is_git_url, TemplateContext, TemplateEngine};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::fs;
 use tokio::process::Command;
-use log::{debug, info, warn, error};
+use log::{debug, info, warn};
+use regex::Regex;
+use toml_edit::Value;
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
 
 /// Project template
@@ -27,28 +37,84 @@ pub enum ProjectTemplate {
     /// Web service with Actix
     WebService,
     
-    /// Tauri desktop application
+    /// Tauri 2 desktop application with a Vite frontend
     TauriApp,
     
     /// Web service with Axum
     Axum,
     
+    /// Database-backed API with Axum, SQLx and Postgres
+    DatabaseApi,
+    
     /// REST API with Rocket
     RocketApi,
     
+    /// gRPC service with Tonic
+    Grpc,
+    
     /// WebAssembly project
     WasmProject,
     
+    /// Browser application with Leptos, bundled with Trunk
+    Leptos,
+    
+    /// Browser application with Yew, bundled with Trunk
+    Yew,
+    
     /// Embedded Rust project
     EmbeddedRust,
     
     /// Machine Learning project
     MachineLearning,
     
-    /// Custom template
+    /// Custom template: a template of a user template directory, or the URL of a git repository
     Custom(String),
 }
 
+impl ProjectTemplate {
+    /// Template by name; names other than the built-in ones select a user template
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "basic" => ProjectTemplate::Basic,
+            "library" => ProjectTemplate::Library,
+            "cli" => ProjectTemplate::Cli,
+            "web-service" => ProjectTemplate::WebService,
+            "tauri" => ProjectTemplate::TauriApp,
+            "axum" => ProjectTemplate::Axum,
+            "axum-sqlx" => ProjectTemplate::DatabaseApi,
+            "rocket" => ProjectTemplate::RocketApi,
+            "grpc" => ProjectTemplate::Grpc,
+            "wasm" => ProjectTemplate::WasmProject,
+            "leptos" => ProjectTemplate::Leptos,
+            "yew" => ProjectTemplate::Yew,
+            "embedded" => ProjectTemplate::EmbeddedRust,
+            "machine-learning" => ProjectTemplate::MachineLearning,
+            name => ProjectTemplate::Custom(name.to_string()),
+        }
+    }
+    
+    /// Name of the template directory rendering this template
+    pub fn template_name(&self) -> String {
+        match self {
+            ProjectTemplate::Basic => "basic",
+            ProjectTemplate::Library => "library",
+            ProjectTemplate::Cli => "cli",
+            ProjectTemplate::WebService => "web-service",
+            ProjectTemplate::TauriApp => "tauri",
+            ProjectTemplate::Axum => "axum",
+            ProjectTemplate::DatabaseApi => "axum-sqlx",
+            ProjectTemplate::RocketApi => "rocket",
+            ProjectTemplate::Grpc => "grpc",
+            ProjectTemplate::WasmProject => "wasm",
+            ProjectTemplate::Leptos => "leptos",
+            ProjectTemplate::Yew => "yew",
+            ProjectTemplate::EmbeddedRust => "embedded",
+            ProjectTemplate::MachineLearning => "machine-learning",
+            ProjectTemplate::Custom(name) => name,
+        }.to_string()
+    }
+}
+
 /// Configuration for project generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
@@ -77,11 +143,145 @@ pub struct ProjectConfig {
     /// Additional dependencies to include
     pub dependencies: Vec<String>,
     
+    /// License of the project (an SPDX expression)
+    #[serde(default = "default_license")]
+    pub license: String,
+    
+    /// User template directories, overriding the embedded templates
+    #[serde(skip)]
+    pub template_dirs: Vec<PathBuf>,
+    
+    /// Versions and features of detected dependencies, extending or overriding the built-in presets
+    #[serde(skip)]
+    pub dependency_presets: BTreeMap<String, DependencyPreset>,
+    
+    /// Whether the post-generation hooks of the template may run
+    #[serde(skip)]
+    pub allow_hooks: bool,
+    
+    /// Whether to add a pre-commit configuration and git hook running fmt, clippy and the analysis
+    #[serde(skip)]
+    pub pre_commit: bool,
+    
+    /// Whether to add a `.devcontainer` definition with the Rust toolchain
+    #[serde(skip)]
+    pub devcontainer: bool,
+    
+    /// AI repair rounds when the generated project fails to build or test
+    /// (`None` skips the verification)
+    #[serde(skip)]
+    pub repair_iterations: Option<u32>,
+    
     /// AI model configuration for code generation
     #[serde(skip)]
     pub ai_model: Option<AiModelConfig>,
 }
 
+/// Template and verification settings of a generated project
+#[derive(Debug, Clone, Default)]
+pub struct TemplateOptions {
+    /// Template to use instead of the one matching the description
+    pub template: Option<String>,
+    
+    /// User template directories, overriding the embedded templates
+    pub template_dirs: Vec<PathBuf>,
+    
+    /// License of the project as an SPDX expression or alias like `dual` (MIT if not set)
+    pub license: Option<String>,
+    
+    /// Versions and features of detected dependencies, extending or overriding the built-in presets
+    pub dependency_presets: BTreeMap<String, DependencyPreset>,
+    
+    /// Run the post-generation hooks of the template
+    pub allow_hooks: bool,
+    
+    /// Add a `.pre-commit-config.yaml` and a git hook in `.githooks/` running
+    /// `cargo fmt`, `cargo clippy` and `rust-ai-tool analyze --changed --fail-on error`
+    pub pre_commit: bool,
+    
+    /// Add a `.devcontainer` definition with the Rust toolchain
+    pub devcontainer: bool,
+    
+    /// Build and test the project, letting the AI model repair it this many
+    /// times if it fails (`None` skips the verification)
+    pub repair_iterations: Option<u32>,
+}
+
+/// Version requirement and features a dependency of a generated project is added with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DependencyPreset {
+    /// Version requirement
+    pub version: String,
+    
+    /// Features to enable
+    #[serde(default)]
+    pub features: Vec<String>,
+    
+    /// Whether the default features of the crate stay enabled
+    #[serde(default = "default_features_enabled")]
+    pub default_features: bool,
+}
+
+/// Built-in dependency presets: crate, version, features and whether default features stay enabled
+const DEPENDENCY_PRESETS: &[(&str, &str, &[&str], bool)] = &[
+    ("actix-web", "4", &[], true),
+    ("anyhow", "1.0", &[], true),
+    ("async-std", "1.12", &["attributes"], true),
+    ("axum", "0.7", &[], true),
+    ("chrono", "0.4", &["serde"], true),
+    ("clap", "4.3", &["derive"], true),
+    ("diesel", "2.1", &["postgres"], true),
+    ("env_logger", "0.11", &[], true),
+    ("log", "0.4", &[], true),
+    ("rand", "0.8", &[], true),
+    ("regex", "1.8", &[], true),
+    // rustls avoids a dependency on the system OpenSSL
+    ("reqwest", "0.12", &["json", "rustls-tls"], false),
+    ("rocket", "0.5.0", &["json"], true),
+    ("rusqlite", "0.31", &["bundled"], true),
+    ("serde", "1.0", &["derive"], true),
+    ("sqlx", "0.8", &["runtime-tokio", "postgres"], true),
+    ("tauri", "2", &[], true),
+    ("thiserror", "1.0", &[], true),
+    ("tokio", "1.28", &["full"], true),
+    ("tracing", "0.1", &[], true),
+    ("uuid", "1.3", &["v4", "serde"], true),
+    ("wasm-bindgen", "0.2", &[], true),
+];
+
+/// Dependency presets: the built-in ones, extended or overridden by the user's
+///
+/// # Arguments
+///
+/// * `user_presets` - Presets of the configuration, by crate name
+///
+/// # Returns
+///
+/// Presets by crate name
+pub fn dependency_presets(user_presets: &BTreeMap<String, DependencyPreset>) -> BTreeMap<String, DependencyPreset> {
+    let mut presets: BTreeMap<String, DependencyPreset> = DEPENDENCY_PRESETS.iter()
+        .map(|(name, version, features, default_features)| {
+            let preset = DependencyPreset {
+                version: version.to_string(),
+                features: features.iter().map(|feature| feature.to_string()).collect(),
+                default_features: *default_features,
+            };
+            (name.to_string(), preset)
+        })
+        .collect();
+    
+    presets.extend(user_presets.iter().map(|(name, preset)| (name.clone(), preset.clone())));
+    presets
+}
+
+fn default_license() -> String {
+    "MIT".to_string()
+}
+
+fn default_features_enabled() -> bool {
+    true
+}
+
 /// Generate a new Rust project from a description
 ///
 /// # Arguments
@@ -90,6 +290,7 @@ pub struct ProjectConfig {
 /// * `output_dir` - Output directory
 /// * `name` - Project name
 /// * `ai_model` - AI model configuration
+/// * `template_options` - Template, template directories, license and verification
 ///
 /// # Returns
 ///
@@ -99,11 +300,32 @@ pub async fn generate_project_from_description(
     output_dir: &Path,
     name: &str,
     ai_model: &AiModelConfig,
+    template_options: &TemplateOptions,
 ) -> Result<PathBuf> {
     info!("Generating project from description: {}", description);
     
     // Create a project configuration based on the description
-    let config = analyze_description(description, output_dir, name, ai_model).await?;
+    let mut config = analyze_description(description, output_dir, name, ai_model, &template_options.dependency_presets).await?;
+    
+    if let Some(template) = &template_options.template {
+        config.template = ProjectTemplate::from_name(template);
+        config.crate_type = if config.template == ProjectTemplate::Library { "lib" } else { "bin" }.to_string();
+    }
+    config.template_dirs = template_options.template_dirs.clone();
+    config.dependency_presets = template_options.dependency_presets.clone();
+    config.allow_hooks = template_options.allow_hooks;
+    config.pre_commit = template_options.pre_commit;
+    config.devcontainer = template_options.devcontainer;
+    config.repair_iterations = template_options.repair_iterations;
+    if let Some(license) = &template_options.license {
+        config.license = match License::parse(license) {
+            Some(known) => known.spdx().to_string(),
+            None => {
+                warn!("No license text is available for {}, only setting the license field", license);
+                license.clone()
+            }
+        };
+    }
     
     // Generate the project
     generate_project(&config).await
@@ -111,12 +333,16 @@ pub async fn generate_project_from_description(
 
 /// Analyze a project description to determine configuration
 ///
+/// The AI model extracts the template, dependencies and crate type; the
+/// keyword heuristics are used when it cannot be reached or its answer is unusable.
+///
 /// # Arguments
 ///
 /// * `description` - Project description
 /// * `output_dir` - Output directory
 /// * `name` - Project name
 /// * `ai_model` - AI model configuration
+/// * `user_presets` - Dependency presets of the configuration, whose crates are detected too
 ///
 /// # Returns
 ///
@@ -126,17 +352,22 @@ async fn analyze_description(
     output_dir: &Path,
     name: &str,
     ai_model: &AiModelConfig,
+    user_presets: &BTreeMap<String, DependencyPreset>,
 ) -> Result<ProjectConfig> {
-    // Choose the appropriate template based on the description
-    let template = determine_template(description);
-    debug!("Selected template: {:?}", template);
-    
-    let crate_type = match template {
-        ProjectTemplate::Library => "lib".to_string(),
-        _ => "bin".to_string(),
+    let (template, crate_type, dependencies) = match extract_requirements_with_ai(description, ai_model).await {
+        Some(requirements) => requirements,
+        None => {
+            // Choose the appropriate template based on the description
+            let template = determine_template(description);
+            let crate_type = match template {
+                ProjectTemplate::Library => "lib".to_string(),
+                _ => "bin".to_string(),
+            };
+            
+            (template, crate_type, extract_dependencies(description, user_presets))
+        }
     };
-    
-    let dependencies = extract_dependencies(description);
+    debug!("Selected template: {:?}", template);
     
     let author = std::env::var("USER")
         .or_else(|_| std::env::var("USERNAME"))
@@ -151,48 +382,204 @@ async fn analyze_description(
         output_dir: output_dir.to_path_buf(),
         init_git: true,
         dependencies,
+        license: default_license(),
+        template_dirs: Vec::new(),
+        dependency_presets: user_presets.clone(),
+        allow_hooks: false,
+        pre_commit: false,
+        devcontainer: false,
+        repair_iterations: None,
         ai_model: Some(ai_model.clone()),
     })
 }
 
+/// Built-in templates and what they generate, as offered to the AI model
+const TEMPLATE_SUMMARIES: &[(&str, &str)] = &[
+    ("basic", "a plain binary"),
+    ("library", "a library crate"),
+    ("cli", "a command-line application with clap"),
+    ("web-service", "a web service with Actix"),
+    ("axum", "a web service with Axum"),
+    ("axum-sqlx", "a database-backed REST API with Axum, SQLx and Postgres"),
+    ("rocket", "a REST API with Rocket"),
+    ("grpc", "a gRPC service with Tonic"),
+    ("tauri", "a Tauri 2 desktop application with a web frontend"),
+    ("wasm", "a WebAssembly module"),
+    ("leptos", "a browser application with Leptos"),
+    ("yew", "a browser application with Yew"),
+    ("embedded", "no_std firmware for a microcontroller"),
+    ("machine-learning", "a machine learning project with linfa"),
+];
+
+/// Extract the template, crate type and dependencies of a project with the AI model
+///
+/// # Arguments
+///
+/// * `description` - Project description
+/// * `ai_model` - AI model configuration
+///
+/// # Returns
+///
+/// The template, crate type and dependencies, or `None` if the model failed
+/// or answered with something other than a built-in template
+async fn extract_requirements_with_ai(
+    description: &str,
+    ai_model: &AiModelConfig,
+) -> Option<(ProjectTemplate, String, Vec<String>)> {
+    let response = match AiModelClient::new(ai_model.clone()) {
+        Ok(client) => client.extract_project_requirements(description, TEMPLATE_SUMMARIES).await,
+        Err(e) => Err(e),
+    };
+    
+    let requirements = response.and_then(|response| parse_requirements(&response));
+    match requirements {
+        Ok(requirements) => Some(requirements),
+        Err(e) => {
+            warn!("Falling back to keyword matching to analyze the description: {}", e);
+            None
+        }
+    }
+}
+
+/// Requirements of a project as extracted by the AI model
+#[derive(Debug, Deserialize)]
+struct ExtractedRequirements {
+    /// Name of a built-in template
+    template: String,
+    
+    /// Crate names
+    #[serde(default)]
+    dependencies: Vec<String>,
+    
+    /// `bin` or `lib`
+    crate_type: String,
+}
+
+/// Parse and check the JSON object of requirements in a model response
+///
+/// The dependencies the template relies on are added if the model left them out.
+///
+/// # Returns
+///
+/// The template, crate type and dependencies
+fn parse_requirements(response: &str) -> Result<(ProjectTemplate, String, Vec<String>)> {
+    let json = match (response.find('{'), response.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => return Err(RustAiToolError::AiModel("Requirements response contains no JSON object".to_string())),
+    };
+    
+    let requirements: ExtractedRequirements = serde_json::from_str(json)
+        .map_err(|e| RustAiToolError::AiModel(format!("Invalid requirements response: {}", e)))?;
+    
+    if !TEMPLATE_SUMMARIES.iter().any(|(name, _)| *name == requirements.template) {
+        return Err(RustAiToolError::AiModel(format!("Unknown template in requirements response: {}", requirements.template)));
+    }
+    if requirements.crate_type != "bin" && requirements.crate_type != "lib" {
+        return Err(RustAiToolError::AiModel(format!("Unknown crate type in requirements response: {}", requirements.crate_type)));
+    }
+    
+    let crate_name_regex = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_-]*$").unwrap();
+    let template = ProjectTemplate::from_name(&requirements.template);
+    let mut dependencies: Vec<String> = Vec::new();
+    
+    for dependency in requirements.dependencies.into_iter().chain(template_default_dependency(&template).map(String::from)) {
+        if !crate_name_regex.is_match(&dependency) {
+            warn!("Ignoring invalid crate name from the AI model: {}", dependency);
+        } else if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+    }
+    
+    Ok((template, requirements.crate_type, dependencies))
+}
+
+/// Whether a description mentions a word or phrase, on its own or in plural form
+///
+/// Unlike a substring check, `ml` does not match `email` and `lib` does not match `library`.
+fn mentions(description: &str, word: &str) -> bool {
+    matches_word(description, &format!("{}s?", regex::escape(word)))
+}
+
+/// Whether a description mentions a crate by its exact name
+fn mentions_crate(description: &str, name: &str) -> bool {
+    matches_word(description, &regex::escape(name))
+}
+
+/// Whether a pattern matches a description between word boundaries, ignoring case
+fn matches_word(description: &str, pattern: &str) -> bool {
+    let pattern = format!(r"(?i)(?:^|[^[:alnum:]_]){}(?:$|[^[:alnum:]_])", pattern);
+    Regex::new(&pattern).is_ok_and(|regex| regex.is_match(description))
+}
+
+/// Whether a description mentions any of the words or phrases
+fn mentions_any(description: &str, words: &[&str]) -> bool {
+    words.iter().any(|word| mentions(description, word))
+}
+
 /// Determine the best template for the project based on description
 fn determine_template(description: &str) -> ProjectTemplate {
-    let description = description.to_lowercase();
-    
     // Check for specific keywords to select the appropriate template
-    if description.contains("wasm") || description.contains("webassembly") {
+    if mentions_crate(description, "leptos") {
+        ProjectTemplate::Leptos
+    } else if mentions_crate(description, "yew") {
+        ProjectTemplate::Yew
+    } else if mentions_any(description, &["wasm", "webassembly"]) {
         ProjectTemplate::WasmProject
-    } else if description.contains("embedded") || description.contains("microcontroller") || description.contains("arduino") {
+    } else if mentions_any(description, &["embedded", "microcontroller", "arduino", "firmware", "no_std"]) {
         ProjectTemplate::EmbeddedRust
-    } else if description.contains("machine learning") || description.contains("ml") || description.contains("ai") {
+    } else if mentions_any(description, &["machine learning", "ml", "ai"]) {
         ProjectTemplate::MachineLearning
-    } else if description.contains("tauri") || description.contains("desktop app") || description.contains("gui") {
+    } else if mentions_any(description, &["tauri", "desktop app", "gui"]) {
         ProjectTemplate::TauriApp
-    } else if description.contains("axum") {
+    } else if mentions_any(description, &["grpc", "protobuf", "tonic"]) {
+        ProjectTemplate::Grpc
+    } else if mentions_any(description, &["sqlx", "postgres", "postgresql", "database"]) {
+        ProjectTemplate::DatabaseApi
+    } else if mentions_crate(description, "axum") {
         ProjectTemplate::Axum
-    } else if description.contains("rocket") || description.contains("rest api") {
+    } else if mentions_any(description, &["rocket", "rest api"]) {
         ProjectTemplate::RocketApi
-    } else if description.contains("web") || description.contains("server") || description.contains("api") {
+    } else if mentions_any(description, &["web", "server", "api"]) {
         ProjectTemplate::WebService
-    } else if description.contains("cli") || description.contains("command") {
+    } else if mentions_any(description, &["cli", "command", "command-line"]) {
         ProjectTemplate::Cli
-    } else if description.contains("library") || description.contains("lib") {
+    } else if mentions_any(description, &["library", "lib"]) {
         ProjectTemplate::Library
     } else {
         ProjectTemplate::Basic
     }
 }
 
+/// Crate the code of a built-in template is written against
+fn template_default_dependency(template: &ProjectTemplate) -> Option<&'static str> {
+    match template {
+        ProjectTemplate::Cli => Some("clap"),
+        ProjectTemplate::WebService => Some("actix-web"),
+        ProjectTemplate::Axum => Some("axum"),
+        ProjectTemplate::DatabaseApi => Some("sqlx"),
+        ProjectTemplate::RocketApi => Some("rocket"),
+        ProjectTemplate::Grpc => Some("tonic"),
+        ProjectTemplate::TauriApp => Some("tauri"),
+        ProjectTemplate::WasmProject => Some("wasm-bindgen"),
+        ProjectTemplate::Leptos => Some("leptos"),
+        ProjectTemplate::Yew => Some("yew"),
+        ProjectTemplate::EmbeddedRust => Some("embedded-hal"),
+        ProjectTemplate::MachineLearning => Some("linfa"),
+        _ => None,
+    }
+}
+
 /// Extract dependencies from a project description
 ///
 /// # Arguments
 ///
 /// * `description` - Project description
+/// * `user_presets` - Dependency presets of the configuration, whose crates are detected too
 ///
 /// # Returns
 ///
 /// List of dependencies
-fn extract_dependencies(description: &str) -> Vec<String> {
+fn extract_dependencies(description: &str, user_presets: &BTreeMap<String, DependencyPreset>) -> Vec<String> {
     let mut dependencies = Vec::new();
     
     // Common crates to detect
@@ -201,60 +588,25 @@ fn extract_dependencies(description: &str) -> Vec<String> {
         "rocket", "diesel", "sqlx", "rusqlite", "mongodb", "tauri",
         "egui", "wgpu", "image", "anyhow", "thiserror", "tracing",
         "log", "env_logger", "rand", "chrono", "uuid", "regex",
-        "axum", "wasm-bindgen", "web-sys", "js-sys", "linfa",
-        "embedded-hal", "cortex-m", "no_std", "alloc", "async-std",
+        "axum", "wasm-bindgen", "web-sys", "js-sys", "linfa", "leptos", "yew",
+        "embedded-hal", "cortex-m", "async-std",
     ];
     
-    for crate_name in &known_crates {
-        if description.to_lowercase().contains(crate_name) {
+    let user_crates = user_presets.keys()
+        .map(String::as_str)
+        .filter(|name| !known_crates.contains(name));
+    
+    for crate_name in known_crates.into_iter().chain(user_crates) {
+        if mentions_crate(description, crate_name) {
             dependencies.push(crate_name.to_string());
         }
     }
     
     // Add template-specific dependencies
-    let template = determine_template(description);
-    match template {
-        ProjectTemplate::Cli => {
-            if !dependencies.contains(&"clap".to_string()) {
-                dependencies.push("clap".to_string());
-            }
-        },
-        ProjectTemplate::WebService => {
-            if !dependencies.contains(&"actix-web".to_string()) {
-                dependencies.push("actix-web".to_string());
-            }
-        },
-        ProjectTemplate::Axum => {
-            if !dependencies.contains(&"axum".to_string()) {
-                dependencies.push("axum".to_string());
-            }
-        },
-        ProjectTemplate::RocketApi => {
-            if !dependencies.contains(&"rocket".to_string()) {
-                dependencies.push("rocket".to_string());
-            }
-        },
-        ProjectTemplate::TauriApp => {
-            if !dependencies.contains(&"tauri".to_string()) {
-                dependencies.push("tauri".to_string());
-            }
-        },
-        ProjectTemplate::WasmProject => {
-            if !dependencies.contains(&"wasm-bindgen".to_string()) {
-                dependencies.push("wasm-bindgen".to_string());
-            }
-        },
-        ProjectTemplate::EmbeddedRust => {
-            if !dependencies.contains(&"embedded-hal".to_string()) {
-                dependencies.push("embedded-hal".to_string());
-            }
-        },
-        ProjectTemplate::MachineLearning => {
-            if !dependencies.contains(&"linfa".to_string()) {
-                dependencies.push("linfa".to_string());
-            }
-        },
-        _ => {}
+    if let Some(dependency) = template_default_dependency(&determine_template(description)) {
+        if !dependencies.iter().any(|name| name == dependency) {
+            dependencies.push(dependency.to_string());
+        }
     }
     
     dependencies
@@ -277,20 +629,18 @@ pub async fn generate_project(config: &ProjectConfig) -> Result<PathBuf> {
         .map_err(|e| RustAiToolError::Io(e))?;
     
     // Initialize Cargo project
-    let cargo_init_result = Command::new("cargo")
-        .arg("init")
-        .arg("--name")
-        .arg(&config.name)
-        .arg(if config.crate_type == "lib" { "--lib" } else { "--bin" })
-        .current_dir(&project_dir)
-        .status()
+    let cargo_init_result = CargoCommand::new(&project_dir)
+        .args(["init", "--name", config.name.as_str()])
+        .args([if config.crate_type == "lib" { "--lib" } else { "--bin" }])
+        .output_async()
         .await
         .map_err(|e| RustAiToolError::Io(e))?;
     
-    if !cargo_init_result.success() {
+    if !cargo_init_result.status.success() {
         return Err(RustAiToolError::ProjectGeneration(format!(
-            "Failed to initialize Cargo project (exit code: {:?})",
-            cargo_init_result.code()
+            "Failed to initialize Cargo project (exit code: {:?}): {}",
+            cargo_init_result.status.code(),
+            String::from_utf8_lossy(&cargo_init_result.stderr).trim()
         )));
     }
     
@@ -300,9 +650,21 @@ pub async fn generate_project(config: &ProjectConfig) -> Result<PathBuf> {
     // Generate project files based on template
     generate_project_files(&project_dir, config).await?;
     
+    // Make sure the project builds and its tests pass
+    if let Some(repair_iterations) = config.repair_iterations {
+        verify_project(&project_dir, config, repair_iterations).await?;
+    }
+    
+    // Document the final API, now that the code builds
+    generate_readme(&project_dir, config).await?;
+    
     // Initialize Git repository if requested
     if config.init_git {
         init_git_repository(&project_dir).await?;
+        
+        if config.pre_commit {
+            enable_git_hooks(&project_dir).await?;
+        }
     }
     
     Ok(project_dir)
@@ -320,144 +682,33 @@ pub async fn generate_project(config: &ProjectConfig) -> Result<PathBuf> {
 /// Success status
 async fn update_cargo_toml(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
     let cargo_toml_path = project_dir.join("Cargo.toml");
-    
-    // Read the existing Cargo.toml
-    let cargo_toml = fs::read_to_string(&cargo_toml_path)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Parse it
-    let mut cargo_doc = cargo_toml.parse::<toml::Document>()
-        .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to parse Cargo.toml: {}", e)))?;
+    let mut manifest = Manifest::load(&cargo_toml_path)?;
     
     // Update package metadata
-    if let Some(package) = cargo_doc.get_mut("package") {
-        if let Some(table) = package.as_table_mut() {
-            // Update description
-            table.insert("description", toml::value::Value::String(config.description.clone()));
-            
-            // Update author
-            table.insert("authors", toml::value::Value::Array(vec![
-                toml::value::Value::String(config.author.clone())
-            ]));
-            
-            // Add license
-            table.insert("license", toml::value::Value::String("MIT".to_string()));
-            
-            // Add repository (default to GitHub)
-            table.insert(
-                "repository",
-                toml::value::Value::String(format!("https://github.com/username/{}", config.name)),
-            );
-            
-            // Add keywords
-            let keywords = extract_keywords(&config.description);
-            table.insert(
-                "keywords",
-                toml::value::Value::Array(
-                    keywords
-                        .iter()
-                        .map(|k| toml::value::Value::String(k.clone()))
-                        .collect(),
-                ),
-            );
-        }
-    }
-    
-    // Add dependencies
-    if let Some(deps) = cargo_doc.get_mut("dependencies") {
-        if let Some(table) = deps.as_table_mut() {
-            for dep in &config.dependencies {
-                // Handle special cases for specific dependencies
-                if dep == "clap" {
-                    // Add clap with features
-                    table.insert(
-                        "clap",
-                        toml::value::Value::Table({
-                            let mut t = toml::Table::new();
-                            t.insert(
-                                "version".to_string(),
-                                toml::value::Value::String("4.3".to_string()),
-                            );
-                            t.insert(
-                                "features".to_string(),
-                                toml::value::Value::Array(vec![
-                                    toml::value::Value::String("derive".to_string()),
-                                ]),
-                            );
-                            t
-                        }),
-                    );
-                } else if dep == "tokio" {
-                    // Add tokio with features
-                    table.insert(
-                        "tokio",
-                        toml::value::Value::Table({
-                            let mut t = toml::Table::new();
-                            t.insert(
-                                "version".to_string(),
-                                toml::value::Value::String("1.28".to_string()),
-                            );
-                            t.insert(
-                                "features".to_string(),
-                                toml::value::Value::Array(vec![
-                                    toml::value::Value::String("full".to_string()),
-                                ]),
-                            );
-                            t
-                        }),
-                    );
-                } else if dep == "serde" {
-                    // Add serde with features
-                    table.insert(
-                        "serde",
-                        toml::value::Value::Table({
-                            let mut t = toml::Table::new();
-                            t.insert(
-                                "version".to_string(),
-                                toml::value::Value::String("1.0".to_string()),
-                            );
-                            t.insert(
-                                "features".to_string(),
-                                toml::value::Value::Array(vec![
-                                    toml::value::Value::String("derive".to_string()),
-                                ]),
-                            );
-                            t
-                        }),
-                    );
-                } else if dep == "tauri" {
-                    // Add tauri with features
-                    table.insert(
-                        "tauri",
-                        toml::value::Value::Table({
-                            let mut t = toml::Table::new();
-                            t.insert(
-                                "version".to_string(),
-                                toml::value::Value::String("1.4".to_string()),
-                            );
-                            t.insert(
-                                "features".to_string(),
-                                toml::value::Value::Array(vec![
-                                    toml::value::Value::String("dialog".to_string()),
-                                    toml::value::Value::String("fs".to_string()),
-                                ]),
-                            );
-                            t
-                        }),
-                    );
-                } else {
-                    // Default for other dependencies
-                    table.insert(dep, toml::value::Value::String("*".to_string()));
-                }
-            }
+    manifest.set_package_field("description", config.description.as_str())?;
+    manifest.set_package_list("authors", &[&config.author])?;
+    manifest.set_package_field("license", config.license.as_str())?;
+    
+    // Add repository (default to GitHub)
+    manifest.set_package_field("repository", format!("https://github.com/username/{}", config.name))?;
+    manifest.set_package_list("keywords", &extract_keywords(&config.description))?;
+    
+    // Add dependencies, with the versions and features of their presets
+    let presets = dependency_presets(&config.dependency_presets);
+    for dep in &config.dependencies {
+        let Some(preset) = presets.get(dep) else {
+            manifest.add_dependency("dependencies", dep, "*", &[])?;
+            continue;
+        };
+        
+        let features: Vec<&str> = preset.features.iter().map(String::as_str).collect();
+        manifest.add_dependency("dependencies", dep, &preset.version, &features)?;
+        if !preset.default_features {
+            manifest.set_default_features("dependencies", dep, false)?;
         }
     }
     
-    // Write the updated Cargo.toml
-    fs::write(&cargo_toml_path, cargo_doc.to_string())
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    Ok(())
+    manifest.save(&cargo_toml_path)
 }
 
 /// Extract keywords from a project description
@@ -480,7 +731,7 @@ fn extract_keywords(description: &str) -> Vec<String> {
     ];
     
     for keyword in &common_keywords {
-        if description.to_lowercase().contains(keyword) && !keywords.contains(&keyword.to_string()) {
+        if mentions(description, keyword) && !keywords.contains(&keyword.to_string()) {
             keywords.push(keyword.to_string());
         }
     }
@@ -493,6 +744,9 @@ fn extract_keywords(description: &str) -> Vec<String> {
 
 /// Generate project files based on template
 ///
+/// The `basic` template is rendered first, so every project has its files
+/// unless the selected template overrides them.
+///
 /// # Arguments
 ///
 /// * `project_dir` - Project directory
@@ -502,473 +756,427 @@ fn extract_keywords(description: &str) -> Vec<String> {
 ///
 /// Success status
 async fn generate_project_files(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    match &config.template {
-        ProjectTemplate::Basic => generate_basic_project(project_dir, config).await?,
-        ProjectTemplate::Library => generate_library_project(project_dir, config).await?,
-        ProjectTemplate::Cli => generate_cli_project(project_dir, config).await?,
-        ProjectTemplate::WebService => generate_web_service_project(project_dir, config).await?,
-        ProjectTemplate::Axum => generate_axum_project(project_dir, config).await?,
-        ProjectTemplate::RocketApi => generate_rocket_project(project_dir, config).await?,
-        ProjectTemplate::TauriApp => generate_tauri_project(project_dir, config).await?,
-        ProjectTemplate::WasmProject => generate_wasm_project(project_dir, config).await?,
-        ProjectTemplate::EmbeddedRust => generate_embedded_project(project_dir, config).await?,
-        ProjectTemplate::MachineLearning => generate_ml_project(project_dir, config).await?,
-        ProjectTemplate::Custom(template_path) => {
-            generate_custom_project(project_dir, config, template_path).await?
-        },
+    let mut engine = TemplateEngine::new(&config.template_dirs)?;
+    let template = config.template.template_name();
+    
+    if is_git_url(&template) {
+        let checkout = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+        clone_template(&template, checkout.path()).await?;
+        engine.load_template(&template, checkout.path(), true)?;
+    }
+    
+    let mut context = TemplateContext::new(
+        &config.name,
+        &config.description,
+        &config.author,
+        &config.license,
+        &config.dependencies,
+    );
+    if let Some(prompts) = engine.snippet_prompts(&template) {
+        context.snippets = generate_snippets(prompts, config).await?;
+    }
+    
+    if let Some(license) = License::parse(&config.license) {
+        context.license_header = license.header(current_year(), &config.author);
+        write_license_files(project_dir, license, &config.author)?;
+    }
+    
+    engine.render("basic", project_dir, &context)?;
+    
+    // If we have an AI model, we can also generate some initial code
+    if let Some(ai_model) = &config.ai_model {
+        generate_main_rs_with_ai(project_dir, config, ai_model).await?;
+    }
+    
+    if template != "basic" {
+        if engine.has_template(&template) || matches!(config.template, ProjectTemplate::Custom(_)) {
+            engine.render(&template, project_dir, &context)?;
+        } else {
+            warn!("No {} template is available, generating a basic project", template);
+        }
+    }
+    
+    // The Tauri template turns the root into a workspace of src-tauri, and its frontend owns src/
+    if config.template == ProjectTemplate::TauriApp && engine.has_template(&template) {
+        let root_main = project_dir.join("src").join("main.rs");
+        if root_main.exists() {
+            fs::remove_file(&root_main).map_err(RustAiToolError::Io)?;
+        }
     }
     
+    render_onboarding_files(&engine, project_dir, config, &context)?;
+    add_template_dependencies(project_dir, config)?;
+    run_post_hooks(engine.post_hooks(&template), project_dir, config).await?;
+    
     Ok(())
 }
 
-/// Generate a basic Rust project
+/// Render the pre-commit and devcontainer files the configuration asks for
 ///
 /// # Arguments
 ///
+/// * `engine` - Template engine
 /// * `project_dir` - Project directory
 /// * `config` - Project configuration
+/// * `context` - Template context
 ///
 /// # Returns
 ///
 /// Success status
-async fn generate_basic_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // The most basic project will have been initialized by cargo init
-    // We can add some additional files or customizations here
-    
-    // Create a README.md
-    let readme_path = project_dir.join("README.md");
-    let readme_content = format!(
-        "# {}\n\n{}\n\n## Getting Started\n\n```bash\ncargo run\n```\n",
-        config.name, config.description
-    );
-    
-    fs::write(&readme_path, readme_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create a .gitignore
-    let gitignore_path = project_dir.join(".gitignore");
-    let gitignore_content = r#"/target
-**/*.rs.bk
-Cargo.lock
-"#;
-    
-    fs::write(&gitignore_path, gitignore_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+fn render_onboarding_files(
+    engine: &TemplateEngine,
+    project_dir: &Path,
+    config: &ProjectConfig,
+    context: &TemplateContext,
+) -> Result<()> {
+    if config.pre_commit {
+        engine.render("pre-commit", project_dir, context)?;
+        
+        // Git only runs hooks that are executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            
+            fs::set_permissions(project_dir.join(".githooks").join("pre-commit"), fs::Permissions::from_mode(0o755))
+                .map_err(RustAiToolError::Io)?;
+        }
+    }
     
-    // If we have an AI model, we can also generate some initial code
-    if let Some(ai_model) = &config.ai_model {
-        // Generate main.rs content with AI
-        generate_main_rs_with_ai(project_dir, config, ai_model).await?;
+    if config.devcontainer {
+        engine.render("devcontainer", project_dir, context)?;
     }
     
     Ok(())
 }
 
-/// Generate a library Rust project
+/// Point git at the hooks in `.githooks/`, so the pre-commit hook runs without installing it
 ///
 /// # Arguments
 ///
 /// * `project_dir` - Project directory
-/// * `config` - Project configuration
 ///
 /// # Returns
 ///
 /// Success status
-async fn generate_library_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // Create a basic project first
-    generate_basic_project(project_dir, config).await?;
-    
-    // Create src/lib.rs with better documentation
-    let lib_rs_path = project_dir.join("src").join("lib.rs");
-    let lib_rs_content = format!(
-        r#"//! # {}
-//!
-//! {}
-//!
-//! ## Examples
-//!
-//! ```
-//! // Example code will go here
-//! ```
+async fn enable_git_hooks(project_dir: &Path) -> Result<()> {
+    let status = Command::new("git")
+        .args(["config", "core.hooksPath", ".githooks"])
+        .current_dir(project_dir)
+        .status()
+        .await
+        .map_err(RustAiToolError::Io)?;
+    
+    if !status.success() {
+        warn!("Failed to enable the git hooks; run `git config core.hooksPath .githooks` in the project");
+    }
+    
+    Ok(())
+}
 
-/// Example function
+/// Clone a template repository
 ///
-/// # Examples
+/// # Arguments
 ///
-/// ```
-//! // Example usage
-//! ```
-pub fn example_function() -> bool {{
-    true
-}}
-
-#[cfg(test)]
-mod tests {{
-    use super::*;
-
-    #[test]
-    fn it_works() {{
-        assert_eq!(example_function(), true);
-    }}
-}}
-"#,
-        config.name, config.description
-    );
-    
-    fs::write(&lib_rs_path, lib_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create examples directory with a simple example
-    let examples_dir = project_dir.join("examples");
-    fs::create_dir_all(&examples_dir)
-        .map_err(|e| RustAiToolError::Io(e))?;
+/// * `url` - URL of the repository
+/// * `target_dir` - Directory the repository is cloned into
+///
+/// # Returns
+///
+/// Success status
+async fn clone_template(url: &str, target_dir: &Path) -> Result<()> {
+    info!("Cloning template {}", url);
     
-    let example_path = examples_dir.join("simple_example.rs");
-    let example_content = format!(
-        r#"fn main() {{
-    println!("Example for {}: {{}}", {}::example_function());
-}}
-"#,
-        config.name, config.name.replace('-', "_")
-    );
+    let output = Command::new("git")
+        .args(["clone", "--depth", "1", url])
+        .arg(target_dir)
+        .output()
+        .await
+        .map_err(RustAiToolError::Io)?;
     
-    fs::write(&example_path, example_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    if !output.status.success() {
+        return Err(RustAiToolError::ProjectGeneration(format!(
+            "Failed to clone template {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
     
     Ok(())
 }
 
-/// Generate a CLI Rust project
+/// Run the post-generation hooks of a template in the generated project
+///
+/// Hooks run arbitrary commands, so they only run when the configuration allows it.
 ///
 /// # Arguments
 ///
+/// * `hooks` - Commands of the template
 /// * `project_dir` - Project directory
 /// * `config` - Project configuration
 ///
 /// # Returns
 ///
 /// Success status
-async fn generate_cli_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // Create a basic project first
-    generate_basic_project(project_dir, config).await?;
-    
-    // Create src/main.rs with CLI setup
-    let main_rs_path = project_dir.join("src").join("main.rs");
-    let main_rs_content = format!(
-        r#"use clap::{{Parser, Subcommand}};
-
-/// {} - {}
-#[derive(Parser, Debug)]
-#[clap(author, version, about)]
-struct Cli {{
-    /// Input file
-    #[clap(short, long)]
-    input: Option<std::path::PathBuf>,
-
-    /// Output file
-    #[clap(short, long)]
-    output: Option<std::path::PathBuf>,
-
-    /// Verbosity level
-    #[clap(short, long, action = clap::ArgAction::Count)]
-    verbose: u8,
-
-    /// Subcommand to execute
-    #[clap(subcommand)]
-    command: Option<Commands>,
-}}
-
-#[derive(Subcommand, Debug)]
-enum Commands {{
-    /// Example command
-    Example {{
-        /// Example argument
-        #[clap(short, long)]
-        name: String,
-    }},
-}}
-
-fn main() {{
-    let cli = Cli::parse();
-    
-    // Set up logging based on verbosity
-    let log_level = match cli.verbose {{
-        0 => log::LevelFilter::Warn,
-        1 => log::LevelFilter::Info,
-        2 => log::LevelFilter::Debug,
-        _ => log::LevelFilter::Trace,
-    }};
-    
-    env_logger::Builder::new()
-        .filter_level(log_level)
-        .init();
-    
-    log::info!("Starting application");
-    
-    // Handle subcommands
-    match &cli.command {{
-        Some(Commands::Example {{ name }}) => {{
-            println!("Running example command with name: {{}}", name);
-        }},
-        None => {{
-            println!("No subcommand specified, running default action");
-        }},
-    }}
-}}
-"#,
-        config.name, config.description
-    );
-    
-    fs::write(&main_rs_path, main_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+async fn run_post_hooks(hooks: &[String], project_dir: &Path, config: &ProjectConfig) -> Result<()> {
+    if hooks.is_empty() {
+        return Ok(());
+    }
     
-    // Update Cargo.toml to add clap and logging dependencies if not already added
-    let mut dependencies = vec!["clap".to_string(), "log".to_string(), "env_logger".to_string()];
-    dependencies.retain(|d| !config.dependencies.contains(d));
+    if !config.allow_hooks {
+        warn!("Skipping {} template hooks; run with --allow-hooks to run them: {}", hooks.len(), hooks.join("; "));
+        return Ok(());
+    }
     
-    if !dependencies.is_empty() {
-        let cargo_toml_path = project_dir.join("Cargo.toml");
-        let cargo_toml = fs::read_to_string(&cargo_toml_path)
-            .map_err(|e| RustAiToolError::Io(e))?;
+    for hook in hooks {
+        info!("Running template hook: {}", hook);
         
-        let mut cargo_doc = cargo_toml.parse::<toml::Document>()
-            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to parse Cargo.toml: {}", e)))?;
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let status = Command::new(shell)
+            .args([flag, hook])
+            .current_dir(project_dir)
+            .env("PROJECT_NAME", &config.name)
+            .env("CRATE_NAME", config.name.replace('-', "_"))
+            .status()
+            .await
+            .map_err(RustAiToolError::Io)?;
         
-        if let Some(deps) = cargo_doc.get_mut("dependencies") {
-            if let Some(table) = deps.as_table_mut() {
-                for dep in dependencies {
-                    if dep == "clap" {
-                        // Add clap with features
-                        table.insert(
-                            "clap",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("4.3".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("derive".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else {
-                        table.insert(dep, toml::value::Value::String("0.4".to_string()));
-                    }
-                }
-            }
+        if !status.success() {
+            return Err(RustAiToolError::ProjectGeneration(format!(
+                "Template hook failed (exit code: {:?}): {}",
+                status.code(),
+                hook
+            )));
         }
-        
-        fs::write(&cargo_toml_path, cargo_doc.to_string())
-            .map_err(|e| RustAiToolError::Io(e))?;
     }
     
     Ok(())
 }
 
-/// Generate a web service Rust project with Actix
+/// Dependencies a built-in template needs: their dependency table, name, version and features
+fn template_dependencies(template: &ProjectTemplate) -> Vec<(&'static str, &'static str, &'static str, &'static [&'static str])> {
+    match template {
+        ProjectTemplate::Cli => vec![
+            ("dependencies", "clap", "4.3", &["derive"]),
+            ("dependencies", "log", "0.4", &[]),
+            ("dependencies", "env_logger", "0.4", &[]),
+        ],
+        ProjectTemplate::WebService => vec![
+            ("dependencies", "actix-web", "*", &[]),
+            ("dependencies", "tokio", "1.28", &["full"]),
+            ("dependencies", "serde", "1.0", &["derive"]),
+            ("dependencies", "serde_json", "*", &[]),
+            ("dependencies", "log", "*", &[]),
+            ("dependencies", "env_logger", "*", &[]),
+        ],
+        ProjectTemplate::Axum => vec![
+            ("dependencies", "axum", "*", &[]),
+            ("dependencies", "tokio", "1.28", &["full", "rt-multi-thread"]),
+            ("dependencies", "serde", "1.0", &["derive"]),
+            ("dependencies", "serde_json", "*", &[]),
+            ("dependencies", "tracing", "*", &[]),
+            ("dependencies", "tracing-subscriber", "*", &[]),
+        ],
+        ProjectTemplate::DatabaseApi => vec![
+            ("dependencies", "axum", "0.7", &[]),
+            ("dependencies", "sqlx", "0.8", &["runtime-tokio", "postgres", "macros", "migrate"]),
+            ("dependencies", "tokio", "1.28", &["macros", "rt-multi-thread"]),
+            ("dependencies", "serde", "1.0", &["derive"]),
+            ("dependencies", "async-trait", "0.1", &[]),
+            ("dev-dependencies", "serde_json", "1.0", &[]),
+            ("dev-dependencies", "tower", "0.5", &["util"]),
+        ],
+        ProjectTemplate::RocketApi => vec![
+            ("dependencies", "rocket", "0.5.0", &["json"]),
+        ],
+        ProjectTemplate::EmbeddedRust => vec![
+            ("dependencies", "cortex-m", "0.7", &["critical-section-single-core"]),
+            ("dependencies", "cortex-m-rt", "0.7", &[]),
+            ("dependencies", "stm32f4xx-hal", "0.20", &["rt", "stm32f411"]),
+        ],
+        ProjectTemplate::Leptos => vec![
+            ("dependencies", "leptos", "0.7", &["csr"]),
+            ("dependencies", "console_error_panic_hook", "0.1", &[]),
+        ],
+        ProjectTemplate::Yew => vec![
+            ("dependencies", "yew", "0.21", &["csr"]),
+        ],
+        ProjectTemplate::Grpc => vec![
+            ("dependencies", "tonic", "0.12", &[]),
+            ("dependencies", "prost", "0.13", &[]),
+            ("dependencies", "tokio", "1.28", &["macros", "rt-multi-thread"]),
+            ("build-dependencies", "tonic-build", "0.12", &[]),
+            ("build-dependencies", "prost-build", "0.13", &[]),
+            ("build-dependencies", "protoc-bin-vendored", "3", &[]),
+            ("dev-dependencies", "tokio-stream", "0.1", &["net"]),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Release profile a built-in template needs
+fn template_release_profile(template: &ProjectTemplate) -> Vec<(&'static str, Value)> {
+    match template {
+        // Small, debuggable firmware: symbols stay on the host, not in flash
+        ProjectTemplate::EmbeddedRust => vec![
+            ("codegen-units", Value::from(1)),
+            ("debug", Value::from(true)),
+            ("lto", Value::from(true)),
+            ("opt-level", Value::from("s")),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Cargo commands verifying a generated project
+fn verification_commands(template: &ProjectTemplate) -> &'static [&'static str] {
+    match template {
+        // no_std firmware cannot run the test harness on the host
+        ProjectTemplate::EmbeddedRust => &["build"],
+        _ => &["build", "test"],
+    }
+}
+
+/// Build and test a generated project, repairing it with the AI model until it passes
+///
+/// Each round runs `cargo build`, then `cargo test` (only `cargo build` for
+/// no_std templates); the output of the first
+/// failing command is sent to the model along with every source file it points at.
 ///
 /// # Arguments
 ///
 /// * `project_dir` - Project directory
 /// * `config` - Project configuration
+/// * `repair_iterations` - Repair rounds before giving up
 ///
 /// # Returns
 ///
-/// Success status
-async fn generate_web_service_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // Create a basic project first
-    generate_basic_project(project_dir, config).await?;
-    
-    // Create src directory structure
-    let src_dir = project_dir.join("src");
-    fs::create_dir_all(&src_dir.join("routes"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    fs::create_dir_all(&src_dir.join("models"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    fs::create_dir_all(&src_dir.join("handlers"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create main.rs with web server setup
-    let main_rs_path = src_dir.join("main.rs");
-    let main_rs_content = format!(
-        r#"use actix_web::{{web, App, HttpServer, Responder, HttpResponse}};
-use serde::{{Deserialize, Serialize}};
-
-mod routes;
-mod models;
-mod handlers;
-
-#[derive(Serialize)]
-struct ApiResponse {{
-    status: String,
-    message: String,
-}}
-
-async fn health_check() -> impl Responder {{
-    HttpResponse::Ok().json(ApiResponse {{
-        status: "ok".to_string(),
-        message: "Service is running".to_string(),
-    }})
-}}
-
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {{
-    // Initialize logger
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    
-    log::info!("Starting {} server at http://localhost:8080", "{}");
-    
-    HttpServer::new(|| {{
-        App::new()
-            .route("/health", web::get().to(health_check))
-            .configure(routes::init_routes)
-    }})
-    .bind("127.0.0.1:8080")?
-    .run()
-    .await
-}}
-"#,
-        config.name, config.name
-    );
-    
-    fs::write(&main_rs_path, main_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create routes.rs
-    let routes_rs_path = src_dir.join("routes.rs");
-    let routes_rs_content = r#"use actix_web::web;
-use crate::handlers;
-
-pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api")
-            .route("/example", web::get().to(handlers::get_example))
-    );
-}
-"#;
+/// Success status; an error if the project still fails after the last round
+async fn verify_project(project_dir: &Path, config: &ProjectConfig, repair_iterations: u32) -> Result<()> {
+    let client = match &config.ai_model {
+        Some(ai_model) if repair_iterations > 0 => Some(AiModelClient::new(ai_model.clone())?.with_project_root(project_dir)),
+        _ => None,
+    };
     
-    fs::write(&routes_rs_path, routes_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    for iteration in 0..=repair_iterations {
+        let Some((command, output)) = failing_cargo_command(project_dir, verification_commands(&config.template)).await? else {
+            info!("Generated project builds and passes its tests");
+            return Ok(());
+        };
+        
+        let files = failing_files(&output, project_dir);
+        let client = match &client {
+            Some(client) if iteration < repair_iterations && !files.is_empty() => client,
+            _ => {
+                return Err(RustAiToolError::ProjectGeneration(format!(
+                    "`cargo {}` fails for the project generated at {} after {} repair iterations:\n{}",
+                    command,
+                    project_dir.display(),
+                    iteration,
+                    output
+                )));
+            }
+        };
+        
+        warn!(
+            "`cargo {}` fails, repairing {} files (iteration {} of {})",
+            command,
+            files.len(),
+            iteration + 1,
+            repair_iterations
+        );
+        
+        let errors = format!("`cargo {}` fails with:\n{}", command, truncate_output(&output));
+        for file in files {
+            let path = project_dir.join(&file);
+            let code = fs::read_to_string(&path)
+                .map_err(RustAiToolError::Io)?;
+            
+            debug!("Repairing {}", file);
+            let repaired = client.generate_fixes(&code, &errors).await?;
+            if repaired.trim().is_empty() {
+                continue;
+            }
+            
+            let repaired = if repaired.ends_with('\n') { repaired } else { repaired + "\n" };
+            fs::write(&path, repaired)
+                .map_err(RustAiToolError::Io)?;
+        }
+    }
     
-    // Create handlers.rs
-    let handlers_rs_path = src_dir.join("handlers.rs");
-    let handlers_rs_content = r#"use actix_web::{web, Responder, HttpResponse};
-use serde::Serialize;
-
-#[derive(Serialize)]
-pub struct ExampleResponse {
-    message: String,
-    data: Vec<String>,
+    Ok(())
 }
 
-pub async fn get_example() -> impl Responder {
-    let response = ExampleResponse {
-        message: "Example endpoint".to_string(),
-        data: vec!["item1".to_string(), "item2".to_string()],
-    };
+/// Run cargo commands in a project, stopping at the first failure
+///
+/// # Returns
+///
+/// The first command that failed and its output, or `None` if all passed
+async fn failing_cargo_command(project_dir: &Path, commands: &[&'static str]) -> Result<Option<(&'static str, String)>> {
+    for &command in commands {
+        info!("Running cargo {} in {}", command, project_dir.display());
+        
+        let output = CargoCommand::new(project_dir)
+            .args([command, "--color", "never"])
+            .output_async()
+            .await
+            .map_err(RustAiToolError::Io)?;
+        
+        if !output.status.success() {
+            let output = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stderr),
+                String::from_utf8_lossy(&output.stdout)
+            );
+            return Ok(Some((command, output)));
+        }
+    }
     
-    HttpResponse::Ok().json(response)
+    Ok(None)
 }
-"#;
-    
-    fs::write(&handlers_rs_path, handlers_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create models.rs
-    let models_rs_path = src_dir.join("models.rs");
-    let models_rs_content = r#"use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExampleModel {
-    pub id: u32,
-    pub name: String,
-    pub active: bool,
-}
-"#;
-    
-    fs::write(&models_rs_path, models_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Update Cargo.toml to add web service dependencies
-    let mut dependencies = vec![
-        "actix-web".to_string(),
-        "tokio".to_string(),
-        "serde".to_string(),
-        "serde_json".to_string(),
-        "log".to_string(),
-        "env_logger".to_string(),
-    ];
-    dependencies.retain(|d| !config.dependencies.contains(d));
-    
-    if !dependencies.is_empty() {
-        let cargo_toml_path = project_dir.join("Cargo.toml");
-        let cargo_toml = fs::read_to_string(&cargo_toml_path)
-            .map_err(|e| RustAiToolError::Io(e))?;
-        
-        let mut cargo_doc = cargo_toml.parse::<toml::Document>()
-            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to parse Cargo.toml: {}", e)))?;
+/// Source files of a project that compiler errors or test panics point at
+///
+/// # Arguments
+///
+/// * `output` - Output of the failing cargo command
+/// * `project_dir` - Project directory
+///
+/// # Returns
+///
+/// Paths relative to the project directory, in order of first mention
+fn failing_files(output: &str, project_dir: &Path) -> Vec<String> {
+    let location_regex = Regex::new(r"(?:-->|panicked at) ([^\s:]+\.rs):\d+:\d+").unwrap();
+    let mut files: Vec<String> = Vec::new();
+    
+    for captures in location_regex.captures_iter(output) {
+        let file = captures[1].replace('\\', "/");
+        let inside_project = !Path::new(&file).is_absolute() && !file.split('/').any(|part| part == "..");
         
-        if let Some(deps) = cargo_doc.get_mut("dependencies") {
-            if let Some(table) = deps.as_table_mut() {
-                for dep in dependencies {
-                    if dep == "tokio" {
-                        // Add tokio with features
-                        table.insert(
-                            "tokio",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("1.28".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("full".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else if dep == "serde" {
-                        // Add serde with features
-                        table.insert(
-                            "serde",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("1.0".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("derive".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else {
-                        table.insert(dep, toml::value::Value::String("*".to_string()));
-                    }
-                }
-            }
+        if inside_project && project_dir.join(&file).is_file() && !files.contains(&file) {
+            files.push(file);
         }
-        
-        fs::write(&cargo_toml_path, cargo_doc.to_string())
-            .map_err(|e| RustAiToolError::Io(e))?;
     }
     
-    Ok(())
+    files
+}
+
+/// Keep the end of long cargo output, where the errors are summarized
+fn truncate_output(output: &str) -> &str {
+    const MAX_OUTPUT_CHARS: usize = 8000;
+    
+    match output.char_indices().rev().nth(MAX_OUTPUT_CHARS) {
+        Some((start, _)) => &output[start..],
+        None => output,
+    }
 }
 
-/// Generate a web service Rust project with Axum
+/// Generate the README with the AI model, documenting the API of the generated code
+///
+/// For libraries of verified projects, `src/lib.rs` includes the README as
+/// doc comment of a `#[cfg(doctest)]` item, so `cargo test --doc` compiles and
+/// runs its examples; failing examples are sent back to the model until they
+/// pass or the repair rounds are used up. The template README is kept when the
+/// model fails or the examples never pass.
 ///
 /// # Arguments
 ///
@@ -978,202 +1186,139 @@ pub struct ExampleModel {
 /// # Returns
 ///
 /// Success status
-async fn generate_axum_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // Create a basic project first
-    generate_basic_project(project_dir, config).await?;
-    
-    // Create src directory structure
-    let src_dir = project_dir.join("src");
-    fs::create_dir_all(&src_dir.join("routes"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    fs::create_dir_all(&src_dir.join("models"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    fs::create_dir_all(&src_dir.join("handlers"))
-        .map_err(|e| RustAiToolError::Io(e))?;
+async fn generate_readme(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
+    let Some(ai_model) = &config.ai_model else {
+        return Ok(());
+    };
     
-    // Create main.rs with Axum setup
-    let main_rs_path = src_dir.join("main.rs");
-    let main_rs_content = format!(
-        r#"use axum::{{
-    extract::Extension,
-    routing::{{get, post}},
-    Router,
-}};
-use serde::{{Deserialize, Serialize}};
-use std::net::SocketAddr;
-
-mod routes;
-mod models;
-mod handlers;
-
-#[tokio::main]
-async fn main() {{
-    // Initialize logger
-    tracing_subscriber::fmt::init();
-    
-    // Build our application
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .nest("/api", routes::api_routes());
-    
-    // Run it
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
-    tracing::info!("Starting {} server at http://localhost:8080", "{}");
-    
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
-}}
-
-#[derive(Serialize)]
-struct HealthResponse {{
-    status: String,
-    message: String,
-}}
-
-// Basic health check handler
-async fn health_check() -> axum::Json<HealthResponse> {{
-    axum::Json(HealthResponse {{
-        status: "ok".to_string(),
-        message: "Service is running".to_string(),
-    }})
-}}
-"#,
-        config.name, config.name
-    );
+    let client = AiModelClient::new(ai_model.clone())?.with_project_root(project_dir);
+    let readme_path = project_dir.join("README.md");
+    let template_readme = fs::read_to_string(&readme_path).ok();
+    
+    let lib_path = project_dir.join("src").join("lib.rs");
+    let doctests = config.repair_iterations.is_some() && lib_path.is_file();
+    if doctests {
+        let mut lib = fs::read_to_string(&lib_path)
+            .map_err(RustAiToolError::Io)?;
+        if !lib.contains(README_DOCTESTS) {
+            lib.push_str(README_DOCTESTS);
+            fs::write(&lib_path, lib)
+                .map_err(RustAiToolError::Io)?;
+        }
+    }
     
-    fs::write(&main_rs_path, main_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    let project = format!("`{}`: {}", config.name, config.description);
+    let sources = project_sources(project_dir)?;
+    let badges = readme_badges(config, project_dir);
+    let mut errors = None;
+    
+    for iteration in 0..=config.repair_iterations.unwrap_or(0) {
+        info!("Generating README.md");
+        let readme = match client.generate_readme(&project, &sources, &badges, doctests, errors.as_deref()).await {
+            Ok(readme) => strip_markdown_fence(&readme),
+            Err(e) => {
+                warn!("Failed to generate the README, keeping the template README: {}", e);
+                return Ok(());
+            }
+        };
+        fs::write(&readme_path, readme)
+            .map_err(RustAiToolError::Io)?;
+        
+        if !doctests {
+            return Ok(());
+        }
+        
+        info!("Running the README examples as doc tests");
+        let output = CargoCommand::new(project_dir)
+            .args(["test", "--doc", "--color", "never"])
+            .output_async()
+            .await
+            .map_err(RustAiToolError::Io)?;
+        
+        if output.status.success() {
+            return Ok(());
+        }
+        
+        warn!("README examples fail to compile or run (attempt {})", iteration + 1);
+        let output = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        );
+        errors = Some(truncate_output(&output).to_string());
+    }
     
-    // Create routes.rs
-    let routes_rs_path = src_dir.join("routes.rs");
-    let routes_rs_content = r#"use axum::{
-    routing::{get, post},
-    Router,
-};
-use crate::handlers;
-
-pub fn api_routes() -> Router {
-    Router::new()
-        .route("/example", get(handlers::get_example))
+    warn!("README examples still fail, keeping the template README");
+    match template_readme {
+        Some(readme) => fs::write(&readme_path, readme).map_err(RustAiToolError::Io),
+        None => fs::remove_file(&readme_path).map_err(RustAiToolError::Io),
+    }
 }
-"#;
-    
-    fs::write(&routes_rs_path, routes_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Create handlers.rs
-    let handlers_rs_path = src_dir.join("handlers.rs");
-    let handlers_rs_content = r#"use axum::Json;
-use serde::Serialize;
 
-#[derive(Serialize)]
-pub struct ExampleResponse {
-    message: String,
-    data: Vec<String>,
-}
+/// Item appended to `src/lib.rs` to run the README code blocks as doc tests
+const README_DOCTESTS: &str = "\n#[doc = include_str!(\"../README.md\")]\n#[cfg(doctest)]\npub struct ReadmeDoctests;\n";
 
-pub async fn get_example() -> Json<ExampleResponse> {
-    Json(ExampleResponse {
-        message: "Example endpoint".to_string(),
-        data: vec!["item1".to_string(), "item2".to_string()],
-    })
-}
-"#;
-    
-    fs::write(&handlers_rs_path, handlers_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+/// Rust sources of a generated project, as Markdown code blocks for a prompt
+fn project_sources(project_dir: &Path) -> Result<String> {
+    const MAX_SOURCE_CHARS: usize = 24_000;
+    
+    let mut sources = String::new();
+    let files = walkdir::WalkDir::new(project_dir.join("src"))
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "rs"));
+    
+    for entry in files {
+        let code = fs::read_to_string(entry.path())
+            .map_err(RustAiToolError::Io)?;
+        let path = entry.path().strip_prefix(project_dir).unwrap_or(entry.path());
+        let block = format!("`{}`:\n```rust\n{}\n```\n\n", path.display(), code.trim_end());
+        
+        if sources.len() + block.len() > MAX_SOURCE_CHARS {
+            debug!("Leaving {} and later files out of the README prompt", path.display());
+            break;
+        }
+        sources.push_str(&block);
+    }
     
-    // Create models.rs
-    let models_rs_path = src_dir.join("models.rs");
-    let models_rs_content = r#"use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExampleModel {
-    pub id: u32,
-    pub name: String,
-    pub active: bool,
+    Ok(sources)
 }
-"#;
-    
-    fs::write(&models_rs_path, models_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
-    
-    // Update Cargo.toml to add Axum dependencies
-    let mut dependencies = vec![
-        "axum".to_string(),
-        "tokio".to_string(),
-        "serde".to_string(),
-        "serde_json".to_string(),
-        "tracing".to_string(),
-        "tracing-subscriber".to_string(),
+
+/// Badge line of a generated README: crates.io, docs.rs, the license and CI if the template has a workflow
+fn readme_badges(config: &ProjectConfig, project_dir: &Path) -> String {
+    let repository = format!("https://github.com/username/{}", config.name);
+    let mut badges = vec![
+        format!("[![Crates.io](https://img.shields.io/crates/v/{0}.svg)](https://crates.io/crates/{0})", config.name),
+        format!("[![Documentation](https://docs.rs/{0}/badge.svg)](https://docs.rs/{0})", config.name),
+        format!(
+            "[![License](https://img.shields.io/badge/license-{}-blue.svg)]({}#license)",
+            config.license.replace('-', "--").replace(' ', "%20"),
+            repository
+        ),
     ];
-    dependencies.retain(|d| !config.dependencies.contains(d));
     
-    if !dependencies.is_empty() {
-        let cargo_toml_path = project_dir.join("Cargo.toml");
-        let cargo_toml = fs::read_to_string(&cargo_toml_path)
-            .map_err(|e| RustAiToolError::Io(e))?;
-        
-        let mut cargo_doc = cargo_toml.parse::<toml::Document>()
-            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to parse Cargo.toml: {}", e)))?;
-        
-        if let Some(deps) = cargo_doc.get_mut("dependencies") {
-            if let Some(table) = deps.as_table_mut() {
-                for dep in dependencies {
-                    if dep == "tokio" {
-                        table.insert(
-                            "tokio",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("1.28".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("full".to_string()),
-                                        toml::value::Value::String("rt-multi-thread".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else if dep == "serde" {
-                        table.insert(
-                            "serde",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("1.0".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("derive".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else {
-                        table.insert(dep, toml::value::Value::String("*".to_string()));
-                    }
-                }
-            }
-        }
-        
-        fs::write(&cargo_toml_path, cargo_doc.to_string())
-            .map_err(|e| RustAiToolError::Io(e))?;
+    if project_dir.join(".github").join("workflows").join("ci.yml").is_file() {
+        badges.push(format!("[![CI]({0}/actions/workflows/ci.yml/badge.svg)]({0}/actions/workflows/ci.yml)", repository));
     }
     
-    Ok(())
+    badges.join(" ")
 }
 
-/// Generate a Rocket web API project
+/// Markdown of a model response, without a code fence wrapping all of it
+fn strip_markdown_fence(response: &str) -> String {
+    let fence_regex = Regex::new(r"^```(?:markdown|md)?[ \t]*\n([\s\S]*?)\n```$").unwrap();
+    let response = response.trim();
+    let readme = fence_regex.captures(response)
+        .and_then(|captures| captures.get(1))
+        .map_or(response, |readme| readme.as_str());
+    
+    format!("{}\n", readme.trim_end())
+}
+
+/// Add the dependencies and release profile of the built-in template to Cargo.toml
+///
+/// Dependencies already requested in the configuration are left as they are.
 ///
 /// # Arguments
 ///
@@ -1183,139 +1328,198 @@ pub struct ExampleModel {
 /// # Returns
 ///
 /// Success status
-async fn generate_rocket_project(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
-    // Create a basic project first
-    generate_basic_project(project_dir, config).await?;
+fn add_template_dependencies(project_dir: &Path, config: &ProjectConfig) -> Result<()> {
+    // Replaces the requirements detected in the description with the versions and features the template code needs
+    let dependencies = template_dependencies(&config.template);
+    let release_profile = template_release_profile(&config.template);
     
-    // Create src directory structure
-    let src_dir = project_dir.join("src");
-    fs::create_dir_all(&src_dir.join("routes"))
-        .map_err(|e| RustAiToolError::Io(e))?;
-    fs::create_dir_all(&src_dir.join("models"))
-        .map_err(|e| RustAiToolError::Io(e))?;
+    if dependencies.is_empty() && release_profile.is_empty() {
+        return Ok(());
+    }
     
-    // Create main.rs with Rocket setup
-    let main_rs_path = src_dir.join("main.rs");
-    let main_rs_content = format!(
-        r#"#[macro_use] extern crate rocket;
-use rocket::serde::{{Serialize, json::Json}};
-
-mod routes;
-mod models;
-
-#[derive(Serialize)]
-#[serde(crate = "rocket::serde")]
-struct HealthResponse {{
-    status: String,
-    message: String,
-}}
-
-#[get("/health")]
-fn health_check() -> Json<HealthResponse> {{
-    Json(HealthResponse {{
-        status: "ok".to_string(),
-        message: "Service is running".to_string(),
-    }})
-}}
-
-#[launch]
-fn rocket() -> _ {{
-    println!("Starting {} server", "{}");
-    
-    rocket::build()
-        .mount("/", routes![health_check])
-        .mount("/api", routes::routes())
-}}
-"#,
-        config.name, config.name
-    );
+    let cargo_toml_path = project_dir.join("Cargo.toml");
+    let mut manifest = Manifest::load(&cargo_toml_path)?;
     
-    fs::write(&main_rs_path, main_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    for (table, name, version, features) in dependencies {
+        manifest.add_dependency(table, name, version, features)?;
+    }
+    for (key, value) in release_profile {
+        manifest.set_field(&["profile", "release"], key, value)?;
+    }
     
-    // Create routes.rs
-    let routes_rs_path = src_dir.join("routes.rs");
-    let routes_rs_content = r#"use rocket::{serde::json::Json, Route};
-use crate::models::ExampleModel;
-
-#[get("/example")]
-fn example() -> Json<ExampleModel> {
-    Json(ExampleModel {
-        id: 1,
-        name: "Example".to_string(),
-        active: true,
-    })
+    manifest.save(&cargo_toml_path)
 }
 
-pub fn routes() -> Vec<Route> {
-    routes![example]
-}
-"#;
+/// Generate the snippets a template declares with the AI model
+///
+/// # Arguments
+///
+/// * `prompts` - Prompts by snippet name
+/// * `config` - Project configuration
+///
+/// # Returns
+///
+/// The generated code by snippet name
+async fn generate_snippets(
+    prompts: &BTreeMap<String, String>,
+    config: &ProjectConfig,
+) -> Result<BTreeMap<String, String>> {
+    let Some(ai_model) = &config.ai_model else {
+        warn!("The template declares {} snippets, but no AI model is configured", prompts.len());
+        return Ok(BTreeMap::new());
+    };
     
-    fs::write(&routes_rs_path, routes_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    let client = AiModelClient::new(ai_model.clone())?;
+    let code_block_regex = Regex::new(r"```(?:rust)?\s*\n([\s\S]+?)\n```").unwrap();
+    let mut snippets = BTreeMap::new();
     
-    // Create models.rs
-    let models_rs_path = src_dir.join("models.rs");
-    let models_rs_content = r#"use rocket::serde::{Serialize, Deserialize};
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(crate = "rocket::serde")]
-pub struct ExampleModel {
-    pub id: u32,
-    pub name: String,
-    pub active: bool,
+    for (name, prompt) in prompts {
+        info!("Generating snippet {}", name);
+        
+        let response = client.generate_code(
+            &format!("Project {}: {}\n\n{}", config.name, config.description, prompt),
+            None,
+            None,
+        ).await?;
+        
+        let code = code_block_regex.captures(&response)
+            .and_then(|captures| captures.get(1))
+            .map_or_else(|| response.trim().to_string(), |code| code.as_str().to_string());
+        snippets.insert(name.clone(), code);
+    }
+    
+    Ok(snippets)
 }
-"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
     
-    fs::write(&models_rs_path, models_rs_content)
-        .map_err(|e| RustAiToolError::Io(e))?;
+    #[test]
+    fn test_failing_files() {
+        let project = tempdir().unwrap();
+        fs::create_dir_all(project.path().join("src")).unwrap();
+        fs::write(project.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(project.path().join("src/lib.rs"), "").unwrap();
+        
+        let output = "error[E0425]: cannot find value `x` in this scope\n\
+                      \x20--> src/main.rs:3:5\n\
+                      warning: unused import\n\
+                      \x20 --> /home/user/.cargo/registry/src/dep/lib.rs:1:1\n\
+                      thread 'tests::it_works' panicked at src/lib.rs:10:9:\n\
+                      \x20--> src/main.rs:7:1\n\
+                      \x20--> src/missing.rs:1:1\n";
+        
+        assert_eq!(failing_files(output, project.path()), ["src/main.rs", "src/lib.rs"]);
+        assert_eq!(truncate_output("short"), "short");
+    }
     
-    // Update Cargo.toml to add Rocket dependencies
-    let mut dependencies = vec![
-        "rocket".to_string(),
-    ];
-    dependencies.retain(|d| !config.dependencies.contains(d));
+    #[test]
+    fn test_readme_helpers() {
+        assert_eq!(strip_markdown_fence("```markdown\n# demo\n\n```rust\nfn main() {}\n```\n```\n"), "# demo\n\n```rust\nfn main() {}\n```\n");
+        assert_eq!(strip_markdown_fence("# demo\n\n```sh\ncargo run\n```"), "# demo\n\n```sh\ncargo run\n```\n");
+        
+        let project = tempdir().unwrap();
+        let config = test_config(project.path());
+        
+        let badges = readme_badges(&config, project.path());
+        assert!(badges.contains("https://docs.rs/demo/badge.svg"));
+        assert!(badges.contains("license-MIT%20OR%20Apache--2.0-blue.svg"));
+        assert!(!badges.contains("CI"));
+    }
     
-    if !dependencies.is_empty() {
-        let cargo_toml_path = project_dir.join("Cargo.toml");
-        let cargo_toml = fs::read_to_string(&cargo_toml_path)
-            .map_err(|e| RustAiToolError::Io(e))?;
+    #[test]
+    fn test_onboarding_files() {
+        let project = tempdir().unwrap();
+        let engine = TemplateEngine::new(&[]).unwrap();
+        let context = TemplateContext::new("demo", "A demo", "Ada", "MIT", &[]);
         
-        let mut cargo_doc = cargo_toml.parse::<toml::Document>()
-            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to parse Cargo.toml: {}", e)))?;
+        let mut config = test_config(project.path());
+        render_onboarding_files(&engine, project.path(), &config, &context).unwrap();
+        assert!(!project.path().join(".pre-commit-config.yaml").exists());
         
-        if let Some(deps) = cargo_doc.get_mut("dependencies") {
-            if let Some(table) = deps.as_table_mut() {
-                for dep in dependencies {
-                    if dep == "rocket" {
-                        table.insert(
-                            "rocket",
-                            toml::value::Value::Table({
-                                let mut t = toml::Table::new();
-                                t.insert(
-                                    "version".to_string(),
-                                    toml::value::Value::String("0.5.0".to_string()),
-                                );
-                                t.insert(
-                                    "features".to_string(),
-                                    toml::value::Value::Array(vec![
-                                        toml::value::Value::String("json".to_string()),
-                                    ]),
-                                );
-                                t
-                            }),
-                        );
-                    } else {
-                        table.insert(dep, toml::value::Value::String("*".to_string()));
-                    }
-                }
-            }
+        config.pre_commit = true;
+        config.devcontainer = true;
+        render_onboarding_files(&engine, project.path(), &config, &context).unwrap();
+        
+        let pre_commit = fs::read_to_string(project.path().join(".pre-commit-config.yaml")).unwrap();
+        assert!(pre_commit.contains("entry: rust-ai-tool analyze --changed --fail-on error"));
+        let devcontainer = fs::read_to_string(project.path().join(".devcontainer/devcontainer.json")).unwrap();
+        assert!(devcontainer.contains("\"name\": \"demo\""));
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            
+            let hook = fs::metadata(project.path().join(".githooks/pre-commit")).unwrap();
+            assert_eq!(hook.permissions().mode() & 0o111, 0o111);
         }
+    }
+    
+    #[test]
+    fn test_dependency_presets() {
+        let user_presets = BTreeMap::from([
+            ("reqwest".to_string(), DependencyPreset { version: "0.11".to_string(), features: vec!["blocking".to_string()], default_features: true }),
+            ("polars".to_string(), DependencyPreset { version: "0.41".to_string(), features: vec!["lazy".to_string()], default_features: true }),
+        ]);
+        
+        let presets = dependency_presets(&user_presets);
+        assert_eq!(presets["sqlx"].features, ["runtime-tokio", "postgres"]);
+        assert_eq!(presets["reqwest"].version, "0.11");
+        assert_eq!(presets["polars"].features, ["lazy"]);
+        assert!(!dependency_presets(&BTreeMap::new())["reqwest"].default_features);
         
-        fs::write(&cargo_toml_path, cargo_doc.to_string())
-            .map_err(|e| RustAiToolError::Io(e))?;
+        let dependencies = extract_dependencies("A CLI fetching data with reqwest into polars frames", &user_presets);
+        assert_eq!(dependencies, ["reqwest", "polars", "clap"]);
     }
     
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn test_keyword_heuristics() {
+        assert_eq!(determine_template("A tool sending email digests"), ProjectTemplate::Basic);
+        assert_eq!(determine_template("An AI assistant"), ProjectTemplate::MachineLearning);
+        assert_eq!(determine_template("Serve REST APIs for a todo app"), ProjectTemplate::RocketApi);
+        assert_eq!(determine_template("A command-line tool"), ProjectTemplate::Cli);
+        assert_eq!(determine_template("A parsing library"), ProjectTemplate::Library);
+        assert_eq!(determine_template("A calibration utility"), ProjectTemplate::Basic);
+        
+        assert_eq!(extract_dependencies("Parse logs with regex, no blogging", &BTreeMap::new()), ["regex"]);
+        assert_eq!(extract_keywords("An email client"), ["client"]);
+    }
+    
+    #[test]
+    fn test_parse_requirements() {
+        let response = "```json\n{\"template\": \"cli\", \"dependencies\": [\"serde\", \"csv\", \"serde\", \"not a crate\"], \"crate_type\": \"bin\"}\n```";
+        let (template, crate_type, dependencies) = parse_requirements(response).unwrap();
+        
+        assert_eq!(template, ProjectTemplate::Cli);
+        assert_eq!(crate_type, "bin");
+        assert_eq!(dependencies, ["serde", "csv", "clap"]);
+        
+        assert!(parse_requirements("{\"template\": \"django\", \"crate_type\": \"bin\"}").is_err());
+        assert!(parse_requirements("{\"template\": \"basic\", \"crate_type\": \"dylib\"}").is_err());
+        assert!(parse_requirements("no idea").is_err());
+    }
+    
+    /// Configuration of a library project generated into a directory
+    fn test_config(output_dir: &Path) -> ProjectConfig {
+        ProjectConfig {
+            name: "demo".to_string(),
+            description: "A demo".to_string(),
+            template: ProjectTemplate::Library,
+            author: "Ada".to_string(),
+            crate_type: "lib".to_string(),
+            output_dir: output_dir.to_path_buf(),
+            init_git: false,
+            dependencies: Vec::new(),
+            license: "MIT OR Apache-2.0".to_string(),
+            template_dirs: Vec::new(),
+            dependency_presets: BTreeMap::new(),
+            allow_hooks: false,
+            pre_commit: false,
+            devcontainer: false,
+            repair_iterations: None,
+            ai_model: None,
+        }
+    }
+}
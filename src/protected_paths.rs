@@ -0,0 +1,117 @@
+//! Protected paths module
+//!
+//! This module keeps fixes away from files that must only be edited by hand,
+//! like cryptography code, database migrations or generated files:
+//! - Files are protected by gitignore-style globs in `protected_paths` of the
+//!   validation options, relative to the project (`src/crypto/**`, `migrations/`)
+//! - Validation rejects fixes for protected files, and `apply` refuses them
+//! - `--allow-protected` lifts the protection for one run
+
+use crate::codeowners::pattern_regex;
+use crate::modification::CodeModification;
+use crate::{Result, RustAiToolError};
+use std::path::{Component, Path, PathBuf};
+
+/// Glob protecting a file, if any
+///
+/// # Arguments
+///
+/// * `globs` - Protected path globs
+/// * `file_path` - File to check
+/// * `project_path` - Project directory the globs are relative to
+///
+/// # Returns
+///
+/// The first glob matching the file, or an error if a glob is invalid
+pub fn protecting_glob<'a>(globs: &'a [String], file_path: &Path, project_path: &Path) -> Result<Option<&'a str>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+
+    let relative_path = project_relative_path(file_path, project_path);
+
+    for glob in globs {
+        let regex = pattern_regex(glob)
+            .map_err(|e| RustAiToolError::Validation(format!("Invalid protected path '{}': {}", glob, e)))?;
+
+        if regex.is_match(&relative_path) {
+            return Ok(Some(glob));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Modifications touching protected files
+///
+/// # Arguments
+///
+/// * `modifications` - Modifications to check
+/// * `globs` - Protected path globs
+/// * `project_path` - Project directory the globs are relative to
+///
+/// # Returns
+///
+/// The path of every protected file with the glob protecting it
+pub fn protected_modifications(
+    modifications: &[CodeModification],
+    globs: &[String],
+    project_path: &Path,
+) -> Result<Vec<(PathBuf, String)>> {
+    let mut protected = Vec::new();
+
+    for modification in modifications {
+        if let Some(glob) = protecting_glob(globs, &modification.file_path, project_path)? {
+            protected.push((modification.file_path.clone(), glob.to_string()));
+        }
+    }
+
+    Ok(protected)
+}
+
+/// Path of a file relative to the project, with `/` separators
+fn project_relative_path(file_path: &Path, project_path: &Path) -> String {
+    let mut relative = file_path.strip_prefix(project_path).ok().map(Path::to_path_buf);
+
+    // Absolute paths of a project given as a relative path, or through a symlink
+    if relative.is_none() && file_path.is_absolute() {
+        if let Ok(root) = project_path.canonicalize() {
+            let file_path = file_path.canonicalize().unwrap_or_else(|_| file_path.to_path_buf());
+            relative = file_path.strip_prefix(&root).ok().map(Path::to_path_buf);
+        }
+    }
+
+    relative.as_deref()
+        .unwrap_or(file_path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::create_modification;
+
+    #[test]
+    fn test_protected_paths() {
+        let globs = vec!["src/crypto/**".to_string(), "migrations/".to_string(), "*.generated.rs".to_string()];
+        let project = Path::new("project");
+
+        assert_eq!(protecting_glob(&globs, Path::new("project/src/crypto/aes.rs"), project).unwrap(), Some("src/crypto/**"));
+        assert_eq!(protecting_glob(&globs, Path::new("./migrations/0001.rs"), Path::new(".")).unwrap(), Some("migrations/"));
+        assert_eq!(protecting_glob(&globs, Path::new("src/api/schema.generated.rs"), Path::new(".")).unwrap(), Some("*.generated.rs"));
+        assert_eq!(protecting_glob(&globs, Path::new("project/src/main.rs"), project).unwrap(), None);
+        assert_eq!(protecting_glob(&globs, Path::new("project/crypto/aes.rs"), project).unwrap(), None);
+
+        let modification = |path: &str| create_modification(PathBuf::from(path), String::new(), String::new(), String::new(), 90);
+        let protected = protected_modifications(
+            &[modification("project/src/lib.rs"), modification("project/migrations/0002.rs")],
+            &globs,
+            project,
+        ).unwrap();
+        assert_eq!(protected, [(PathBuf::from("project/migrations/0002.rs"), "migrations/".to_string())]);
+    }
+}
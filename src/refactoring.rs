@@ -0,0 +1,672 @@
+//! Refactoring module
+//!
+//! This module implements structural refactorings, emitted as code modifications:
+//! - Extract a range of statements into a new function
+//! - Infer the parameters and return type from the surrounding function
+//! - Fall back to the AI model when the region is too involved to rewrite mechanically
+
+use crate::modification::{create_modification, CodeModification};
+use crate::models::AiModelClient;
+use crate::{Result, RustAiToolError};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use log::{debug, info};
+
+/// Confidence of an extraction computed from the syntax tree
+const INFERRED_CONFIDENCE: u8 = 90;
+
+/// Confidence of an extraction written by the AI model
+const AI_CONFIDENCE: u8 = 70;
+
+/// A variable binding visible at the extracted region
+#[derive(Debug, Clone)]
+struct Binding {
+    /// Name of the variable
+    name: String,
+
+    /// Declared type, if written in the source
+    ty: Option<String>,
+
+    /// Whether the binding is mutable
+    mutable: bool,
+}
+
+/// Function containing the extracted region
+struct EnclosingFn<'a> {
+    /// Signature of the function
+    sig: &'a syn::Signature,
+
+    /// Body of the function
+    block: &'a syn::Block,
+
+    /// Lines covered by the function item, including attributes (1-based)
+    lines: RangeInclusive<usize>,
+
+    /// Whether the function is an associated function of an impl block
+    in_impl: bool,
+}
+
+/// Outcome of planning an extraction
+enum Extraction {
+    /// The file content with the region extracted
+    Rewritten(String),
+
+    /// The region needs the AI model
+    NeedsAi {
+        /// Why the extraction could not be inferred
+        reason: String,
+
+        /// Lines of the enclosing function (1-based)
+        fn_lines: RangeInclusive<usize>,
+    },
+}
+
+/// Extract a range of lines into a new function
+///
+/// The lines must cover whole statements of a function body. Parameters and
+/// the return type are inferred from the surrounding function; when that is
+/// not possible (control flow leaving the region, untyped bindings, mutated
+/// variables, ...) the AI model is asked to perform the extraction instead.
+///
+/// # Arguments
+///
+/// * `file_path` - File containing the code to extract
+/// * `lines` - Lines to extract (1-based, inclusive)
+/// * `new_name` - Name of the new function
+/// * `ai_client` - AI model used for regions that cannot be extracted mechanically
+///
+/// # Returns
+///
+/// Modification replacing the region with a call to the new function
+pub async fn extract_function(
+    file_path: &Path,
+    lines: RangeInclusive<usize>,
+    new_name: &str,
+    ai_client: Option<&AiModelClient>,
+) -> Result<CodeModification> {
+    info!(
+        "Extracting lines {}-{} of {} into `{}`",
+        lines.start(),
+        lines.end(),
+        file_path.display(),
+        new_name
+    );
+
+    let source = fs::read_to_string(file_path)
+        .map_err(RustAiToolError::Io)?;
+
+    let (modified, confidence) = match plan_extraction(&source, &lines, new_name)? {
+        Extraction::Rewritten(content) => (content, INFERRED_CONFIDENCE),
+        Extraction::NeedsAi { reason, fn_lines } => {
+            let client = ai_client.ok_or_else(|| {
+                RustAiToolError::Modification(format!(
+                    "Cannot infer the signature of `{}` ({}) and no AI model is configured",
+                    new_name, reason
+                ))
+            })?;
+
+            info!("Asking the AI model to extract the region: {}", reason);
+            let content = extract_with_ai(client, &source, &lines, &fn_lines, new_name).await?;
+            (content, AI_CONFIDENCE)
+        }
+    };
+
+    Ok(create_modification(
+        file_path.to_path_buf(),
+        source,
+        modified,
+        format!("Extract lines {}-{} into function `{}`", lines.start(), lines.end(), new_name),
+        confidence,
+    ))
+}
+
+/// Work out the extraction from the syntax tree
+fn plan_extraction(source: &str, lines: &RangeInclusive<usize>, new_name: &str) -> Result<Extraction> {
+    if syn::parse_str::<syn::Ident>(new_name).is_err() {
+        return Err(RustAiToolError::Modification(format!("`{}` is not a valid function name", new_name)));
+    }
+
+    let file = syn::parse_file(source)
+        .map_err(|e| RustAiToolError::Modification(format!("Failed to parse source: {}", e)))?;
+    let source_lines: Vec<&str> = source.split_inclusive('\n').collect();
+
+    let enclosing = find_enclosing_fn(&file.items, lines, false).ok_or_else(|| {
+        RustAiToolError::Modification(format!(
+            "Lines {}-{} are not inside a function body",
+            lines.start(),
+            lines.end()
+        ))
+    })?;
+
+    let mut scope = Vec::new();
+    for input in &enclosing.sig.inputs {
+        match input {
+            syn::FnArg::Receiver(receiver) => scope.push(Binding {
+                name: "self".to_string(),
+                ty: None,
+                mutable: receiver.mutability.is_some(),
+            }),
+            syn::FnArg::Typed(pat_type) => {
+                let ty = span_text(&source_lines, pat_type.ty.span());
+                pat_bindings(&pat_type.pat, Some(ty), &source_lines, &mut scope);
+            }
+        }
+    }
+
+    let block = find_region_block(enclosing.block, lines, &source_lines, &mut scope).ok_or_else(|| {
+        RustAiToolError::Modification(format!(
+            "Lines {}-{} do not cover whole statements of `{}`",
+            lines.start(),
+            lines.end(),
+            enclosing.sig.ident
+        ))
+    })?;
+
+    let selected: Vec<usize> = block.stmts.iter()
+        .enumerate()
+        .filter(|(_, stmt)| {
+            let range = line_range(stmt.span());
+            range.start() <= lines.end() && lines.start() <= range.end()
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let (first, last) = match (selected.first(), selected.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => {
+            return Err(RustAiToolError::Modification(format!(
+                "No statements found in lines {}-{}",
+                lines.start(),
+                lines.end()
+            )))
+        }
+    };
+
+    let region_stmts = &block.stmts[first..=last];
+    let region_tokens: Vec<String> = region_stmts.iter()
+        .flat_map(|stmt| token_idents(stmt.to_token_stream()))
+        .collect();
+
+    let needs_ai = |reason: String| Ok(Extraction::NeedsAi { reason, fn_lines: enclosing.lines.clone() });
+
+    for keyword in ["return", "break", "continue", "await", "self"] {
+        if region_tokens.iter().any(|t| t == keyword) {
+            return needs_ai(format!("the region uses `{}`", keyword));
+        }
+    }
+
+    if region_stmts.iter().any(|stmt| has_question_mark(stmt.to_token_stream())) {
+        return needs_ai("the region uses the `?` operator".to_string());
+    }
+
+    if !enclosing.sig.generics.params.is_empty() {
+        return needs_ai(format!("`{}` is generic", enclosing.sig.ident));
+    }
+
+    // Variables used in the region that come from the enclosing scope
+    let mut inputs: Vec<Binding> = Vec::new();
+    for ident in &region_tokens {
+        if inputs.iter().any(|b| &b.name == ident) {
+            continue;
+        }
+
+        if let Some(binding) = scope.iter().rev().find(|b| &b.name == ident) {
+            inputs.push(binding.clone());
+        }
+    }
+
+    for input in &inputs {
+        if input.mutable {
+            return needs_ai(format!("the region may mutate `{}`", input.name));
+        }
+
+        if input.ty.is_none() {
+            return needs_ai(format!("the type of `{}` is not written in the source", input.name));
+        }
+    }
+
+    let later_tokens: Vec<String> = block.stmts[last + 1..].iter()
+        .flat_map(|stmt| token_idents(stmt.to_token_stream()))
+        .collect();
+
+    // Variables declared in the region that are used after it
+    let mut declared = Vec::new();
+    for stmt in region_stmts {
+        if let syn::Stmt::Local(local) = stmt {
+            pat_bindings(&local.pat, None, &source_lines, &mut declared);
+        }
+    }
+
+    let mut outputs: Vec<Binding> = Vec::new();
+    for binding in declared.into_iter().rev() {
+        if later_tokens.contains(&binding.name) && !outputs.iter().any(|b| b.name == binding.name) {
+            outputs.insert(0, binding);
+        }
+    }
+
+    let is_tail = last + 1 == block.stmts.len() && matches!(block.stmts[last], syn::Stmt::Expr(_, None));
+    let return_type = if is_tail {
+        if !std::ptr::eq(block, enclosing.block) {
+            return needs_ai("the region ends with the value of a nested block".to_string());
+        }
+
+        match &enclosing.sig.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ty) => Some(span_text(&source_lines, ty.span())),
+        }
+    } else {
+        let mut types = Vec::new();
+        for output in &outputs {
+            match &output.ty {
+                Some(ty) => types.push(ty.clone()),
+                None => return needs_ai(format!("the type of `{}` is not written in the source", output.name)),
+            }
+        }
+
+        match types.len() {
+            0 => None,
+            1 => types.pop(),
+            _ => Some(format!("({})", types.join(", "))),
+        }
+    };
+
+    // Lines actually covered by the selected statements
+    let region_lines = *line_range(region_stmts[0].span()).start()
+        ..=*line_range(region_stmts[region_stmts.len() - 1].span()).end();
+
+    let params: Vec<String> = inputs.iter()
+        .map(|input| {
+            let ty = input.ty.as_deref().unwrap_or_default();
+            if is_copy_type(ty) || !later_tokens.contains(&input.name) {
+                format!("{}: {}", input.name, ty)
+            } else {
+                format!("{}: &{}", input.name, ty)
+            }
+        })
+        .collect();
+
+    let args: Vec<String> = inputs.iter()
+        .map(|input| {
+            let ty = input.ty.as_deref().unwrap_or_default();
+            if is_copy_type(ty) || !later_tokens.contains(&input.name) {
+                input.name.clone()
+            } else {
+                format!("&{}", input.name)
+            }
+        })
+        .collect();
+
+    let fn_indent = leading_whitespace(source_lines[enclosing.lines.start() - 1]);
+    let body_indent = format!("{}    ", fn_indent);
+    let region_indent = leading_whitespace(source_lines[region_lines.start() - 1]);
+
+    // New function
+    let mut new_fn = format!("\n{}fn {}({})", fn_indent, new_name, params.join(", "));
+    if let Some(return_type) = &return_type {
+        new_fn.push_str(&format!(" -> {}", return_type));
+    }
+    new_fn.push_str(" {\n");
+
+    for line in &source_lines[region_lines.start() - 1..*region_lines.end()] {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            new_fn.push('\n');
+        } else {
+            new_fn.push_str(&format!("{}{}\n", body_indent, line.strip_prefix(region_indent).unwrap_or(line.trim_start())));
+        }
+    }
+
+    if !is_tail {
+        match outputs.len() {
+            0 => {}
+            1 => new_fn.push_str(&format!("{}{}\n", body_indent, outputs[0].name)),
+            _ => new_fn.push_str(&format!(
+                "{}({})\n",
+                body_indent,
+                outputs.iter().map(|o| o.name.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+    new_fn.push_str(&format!("{}}}\n", fn_indent));
+
+    // Call replacing the region
+    let callee = if enclosing.in_impl { format!("Self::{}", new_name) } else { new_name.to_string() };
+    let call = format!("{}({})", callee, args.join(", "));
+    let call_line = if is_tail {
+        format!("{}{}\n", region_indent, call)
+    } else {
+        let binding = |b: &Binding| if b.mutable { format!("mut {}", b.name) } else { b.name.clone() };
+        match outputs.len() {
+            0 => format!("{}{};\n", region_indent, call),
+            1 => format!("{}let {} = {};\n", region_indent, binding(&outputs[0]), call),
+            _ => format!(
+                "{}let ({}) = {};\n",
+                region_indent,
+                outputs.iter().map(binding).collect::<Vec<_>>().join(", "),
+                call
+            ),
+        }
+    };
+
+    debug!("Extracted `{}` with {} parameters and {} outputs", new_name, inputs.len(), outputs.len());
+
+    let mut result = String::with_capacity(source.len() + new_fn.len());
+    result.extend(source_lines[..region_lines.start() - 1].iter().copied());
+    result.push_str(&call_line);
+    result.extend(source_lines[*region_lines.end()..*enclosing.lines.end()].iter().copied());
+    result.push_str(&new_fn);
+    result.extend(source_lines[*enclosing.lines.end()..].iter().copied());
+
+    Ok(Extraction::Rewritten(result))
+}
+
+/// Ask the AI model to rewrite the enclosing function with the region extracted
+async fn extract_with_ai(
+    client: &AiModelClient,
+    source: &str,
+    lines: &RangeInclusive<usize>,
+    fn_lines: &RangeInclusive<usize>,
+    new_name: &str,
+) -> Result<String> {
+    let source_lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let function = source_lines[fn_lines.start() - 1..*fn_lines.end()].concat();
+    let region = source_lines[lines.start() - 1..(*lines.end()).min(source_lines.len())].concat();
+
+    let prompt = format!(
+        "Extract the marked region of this Rust function into a new function named `{}`.\n\
+        Infer the parameters and return type, borrow values the caller still needs, \
+        keep the behavior identical and replace the region with a call to the new function.\n\n\
+        Function:\n```rust\n{}```\n\nRegion to extract:\n```rust\n{}```\n\n\
+        Respond with the rewritten function followed by the new function in a single rust code block, \
+        keeping the original indentation.",
+        new_name, function, region
+    );
+
+    let response = client.generate_code(&prompt, Some(4000), Some(0.2)).await?;
+
+    let code_block_regex = regex::Regex::new(r"```(?:rust)?\s*\n([\s\S]+?)\n```").unwrap();
+    let code = code_block_regex.captures(&response)
+        .and_then(|captures| captures.get(1))
+        .map(|m| m.as_str().to_string())
+        .unwrap_or(response);
+
+    let mut result = source_lines[..fn_lines.start() - 1].concat();
+    result.push_str(code.trim_end());
+    result.push('\n');
+    result.push_str(&source_lines[*fn_lines.end()..].concat());
+
+    Ok(result)
+}
+
+/// Find the function whose body contains the given lines
+fn find_enclosing_fn<'a>(
+    items: &'a [syn::Item],
+    lines: &RangeInclusive<usize>,
+    in_impl: bool,
+) -> Option<EnclosingFn<'a>> {
+    for item in items {
+        match item {
+            syn::Item::Fn(item_fn) if covers(item_fn.block.span(), lines) => {
+                return Some(EnclosingFn {
+                    sig: &item_fn.sig,
+                    block: &item_fn.block,
+                    lines: line_range(item.span()),
+                    in_impl,
+                });
+            }
+            syn::Item::Impl(item_impl) => {
+                for impl_item in &item_impl.items {
+                    if let syn::ImplItem::Fn(impl_fn) = impl_item {
+                        if covers(impl_fn.block.span(), lines) {
+                            return Some(EnclosingFn {
+                                sig: &impl_fn.sig,
+                                block: &impl_fn.block,
+                                lines: line_range(impl_item.span()),
+                                in_impl: true,
+                            });
+                        }
+                    }
+                }
+            }
+            syn::Item::Mod(item_mod) => {
+                if let Some((_, items)) = &item_mod.content {
+                    if let Some(found) = find_enclosing_fn(items, lines, in_impl) {
+                        return Some(found);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Find the innermost block whose statements start the region
+///
+/// Bindings declared before the region are added to `scope`.
+fn find_region_block<'a>(
+    block: &'a syn::Block,
+    lines: &RangeInclusive<usize>,
+    source_lines: &[&str],
+    scope: &mut Vec<Binding>,
+) -> Option<&'a syn::Block> {
+    for stmt in &block.stmts {
+        let range = line_range(stmt.span());
+
+        if range.end() < lines.start() {
+            if let syn::Stmt::Local(local) = stmt {
+                pat_bindings(&local.pat, None, source_lines, scope);
+            }
+            continue;
+        }
+
+        if range.start() >= lines.start() {
+            return if range.start() <= lines.end() { Some(block) } else { None };
+        }
+
+        // The statement starts before the region: descend into its blocks
+        let expr = match stmt {
+            syn::Stmt::Expr(expr, _) => expr,
+            syn::Stmt::Local(local) => &*local.init.as_ref()?.expr,
+            _ => return None,
+        };
+
+        for (inner, pats) in nested_blocks(expr) {
+            if covers(inner.span(), lines) {
+                for pat in pats {
+                    pat_bindings(pat, None, source_lines, scope);
+                }
+                return find_region_block(inner, lines, source_lines, scope);
+            }
+        }
+
+        return None;
+    }
+
+    None
+}
+
+/// Blocks directly nested in an expression, with the patterns they bind
+fn nested_blocks(expr: &syn::Expr) -> Vec<(&syn::Block, Vec<&syn::Pat>)> {
+    match expr {
+        syn::Expr::Block(expr_block) => vec![(&expr_block.block, Vec::new())],
+        syn::Expr::Unsafe(expr_unsafe) => vec![(&expr_unsafe.block, Vec::new())],
+        syn::Expr::Loop(expr_loop) => vec![(&expr_loop.body, Vec::new())],
+        syn::Expr::While(expr_while) => vec![(&expr_while.body, let_pattern(&expr_while.cond))],
+        syn::Expr::ForLoop(expr_for) => vec![(&expr_for.body, vec![&*expr_for.pat])],
+        syn::Expr::If(expr_if) => {
+            let mut blocks = vec![(&expr_if.then_branch, let_pattern(&expr_if.cond))];
+            if let Some((_, else_branch)) = &expr_if.else_branch {
+                blocks.extend(nested_blocks(else_branch));
+            }
+            blocks
+        }
+        syn::Expr::Match(expr_match) => expr_match.arms.iter()
+            .filter_map(|arm| match &*arm.body {
+                syn::Expr::Block(expr_block) => Some((&expr_block.block, vec![&arm.pat])),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Pattern bound by an `if let` or `while let` condition
+fn let_pattern(cond: &syn::Expr) -> Vec<&syn::Pat> {
+    match cond {
+        syn::Expr::Let(expr_let) => vec![&*expr_let.pat],
+        _ => Vec::new(),
+    }
+}
+
+/// Collect the variables bound by a pattern
+fn pat_bindings(pat: &syn::Pat, ty: Option<String>, source_lines: &[&str], out: &mut Vec<Binding>) {
+    match pat {
+        syn::Pat::Ident(pat_ident) => out.push(Binding {
+            name: pat_ident.ident.to_string(),
+            ty,
+            mutable: pat_ident.mutability.is_some(),
+        }),
+        syn::Pat::Type(pat_type) => {
+            let ty = span_text(source_lines, pat_type.ty.span());
+            pat_bindings(&pat_type.pat, Some(ty), source_lines, out);
+        }
+        syn::Pat::Reference(pat_ref) => pat_bindings(&pat_ref.pat, None, source_lines, out),
+        syn::Pat::Tuple(pat_tuple) => {
+            for elem in &pat_tuple.elems {
+                pat_bindings(elem, None, source_lines, out);
+            }
+        }
+        syn::Pat::TupleStruct(pat_tuple) => {
+            for elem in &pat_tuple.elems {
+                pat_bindings(elem, None, source_lines, out);
+            }
+        }
+        syn::Pat::Slice(pat_slice) => {
+            for elem in &pat_slice.elems {
+                pat_bindings(elem, None, source_lines, out);
+            }
+        }
+        syn::Pat::Struct(pat_struct) => {
+            for field in &pat_struct.fields {
+                pat_bindings(&field.pat, None, source_lines, out);
+            }
+        }
+        syn::Pat::Or(pat_or) => {
+            if let Some(case) = pat_or.cases.first() {
+                pat_bindings(case, None, source_lines, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// All identifiers in a token stream, including inside groups
+fn token_idents(tokens: TokenStream) -> Vec<String> {
+    let mut idents = Vec::new();
+
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => idents.extend(token_idents(group.stream())),
+            _ => {}
+        }
+    }
+
+    idents
+}
+
+/// Whether a token stream contains the `?` operator
+fn has_question_mark(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Punct(punct) => punct.as_char() == '?',
+        TokenTree::Group(group) => has_question_mark(group.stream()),
+        _ => false,
+    })
+}
+
+/// Whether values of a type can be passed by value without moving them
+fn is_copy_type(ty: &str) -> bool {
+    ty.starts_with('&')
+        || matches!(
+            ty,
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize"
+                | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+                | "f32" | "f64" | "bool" | "char"
+        )
+}
+
+/// Lines covered by a span (1-based, inclusive)
+fn line_range(span: Span) -> RangeInclusive<usize> {
+    span.start().line..=span.end().line
+}
+
+/// Whether a span strictly contains the given lines
+fn covers(span: Span, lines: &RangeInclusive<usize>) -> bool {
+    span.start().line < *lines.start() && *lines.end() < span.end().line
+}
+
+/// Source text covered by a span
+fn span_text(source_lines: &[&str], span: Span) -> String {
+    let (start, end) = (span.start(), span.end());
+    let mut text = String::new();
+
+    for line in start.line..=end.line {
+        let content = source_lines.get(line - 1).copied().unwrap_or_default();
+        let from = if line == start.line { start.column } else { 0 };
+        let to = if line == end.line { end.column } else { content.chars().count() };
+        text.extend(content.chars().skip(from).take(to.saturating_sub(from)));
+    }
+
+    text.trim().to_string()
+}
+
+/// Indentation of a line
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_function_infers_signature() {
+        let source = [
+            "fn report(values: &[f64], title: String) {",
+            "    let count: usize = values.len();",
+            "    let total: f64 = values.iter().sum();",
+            "    let mean: f64 = total / count as f64;",
+            "    println!(\"{}: {}\", title, mean);",
+            "}",
+            "",
+        ].join("\n");
+
+        let rewritten = match plan_extraction(&source, &(3..=4), "mean_of").unwrap() {
+            Extraction::Rewritten(content) => content,
+            Extraction::NeedsAi { reason, .. } => panic!("unexpected AI fallback: {}", reason),
+        };
+
+        let expected = [
+            "fn report(values: &[f64], title: String) {",
+            "    let count: usize = values.len();",
+            "    let mean = mean_of(values, count);",
+            "    println!(\"{}: {}\", title, mean);",
+            "}",
+            "",
+            "fn mean_of(values: &[f64], count: usize) -> f64 {",
+            "    let total: f64 = values.iter().sum();",
+            "    let mean: f64 = total / count as f64;",
+            "    mean",
+            "}",
+            "",
+        ].join("\n");
+
+        assert_eq!(rewritten, expected);
+    }
+}
@@ -0,0 +1,531 @@
+//! Repository batch module
+//!
+//! This module runs a pipeline over the repositories listed in a manifest file:
+//! - Repositories are local checkouts (`path`), GitHub repositories (`github`,
+//!   cloned for the run) or both (a local checkout of a GitHub repository)
+//! - The `analyze` pipeline analyzes each repository; the `fix` pipeline also
+//!   generates validated fixes and opens a pull request with them, or writes them
+//!   to `fixes.json` in repositories without a GitHub remote
+//! - A bounded number of repositories run at the same time, each with its own
+//!   pipeline if the manifest overrides it
+//! - The outcomes are consolidated into one report across all repositories
+//!
+//! A repository whose pipeline fails is reported with its error instead of
+//! stopping the batch. With a checkpoint, a resumed batch skips the repositories
+//! the interrupted one completed.
+
+use crate::analysis::{analyze_project, AnalysisResult};
+use crate::analyzer_plugins::checkout_options;
+use crate::checkpoint::{unit_key, Checkpoint, BATCH_STAGE};
+use crate::daemon::ClientFactory;
+use crate::forge::publish_fixes;
+use crate::github::{GithubClient, PullRequestOptions};
+use crate::pipeline::{run_fix_pipeline, FixPipelineOptions};
+use crate::{AnalysisOptions, Result, RustAiToolError, Severity, SshConfig, ValidationOptions};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+
+/// File the fixes of a repository without a GitHub remote are written to
+pub const FIXES_FILE: &str = "fixes.json";
+
+/// Pipeline run on a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchPipeline {
+    /// Analyze the repository
+    #[default]
+    Analyze,
+
+    /// Analyze the repository, fix the issues and open a pull request with the fixes
+    Fix,
+}
+
+/// Pull request opened by the `fix` pipeline
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchPullRequest {
+    /// Branch the fixes are committed to
+    #[serde(default = "default_branch")]
+    pub branch: String,
+
+    /// Title of the pull request
+    #[serde(default = "default_title")]
+    pub title: String,
+
+    /// Open the pull request as a draft
+    #[serde(default)]
+    pub draft: bool,
+
+    /// Labels added to the pull request
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl Default for BatchPullRequest {
+    fn default() -> Self {
+        Self {
+            branch: default_branch(),
+            title: default_title(),
+            draft: false,
+            labels: Vec::new(),
+        }
+    }
+}
+
+/// Repository listed in a manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRepo {
+    /// Name in the report (defaults to the GitHub repository or the directory name)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Local checkout, relative to the manifest
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// GitHub repository as `owner/repo`, cloned if there is no local checkout
+    #[serde(default)]
+    pub github: Option<String>,
+
+    /// Branch cloned (defaults to the default branch of the repository)
+    #[serde(default)]
+    pub branch: Option<String>,
+
+    /// Pipeline of this repository, instead of the one of the manifest
+    #[serde(default)]
+    pub pipeline: Option<BatchPipeline>,
+}
+
+impl BatchRepo {
+    /// Name of the repository in the report
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+
+        match (&self.github, &self.path) {
+            (Some(github), _) => github.clone(),
+            (None, Some(path)) => path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Owner and name of the GitHub repository
+    fn github_repo(&self) -> Option<(&str, &str)> {
+        self.github.as_deref().and_then(|github| github.split_once('/'))
+    }
+}
+
+/// Manifest listing the repositories of a batch
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchManifest {
+    /// Pipeline run on every repository
+    #[serde(default)]
+    pub pipeline: BatchPipeline,
+
+    /// Repositories run at the same time
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+
+    /// Refinement rounds after a fix fails validation
+    #[serde(default = "default_max_refinements")]
+    pub max_refinements: u32,
+
+    /// Pull request opened by the `fix` pipeline
+    #[serde(default)]
+    pub pull_request: BatchPullRequest,
+
+    /// Repositories of the batch
+    #[serde(default)]
+    pub repos: Vec<BatchRepo>,
+}
+
+impl BatchManifest {
+    /// Pipeline of a repository of the manifest
+    pub fn pipeline_of(&self, repo: &BatchRepo) -> BatchPipeline {
+        repo.pipeline.unwrap_or(self.pipeline)
+    }
+
+    /// Whether the batch clones repositories or generates fixes, which needs network access
+    pub fn needs_network(&self) -> bool {
+        self.repos.iter().any(|repo| repo.path.is_none() || self.pipeline_of(repo) == BatchPipeline::Fix)
+    }
+}
+
+fn default_branch() -> String {
+    "rust-ai-tool-fixes".to_string()
+}
+
+fn default_title() -> String {
+    "Fix issues found by rust-ai-tool".to_string()
+}
+
+fn default_concurrency() -> usize {
+    4
+}
+
+fn default_max_refinements() -> u32 {
+    2
+}
+
+/// Settings of a batch besides its manifest
+#[derive(Clone)]
+pub struct BatchOptions {
+    /// Options of the analysis of every repository
+    pub analysis_options: AnalysisOptions,
+
+    /// Options of the validation of generated fixes
+    pub validation_options: ValidationOptions,
+
+    /// Files of a repository fixed concurrently
+    pub fix_concurrency: usize,
+
+    /// GitHub access token, needed for GitHub repositories
+    pub access_token: Option<String>,
+
+    /// SSH settings for cloning (HTTPS if `None`)
+    pub ssh: Option<SshConfig>,
+
+    /// Creates AI model clients (the `fix` pipeline is unavailable without one)
+    pub ai_client: Option<ClientFactory>,
+}
+
+/// Outcome of the pipeline of one repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRepoReport {
+    /// Repository name
+    pub name: String,
+
+    /// Pipeline run
+    pub pipeline: BatchPipeline,
+
+    /// Number of issues found
+    pub issues: usize,
+
+    /// Number of issues with the error severity
+    pub errors: usize,
+
+    /// Number of issues with the warning severity
+    pub warnings: usize,
+
+    /// Number of validated fixes
+    pub fixes: usize,
+
+    /// Number of files whose fixes never passed validation
+    pub failed_fixes: usize,
+
+    /// Pull request opened with the fixes
+    pub pull_request: Option<String>,
+
+    /// File the fixes were written to, for repositories without a GitHub remote
+    pub fixes_file: Option<PathBuf>,
+
+    /// Why the pipeline failed
+    pub error: Option<String>,
+}
+
+/// Read a repository manifest
+///
+/// # Arguments
+///
+/// * `path` - Path to the manifest (TOML with `[[repos]]` entries)
+///
+/// # Returns
+///
+/// The manifest, with the paths of local checkouts relative to the current directory
+pub fn load_manifest(path: &Path) -> Result<BatchManifest> {
+    let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+    let invalid = |message: String| RustAiToolError::Other(format!("Invalid repository manifest {}: {}", path.display(), message));
+
+    let mut manifest: BatchManifest = toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+    let manifest_dir = path.parent().unwrap_or(Path::new(""));
+
+    for (index, repo) in manifest.repos.iter_mut().enumerate() {
+        if repo.path.is_none() && repo.github.is_none() {
+            return Err(invalid(format!("repository #{} has neither a path nor a GitHub repository", index + 1)));
+        }
+        if repo.github.is_some() && repo.github_repo().is_none_or(|(owner, name)| owner.is_empty() || name.is_empty()) {
+            return Err(invalid(format!("repository #{} is not a GitHub repository like owner/repo", index + 1)));
+        }
+
+        if let Some(local) = &mut repo.path {
+            *local = manifest_dir.join(&*local);
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Run the pipelines of a batch
+///
+/// # Arguments
+///
+/// * `manifest` - Repositories and pipelines of the batch
+/// * `options` - Settings of the pipelines
+/// * `checkpoint` - Checkpoint recording every completed repository, to resume an interrupted batch
+///
+/// # Returns
+///
+/// One report per repository, in the order of the manifest
+pub async fn run_batch(
+    manifest: &BatchManifest,
+    options: &BatchOptions,
+    checkpoint: Option<&Checkpoint>,
+) -> Result<Vec<BatchRepoReport>> {
+    info!("Running the pipelines of {} repositories, {} at a time", manifest.repos.len(), manifest.concurrency.max(1));
+
+    let mut reports: Vec<(usize, BatchRepoReport)> = stream::iter(manifest.repos.iter().enumerate())
+        .map(|(index, repo)| async move {
+            let pipeline = manifest.pipeline_of(repo);
+            let path = repo.path.as_ref().map(|path| path.to_string_lossy().into_owned()).unwrap_or_default();
+            let key = unit_key(&[
+                &repo.display_name(),
+                &path,
+                repo.github.as_deref().unwrap_or_default(),
+                repo.branch.as_deref().unwrap_or_default(),
+                &format!("{:?}", pipeline),
+            ]);
+            if let Some(report) = checkpoint.and_then(|checkpoint| checkpoint.completed::<BatchRepoReport>(BATCH_STAGE, &key)) {
+                info!("Reusing the checkpointed outcome of {}", report.name);
+                return (index, report);
+            }
+
+            let mut report = BatchRepoReport {
+                name: repo.display_name(),
+                pipeline,
+                issues: 0,
+                errors: 0,
+                warnings: 0,
+                fixes: 0,
+                failed_fixes: 0,
+                pull_request: None,
+                fixes_file: None,
+                error: None,
+            };
+
+            match run_repo(repo, pipeline, manifest, options, &mut report).await {
+                Ok(()) => {
+                    // Failed repositories run again when resuming
+                    if let Some(checkpoint) = checkpoint {
+                        if let Err(e) = checkpoint.record(BATCH_STAGE, &key, &report) {
+                            warn!("Failed to checkpoint the outcome of {}: {}", report.name, e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("The {:?} pipeline of {} failed: {}", pipeline, report.name, e);
+                    report.error = Some(e.to_string());
+                }
+            }
+
+            (index, report)
+        })
+        .buffer_unordered(manifest.concurrency.max(1))
+        .collect()
+        .await;
+
+    reports.sort_by_key(|(index, _)| *index);
+    Ok(reports.into_iter().map(|(_, report)| report).collect())
+}
+
+/// Run the pipeline of one repository, filling its report as the stages complete
+async fn run_repo(
+    repo: &BatchRepo,
+    pipeline: BatchPipeline,
+    manifest: &BatchManifest,
+    options: &BatchOptions,
+    report: &mut BatchRepoReport,
+) -> Result<()> {
+    let github = match repo.github_repo() {
+        Some((owner, name)) => {
+            let access_token = options.access_token.as_deref()
+                .ok_or_else(|| RustAiToolError::GitHub("GitHub configuration not found in config file".to_string()))?;
+            Some(GithubClient::new(access_token, owner, name)?.with_ssh(options.ssh.clone()))
+        }
+        None => None,
+    };
+
+    // Kept until the pipeline completes, as the clone lives in it
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let checkout = match (&repo.path, &github) {
+        (Some(path), _) if !path.is_dir() => {
+            return Err(RustAiToolError::Other(format!("{} is not a directory", path.display())));
+        }
+        (Some(path), _) => path.clone(),
+        (None, Some(github)) => github.clone_repo(repo.branch.as_deref(), temp_dir.path()).await?,
+        (None, None) => return Err(RustAiToolError::Other("Repository has neither a path nor a GitHub repository".to_string())),
+    };
+
+    let analysis_options = match &repo.path {
+        Some(_) => options.analysis_options.clone(),
+        None => checkout_options(&options.analysis_options, &checkout, &checkout),
+    };
+    let analyzed_path = checkout.clone();
+    let results: Vec<AnalysisResult> = tokio::task::spawn_blocking(move || analyze_project(&analyzed_path, &analysis_options))
+        .await
+        .map_err(|e| RustAiToolError::Analysis(format!("Analysis task failed: {}", e)))??;
+
+    let issues = results.iter().flat_map(|r| &r.issues);
+    report.issues = issues.clone().count();
+    report.errors = issues.clone().filter(|issue| issue.severity == Severity::Error).count();
+    report.warnings = issues.filter(|issue| issue.severity == Severity::Warning).count();
+    info!("Found {} issues in {}", report.issues, report.name);
+
+    if pipeline == BatchPipeline::Analyze || report.issues == 0 {
+        return Ok(());
+    }
+
+    let factory = options.ai_client.as_ref()
+        .ok_or_else(|| RustAiToolError::Offline("the fix pipeline".to_string()))?;
+    let client = factory(&checkout)?;
+    let fix_options = FixPipelineOptions {
+        max_refinements: manifest.max_refinements,
+        validation: options.validation_options.clone(),
+        project_path: checkout.clone(),
+        concurrency: options.fix_concurrency,
+        generate_tests: false,
+        checkpoint: None,
+    };
+
+    let fixed = run_fix_pipeline(&results, &client, &fix_options).await?;
+    report.fixes = fixed.fixes.len();
+    report.failed_fixes = fixed.failed.len();
+    if fixed.fixes.is_empty() {
+        return Ok(());
+    }
+
+    match &github {
+        Some(github) => {
+            let pr_options = PullRequestOptions {
+                draft: manifest.pull_request.draft,
+                labels: manifest.pull_request.labels.clone(),
+                ..PullRequestOptions::default()
+            };
+            let pr = publish_fixes(
+                github,
+                &fixed.fixes,
+                &checkout,
+                &manifest.pull_request.branch,
+                &manifest.pull_request.title,
+                &pr_options,
+                false,
+            ).await?;
+
+            info!("Opened pull request {} for {}", pr.url, report.name);
+            report.pull_request = Some(pr.url);
+        }
+        None => {
+            let fixes_file = checkout.join(FIXES_FILE);
+            let fixes_json = serde_json::to_string_pretty(&fixed.fixes)?;
+            fs::write(&fixes_file, fixes_json).map_err(RustAiToolError::Io)?;
+
+            info!("{} fixes for {} written to {}", fixed.fixes.len(), report.name, fixes_file.display());
+            report.fixes_file = Some(fixes_file);
+        }
+    }
+
+    Ok(())
+}
+
+/// Format the reports of a batch as one Markdown report
+///
+/// # Arguments
+///
+/// * `reports` - Reports of the repositories of the batch
+///
+/// # Returns
+///
+/// The totals of the batch, followed by a table of the outcome of every repository
+pub fn format_batch_report(reports: &[BatchRepoReport]) -> String {
+    let failed = reports.iter().filter(|report| report.error.is_some()).count();
+    let issues: usize = reports.iter().map(|report| report.issues).sum();
+    let fixes: usize = reports.iter().map(|report| report.fixes).sum();
+    let pull_requests = reports.iter().filter(|report| report.pull_request.is_some()).count();
+
+    let mut markdown = format!(
+        "# Rust AI Tool Batch Report\n\n**Repositories**: {} ({} failed)\n**Total Issues Found**: {}\n**Validated Fixes**: {} ({} pull requests)\n\n",
+        reports.len(),
+        failed,
+        issues,
+        fixes,
+        pull_requests
+    );
+
+    markdown.push_str("| Repository | Pipeline | Issues | Errors | Warnings | Fixes | Failed Fixes | Outcome |\n|---|---|---|---|---|---|---|---|\n");
+    for report in reports {
+        let outcome = match (&report.error, &report.pull_request, &report.fixes_file) {
+            (Some(e), _, _) => format!("failed: {}", e.replace('|', "\\|").replace('\n', " ")),
+            (None, Some(url), _) => url.clone(),
+            (None, None, Some(fixes_file)) => format!("`{}`", fixes_file.display()),
+            (None, None, None) => "completed".to_string(),
+        };
+
+        markdown.push_str(&format!(
+            "| {} | {:?} | {} | {} | {} | {} | {} | {} |\n",
+            report.name,
+            report.pipeline,
+            report.issues,
+            report.errors,
+            report.warnings,
+            report.fixes,
+            report.failed_fixes,
+            outcome,
+        ));
+    }
+
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = tempdir().unwrap();
+        let manifest_path = dir.path().join("repos.toml");
+        fs::write(&manifest_path, r#"
+concurrency = 2
+
+[pull_request]
+branch = "ai-fixes"
+draft = true
+
+[[repos]]
+path = "services/api"
+
+[[repos]]
+github = "acme/web"
+branch = "develop"
+pipeline = "fix"
+
+[[repos]]
+name = "billing"
+path = "../billing"
+github = "acme/billing"
+"#).unwrap();
+
+        let manifest = load_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.concurrency, 2);
+        assert_eq!(manifest.max_refinements, 2);
+        assert_eq!(manifest.pull_request.branch, "ai-fixes");
+        assert_eq!(manifest.pull_request.title, "Fix issues found by rust-ai-tool");
+
+        let names: Vec<String> = manifest.repos.iter().map(BatchRepo::display_name).collect();
+        assert_eq!(names, ["api", "acme/web", "billing"]);
+        assert_eq!(manifest.repos[0].path.as_deref(), Some(dir.path().join("services/api").as_path()));
+        assert_eq!(manifest.repos[2].github_repo(), Some(("acme", "billing")));
+
+        let pipelines: Vec<BatchPipeline> = manifest.repos.iter().map(|repo| manifest.pipeline_of(repo)).collect();
+        assert_eq!(pipelines, [BatchPipeline::Analyze, BatchPipeline::Fix, BatchPipeline::Analyze]);
+        assert!(manifest.needs_network());
+
+        fs::write(&manifest_path, "[[repos]]\ngithub = \"acme\"\n").unwrap();
+        assert!(load_manifest(&manifest_path).is_err());
+        fs::write(&manifest_path, "[[repos]]\nbranch = \"main\"\n").unwrap();
+        assert!(load_manifest(&manifest_path).is_err());
+    }
+}
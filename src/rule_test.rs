@@ -0,0 +1,242 @@
+//! Rule test module
+//!
+//! This module checks custom rules against fixture files, without analyzing a
+//! project:
+//! - Lines that should match are annotated with `// expect: <rule>` (several
+//!   rules separated by commas); the annotation is blanked out before matching
+//! - Every match is compared with the annotations: expected matches are
+//!   reported as matched, annotated lines without a match as missed and
+//!   matches on other lines as unexpected
+//! - Fixtures are Rust files, or directories searched for them
+
+use crate::analysis::{apply_custom_rule, collect_rust_files};
+use crate::{CustomRule, Result, RustAiToolError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::debug;
+
+/// Marker of an expected match, followed by rule names
+const EXPECT_MARKER: &str = "// expect:";
+
+/// Custom rules of a rule pack file
+#[derive(Debug, Deserialize)]
+pub struct RulePack {
+    /// Rules in the same format as `custom_rules` of the analysis options
+    pub custom_rules: Vec<CustomRule>,
+}
+
+/// How a rule did on a line of a fixture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RuleTestOutcome {
+    /// The rule matches an annotated line
+    Matched,
+
+    /// The rule does not match an annotated line
+    Missed,
+
+    /// The rule matches a line without annotation
+    Unexpected,
+}
+
+/// Outcome of a rule on a line of a fixture
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestLine {
+    /// Fixture file
+    pub file_path: PathBuf,
+
+    /// Line of the fixture (1-based)
+    pub line: usize,
+
+    /// Name of the rule
+    pub rule: String,
+
+    /// How the rule did
+    pub outcome: RuleTestOutcome,
+}
+
+/// Outcomes of a rule test, by fixture and line
+#[derive(Debug, Default, Serialize)]
+pub struct RuleTestReport {
+    /// Outcome of every annotated or matched line
+    pub lines: Vec<RuleTestLine>,
+}
+
+impl RuleTestReport {
+    /// Whether every annotated line matched and no other did
+    pub fn passed(&self) -> bool {
+        self.lines.iter().all(|line| line.outcome == RuleTestOutcome::Matched)
+    }
+
+    /// Number of lines with an outcome, for one rule or all of them
+    pub fn count(&self, rule: Option<&str>, outcome: RuleTestOutcome) -> usize {
+        self.lines.iter()
+            .filter(|line| line.outcome == outcome && rule.is_none_or(|rule| line.rule == rule))
+            .count()
+    }
+}
+
+/// Read the custom rules of a rule pack file (TOML with `[[custom_rules]]` entries)
+pub fn load_rule_pack(path: &Path) -> Result<Vec<CustomRule>> {
+    let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+    let pack: RulePack = toml::from_str(&content)
+        .map_err(|e| RustAiToolError::Analysis(format!("Invalid rule pack {}: {}", path.display(), e)))?;
+
+    Ok(pack.custom_rules)
+}
+
+/// Run custom rules on fixture files and compare their matches with the annotations
+///
+/// # Arguments
+///
+/// * `rules` - Rules to test
+/// * `fixtures` - Fixture files, or directories containing them
+///
+/// # Returns
+///
+/// The outcome of every annotated or matched line
+pub fn test_rules(rules: &[CustomRule], fixtures: &[PathBuf]) -> Result<RuleTestReport> {
+    for rule in rules {
+        regex::Regex::new(&rule.pattern)
+            .map_err(|e| RustAiToolError::Analysis(format!("Invalid pattern of rule '{}': {}", rule.name, e)))?;
+    }
+
+    let mut report = RuleTestReport::default();
+
+    for file_path in fixture_files(fixtures)? {
+        debug!("Testing rules on {}", file_path.display());
+        let content = fs::read_to_string(&file_path).map_err(RustAiToolError::Io)?;
+        let (code, expectations) = parse_expectations(&content);
+
+        for (line, rule) in &expectations {
+            if !rules.iter().any(|r| r.name == *rule) {
+                return Err(RustAiToolError::Analysis(format!(
+                    "{}:{} expects unknown rule '{}'",
+                    file_path.display(),
+                    line,
+                    rule
+                )));
+            }
+        }
+
+        for rule in rules {
+            let matched_lines: BTreeSet<usize> = apply_custom_rule(rule, &code, &file_path)?
+                .iter()
+                .map(|issue| issue.line_start)
+                .collect();
+            let expected_lines: BTreeSet<usize> = expectations.iter()
+                .filter(|(_, name)| *name == rule.name)
+                .map(|(line, _)| *line)
+                .collect();
+
+            for line in matched_lines.union(&expected_lines) {
+                let outcome = match (matched_lines.contains(line), expected_lines.contains(line)) {
+                    (true, true) => RuleTestOutcome::Matched,
+                    (false, _) => RuleTestOutcome::Missed,
+                    (true, false) => RuleTestOutcome::Unexpected,
+                };
+
+                report.lines.push(RuleTestLine {
+                    file_path: file_path.clone(),
+                    line: *line,
+                    rule: rule.name.clone(),
+                    outcome,
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Rust files of the fixtures, directories searched recursively
+fn fixture_files(fixtures: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for fixture in fixtures {
+        if fixture.is_dir() {
+            let mut found = collect_rust_files(fixture)?;
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(fixture.clone());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Split a fixture into the code rules run on and its expected matches
+///
+/// # Returns
+///
+/// The code with the annotations blanked out, so the lines and columns stay
+/// the same, and the line and rule name of every expected match
+fn parse_expectations(content: &str) -> (String, Vec<(usize, String)>) {
+    let mut code = String::with_capacity(content.len());
+    let mut expectations = Vec::new();
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        match line.find(EXPECT_MARKER) {
+            Some(start) => {
+                let end = line.trim_end_matches(['\r', '\n']).len();
+                expectations.extend(line[start + EXPECT_MARKER.len()..end].split(',')
+                    .map(str::trim)
+                    .filter(|rule| !rule.is_empty())
+                    .map(|rule| (index + 1, rule.to_string())));
+
+                code.push_str(&line[..start]);
+                code.push_str(&" ".repeat(end - start));
+                code.push_str(&line[end..]);
+            }
+            None => code.push_str(line),
+        }
+    }
+
+    (code, expectations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rule_fixtures() {
+        let dir = tempdir().unwrap();
+        let fixture = dir.path().join("no_unwrap.rs");
+        fs::write(&fixture, concat!(
+            "fn main() {\n",
+            "    let a = Some(1).unwrap(); // expect: no-unwrap\n",
+            "    let b = Some(2).expect(\"b\"); // expect: no-unwrap\n",
+            "    let c = Some(3).unwrap_or(0);\n",
+            "}\n",
+        )).unwrap();
+
+        let rule = CustomRule {
+            name: "no-unwrap".to_string(),
+            pattern: r"\.unwrap".to_string(),
+            message: "Avoid unwrap".to_string(),
+            severity: Severity::Warning,
+        };
+
+        let report = test_rules(std::slice::from_ref(&rule), &[dir.path().to_path_buf()]).unwrap();
+        let outcomes: Vec<(usize, RuleTestOutcome)> = report.lines.iter().map(|line| (line.line, line.outcome)).collect();
+        assert_eq!(outcomes, [
+            (2, RuleTestOutcome::Matched),
+            (3, RuleTestOutcome::Missed),
+            (4, RuleTestOutcome::Unexpected),
+        ]);
+        assert!(!report.passed());
+        assert_eq!(report.count(Some("no-unwrap"), RuleTestOutcome::Missed), 1);
+
+        let (code, expectations) = parse_expectations("x(); // expect: a, b\r\ny();\n");
+        assert_eq!(code, "x();                \r\ny();\n");
+        assert_eq!(expectations, [(1, "a".to_string()), (1, "b".to_string())]);
+
+        fs::write(&fixture, "x(); // expect: no-such-rule\n").unwrap();
+        assert!(test_rules(&[rule], &[fixture]).is_err());
+    }
+}
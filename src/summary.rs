@@ -0,0 +1,153 @@
+//! Summary module
+//!
+//! This module defines the outcome of a command line run for scripts:
+//! - A status with a distinct exit code: 0 clean, 1 issues found, 2 tool
+//!   error, 3 invalid fixes
+//! - A final summary line of `key=value` pairs, naming the command, the status,
+//!   the exit code and the counts the command reported, like
+//!   `rust-ai-tool: command=analyze status=issues-found exit=1 files=4 issues=2`
+//! - Values never contain spaces, so the line splits on whitespace
+
+use serde::Serialize;
+use std::fmt;
+
+/// Outcome of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunStatus {
+    /// The command succeeded and found nothing to report
+    #[default]
+    Clean,
+
+    /// The command succeeded and found issues, like analysis issues, failing
+    /// rule fixtures or configuration errors
+    IssuesFound,
+
+    /// The tool failed, like on a missing file or an unreachable AI provider
+    Error,
+
+    /// Fixes were rejected by validation or could not be applied
+    InvalidFixes,
+}
+
+impl RunStatus {
+    /// Exit code of the process for the status
+    pub fn exit_code(self) -> i32 {
+        match self {
+            RunStatus::Clean => 0,
+            RunStatus::IssuesFound => 1,
+            RunStatus::Error => 2,
+            RunStatus::InvalidFixes => 3,
+        }
+    }
+
+    /// Status of an exit code, like the one of a plugin; unknown codes are errors
+    pub fn from_exit_code(code: i32) -> Self {
+        match code {
+            0 => RunStatus::Clean,
+            1 => RunStatus::IssuesFound,
+            3 => RunStatus::InvalidFixes,
+            _ => RunStatus::Error,
+        }
+    }
+
+    /// How bad the status is; an error outranks invalid fixes, which outrank issues
+    fn rank(self) -> u8 {
+        match self {
+            RunStatus::Clean => 0,
+            RunStatus::IssuesFound => 1,
+            RunStatus::InvalidFixes => 2,
+            RunStatus::Error => 3,
+        }
+    }
+
+    /// Name of the status in the summary line
+    pub fn name(self) -> &'static str {
+        match self {
+            RunStatus::Clean => "clean",
+            RunStatus::IssuesFound => "issues-found",
+            RunStatus::Error => "error",
+            RunStatus::InvalidFixes => "invalid-fixes",
+        }
+    }
+}
+
+/// Summary of a run, written as the last line of its output
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    /// Command that ran, with its subcommands joined by `-` (like `rule-test`)
+    pub command: String,
+
+    /// Outcome of the run
+    pub status: RunStatus,
+
+    /// Counts reported by the command, in the order they were reported
+    pub counts: Vec<(String, usize)>,
+}
+
+impl RunSummary {
+    /// Create the summary of a clean run of a command
+    pub fn new(command: impl Into<String>) -> Self {
+        RunSummary {
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Record a count, replacing an earlier count with the same name
+    pub fn count(&mut self, name: &str, value: usize) -> &mut Self {
+        match self.counts.iter_mut().find(|(key, _)| key == name) {
+            Some((_, count)) => *count = value,
+            None => self.counts.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    /// Record the outcome, keeping the worse of the current and the new status
+    pub fn fail(&mut self, status: RunStatus) -> &mut Self {
+        if status.rank() > self.status.rank() {
+            self.status = status;
+        }
+        self
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let command = if self.command.is_empty() { "none" } else { &self.command };
+        write!(
+            f,
+            "rust-ai-tool: command={} status={} exit={}",
+            command,
+            self.status.name(),
+            self.status.exit_code()
+        )?;
+
+        for (name, value) in &self.counts {
+            write!(f, " {}={}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_line() {
+        let mut summary = RunSummary::new("analyze");
+        assert_eq!(summary.to_string(), "rust-ai-tool: command=analyze status=clean exit=0");
+
+        summary.count("files", 4).count("issues", 1).count("issues", 2);
+        summary.fail(RunStatus::InvalidFixes).fail(RunStatus::IssuesFound);
+        assert_eq!(
+            summary.to_string(),
+            "rust-ai-tool: command=analyze status=invalid-fixes exit=3 files=4 issues=2"
+        );
+
+        summary.fail(RunStatus::Error);
+        assert_eq!(summary.status.exit_code(), 2);
+    }
+}
@@ -0,0 +1,498 @@
+//! Supply chain module
+//!
+//! This module checks the dependency tree of a project against the `[policy]`
+//! of the configuration, like cargo-deny:
+//! - Licenses: the SPDX expression of every dependency (`MIT OR Apache-2.0`,
+//!   `(MIT AND BSD-3-Clause)`) must be satisfiable with allowed, not denied
+//!   licenses; dependencies without an SPDX license are reported as warnings
+//! - Bans: crates, or versions of crates, that must not be in the tree
+//! - Sources: dependencies must come from an allowed registry or git repository
+//! - The tree comes from `cargo metadata`, and every issue is reported on the
+//!   `Cargo.toml` of the project, at the dependency when it is a direct one, or
+//!   naming the direct dependency pulling it in
+//! - License and ban issues are `Policy` issues, source issues `Security` ones,
+//!   with the lints `policy::license`, `policy::ban` and `policy::source`
+//! - Direct dependencies get manifest fixes: switching a git dependency to its
+//!   registry release, or removing a banned or denied dependency
+
+use crate::analysis::{AnalysisResult, CodeFix, CodeIssue, IssueCategory};
+use crate::execution::CargoCommand;
+use crate::{BannedCrate, LicensePolicy, Result, RustAiToolError, Severity, SourcePolicy, SupplyChainPolicy};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use log::debug;
+
+/// Index URLs of crates.io (git and sparse protocols)
+const CRATES_IO_INDEXES: &[&str] = &["https://github.com/rust-lang/crates.io-index", "https://index.crates.io/"];
+
+/// Output of `cargo metadata` (only the fields used)
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    resolve: Option<Resolve>,
+}
+
+/// Package of the dependency tree
+#[derive(Debug, Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    license: Option<String>,
+    license_file: Option<String>,
+    source: Option<String>,
+}
+
+/// Resolved dependency graph
+#[derive(Debug, Deserialize)]
+struct Resolve {
+    nodes: Vec<ResolveNode>,
+}
+
+/// Package of the resolved graph with its dependencies
+#[derive(Debug, Deserialize)]
+struct ResolveNode {
+    id: String,
+    dependencies: Vec<String>,
+}
+
+/// Dependency declared in the manifest of the project
+#[derive(Debug, Clone)]
+pub(crate) struct ManifestEntry {
+    /// Line of the declaration (1-based)
+    pub(crate) line: usize,
+
+    /// Text of the line, if the whole declaration is on it
+    pub(crate) inline: Option<String>,
+}
+
+/// Check the dependency tree of a project against a policy
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory, with a `Cargo.toml`
+/// * `policy` - Policy to check
+///
+/// # Returns
+///
+/// The result of the manifest, with one issue per violation, or an error if
+/// `cargo metadata` fails
+pub fn check_policy(project_path: &Path, policy: &SupplyChainPolicy) -> Result<AnalysisResult> {
+    debug!("Checking the dependency policy of {}", project_path.display());
+
+    let output = CargoCommand::new(project_path)
+        .args(["metadata", "--format-version", "1"])
+        .output()
+        .map_err(|e| RustAiToolError::Analysis(format!("Failed to execute cargo metadata: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(RustAiToolError::Analysis(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let metadata: Metadata = serde_json::from_slice(&output.stdout)
+        .map_err(|e| RustAiToolError::Analysis(format!("Invalid cargo metadata output: {}", e)))?;
+
+    let manifest_path = project_path.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).map_err(RustAiToolError::Io)?;
+
+    Ok(AnalysisResult {
+        issues: policy_issues(&metadata, &manifest, &manifest_path, policy),
+        file_path: manifest_path,
+        errors: Vec::new(),
+        success: true,
+    })
+}
+
+/// Issues of the third-party packages of a dependency tree
+fn policy_issues(metadata: &Metadata, manifest: &str, manifest_path: &Path, policy: &SupplyChainPolicy) -> Vec<CodeIssue> {
+    let entries = manifest_entries(manifest);
+    let pulled_in_by = direct_dependents(metadata);
+    let members: HashSet<&str> = metadata.workspace_members.iter().map(String::as_str).collect();
+
+    let mut packages: Vec<&Package> = metadata.packages.iter()
+        .filter(|package| !members.contains(package.id.as_str()))
+        .collect();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    let mut issues = Vec::new();
+    for package in packages {
+        let entry = entries.get(&package.name).filter(|_| !pulled_in_by.contains_key(&package.id));
+        let context = match pulled_in_by.get(&package.id) {
+            Some(direct) => format!(" (pulled in by {})", direct),
+            None => String::new(),
+        };
+
+        let mut report = |severity, category, lint: &str, message: String, fix: Option<CodeFix>| {
+            issues.push(CodeIssue {
+                file_path: manifest_path.to_path_buf(),
+                line_start: entry.map_or(1, |entry| entry.line),
+                column_start: 1,
+                line_end: entry.map_or(1, |entry| entry.line),
+                column_end: entry.and_then(|entry| entry.inline.as_ref()).map_or(1, |line| line.len() + 1),
+                category,
+                severity,
+                message: format!("{}{}", message, context),
+                suggested_fix: fix,
+                priority: None,
+                lint: Some(lint.to_string()),
+                owners: Vec::new(),
+            });
+        };
+
+        if let Some(licenses) = &policy.licenses {
+            if !licenses.exceptions.contains(&package.name) {
+                match &package.license {
+                    Some(expression) => match license_allowed(expression, licenses) {
+                        Some(true) => {}
+                        Some(false) => report(
+                            Severity::Error,
+                            IssueCategory::Policy,
+                            "policy::license",
+                            format!("{} {} is licensed under {}, which the license policy does not allow", package.name, package.version, expression),
+                            entry.and_then(|entry| removal_fix(entry, &format!("Remove the dependency on {}, whose license is not allowed", package.name))),
+                        ),
+                        None => report(
+                            Severity::Warning,
+                            IssueCategory::Policy,
+                            "policy::license",
+                            format!("{} {} has an invalid SPDX license expression: {}", package.name, package.version, expression),
+                            None,
+                        ),
+                    },
+                    None => report(
+                        Severity::Warning,
+                        IssueCategory::Policy,
+                        "policy::license",
+                        match &package.license_file {
+                            Some(file) => format!("{} {} has no SPDX license, only the license file {}; review it", package.name, package.version, file),
+                            None => format!("{} {} declares no license", package.name, package.version),
+                        },
+                        None,
+                    ),
+                }
+            }
+        }
+
+        for ban in policy.bans.iter().filter(|ban| is_banned(package, ban)) {
+            let mut message = format!("{} {} is banned", package.name, package.version);
+            if let Some(reason) = &ban.reason {
+                message.push_str(&format!(": {}", reason));
+            }
+            let mut description = format!("Remove the dependency on {}", package.name);
+            if let Some(use_instead) = &ban.use_instead {
+                message.push_str(&format!("; use {} instead", use_instead));
+                description.push_str(&format!(" and use {} instead", use_instead));
+            }
+
+            report(Severity::Error, IssueCategory::Policy, "policy::ban", message, entry.and_then(|entry| removal_fix(entry, &description)));
+        }
+
+        if let (Some(sources), Some(source)) = (&policy.sources, &package.source) {
+            if !source_allowed(source, sources) {
+                let fix = entry
+                    .filter(|_| source.starts_with("git+") && sources.allow_registries.iter().any(|registry| registry == "crates.io"))
+                    .and_then(|entry| registry_fix(entry, package));
+                report(
+                    Severity::Error,
+                    IssueCategory::Security,
+                    "policy::source",
+                    format!("{} {} comes from {}, which is not an allowed source", package.name, package.version, display_source(source)),
+                    fix,
+                );
+            }
+        }
+    }
+
+    issues
+}
+
+/// Whether a license expression can be satisfied under a policy
+///
+/// `None` if the expression is not valid SPDX. The legacy `/` separator means `OR`.
+fn license_allowed(expression: &str, policy: &LicensePolicy) -> Option<bool> {
+    let spaced = expression.replace('/', " OR ").replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+
+    let mut position = 0;
+    let allowed = parse_or(&tokens, &mut position, policy)?;
+    (position == tokens.len()).then_some(allowed)
+}
+
+fn parse_or(tokens: &[&str], position: &mut usize, policy: &LicensePolicy) -> Option<bool> {
+    let mut allowed = parse_and(tokens, position, policy)?;
+    while tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("OR")) {
+        *position += 1;
+        allowed |= parse_and(tokens, position, policy)?;
+    }
+    Some(allowed)
+}
+
+fn parse_and(tokens: &[&str], position: &mut usize, policy: &LicensePolicy) -> Option<bool> {
+    let mut allowed = parse_license(tokens, position, policy)?;
+    while tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("AND")) {
+        *position += 1;
+        allowed &= parse_license(tokens, position, policy)?;
+    }
+    Some(allowed)
+}
+
+fn parse_license(tokens: &[&str], position: &mut usize, policy: &LicensePolicy) -> Option<bool> {
+    let token = *tokens.get(*position)?;
+    *position += 1;
+
+    if token == "(" {
+        let allowed = parse_or(tokens, position, policy)?;
+        if tokens.get(*position) != Some(&")") {
+            return None;
+        }
+        *position += 1;
+        return Some(allowed);
+    }
+    if token == ")" || ["AND", "OR", "WITH"].iter().any(|operator| token.eq_ignore_ascii_case(operator)) {
+        return None;
+    }
+
+    // Exceptions only widen what a license permits
+    if tokens.get(*position).is_some_and(|token| token.eq_ignore_ascii_case("WITH")) {
+        tokens.get(*position + 1)?;
+        *position += 2;
+    }
+
+    let id = token.trim_end_matches('+');
+    let matches = |list: &[String]| list.iter().any(|license| license.eq_ignore_ascii_case(id) || license.eq_ignore_ascii_case(token));
+    Some(!matches(&policy.deny) && (policy.allow.is_empty() || matches(&policy.allow)))
+}
+
+/// Whether a package is one of the banned versions of a crate
+fn is_banned(package: &Package, ban: &BannedCrate) -> bool {
+    if package.name != ban.name {
+        return false;
+    }
+
+    let Some(requirement) = &ban.version else {
+        return true;
+    };
+    match (semver::VersionReq::parse(requirement), semver::Version::parse(&package.version)) {
+        (Ok(requirement), Ok(version)) => requirement.matches(&version),
+        // An invalid requirement bans every version rather than none
+        _ => true,
+    }
+}
+
+/// Whether a source (like `registry+https://...` or `git+https://...#sha`) is allowed
+fn source_allowed(source: &str, policy: &SourcePolicy) -> bool {
+    if let Some(url) = source.strip_prefix("git+") {
+        let url = url.split(['?', '#']).next().unwrap_or(url);
+        return policy.allow_git.iter().any(|prefix| url.starts_with(prefix.as_str()));
+    }
+
+    let url = source.split_once('+').map_or(source, |(_, url)| url);
+    policy.allow_registries.iter().any(|registry| {
+        if registry == "crates.io" {
+            is_crates_io(source)
+        } else {
+            url.trim_end_matches('/') == registry.trim_end_matches('/')
+        }
+    })
+}
+
+/// Whether a package source (like `registry+https://...`) is crates.io
+pub(crate) fn is_crates_io(source: &str) -> bool {
+    let url = source.split_once('+').map_or(source, |(_, url)| url);
+    CRATES_IO_INDEXES.iter().any(|index| url.trim_end_matches('/') == index.trim_end_matches('/'))
+}
+
+/// Source in messages: the URL, without the commit of git sources
+fn display_source(source: &str) -> &str {
+    let url = source.split_once('+').map_or(source, |(_, url)| url);
+    url.split('#').next().unwrap_or(url)
+}
+
+/// Direct dependency of the workspace that pulls in each transitive dependency
+///
+/// Direct dependencies are not in the map. A package reached through several
+/// direct dependencies is attributed to the first one by name.
+fn direct_dependents(metadata: &Metadata) -> HashMap<String, String> {
+    let Some(resolve) = &metadata.resolve else {
+        return HashMap::new();
+    };
+
+    let graph: HashMap<&str, &[String]> = resolve.nodes.iter().map(|node| (node.id.as_str(), node.dependencies.as_slice())).collect();
+    let names: HashMap<&str, &str> = metadata.packages.iter().map(|package| (package.id.as_str(), package.name.as_str())).collect();
+    let members: HashSet<&str> = metadata.workspace_members.iter().map(String::as_str).collect();
+
+    let direct: BTreeMap<&str, &str> = metadata.workspace_members.iter()
+        .flat_map(|member| graph.get(member.as_str()).copied().unwrap_or_default())
+        .filter(|id| !members.contains(id.as_str()))
+        .map(|id| (names.get(id.as_str()).copied().unwrap_or(id.as_str()), id.as_str()))
+        .collect();
+    let direct_ids: HashSet<&str> = direct.values().copied().collect();
+
+    let mut pulled_in_by = HashMap::new();
+    for (name, id) in &direct {
+        let mut stack = vec![*id];
+        let mut seen = HashSet::from([*id]);
+        while let Some(current) = stack.pop() {
+            for dependency in graph.get(current).copied().unwrap_or_default() {
+                if seen.insert(dependency.as_str()) {
+                    if !direct_ids.contains(dependency.as_str()) && !members.contains(dependency.as_str()) {
+                        pulled_in_by.entry(dependency.clone()).or_insert_with(|| name.to_string());
+                    }
+                    stack.push(dependency);
+                }
+            }
+        }
+    }
+
+    pulled_in_by
+}
+
+/// Dependencies declared in a manifest, by crate name
+pub(crate) fn manifest_entries(manifest: &str) -> HashMap<String, ManifestEntry> {
+    let mut entries = HashMap::new();
+    let mut in_dependencies = false;
+
+    for (index, line) in manifest.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            let header = trimmed.trim_start_matches('[').trim_end_matches(']').trim();
+            let parts: Vec<&str> = header.split('.').collect();
+            match parts.iter().rposition(|part| part.ends_with("dependencies")) {
+                // `[dependencies.name]` declares one dependency in a table
+                Some(position) if position + 1 < parts.len() => {
+                    in_dependencies = false;
+                    entries.entry(parts[position + 1].trim_matches('"').to_string())
+                        .or_insert(ManifestEntry { line: index + 1, inline: None });
+                }
+                Some(_) => in_dependencies = true,
+                None => in_dependencies = false,
+            }
+            continue;
+        }
+
+        if !in_dependencies {
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let name = key.trim().split('.').next().unwrap_or_default().trim_matches('"').to_string();
+        if name.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // A declaration continued on the next lines cannot be replaced as a line
+        let complete = value.matches('{').count() == value.matches('}').count();
+        let inline = (complete && !key.contains('.')).then(|| line.to_string());
+        entries.entry(name).or_insert(ManifestEntry { line: index + 1, inline });
+    }
+
+    entries
+}
+
+/// Fix removing the line of a direct dependency
+fn removal_fix(entry: &ManifestEntry, description: &str) -> Option<CodeFix> {
+    Some(CodeFix {
+        original_code: entry.inline.clone()?,
+        replacement_code: String::new(),
+        confidence: 40,
+        description: format!("{}; code using it must be changed too", description),
+    })
+}
+
+/// Fix depending on the registry release of a git dependency
+fn registry_fix(entry: &ManifestEntry, package: &Package) -> Option<CodeFix> {
+    let line = entry.inline.as_ref()?;
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let key = line.split_once('=')?.0.trim();
+
+    Some(CodeFix {
+        original_code: line.clone(),
+        replacement_code: format!("{}{} = \"{}\"", indent, key, package.version),
+        confidence: 70,
+        description: format!("Depend on the crates.io release of {} instead of the git repository", package.name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, license: Option<&str>, source: &str) -> Package {
+        Package {
+            id: format!("{} {}", name, version),
+            name: name.to_string(),
+            version: version.to_string(),
+            license: license.map(String::from),
+            license_file: None,
+            source: Some(source.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_policy_issues() {
+        let licenses = LicensePolicy {
+            allow: vec!["MIT".to_string(), "Apache-2.0".to_string(), "BSD-3-Clause".to_string()],
+            deny: vec!["GPL-3.0".to_string()],
+            exceptions: Vec::new(),
+        };
+        assert_eq!(license_allowed("MIT OR Apache-2.0", &licenses), Some(true));
+        assert_eq!(license_allowed("MIT/GPL-3.0", &licenses), Some(true));
+        assert_eq!(license_allowed("(MIT OR Apache-2.0) AND Unicode-DFS-2016", &licenses), Some(false));
+        assert_eq!(license_allowed("Apache-2.0 WITH LLVM-exception AND BSD-3-Clause", &licenses), Some(true));
+        assert_eq!(license_allowed("GPL-3.0+", &licenses), Some(false));
+        assert_eq!(license_allowed("MIT AND (", &licenses), None);
+
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        let mut app = package("app", "0.1.0", Some("MIT"), registry);
+        app.source = None;
+        let metadata = Metadata {
+            packages: vec![
+                app,
+                package("openssl", "0.10.40", Some("Apache-2.0"), registry),
+                package("openssl-sys", "0.9.80", Some("MIT"), registry),
+                package("gplcrate", "1.0.0", Some("GPL-3.0"), registry),
+                package("forked", "2.1.0", Some("MIT"), "git+https://github.com/someone/forked?branch=main#abc123"),
+            ],
+            workspace_members: vec!["app 0.1.0".to_string()],
+            resolve: Some(Resolve {
+                nodes: vec![
+                    ResolveNode { id: "app 0.1.0".to_string(), dependencies: vec!["openssl 0.10.40".to_string(), "gplcrate 1.0.0".to_string(), "forked 2.1.0".to_string()] },
+                    ResolveNode { id: "openssl 0.10.40".to_string(), dependencies: vec!["openssl-sys 0.9.80".to_string()] },
+                ],
+            }),
+        };
+        let manifest = "[package]\nname = \"app\"\n\n[dependencies]\nopenssl = \"0.10\"\ngplcrate = { version = \"1\" }\nforked = { git = \"https://github.com/someone/forked\", branch = \"main\" }\n";
+        let policy = SupplyChainPolicy {
+            licenses: Some(licenses),
+            bans: vec![
+                BannedCrate { name: "openssl".to_string(), version: Some("<0.10.55".to_string()), reason: Some("RUSTSEC-2023-0044".to_string()), use_instead: Some("rustls".to_string()) },
+                BannedCrate { name: "openssl-sys".to_string(), version: None, reason: None, use_instead: None },
+            ],
+            sources: Some(SourcePolicy::default()),
+        };
+
+        let issues = policy_issues(&metadata, manifest, Path::new("Cargo.toml"), &policy);
+        let summary: Vec<(usize, &str, &str)> = issues.iter()
+            .map(|issue| (issue.line_start, issue.lint.as_deref().unwrap(), issue.message.as_str()))
+            .collect();
+        assert_eq!(summary, [
+            (7, "policy::source", "forked 2.1.0 comes from https://github.com/someone/forked?branch=main, which is not an allowed source"),
+            (6, "policy::license", "gplcrate 1.0.0 is licensed under GPL-3.0, which the license policy does not allow"),
+            (5, "policy::ban", "openssl 0.10.40 is banned: RUSTSEC-2023-0044; use rustls instead"),
+            (1, "policy::ban", "openssl-sys 0.9.80 is banned (pulled in by openssl)"),
+        ]);
+
+        let fix = issues[0].suggested_fix.as_ref().unwrap();
+        assert_eq!(fix.replacement_code, "forked = \"2.1.0\"");
+        assert_eq!(issues[0].category, IssueCategory::Security);
+        assert_eq!(issues[2].suggested_fix.as_ref().unwrap().original_code, "openssl = \"0.10\"");
+        assert!(issues[3].suggested_fix.is_none());
+    }
+}
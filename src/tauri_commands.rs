@@ -0,0 +1,338 @@
+//! Tauri commands module
+//!
+//! This module checks that the commands of a Tauri app line up between its
+//! Rust backend and its JavaScript/TypeScript frontend:
+//! - Commands are the `#[tauri::command]` functions of the backend, registered
+//!   in `tauri::generate_handler![...]` lists
+//! - The frontend calls them with `invoke("name")` (or `invoke<T>("name", ...)`)
+//!   in `.js`, `.jsx`, `.ts`, `.tsx`, `.mjs`, `.cjs`, `.vue` and `.svelte` files
+//!   outside `node_modules` and build directories
+//! - Commands invoked but not defined are reported at the call, with a fix when
+//!   a command of a similar name exists (like `getUser` for `get_user`)
+//! - Commands defined but not registered, and registered commands the frontend
+//!   never invokes, are reported at the definition
+//! - Plugin commands (`plugin:name|command`) and names computed at runtime are
+//!   not checked
+
+use crate::analysis::{offset_to_line_column, CodeFix, CodeIssue, IssueCategory};
+use crate::{Result, RustAiToolError, Severity};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+use log::debug;
+
+/// Extensions of frontend files
+const FRONTEND_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs", "vue", "svelte"];
+
+/// Directories of dependencies and build output, never searched
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target", "dist", "build", "out", "src-tauri"];
+
+/// Larger frontend files are bundles, not sources
+const MAX_FRONTEND_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Place a command is defined, registered or invoked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSite {
+    /// Name of the command
+    pub name: String,
+
+    /// File of the site
+    pub file_path: PathBuf,
+
+    /// Line of the site
+    pub line: usize,
+
+    /// Column of the site
+    pub column: usize,
+
+    /// Source text of the site (the quoted name for invocations)
+    pub text: String,
+}
+
+/// Commands of a Tauri app
+#[derive(Debug, Default)]
+pub struct TauriCommands {
+    /// `#[tauri::command]` functions
+    pub definitions: Vec<CommandSite>,
+
+    /// Entries of `generate_handler!` lists
+    pub registrations: Vec<CommandSite>,
+
+    /// `invoke` calls of the frontend
+    pub invocations: Vec<CommandSite>,
+}
+
+/// Directories of the Tauri app a project belongs to
+///
+/// # Arguments
+///
+/// * `project_path` - App directory (holding `src-tauri`), or its `src-tauri` directory
+///
+/// # Returns
+///
+/// The backend and frontend directories, or `None` if the project is not a Tauri app
+pub fn find_tauri_app(project_path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let backend = project_path.join("src-tauri");
+    if backend.join("Cargo.toml").is_file() {
+        return Some((backend, project_path.to_path_buf()));
+    }
+
+    let is_backend = ["tauri.conf.json", "tauri.conf.json5", "Tauri.toml"].iter().any(|file| project_path.join(file).is_file());
+    if is_backend {
+        let frontend = project_path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".."));
+        return Some((project_path.to_path_buf(), frontend.to_path_buf()));
+    }
+
+    None
+}
+
+/// Collect the commands of a Tauri app
+///
+/// # Arguments
+///
+/// * `backend` - Directory of the Rust crate
+/// * `frontend` - Directory of the frontend sources
+///
+/// # Returns
+///
+/// The definitions, registrations and invocations of commands
+pub fn collect_commands(backend: &Path, frontend: &Path) -> Result<TauriCommands> {
+    let mut commands = TauriCommands::default();
+
+    for path in source_files(backend, &["rs"]) {
+        let content = fs::read_to_string(&path).map_err(RustAiToolError::Io)?;
+        commands.definitions.extend(definitions(&path, &content));
+        commands.registrations.extend(registrations(&path, &content));
+    }
+
+    for path in source_files(frontend, FRONTEND_EXTENSIONS) {
+        let too_large = fs::metadata(&path).map_or(true, |metadata| metadata.len() > MAX_FRONTEND_FILE_SIZE);
+        if too_large || path.to_string_lossy().ends_with(".min.js") {
+            continue;
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(content) => commands.invocations.extend(invocations(&path, &content)),
+            Err(e) => debug!("Skipping unreadable frontend file {}: {}", path.display(), e),
+        }
+    }
+
+    debug!(
+        "Found {} Tauri commands, {} registrations and {} invocations",
+        commands.definitions.len(), commands.registrations.len(), commands.invocations.len()
+    );
+    Ok(commands)
+}
+
+/// Check the commands of the Tauri app of a project
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+///
+/// # Returns
+///
+/// The issues, in backend and frontend files; none if the project is not a Tauri app
+pub fn check_commands(project_path: &Path) -> Result<Vec<CodeIssue>> {
+    let Some((backend, frontend)) = find_tauri_app(project_path) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(command_issues(&collect_commands(&backend, &frontend)?))
+}
+
+/// Issues of mismatched commands
+fn command_issues(commands: &TauriCommands) -> Vec<CodeIssue> {
+    let defined: HashSet<&str> = commands.definitions.iter().map(|site| site.name.as_str()).collect();
+    let registered: HashSet<&str> = commands.registrations.iter().map(|site| site.name.as_str()).collect();
+    let invoked: HashSet<&str> = commands.invocations.iter().map(|site| site.name.as_str()).collect();
+    let mut issues = Vec::new();
+
+    for site in commands.invocations.iter().filter(|site| !defined.contains(site.name.as_str())) {
+        let similar = commands.definitions.iter().find(|definition| normalized(&definition.name) == normalized(&site.name));
+        let mut message = format!("invoke(\"{}\") calls a Tauri command that no #[tauri::command] function defines", site.name);
+        if let Some(similar) = similar {
+            message.push_str(&format!("; did you mean `{}`?", similar.name));
+        }
+
+        let suggested_fix = similar.map(|similar| CodeFix {
+            original_code: site.text.clone(),
+            replacement_code: site.text.replacen(&site.name, &similar.name, 1),
+            confidence: 80,
+            description: format!("Invoke the `{}` command", similar.name),
+        });
+        issues.push(command_issue(site, Severity::Error, "tauri::undefined-command", message, suggested_fix));
+    }
+
+    for site in &commands.definitions {
+        if !registered.contains(site.name.as_str()) {
+            let message = format!("Tauri command '{}' is not registered in any generate_handler! list", site.name);
+            issues.push(command_issue(site, Severity::Error, "tauri::unregistered-command", message, None));
+        } else if !commands.invocations.is_empty() && !invoked.contains(site.name.as_str()) {
+            // Without any invocation the frontend was not found, or builds its names at runtime
+            let message = format!("Tauri command '{}' is registered but the frontend never invokes it", site.name);
+            issues.push(command_issue(site, Severity::Info, "tauri::unused-command", message, None));
+        }
+    }
+
+    issues
+}
+
+/// `#[tauri::command]` functions of a Rust file
+fn definitions(path: &Path, content: &str) -> Vec<CommandSite> {
+    // `#[command]` alone is Tauri's only when the file imports it
+    if !content.contains("tauri::command") && !content.contains("tauri::{") {
+        return Vec::new();
+    }
+
+    sites(
+        path,
+        content,
+        r"#\[(?:tauri::)?command(?:\([^)]*\))?\]\s*(?:#\[[^\]]*\]\s*)*(?:pub(?:\([^)]*\))?\s+)?(?:async\s+)?fn\s+([A-Za-z0-9_]+)",
+    )
+}
+
+/// Entries of the `generate_handler!` lists of a Rust file
+fn registrations(path: &Path, content: &str) -> Vec<CommandSite> {
+    let Ok(list) = Regex::new(r"generate_handler!\s*[\[(]([^\])]*)[\])]") else {
+        return Vec::new();
+    };
+    let Ok(entry) = Regex::new(r"(?:[A-Za-z0-9_]+::)*([A-Za-z0-9_]+)") else {
+        return Vec::new();
+    };
+
+    list.captures_iter(content)
+        .filter_map(|captures| captures.get(1))
+        .flat_map(|entries| {
+            entry.captures_iter(entries.as_str())
+                .filter_map(move |captures| {
+                    let (name, whole) = (captures.get(1)?, captures.get(0)?);
+                    Some(site(path, content, name.as_str(), entries.start() + whole.start(), whole.as_str()))
+                })
+        })
+        .collect()
+}
+
+/// `invoke` calls with a literal command name in a frontend file
+fn invocations(path: &Path, content: &str) -> Vec<CommandSite> {
+    sites(path, content, r#"\binvoke\s*(?:<[^()]*?>)?\s*\(\s*("[^"\n]+"|'[^'\n]+'|`[^`$\n]+`)"#)
+        .into_iter()
+        .filter_map(|mut site| {
+            site.name = site.name[1..site.name.len() - 1].to_string();
+            (!site.name.starts_with("plugin:")).then_some(site)
+        })
+        .collect()
+}
+
+/// Sites matching a pattern whose first group is the command name
+fn sites(path: &Path, content: &str, pattern: &str) -> Vec<CommandSite> {
+    let Ok(re) = Regex::new(pattern) else {
+        return Vec::new();
+    };
+
+    re.captures_iter(content)
+        .filter_map(|captures| {
+            let name = captures.get(1)?;
+            Some(site(path, content, name.as_str(), name.start(), name.as_str()))
+        })
+        .collect()
+}
+
+fn site(path: &Path, content: &str, name: &str, offset: usize, text: &str) -> CommandSite {
+    let (line, column) = offset_to_line_column(content, offset);
+    CommandSite { name: name.to_string(), file_path: path.to_path_buf(), line, column, text: text.to_string() }
+}
+
+fn command_issue(site: &CommandSite, severity: Severity, lint: &str, message: String, suggested_fix: Option<CodeFix>) -> CodeIssue {
+    CodeIssue {
+        file_path: site.file_path.clone(),
+        line_start: site.line,
+        column_start: site.column,
+        line_end: site.line,
+        column_end: site.column + site.text.len(),
+        category: IssueCategory::TauriCompatibility,
+        severity,
+        message,
+        suggested_fix,
+        priority: None,
+        lint: Some(lint.to_string()),
+        owners: Vec::new(),
+    }
+}
+
+/// Name compared for near misses: `getUser`, `get_user` and `get-user` are the same
+fn normalized(name: &str) -> String {
+    name.chars().filter(|c| *c != '_' && *c != '-').flat_map(char::to_lowercase).collect()
+}
+
+/// Source files with one of the extensions below a directory
+fn source_files(root: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_skipped(entry))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(DirEntry::into_path)
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extensions.contains(&extension)))
+        .collect()
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    name.starts_with('.') || (entry.file_type().is_dir() && SKIPPED_DIRECTORIES.contains(&name.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_issues() {
+        let main_rs = Path::new("src-tauri/src/main.rs");
+        let backend = r#"use tauri::command;
+
+#[tauri::command]
+async fn get_user(id: u32) -> String { String::new() }
+
+#[command(rename_all = "snake_case")]
+pub fn save_settings() {}
+
+#[tauri::command]
+fn forgotten() {}
+
+fn main() {
+    tauri::Builder::default()
+        .invoke_handler(tauri::generate_handler![get_user, commands::save_settings])
+        .run(tauri::generate_context!());
+}
+"#;
+        let app_ts = Path::new("src/App.tsx");
+        let frontend = "const user = await invoke<User>('get_user', { id });\nawait invoke(\"getUser\");\nawait invoke(\"plugin:fs|read\");\nawait invoke(`delete_all`);\n";
+
+        let commands = TauriCommands {
+            definitions: definitions(main_rs, backend),
+            registrations: registrations(main_rs, backend),
+            invocations: invocations(app_ts, frontend),
+        };
+        let names = |sites: &[CommandSite]| sites.iter().map(|site| site.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&commands.definitions), ["get_user", "save_settings", "forgotten"]);
+        assert_eq!(names(&commands.registrations), ["get_user", "save_settings"]);
+        assert_eq!(names(&commands.invocations), ["get_user", "getUser", "delete_all"]);
+        assert_eq!((commands.registrations[1].line, commands.invocations[1].line), (14, 2));
+
+        let issues = command_issues(&commands);
+        let summary: Vec<(&str, usize, &str)> = issues.iter()
+            .map(|issue| (issue.lint.as_deref().unwrap(), issue.line_start, issue.message.as_str()))
+            .collect();
+        assert_eq!(summary, [
+            ("tauri::undefined-command", 2, "invoke(\"getUser\") calls a Tauri command that no #[tauri::command] function defines; did you mean `get_user`?"),
+            ("tauri::undefined-command", 4, "invoke(\"delete_all\") calls a Tauri command that no #[tauri::command] function defines"),
+            ("tauri::unused-command", 7, "Tauri command 'save_settings' is registered but the frontend never invokes it"),
+            ("tauri::unregistered-command", 10, "Tauri command 'forgotten' is not registered in any generate_handler! list"),
+        ]);
+        assert_eq!(issues[0].suggested_fix.as_ref().unwrap().replacement_code, "\"get_user\"");
+    }
+}
@@ -0,0 +1,597 @@
+//! Tauri config module
+//!
+//! This module checks the configuration of a Tauri app, `tauri.conf.json` and the
+//! v2 capability files of `src-tauri/capabilities`:
+//! - Files are validated against the official JSON schemas the Tauri tooling
+//!   installs: the `$schema` of the file when it is a local path, else
+//!   `config.schema.json` of the `@tauri-apps/cli` npm package for the
+//!   configuration and the `gen/schemas` files `tauri-build` generates (listing
+//!   every permission of the app and its plugins) for capabilities; without
+//!   them only the other checks run
+//! - Over-broad access is reported as Security issues: file scopes covering the
+//!   home directory or the whole disk, URL scopes and remote origins matching
+//!   any host, shell commands allowed with any arguments, v1 allowlists enabling
+//!   everything (`"all": true`), `dangerous*` settings and a missing Content
+//!   Security Policy
+//! - Capabilities are compared with the plugins registered in code
+//!   (`.plugin(tauri_plugin_fs::init())`): permissions of plugins the app never
+//!   registers, and registered plugins no capability grants any permission
+
+use crate::analysis::{offset_to_line_column, AnalysisResult, CodeIssue, IssueCategory};
+use crate::tauri_commands::find_tauri_app;
+use crate::{Result, RustAiToolError, Severity};
+use jsonschema::JSONSchema;
+use log::debug;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Plugins without commands, which need no permission
+const PLUGINS_WITHOUT_COMMANDS: &[&str] = &["single-instance", "localhost", "persisted-scope", "prevent-default"];
+
+/// Files of a Tauri app whose schema or code cross-check applies
+struct ConfigFile {
+    path: PathBuf,
+    content: String,
+    json: Value,
+    schema: Option<Value>,
+}
+
+/// Check the configuration and capabilities of the Tauri app of a project
+///
+/// # Arguments
+///
+/// * `project_path` - Project directory
+///
+/// # Returns
+///
+/// One result per configuration or capability file; none if the project is not a Tauri app
+pub fn check_config(project_path: &Path) -> Result<Vec<AnalysisResult>> {
+    let Some((backend, frontend)) = find_tauri_app(project_path) else {
+        return Ok(Vec::new());
+    };
+
+    let mut results = Vec::new();
+    let plugins = registered_plugins(&backend)?;
+
+    let config_path = backend.join("tauri.conf.json");
+    if config_path.is_file() {
+        let fallback = [
+            frontend.join("node_modules/@tauri-apps/cli/config.schema.json"),
+            frontend.join("node_modules/@tauri-apps/cli/schema.json"),
+        ];
+        results.push(match load(&config_path, &fallback) {
+            Ok(file) => {
+                let mut result = validate(&file);
+                result.issues.extend(config_issues(&file.path, &file.content, &file.json));
+                result
+            }
+            Err(e) => failed(&config_path, e),
+        });
+    }
+
+    let capabilities_dir = backend.join("capabilities");
+    if capabilities_dir.is_dir() {
+        let fallback = [backend.join("gen/schemas/desktop-schema.json")];
+        let mut granted = BTreeSet::new();
+        let mut capability_results = Vec::new();
+
+        for path in capability_files(&capabilities_dir) {
+            capability_results.push(match load(&path, &fallback) {
+                Ok(file) => {
+                    let permissions = permissions(&file.json);
+                    granted.extend(permissions.iter().filter_map(|(identifier, _)| plugin_of(identifier)).map(str::to_string));
+
+                    let mut result = validate(&file);
+                    result.issues.extend(capability_issues(&file.path, &file.content, &file.json, &plugins));
+                    result
+                }
+                Err(e) => failed(&path, e),
+            });
+        }
+
+        // A plugin without permissions has all its commands denied in Tauri 2
+        if let Some(result) = capability_results.first_mut() {
+            for plugin in plugins.iter().filter(|plugin| !granted.contains(*plugin) && !PLUGINS_WITHOUT_COMMANDS.contains(&plugin.as_str())) {
+                result.issues.push(config_issue(
+                    &result.file_path,
+                    1,
+                    IssueCategory::TauriCompatibility,
+                    Severity::Warning,
+                    "tauri::plugin-without-permission",
+                    format!("Plugin '{}' is registered but no capability grants any of its permissions, so the frontend cannot call it", plugin),
+                ));
+            }
+        }
+        results.extend(capability_results);
+    }
+
+    Ok(results)
+}
+
+/// Read a configuration file and the schema it is validated against
+fn load(path: &Path, fallback: &[PathBuf]) -> Result<ConfigFile> {
+    let content = fs::read_to_string(path).map_err(RustAiToolError::Io)?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| RustAiToolError::Analysis(format!("Invalid JSON in {}: {}", path.display(), e)))?;
+
+    // Schema URLs are not fetched; the tooling installs the same schemas locally
+    let declared = json.get("$schema")
+        .and_then(Value::as_str)
+        .filter(|schema| !schema.contains("://"))
+        .and_then(|schema| path.parent().map(|dir| dir.join(schema)));
+    let schema = declared.iter()
+        .chain(fallback)
+        .find(|schema_path| schema_path.is_file())
+        .and_then(|schema_path| {
+            debug!("Validating {} against {}", path.display(), schema_path.display());
+            let schema = fs::read_to_string(schema_path).ok()?;
+            serde_json::from_str(&schema).ok()
+        });
+
+    Ok(ConfigFile { path: path.to_path_buf(), content, json, schema })
+}
+
+/// Validate a file against its schema
+fn validate(file: &ConfigFile) -> AnalysisResult {
+    let mut result = AnalysisResult {
+        file_path: file.path.clone(),
+        issues: Vec::new(),
+        errors: Vec::new(),
+        success: true,
+    };
+    let Some(schema) = &file.schema else {
+        return result;
+    };
+
+    let compiled = match JSONSchema::compile(schema) {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            result.errors.push(format!("Invalid schema for {}: {}", file.path.display(), e));
+            return result;
+        }
+    };
+
+    if let Err(errors) = compiled.validate(&file.json) {
+        for error in errors {
+            let pointer = error.instance_path.to_string();
+            let location = if pointer.is_empty() { "the root".to_string() } else { format!("'{}'", pointer) };
+            let message = format!("Invalid value at {}: {}", location, error);
+            let line = json_line(&file.content, &error.instance_path.into_vec());
+            result.issues.push(config_issue(&file.path, line, IssueCategory::TauriCompatibility, Severity::Error, "tauri::config-schema", message));
+        }
+    }
+
+    result
+}
+
+/// Security issues of `tauri.conf.json`
+fn config_issues(path: &Path, content: &str, json: &Value) -> Vec<CodeIssue> {
+    let mut issues = Vec::new();
+    let mut broad = |pointer: &[&str], message: String| {
+        let line = json_line(content, &pointer.iter().map(|segment| segment.to_string()).collect::<Vec<_>>());
+        issues.push(config_issue(path, line, IssueCategory::Security, Severity::Warning, "tauri::broad-permission", message));
+    };
+
+    // Tauri 1 grants APIs with the allowlist instead of capabilities
+    if let Some(allowlist) = json.pointer("/tauri/allowlist").and_then(Value::as_object) {
+        if allowlist.get("all").and_then(Value::as_bool) == Some(true) {
+            broad(&["tauri", "allowlist", "all"], "The allowlist enables every Tauri API".to_string());
+        }
+        for (module, options) in allowlist {
+            if options.get("all").and_then(Value::as_bool) == Some(true) {
+                broad(&["tauri", "allowlist", module, "all"], format!("The allowlist enables every {} API", module));
+            }
+
+            let scope = options.get("scope").or_else(|| options.get("assetScope"));
+            let entries = scope.map(|scope| scope.get("allow").unwrap_or(scope)).and_then(Value::as_array);
+            for entry in entries.into_iter().flatten() {
+                if let Some(reason) = broad_scope(entry) {
+                    broad(&["tauri", "allowlist", module], format!("The {} scope of the allowlist {}", module, reason));
+                }
+            }
+        }
+    }
+
+    let security = json.pointer("/app/security").map(|security| (security, "app")).or_else(|| json.pointer("/tauri/security").map(|security| (security, "tauri")));
+    if let Some((security, parent)) = security {
+        if security.get("csp").is_none_or(Value::is_null) {
+            let line = json_line(content, &[parent.to_string(), "security".to_string()]);
+            issues.push(config_issue(
+                path,
+                line,
+                IssueCategory::Security,
+                Severity::Info,
+                "tauri::missing-csp",
+                "No Content Security Policy is set, so injected scripts can call the app's commands".to_string(),
+            ));
+        }
+    }
+
+    dangerous_settings(path, content, json, &mut Vec::new(), &mut issues);
+    issues
+}
+
+/// Security and code cross-check issues of a capability file
+fn capability_issues(path: &Path, content: &str, json: &Value, plugins: &BTreeSet<String>) -> Vec<CodeIssue> {
+    let mut issues = Vec::new();
+
+    for (identifier, pointer) in permissions(json) {
+        let line = json_line(content, &pointer);
+        let mut issue = |category, severity, lint: &str, message| issues.push(config_issue(path, line, category, severity, lint, message));
+
+        if identifier.starts_with("fs:") && identifier.contains("home") && identifier.contains("recursive") {
+            issue(IssueCategory::Security, Severity::Warning, "tauri::broad-permission", format!("Permission '{}' grants access to the whole home directory", identifier));
+        }
+        if let Some(plugin) = plugin_of(&identifier).filter(|plugin| !plugins.contains(*plugin)) {
+            issue(
+                IssueCategory::TauriCompatibility,
+                Severity::Warning,
+                "tauri::unused-plugin-permission",
+                format!("Permission '{}' is granted but the app never registers the {} plugin", identifier, plugin),
+            );
+        }
+
+        let scope = json.pointer(&format!("/{}", pointer.join("/")));
+        let entries = scope.and_then(|scope| scope.get("allow")).and_then(Value::as_array);
+        for entry in entries.into_iter().flatten() {
+            if let Some(reason) = broad_scope(entry) {
+                issue(IssueCategory::Security, Severity::Warning, "tauri::broad-permission", format!("The scope of permission '{}' {}", identifier, reason));
+            }
+        }
+    }
+
+    let capabilities = capabilities(json);
+    for (capability, pointer) in capabilities {
+        let urls = capability.pointer("/remote/urls").and_then(Value::as_array);
+        for url in urls.into_iter().flatten().filter_map(Value::as_str).filter(|url| is_broad_url(url)) {
+            let mut pointer = pointer.clone();
+            pointer.push("remote".to_string());
+            issues.push(config_issue(
+                path,
+                json_line(content, &pointer),
+                IssueCategory::Security,
+                Severity::Error,
+                "tauri::broad-permission",
+                format!("Remote URL '{}' lets any website call the commands of the capability", url),
+            ));
+        }
+    }
+
+    dangerous_settings(path, content, json, &mut Vec::new(), &mut issues);
+    issues
+}
+
+/// Capabilities of a capability file, with their JSON path
+///
+/// A file holds one capability, an array of them or a `capabilities` list.
+fn capabilities(json: &Value) -> Vec<(&Value, Vec<String>)> {
+    let (list, prefix) = match json {
+        Value::Array(list) => (list, Vec::new()),
+        Value::Object(object) => match object.get("capabilities").and_then(Value::as_array) {
+            Some(list) => (list, vec!["capabilities".to_string()]),
+            None => return vec![(json, Vec::new())],
+        },
+        _ => return Vec::new(),
+    };
+
+    list.iter()
+        .enumerate()
+        .map(|(index, capability)| {
+            let mut pointer = prefix.clone();
+            pointer.push(index.to_string());
+            (capability, pointer)
+        })
+        .collect()
+}
+
+/// Permission identifiers of a capability file, with their JSON path
+fn permissions(json: &Value) -> Vec<(String, Vec<String>)> {
+    let mut permissions = Vec::new();
+
+    for (capability, pointer) in capabilities(json) {
+        let entries = capability.get("permissions").and_then(Value::as_array);
+        for (index, entry) in entries.into_iter().flatten().enumerate() {
+            let identifier = entry.as_str().or_else(|| entry.get("identifier").and_then(Value::as_str));
+            if let Some(identifier) = identifier {
+                let mut pointer = pointer.clone();
+                pointer.extend(["permissions".to_string(), index.to_string()]);
+                permissions.push((identifier.to_string(), pointer));
+            }
+        }
+    }
+
+    permissions
+}
+
+/// Plugin of a permission identifier, like `fs` for `fs:allow-read-file`
+fn plugin_of(identifier: &str) -> Option<&str> {
+    identifier.split_once(':')
+        .map(|(plugin, _)| plugin)
+        .filter(|plugin| *plugin != "core")
+}
+
+/// Why a scope entry is over-broad, if it is
+fn broad_scope(entry: &Value) -> Option<String> {
+    if let Some(pattern) = entry.as_str().or_else(|| entry.get("path").and_then(Value::as_str)) {
+        return is_broad_path(pattern).then(|| format!("allows '{}', covering the home directory or the whole disk", pattern));
+    }
+    if let Some(url) = entry.get("url").and_then(Value::as_str) {
+        return is_broad_url(url).then(|| format!("allows '{}', matching any host", url));
+    }
+    if entry.get("args").and_then(Value::as_bool) == Some(true) {
+        let name = entry.get("name").and_then(Value::as_str).unwrap_or("a command");
+        return Some(format!("runs {} with any arguments", name));
+    }
+
+    None
+}
+
+/// Whether a path glob covers the home directory or a whole disk
+fn is_broad_path(pattern: &str) -> bool {
+    let Some(glob) = pattern.find(['*', '?', '[']) else {
+        return false;
+    };
+    let base = pattern[..glob].trim_end_matches(['/', '\\']);
+
+    base.is_empty()
+        || matches!(base, "$HOME" | "~")
+        || (base.len() == 2 && base.ends_with(':') && base.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Whether a URL pattern matches any host
+fn is_broad_url(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', ':']).next().unwrap_or_default();
+
+    matches!(host, "" | "*" | "**")
+}
+
+/// Report `dangerous*` settings that are turned on
+fn dangerous_settings(path: &Path, content: &str, value: &Value, pointer: &mut Vec<String>, issues: &mut Vec<CodeIssue>) {
+    let Value::Object(object) = value else {
+        return;
+    };
+
+    for (key, value) in object {
+        pointer.push(key.clone());
+        let enabled = !matches!(value, Value::Null | Value::Bool(false));
+        if key.starts_with("dangerous") && enabled {
+            issues.push(config_issue(
+                path,
+                json_line(content, pointer),
+                IssueCategory::Security,
+                Severity::Warning,
+                "tauri::dangerous-setting",
+                format!("'{}' turns off a Tauri security protection", pointer.join(".")),
+            ));
+        } else {
+            dangerous_settings(path, content, value, pointer, issues);
+        }
+        pointer.pop();
+    }
+}
+
+/// Plugins registered by the Rust code, like `fs` for `.plugin(tauri_plugin_fs::init())`
+fn registered_plugins(backend: &Path) -> Result<BTreeSet<String>> {
+    let plugin = Regex::new(r"\.plugin\(\s*(?:::)?tauri_plugin_([A-Za-z0-9_]+)::")
+        .map_err(|e| RustAiToolError::Analysis(format!("Invalid plugin pattern: {}", e)))?;
+    let mut plugins = BTreeSet::new();
+
+    let files = WalkDir::new(backend)
+        .into_iter()
+        .filter_entry(|entry| !matches!(entry.file_name().to_str(), Some("target" | "gen")))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "rs"));
+    for entry in files {
+        let content = fs::read_to_string(entry.path()).map_err(RustAiToolError::Io)?;
+        plugins.extend(plugin.captures_iter(&content).map(|captures| captures[1].replace('_', "-")));
+    }
+
+    Ok(plugins)
+}
+
+/// JSON capability files, in a stable order
+fn capability_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn failed(path: &Path, error: RustAiToolError) -> AnalysisResult {
+    AnalysisResult {
+        file_path: path.to_path_buf(),
+        issues: Vec::new(),
+        errors: vec![error.to_string()],
+        success: false,
+    }
+}
+
+fn config_issue(path: &Path, line: usize, category: IssueCategory, severity: Severity, lint: &str, message: String) -> CodeIssue {
+    CodeIssue {
+        file_path: path.to_path_buf(),
+        line_start: line,
+        column_start: 1,
+        line_end: line,
+        column_end: 1,
+        category,
+        severity,
+        message,
+        suggested_fix: None,
+        priority: None,
+        lint: Some(lint.to_string()),
+        owners: Vec::new(),
+    }
+}
+
+/// Line of the value at a JSON path, or of the closest value found
+fn json_line(content: &str, pointer: &[String]) -> usize {
+    let bytes = content.as_bytes();
+    let mut offset = skip_whitespace(bytes, 0);
+
+    for segment in pointer {
+        let found = match bytes.get(offset) {
+            Some(b'{') => member(bytes, offset, segment),
+            Some(b'[') => segment.parse().ok().and_then(|index| element(bytes, offset, index)),
+            _ => None,
+        };
+        match found {
+            Some(found) => offset = found,
+            None => break,
+        }
+    }
+
+    offset_to_line_column(content, offset).0
+}
+
+/// Offset of the value of a member of the object at an offset
+fn member(bytes: &[u8], object: usize, name: &str) -> Option<usize> {
+    let mut offset = skip_whitespace(bytes, object + 1);
+
+    while bytes.get(offset) == Some(&b'"') {
+        let key_end = skip_value(bytes, offset)?;
+        let key: String = serde_json::from_slice(&bytes[offset..key_end]).ok()?;
+        offset = skip_whitespace(bytes, key_end);
+        if bytes.get(offset) != Some(&b':') {
+            return None;
+        }
+
+        let value = skip_whitespace(bytes, offset + 1);
+        if key == name {
+            return Some(value);
+        }
+        offset = skip_whitespace(bytes, skip_value(bytes, value)?);
+        if bytes.get(offset) == Some(&b',') {
+            offset = skip_whitespace(bytes, offset + 1);
+        }
+    }
+
+    None
+}
+
+/// Offset of an element of the array at an offset
+fn element(bytes: &[u8], array: usize, index: usize) -> Option<usize> {
+    let mut offset = skip_whitespace(bytes, array + 1);
+
+    for _ in 0..index {
+        offset = skip_whitespace(bytes, skip_value(bytes, offset)?);
+        if bytes.get(offset) != Some(&b',') {
+            return None;
+        }
+        offset = skip_whitespace(bytes, offset + 1);
+    }
+
+    (bytes.get(offset) != Some(&b']')).then_some(offset)
+}
+
+/// Offset after the value at an offset
+fn skip_value(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut offset = start;
+
+    while let Some(&byte) = bytes.get(offset) {
+        offset += 1;
+        if in_string {
+            match byte {
+                b'\\' => offset += 1,
+                b'"' => {
+                    in_string = false;
+                    if depth == 0 {
+                        return Some(offset);
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => return Some(offset - 1),
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(offset);
+                }
+            }
+            b',' if depth == 0 => return Some(offset - 1),
+            _ => {}
+        }
+    }
+
+    Some(offset)
+}
+
+fn skip_whitespace(bytes: &[u8], mut offset: usize) -> usize {
+    while bytes.get(offset).is_some_and(u8::is_ascii_whitespace) {
+        offset += 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_capability_issues() {
+        let path = Path::new("src-tauri/capabilities/default.json");
+        let content = r#"{
+  "identifier": "default",
+  "windows": ["main"],
+  "remote": { "urls": ["https://*"] },
+  "permissions": [
+    "core:default",
+    "fs:allow-home-read-recursive",
+    {
+      "identifier": "http:default",
+      "allow": [{ "url": "https://api.example.com/*" }, { "url": "https://*" }]
+    },
+    { "identifier": "shell:allow-execute", "allow": [{ "name": "git", "cmd": "git", "args": true }] }
+  ],
+  "dangerousUseHttpScheme": true
+}"#;
+        let json: Value = serde_json::from_str(content).unwrap();
+        let plugins: BTreeSet<String> = ["fs", "shell"].into_iter().map(str::to_string).collect();
+
+        let issues = capability_issues(path, content, &json, &plugins);
+        let summary: Vec<(usize, &str, &str)> = issues.iter()
+            .map(|issue| (issue.line_start, issue.lint.as_deref().unwrap(), issue.message.as_str()))
+            .collect();
+        assert_eq!(summary, [
+            (7, "tauri::broad-permission", "Permission 'fs:allow-home-read-recursive' grants access to the whole home directory"),
+            (8, "tauri::unused-plugin-permission", "Permission 'http:default' is granted but the app never registers the http plugin"),
+            (8, "tauri::broad-permission", "The scope of permission 'http:default' allows 'https://*', matching any host"),
+            (12, "tauri::broad-permission", "The scope of permission 'shell:allow-execute' runs git with any arguments"),
+            (4, "tauri::broad-permission", "Remote URL 'https://*' lets any website call the commands of the capability"),
+            (14, "tauri::dangerous-setting", "'dangerousUseHttpScheme' turns off a Tauri security protection"),
+        ]);
+
+        let schema = json!({
+            "type": "object",
+            "required": ["identifier", "permissions"],
+            "properties": { "windows": { "type": "array", "items": { "type": "string", "minLength": 1 } } }
+        });
+        let file = ConfigFile {
+            path: path.to_path_buf(),
+            content: "{\n  \"windows\": [\n    \"main\",\n    \"\"\n  ]\n}".to_string(),
+            json: json!({ "windows": ["main", ""] }),
+            schema: Some(schema),
+        };
+        let lines: Vec<usize> = validate(&file).issues.iter().map(|issue| issue.line_start).collect();
+        assert_eq!(lines, [4, 1, 1]);
+
+        assert!(is_broad_path("$HOME/**") && is_broad_path("C:/**") && !is_broad_path("$APPDATA/**"));
+    }
+}
@@ -0,0 +1,465 @@
+//! Telemetry module
+//!
+//! This module collects `tracing` spans and exports them to an OpenTelemetry
+//! collector, so long CI runs can be profiled and failures correlated:
+//! - Analysis, AI requests, GitHub operations and apply run in spans with the
+//!   file, model or repository they work on; the run itself is the root span
+//! - Errors returned by instrumented functions mark their span as failed
+//! - Spans are sent with OTLP over HTTP (JSON) to `[telemetry] otlp_endpoint`
+//!   or `OTEL_EXPORTER_OTLP_ENDPOINT`, in batches and when the run ends
+//! - Without an endpoint, or in offline mode, spans are dropped; log messages
+//!   are unaffected either way
+
+use crate::{Result, RustAiToolError, TelemetryConfig};
+use data_encoding::HEXLOWER;
+use log::{debug, warn};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Value as JsonValue};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Subscriber;
+use tracing::{Event, Level, Metadata};
+use tracing_core::span::Current;
+
+/// Finished spans sent in one request while the run goes on
+const BATCH_SIZE: usize = 512;
+
+/// Spans and exporter of this process
+static STATE: OnceLock<State> = OnceLock::new();
+
+thread_local! {
+    /// Spans entered on this thread, innermost last
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Where finished spans are sent
+#[derive(Debug, Clone)]
+struct Exporter {
+    /// URL of the traces endpoint
+    url: String,
+
+    /// Headers of each request, like an authorization token
+    headers: Vec<(String, String)>,
+
+    /// `service.name` of the spans
+    service_name: String,
+}
+
+/// A span that has not been closed yet
+#[derive(Debug)]
+struct OpenSpan {
+    span_id: [u8; 8],
+    parent_id: Option<[u8; 8]>,
+    metadata: &'static Metadata<'static>,
+    start: u128,
+    attributes: Vec<(String, String)>,
+    error: Option<String>,
+    references: usize,
+}
+
+/// A closed span, ready to be exported
+#[derive(Debug, Clone)]
+struct FinishedSpan {
+    span_id: [u8; 8],
+    parent_id: Option<[u8; 8]>,
+    name: &'static str,
+    start: u128,
+    end: u128,
+    attributes: Vec<(String, String)>,
+    error: Option<String>,
+}
+
+struct State {
+    trace_id: [u8; 16],
+    next_id: AtomicU64,
+    open: Mutex<HashMap<u64, OpenSpan>>,
+    finished: Mutex<Vec<FinishedSpan>>,
+    exporter: OnceLock<Exporter>,
+    exports: Mutex<Vec<JoinHandle<()>>>,
+}
+
+/// Subscriber recording spans into the state of the process
+struct SpanCollector;
+
+/// Install the span collector, before any span is created
+///
+/// Only the first call has an effect.
+pub fn init() {
+    let mut trace_id = [0; 16];
+    if SystemRandom::new().fill(&mut trace_id).is_err() {
+        trace_id = now_nanos().to_be_bytes();
+    }
+
+    let state = State {
+        trace_id,
+        next_id: AtomicU64::new(1),
+        open: Mutex::new(HashMap::new()),
+        finished: Mutex::new(Vec::new()),
+        exporter: OnceLock::new(),
+        exports: Mutex::new(Vec::new()),
+    };
+    if STATE.set(state).is_ok() {
+        let _ = tracing::subscriber::set_global_default(SpanCollector);
+    }
+}
+
+/// Export the spans of this run as configured
+///
+/// # Arguments
+///
+/// * `config` - Telemetry settings
+/// * `offline` - Whether network access is disabled
+///
+/// # Returns
+///
+/// An error if the endpoint is not an HTTP URL
+pub fn configure(config: &TelemetryConfig, offline: bool) -> Result<()> {
+    let Some(endpoint) = config.otlp_endpoint.clone().or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()) else {
+        return Ok(());
+    };
+    let Some(state) = STATE.get() else {
+        return Ok(());
+    };
+    if offline {
+        warn!("Not exporting traces to {} in offline mode", endpoint);
+        return Ok(());
+    }
+
+    let exporter = Exporter {
+        url: traces_url(&endpoint)?,
+        headers: config.headers.iter().map(|(name, value)| (name.clone(), value.clone())).collect(),
+        service_name: config.service_name.clone()
+            .or_else(|| std::env::var("OTEL_SERVICE_NAME").ok())
+            .unwrap_or_else(|| "rust-ai-tool".to_string()),
+    };
+    debug!("Exporting traces to {} (trace id {})", exporter.url, HEXLOWER.encode(&state.trace_id));
+    let _ = state.exporter.set(exporter);
+
+    Ok(())
+}
+
+/// Send the remaining spans and wait for the batches sent during the run
+///
+/// Spans still open, like ones held by background tasks, end now. Export
+/// failures are only warned about, since telemetry must not fail a run.
+pub async fn shutdown() {
+    let Some(state) = STATE.get() else {
+        return;
+    };
+    let Some(exporter) = state.exporter.get() else {
+        return;
+    };
+
+    let open: Vec<OpenSpan> = state.open.lock().unwrap().drain().map(|(_, span)| span).collect();
+    for span in open {
+        state.finish(span);
+    }
+
+    let pending: Vec<JoinHandle<()>> = state.exports.lock().unwrap().drain(..).collect();
+    for export in pending {
+        let _ = export.await;
+    }
+
+    let spans: Vec<FinishedSpan> = state.finished.lock().unwrap().drain(..).collect();
+    export(exporter, &state.trace_id, &spans).await;
+}
+
+/// URL of the traces endpoint of an OTLP/HTTP collector
+fn traces_url(endpoint: &str) -> Result<String> {
+    if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        return Err(RustAiToolError::Other(format!("Invalid OTLP endpoint '{}': expected an http:// or https:// URL", endpoint)));
+    }
+
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        Ok(endpoint.to_string())
+    } else {
+        Ok(format!("{}/v1/traces", endpoint))
+    }
+}
+
+/// Send spans to the collector
+async fn export(exporter: &Exporter, trace_id: &[u8; 16], spans: &[FinishedSpan]) {
+    if spans.is_empty() {
+        return;
+    }
+
+    let body = otlp_body(exporter, trace_id, spans);
+    let mut request = crate::network::client_builder()
+        .build()
+        .map(|client| client.post(&exporter.url).json(&body));
+    for (name, value) in &exporter.headers {
+        request = request.map(|request| request.header(name, value));
+    }
+
+    let result = match request {
+        Ok(request) => request.send().await.and_then(|response| response.error_for_status()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => debug!("Exported {} spans to {}", spans.len(), exporter.url),
+        Err(e) => warn!("Failed to export {} spans to {}: {}", spans.len(), exporter.url, e),
+    }
+}
+
+/// OTLP/JSON request exporting spans
+fn otlp_body(exporter: &Exporter, trace_id: &[u8; 16], spans: &[FinishedSpan]) -> JsonValue {
+    let spans: Vec<JsonValue> = spans.iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": HEXLOWER.encode(trace_id),
+                "spanId": HEXLOWER.encode(&span.span_id),
+                "name": span.name,
+                "kind": 1,
+                "startTimeUnixNano": span.start.to_string(),
+                "endTimeUnixNano": span.end.to_string(),
+                "attributes": span.attributes.iter()
+                    .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+                    .collect::<Vec<_>>(),
+                "status": match &span.error {
+                    Some(message) => json!({ "code": 2, "message": message }),
+                    None => json!({ "code": 0 }),
+                },
+            });
+            if let Some(parent_id) = &span.parent_id {
+                value["parentSpanId"] = json!(HEXLOWER.encode(parent_id));
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{ "key": "service.name", "value": { "stringValue": exporter.service_name } }],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "rust-ai-tool", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn now_nanos() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+}
+
+/// Collects the fields of a span or event as strings
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl State {
+    /// Span the current span of this thread, or the explicit parent, stands for
+    fn parent_id(&self, explicit: Option<&Id>, contextual: bool) -> Option<[u8; 8]> {
+        let parent = match explicit {
+            Some(parent) => Some(parent.into_u64()),
+            None if contextual => ENTERED.with(|entered| entered.borrow().last().copied()),
+            None => None,
+        }?;
+
+        self.open.lock().unwrap().get(&parent).map(|span| span.span_id)
+    }
+
+    /// Move a closed span to the spans to export, sending a batch when it is full
+    fn finish(&self, span: OpenSpan) {
+        let Some(exporter) = self.exporter.get() else {
+            return;
+        };
+
+        let mut finished = self.finished.lock().unwrap();
+        finished.push(FinishedSpan {
+            span_id: span.span_id,
+            parent_id: span.parent_id,
+            name: span.metadata.name(),
+            start: span.start,
+            end: now_nanos(),
+            attributes: span.attributes,
+            error: span.error,
+        });
+
+        if finished.len() >= BATCH_SIZE {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                let batch: Vec<FinishedSpan> = finished.drain(..).collect();
+                let exporter = exporter.clone();
+                let trace_id = self.trace_id;
+                let handle = runtime.spawn(async move { export(&exporter, &trace_id, &batch).await });
+                self.exports.lock().unwrap().push(handle);
+            }
+        }
+    }
+}
+
+impl Subscriber for SpanCollector {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Events only matter for marking their span as failed
+        metadata.is_span() || *metadata.level() == Level::ERROR
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let state = STATE.get().expect("span collector without state");
+        let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut span_id = id.to_be_bytes();
+        let _ = SystemRandom::new().fill(&mut span_id[..4]);
+
+        let mut fields = Vec::new();
+        attributes.record(&mut FieldVisitor(&mut fields));
+
+        let span = OpenSpan {
+            span_id,
+            parent_id: state.parent_id(attributes.parent(), attributes.is_contextual()),
+            metadata: attributes.metadata(),
+            start: now_nanos(),
+            attributes: fields,
+            error: None,
+            references: 1,
+        };
+        state.open.lock().unwrap().insert(id, span);
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let state = STATE.get().expect("span collector without state");
+        if let Some(span) = state.open.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut FieldVisitor(&mut span.attributes));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let span = match event.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if event.is_contextual() => ENTERED.with(|entered| entered.borrow().last().copied()),
+            None => None,
+        };
+        let Some(span) = span else {
+            return;
+        };
+
+        let mut fields = Vec::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let message = fields.into_iter()
+            .map(|(name, value)| if name == "message" { value } else { format!("{}={}", name, value) })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let state = STATE.get().expect("span collector without state");
+        if let Some(span) = state.open.lock().unwrap().get_mut(&span) {
+            span.error = Some(message);
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(position) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(position);
+            }
+        });
+    }
+
+    fn current_span(&self) -> Current {
+        let state = STATE.get().expect("span collector without state");
+        let current = ENTERED.with(|entered| entered.borrow().last().copied());
+        let open = state.open.lock().unwrap();
+        match current.and_then(|id| open.get(&id).map(|span| (id, span.metadata))) {
+            Some((id, metadata)) => Current::new(Id::from_u64(id), metadata),
+            None => Current::none(),
+        }
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        let state = STATE.get().expect("span collector without state");
+        if let Some(span) = state.open.lock().unwrap().get_mut(&span.into_u64()) {
+            span.references += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let state = STATE.get().expect("span collector without state");
+        let closed = {
+            let mut open = state.open.lock().unwrap();
+            match open.get_mut(&span.into_u64()) {
+                Some(open_span) if open_span.references > 1 => {
+                    open_span.references -= 1;
+                    None
+                }
+                Some(_) => open.remove(&span.into_u64()),
+                None => None,
+            }
+        };
+
+        match closed {
+            Some(closed) => {
+                state.finish(closed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otlp_body() {
+        assert_eq!(traces_url("http://localhost:4318/").unwrap(), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_url("https://otel.example.com/v1/traces").unwrap(), "https://otel.example.com/v1/traces");
+        assert!(traces_url("localhost:4317").is_err());
+
+        let exporter = Exporter {
+            url: "http://localhost:4318/v1/traces".to_string(),
+            headers: Vec::new(),
+            service_name: "ci".to_string(),
+        };
+        let spans = [
+            FinishedSpan { span_id: [1; 8], parent_id: None, name: "run", start: 10, end: 50, attributes: Vec::new(), error: None },
+            FinishedSpan {
+                span_id: [2; 8],
+                parent_id: Some([1; 8]),
+                name: "ai_request",
+                start: 20,
+                end: 40,
+                attributes: vec![("model".to_string(), "claude".to_string())],
+                error: Some("timeout".to_string()),
+            },
+        ];
+
+        let body = otlp_body(&exporter, &[0xab; 16], &spans);
+        let resource_spans = &body["resourceSpans"][0];
+        assert_eq!(resource_spans["resource"]["attributes"][0]["value"]["stringValue"], "ci");
+        let exported = &resource_spans["scopeSpans"][0]["spans"];
+        assert_eq!(exported[0]["traceId"], "ab".repeat(16));
+        assert!(exported[0].get("parentSpanId").is_none());
+        assert_eq!(exported[1]["parentSpanId"], "0101010101010101");
+        assert_eq!(exported[1]["startTimeUnixNano"], "20");
+        assert_eq!(exported[1]["attributes"][0]["key"], "model");
+        assert_eq!(exported[1]["status"], json!({ "code": 2, "message": "timeout" }));
+    }
+}
@@ -0,0 +1,473 @@
+//! Project templates module
+//!
+//! This module renders the files of generated projects with Handlebars:
+//! - An embedded set of templates for the built-in project types
+//! - User template directories, laid out as `<template>/<path>.hbs`, adding
+//!   templates or overriding single files of the embedded ones
+//! - Variables for the project name, author, license, dependencies and
+//!   AI-generated snippets declared in `<template>/snippets.toml`, and a
+//!   `{{toml value}}` helper quoting values for manifests
+//! - cargo-generate style templates cloned from git repositories, with
+//!   placeholders in every text file and file name, and post-generation hooks
+
+use crate::{Result, RustAiToolError};
+use handlebars::{handlebars_helper, Handlebars};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use log::{debug, info};
+
+// `{{toml value}}` writes a value as a quoted TOML string, escaping quotes and backslashes
+handlebars_helper!(toml_string: |value: str| toml::Value::String(value.to_string()).to_string());
+
+/// Extension of template files, removed from the rendered file names
+const TEMPLATE_EXTENSION: &str = "hbs";
+
+/// File of a template directory mapping snippet names to the prompts generating them
+pub const SNIPPETS_FILE: &str = "snippets.toml";
+
+/// File of a template directory with its settings, such as hooks
+pub const TEMPLATE_CONFIG_FILE: &str = "template.toml";
+
+/// Templates shipped with the tool, as `<template>/<path>` and content
+const EMBEDDED_TEMPLATES: &[(&str, &str)] = &[
+    ("basic/README.md", include_str!("../templates/basic/README.md.hbs")),
+    ("basic/.gitignore", include_str!("../templates/basic/.gitignore.hbs")),
+    ("library/src/lib.rs", include_str!("../templates/library/src/lib.rs.hbs")),
+    ("library/examples/simple_example.rs", include_str!("../templates/library/examples/simple_example.rs.hbs")),
+    ("cli/src/main.rs", include_str!("../templates/cli/src/main.rs.hbs")),
+    ("web-service/src/main.rs", include_str!("../templates/web-service/src/main.rs.hbs")),
+    ("web-service/src/routes.rs", include_str!("../templates/web-service/src/routes.rs.hbs")),
+    ("web-service/src/handlers.rs", include_str!("../templates/web-service/src/handlers.rs.hbs")),
+    ("web-service/src/models.rs", include_str!("../templates/web-service/src/models.rs.hbs")),
+    ("axum/src/main.rs", include_str!("../templates/axum/src/main.rs.hbs")),
+    ("axum/src/routes.rs", include_str!("../templates/axum/src/routes.rs.hbs")),
+    ("axum/src/handlers.rs", include_str!("../templates/axum/src/handlers.rs.hbs")),
+    ("axum/src/models.rs", include_str!("../templates/axum/src/models.rs.hbs")),
+    ("axum-sqlx/docker-compose.yml", include_str!("../templates/axum-sqlx/docker-compose.yml.hbs")),
+    ("axum-sqlx/.env.example", include_str!("../templates/axum-sqlx/.env.example.hbs")),
+    ("axum-sqlx/migrations/20240101000000_create_todos.sql", include_str!("../templates/axum-sqlx/migrations/20240101000000_create_todos.sql.hbs")),
+    ("axum-sqlx/src/lib.rs", include_str!("../templates/axum-sqlx/src/lib.rs.hbs")),
+    ("axum-sqlx/src/db.rs", include_str!("../templates/axum-sqlx/src/db.rs.hbs")),
+    ("axum-sqlx/src/models.rs", include_str!("../templates/axum-sqlx/src/models.rs.hbs")),
+    ("axum-sqlx/src/repository.rs", include_str!("../templates/axum-sqlx/src/repository.rs.hbs")),
+    ("axum-sqlx/src/routes.rs", include_str!("../templates/axum-sqlx/src/routes.rs.hbs")),
+    ("axum-sqlx/src/main.rs", include_str!("../templates/axum-sqlx/src/main.rs.hbs")),
+    ("axum-sqlx/tests/api.rs", include_str!("../templates/axum-sqlx/tests/api.rs.hbs")),
+    ("axum-sqlx/tests/postgres.rs", include_str!("../templates/axum-sqlx/tests/postgres.rs.hbs")),
+    ("rocket/src/main.rs", include_str!("../templates/rocket/src/main.rs.hbs")),
+    ("rocket/src/routes.rs", include_str!("../templates/rocket/src/routes.rs.hbs")),
+    ("rocket/src/models.rs", include_str!("../templates/rocket/src/models.rs.hbs")),
+    ("embedded/.cargo/config.toml", include_str!("../templates/embedded/.cargo/config.toml.hbs")),
+    ("embedded/memory.x", include_str!("../templates/embedded/memory.x.hbs")),
+    ("embedded/build.rs", include_str!("../templates/embedded/build.rs.hbs")),
+    ("embedded/src/main.rs", include_str!("../templates/embedded/src/main.rs.hbs")),
+    ("embedded/examples/blinky.rs", include_str!("../templates/embedded/examples/blinky.rs.hbs")),
+    ("tauri/Cargo.toml", include_str!("../templates/tauri/Cargo.toml.hbs")),
+    ("tauri/.gitignore", include_str!("../templates/tauri/.gitignore.hbs")),
+    ("tauri/README.md", include_str!("../templates/tauri/README.md.hbs")),
+    ("tauri/package.json", include_str!("../templates/tauri/package.json.hbs")),
+    ("tauri/vite.config.js", include_str!("../templates/tauri/vite.config.js.hbs")),
+    ("tauri/index.html", include_str!("../templates/tauri/index.html.hbs")),
+    ("tauri/src/main.js", include_str!("../templates/tauri/src/main.js.hbs")),
+    ("tauri/src/styles.css", include_str!("../templates/tauri/src/styles.css.hbs")),
+    ("tauri/src-tauri/Cargo.toml", include_str!("../templates/tauri/src-tauri/Cargo.toml.hbs")),
+    ("tauri/src-tauri/build.rs", include_str!("../templates/tauri/src-tauri/build.rs.hbs")),
+    ("tauri/src-tauri/tauri.conf.json", include_str!("../templates/tauri/src-tauri/tauri.conf.json.hbs")),
+    ("tauri/src-tauri/capabilities/default.json", include_str!("../templates/tauri/src-tauri/capabilities/default.json.hbs")),
+    ("tauri/src-tauri/src/main.rs", include_str!("../templates/tauri/src-tauri/src/main.rs.hbs")),
+    ("tauri/src-tauri/src/lib.rs", include_str!("../templates/tauri/src-tauri/src/lib.rs.hbs")),
+    ("leptos/index.html", include_str!("../templates/leptos/index.html.hbs")),
+    ("leptos/Trunk.toml", include_str!("../templates/leptos/Trunk.toml.hbs")),
+    ("leptos/style.css", include_str!("../templates/leptos/style.css.hbs")),
+    ("leptos/.gitignore", include_str!("../templates/leptos/.gitignore.hbs")),
+    ("leptos/README.md", include_str!("../templates/leptos/README.md.hbs")),
+    ("leptos/.github/workflows/ci.yml", include_str!("../templates/leptos/.github/workflows/ci.yml.hbs")),
+    ("leptos/src/main.rs", include_str!("../templates/leptos/src/main.rs.hbs")),
+    ("leptos/src/counter.rs", include_str!("../templates/leptos/src/counter.rs.hbs")),
+    ("yew/index.html", include_str!("../templates/yew/index.html.hbs")),
+    ("yew/Trunk.toml", include_str!("../templates/yew/Trunk.toml.hbs")),
+    ("yew/style.css", include_str!("../templates/yew/style.css.hbs")),
+    ("yew/.gitignore", include_str!("../templates/yew/.gitignore.hbs")),
+    ("yew/README.md", include_str!("../templates/yew/README.md.hbs")),
+    ("yew/.github/workflows/ci.yml", include_str!("../templates/yew/.github/workflows/ci.yml.hbs")),
+    ("yew/src/main.rs", include_str!("../templates/yew/src/main.rs.hbs")),
+    ("yew/src/counter.rs", include_str!("../templates/yew/src/counter.rs.hbs")),
+    ("grpc/proto/greeter.proto", include_str!("../templates/grpc/proto/greeter.proto.hbs")),
+    ("grpc/build.rs", include_str!("../templates/grpc/build.rs.hbs")),
+    ("grpc/src/lib.rs", include_str!("../templates/grpc/src/lib.rs.hbs")),
+    ("grpc/src/service.rs", include_str!("../templates/grpc/src/service.rs.hbs")),
+    ("grpc/src/main.rs", include_str!("../templates/grpc/src/main.rs.hbs")),
+    ("grpc/examples/client.rs", include_str!("../templates/grpc/examples/client.rs.hbs")),
+    ("grpc/tests/greeter.rs", include_str!("../templates/grpc/tests/greeter.rs.hbs")),
+    ("pre-commit/.pre-commit-config.yaml", include_str!("../templates/pre-commit/.pre-commit-config.yaml.hbs")),
+    ("pre-commit/.githooks/pre-commit", include_str!("../templates/pre-commit/.githooks/pre-commit.hbs")),
+    ("devcontainer/.devcontainer/devcontainer.json", include_str!("../templates/devcontainer/.devcontainer/devcontainer.json.hbs")),
+];
+
+/// Binary files shipped with the tool, copied without rendering
+const EMBEDDED_RAW_FILES: &[(&str, &[u8])] = &[
+    ("tauri/src-tauri/icons/icon.png", include_bytes!("../templates/tauri/src-tauri/icons/icon.png")),
+];
+
+/// Variables available to templates
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateContext {
+    /// Project name
+    pub name: String,
+
+    /// Project name, as cargo-generate templates name it
+    #[serde(rename = "project-name")]
+    pub project_name: String,
+
+    /// Project name as a Rust identifier (`my-app` becomes `my_app`)
+    pub crate_name: String,
+
+    /// Project description
+    pub description: String,
+
+    /// Author name
+    pub author: String,
+
+    /// Author name, as cargo-generate templates name it
+    pub authors: String,
+
+    /// License of the project
+    pub license: String,
+
+    /// Dependencies of the project
+    pub dependencies: Vec<String>,
+
+    /// Copyright header for source files, empty if the license needs none
+    pub license_header: String,
+
+    /// AI-generated snippets, by the names declared in `snippets.toml`
+    pub snippets: BTreeMap<String, String>,
+}
+
+impl TemplateContext {
+    /// Variables of a project
+    pub fn new(name: &str, description: &str, author: &str, license: &str, dependencies: &[String]) -> Self {
+        TemplateContext {
+            name: name.to_string(),
+            project_name: name.to_string(),
+            crate_name: name.replace('-', "_"),
+            description: description.to_string(),
+            author: author.to_string(),
+            authors: author.to_string(),
+            license: license.to_string(),
+            dependencies: dependencies.to_vec(),
+            license_header: String::new(),
+            snippets: BTreeMap::new(),
+        }
+    }
+}
+
+/// Settings of a template, read from `template.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TemplateConfig {
+    #[serde(default)]
+    hooks: TemplateHooks,
+}
+
+/// Commands a template runs in the generated project
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TemplateHooks {
+    /// Commands run after the files are generated
+    #[serde(default)]
+    post: Vec<String>,
+}
+
+/// Embedded and user templates, ready to render
+pub struct TemplateEngine {
+    registry: Handlebars<'static>,
+
+    /// Rendered paths of each template
+    files: BTreeMap<String, Vec<String>>,
+
+    /// Files of each template copied without rendering, with their content
+    raw_files: BTreeMap<String, Vec<(String, Vec<u8>)>>,
+
+    /// Snippet prompts of each template
+    snippet_prompts: BTreeMap<String, BTreeMap<String, String>>,
+
+    /// Settings of each template
+    configs: BTreeMap<String, TemplateConfig>,
+}
+
+impl TemplateEngine {
+    /// Load the embedded templates and the templates of user directories
+    ///
+    /// Directories are loaded in order, so a file of a later directory overrides
+    /// the same file of an earlier directory or of the embedded set.
+    ///
+    /// # Arguments
+    ///
+    /// * `template_dirs` - User template directories
+    ///
+    /// # Returns
+    ///
+    /// The template engine
+    pub fn new(template_dirs: &[PathBuf]) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        // Templates produce source code, not HTML
+        registry.register_escape_fn(handlebars::no_escape);
+        registry.register_helper("toml", Box::new(toml_string));
+
+        let mut engine = TemplateEngine {
+            registry,
+            files: BTreeMap::new(),
+            raw_files: BTreeMap::new(),
+            snippet_prompts: BTreeMap::new(),
+            configs: BTreeMap::new(),
+        };
+
+        for (key, content) in EMBEDDED_TEMPLATES {
+            let (template, path) = key.split_once('/').unwrap_or(("basic", key));
+            engine.register(template, path, content)?;
+        }
+        for (key, content) in EMBEDDED_RAW_FILES {
+            let (template, path) = key.split_once('/').unwrap_or(("basic", key));
+            engine.raw_files.entry(template.to_string()).or_default().push((path.to_string(), content.to_vec()));
+        }
+
+        for dir in template_dirs {
+            engine.load_dir(dir)?;
+        }
+
+        Ok(engine)
+    }
+
+    /// Whether a template with this name exists
+    pub fn has_template(&self, template: &str) -> bool {
+        self.files.contains_key(template) || self.raw_files.contains_key(template)
+    }
+
+    /// Names of all templates
+    pub fn template_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.files.keys().chain(self.raw_files.keys()).map(String::as_str).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Snippets a template uses, with the prompts generating them
+    pub fn snippet_prompts(&self, template: &str) -> Option<&BTreeMap<String, String>> {
+        self.snippet_prompts.get(template)
+    }
+
+    /// Commands a template runs in the generated project after rendering it
+    pub fn post_hooks(&self, template: &str) -> &[String] {
+        self.configs.get(template).map_or(&[], |config| config.hooks.post.as_slice())
+    }
+
+    /// Render the files of a template into a directory
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Template name
+    /// * `project_dir` - Directory the files are written to
+    /// * `context` - Template variables
+    ///
+    /// # Returns
+    ///
+    /// The written files
+    pub fn render(&self, template: &str, project_dir: &Path, context: &TemplateContext) -> Result<Vec<PathBuf>> {
+        let paths = self.files.get(template);
+        let raw_files = self.raw_files.get(template);
+        if paths.is_none() && raw_files.is_none() {
+            return Err(RustAiToolError::ProjectGeneration(format!(
+                "Unknown template: {} (available: {})",
+                template,
+                self.template_names().join(", ")
+            )));
+        }
+
+        let mut written = Vec::new();
+        for path in paths.into_iter().flatten() {
+            let content = self.registry.render(&format!("{}/{}", template, path), context)
+                .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to render {}/{}: {}", template, path, e)))?;
+
+            written.push(self.write_file(project_dir, path, content.as_bytes(), context)?);
+            debug!("Rendered {}/{}", template, path);
+        }
+
+        for (path, content) in raw_files.into_iter().flatten() {
+            written.push(self.write_file(project_dir, path, content, context)?);
+        }
+
+        Ok(written)
+    }
+
+    /// Load a directory as a single template, such as the checkout of a template repository
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Name of the template
+    /// * `root` - Root directory of the template
+    /// * `render_all` - Render every text file, not only `.hbs` files, like cargo-generate does
+    pub fn load_template(&mut self, template: &str, root: &Path, render_all: bool) -> Result<()> {
+        let walker = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        for entry in walker {
+            let entry = entry.map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to read templates: {}", e)))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            let path = relative.to_string_lossy().replace('\\', "/");
+            let content = fs::read(entry.path()).map_err(RustAiToolError::Io)?;
+
+            if path == SNIPPETS_FILE {
+                let prompts: BTreeMap<String, String> = parse_toml(template, &path, &content)?;
+                self.snippet_prompts.insert(template.to_string(), prompts);
+            } else if path == TEMPLATE_CONFIG_FILE {
+                let config: TemplateConfig = parse_toml(template, &path, &content)?;
+                self.configs.insert(template.to_string(), config);
+            } else if let Some(stripped) = path.strip_suffix(&format!(".{}", TEMPLATE_EXTENSION)) {
+                let text = String::from_utf8(content)
+                    .map_err(|_| RustAiToolError::ProjectGeneration(format!("Template {}/{} is not UTF-8", template, path)))?;
+                self.register(template, stripped, &text)?;
+            } else if let (true, Ok(text)) = (render_all, std::str::from_utf8(&content)) {
+                self.register(template, &path, text)?;
+            } else {
+                // Binary files, and other files of template directories, are copied as they are
+                self.raw_files.entry(template.to_string()).or_default().push((path, content));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load the templates of a user directory, one per subdirectory
+    fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Err(RustAiToolError::ProjectGeneration(format!(
+                "Template directory not found: {}",
+                dir.display()
+            )));
+        }
+
+        info!("Loading templates from {}", dir.display());
+
+        let mut entries: Vec<_> = fs::read_dir(dir)
+            .map_err(RustAiToolError::Io)?
+            .collect::<std::io::Result<_>>()
+            .map_err(RustAiToolError::Io)?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            if entry.path().is_dir() {
+                let template = entry.file_name().to_string_lossy().to_string();
+                self.load_template(&template, &entry.path(), false)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write one file of a template, with the placeholders of its path replaced
+    fn write_file(&self, project_dir: &Path, path: &str, content: &[u8], context: &TemplateContext) -> Result<PathBuf> {
+        let path = self.registry.render_template(path, context)
+            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Failed to render the path {}: {}", path, e)))?;
+
+        let file_path = project_dir.join(path);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).map_err(RustAiToolError::Io)?;
+        }
+        fs::write(&file_path, content).map_err(RustAiToolError::Io)?;
+
+        Ok(file_path)
+    }
+
+    /// Register one file of a template
+    fn register(&mut self, template: &str, path: &str, content: &str) -> Result<()> {
+        let key = format!("{}/{}", template, path);
+        self.registry.register_template_string(&key, content)
+            .map_err(|e| RustAiToolError::ProjectGeneration(format!("Invalid template {}: {}", key, e)))?;
+
+        let paths = self.files.entry(template.to_string()).or_default();
+        if !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a template name is the URL of a git repository
+pub fn is_git_url(template: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"].iter().any(|scheme| template.starts_with(scheme))
+        || template.ends_with(".git")
+}
+
+/// Parse a TOML file of a template
+fn parse_toml<T: serde::de::DeserializeOwned>(template: &str, path: &str, content: &[u8]) -> Result<T> {
+    toml::from_str(&String::from_utf8_lossy(content))
+        .map_err(|e| RustAiToolError::ProjectGeneration(format!("Invalid {}/{}: {}", template, path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_with_user_templates() {
+        let templates = tempdir().unwrap();
+        fs::create_dir_all(templates.path().join("basic")).unwrap();
+        fs::create_dir_all(templates.path().join("service/src")).unwrap();
+        fs::write(templates.path().join("basic/README.md.hbs"), "# {{name}} ({{license}})\n").unwrap();
+        fs::write(
+            templates.path().join("service/src/main.rs.hbs"),
+            "// {{author}}\n{{#each dependencies}}use {{this}};\n{{/each}}fn main() { {{snippets.body}} }\n// {{toml description}}\n",
+        ).unwrap();
+        fs::write(templates.path().join("service/snippets.toml"), "body = \"Print a greeting\"\n").unwrap();
+
+        let engine = TemplateEngine::new(&[templates.path().to_path_buf()]).unwrap();
+        assert!(engine.has_template("cli"));
+        assert_eq!(engine.snippet_prompts("service").unwrap()["body"], "Print a greeting");
+
+        let mut context = TemplateContext::new("demo", "A \"demo\"", "Ada", "MIT", &["serde".to_string()]);
+        context.snippets.insert("body".to_string(), "println!(\"<hi>\");".to_string());
+
+        let project = tempdir().unwrap();
+        engine.render("basic", project.path(), &context).unwrap();
+        engine.render("service", project.path(), &context).unwrap();
+
+        // The user README overrides the embedded one, the embedded .gitignore stays
+        assert_eq!(fs::read_to_string(project.path().join("README.md")).unwrap(), "# demo (MIT)\n");
+        assert!(project.path().join(".gitignore").exists());
+        assert_eq!(
+            fs::read_to_string(project.path().join("src/main.rs")).unwrap(),
+            "// Ada\nuse serde;\nfn main() { println!(\"<hi>\"); }\n// \"A \\\"demo\\\"\"\n"
+        );
+        assert!(engine.render("missing", project.path(), &context).is_err());
+
+        // A cargo-generate style repository renders every text file and file name
+        let repository = tempdir().unwrap();
+        fs::create_dir_all(repository.path().join(".git")).unwrap();
+        fs::write(repository.path().join(".git/HEAD"), "ref: {{broken").unwrap();
+        fs::write(repository.path().join("{{project-name}}.md"), "# {{crate_name}} by {{authors}}\n").unwrap();
+        fs::write(repository.path().join("logo.png"), [0x89, 0x50, 0xff]).unwrap();
+        fs::write(repository.path().join(TEMPLATE_CONFIG_FILE), "[hooks]\npost = [\"cargo fmt\"]\n").unwrap();
+
+        let url = "https://example.com/team/scaffold.git";
+        assert!(is_git_url(url) && !is_git_url("service"));
+        let mut engine = TemplateEngine::new(&[]).unwrap();
+        engine.load_template(url, repository.path(), true).unwrap();
+        assert_eq!(engine.post_hooks(url), ["cargo fmt"]);
+
+        let project = tempdir().unwrap();
+        engine.render(url, project.path(), &TemplateContext::new("my-app", "", "Ada", "MIT", &[])).unwrap();
+        assert_eq!(fs::read_to_string(project.path().join("my-app.md")).unwrap(), "# my_app by Ada\n");
+        assert_eq!(fs::read(project.path().join("logo.png")).unwrap(), [0x89, 0x50, 0xff]);
+        assert!(!project.path().join(".git").exists());
+    }
+}
@@ -0,0 +1,121 @@
+//! Issue triage module
+//!
+//! This module makes large analysis reports actionable:
+//! - The AI model ranks issues by estimated impact and fix risk
+//! - Every issue gets a `priority` from 0 (ignore) to 100 (fix first)
+//! - Results are sorted so the most urgent issues come first in every report format
+
+use crate::analysis::{sort_by_priority, AnalysisResult, CodeIssue};
+use crate::models::AiModelClient;
+use crate::{Result, RustAiToolError};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use log::{debug, info, warn};
+
+/// Issues ranked in one request
+const TRIAGE_BATCH_SIZE: usize = 50;
+
+/// Priority assigned by the model to one issue
+#[derive(Debug, Deserialize)]
+struct IssuePriority {
+    /// Id of the issue
+    id: String,
+
+    /// Priority from 0 (ignore) to 100 (fix first)
+    priority: u8,
+}
+
+/// Rank the issues of analysis results and sort the results by priority
+///
+/// Issues the model does not rank keep no priority and are listed after the
+/// ranked ones.
+///
+/// # Arguments
+///
+/// * `results` - Analysis results, updated in place
+/// * `client` - AI model client ranking the issues
+///
+/// # Returns
+///
+/// Number of issues that got a priority
+pub async fn triage_issues(results: &mut [AnalysisResult], client: &AiModelClient) -> Result<usize> {
+    let issues: Vec<&CodeIssue> = results.iter().flat_map(|r| &r.issues).collect();
+    let total = issues.len();
+    info!("Triaging {} issues", total);
+
+    let mut sources: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    let mut priorities: HashMap<String, u8> = HashMap::new();
+
+    for batch in issues.chunks(TRIAGE_BATCH_SIZE) {
+        let issue_list = batch.iter()
+            .map(|issue| {
+                let lines = sources.entry(issue.file_path.clone()).or_insert_with(|| {
+                    fs::read_to_string(&issue.file_path)
+                        .map(|source| source.lines().map(String::from).collect())
+                        .unwrap_or_default()
+                });
+                let code = lines.get(issue.line_start.saturating_sub(1)).map_or("", |line| line.trim());
+
+                format!(
+                    "- id {}: {}:{} [{:?}/{:?}] {}\n  code: {}",
+                    issue.id(),
+                    issue.file_path.display(),
+                    issue.line_start,
+                    issue.category,
+                    issue.severity,
+                    issue.message,
+                    code
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let response = client.prioritize_issues(&issue_list).await?;
+        let ranked = parse_priorities(&response)?;
+        debug!("Model ranked {} of {} issues", ranked.len(), batch.len());
+
+        priorities.extend(ranked.into_iter().map(|p| (p.id, p.priority.min(100))));
+    }
+
+    let mut ranked = 0;
+    for issue in results.iter_mut().flat_map(|r| r.issues.iter_mut()) {
+        issue.priority = priorities.get(&issue.id()).copied();
+        ranked += issue.priority.is_some() as usize;
+    }
+
+    if ranked < total {
+        warn!("{} issues were not ranked by the model", total - ranked);
+    }
+
+    sort_by_priority(results);
+    Ok(ranked)
+}
+
+/// Parse the JSON array of priorities in a model response
+fn parse_priorities(response: &str) -> Result<Vec<IssuePriority>> {
+    let json = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => return Err(RustAiToolError::AiModel("Triage response contains no JSON array".to_string())),
+    };
+
+    serde_json::from_str(json)
+        .map_err(|e| RustAiToolError::AiModel(format!("Invalid triage response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priorities() {
+        let response = "Here is the ranking:\n```json\n[{\"id\": \"38b3cb74\", \"priority\": 90}, {\"id\": \"05ab84a6\", \"priority\": 15}]\n```";
+        let priorities = parse_priorities(response).unwrap();
+
+        assert_eq!(priorities.len(), 2);
+        assert_eq!(priorities[0].id, "38b3cb74");
+        assert_eq!(priorities[1].priority, 15);
+        assert!(parse_priorities("no ranking").is_err());
+    }
+}
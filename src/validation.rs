@@ -7,8 +7,12 @@
 //! - Tauri compatibility
 //! - Structural integrity
 
+use crate::directory_config::DirectoryConfigs;
+use crate::execution::CargoCommand;
+use crate::protected_paths::protecting_glob;
 use crate::{RustAiToolError, ValidationOptions, Result};
 use ra_ap_syntax::{SourceFile, SyntaxNode, SyntaxKind};
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use log::{debug, info, warn, error};
@@ -183,12 +187,39 @@ impl ValidationPartialResult {
 ///
 /// A list of validation results, one for each fix
 pub fn validate_fixes(fixes: &[FixToValidate], options: &ValidationOptions) -> Result<Vec<ValidationResult>> {
+    validate_each(fixes, |_| Ok(Cow::Borrowed(options)))
+}
+
+/// Validates a list of suggested fixes for files of a project
+///
+/// Directory configurations of the project override the options for the files below them.
+///
+/// # Arguments
+///
+/// * `project_path` - Project the fixed files belong to
+/// * `fixes` - List of fixes to validate
+/// * `options` - Validation options
+///
+/// # Returns
+///
+/// A list of validation results, one for each fix
+pub fn validate_project_fixes(project_path: &Path, fixes: &[FixToValidate], options: &ValidationOptions) -> Result<Vec<ValidationResult>> {
+    let directories = DirectoryConfigs::new(project_path);
+    validate_each(fixes, |fix| directories.validation_options(&fix.file_path, options))
+}
+
+/// Validates fixes with the options of each fix
+fn validate_each<'a>(
+    fixes: &[FixToValidate],
+    options_for: impl Fn(&FixToValidate) -> Result<Cow<'a, ValidationOptions>>,
+) -> Result<Vec<ValidationResult>> {
     info!("Validating {} fixes", fixes.len());
     let mut results = Vec::new();
     
     for (i, fix) in fixes.iter().enumerate() {
         debug!("Validating fix #{} for {}", i + 1, fix.file_path.display());
-        match validate_fix(fix, options) {
+        let options = options_for(fix)?;
+        match validate_fix(fix, &options) {
             Ok(result) => {
                 if result.is_valid {
                     debug!("Fix #{} is valid", i + 1);
@@ -231,6 +262,20 @@ pub fn validate_fix(fix: &FixToValidate, options: &ValidationOptions) -> Result<
     let mut messages = Vec::new();
     let mut severity = ValidationSeverity::None;
     
+    // Protected files are never modified, however good the fix is
+    if let Some(glob) = protecting_glob(&options.protected_paths, &fix.file_path, Path::new("."))? {
+        return Ok(ValidationResult {
+            file_path: fix.file_path.clone(),
+            is_valid: false,
+            messages: vec![ValidationMessage {
+                message_type: ValidationMessageType::Error,
+                text: format!("{} is protected by '{}' in protected_paths", fix.file_path.display(), glob),
+                location: None,
+            }],
+            severity: ValidationSeverity::Critical,
+        });
+    }
+    
     // Always validate syntax
     let syntax_result = validate_syntax(&fix.modified_code);
     messages.extend(syntax_result.messages);
@@ -294,6 +339,102 @@ pub fn validate_fix(fix: &FixToValidate, options: &ValidationOptions) -> Result<
     })
 }
 
+/// Run the tests of a fix in a scratch copy of the project
+///
+/// The project is copied without its `target` directory and hidden files,
+/// the fixed file is written into the copy and `cargo test` runs the tests
+/// matching `test_filter`. Builds share `target/rust-ai-tool` of the project.
+///
+/// # Arguments
+///
+/// * `project_path` - Root of the project
+/// * `file_path` - Fixed file, inside the project
+/// * `code` - Fixed code including the tests
+/// * `test_filter` - Name filter selecting the tests to run
+///
+/// # Returns
+///
+/// Validation result; valid if the selected tests ran and passed
+pub fn run_fix_tests(project_path: &Path, file_path: &Path, code: &str, test_filter: &str) -> Result<ValidationResult> {
+    let project_path = project_path.canonicalize()
+        .map_err(RustAiToolError::Io)?;
+    let relative = file_path.canonicalize()
+        .map_err(RustAiToolError::Io)?
+        .strip_prefix(&project_path)
+        .map(Path::to_path_buf)
+        .map_err(|_| RustAiToolError::Validation(format!("{} is outside {}", file_path.display(), project_path.display())))?;
+    
+    static SCRATCH_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let scratch = std::env::temp_dir().join(format!(
+        "rust-ai-tool-tests-{}-{}",
+        std::process::id(),
+        SCRATCH_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    
+    let output = copy_project(&project_path, &scratch)
+        .and_then(|_| std::fs::write(scratch.join(&relative), code).map_err(RustAiToolError::Io))
+        .and_then(|_| {
+            info!("Running tests {} for the fix of {}", test_filter, file_path.display());
+            
+            CargoCommand::new(&scratch)
+                .args(["test", "--quiet", test_filter])
+                .with_target_dir(&project_path.join("target").join("rust-ai-tool"))
+                .output()
+                .map_err(|e| RustAiToolError::Validation(format!("Failed to execute cargo test: {}", e)))
+        });
+    
+    if let Err(e) = std::fs::remove_dir_all(&scratch) {
+        debug!("Failed to remove {}: {}", scratch.display(), e);
+    }
+    let output = output?;
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ran_tests = stdout.lines().any(|line| line.starts_with("test result:") && !line.contains(" 0 passed; 0 failed"));
+    
+    let mut result = ValidationPartialResult::new();
+    if output.status.success() && ran_tests {
+        result.add_info(format!("Tests {} passed", test_filter), None);
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let details: Vec<&str> = stdout.lines()
+            .chain(stderr.lines())
+            .filter(|line| line.contains("panicked") || line.starts_with("error") || line.starts_with("test result:"))
+            .take(10)
+            .collect();
+        let summary = if ran_tests { "failed" } else { "did not run" };
+        result.add_critical_error(format!("Tests {} {}: {}", test_filter, summary, details.join("; ")), None);
+    }
+    
+    Ok(ValidationResult {
+        file_path: file_path.to_path_buf(),
+        is_valid: result.severity != ValidationSeverity::Critical,
+        messages: result.messages,
+        severity: result.severity,
+    })
+}
+
+/// Copy a project without its build output and hidden files
+fn copy_project(from: &Path, to: &Path) -> Result<()> {
+    let entries = walkdir::WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !(entry.file_name() == "target" || entry.file_name().to_string_lossy().starts_with('.'))
+        });
+    
+    for entry in entries {
+        let entry = entry.map_err(|e| RustAiToolError::Validation(format!("Failed to copy project: {}", e)))?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target).map_err(RustAiToolError::Io)?;
+        } else if entry.file_type().is_file() {
+            std::fs::copy(entry.path(), &target).map_err(RustAiToolError::Io)?;
+        }
+    }
+    
+    Ok(())
+}
+
 /// Validates syntax of modified code
 fn validate_syntax(code: &str) -> ValidationPartialResult {
     let mut result = ValidationPartialResult::new();
@@ -0,0 +1,411 @@
+//! GitHub webhook module
+//!
+//! This module turns the tool into a self-hosted review bot:
+//! - An HTTP server receiving GitHub `pull_request` webhook events
+//! - Verification of the `X-Hub-Signature-256` signature of every delivery; the
+//!   server does not start without a secret and unsigned deliveries are rejected
+//! - Cloning the head branch of opened or updated pull requests and analyzing it,
+//!   without Clippy unless Cargo runs in a Docker container, as building the head
+//!   would run its build scripts and proc macros on the host
+//! - Posting the issues found in the changed files as a pull request comment
+//! - Posting the outcome of each review to the `[[notifications]]` webhooks
+
+use crate::analysis::{analyze_project, AnalysisResult};
+use crate::analyzer_plugins::checkout_options;
+use crate::execution;
+use crate::github::GithubClient;
+use crate::notifications::{self, IssueCounts, RunReport};
+use crate::summary::RunStatus;
+use crate::{AnalysisOptions, NotificationTarget, Result, RustAiToolError, SshConfig};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use log::{debug, error, info, warn};
+
+/// Pull request actions that trigger an analysis
+const REVIEWED_ACTIONS: &[&str] = &["opened", "reopened", "synchronize"];
+
+/// Issues listed in a review comment; the rest are only counted
+const MAX_COMMENT_ISSUES: usize = 100;
+
+/// Settings of the webhook server
+#[derive(Debug, Clone)]
+pub struct WebhookOptions {
+    /// Secret configured for the webhook on GitHub (the server does not start without one)
+    pub secret: Option<String>,
+
+    /// GitHub access token used to clone and comment
+    pub access_token: String,
+
+    /// Options of the analysis run on each pull request
+    pub analysis_options: AnalysisOptions,
+
+    /// SSH settings for cloning (HTTPS if `None`)
+    pub ssh: Option<SshConfig>,
+
+    /// Webhooks the outcome of each review is posted to
+    pub notifications: Vec<NotificationTarget>,
+}
+
+/// Payload of a `pull_request` event (only the fields used)
+#[derive(Debug, Deserialize)]
+pub struct PullRequestEvent {
+    /// What happened to the pull request (e.g. `opened`)
+    pub action: String,
+
+    /// Number of the pull request
+    pub number: u64,
+
+    /// The pull request
+    pub pull_request: PullRequestPayload,
+}
+
+/// Pull request in an event payload
+#[derive(Debug, Deserialize)]
+pub struct PullRequestPayload {
+    /// Web page of the pull request
+    #[serde(default)]
+    pub html_url: Option<String>,
+
+    /// Branch the changes are merged into
+    pub base: BranchPayload,
+
+    /// Branch with the changes
+    pub head: BranchPayload,
+}
+
+/// Branch in an event payload
+#[derive(Debug, Deserialize)]
+pub struct BranchPayload {
+    /// Branch name
+    #[serde(rename = "ref")]
+    pub branch: String,
+
+    /// Commit the branch points to
+    pub sha: String,
+
+    /// Repository of the branch (`None` if it was deleted)
+    pub repo: Option<RepoPayload>,
+}
+
+/// Repository in an event payload
+#[derive(Debug, Deserialize)]
+pub struct RepoPayload {
+    /// Repository name
+    pub name: String,
+
+    /// Owner of the repository
+    pub owner: OwnerPayload,
+}
+
+/// Repository owner in an event payload
+#[derive(Debug, Deserialize)]
+pub struct OwnerPayload {
+    /// Login of the owner
+    pub login: String,
+}
+
+/// Listen for GitHub webhook deliveries until the process is stopped
+///
+/// Each accepted `pull_request` event is answered right away and reviewed
+/// in the background.
+///
+/// # Arguments
+///
+/// * `addr` - Address to listen on
+/// * `options` - Settings of the server
+///
+/// # Returns
+///
+/// An error if no secret is configured or the server cannot be started
+pub async fn serve(addr: SocketAddr, options: WebhookOptions) -> Result<()> {
+    if options.secret.is_none() {
+        return Err(RustAiToolError::GitHub(
+            "A webhook secret is required, as unsigned deliveries could make the server build any repository".to_string(),
+        ));
+    }
+    if !execution::is_sandboxed() {
+        warn!("Cargo does not run in a Docker container; pull requests are analyzed without Clippy");
+    }
+
+    let options = Arc::new(options);
+    let make_service = make_service_fn(move |_connection| {
+        let options = options.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle_delivery(request, options.clone())))
+        }
+    });
+
+    let server = Server::try_bind(&addr)
+        .map_err(|e| RustAiToolError::GitHub(format!("Failed to listen on {}: {}", addr, e)))?
+        .serve(make_service);
+
+    info!("Listening for GitHub webhooks on http://{}", addr);
+    server.await
+        .map_err(|e| RustAiToolError::GitHub(format!("Webhook server failed: {}", e)))
+}
+
+/// Answer one webhook delivery, starting a review for relevant events
+async fn handle_delivery(request: Request<Body>, options: Arc<WebhookOptions>) -> std::result::Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(respond(StatusCode::METHOD_NOT_ALLOWED, "Only POST is supported"));
+    }
+
+    let header = |name: &str| {
+        request.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from)
+    };
+    let event = header("X-GitHub-Event").unwrap_or_default();
+    let signature = header("X-Hub-Signature-256");
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return Ok(respond(StatusCode::BAD_REQUEST, &format!("Failed to read body: {}", e))),
+    };
+
+    let verified = match (&options.secret, signature) {
+        (Some(secret), Some(signature)) => verify_signature(secret, &body, &signature),
+        _ => false,
+    };
+    if !verified {
+        warn!("Rejected webhook delivery with a missing or invalid signature");
+        return Ok(respond(StatusCode::UNAUTHORIZED, "Invalid signature"));
+    }
+
+    match event.as_str() {
+        "ping" => return Ok(respond(StatusCode::OK, "pong")),
+        "pull_request" => {}
+        _ => return Ok(respond(StatusCode::OK, "Event ignored")),
+    }
+
+    let event: PullRequestEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => return Ok(respond(StatusCode::BAD_REQUEST, &format!("Invalid pull_request payload: {}", e))),
+    };
+
+    if !REVIEWED_ACTIONS.contains(&event.action.as_str()) {
+        debug!("Ignoring pull request #{} action {}", event.number, event.action);
+        return Ok(respond(StatusCode::OK, "Action ignored"));
+    }
+
+    tokio::spawn(async move {
+        let number = event.number;
+        let project = event.pull_request.base.repo.as_ref()
+            .map(|repo| format!("{}/{}#{}", repo.owner.login, repo.name, number));
+        let pull_requests = event.pull_request.html_url.clone().into_iter().collect();
+
+        if let Err(e) = review_pull_request(event, &options).await {
+            error!("Failed to review pull request #{}: {}", number, e);
+
+            let report = RunReport {
+                command: "webhook".to_string(),
+                project,
+                status: RunStatus::Error,
+                issues: None,
+                pull_requests,
+                failures: vec![e.to_string()],
+            };
+            notifications::send(&options.notifications, &report).await;
+        }
+    });
+
+    Ok(respond(StatusCode::ACCEPTED, "Review started"))
+}
+
+/// Clone the head branch of a pull request, analyze it and comment the results
+///
+/// # Arguments
+///
+/// * `event` - The `pull_request` event
+/// * `options` - Settings of the server
+///
+/// # Returns
+///
+/// The number of issues found in the changed files
+pub async fn review_pull_request(event: PullRequestEvent, options: &WebhookOptions) -> Result<usize> {
+    let pull_request = &event.pull_request;
+    let (Some(base_repo), Some(head_repo)) = (&pull_request.base.repo, &pull_request.head.repo) else {
+        return Err(RustAiToolError::GitHub("The repository of the pull request no longer exists".to_string()));
+    };
+
+    info!(
+        "Reviewing pull request #{} of {}/{} ({})",
+        event.number,
+        base_repo.owner.login,
+        base_repo.name,
+        pull_request.head.branch
+    );
+
+    let base = GithubClient::new(&options.access_token, &base_repo.owner.login, &base_repo.name)?;
+    let head = GithubClient::new(&options.access_token, &head_repo.owner.login, &head_repo.name)?
+        .with_ssh(options.ssh.clone());
+
+    let changed_files: HashSet<String> = base.compare_branches(&pull_request.base.sha, &pull_request.head.sha)
+        .await?
+        .into_iter()
+        .collect();
+
+    let temp_dir = tempfile::tempdir().map_err(RustAiToolError::Io)?;
+    let repo_path = head.clone_repo(Some(&pull_request.head.branch), temp_dir.path()).await?;
+
+    // Clippy builds the head, running its build scripts and proc macros
+    let mut analysis_options = checkout_options(&options.analysis_options, &repo_path, &repo_path);
+    if !execution::is_sandboxed() {
+        analysis_options.run_clippy = false;
+    }
+    let analyzed_path = repo_path.clone();
+    let results = tokio::task::spawn_blocking(move || analyze_project(&analyzed_path, &analysis_options))
+        .await
+        .map_err(|e| RustAiToolError::Analysis(format!("Analysis task failed: {}", e)))??;
+
+    let results: Vec<AnalysisResult> = results.into_iter()
+        .filter(|result| {
+            let relative = result.file_path.strip_prefix(&repo_path).unwrap_or(&result.file_path);
+            changed_files.contains(&relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+
+    let issue_count = results.iter().map(|r| r.issues.len()).sum();
+    base.add_pr_comment(event.number, &format_review_comment(&results, &repo_path)).await?;
+
+    info!("Commented {} issues on pull request #{}", issue_count, event.number);
+
+    let report = RunReport {
+        command: "webhook".to_string(),
+        project: Some(format!("{}/{}#{}", base_repo.owner.login, base_repo.name, event.number)),
+        status: if issue_count > 0 { RunStatus::IssuesFound } else { RunStatus::Clean },
+        issues: Some(IssueCounts::of(&results)),
+        pull_requests: pull_request.html_url.clone().into_iter().collect(),
+        failures: Vec::new(),
+    };
+    notifications::send(&options.notifications, &report).await;
+
+    Ok(issue_count)
+}
+
+/// Format analysis results as a pull request comment
+///
+/// # Arguments
+///
+/// * `results` - Analysis results of the changed files
+/// * `repo_path` - Root of the analyzed checkout, stripped from file paths
+///
+/// # Returns
+///
+/// The comment, in GitHub-flavored Markdown
+pub fn format_review_comment(results: &[AnalysisResult], repo_path: &Path) -> String {
+    let issues: Vec<_> = results.iter().flat_map(|r| &r.issues).collect();
+
+    if issues.is_empty() {
+        return "### Rust AI Tool review\n\nNo issues found in the changed files.".to_string();
+    }
+
+    let mut comment = format!(
+        "### Rust AI Tool review\n\n{} issues found in the changed files.\n\n| File | Line | Severity | Issue |\n|---|---|---|---|\n",
+        issues.len()
+    );
+
+    for issue in issues.iter().take(MAX_COMMENT_ISSUES) {
+        let file_path = issue.file_path.strip_prefix(repo_path).unwrap_or(&issue.file_path);
+        comment.push_str(&format!(
+            "| `{}` | {} | {:?} | {} |\n",
+            file_path.display(),
+            issue.line_start,
+            issue.severity,
+            issue.message.replace('|', "\\|").replace('\n', " ")
+        ));
+    }
+
+    if issues.len() > MAX_COMMENT_ISSUES {
+        comment.push_str(&format!("\n{} more issues not shown.\n", issues.len() - MAX_COMMENT_ISSUES));
+    }
+
+    comment
+}
+
+/// Check the `X-Hub-Signature-256` header of a delivery
+///
+/// # Arguments
+///
+/// * `secret` - Secret configured for the webhook
+/// * `body` - Raw body of the delivery
+/// * `signature` - Value of the header (`sha256=<hex digest>`)
+///
+/// # Returns
+///
+/// Whether the body was signed with the secret
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(digest) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(digest) = data_encoding::HEXLOWER_PERMISSIVE.decode(digest.as_bytes()) else {
+        return false;
+    };
+
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    ring::hmac::verify(&key, body, &digest).is_ok()
+}
+
+fn respond(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_signature() {
+        // Example from the GitHub documentation on validating webhook deliveries
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+        assert!(verify_signature("It's a Secret to Everybody", b"Hello, World!", signature));
+        assert!(!verify_signature("another secret", b"Hello, World!", signature));
+        assert!(!verify_signature("It's a Secret to Everybody", b"Hello, World!", "757107ea"));
+    }
+
+    #[tokio::test]
+    async fn test_reject_unsigned_deliveries() {
+        let options = WebhookOptions {
+            secret: None,
+            access_token: String::new(),
+            analysis_options: AnalysisOptions {
+                run_clippy: false,
+                use_rust_analyzer: false,
+                custom_rules: Vec::new(),
+                analyzers: Vec::new(),
+                filter: Default::default(),
+                lints: Default::default(),
+                policy: None,
+            },
+            ssh: None,
+            notifications: Vec::new(),
+        };
+        assert!(serve("127.0.0.1:0".parse().unwrap(), options.clone()).await.is_err());
+
+        let options = Arc::new(WebhookOptions { secret: Some("secret".to_string()), ..options });
+        let delivery = |signature: Option<&str>| {
+            let mut request = Request::post("/").header("X-GitHub-Event", "ping");
+            if let Some(signature) = signature {
+                request = request.header("X-Hub-Signature-256", signature);
+            }
+            request.body(Body::from("{}")).unwrap()
+        };
+
+        let unsigned = handle_delivery(delivery(None), options.clone()).await.unwrap();
+        assert_eq!(unsigned.status(), StatusCode::UNAUTHORIZED);
+        let forged = handle_delivery(delivery(Some("sha256=00")), options.clone()).await.unwrap();
+        assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, b"secret");
+        let digest: String = ring::hmac::sign(&key, b"{}").as_ref().iter().map(|byte| format!("{:02x}", byte)).collect();
+        let signed = handle_delivery(delivery(Some(&format!("sha256={}", digest))), options).await.unwrap();
+        assert_eq!(signed.status(), StatusCode::OK);
+    }
+}